@@ -0,0 +1,91 @@
+# Deutsche Meldungen. Fehlende IDs fallen auf Englisch (en.ftl) zurück.
+
+## Auswahllisten
+
+help-select = ↑↓ bewegen, Enter auswählen, tippen zum Filtern
+pick-context = Kontext wählen:
+pick-context-in = Kontext in { $cluster } wählen:
+pick-cluster = Cluster wählen:
+pick-namespace = Namespace wählen:
+pick-namespaces = Namespaces wählen:
+pick-namespaces-help = Leertaste markiert, Enter öffnet für jeden ein Fenster
+pick-session = Zu Sitzung wechseln:
+pick-configs-remove = Zu entfernende Konfigurationen wählen:
+pick-contexts-remove = Zu entfernende Kontexte wählen:
+pick-contexts-prune = Aufzuräumende Kontexte wählen:
+pick-clusters-add = Hinzuzufügende Cluster wählen:
+pick-file-edit = Zu bearbeitende Datei wählen:
+
+## Bestätigungen
+
+confirm-proceed = Fortfahren?
+confirm-keep-changes = Änderungen behalten?
+confirm-register-aliases = Diese Aliasse in der k8pk-Konfiguration eintragen?
+confirm-remove-stale = { $count } veraltete isolierte Kubeconfig(s) entfernen (seit { $days } Tagen unbenutzt oder für nicht mehr vorhandene Kontexte)?
+confirm-create-namespace = Namespace '{ $namespace }' existiert in { $context } nicht. Anlegen?
+confirm-protected-shell-pod = '{ $context }' ist ein geschützter Kontext. Pod { $pod } ({ $image }) in { $namespace } starten?
+confirm-insecure = insecure-skip-tls-verify für diesen Kontext aktivieren?
+confirm-always-insecure = TLS-Prüfung für '{ $context }' immer überspringen? (speichert in insecure_contexts der Konfiguration)
+
+## Anmeldung
+
+prompt-cluster-type = Cluster-Typ:
+prompt-oc-path = Pfad zu oc (nicht im PATH):
+prompt-server = Server-URL:
+prompt-auth-method = Anmeldeverfahren:
+prompt-credentials = Zugangsdaten:
+credentials-type-in = Selbst eingeben
+prompt-pass-entry = Name des pass-Eintrags:
+prompt-op-item = 1Password-Eintrag (Name oder ID):
+prompt-bw-item = Bitwarden-Eintrag (Name oder ID):
+prompt-token = Token:
+prompt-username = Benutzername:
+prompt-username-ad = Benutzername (für AD DOMÄNE\benutzer oder benutzer@domain.com versuchen):
+prompt-password = Passwort:
+prompt-rancher-server = Rancher-Server-URL (z. B. https://rancher.example.com):
+prompt-rancher-username = Rancher-Benutzername:
+prompt-rancher-password = Rancher-Passwort:
+prompt-rancher-account-type = Rancher-Kontotyp:
+prompt-client-cert = Pfad zum Client-Zertifikat:
+prompt-client-key = Pfad zum Client-Schlüssel:
+prompt-ca = Pfad zur Zertifizierungsstelle (optional):
+prompt-exec-preset = Exec-Vorlage:
+prompt-exec-command = Exec-Befehl:
+prompt-exec-args = Exec-Argumente (durch Leerzeichen getrennt, optional):
+prompt-exec-env = Exec-Umgebung (KEY=VALUE, durch Kommas getrennt, optional):
+prompt-exec-api-version = Exec-apiVersion (optional):
+prompt-eks-cluster = Name des EKS-Clusters:
+prompt-teleport-cluster = Teleport-Kube-Cluster:
+prompt-aks-server-id = AKS-Server-ID:
+prompt-aws-region = AWS-Region (optional):
+prompt-context-name = Kontextname:
+prompt-output-dir = Ausgabeverzeichnis:
+confirm-custom-name = Eigenen Kontextnamen festlegen?
+confirm-custom-output-dir = Eigenes Ausgabeverzeichnis festlegen?
+confirm-skip-tls = TLS-Prüfung überspringen?
+confirm-use-vault = Zugangsdaten im Tresor speichern/abrufen?
+confirm-save-vault = Zugangsdaten im Tresor speichern?
+confirm-retry-credentials = Mit anderen Zugangsdaten erneut versuchen?
+confirm-retry-token = Mit einem anderen Token erneut versuchen?
+prompt-existing-login = '{ $context }' meldet sich bereits bei { $server } an:
+choice-login-reuse = Zu diesem Kontext wechseln
+choice-login-refresh = Neu anmelden und ihn aktualisieren
+choice-login-new = Neuen Kontext anlegen
+
+## Meldungen
+
+msg-detect-type-failed = Cluster-Typ konnte nicht aus der URL erkannt werden. Bitte wählen:
+msg-unknown-type-relogin = Unbekannter Cluster-Typ für '{ $context }'. Typ für die erneute Anmeldung wählen (wird gespeichert):
+msg-relogin-userpass = Sitzung für '{ $context }' abgelaufen. Erneut anmelden (Benutzername und Passwort).
+msg-relogin-token-or-userpass = Sitzung für '{ $context }' abgelaufen. Erneut anmelden (Token oder Benutzername/Passwort).
+msg-auth-failed-token = Anmeldung fehlgeschlagen. Bitte das Token prüfen.
+msg-auth-failed-userpass = Anmeldung fehlgeschlagen. Bitte Benutzername und Passwort prüfen.
+msg-auth-failed-rancher =
+    Anmeldung fehlgeschlagen. Häufige Ursachen:
+      - Für AD: DOMÄNE\benutzer oder benutzer@domain.com versuchen
+      - Prüfen, ob das Konto Zugriff auf Rancher hat
+      - Prüfen, ob das Passwort stimmt
+msg-tls-error = TLS-Zertifikatsfehler für '{ $context }'.
+msg-connected-insecure = Verbunden (unsicherer Modus).
+msg-applied-insecure = insecure-skip-tls-verify in der Kubeconfig gesetzt.
+msg-saved-insecure = '{ $context }' in insecure_contexts der Konfiguration gespeichert.
@@ -0,0 +1,92 @@
+# English messages (the fallback: every id used by k8pk must be here).
+# Other catalogs translate the same ids; see src/i18n.rs.
+
+## Pickers
+
+help-select = ↑↓ to move, enter to select, type to filter
+pick-context = Select context:
+pick-context-in = Select context in { $cluster }:
+pick-cluster = Select cluster:
+pick-namespace = Select namespace:
+pick-namespaces = Select namespaces:
+pick-namespaces-help = space to select, enter to open a window for each
+pick-session = Switch to session:
+pick-configs-remove = Select configs to remove:
+pick-contexts-remove = Select contexts to remove:
+pick-contexts-prune = Select contexts to prune:
+pick-clusters-add = Select clusters to add:
+pick-file-edit = Select file to edit:
+
+## Confirmations
+
+confirm-proceed = Proceed?
+confirm-keep-changes = Keep these changes?
+confirm-register-aliases = Register these aliases in the k8pk config?
+confirm-remove-stale = Remove { $count } stale isolated kubeconfig(s) (unused for { $days } days, or for contexts that no longer exist)?
+confirm-create-namespace = Namespace '{ $namespace }' does not exist in { $context }. Create it?
+confirm-protected-shell-pod = '{ $context }' is a protected context. Start pod { $pod } ({ $image }) in { $namespace }?
+confirm-insecure = Enable insecure-skip-tls-verify for this context?
+confirm-always-insecure = Always skip TLS for '{ $context }'? (saves to insecure_contexts in config)
+
+## Login
+
+prompt-cluster-type = Cluster type:
+prompt-oc-path = Path to oc (not on PATH):
+prompt-server = Server URL:
+prompt-auth-method = Authentication method:
+prompt-credentials = Credentials:
+credentials-type-in = Type them in
+prompt-pass-entry = pass entry name:
+prompt-op-item = 1Password item (name or ID):
+prompt-bw-item = Bitwarden item (name or ID):
+prompt-token = Token:
+prompt-username = Username:
+prompt-username-ad = Username (for AD try DOMAIN\user or user@domain.com):
+prompt-password = Password:
+prompt-rancher-server = Rancher server URL (e.g., https://rancher.example.com):
+prompt-rancher-username = Rancher username:
+prompt-rancher-password = Rancher password:
+prompt-rancher-account-type = Rancher account type:
+prompt-client-cert = Client certificate path:
+prompt-client-key = Client key path:
+prompt-ca = Certificate authority path (optional):
+prompt-exec-preset = Exec preset:
+prompt-exec-command = Exec command:
+prompt-exec-args = Exec args (space-separated, optional):
+prompt-exec-env = Exec env (KEY=VALUE, comma-separated, optional):
+prompt-exec-api-version = Exec apiVersion (optional):
+prompt-eks-cluster = EKS cluster name:
+prompt-teleport-cluster = Teleport kube cluster:
+prompt-aks-server-id = AKS server ID:
+prompt-aws-region = AWS region (optional):
+prompt-context-name = Context name:
+prompt-output-dir = Output directory:
+confirm-custom-name = Set custom context name?
+confirm-custom-output-dir = Set custom output directory?
+confirm-skip-tls = Skip TLS verification?
+confirm-use-vault = Use vault to store/retrieve credentials?
+confirm-save-vault = Save credentials to vault?
+confirm-retry-credentials = Retry with different credentials?
+confirm-retry-token = Retry with a different token?
+prompt-existing-login = '{ $context }' already logs in to { $server }:
+choice-login-reuse = Switch to it
+choice-login-refresh = Log in again and update it
+choice-login-new = Create a new context
+
+## Messages
+
+msg-detect-type-failed = Could not detect cluster type from URL. Please select:
+msg-unknown-type-relogin = Unknown cluster type for '{ $context }'. Choose type for re-login (saved for next time):
+msg-relogin-userpass = Session expired for '{ $context }'. Re-login (username and password).
+msg-relogin-token-or-userpass = Session expired for '{ $context }'. Re-login (token or username/password).
+msg-auth-failed-token = Authentication failed. Check your token.
+msg-auth-failed-userpass = Authentication failed. Check your username and password.
+msg-auth-failed-rancher =
+    Authentication failed. Common issues:
+      - For AD: try DOMAIN\username or username@domain.com
+      - Check if your account has Rancher access
+      - Verify password is correct
+msg-tls-error = TLS certificate error for '{ $context }'.
+msg-connected-insecure = Connected (insecure mode).
+msg-applied-insecure = Applied insecure-skip-tls-verify to kubeconfig.
+msg-saved-insecure = Saved '{ $context }' to insecure_contexts in config.
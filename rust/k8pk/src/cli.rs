@@ -16,14 +16,23 @@ You choose a context, then k8pk opens a shell already wired to kubectl/oc — th
                   k8pk ctx NAME           # Switch context here\n  \
                   k8pk rm NAME            # Drop a stale context\n  \
                   k8pk login …            # New cluster\n  \
-                  k8pk --oc /path/to/oc … # OpenShift CLI for this run (same as $K8PK_OC)\n\n\
+                  k8pk --oc /path/to/oc … # OpenShift CLI for this run (same as $K8PK_OC)\n  \
+                  k8pk --alias p=prod ctx p  # One-off alias (same as $K8PK_ALIASES)\n\n\
                   Examples:\n  \
                   k8pk                    # Default: pick → shell\n  \
                   k8pk ctx dev            # Jump to context\n  \
                   k8pk ctx -\n  \
                   k8pk ns production\n  \
                   k8pk contexts\n  \
-                  k8pk which"
+                  k8pk which",
+    after_long_help = "Exit codes:\n  \
+                  0    success\n  \
+                  1    other failure (including a command run by `exec` that failed)\n  \
+                  2    invalid arguments, or input required under --no-input\n  \
+                  3    context, cluster, kubeconfig, or history entry not found\n  \
+                  4    authentication failed or session expired\n  \
+                  5    cluster unreachable (network error, timeout, untrusted TLS certificate)\n  \
+                  130  cancelled at a prompt"
 )]
 pub struct Cli {
     #[command(subcommand)]
@@ -41,43 +50,167 @@ pub struct Cli {
     #[arg(long, global = true, value_name = "PATH")]
     pub oc: Option<PathBuf>,
 
+    /// Never prompt: fail with an error wherever input would be needed (same as K8PK_NO_INPUT=1)
+    #[arg(long, global = true)]
+    pub no_input: bool,
+
+    /// Temporary context alias for this run, NAME=CONTEXT (repeatable; merged over config and $K8PK_ALIASES)
+    #[arg(long, global = true, action = clap::ArgAction::Append, value_name = "NAME=CONTEXT")]
+    pub alias: Vec<String>,
+
+    /// Output format for command results: plain, json, or yaml (same as K8PK_OUTPUT); make, dotenv, or github-actions for `k8pk env`
+    #[arg(long, global = true, value_enum, value_name = "FORMAT")]
+    pub format: Option<OutputFormat>,
+
+    /// Suppress informational messages; results and errors are still printed (same as K8PK_QUIET=1)
+    #[arg(short, long, global = true)]
+    pub quiet: bool,
+
+    /// Interactive picker: builtin, fzf, or sk (same as K8PK_PICKER; default from `picker:` in config)
+    #[arg(long, global = true, value_enum, value_name = "PICKER")]
+    pub picker: Option<PickerKind>,
+
+    /// Which file wins when kubeconfigs define the same name: first (like kubectl), last, or strict (same as K8PK_MERGE_STRATEGY)
+    #[arg(long, global = true, value_enum, value_name = "STRATEGY")]
+    pub merge_strategy: Option<MergeStrategy>,
+
+    /// Reuse existing isolated kubeconfigs as they are, even if their source changed (same as K8PK_NO_REFRESH=1)
+    #[arg(long, global = true)]
+    pub no_refresh: bool,
+
+    /// Don't color output (same as NO_COLOR=1)
+    #[arg(long, global = true)]
+    pub no_color: bool,
+
+    /// Don't show context banners (`motd`) when switching (same as K8PK_NO_MOTD=1)
+    #[arg(long, global = true)]
+    pub no_motd: bool,
+
+    /// Make no network calls: use cached namespace lists and cluster facts, skip session checks, and refuse login, discover, and update (same as K8PK_OFFLINE=1 or `offline: true`)
+    #[arg(long, global = true)]
+    pub offline: bool,
+
+    /// Shorthand for --format yaml
+    #[arg(long, global = true, conflicts_with = "format")]
+    pub yaml: bool,
+
     /// Enable verbose output (can be repeated: -v, -vv, -vvv)
     #[arg(short, long, action = clap::ArgAction::Count, global = true)]
     pub verbose: u8,
 }
 
+/// Cluster types reported by `k8pk which` (`--type`)
+const CLUSTER_TYPES: [&str; 6] = ["eks", "gke", "aks", "ocp", "rancher", "k8s"];
+
+/// Format for command results (`--format`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable text (default)
+    Plain,
+    /// Pretty-printed JSON
+    Json,
+    /// YAML
+    Yaml,
+    /// Makefile assignments for `include` (`k8pk env` only)
+    Make,
+    /// A .env file (`k8pk env` only)
+    Dotenv,
+    /// Lines for `$GITHUB_ENV` (`k8pk env` only)
+    GithubActions,
+}
+
+/// How same-named clusters, contexts, and users from several kubeconfig
+/// files combine (`--merge-strategy`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum MergeStrategy {
+    /// The first file defining a name wins, as in kubectl (default)
+    #[default]
+    First,
+    /// The last file defining a name wins
+    Last,
+    /// Fail when a name is defined more than once
+    Strict,
+}
+
+/// Strategy for top-level blocks (preferences, extensions, current-context)
+/// in `k8pk merge` (`--prefer`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum MergePrefer {
+    /// Keep the value from the first file that sets it
+    #[default]
+    First,
+    /// Keep the value from the last file that sets it
+    Last,
+    /// Combine mappings and named lists; on conflicts the first file wins
+    Union,
+}
+
+/// Interactive selector for contexts, clusters, and namespaces (`--picker`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum PickerKind {
+    /// Built-in list with type-to-filter (default)
+    #[value(alias = "inquire")]
+    Builtin,
+    /// fzf, with a `k8pk which` preview for contexts
+    Fzf,
+    /// skim, with a `k8pk which` preview for contexts
+    Sk,
+}
+
+/// Sort order for `contexts` and `which` (`--sort`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum SortKey {
+    /// Context name
+    Name,
+    /// Cluster type, then name
+    Type,
+    /// Most recently switched to first
+    LastUsed,
+    /// Source kubeconfig file, then name
+    File,
+}
+
+/// Release channel for `k8pk update` (`--channel`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum UpdateChannel {
+    /// The latest full release
+    #[default]
+    Stable,
+    /// The newest pre-release
+    Nightly,
+}
+
 #[derive(Subcommand)]
 #[allow(clippy::large_enum_variant)]
 pub enum Command {
     /// List all available context names
     #[command(visible_alias = "ls")]
-    Contexts {
-        /// Output as JSON array
-        #[arg(long, help = "Output as JSON array")]
-        json: bool,
-        /// Include source file path for each context
-        #[arg(long, help = "Show which file each context comes from")]
-        path: bool,
-    },
+    Contexts(ContextsArgs),
 
     /// Generate a minimal kubeconfig file for a specific context
-    Gen {
-        /// Context name to extract
-        #[arg(long, value_name = "NAME")]
-        context: String,
-        /// Output file path
-        #[arg(long, value_name = "PATH")]
-        out: PathBuf,
-        /// Override the default namespace
-        #[arg(long, value_name = "NS")]
-        namespace: Option<String>,
-        /// Output as JSON
-        #[arg(long)]
-        json: bool,
-        /// Suppress non-essential output
-        #[arg(long)]
-        quiet: bool,
-    },
+    #[command(
+        after_help = "With --flatten the result is self-contained, like `kubectl config view\n\
+        --flatten --minify --context NAME`, without needing kubectl. Exec plugins and\n\
+        token files are kept as references and reported on stderr.\n\n\
+        Examples:\n  \
+        k8pk gen --context prod --out prod.yaml\n  \
+        k8pk gen --context 'prod-eu*' --out eu.yaml # Errors if the glob matches several\n  \
+        k8pk gen --flatten > current.yaml           # Current context, to stdout\n  \
+        k8pk gen --context prod --flatten --redact --out prod-redacted.yaml"
+    )]
+    Gen(GenArgs),
+
+    /// Write a kubeconfig with secrets replaced by placeholders, for bug reports
+    /// or sharing cluster endpoints
+    #[command(
+        after_help = "Tokens, client-key-data, passwords, auth-provider secrets, and exec env\n\
+        values become REDACTED. Context, cluster, and user names, server URLs, and CA\n\
+        data are kept.\n\n\
+        Examples:\n  \
+        k8pk export-safe > kubeconfig-redacted.yaml   # All contexts\n  \
+        k8pk export-safe --context prod --out prod.yaml"
+    )]
+    ExportSafe(ExportSafeArgs),
 
     /// Print the current context name
     Current {
@@ -87,64 +220,29 @@ pub enum Command {
     },
 
     /// List namespaces accessible in a context
-    Namespaces {
-        /// Context to query namespaces for (defaults to K8PK_CONTEXT or current-context)
-        #[arg(long, value_name = "NAME")]
-        context: Option<String>,
-        /// Output as JSON array
-        #[arg(long, help = "Output as JSON array")]
-        json: bool,
-    },
+    Namespaces(NamespacesArgs),
 
     /// Output shell export statements for a context/namespace
-    #[command(hide = true)] // Internal use
-    Env {
-        /// Context name
-        #[arg(long, value_name = "NAME")]
-        context: String,
-        /// Namespace (optional)
-        #[arg(long, value_name = "NS")]
-        namespace: Option<String>,
-        /// Shell type: bash, zsh, fish
-        #[arg(long, default_value = "bash", value_name = "SHELL")]
-        shell: String,
-        /// Include additional debug info
-        #[arg(long = "detail")]
-        detail: bool,
-    },
+    #[command(
+        hide = true, // Internal use
+        after_help = "Examples:\n  \
+        k8pk env --context dev --format make > k8pk.mk        # Makefile: include k8pk.mk\n  \
+        k8pk env --context dev --format dotenv > .env\n  \
+        k8pk env --context dev --format github-actions >> \"$GITHUB_ENV\""
+    )]
+    Env(EnvArgs),
+
+    /// Write an .envrc stanza so direnv selects a context in this directory
+    #[command(after_help = "Examples:\n  \
+        k8pk direnv dev               # Add/update the k8pk block in ./.envrc\n  \
+        k8pk direnv prod payments     # With a namespace\n  \
+        k8pk direnv dev --print       # Print the stanza instead\n  \
+        k8pk direnv dev --watch       # Keep it fresh while the kubeconfig changes\n\n\
+        Run 'direnv allow' after the file changes.")]
+    Direnv(DirenvArgs),
 
     /// Pick context (and namespace if configured), then open a shell — same as running `k8pk` with no subcommand
-    Pick {
-        /// Optional filter: pre-filter contexts (substring/glob), e.g. `k8pk pick prod`
-        #[arg(value_name = "FILTER")]
-        filter: Option<String>,
-        /// Output format: env, json, spawn (default: env)
-        #[arg(
-            long,
-            value_name = "FORMAT",
-            help = "Output format: env | json | spawn (default: env)"
-        )]
-        output: Option<String>,
-        /// Include additional info in output
-        #[arg(long = "detail")]
-        detail: bool,
-        /// Force subshell even when inside tmux
-        #[arg(long, help = "Force subshell instead of tmux window/session")]
-        no_tmux: bool,
-        /// Skip TLS certificate verification for the chosen context
-        #[arg(
-            long,
-            visible_alias = "insecure",
-            help = "Skip TLS certificate verification"
-        )]
-        insecure_skip_tls: bool,
-        /// Skip session check (also: K8PK_NO_SESSION_CHECK=1)
-        #[arg(
-            long,
-            help = "Skip API session check (fail fast if credentials expired later)"
-        )]
-        no_session_check: bool,
-    },
+    Pick(PickArgs),
 
     /// Clean up old generated kubeconfig files
     #[command(after_help = "Examples:\n  \
@@ -152,32 +250,7 @@ pub enum Command {
         k8pk cleanup --days 7           # Remove files older than 7 days\n  \
         k8pk cleanup --orphaned         # Remove configs for deleted contexts\n  \
         k8pk cleanup --all              # Remove all generated configs")]
-    Cleanup {
-        /// Remove files older than N days
-        #[arg(long, default_value = "30", value_name = "N")]
-        days: u64,
-        /// Remove configs for contexts that no longer exist
-        #[arg(long, help = "Remove configs for contexts that no longer exist")]
-        orphaned: bool,
-        /// Show what would be deleted without deleting
-        #[arg(long, help = "Preview changes without making them")]
-        dry_run: bool,
-        /// Remove ALL generated configs (dangerous!)
-        #[arg(long, help = "Remove all generated configs (use with caution)")]
-        all: bool,
-        /// Operate on specific kubeconfig file
-        #[arg(long, value_name = "PATH")]
-        from_file: Option<PathBuf>,
-        /// Prompt before each deletion
-        #[arg(long, short = 'i', help = "Prompt before each deletion")]
-        interactive: bool,
-        /// Output as JSON
-        #[arg(long)]
-        json: bool,
-        /// Suppress non-essential output
-        #[arg(long)]
-        quiet: bool,
-    },
+    Cleanup(CleanupArgs),
 
     /// Remove a context (auto-finds source file)
     #[command(
@@ -188,108 +261,72 @@ pub enum Command {
         k8pk rm dead-cluster --yes   # Skip confirmation\n  \
         k8pk rm dead-cluster --dry-run  # Preview without removing"
     )]
-    Rm {
-        /// Context name to remove (interactive picker if omitted)
-        #[arg(value_name = "CONTEXT")]
-        context: Option<String>,
-        /// Preview changes without making them
-        #[arg(long, help = "Preview changes without making them")]
-        dry_run: bool,
-        /// Skip the confirmation prompt (use with care)
-        #[arg(short = 'y', long, help = "Skip confirmation prompt")]
-        yes: bool,
-        /// Output as JSON
-        #[arg(long)]
-        json: bool,
-    },
+    Rm(RmArgs),
 
     /// Rename a context in a kubeconfig file
-    RenameContext {
-        /// Kubeconfig file to modify (default: ~/.kube/config)
-        #[arg(long, value_name = "PATH")]
-        from_file: Option<PathBuf>,
-        /// Current context name
-        #[arg(long, value_name = "OLD")]
-        context: String,
-        /// New context name
-        #[arg(long, value_name = "NEW")]
-        new_name: String,
-        /// Preview changes without making them
-        #[arg(long, help = "Preview changes without making them")]
-        dry_run: bool,
-        /// Output as JSON
-        #[arg(long)]
-        json: bool,
-        /// Suppress non-essential output
-        #[arg(long)]
-        quiet: bool,
-    },
+    #[command(after_help = "Examples:\n  \
+        k8pk rename-context --context old --new-name new\n  \
+        k8pk rename-context --pattern 's/^arn:aws:eks:.*cluster\\///' --dry-run\n  \
+        k8pk rename-context --template '{type}-{cluster}' --from-file ~/.kube/eks.yaml\n\n\
+        Template fields: {type} (eks, gke, ocp, ...), {cluster} (friendly cluster name),\n\
+        {name} (current context name), {namespace}, {user}")]
+    RenameContext(RenameContextArgs),
 
     /// Copy a context from one kubeconfig file to another
-    CopyContext {
-        /// Source kubeconfig file
-        #[arg(long, value_name = "PATH")]
-        from_file: PathBuf,
-        /// Destination file (default: ~/.kube/config)
-        #[arg(long, value_name = "PATH")]
-        to_file: Option<PathBuf>,
-        /// Context name to copy
-        #[arg(long, value_name = "NAME")]
-        context: String,
-        /// Rename context in destination
-        #[arg(long, value_name = "NAME")]
-        new_name: Option<String>,
-        /// Preview changes without making them
-        #[arg(long, help = "Preview changes without making them")]
-        dry_run: bool,
-        /// Output as JSON
-        #[arg(long)]
-        json: bool,
-        /// Suppress non-essential output
-        #[arg(long)]
-        quiet: bool,
-    },
+    CopyContext(CopyContextArgs),
+
+    /// Create a context from a server (or existing cluster) and an existing user
+    #[command(after_help = "Examples:\n  \
+        k8pk create-context staging --server https://staging.example.com:6443 --user-from sso-user\n  \
+        k8pk create-context prod-apps --cluster-name prod --user-from prod-admin --namespace apps\n  \
+        k8pk create-context --template staging.yaml --namespace web\n\n\
+        A template is YAML with the same keys as the flags (name, server,\n\
+        cluster-name, user-from, namespace); flags override it. Missing clusters\n\
+        and users are copied from the merged kubeconfig.")]
+    CreateContext(CreateContextArgs),
+
+    /// Set cluster, user, or namespace of a context, as `kubectl config set-context`
+    #[command(
+        after_help = "Edits the kubeconfig file that defines the context (a new context goes to\n\
+        the first file k8pk resolved), keeping its comments. Unlike `k8pk ctx`, this\n\
+        changes the file for every shell.\n\n\
+        Examples:\n  \
+        k8pk set-context dev --namespace web\n  \
+        k8pk set-context --current --namespace api\n  \
+        k8pk set-context staging --cluster staging --user sso-user"
+    )]
+    SetContext(SetContextArgs),
+
+    /// Set current-context in the kubeconfig, as `kubectl config use-context`
+    #[command(
+        after_help = "Writes current-context to the file kubectl reads it from: the first file\n\
+        that sets one (the last with --merge-strategy last), else the first file.\n\
+        Use `k8pk ctx` to switch only the current shell.\n\n\
+        Examples:\n  \
+        k8pk use-context prod"
+    )]
+    UseContext(UseContextArgs),
+
+    /// Print the merged kubeconfig, as `kubectl config view`
+    #[command(
+        after_help = "Secrets are shown as REDACTED and certificate data as DATA+OMITTED\n\
+        unless --raw is given.\n\n\
+        Examples:\n  \
+        k8pk view --minify                        # Current context only\n  \
+        k8pk view --minify --flatten --raw > ci.yaml\n  \
+        k8pk view --context prod -o json"
+    )]
+    View(ViewArgs),
 
     /// Merge multiple kubeconfig files into one
     #[command(after_help = "Examples:\n  \
         k8pk merge --files a.yaml b.yaml --out merged.yaml\n  \
-        k8pk merge --files ~/.kube/*.yaml --out combined.yaml")]
-    Merge {
-        /// Kubeconfig files to merge
-        #[arg(long, num_args = 1.., value_name = "FILES")]
-        files: Vec<PathBuf>,
-        /// Output file (default: stdout)
-        #[arg(long, value_name = "PATH")]
-        out: Option<PathBuf>,
-        /// Overwrite existing contexts with same name
-        #[arg(long, help = "Overwrite existing contexts with same name")]
-        overwrite: bool,
-        /// Output as JSON
-        #[arg(long)]
-        json: bool,
-        /// Suppress non-essential output
-        #[arg(long)]
-        quiet: bool,
-    },
+        k8pk merge --files ~/.kube/*.yaml --out combined.yaml\n  \
+        k8pk merge --files base.yaml team.yaml --prefer union")]
+    Merge(MergeArgs),
 
     /// Compare two kubeconfig files
-    Diff {
-        /// First kubeconfig file
-        #[arg(long, value_name = "PATH")]
-        file1: PathBuf,
-        /// Second kubeconfig file
-        #[arg(long, value_name = "PATH")]
-        file2: PathBuf,
-        /// Only show differences (hide common contexts)
-        #[arg(long, help = "Only show differences")]
-        diff_only: bool,
-        /// Output as JSON
-        #[arg(long)]
-        json: bool,
-        /// Suppress non-essential output
-        #[arg(long)]
-        quiet: bool,
-    },
+    Diff(DiffArgs),
 
     /// Execute a command in a specific context/namespace
     #[command(
@@ -301,91 +338,60 @@ pub enum Command {
         k8pk exec dev api -- kubectl logs -f deployment/api\n  \
         k8pk exec prod --no-session-check -- kubectl get ns"
     )]
-    Exec {
-        /// Context to use (supports glob patterns)
-        #[arg(value_name = "CONTEXT")]
-        context: String,
-        /// Namespace to use (optional, defaults to context's configured namespace)
-        #[arg(value_name = "NAMESPACE")]
-        namespace: Option<String>,
-        /// Command to execute (after --)
-        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
-        command: Vec<String>,
-        /// Exit immediately on first error
-        #[arg(short = 'e', long, help = "Exit on first error")]
-        fail_early: bool,
-        /// Suppress context/namespace headers
-        #[arg(long, help = "Suppress context/namespace headers")]
-        no_headers: bool,
-        /// Output results as JSON (wraps stdout/stderr per context)
-        #[arg(long)]
-        json: bool,
-        /// Skip session check and credential refresh (fail fast if expired)
-        #[arg(long, help = "Skip session liveness check and re-login (for scripts)")]
-        no_session_check: bool,
-    },
+    Exec(ExecArgs),
+
+    /// Run one command in a context, for scripts and Makefiles
+    #[command(
+        after_help = "Lighter than exec: one context (aliases allowed), no headers, and the\n\
+        command's exit code is passed through unchanged.\n\n\
+        Examples:\n  \
+        k8pk run prod -- kubectl get pods\n  \
+        k8pk run prod/kube-system -- kubectl get pods\n  \
+        k8pk run --no-session-check dev/web -- helm list"
+    )]
+    Run(RunArgs),
+
+    /// Regenerate the current k8pk shell's kubeconfig in place with fresh credentials
+    #[command(
+        after_help = "Run inside a shell started by k8pk. The context's kubeconfig is rebuilt from
+        its source files (and re-login runs if the session expired), then written over
+        $KUBECONFIG so the running shell picks up the new credentials.
+
+        Examples:
+          k8pk refresh
+          k8pk refresh --no-session-check   # only re-read the source files"
+    )]
+    Refresh(RefreshArgs),
 
     /// Get information about current context/namespace
     #[command(
         visible_alias = "status",
-        after_help = "What to show: ctx, ns, depth, config, oc, all (default)\n\n\
+        after_help = "What to show: ctx, ns, depth, config, cluster, user, server, expiry, oc, all (default)\n\n\
         Examples:\n  \
         k8pk info ctx --display\n  \
         k8pk info depth\n  \
+        k8pk info server         # API server URL of the session's cluster\n  \
+        k8pk info expiry         # Time left before the session expires (session_ttl)\n  \
         k8pk info oc             # OpenShift CLI path (K8PK_OC / PATH)\n  \
         k8pk status              # Same as 'k8pk info all'\n  \
         k8pk info all"
     )]
-    Info {
-        /// What to show: ctx, ns, depth, config, all
-        #[arg(default_value = "all", value_name = "WHAT")]
-        what: String,
-        /// Show friendly context display name (ctx only)
-        #[arg(long, help = "Show display context name (ctx only)")]
-        display: bool,
-        /// Show raw context name (ctx only)
-        #[arg(long, help = "Show raw context name (ctx only)")]
-        raw: bool,
-    },
+    Info(InfoArgs),
 
     /// Switch to context (with history support, use '-' for previous)
-    #[command(after_help = "Examples:\n  \
+    #[command(
+        allow_negative_numbers = true,
+        after_help = "Examples:\n  \
         k8pk ctx dev              # Switch to 'dev'\n  \
         k8pk ctx dev -n prod      # Switch to 'dev' context, 'prod' namespace\n  \
+        k8pk ctx dev              # Restores the last namespace used in 'dev'\n  \
         k8pk ctx -                # Switch to previous context\n  \
+        k8pk ctx -3               # Three switches back in this shell (see k8pk back)\n  \
+        k8pk ctx 'dev-*'          # The only match, or a picker of the matches\n  \
         k8pk ctx                  # Interactive selection\n  \
-        k8pk ctx dev -o json      # Output as JSON instead of spawning")]
-    Ctx {
-        /// Context name (use '-' for previous)
-        #[arg(value_name = "CONTEXT")]
-        context: Option<String>,
-        /// Also switch namespace
-        #[arg(short = 'n', long, value_name = "NS")]
-        namespace: Option<String>,
-        /// Spawn recursive subshell instead of modifying current
-        #[arg(
-            short = 'r',
-            long,
-            help = "Spawn subshell instead of modifying current"
-        )]
-        recursive: bool,
-        /// Output format: env, json, spawn (default: env for eval)
-        #[arg(short = 'o', long, value_name = "FORMAT")]
-        output: Option<String>,
-        /// Force subshell even when inside tmux
-        #[arg(long, help = "Force subshell instead of tmux window/session")]
-        no_tmux: bool,
-        /// Skip TLS certificate verification for this context
-        #[arg(
-            long,
-            visible_alias = "insecure",
-            help = "Skip TLS certificate verification"
-        )]
-        insecure_skip_tls: bool,
-        /// Skip session check (also: K8PK_NO_SESSION_CHECK=1)
-        #[arg(long, help = "Skip API session check")]
-        no_session_check: bool,
-    },
+        k8pk ctx dev -o json      # Output as JSON instead of spawning"
+    )]
+    Ctx(CtxArgs),
 
     /// Switch to namespace (with history support, use '-' for previous)
     #[command(after_help = "Examples:\n  \
@@ -393,46 +399,33 @@ pub enum Command {
         k8pk ns -                 # Switch to previous namespace\n  \
         k8pk ns                   # Interactive selection (spawns shell)\n  \
         k8pk ns prod -o json      # Output as JSON\n  \
-        k8pk ns prod -o env       # Output exports for eval")]
-    Ns {
-        /// Namespace name (use '-' for previous)
-        #[arg(value_name = "NAMESPACE")]
-        namespace: Option<String>,
-        /// Spawn recursive subshell instead of modifying current
-        #[arg(
-            short = 'r',
-            long,
-            help = "Spawn subshell instead of modifying current"
-        )]
-        recursive: bool,
-        /// Output format: env, json, spawn (default: env)
-        #[arg(short = 'o', long, value_name = "FORMAT")]
-        output: Option<String>,
-        /// Force subshell even when inside tmux
-        #[arg(long, help = "Force subshell instead of tmux window/session")]
-        no_tmux: bool,
-        /// Skip TLS certificate verification for this context
-        #[arg(
-            long,
-            visible_alias = "insecure",
-            help = "Skip TLS certificate verification"
-        )]
-        insecure_skip_tls: bool,
-    },
+        k8pk ns prod -o env       # Output exports for eval\n  \
+        k8pk ns --forget          # Stop restoring a namespace for this context\n  \
+        k8pk ns feature-x --create  # Create it first if missing (asks; -y to skip)\n  \
+        k8pk ns --multi           # Pick several; one tmux window (ctx/ns) each")]
+    Ns(NsArgs),
 
     /// Show recent context/namespace switch history
     #[command(after_help = "Examples:\n  \
         k8pk history              # Show recent switches\n  \
         k8pk history --json       # Output as JSON\n  \
         k8pk history --clear      # Clear history")]
-    History {
-        /// Output as JSON
-        #[arg(long)]
-        json: bool,
-        /// Clear all history
-        #[arg(long)]
-        clear: bool,
-    },
+    History(HistoryArgs),
+
+    /// Go back through this shell's context switches, like a browser
+    #[command(
+        after_help = "Each shell keeps its own stack of switches (ctx, ns, pick). `back` and\n\
+        `forward` move through it without adding entries; switching anywhere else\n\
+        drops the entries ahead, as in a browser. `k8pk history --shell` lists it.\n\n\
+        Examples:\n  \
+        k8pk back                 # Previous switch (same as ctx -)\n  \
+        k8pk back 3               # Three switches back (same as ctx -3)\n  \
+        k8pk forward              # Undo a back"
+    )]
+    Back(BackArgs),
+
+    /// Go forward again after `k8pk back`
+    Forward(ForwardArgs),
 
     /// Clean up current k8pk session (unset all K8PK_* environment variables)
     #[command(
@@ -449,32 +442,25 @@ pub enum Command {
     },
 
     /// Update k8pk to the latest version
-    Update {
-        /// Check for updates without installing
-        #[arg(long, help = "Check for updates without installing")]
-        check: bool,
-        /// Force update even if already on latest
-        #[arg(long, help = "Force reinstall even if up to date")]
-        force: bool,
-        /// Output as JSON
-        #[arg(long)]
-        json: bool,
-        /// Suppress non-essential output
-        #[arg(long)]
-        quiet: bool,
-    },
+    #[command(
+        after_help = "Downloads are checked against the release's .sha256 file before\n\
+        anything is installed. --cosign also checks the Sigstore signature (needs\n\
+        cosign on PATH); --minisign-key checks a .minisig signature (needs minisign).\n\n\
+        A k8pk installed by Homebrew, apt, dnf, or Nix is not overwritten; update\n\
+        prints the package manager's upgrade command instead (--force-binary to\n\
+        replace it anyway).\n\n\
+        Examples:\n  \
+        k8pk update                      # Latest stable release\n  \
+        k8pk update --check --json       # Current vs. latest, for automation\n  \
+        k8pk update --channel nightly    # Newest pre-release\n  \
+        k8pk update --version 1.14.14    # Pin (or downgrade to) a version\n  \
+        k8pk update --cosign             # Also verify the cosign signature"
+    )]
+    Update(UpdateArgs),
 
     /// Export path to isolated kubeconfig file (for scripting)
     #[command(hide = true)]
-    Export {
-        /// Context name
-        context: String,
-        /// Namespace name
-        namespace: String,
-        /// Output as JSON
-        #[arg(long)]
-        json: bool,
-    },
+    Export(ExportArgs),
 
     /// Generate shell completion scripts
     #[command(after_help = "Examples:\n  \
@@ -487,26 +473,37 @@ pub enum Command {
         shell: String,
     },
 
+    /// Print shell integration (ctx/ns wrappers, completions, prompt hook)
+    #[command(after_help = "Examples:\n  \
+        eval \"$(k8pk init bash)\"        # in ~/.bashrc\n  \
+        eval \"$(k8pk init zsh)\"         # in ~/.zshrc\n  \
+        k8pk init fish | source         # in config.fish\n  \
+        k8pk init --install             # append the line for $SHELL")]
+    Init(InitArgs),
+
     /// Lint kubeconfig files for common issues
     #[command(after_help = "Checks for:\n  \
         - Missing cluster/user references\n  \
         - Invalid YAML syntax\n  \
-        - Duplicate context names\n  \
-        - Expired certificates (with --strict)")]
-    Lint {
-        /// Specific file to lint (default: all configured files)
-        #[arg(long, value_name = "PATH")]
-        file: Option<PathBuf>,
-        /// Enable additional checks (cert expiry, etc.)
-        #[arg(long, help = "Enable additional checks (cert expiry, etc.)")]
-        strict: bool,
-        /// Output as JSON
-        #[arg(long)]
-        json: bool,
-        /// Suppress non-essential output
-        #[arg(long)]
-        quiet: bool,
-    },
+        - Contexts defined in several files (and which definition wins)\n  \
+        - Expired certificates (with --strict)\n\n\
+        --fix repairs what it safely can, after a backup (k8pk undo):\n  \
+        - Removes orphaned clusters/users and exact duplicate entries\n  \
+        - Unsets a current-context that doesn't exist\n  \
+        - Sets apiVersion: v1 and kind: Config\n  \
+        - With --strict, removes contexts whose certificate, key, or token file is missing")]
+    Lint(LintArgs),
+
+    /// Manage context aliases (short names for long context names)
+    #[command(
+        args_conflicts_with_subcommands = true,
+        after_help = "Examples:\n  \
+        k8pk alias                                  # List aliases\n  \
+        k8pk alias add prod arn:aws:eks:...:cluster/prod\n  \
+        k8pk alias remove prod\n  \
+        k8pk alias --install                        # Install kctx/kns shell helpers"
+    )]
+    Alias(AliasArgs),
 
     /// Manage k8pk configuration
     #[command(after_help = "Examples:\n  \
@@ -517,16 +514,34 @@ pub enum Command {
     #[command(subcommand)]
     Config(ConfigCommand),
 
-    /// Edit kubeconfig files in your editor
-    Edit {
-        /// Context to edit (opens its source file)
-        #[arg(value_name = "CONTEXT")]
-        context: Option<String>,
-        /// Override $EDITOR
-        #[arg(long, value_name = "CMD")]
-        editor: Option<String>,
+    /// List kubeconfig files and switch single files off or on
+    #[command(after_help = "Examples:\n  \
+        k8pk sources                           # Files k8pk merges, with context counts\n  \
+        k8pk sources disable ~/.kube/acme.yaml # Hide a client's clusters, keep the file\n  \
+        k8pk sources enable ~/.kube/acme.yaml  # Bring them back")]
+    Sources {
+        #[command(subcommand)]
+        command: Option<SourcesCommand>,
     },
 
+    /// Pull the team's git repository of kubeconfigs into ~/.kube/managed
+    #[command(after_help = "Set the repository in the k8pk config:\n  \
+        sync:\n    \
+          repo: git@github.com:acme/kubeconfigs.git\n    \
+          branch: main            # optional\n    \
+          paths: [platform, eu]   # optional sparse checkout\n\n\
+        Examples:\n  \
+        k8pk sync                 # Clone or update, lint, list added/removed contexts\n  \
+        k8pk sync --json          # Same as JSON")]
+    Sync(SyncArgs),
+
+    /// Edit kubeconfig files in your editor
+    #[command(after_help = "Examples:\n  \
+        k8pk edit dev             # Edit the file that defines 'dev'\n  \
+        k8pk edit dev --goto      # Open the editor at the 'dev' entry\n  \
+        k8pk edit --no-review     # Skip the lint/diff review after saving")]
+    Edit(EditArgs),
+
     /// Login to cluster (OCP, K8s, GKE, or Rancher)
     #[command(
         after_help = "OpenShift (OCP) uses the `oc` binary. If it is not on your PATH, run:\n  \
@@ -541,14 +556,26 @@ pub enum Command {
         k8pk login --type ocp https://api.cluster.example.com:6443 --token sha256~abc\n  \
         k8pk login --type ocp https://api.ocp.local:6443 --use-vault\n  \
         k8pk login --type k8s https://k8s.example.com:6443 --pass-entry k8pk/dev\n  \
+        k8pk login --type k8s https://k8s.example.com:6443 --op-item \"dev cluster\"\n  \
         k8pk login --type k8s https://k8s.example.com:6443 --client-certificate ./client.crt --client-key ./client.key\n  \
         k8pk login --type k8s https://k8s.example.com:6443 --auth exec --exec-command aws --exec-arg eks --exec-arg get-token\n  \
+        k8pk login --type k8s https://k8s.example.com:6443 --from-secret sa-token.yaml\n  \
         k8pk login --type k8s https://k8s.example.com:6443 --test\n  \
         k8pk login --wizard\n  \
         k8pk login --auth-help"
     )]
     Login(LoginArgs),
 
+    /// CI pipelines: isolated kubeconfig from secrets in the environment
+    #[command(after_help = "Examples:\n  \
+        eval \"$(k8pk ci setup)\"                              # From $KUBECONFIG_DATA or $K8S_SERVER + $K8S_TOKEN\n  \
+        k8pk ci setup --shell github-actions >> \"$GITHUB_ENV\"\n  \
+        k8pk ci setup --kubeconfig-var PROD_KUBECONFIG --context prod -n deploy")]
+    Ci {
+        #[command(subcommand)]
+        command: CiCommand,
+    },
+
     /// Rancher (Prime) operations
     #[command(after_help = "Examples:\n  \
         k8pk rancher pull https://rancher.example.com -u admin -p secret\n  \
@@ -561,113 +588,1238 @@ pub enum Command {
         command: RancherCommand,
     },
 
+    /// Discover clusters from cloud providers and add kubeconfigs for them
+    #[command(after_help = "Examples:\n  \
+        k8pk discover eks                                  # All profiles, default regions\n  \
+        k8pk discover eks --profile prod --region us-east-1 --region eu-west-1\n  \
+        k8pk discover eks --all --pattern 'prod-*'\n  \
+        k8pk discover eks --dry-run --json\n  \
+        k8pk discover gke --project my-project\n  \
+        k8pk discover aks --admin\n  \
+        k8pk discover teleport --proxy teleport.example.com")]
+    Discover {
+        #[command(subcommand)]
+        provider: DiscoverCommand,
+    },
+
     /// Organize a messy kubeconfig into separate files by cluster type
     #[command(
         after_help = "Cluster types: eks, gke, aks, ocp, rancher, k8s (generic)\n\n\
         Examples:\n  \
         k8pk organize --dry-run                    # Preview organization\n  \
         k8pk organize --output-dir ~/.kube/by-type # Organize to directory\n  \
-        k8pk organize --remove-from-source         # Also clean source file"
+        k8pk organize --remove-from-source         # Also clean source file\n  \
+        k8pk organize --rollback ~/.kube/organized/organize-manifest-1700000000.json"
     )]
-    Organize {
-        /// Source kubeconfig file (default: ~/.kube/config)
-        #[arg(long, value_name = "PATH")]
-        file: Option<PathBuf>,
-        /// Output directory for organized files
-        #[arg(long, value_name = "DIR")]
-        output_dir: Option<PathBuf>,
-        /// Preview changes without making them
-        #[arg(long, help = "Preview changes without making them")]
-        dry_run: bool,
-        /// Remove contexts from source after copying
-        #[arg(long, help = "Remove contexts from source after copying")]
-        remove_from_source: bool,
-        /// Output as JSON
-        #[arg(long)]
-        json: bool,
-        /// Suppress non-essential output
-        #[arg(long)]
-        quiet: bool,
-    },
+    Organize(OrganizeArgs),
+
+    /// Show cluster type and source info for contexts
+    #[command(after_help = "Examples:\n  \
+        k8pk which                # Show all contexts\n  \
+        k8pk which prod           # Filter by pattern\n  \
+        k8pk which --type eks --sort last-used\n  \
+        k8pk which --json         # Output as JSON\n  \
+        k8pk which \"$KUBECONFIG\"  # Where a generated kubeconfig came from\n  \
+        k8pk which prod --peek    # Also node count, version, and namespace count")]
+    Which(WhichArgs),
+
+    /// Restore the kubeconfig k8pk last rewrote to its previous content
+    #[command(
+        after_help = "rm, rename-context, prune, and organize --remove-from-source back up a\n\
+        kubeconfig to ~/.local/share/k8pk/backups before rewriting it (the last 50\n\
+        are kept). Each undo consumes the newest backup, so repeating it steps\n\
+        further back. The content an undo replaces is backed up first (shown in\n\
+        --list), so edits made since are not lost.\n\n\
+        Examples:\n  \
+        k8pk undo --dry-run    # Show which file would be restored\n  \
+        k8pk undo              # Restore it\n  \
+        k8pk undo --list       # Show the backups held"
+    )]
+    Undo(UndoArgs),
+
+    /// Remove stale contexts whose API server is unreachable
+    #[command(
+        after_help = "Proposes contexts not used via k8pk for --days (see `k8pk stats`) whose\n\
+        server does not accept a connection, shows the file each lives in, and removes\n\
+        the ones you keep selected together with clusters and users no longer\n\
+        referenced in that file. A backup of each file is taken first.\n\n\
+        Examples:\n  \
+        k8pk prune --dry-run              # Show what would be removed\n  \
+        k8pk prune                        # Pick from the proposals\n  \
+        k8pk prune --days 90 --yes        # Remove all, no prompt\n  \
+        k8pk prune --include-reachable    # Also propose stale contexts that still answer"
+    )]
+    Prune(PruneArgs),
+
+    /// Context usage: most used, time per cluster this week, stale contexts
+    #[command(
+        after_help = "Switches made with k8pk (ctx, ns, pick, login) are logged to\n\
+        ~/.local/share/k8pk/usage.jsonl; time spent is the gap until the next switch,\n\
+        capped at 4h.\n\n\
+        Examples:\n  \
+        k8pk stats                     # Summary\n  \
+        k8pk stats --stale-days 90     # Contexts unused for 90 days\n  \
+        k8pk stats --json              # For dashboards"
+    )]
+    Stats(StatsArgs),
+
+    /// Local summary of clusters, auth methods, expiring credentials, and lint findings
+    #[command(
+        after_help = "Reads every kubeconfig k8pk sees and prints a Markdown summary to share\n\
+        with a platform team. Nothing is sent anywhere; server URLs, user names, and\n\
+        auth methods are included, credentials are not.\n\n\
+        Examples:\n  \
+        k8pk report > k8pk-report.md          # Markdown\n  \
+        k8pk report --json                    # For collecting across machines\n  \
+        k8pk report --expiring-days 7         # Only credentials expiring this week"
+    )]
+    Report(ReportArgs),
+
+    /// Check one permission across many contexts (`kubectl auth can-i` as a matrix)
+    #[command(
+        after_help = "Users with a bearer token are checked with a SelfSubjectAccessReview sent\n\
+        straight to the API server; others (exec plugins, client certificates) and\n\
+        resource names that need API discovery (CRD short names) go through\n\
+        `kubectl auth can-i`. Contexts are checked in parallel.\n\n\
+        Examples:\n  \
+        k8pk can-i get secrets                         # Every context, its own namespace\n  \
+        k8pk can-i delete pods --tag prod              # Contexts tagged prod in the config\n  \
+        k8pk can-i create deploy --context 'dev-*' -n web -n api\n  \
+        k8pk can-i list nodes -A --json"
+    )]
+    CanI(CanIArgs),
+
+    /// Compare installed kubectl/oc versions against each cluster's server version
+    #[command(
+        after_help = "kubectl is supported within one minor version (older or newer) of the API\n\
+        server. oc 4.N is compared as the Kubernetes 1.(N+13) client it bundles.\n\
+        Server versions are cached for five minutes, like `which --peek`. Exits\n\
+        non-zero when any context is outside the supported skew.\n\n\
+        Examples:\n  \
+        k8pk version-skew              # All contexts\n  \
+        k8pk version-skew 'prod-*'     # Only matching contexts\n  \
+        k8pk version-skew --json"
+    )]
+    VersionSkew(VersionSkewArgs),
+
+    /// Manage stored credentials vault
+    #[command(after_help = "Examples:\n  \
+        k8pk vault list              # List stored entries\n  \
+        k8pk vault delete my-cluster # Delete an entry\n  \
+        k8pk vault path              # Show vault file location")]
+    #[command(subcommand)]
+    Vault(VaultCommand),
+
+    /// Live overview of sessions, API reachability, credential expiry, and recent switches
+    #[command(
+        visible_alias = "top-contexts",
+        after_help = "Read-only; refreshes every --interval seconds (r refreshes now, q quits).\n\
+        Each distinct API server gets a TCP probe (skipped in offline mode), and\n\
+        expiry is read from client certificates and JWT tokens in the kubeconfigs;\n\
+        exec-plugin credentials show '-'.\n\n\
+        Examples:\n  \
+        k8pk dashboard                 # Full-screen view\n  \
+        k8pk dashboard --interval 30   # Probe less often\n  \
+        k8pk dashboard --json          # One snapshot, no TUI"
+    )]
+    Dashboard(DashboardArgs),
+
+    /// Tail the events of the current context and namespace
+    #[command(
+        after_help = "Prints the existing events, then follows new ones until interrupted,\n\
+        reconnecting when the watch drops. Warnings are yellow, and warnings about\n\
+        failures (BackOff, Failed*, OOM, Evicted, Unhealthy) red; NO_COLOR turns that off.\n\
+        Uses the current k8pk session unless --context is given.\n\n\
+        Examples:\n  \
+        k8pk events                    # The session's namespace\n  \
+        k8pk events -A                 # All namespaces\n  \
+        k8pk events --context dev -n web --watch-only\n  \
+        k8pk events --json | jq 'select(.severity != \"normal\")'"
+    )]
+    Events(EventsArgs),
+
+    /// Shell in a throwaway debug pod in the current context and namespace
+    #[command(
+        name = "shell-pod",
+        after_help = "Starts a pod that only sleeps, execs a shell in it, and deletes it when the\n\
+        shell exits. Running it again while the pod is up (e.g. in a second terminal)\n\
+        attaches to the same pod instead, and leaves the deletion to the first run.\n\
+        Uses the current k8pk session unless --context is given.\n\n\
+        Examples:\n  \
+        k8pk shell-pod                           # busybox in the session's namespace\n  \
+        k8pk shell-pod --image nicolaka/netshoot --shell bash\n  \
+        k8pk shell-pod --context dev -n web      # Outside a session\n  \
+        k8pk shell-pod --keep                    # Leave the pod running afterwards"
+    )]
+    ShellPod(ShellPodArgs),
+
+    /// Long-running port-forwards that restart on failure
+    #[command(
+        name = "pf",
+        visible_alias = "port-forward",
+        after_help = "Each forward runs `kubectl port-forward` in the background against the\n\
+        context's isolated kubeconfig and is restarted with backoff when it exits.\n\
+        Definitions are saved, so `k8pk pf start` brings them back after a reboot.\n\n\
+        Examples:\n  \
+        k8pk pf add dev web svc/api 8080:80   # Forward localhost:8080 to svc/api:80\n  \
+        k8pk pf list                          # Definitions and whether they run\n  \
+        k8pk pf stop api-8080                 # Stop one (all without a name)\n  \
+        k8pk pf start                         # Start every stopped forward\n  \
+        k8pk pf rm api-8080                   # Stop and forget"
+    )]
+    #[command(subcommand)]
+    Pf(PfCommand),
+
+    /// Manage active k8pk sessions across terminals and tmux
+    #[command(after_help = "Actions:\n  \
+        list (default)  Interactive picker (TTY) or table (pipe)\n  \
+        adopt <ID>      Switch to the same context as another session\n  \
+        register        Register current shell (used by shell integration)\n  \
+        deregister      Remove current shell from registry\n\n\
+        Examples:\n  \
+        k8pk sessions              # Interactive session picker\n  \
+        k8pk sessions list         # Table view\n  \
+        k8pk sessions --json       # Machine-readable output\n  \
+        k8pk sessions adopt 3      # Adopt same context as window 3")]
+    Sessions(SessionsArgs),
+
+    /// Swap the contexts and names of two k8pk tmux windows
+    #[command(
+        after_help = "Each window's shell is switched to the other's context and namespace,\n\
+        and the window names are exchanged. Both shells must be at a prompt.\n\
+        Windows are given by index or name; with one given, it swaps with the\n\
+        current window; with none, the session must have exactly two k8pk windows.\n\n\
+        Examples:\n  \
+        k8pk swap                  # The only two k8pk windows\n  \
+        k8pk swap prod             # The current window and 'prod'\n  \
+        k8pk swap 2 3              # Windows 2 and 3"
+    )]
+    Swap(SwapArgs),
+
+    /// Exec credential plugin written by `login --auth gssapi`: prints an
+    /// ExecCredential with a token fetched from URL using the Kerberos ticket
+    #[command(hide = true)]
+    GssapiToken {
+        #[arg(long, value_name = "URL")]
+        url: String,
+    },
+
+    /// Exec credential plugin of isolated kubeconfigs with a `session_ttl`:
+    /// fails once the session expired, otherwise hands kubectl the original
+    /// credentials
+    #[command(hide = true)]
+    SessionCredential,
+
+    /// Output context or namespace names for shell completion
+    #[command(hide = true)]
+    Complete(CompleteArgs),
+
+    /// Completion protocol used by the generated shell scripts: prints
+    /// candidates for the last word (contexts, aliases, namespaces)
+    #[command(name = "complete-words", alias = "__complete", hide = true)]
+    DynamicComplete(DynamicCompleteArgs),
+
+    /// Workflows, command map, and k8pk vs kubie (read this once)
+    #[command(
+        visible_alias = "topics",
+        after_help = "Shows common tasks, when to use ctx vs pick vs rm, and scripting tips."
+    )]
+    Guide,
+
+    /// Diagnose common k8pk and kubectl issues
+    #[command(after_help = "Examples:\n  \
+        k8pk doctor               # Run all checks\n  \
+        k8pk doctor --fix         # Attempt to fix issues\n  \
+        k8pk doctor --connectivity  # Also try to reach every API server")]
+    Doctor(DoctorArgs),
+}
+
+/// CLI fields for `k8pk contexts`
+#[derive(Args, Debug)]
+pub struct ContextsArgs {
+    /// Output as JSON array
+    #[arg(long, help = "Output as JSON array")]
+    pub json: bool,
+    /// Include source file path for each context
+    #[arg(long, help = "Show which file each context comes from")]
+    pub path: bool,
+    /// Table with friendly name, type, server, namespace, source file, and last use
+    #[arg(long, short = 'w', conflicts_with = "path")]
+    pub wide: bool,
+    /// Only contexts matching PATTERN (exact, glob, or substring, as in `k8pk ctx`)
+    #[arg(long, value_name = "PATTERN")]
+    pub filter: Option<String>,
+    /// Only contexts of this cluster type
+    #[arg(long = "type", value_name = "TYPE", value_parser = CLUSTER_TYPES)]
+    pub cluster_type: Option<String>,
+    /// Sort order (default: kubeconfig order)
+    #[arg(long, value_enum, value_name = "KEY")]
+    pub sort: Option<SortKey>,
+}
+
+/// CLI fields for `k8pk gen`
+#[derive(Args, Debug)]
+pub struct GenArgs {
+    /// Context to extract: name, alias, or a glob/substring matching exactly one (default: current context)
+    #[arg(long, value_name = "NAME")]
+    pub context: Option<String>,
+    /// Output file path (default: stdout)
+    #[arg(long, value_name = "PATH")]
+    pub out: Option<PathBuf>,
+    /// Override the default namespace
+    #[arg(long, value_name = "NS")]
+    pub namespace: Option<String>,
+    /// Replace tokens, client keys, and passwords with placeholders
+    #[arg(long)]
+    pub redact: bool,
+    /// Inline certificate and key files as base64 `-data` fields
+    #[arg(long, visible_alias = "flatten")]
+    pub embed_certs: bool,
+    /// Output as JSON (with --out)
+    #[arg(long, requires = "out")]
+    pub json: bool,
+}
+
+/// CLI fields for `k8pk env`
+#[derive(Args, Debug)]
+pub struct EnvArgs {
+    /// Context name
+    #[arg(long, value_name = "NAME")]
+    pub context: String,
+    /// Namespace (optional)
+    #[arg(long, value_name = "NS")]
+    pub namespace: Option<String>,
+    /// Shell syntax: bash, zsh, or fish (files for other tools: --format)
+    #[arg(long, default_value = "bash", value_name = "SHELL")]
+    pub shell: String,
+    /// Include additional debug info
+    #[arg(long = "detail")]
+    pub detail: bool,
+}
+
+/// CLI fields for `k8pk direnv`
+#[derive(Args, Debug)]
+pub struct DirenvArgs {
+    /// Context name (or alias)
+    #[arg(value_name = "CONTEXT")]
+    pub context: String,
+    /// Namespace
+    #[arg(value_name = "NAMESPACE")]
+    pub namespace: Option<String>,
+    /// Directory holding the .envrc (default: current directory)
+    #[arg(long, value_name = "DIR")]
+    pub dir: Option<PathBuf>,
+    /// Print the stanza instead of writing .envrc
+    #[arg(long, conflicts_with = "watch")]
+    pub print: bool,
+    /// Regenerate whenever the source kubeconfig files change (runs until interrupted)
+    #[arg(long)]
+    pub watch: bool,
+    /// Output as JSON
+    #[arg(long, conflicts_with = "watch")]
+    pub json: bool,
+}
+
+/// CLI fields for `k8pk pick`
+#[derive(Args, Debug)]
+pub struct PickArgs {
+    /// Optional filter: pre-filter contexts (substring/glob), e.g. `k8pk pick prod`
+    #[arg(value_name = "FILTER")]
+    pub filter: Option<String>,
+    /// Output format: env, json, spawn (default: env)
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        help = "Output format: env | json | spawn (default: env)"
+    )]
+    pub output: Option<String>,
+    /// Include additional info in output
+    #[arg(long = "detail")]
+    pub detail: bool,
+    /// Force subshell even when inside tmux
+    #[arg(long, help = "Force subshell instead of tmux window/session")]
+    pub no_tmux: bool,
+    /// Skip TLS certificate verification for the chosen context
+    #[arg(
+        long,
+        visible_alias = "insecure",
+        help = "Skip TLS certificate verification"
+    )]
+    pub insecure_skip_tls: bool,
+    /// Skip session check (also: K8PK_NO_SESSION_CHECK=1)
+    #[arg(
+        long,
+        help = "Skip API session check (fail fast if credentials expired later)"
+    )]
+    pub no_session_check: bool,
+}
+
+/// CLI fields for `k8pk cleanup`
+#[derive(Args, Debug)]
+pub struct CleanupArgs {
+    /// Remove files older than N days
+    #[arg(long, default_value = "30", value_name = "N")]
+    pub days: u64,
+    /// Remove configs for contexts that no longer exist
+    #[arg(long, help = "Remove configs for contexts that no longer exist")]
+    pub orphaned: bool,
+    /// Show what would be deleted without deleting
+    #[arg(long, help = "Preview changes without making them")]
+    pub dry_run: bool,
+    /// Remove ALL generated configs (dangerous!)
+    #[arg(long, help = "Remove all generated configs (use with caution)")]
+    pub all: bool,
+    /// Operate on specific kubeconfig file
+    #[arg(long, value_name = "PATH")]
+    pub from_file: Option<PathBuf>,
+    /// Prompt before each deletion
+    #[arg(long, short = 'i', help = "Prompt before each deletion")]
+    pub interactive: bool,
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// CLI fields for `k8pk rm`
+#[derive(Args, Debug)]
+pub struct RmArgs {
+    /// Context name to remove (interactive picker if omitted)
+    #[arg(value_name = "CONTEXT")]
+    pub context: Option<String>,
+    /// Preview changes without making them
+    #[arg(long, help = "Preview changes without making them")]
+    pub dry_run: bool,
+    /// Skip the confirmation prompt (use with care)
+    #[arg(short = 'y', long, help = "Skip confirmation prompt")]
+    pub yes: bool,
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// CLI fields for `k8pk rename-context`
+#[derive(Args, Debug)]
+pub struct RenameContextArgs {
+    /// Kubeconfig file to modify (default: ~/.kube/config)
+    #[arg(long, value_name = "PATH")]
+    pub from_file: Option<PathBuf>,
+    /// Current context name
+    #[arg(
+        long,
+        value_name = "OLD",
+        required_unless_present_any = ["pattern", "template"],
+        requires = "new_name"
+    )]
+    pub context: Option<String>,
+    /// New context name
+    #[arg(long, value_name = "NEW", requires = "context")]
+    pub new_name: Option<String>,
+    /// Rename all matching contexts with a sed-style expression: s/REGEX/REPLACEMENT/[gi]
+    #[arg(long, value_name = "EXPR", conflicts_with_all = ["context", "template"])]
+    pub pattern: Option<String>,
+    /// Rename all contexts from a template, e.g. '{type}-{cluster}'
+    #[arg(long, value_name = "TEMPLATE", conflicts_with = "context")]
+    pub template: Option<String>,
+    /// Preview changes without making them
+    #[arg(long, help = "Preview changes without making them")]
+    pub dry_run: bool,
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// CLI fields for `k8pk copy-context`
+#[derive(Args, Debug)]
+pub struct CopyContextArgs {
+    /// Source kubeconfig file
+    #[arg(long, value_name = "PATH")]
+    pub from_file: PathBuf,
+    /// Destination file (default: ~/.kube/config)
+    #[arg(long, value_name = "PATH")]
+    pub to_file: Option<PathBuf>,
+    /// Context name to copy
+    #[arg(long, value_name = "NAME")]
+    pub context: String,
+    /// Rename context in destination
+    #[arg(long, value_name = "NAME")]
+    pub new_name: Option<String>,
+    /// Inline certificate and key files as base64 `-data` fields
+    #[arg(long)]
+    pub embed_certs: bool,
+    /// Preview changes without making them
+    #[arg(long, help = "Preview changes without making them")]
+    pub dry_run: bool,
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// CLI fields for `k8pk create-context`
+#[derive(Args, Debug)]
+pub struct CreateContextArgs {
+    /// Name of the new context
+    pub name: Option<String>,
+    /// API server URL of a new cluster
+    #[arg(long, value_name = "URL")]
+    pub server: Option<String>,
+    /// Cluster name (default with --server: <NAME>-cluster); without
+    /// --server, an existing cluster to use
+    #[arg(long, value_name = "NAME")]
+    pub cluster_name: Option<String>,
+    /// Existing user whose credentials the context uses
+    #[arg(long, value_name = "USER")]
+    pub user_from: Option<String>,
+    /// Default namespace for the context
+    #[arg(long, value_name = "NS")]
+    pub namespace: Option<String>,
+    /// Read the spec from a YAML file
+    #[arg(long, value_name = "FILE")]
+    pub template: Option<PathBuf>,
+    /// Kubeconfig to write (default: ~/.kube/config)
+    #[arg(long, value_name = "PATH")]
+    pub to_file: Option<PathBuf>,
+    /// Replace an existing context or cluster of the same name
+    #[arg(long)]
+    pub force: bool,
+    /// Preview changes without making them
+    #[arg(long, help = "Preview changes without making them")]
+    pub dry_run: bool,
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// CLI fields for `k8pk merge`
+#[derive(Args, Debug)]
+pub struct MergeArgs {
+    /// Kubeconfig files to merge
+    #[arg(long, num_args = 1.., value_name = "FILES")]
+    pub files: Vec<PathBuf>,
+    /// Output file (default: stdout)
+    #[arg(long, value_name = "PATH")]
+    pub out: Option<PathBuf>,
+    /// Overwrite existing contexts with same name
+    #[arg(long, help = "Overwrite existing contexts with same name")]
+    pub overwrite: bool,
+    /// Strategy for preferences, extensions, and current-context: first, last, or union
+    #[arg(long, value_enum, default_value_t, value_name = "STRATEGY")]
+    pub prefer: MergePrefer,
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// CLI fields for `k8pk diff`
+#[derive(Args, Debug)]
+pub struct DiffArgs {
+    /// First kubeconfig file
+    #[arg(long, value_name = "PATH")]
+    pub file1: PathBuf,
+    /// Second kubeconfig file
+    #[arg(long, value_name = "PATH")]
+    pub file2: PathBuf,
+    /// Only show differences (hide common contexts)
+    #[arg(long, help = "Only show differences")]
+    pub diff_only: bool,
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// CLI fields for `k8pk exec`
+#[derive(Args, Debug)]
+pub struct ExecArgs {
+    /// Context to use (supports glob patterns and aliases of them)
+    #[arg(value_name = "CONTEXT")]
+    pub context: String,
+    /// Namespace to use (optional, defaults to context's configured namespace)
+    #[arg(value_name = "NAMESPACE")]
+    pub namespace: Option<String>,
+    /// Command to execute (after --)
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+    pub command: Vec<String>,
+    /// Exit immediately on first error
+    #[arg(short = 'e', long, help = "Exit on first error")]
+    pub fail_early: bool,
+    /// Suppress context/namespace headers
+    #[arg(long, help = "Suppress context/namespace headers")]
+    pub no_headers: bool,
+    /// Output results as JSON (wraps stdout/stderr per context)
+    #[arg(long)]
+    pub json: bool,
+    /// Skip session check and credential refresh (fail fast if expired)
+    #[arg(long, help = "Skip session liveness check and re-login (for scripts)")]
+    pub no_session_check: bool,
+    /// Run the command inside this running Docker container
+    #[arg(long, value_name = "CONTAINER", conflicts_with = "devcontainer")]
+    pub docker: Option<String>,
+    /// Run the command inside the dev container for DIR (default: current directory)
+    #[arg(long, value_name = "DIR", num_args = 0..=1, default_missing_value = ".")]
+    pub devcontainer: Option<PathBuf>,
+    /// Run the command on USER@HOST over ssh (e.g. a jump host); the kubeconfig
+    /// goes to a private temp file there and is removed afterwards
+    #[arg(long, value_name = "USER@HOST", conflicts_with_all = ["docker", "devcontainer"])]
+    pub ssh: Option<String>,
+    /// With --ssh, send the kubeconfig with secrets replaced by placeholders
+    #[arg(long, requires = "ssh")]
+    pub redact: bool,
+}
+
+/// CLI fields for `k8pk ctx`
+#[derive(Args, Debug)]
+pub struct CtxArgs {
+    /// Context name, alias, glob, or substring ('-' or '-N' to go back through this shell's switches)
+    #[arg(value_name = "CONTEXT")]
+    pub context: Option<String>,
+    /// Also switch namespace
+    #[arg(short = 'n', long, value_name = "NS")]
+    pub namespace: Option<String>,
+    /// Spawn recursive subshell instead of modifying current
+    #[arg(
+        short = 'r',
+        long,
+        help = "Spawn subshell instead of modifying current"
+    )]
+    pub recursive: bool,
+    /// Output format: env, json, spawn (default: env for eval)
+    #[arg(short = 'o', long, value_name = "FORMAT")]
+    pub output: Option<String>,
+    /// Force subshell even when inside tmux
+    #[arg(long, help = "Force subshell instead of tmux window/session")]
+    pub no_tmux: bool,
+    /// Skip TLS certificate verification for this context
+    #[arg(
+        long,
+        visible_alias = "insecure",
+        help = "Skip TLS certificate verification"
+    )]
+    pub insecure_skip_tls: bool,
+    /// Skip session check (also: K8PK_NO_SESSION_CHECK=1)
+    #[arg(long, help = "Skip API session check")]
+    pub no_session_check: bool,
+}
+
+/// CLI fields for `k8pk ns`
+#[derive(Args, Debug)]
+pub struct NsArgs {
+    /// Namespace name (use '-' for previous)
+    #[arg(value_name = "NAMESPACE")]
+    pub namespace: Option<String>,
+    /// Forget the remembered namespace for the current context
+    #[arg(
+        long,
+        conflicts_with = "namespace",
+        help = "Forget the remembered namespace for the current context"
+    )]
+    pub forget: bool,
+    /// Create the namespace if the cluster doesn't have it (asks first)
+    #[arg(long, requires = "namespace", conflicts_with = "forget")]
+    pub create: bool,
+    /// Create without asking (with --create)
+    #[arg(
+        short = 'y',
+        long,
+        requires = "create",
+        help = "Skip confirmation prompt"
+    )]
+    pub yes: bool,
+    /// Spawn recursive subshell instead of modifying current
+    #[arg(
+        short = 'r',
+        long,
+        help = "Spawn subshell instead of modifying current"
+    )]
+    pub recursive: bool,
+    /// Output format: env, json, spawn (default: env)
+    #[arg(short = 'o', long, value_name = "FORMAT")]
+    pub output: Option<String>,
+    /// Force subshell even when inside tmux
+    #[arg(long, help = "Force subshell instead of tmux window/session")]
+    pub no_tmux: bool,
+    /// Skip TLS certificate verification for this context
+    #[arg(
+        long,
+        visible_alias = "insecure",
+        help = "Skip TLS certificate verification"
+    )]
+    pub insecure_skip_tls: bool,
+    /// Pick several namespaces and open a tmux window per namespace
+    /// (outside tmux, print one command per namespace)
+    #[arg(
+        long,
+        conflicts_with_all = ["namespace", "forget", "create", "recursive", "output"],
+        help = "Pick several namespaces; open a tmux window (or print a command) for each"
+    )]
+    pub multi: bool,
+    /// Show pod counts and phases per namespace in the picker (one list call)
+    #[arg(long, conflicts_with_all = ["namespace", "forget"])]
+    pub details: bool,
+}
+
+/// CLI fields for `k8pk lint`
+#[derive(Args, Debug)]
+pub struct LintArgs {
+    /// Specific file to lint (default: all configured files)
+    #[arg(long, value_name = "PATH")]
+    pub file: Option<PathBuf>,
+    /// Enable additional checks (cert expiry, etc.)
+    #[arg(long, help = "Enable additional checks (cert expiry, etc.)")]
+    pub strict: bool,
+    /// Repair fixable issues in place (backed up first)
+    #[arg(long)]
+    pub fix: bool,
+    /// With --fix, show the repairs without writing
+    #[arg(long, requires = "fix")]
+    pub dry_run: bool,
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// CLI fields for `k8pk edit`
+#[derive(Args, Debug)]
+pub struct EditArgs {
+    /// Context to edit (opens its source file)
+    #[arg(value_name = "CONTEXT")]
+    pub context: Option<String>,
+    /// Override $EDITOR
+    #[arg(long, value_name = "CMD")]
+    pub editor: Option<String>,
+    /// Open the editor at the context's line (vim, nano, emacs, code, ...)
+    #[arg(
+        long,
+        requires = "context",
+        help = "Open the editor at the context's line"
+    )]
+    pub goto: bool,
+    /// Skip linting, diffing, and confirming the edit
+    #[arg(long, help = "Skip the lint/diff review after editing")]
+    pub no_review: bool,
+}
+
+/// CLI fields for `k8pk organize`
+#[derive(Args, Debug)]
+pub struct OrganizeArgs {
+    /// Source kubeconfig file (default: ~/.kube/config)
+    #[arg(long, value_name = "PATH")]
+    pub file: Option<PathBuf>,
+    /// Undo a previous organize run using the manifest it wrote
+    #[arg(
+        long,
+        value_name = "MANIFEST",
+        conflicts_with_all = ["file", "output_dir", "remove_from_source", "auto_alias", "embed_certs"]
+    )]
+    pub rollback: Option<PathBuf>,
+    /// Output directory for organized files
+    #[arg(long, value_name = "DIR")]
+    pub output_dir: Option<PathBuf>,
+    /// Preview changes without making them
+    #[arg(long, help = "Preview changes without making them")]
+    pub dry_run: bool,
+    /// Remove contexts from source after copying
+    #[arg(long, help = "Remove contexts from source after copying")]
+    pub remove_from_source: bool,
+    /// Register friendly names (e.g. 'prod' for an EKS ARN) as aliases without asking
+    #[arg(long, help = "Register friendly context names as aliases")]
+    pub auto_alias: bool,
+    /// Inline certificate and key files as base64 `-data` fields
+    #[arg(long)]
+    pub embed_certs: bool,
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// CLI fields for `k8pk which`
+#[derive(Args, Debug)]
+pub struct WhichArgs {
+    /// Filter contexts by pattern (glob), or a generated kubeconfig file to trace
+    #[arg(value_name = "PATTERN")]
+    pub context: Option<String>,
+    /// Same as PATTERN
+    #[arg(long, value_name = "PATTERN", conflicts_with = "context")]
+    pub filter: Option<String>,
+    /// Only contexts of this cluster type
+    #[arg(long = "type", value_name = "TYPE", value_parser = CLUSTER_TYPES)]
+    pub cluster_type: Option<String>,
+    /// Sort order (default: kubeconfig order)
+    #[arg(long, value_enum, value_name = "KEY")]
+    pub sort: Option<SortKey>,
+    /// Also query each cluster for its node count, server version, and
+    /// namespace count (cached for five minutes)
+    #[arg(long)]
+    pub peek: bool,
+    /// Output as JSON
+    #[arg(long, help = "Output as JSON")]
+    pub json: bool,
+}
+
+/// CLI fields for `k8pk prune`
+#[derive(Args, Debug)]
+pub struct PruneArgs {
+    /// Propose contexts not used for this many days
+    #[arg(long, value_name = "DAYS", default_value_t = 30)]
+    pub days: u64,
+    /// Also propose stale contexts whose server is reachable
+    #[arg(long)]
+    pub include_reachable: bool,
+    /// Seconds to wait for each server
+    #[arg(long, value_name = "SECS", default_value_t = 2)]
+    pub timeout: u64,
+    /// Preview changes without making them
+    #[arg(long, help = "Preview changes without making them")]
+    pub dry_run: bool,
+    /// Remove every proposed context without prompting
+    #[arg(short = 'y', long, help = "Skip confirmation prompt")]
+    pub yes: bool,
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// CLI fields for `k8pk events`
+#[derive(Args, Debug)]
+pub struct EventsArgs {
+    /// Context name, alias, or pattern (default: the current session's)
+    #[arg(long, value_name = "CONTEXT")]
+    pub context: Option<String>,
+    /// Namespace (default: the session's)
+    #[arg(
+        short = 'n',
+        long,
+        value_name = "NS",
+        conflicts_with = "all_namespaces"
+    )]
+    pub namespace: Option<String>,
+    /// Events of every namespace
+    #[arg(short = 'A', long)]
+    pub all_namespaces: bool,
+    /// Only new events, not the ones that already exist
+    #[arg(long)]
+    pub watch_only: bool,
+    /// One JSON object per event and line
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// CLI fields for `k8pk shell-pod`
+#[derive(Args, Debug)]
+pub struct ShellPodArgs {
+    /// Image to run
+    #[arg(long, default_value = "busybox")]
+    pub image: String,
+    /// Command to exec in the pod
+    #[arg(long, default_value = "sh")]
+    pub shell: String,
+    /// Pod name (default: k8pk-shell-<user>)
+    #[arg(long)]
+    pub name: Option<String>,
+    /// Context name, alias, or pattern (default: the current session's)
+    #[arg(long, value_name = "CONTEXT")]
+    pub context: Option<String>,
+    /// Namespace (default: the session's)
+    #[arg(short = 'n', long, value_name = "NS")]
+    pub namespace: Option<String>,
+    /// Leave the pod running after the shell exits
+    #[arg(long)]
+    pub keep: bool,
+    /// Don't ask before starting a pod in a protected context
+    #[arg(short = 'y', long)]
+    pub yes: bool,
+}
+
+/// CLI fields for `k8pk sessions`
+#[derive(Args, Debug)]
+pub struct SessionsArgs {
+    /// Action: list (default), adopt, register, deregister
+    #[arg(value_name = "ACTION", default_value = "list")]
+    pub action: String,
+    /// Target window/session id (for adopt)
+    #[arg(value_name = "TARGET")]
+    pub target: Option<String>,
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+    /// Force subshell even when inside tmux
+    #[arg(long, help = "Force subshell instead of tmux window/session")]
+    pub no_tmux: bool,
+}
+
+/// CLI fields for `k8pk export-safe`
+#[derive(Args, Debug)]
+pub struct ExportSafeArgs {
+    /// Only this context (with its cluster and user)
+    #[arg(long, value_name = "NAME")]
+    pub context: Option<String>,
+    /// Write to a file instead of stdout
+    #[arg(long, value_name = "PATH")]
+    pub out: Option<PathBuf>,
+    /// Output as JSON (with --out)
+    #[arg(long, requires = "out")]
+    pub json: bool,
+}
+
+/// CLI fields for `k8pk namespaces`
+#[derive(Args, Debug)]
+pub struct NamespacesArgs {
+    /// Context to query namespaces for (defaults to K8PK_CONTEXT or current-context)
+    #[arg(long, value_name = "NAME")]
+    pub context: Option<String>,
+    /// Output as JSON array
+    #[arg(long, help = "Output as JSON array")]
+    pub json: bool,
+}
+
+/// CLI fields for `k8pk use-context`
+#[derive(Args, Debug)]
+pub struct UseContextArgs {
+    /// Context to make current
+    pub name: String,
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// CLI fields for `k8pk run`
+#[derive(Args, Debug)]
+pub struct RunArgs {
+    /// Context or alias, optionally followed by /NAMESPACE
+    #[arg(value_name = "CONTEXT[/NAMESPACE]")]
+    pub target: String,
+    /// Command to execute (after --)
+    #[arg(required = true, last = true)]
+    pub command: Vec<String>,
+    /// Skip session check and credential refresh (fail fast if expired)
+    #[arg(long)]
+    pub no_session_check: bool,
+}
+
+/// CLI fields for `k8pk refresh`
+#[derive(Args, Debug)]
+pub struct RefreshArgs {
+    /// Skip the session check and re-login; only re-read the source kubeconfigs
+    #[arg(long)]
+    pub no_session_check: bool,
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// CLI fields for `k8pk info`
+#[derive(Args, Debug)]
+pub struct InfoArgs {
+    /// What to show: ctx, ns, depth, config, cluster, user, server, expiry, oc, all
+    #[arg(default_value = "all", value_name = "WHAT")]
+    pub what: String,
+    /// Show friendly context display name (ctx only)
+    #[arg(long, help = "Show display context name (ctx only)")]
+    pub display: bool,
+    /// Show raw context name (ctx only)
+    #[arg(long, help = "Show raw context name (ctx only)")]
+    pub raw: bool,
+}
+
+/// CLI fields for `k8pk history`
+#[derive(Args, Debug)]
+pub struct HistoryArgs {
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+    /// Clear all history
+    #[arg(long)]
+    pub clear: bool,
+    /// This shell's back/forward stack instead of the global history
+    #[arg(long, conflicts_with = "clear")]
+    pub shell: bool,
+}
+
+/// CLI fields for `k8pk back`
+#[derive(Args, Debug)]
+pub struct BackArgs {
+    /// Number of switches to go back
+    #[arg(value_name = "N", default_value_t = 1, value_parser = clap::value_parser!(u16).range(1..))]
+    pub steps: u16,
+    /// Output format: env, json, spawn (default: env)
+    #[arg(short = 'o', long, value_name = "FORMAT")]
+    pub output: Option<String>,
+    /// Force subshell even when inside tmux
+    #[arg(long, help = "Force subshell instead of tmux window/session")]
+    pub no_tmux: bool,
+}
+
+/// CLI fields for `k8pk forward`
+#[derive(Args, Debug)]
+pub struct ForwardArgs {
+    /// Number of switches to go forward
+    #[arg(value_name = "N", default_value_t = 1, value_parser = clap::value_parser!(u16).range(1..))]
+    pub steps: u16,
+    /// Output format: env, json, spawn (default: env)
+    #[arg(short = 'o', long, value_name = "FORMAT")]
+    pub output: Option<String>,
+    /// Force subshell even when inside tmux
+    #[arg(long, help = "Force subshell instead of tmux window/session")]
+    pub no_tmux: bool,
+}
+
+/// CLI fields for `k8pk export`
+#[derive(Args, Debug)]
+pub struct ExportArgs {
+    /// Context name
+    pub context: String,
+    /// Namespace name
+    pub namespace: String,
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// CLI fields for `k8pk init`
+#[derive(Args, Debug)]
+pub struct InitArgs {
+    /// Shell: bash, zsh, fish (default: from $SHELL)
+    #[arg(value_name = "SHELL")]
+    pub shell: Option<String>,
+    /// Append the init line to the shell rc file instead of printing
+    #[arg(long)]
+    pub install: bool,
+}
+
+/// CLI fields for `k8pk alias`
+#[derive(Args, Debug)]
+pub struct AliasArgs {
+    #[command(subcommand)]
+    pub command: Option<AliasCommand>,
+    /// Install the kctx/kns shell helpers in your shell rc (same as `k8pk init --install`)
+    #[arg(long)]
+    pub install: bool,
+    /// Shell for --install (default: from $SHELL)
+    #[arg(long, value_name = "SHELL", requires = "install")]
+    pub shell: Option<String>,
+}
+
+/// CLI fields for `k8pk sync`
+#[derive(Args, Debug)]
+pub struct SyncArgs {
+    /// Repository to clone (overrides sync.repo)
+    #[arg(long, value_name = "URL")]
+    pub repo: Option<String>,
+    /// Branch or tag (overrides sync.branch)
+    #[arg(long, value_name = "NAME")]
+    pub branch: Option<String>,
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// CLI fields for `k8pk undo`
+#[derive(Args, Debug)]
+pub struct UndoArgs {
+    /// List backups, newest first, instead of restoring
+    #[arg(long, conflicts_with = "dry_run")]
+    pub list: bool,
+    /// Preview changes without making them
+    #[arg(long, help = "Preview changes without making them")]
+    pub dry_run: bool,
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// CLI fields for `k8pk stats`
+#[derive(Args, Debug)]
+pub struct StatsArgs {
+    /// Report contexts not used for this many days as stale
+    #[arg(long, value_name = "DAYS", default_value_t = 30)]
+    pub stale_days: u64,
+    /// Number of most-used contexts to show
+    #[arg(long, value_name = "N", default_value_t = 10)]
+    pub top: usize,
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
 
-    /// Show cluster type and source info for contexts
-    #[command(after_help = "Examples:\n  \
-        k8pk which                # Show all contexts\n  \
-        k8pk which prod           # Filter by pattern\n  \
-        k8pk which --json         # Output as JSON")]
-    Which {
-        /// Filter contexts by pattern (glob)
-        #[arg(value_name = "PATTERN")]
-        context: Option<String>,
-        /// Output as JSON
-        #[arg(long, help = "Output as JSON")]
-        json: bool,
-    },
+/// CLI fields for `k8pk report`
+#[derive(Args, Debug)]
+pub struct ReportArgs {
+    /// Report credentials expiring within this many days
+    #[arg(long, value_name = "DAYS", default_value_t = 30)]
+    pub expiring_days: u64,
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
 
-    /// Manage stored credentials vault
-    #[command(after_help = "Examples:\n  \
-        k8pk vault list              # List stored entries\n  \
-        k8pk vault delete my-cluster # Delete an entry\n  \
-        k8pk vault path              # Show vault file location")]
-    #[command(subcommand)]
-    Vault(VaultCommand),
+/// CLI fields for `k8pk version-skew`
+#[derive(Args, Debug)]
+pub struct VersionSkewArgs {
+    /// Only contexts matching this pattern (glob)
+    #[arg(value_name = "PATTERN")]
+    pub pattern: Option<String>,
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
 
-    /// Manage active k8pk sessions across terminals and tmux
-    #[command(after_help = "Actions:\n  \
-        list (default)  Interactive picker (TTY) or table (pipe)\n  \
-        adopt <ID>      Switch to the same context as another session\n  \
-        register        Register current shell (used by shell integration)\n  \
-        deregister      Remove current shell from registry\n\n\
-        Examples:\n  \
-        k8pk sessions              # Interactive session picker\n  \
-        k8pk sessions list         # Table view\n  \
-        k8pk sessions --json       # Machine-readable output\n  \
-        k8pk sessions adopt 3      # Adopt same context as window 3")]
-    Sessions {
-        /// Action: list (default), adopt, register, deregister
-        #[arg(value_name = "ACTION", default_value = "list")]
-        action: String,
-        /// Target window/session id (for adopt)
-        #[arg(value_name = "TARGET")]
-        target: Option<String>,
-        /// Output as JSON
-        #[arg(long)]
-        json: bool,
-        /// Force subshell even when inside tmux
-        #[arg(long, help = "Force subshell instead of tmux window/session")]
-        no_tmux: bool,
-    },
+/// CLI fields for `k8pk dashboard`
+#[derive(Args, Debug)]
+pub struct DashboardArgs {
+    /// Seconds between refreshes
+    #[arg(long, default_value_t = 10, value_name = "SECS")]
+    pub interval: u64,
+    /// Print one snapshot as JSON instead of opening the dashboard
+    #[arg(long)]
+    pub json: bool,
+}
 
-    /// Output context or namespace names for shell completion
-    #[command(hide = true)]
-    Complete {
-        /// What to complete: contexts, namespaces
-        #[arg(value_name = "TYPE")]
-        complete_type: String,
-        /// Context name (required for namespace completion)
-        #[arg(long, value_name = "NAME")]
-        context: Option<String>,
-    },
+/// CLI fields for `k8pk swap`
+#[derive(Args, Debug)]
+pub struct SwapArgs {
+    /// First window (index or name)
+    #[arg(value_name = "WINDOW")]
+    pub first: Option<String>,
+    /// Second window (index or name)
+    #[arg(value_name = "WINDOW")]
+    pub second: Option<String>,
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
 
-    /// Workflows, command map, and k8pk vs kubie (read this once)
-    #[command(
-        visible_alias = "topics",
-        after_help = "Shows common tasks, when to use ctx vs pick vs rm, and scripting tips."
+/// CLI fields for `k8pk complete`
+#[derive(Args, Debug)]
+pub struct CompleteArgs {
+    /// What to complete: contexts, namespaces
+    #[arg(value_name = "TYPE")]
+    pub complete_type: String,
+    /// Context name (required for namespace completion)
+    #[arg(long, value_name = "NAME")]
+    pub context: Option<String>,
+}
+
+/// CLI fields for `k8pk complete-words`
+#[derive(Args, Debug)]
+pub struct DynamicCompleteArgs {
+    /// Shell the output is formatted for: bash, zsh, fish
+    #[arg(value_name = "SHELL")]
+    pub shell: String,
+    /// Command line after `k8pk`, ending with the word being completed
+    #[arg(
+        value_name = "WORDS",
+        trailing_var_arg = true,
+        allow_hyphen_values = true
     )]
-    Guide,
+    pub words: Vec<String>,
+}
 
-    /// Diagnose common k8pk and kubectl issues
-    #[command(after_help = "Examples:\n  \
-        k8pk doctor               # Run all checks\n  \
-        k8pk doctor --fix         # Attempt to fix issues")]
-    Doctor {
-        /// Attempt to fix detected issues
-        #[arg(long, help = "Attempt to fix detected issues")]
-        fix: bool,
-        /// Output as JSON
-        #[arg(long)]
-        json: bool,
-    },
+/// CLI fields for `k8pk doctor`
+#[derive(Args, Debug)]
+pub struct DoctorArgs {
+    /// Attempt to fix detected issues
+    #[arg(long, help = "Attempt to fix detected issues")]
+    pub fix: bool,
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+    /// Also check that each context's API server accepts connections
+    #[arg(long)]
+    pub connectivity: bool,
+}
+
+/// CLI fields for `k8pk set-context`
+#[derive(Args, Debug)]
+pub struct SetContextArgs {
+    /// Context to create or modify
+    #[arg(required_unless_present = "current", conflicts_with = "current")]
+    pub name: Option<String>,
+    /// Modify the current context
+    #[arg(long)]
+    pub current: bool,
+    /// Cluster the context uses
+    #[arg(long, value_name = "NAME")]
+    pub cluster: Option<String>,
+    /// User the context uses
+    #[arg(long, value_name = "NAME")]
+    pub user: Option<String>,
+    /// Default namespace of the context
+    #[arg(long, value_name = "NS")]
+    pub namespace: Option<String>,
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// CLI fields for `k8pk view`
+#[derive(Args, Debug)]
+pub struct ViewArgs {
+    /// Only the current context and the cluster and user it references
+    #[arg(long)]
+    pub minify: bool,
+    /// Inline referenced certificate and key files
+    #[arg(long)]
+    pub flatten: bool,
+    /// Show secrets and certificate data
+    #[arg(long)]
+    pub raw: bool,
+    /// Use this context as current-context
+    #[arg(long, value_name = "NAME")]
+    pub context: Option<String>,
+    /// Output format: yaml (default) or json
+    #[arg(short, long, value_name = "FORMAT")]
+    pub output: Option<String>,
+}
+
+/// CLI fields for `k8pk can-i`
+#[derive(Args, Debug)]
+pub struct CanIArgs {
+    /// Verb: get, list, watch, create, update, patch, delete, '*'
+    pub verb: String,
+    /// Resource (pods, deploy, deployments.apps, pods/log) or a non-resource URL (/healthz)
+    pub resource: String,
+    /// Only contexts matching this pattern (glob); all contexts when omitted
+    #[arg(long = "context", value_name = "PATTERN")]
+    pub context: Option<String>,
+    /// Only contexts with this tag (`tags:` under `contexts:` in the k8pk config)
+    #[arg(long, value_name = "TAG")]
+    pub tag: Option<String>,
+    /// Namespace to check (repeatable); default: each context's own namespace
+    #[arg(short = 'n', long = "namespace", value_name = "NAMESPACE")]
+    pub namespaces: Vec<String>,
+    /// Check across all namespaces
+    #[arg(short = 'A', long, conflicts_with = "namespaces")]
+    pub all_namespaces: bool,
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// CLI fields for `k8pk update`
+#[derive(Args, Debug)]
+pub struct UpdateArgs {
+    /// Check for updates without installing
+    #[arg(long, help = "Check for updates without installing")]
+    pub check: bool,
+    /// Force update even if already on latest
+    #[arg(long, help = "Force reinstall even if up to date")]
+    pub force: bool,
+    /// Release channel
+    #[arg(long, value_enum, default_value_t, value_name = "CHANNEL")]
+    pub channel: UpdateChannel,
+    /// Install this version instead of the channel's latest
+    #[arg(long = "version", value_name = "X.Y.Z", conflicts_with = "channel")]
+    pub pin: Option<String>,
+    /// Verify the Sigstore cosign signature (.sig/.pem) of the download
+    #[arg(long)]
+    pub cosign: bool,
+    /// Verify the download's .minisig signature with this minisign public key
+    #[arg(long, value_name = "KEY")]
+    pub minisign_key: Option<String>,
+    /// Replace the binary even if Homebrew, apt, dnf, or Nix installed it
+    #[arg(long)]
+    pub force_binary: bool,
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
 }
 
 /// CLI fields for `k8pk login` (passed through to `run_login_cli`).
@@ -676,7 +1828,7 @@ pub struct LoginArgs {
     /// Cluster type: 'ocp', 'k8s', 'gke', or 'rancher' (default: auto-detect from server URL)
     #[arg(long = "type", value_name = "TYPE", default_value = "auto")]
     pub login_type: String,
-    /// Authentication mode: auto | token | userpass | client-cert | exec
+    /// Authentication mode: auto | token | userpass | client-cert | exec | gssapi
     #[arg(long, value_name = "MODE", default_value = "auto")]
     pub auth: String,
     /// Show auth examples and exit
@@ -685,6 +1837,13 @@ pub struct LoginArgs {
     /// Use guided login wizard
     #[arg(long)]
     pub wizard: bool,
+    /// Log in with a profile from the config's `clusters:` section (a
+    /// server URL works here too)
+    #[arg(value_name = "PROFILE", conflicts_with_all = ["wizard", "from_clipboard"])]
+    pub profile: Option<String>,
+    /// List the login profiles from the config and exit
+    #[arg(long)]
+    pub list: bool,
     /// Server URL
     #[arg(long, value_name = "SERVER")]
     pub server: Option<String>,
@@ -694,6 +1853,10 @@ pub struct LoginArgs {
     /// Bearer token for authentication
     #[arg(long, value_name = "TOKEN")]
     pub token: Option<String>,
+    /// Take server and token from an `oc login --token=... --server=...`
+    /// command on the clipboard (OpenShift console: Copy login command)
+    #[arg(long, conflicts_with_all = ["token", "server", "server_pos", "wizard"])]
+    pub from_clipboard: bool,
     /// Username for basic auth
     #[arg(short = 'u', long, value_name = "USER")]
     pub username: Option<String>,
@@ -706,8 +1869,16 @@ pub struct LoginArgs {
     /// Example: 'pass show k8pk/dev' returns:
     ///   sha256~abc123...
     ///   token: sha256~abc123...
-    #[arg(long, value_name = "ENTRY")]
+    #[arg(long, value_name = "ENTRY", conflicts_with_all = ["op_item", "bw_item"])]
     pub pass_entry: Option<String>,
+    /// Read credentials from a 1Password item (`op item get`); uses the
+    /// username and password fields, and a field labelled "token" if present
+    #[arg(long, value_name = "ITEM", conflicts_with = "bw_item")]
+    pub op_item: Option<String>,
+    /// Read credentials from a Bitwarden item (`bw get item`, needs BW_SESSION);
+    /// uses the login's username and password, and a custom field "token" if present
+    #[arg(long, value_name = "ITEM")]
+    pub bw_item: Option<String>,
     /// Exec auth command (k8s only)
     #[arg(long, value_name = "CMD")]
     pub exec_command: Option<String>,
@@ -720,13 +1891,16 @@ pub struct LoginArgs {
     /// Exec auth API version (k8s only)
     #[arg(long, value_name = "VERSION")]
     pub exec_api_version: Option<String>,
-    /// Exec auth preset: aws-eks | gke | aks
+    /// Exec auth preset: aws-eks | gke | aks | teleport
     #[arg(long, value_name = "NAME")]
     pub exec_preset: Option<String>,
-    /// Exec auth cluster name (aws-eks)
+    /// Token endpoint behind the Kerberos proxy, for --auth gssapi (k8s only)
+    #[arg(long, value_name = "URL")]
+    pub gssapi_token_url: Option<String>,
+    /// Exec auth cluster name (aws-eks, teleport kube cluster)
     #[arg(long, value_name = "NAME")]
     pub exec_cluster: Option<String>,
-    /// Exec auth server ID (aks)
+    /// Exec auth server ID (aks) or Teleport cluster name (teleport)
     #[arg(long, value_name = "ID")]
     pub exec_server_id: Option<String>,
     /// Exec auth region (aws-eks)
@@ -756,8 +1930,14 @@ pub struct LoginArgs {
     )]
     pub use_vault: bool,
     /// Certificate authority file
-    #[arg(long, value_name = "PATH")]
+    #[arg(long, value_name = "PATH", conflicts_with = "ca_data")]
     pub certificate_authority: Option<PathBuf>,
+    /// Certificate authority data, embedded in the kubeconfig (k8s only): base64 PEM, PEM text, or a file path
+    #[arg(long, value_name = "DATA")]
+    pub ca_data: Option<String>,
+    /// Build the user from a service-account token Secret exported with kubectl (YAML or JSON, `-` for stdin; k8s only)
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["token", "username", "client_certificate"])]
+    pub from_secret: Option<PathBuf>,
     /// Client certificate file (k8s only)
     #[arg(long, value_name = "PATH")]
     pub client_certificate: Option<PathBuf>,
@@ -767,6 +1947,10 @@ pub struct LoginArgs {
     /// Print kubeconfig and exit without writing or switching
     #[arg(long)]
     pub dry_run: bool,
+    /// Log in even if a context for the same server and user already exists
+    /// (by default k8pk offers to switch to it or refresh it)
+    #[arg(long)]
+    pub force_new: bool,
     /// Validate credentials after login
     #[arg(long)]
     pub test: bool,
@@ -776,9 +1960,9 @@ pub struct LoginArgs {
     /// Rancher auth provider (rancher only): local, activedirectory, openldap, freeipa, azuread, github, auto, or v3-public path (e.g. activeDirectoryProviders/my-ad). Default local; auto tries common providers. RKE1/RKE2 use the same Rancher login API.
     #[arg(long, value_name = "PROVIDER", default_value = "local")]
     pub rancher_auth_provider: String,
-    /// Suppress non-essential output
+    /// Register the friendly context name (e.g. 'prod' for an EKS ARN) as an alias without asking
     #[arg(long)]
-    pub quiet: bool,
+    pub auto_alias: bool,
     /// Output as JSON
     #[arg(long)]
     pub json: bool,
@@ -808,6 +1992,130 @@ pub enum ConfigCommand {
     Edit,
 }
 
+#[derive(Subcommand, Debug)]
+pub enum AliasCommand {
+    /// List aliases in effect (config, team config, $K8PK_ALIASES)
+    #[command(visible_alias = "ls")]
+    List {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Add or replace an alias in the k8pk config
+    Add {
+        /// Alias name
+        #[arg(value_name = "NAME")]
+        name: String,
+        /// Context the alias points to
+        #[arg(value_name = "CONTEXT")]
+        context: String,
+        /// Skip the context existence and shadowing checks
+        #[arg(long)]
+        force: bool,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Remove aliases from the k8pk config
+    #[command(visible_alias = "rm")]
+    Remove {
+        /// Alias names
+        #[arg(value_name = "NAME", required = true)]
+        names: Vec<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SourcesCommand {
+    /// List kubeconfig files, enabled and disabled
+    #[command(visible_alias = "ls")]
+    List {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Leave a kubeconfig file out of merging and pickers (kept on disk)
+    Disable {
+        /// Kubeconfig file
+        #[arg(value_name = "PATH")]
+        path: String,
+    },
+    /// Merge a disabled kubeconfig file again
+    Enable {
+        /// Kubeconfig file
+        #[arg(value_name = "PATH")]
+        path: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum PfCommand {
+    /// Save a port-forward and start it
+    Add {
+        /// Context name, alias, or pattern
+        #[arg(value_name = "CONTEXT")]
+        context: String,
+        /// Namespace of the target
+        #[arg(value_name = "NAMESPACE")]
+        namespace: String,
+        /// Resource to forward to (svc/x, pod/x, deploy/x)
+        #[arg(value_name = "TARGET")]
+        target: String,
+        /// Port mappings: LOCAL:REMOTE or PORT
+        #[arg(value_name = "PORTS", required = true)]
+        ports: Vec<String>,
+        /// Name to refer to it by (default: <resource>-<local port>)
+        #[arg(long)]
+        name: Option<String>,
+        /// Local address to listen on (default: localhost)
+        #[arg(long)]
+        address: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// List port-forwards and whether they are running
+    #[command(visible_alias = "ls")]
+    List {
+        /// Only forwards for this context
+        #[arg(long, value_name = "CONTEXT")]
+        context: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Start a stopped port-forward (all without a name)
+    Start {
+        #[arg(value_name = "NAME")]
+        name: Option<String>,
+    },
+    /// Stop a running port-forward (all without a name), keeping its definition
+    Stop {
+        #[arg(value_name = "NAME")]
+        name: Option<String>,
+    },
+    /// Stop a port-forward and delete its definition
+    #[command(visible_alias = "remove")]
+    Rm {
+        #[arg(value_name = "NAME")]
+        name: String,
+    },
+    /// Show a port-forward's log
+    Logs {
+        #[arg(value_name = "NAME")]
+        name: String,
+    },
+    /// Run the restart loop of one port-forward (started by `pf add`/`pf start`)
+    #[command(hide = true)]
+    Supervise {
+        #[arg(value_name = "NAME")]
+        name: String,
+    },
+}
+
 #[derive(Subcommand)]
 pub enum VaultCommand {
     /// List all stored credential entries
@@ -833,6 +2141,184 @@ pub enum VaultCommand {
     },
 }
 
+#[derive(Subcommand)]
+pub enum DiscoverCommand {
+    /// Discover EKS clusters via the AWS CLI
+    #[command(
+        after_help = "Runs `aws eks list-clusters` for each profile/region, lets you pick clusters, \
+        and writes an exec-auth kubeconfig (aws eks get-token) per cluster into ~/.kube/eks/ \
+        (or --output-dir). Profiles default to `aws configure list-profiles`; regions default \
+        to each profile's configured region.\n\n\
+        Examples:\n  \
+        k8pk discover eks\n  \
+        k8pk discover eks --profile prod --region us-east-1\n  \
+        k8pk discover eks --all --pattern 'prod-*'"
+    )]
+    Eks {
+        /// AWS profile to search (repeatable; default: all configured profiles)
+        #[arg(long, value_name = "PROFILE")]
+        profile: Vec<String>,
+        /// AWS region to search (repeatable; default: each profile's region)
+        #[arg(long, value_name = "REGION")]
+        region: Vec<String>,
+        /// Add every discovered cluster without prompting
+        #[arg(long)]
+        all: bool,
+        /// Only consider clusters whose name matches this pattern (exact, glob, or substring)
+        #[arg(long, value_name = "PATTERN")]
+        pattern: Option<String>,
+        /// Directory to save kubeconfigs (default: ~/.kube/eks)
+        #[arg(long, value_name = "DIR")]
+        output_dir: Option<PathBuf>,
+        /// Show what would be added without writing files
+        #[arg(long)]
+        dry_run: bool,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Discover GKE clusters via gcloud
+    #[command(
+        after_help = "Runs `gcloud container clusters list` for each project, lets you pick clusters, \
+        and writes a kubeconfig per cluster using gke-gcloud-auth-plugin into ~/.kube/gke/ \
+        (or --output-dir). Projects default to those set in your gcloud configurations.\n\n\
+        Examples:\n  \
+        k8pk discover gke\n  \
+        k8pk discover gke --project acme-prod --project acme-dev\n  \
+        k8pk discover gke --all --pattern 'prod-*'"
+    )]
+    Gke {
+        /// GCP project to search (repeatable; default: projects from gcloud configurations)
+        #[arg(long, value_name = "PROJECT")]
+        project: Vec<String>,
+        /// Add every discovered cluster without prompting
+        #[arg(long)]
+        all: bool,
+        /// Only consider clusters whose name matches this pattern (exact, glob, or substring)
+        #[arg(long, value_name = "PATTERN")]
+        pattern: Option<String>,
+        /// Directory to save kubeconfigs (default: ~/.kube/gke)
+        #[arg(long, value_name = "DIR")]
+        output_dir: Option<PathBuf>,
+        /// Show what would be added without writing files
+        #[arg(long)]
+        dry_run: bool,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Discover AKS clusters via the Azure CLI
+    #[command(
+        after_help = "Runs `az aks list`, lets you pick clusters, and writes a kubeconfig per cluster \
+        into ~/.kube/aks/ (or --output-dir). AAD-enabled clusters authenticate with kubelogin \
+        using your `az login` session; --admin fetches cluster admin credentials instead.\n\n\
+        Examples:\n  \
+        k8pk discover aks\n  \
+        k8pk discover aks --subscription prod-sub --pattern 'prod-*'\n  \
+        k8pk discover aks --admin --all"
+    )]
+    Aks {
+        /// Azure subscription to search (repeatable; default: the az CLI's current subscription)
+        #[arg(long, value_name = "SUBSCRIPTION")]
+        subscription: Vec<String>,
+        /// Fetch cluster admin credentials (requires local accounts to be enabled)
+        #[arg(long)]
+        admin: bool,
+        /// Add every discovered cluster without prompting
+        #[arg(long)]
+        all: bool,
+        /// Only consider clusters whose name matches this pattern (exact, glob, or substring)
+        #[arg(long, value_name = "PATTERN")]
+        pattern: Option<String>,
+        /// Directory to save kubeconfigs (default: ~/.kube/aks)
+        #[arg(long, value_name = "DIR")]
+        output_dir: Option<PathBuf>,
+        /// Show what would be added without writing files
+        #[arg(long)]
+        dry_run: bool,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Discover kube clusters behind a Teleport proxy via tsh
+    #[command(
+        after_help = "Runs `tsh kube ls`, lets you pick clusters, and writes the kubeconfig \
+        `tsh kube login` generates for each into ~/.kube/teleport/ (or --output-dir). \
+        Contexts authenticate through `tsh kube credentials`; when the Teleport certificate \
+        has expired, k8pk runs `tsh login` for you.\n\n\
+        Examples:\n  \
+        k8pk discover teleport\n  \
+        k8pk discover teleport --proxy teleport.example.com\n  \
+        k8pk discover teleport --all --pattern 'prod-*'"
+    )]
+    Teleport {
+        /// Teleport proxy to log in to when there is no active tsh session
+        #[arg(long, value_name = "HOST")]
+        proxy: Option<String>,
+        /// Add every discovered cluster without prompting
+        #[arg(long)]
+        all: bool,
+        /// Only consider clusters whose name matches this pattern (exact, glob, or substring)
+        #[arg(long, value_name = "PATTERN")]
+        pattern: Option<String>,
+        /// Directory to save kubeconfigs (default: ~/.kube/teleport)
+        #[arg(long, value_name = "DIR")]
+        output_dir: Option<PathBuf>,
+        /// Show what would be added without writing files
+        #[arg(long)]
+        dry_run: bool,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum CiCommand {
+    /// Write an isolated kubeconfig from CI secrets, check the cluster, print exports
+    #[command(
+        after_help = "Credentials are read from the first set variable of:\n  \
+        base64 kubeconfig:  K8PK_KUBECONFIG_DATA, KUBECONFIG_DATA, KUBE_CONFIG_DATA\n  \
+        or server + token:  K8PK_SERVER, K8S_SERVER, KUBE_SERVER and K8PK_TOKEN, K8S_TOKEN, KUBE_TOKEN\n  \
+        optional CA:        K8PK_CA_DATA, K8S_CA_DATA, KUBE_CA_DATA (base64 or PEM)\n\n\
+        The kubeconfig goes to .k8pk/kubeconfig in the CI workspace ($GITHUB_WORKSPACE,\n\
+        $CI_PROJECT_DIR, ... or the current directory). On GitHub Actions the secrets are\n\
+        registered with ::add-mask:: before anything else is printed."
+    )]
+    Setup {
+        /// Context to keep from the kubeconfig, or the name to create in token mode (default: ci)
+        #[arg(long, value_name = "NAME")]
+        context: Option<String>,
+        /// Default namespace for the context
+        #[arg(short = 'n', long, value_name = "NS")]
+        namespace: Option<String>,
+        /// Where to write the kubeconfig
+        #[arg(long, value_name = "PATH")]
+        out: Option<PathBuf>,
+        /// Variable holding a base64 kubeconfig
+        #[arg(long, value_name = "VAR")]
+        kubeconfig_var: Option<String>,
+        /// Variable holding the API server URL
+        #[arg(long, value_name = "VAR")]
+        server_var: Option<String>,
+        /// Variable holding the bearer token
+        #[arg(long, value_name = "VAR")]
+        token_var: Option<String>,
+        /// Variable holding the cluster CA
+        #[arg(long, value_name = "VAR")]
+        ca_var: Option<String>,
+        /// Skip the cluster connectivity check
+        #[arg(long)]
+        no_verify: bool,
+        /// Export syntax: bash, zsh, fish, make, dotenv, github-actions
+        #[arg(long, default_value = "bash", value_name = "SHELL")]
+        shell: String,
+        /// Output the result as JSON instead of exports
+        #[arg(long)]
+        json: bool,
+    },
+}
+
 #[derive(Subcommand)]
 pub enum RancherCommand {
     /// Pull kubeconfigs for all clusters from a Rancher (Prime) server
@@ -884,8 +2370,5 @@ pub enum RancherCommand {
         /// Output as JSON
         #[arg(long)]
         json: bool,
-        /// Suppress non-essential output
-        #[arg(long)]
-        quiet: bool,
     },
 }
@@ -0,0 +1,123 @@
+//! Wall-clock time for the timestamps k8pk records (provenance, usage log,
+//! caches, sessions, port-forward state), and RFC 3339 in both directions
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Seconds since the Unix epoch (0 for a clock set before it)
+pub fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Unix seconds as `YYYY-MM-DDTHH:MM:SSZ`
+pub fn rfc3339(secs: u64) -> String {
+    // Howard Hinnant's civil_from_days
+    let days = (secs / 86400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let doe = days - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    let rem = secs % 86400;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+/// Unix seconds as `YYYY-MM-DD` (UTC)
+pub fn format_date(secs: u64) -> String {
+    let mut date = rfc3339(secs);
+    date.truncate(10);
+    date
+}
+
+/// Parse an RFC 3339 timestamp (`2026-10-16T20:00:00.5+02:00`) to unix seconds
+pub fn parse_rfc3339(s: &str) -> Option<u64> {
+    let (date, rest) = s.split_once('T')?;
+    let mut d = date.splitn(3, '-').map(|p| p.parse::<i64>().ok());
+    let (year, month, day) = (d.next()??, d.next()??, d.next()??);
+
+    let time_end = rest.find(['Z', 'z', '+', '-']).unwrap_or(rest.len());
+    let (time, zone) = rest.split_at(time_end);
+    let time = time.split('.').next()?;
+    let mut t = time.splitn(3, ':').map(|p| p.parse::<i64>().ok());
+    let (hour, min, sec) = (t.next()??, t.next()??, t.next()??);
+
+    let offset = match zone.chars().next() {
+        Some(sign @ ('+' | '-')) => {
+            let (oh, om) = zone[1..].split_once(':')?;
+            let secs = oh.parse::<i64>().ok()? * 3600 + om.parse::<i64>().ok()? * 60;
+            if sign == '+' {
+                secs
+            } else {
+                -secs
+            }
+        }
+        _ => 0,
+    };
+
+    let secs = days_from_civil(year, month, day) * 86400 + hour * 3600 + min * 60 + sec - offset;
+    u64::try_from(secs).ok()
+}
+
+/// Days since the epoch (Howard Hinnant's days_from_civil)
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    era * 146_097 + yoe * 365 + yoe / 4 - yoe / 100 + doy - 719_468
+}
+
+/// "3h 05m", "12m", "40s": the rough length of `secs`
+pub fn format_duration(secs: u64) -> String {
+    if secs >= 3600 {
+        format!("{}h {:02}m", secs / 3600, secs % 3600 / 60)
+    } else if secs >= 60 {
+        format!("{}m", secs / 60)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn date_format() {
+        assert_eq!(rfc3339(1_794_763_577), "2026-11-15T17:26:17Z");
+        assert_eq!(format_date(1_794_763_577), "2026-11-15");
+    }
+
+    #[test]
+    fn parse_rfc3339_offsets() {
+        assert_eq!(parse_rfc3339("1970-01-01T00:00:00Z"), Some(0));
+        assert_eq!(parse_rfc3339("2024-03-01T12:00:00Z"), Some(1_709_294_400));
+        assert_eq!(
+            parse_rfc3339("2024-03-01T14:00:00.123456+02:00"),
+            Some(1_709_294_400)
+        );
+        assert!(parse_rfc3339("not a date").is_none());
+        assert_eq!(parse_rfc3339(&rfc3339(1_794_763_577)), Some(1_794_763_577));
+    }
+
+    #[test]
+    fn duration_format() {
+        assert_eq!(format_duration(45), "45s");
+        assert_eq!(format_duration(12 * 60), "12m");
+        assert_eq!(format_duration(3 * 3600 + 5 * 60), "3h 05m");
+    }
+}
@@ -0,0 +1,288 @@
+//! Context aliases: `k8pk alias` list/add/remove, and friendly-name aliases
+//! offered after login/organize
+
+use crate::config;
+use crate::error::{K8pkError, Result};
+use crate::i18n::tr;
+use crate::kubeconfig::{self, KubeConfig};
+use crate::prompt::Ask;
+use inquire::Confirm;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct AliasEntry {
+    pub alias: String,
+    pub context: String,
+}
+
+/// Aliases in effect (config files plus `K8PK_ALIASES` / `--alias`), by name
+pub fn list_aliases() -> Result<Vec<AliasEntry>> {
+    let mut entries: Vec<AliasEntry> = config::load()?
+        .aliases
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(alias, context)| AliasEntry { alias, context })
+        .collect();
+    entries.sort_by(|a, b| a.alias.cmp(&b.alias));
+    Ok(entries)
+}
+
+/// Check that `name` can alias `context`: the context exists and the name
+/// does not shadow a real context (`force` skips both checks)
+fn validate_alias(
+    name: &str,
+    context: &str,
+    context_names: &HashSet<String>,
+    force: bool,
+) -> Result<AliasEntry> {
+    if name.contains(['=', ',']) {
+        return Err(K8pkError::InvalidArgument(format!(
+            "invalid alias '{}': names cannot contain '=' or ','",
+            name
+        )));
+    }
+    let (alias, context) = config::parse_alias(&format!("{}={}", name, context))?;
+    if alias == context {
+        return Err(K8pkError::InvalidArgument(format!(
+            "alias '{}' would point to itself",
+            alias
+        )));
+    }
+    if !force {
+        // A glob target (e.g. `prod-*` for `k8pk exec`) must match something
+        let found = if context.contains(['*', '?', '[']) {
+            let names: Vec<String> = context_names.iter().cloned().collect();
+            !super::match_pattern(&context, &names).is_empty()
+        } else {
+            context_names.contains(&context)
+        };
+        if !found {
+            return Err(K8pkError::ContextNotFound(context));
+        }
+        if context_names.contains(&alias) {
+            return Err(K8pkError::InvalidArgument(format!(
+                "'{}' is already a context name; the alias would hide it (use --force to add anyway)",
+                alias
+            )));
+        }
+    }
+    Ok(AliasEntry { alias, context })
+}
+
+/// Save `name -> context` in the k8pk config, replacing an alias of the same name
+pub fn add_alias(name: &str, context: &str, paths: &[PathBuf], force: bool) -> Result<AliasEntry> {
+    let context_names: HashSet<String> = kubeconfig::load_merged(paths)
+        .map(|m| m.context_names().into_iter().collect())
+        .unwrap_or_default();
+    let entry = validate_alias(name, context, &context_names, force)?;
+    config::add_aliases(&[(entry.alias.clone(), entry.context.clone())])?;
+    Ok(entry)
+}
+
+/// Remove aliases from the k8pk config; every name must exist there
+pub fn remove_aliases(names: &[String]) -> Result<Vec<AliasEntry>> {
+    let existing = config::file_aliases()?;
+    if let Some(missing) = names.iter().find(|n| !existing.contains_key(*n)) {
+        return Err(K8pkError::AliasNotFound(missing.clone()));
+    }
+    Ok(config::remove_aliases(names)?
+        .into_iter()
+        .map(|(alias, context)| AliasEntry { alias, context })
+        .collect())
+}
+
+pub fn print_alias_list(entries: &[AliasEntry]) {
+    if entries.is_empty() {
+        println!("No aliases configured. Add one with: k8pk alias add <NAME> <CONTEXT>");
+        return;
+    }
+    let width = entries.iter().map(|e| e.alias.len()).max().unwrap_or(0);
+    for e in entries {
+        println!("{:width$}  {}", e.alias, e.context, width = width);
+    }
+}
+
+/// Plan friendly-name aliases for `candidates` (context name, cluster type).
+///
+/// Contexts whose friendly name equals their own name, or that already have an
+/// alias, are skipped. A friendly name that is already taken (by another alias,
+/// an existing context, or an earlier candidate) falls back to `{name}-{type}`,
+/// then `{name}-2`, `{name}-3`, ...
+pub fn plan_auto_aliases(
+    candidates: &[(String, &str)],
+    aliases: &HashMap<String, String>,
+    context_names: &HashSet<String>,
+) -> Vec<AliasEntry> {
+    let aliased: HashSet<&str> = aliases.values().map(String::as_str).collect();
+    let mut taken: HashSet<String> = aliases.keys().cloned().collect();
+    taken.extend(context_names.iter().cloned());
+
+    let mut planned = Vec::new();
+    for (context, cluster_type) in candidates {
+        if aliased.contains(context.as_str()) {
+            continue;
+        }
+        let friendly = kubeconfig::friendly_context_name(context, cluster_type);
+        if friendly.is_empty() || friendly == *context {
+            continue;
+        }
+
+        let typed = format!("{}-{}", friendly, cluster_type);
+        let alias = std::iter::once(friendly.clone())
+            .chain(std::iter::once(typed))
+            .chain((2..).map(|n| format!("{}-{}", friendly, n)))
+            .find(|name| !taken.contains(name))
+            .expect("numbered fallbacks are unbounded");
+
+        taken.insert(alias.clone());
+        planned.push(AliasEntry {
+            alias,
+            context: context.clone(),
+        });
+    }
+    planned
+}
+
+/// (context, cluster type) for contexts in `file`, optionally limited to `only`
+pub fn alias_candidates_from_file(
+    file: &Path,
+    only: Option<&[String]>,
+) -> Result<Vec<(String, &'static str)>> {
+    if !file.exists() {
+        return Err(K8pkError::KubeconfigNotFound(file.to_path_buf()));
+    }
+    let cfg: KubeConfig = serde_yaml_ng::from_str(&fs::read_to_string(file)?)?;
+    Ok(cfg
+        .contexts
+        .iter()
+        .filter(|c| only.is_none_or(|names| names.contains(&c.name)))
+        .map(|c| {
+            let server = kubeconfig::extract_context_refs(&c.rest)
+                .ok()
+                .and_then(|(cluster, _)| {
+                    cfg.clusters
+                        .iter()
+                        .find(|cl| cl.name == cluster)
+                        .and_then(|cl| kubeconfig::extract_server_url_from_cluster(&cl.rest))
+                });
+            let cluster_type = kubeconfig::detect_cluster_type(&c.name, server.as_deref());
+            (c.name.clone(), cluster_type)
+        })
+        .collect())
+}
+
+/// Register friendly aliases for `candidates`.
+/// With `auto` they are saved directly; otherwise the user is asked on a TTY
+/// (never when `quiet`). Returns the aliases that were saved.
+pub fn register_friendly_aliases(
+    candidates: &[(String, &str)],
+    context_names: &HashSet<String>,
+    auto: bool,
+    quiet: bool,
+) -> Result<Vec<AliasEntry>> {
+    let existing = config::file_aliases()?;
+    let planned = plan_auto_aliases(candidates, &existing, context_names);
+    if planned.is_empty() {
+        return Ok(planned);
+    }
+
+    if !auto {
+        if quiet || !crate::prompt::can_prompt() {
+            return Ok(Vec::new());
+        }
+        eprintln!("Friendly aliases available:");
+        for entry in &planned {
+            eprintln!("  {} -> {}", entry.alias, entry.context);
+        }
+        let accept = Confirm::new(&tr("confirm-register-aliases"))
+            .with_default(false)
+            .ask()?;
+        if !accept {
+            return Ok(Vec::new());
+        }
+    }
+
+    let pairs: Vec<(String, String)> = planned
+        .iter()
+        .map(|e| (e.alias.clone(), e.context.clone()))
+        .collect();
+    config::add_aliases(&pairs)?;
+    if !quiet {
+        for entry in &planned {
+            eprintln!("Added alias: {} -> {}", entry.alias, entry.context);
+        }
+    }
+    Ok(planned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EKS_PROD: &str = "arn:aws:eks:us-east-1:123456789012:cluster/prod";
+    const EKS_PROD_EU: &str = "arn:aws:eks:eu-west-1:123456789012:cluster/prod";
+
+    #[test]
+    fn validate_alias_checks_context_and_shadowing() {
+        let contexts = HashSet::from(["prod".to_string(), EKS_PROD.to_string()]);
+        let entry = validate_alias("p", EKS_PROD, &contexts, false).unwrap();
+        assert_eq!(entry.alias, "p");
+        assert!(matches!(
+            validate_alias("p", "missing", &contexts, false),
+            Err(K8pkError::ContextNotFound(_))
+        ));
+        assert!(validate_alias("all-prod", "*prod*", &contexts, false).is_ok());
+        assert!(validate_alias("q", "qa-*", &contexts, false).is_err());
+        assert!(validate_alias("prod", EKS_PROD, &contexts, false).is_err());
+        assert!(validate_alias("prod", EKS_PROD, &contexts, true).is_ok());
+        assert!(validate_alias("a,b", "prod", &contexts, true).is_err());
+        assert!(validate_alias("a=b", "prod", &contexts, true).is_err());
+        assert!(validate_alias("", "prod", &contexts, true).is_err());
+        assert!(validate_alias("prod", "prod", &contexts, true).is_err());
+    }
+
+    #[test]
+    fn plan_uses_friendly_name() {
+        let planned = plan_auto_aliases(
+            &[(EKS_PROD.to_string(), "eks")],
+            &HashMap::new(),
+            &HashSet::new(),
+        );
+        assert_eq!(
+            planned,
+            vec![AliasEntry {
+                alias: "prod".into(),
+                context: EKS_PROD.into()
+            }]
+        );
+    }
+
+    #[test]
+    fn plan_skips_plain_and_already_aliased() {
+        let aliases = HashMap::from([("p".to_string(), EKS_PROD.to_string())]);
+        let planned = plan_auto_aliases(
+            &[(EKS_PROD.to_string(), "eks"), ("dev".to_string(), "k8s")],
+            &aliases,
+            &HashSet::new(),
+        );
+        assert!(planned.is_empty());
+    }
+
+    #[test]
+    fn plan_handles_collisions() {
+        let aliases = HashMap::from([("prod".to_string(), "something-else".to_string())]);
+        let contexts = HashSet::from(["prod-eks".to_string()]);
+        let planned = plan_auto_aliases(
+            &[
+                (EKS_PROD.to_string(), "eks"),
+                (EKS_PROD_EU.to_string(), "eks"),
+            ],
+            &aliases,
+            &contexts,
+        );
+        let names: Vec<&str> = planned.iter().map(|e| e.alias.as_str()).collect();
+        assert_eq!(names, vec!["prod-2", "prod-3"]);
+    }
+}
@@ -0,0 +1,444 @@
+//! `k8pk can-i`: one access check across many contexts, as a matrix of
+//! allowed/denied per context and namespace. Users with a bearer token are
+//! asked directly with a SelfSubjectAccessReview; others (exec plugins,
+//! client certificates) go through `kubectl auth can-i`.
+
+use super::{ContextFilter, ContextRow};
+use crate::error::{K8pkError, Result};
+use crate::kubeconfig::{self, BearerApi};
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// Request timeout for each check
+const CAN_I_TIMEOUT_SECS: u64 = 5;
+
+/// Built-in resources by the names kubectl accepts (plural, singular, short),
+/// with their plural and API group, so the native check needs no discovery
+const KNOWN_RESOURCES: &[(&[&str], &str, &str)] = &[
+    (&["*"], "*", "*"),
+    (&["pods", "pod", "po"], "pods", ""),
+    (&["services", "service", "svc"], "services", ""),
+    (&["configmaps", "configmap", "cm"], "configmaps", ""),
+    (&["secrets", "secret"], "secrets", ""),
+    (&["namespaces", "namespace", "ns"], "namespaces", ""),
+    (&["nodes", "node", "no"], "nodes", ""),
+    (
+        &["serviceaccounts", "serviceaccount", "sa"],
+        "serviceaccounts",
+        "",
+    ),
+    (
+        &["persistentvolumeclaims", "persistentvolumeclaim", "pvc"],
+        "persistentvolumeclaims",
+        "",
+    ),
+    (
+        &["persistentvolumes", "persistentvolume", "pv"],
+        "persistentvolumes",
+        "",
+    ),
+    (&["events", "event", "ev"], "events", ""),
+    (
+        &["deployments", "deployment", "deploy"],
+        "deployments",
+        "apps",
+    ),
+    (
+        &["statefulsets", "statefulset", "sts"],
+        "statefulsets",
+        "apps",
+    ),
+    (&["daemonsets", "daemonset", "ds"], "daemonsets", "apps"),
+    (&["replicasets", "replicaset", "rs"], "replicasets", "apps"),
+    (&["jobs", "job"], "jobs", "batch"),
+    (&["cronjobs", "cronjob", "cj"], "cronjobs", "batch"),
+    (
+        &["ingresses", "ingress", "ing"],
+        "ingresses",
+        "networking.k8s.io",
+    ),
+    (&["roles", "role"], "roles", "rbac.authorization.k8s.io"),
+    (
+        &["rolebindings", "rolebinding"],
+        "rolebindings",
+        "rbac.authorization.k8s.io",
+    ),
+    (
+        &["clusterroles", "clusterrole"],
+        "clusterroles",
+        "rbac.authorization.k8s.io",
+    ),
+    (
+        &["clusterrolebindings", "clusterrolebinding"],
+        "clusterrolebindings",
+        "rbac.authorization.k8s.io",
+    ),
+];
+
+/// What a review asks about
+#[derive(Debug, PartialEq, Eq)]
+enum Target {
+    Resource {
+        group: String,
+        resource: String,
+        subresource: Option<String>,
+    },
+    NonResource {
+        path: String,
+    },
+}
+
+/// `pods`, `deploy`, `deployments.apps`, `pods/log`, or `/healthz`. None
+/// when only API discovery (kubectl) can tell the group, e.g. a CRD's short
+/// name.
+fn parse_target(resource: &str) -> Option<Target> {
+    if resource.starts_with('/') {
+        return Some(Target::NonResource {
+            path: resource.to_string(),
+        });
+    }
+    let (name, subresource) = match resource.split_once('/') {
+        Some((name, sub)) => (name, Some(sub.to_string())),
+        None => (resource, None),
+    };
+    let (resource, group) = match KNOWN_RESOURCES
+        .iter()
+        .find(|(names, _, _)| names.contains(&name))
+    {
+        Some((_, plural, group)) => (plural.to_string(), group.to_string()),
+        None => {
+            let (resource, group) = name.split_once('.')?;
+            (resource.to_string(), group.to_string())
+        }
+    };
+    Some(Target::Resource {
+        group,
+        resource,
+        subresource,
+    })
+}
+
+#[derive(Debug, Serialize)]
+pub struct AccessCheck {
+    pub context: String,
+    /// None for a cluster-wide check (`--all-namespaces`)
+    pub namespace: Option<String>,
+    /// None when the check itself failed
+    pub allowed: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// `api` (SelfSubjectAccessReview) or the CLI that answered
+    pub via: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CanIMatrix {
+    pub verb: String,
+    pub resource: String,
+    /// Namespaces asked for with `-n` (empty: each context's own)
+    pub namespaces: Vec<String>,
+    pub all_namespaces: bool,
+    pub checks: Vec<AccessCheck>,
+}
+
+/// What to check and where
+pub struct CanIQuery<'a> {
+    pub verb: &'a str,
+    pub resource: &'a str,
+    pub filter: &'a ContextFilter,
+    /// Namespaces to check in each context; empty means the context's own
+    pub namespaces: &'a [String],
+    pub all_namespaces: bool,
+}
+
+/// Check `verb` on `resource` in every context passing the filter, in
+/// parallel
+pub fn can_i(paths: &[PathBuf], query: &CanIQuery) -> Result<CanIMatrix> {
+    crate::config::require_online("k8pk can-i")?;
+    let rows: Vec<ContextRow> = super::select_context_rows(paths, query.filter, None)?;
+    let kubeconfig = std::env::join_paths(paths)
+        .ok()
+        .map(|p| p.to_string_lossy().into_owned());
+    let target = parse_target(query.resource);
+
+    let checks: Vec<AccessCheck> = std::thread::scope(|s| {
+        let handles: Vec<_> = rows
+            .iter()
+            .map(|row| {
+                let namespaces: Vec<Option<String>> = if query.all_namespaces {
+                    vec![None]
+                } else if !query.namespaces.is_empty() {
+                    query.namespaces.iter().cloned().map(Some).collect()
+                } else {
+                    vec![Some(
+                        row.namespace.clone().unwrap_or_else(|| "default".into()),
+                    )]
+                };
+                let kubeconfig = kubeconfig.as_deref();
+                let target = target.as_ref();
+                s.spawn(move || check_context(&row.context, kubeconfig, query, target, namespaces))
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().unwrap_or_default())
+            .collect()
+    });
+
+    Ok(CanIMatrix {
+        verb: query.verb.to_string(),
+        resource: query.resource.to_string(),
+        namespaces: query.namespaces.to_vec(),
+        all_namespaces: query.all_namespaces,
+        checks,
+    })
+}
+
+fn check_context(
+    context: &str,
+    kubeconfig: Option<&str>,
+    query: &CanIQuery,
+    target: Option<&Target>,
+    namespaces: Vec<Option<String>>,
+) -> Vec<AccessCheck> {
+    let api = target
+        .and_then(|_| BearerApi::for_context(context, kubeconfig, CAN_I_TIMEOUT_SECS).ok())
+        .flatten();
+    namespaces
+        .into_iter()
+        .map(|namespace| {
+            let ns = namespace.as_deref();
+            let (via, outcome) = match (&api, target) {
+                (Some(api), Some(target)) => {
+                    ("api".to_string(), review_api(api, query.verb, target, ns))
+                }
+                _ => match review_cli(context, kubeconfig, query.verb, query.resource, ns) {
+                    Ok((cli, allowed)) => (cli, Ok(allowed)),
+                    Err(e) => ("kubectl".to_string(), Err(e)),
+                },
+            };
+            AccessCheck {
+                context: context.to_string(),
+                namespace,
+                allowed: outcome.as_ref().ok().copied(),
+                error: outcome.err().map(|e| e.to_string()),
+                via,
+            }
+        })
+        .collect()
+}
+
+/// POST a SelfSubjectAccessReview and read `status.allowed`
+fn review_api(
+    api: &BearerApi,
+    verb: &str,
+    target: &Target,
+    namespace: Option<&str>,
+) -> Result<bool> {
+    let spec = match target {
+        Target::Resource {
+            group,
+            resource,
+            subresource,
+        } => serde_json::json!({
+            "resourceAttributes": {
+                "namespace": namespace.unwrap_or(""),
+                "verb": verb,
+                "group": group,
+                "resource": resource,
+                "subresource": subresource.as_deref().unwrap_or(""),
+            }
+        }),
+        Target::NonResource { path } => serde_json::json!({
+            "nonResourceAttributes": { "path": path, "verb": verb }
+        }),
+    };
+    let review = serde_json::json!({
+        "apiVersion": "authorization.k8s.io/v1",
+        "kind": "SelfSubjectAccessReview",
+        "spec": spec,
+    });
+    let resp = api
+        .post("/apis/authorization.k8s.io/v1/selfsubjectaccessreviews")
+        .json(&review)
+        .send()
+        .map_err(|e| K8pkError::HttpError(e.to_string()))?;
+    if !resp.status().is_success() {
+        return Err(K8pkError::HttpError(format!(
+            "access review failed: HTTP {}",
+            resp.status()
+        )));
+    }
+    let body: serde_json::Value = resp
+        .json()
+        .map_err(|e| K8pkError::HttpError(e.to_string()))?;
+    Ok(body["status"]["allowed"].as_bool() == Some(true))
+}
+
+/// `kubectl auth can-i`, which prints `yes` or `no` (exiting 1 for no)
+fn review_cli(
+    context: &str,
+    kubeconfig: Option<&str>,
+    verb: &str,
+    resource: &str,
+    namespace: Option<&str>,
+) -> Result<(String, bool)> {
+    let (cli, mut cmd) =
+        kubeconfig::cli_command_with_timeout(context, kubeconfig, CAN_I_TIMEOUT_SECS)?;
+    cmd.args(["auth", "can-i", verb, resource]);
+    // Non-resource URLs are not namespaced
+    match namespace {
+        _ if resource.starts_with('/') => &mut cmd,
+        Some(ns) => cmd.args(["-n", ns]),
+        None => cmd.arg("--all-namespaces"),
+    };
+    let output = cmd.output()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let name = std::path::Path::new(&cli)
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or(cli);
+    match stdout.trim() {
+        answer if answer.starts_with("yes") => Ok((name, true)),
+        answer if answer.starts_with("no") => Ok((name, false)),
+        _ => Err(K8pkError::CommandFailed(format!(
+            "{} auth can-i failed: {}",
+            name,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))),
+    }
+}
+
+fn cell(check: &AccessCheck) -> &'static str {
+    match check.allowed {
+        Some(true) => "yes",
+        Some(false) => "no",
+        None => "error",
+    }
+}
+
+/// Table: one row per context, one column per namespace asked for (a single
+/// NAMESPACE column when each context used its own); failures listed below
+pub fn print_can_i(matrix: &CanIMatrix) {
+    let width = matrix
+        .checks
+        .iter()
+        .map(|c| c.context.len())
+        .max()
+        .unwrap_or(0)
+        .max("CONTEXT".len());
+
+    if matrix.namespaces.is_empty() && !matrix.all_namespaces {
+        let ns_width = matrix
+            .checks
+            .iter()
+            .filter_map(|c| c.namespace.as_ref().map(String::len))
+            .max()
+            .unwrap_or(0)
+            .max("NAMESPACE".len());
+        println!("{:<width$}  {:<ns_width$}  ALLOWED", "CONTEXT", "NAMESPACE");
+        for check in &matrix.checks {
+            println!(
+                "{:<width$}  {:<ns_width$}  {}",
+                check.context,
+                check.namespace.as_deref().unwrap_or("*"),
+                cell(check)
+            );
+        }
+    } else {
+        let columns: Vec<String> = if matrix.all_namespaces {
+            vec!["(all namespaces)".into()]
+        } else {
+            matrix.namespaces.clone()
+        };
+        let header: Vec<String> = columns
+            .iter()
+            .map(|c| format!("{:<w$}", c, w = c.len().max(5)))
+            .collect();
+        println!("{:<width$}  {}", "CONTEXT", header.join("  ").trim_end());
+        let mut contexts: Vec<&str> = matrix.checks.iter().map(|c| c.context.as_str()).collect();
+        contexts.dedup();
+        for context in contexts {
+            let cells: Vec<String> = matrix
+                .checks
+                .iter()
+                .filter(|c| c.context == context)
+                .zip(&columns)
+                .map(|(check, col)| format!("{:<w$}", cell(check), w = col.len().max(5)))
+                .collect();
+            println!("{:<width$}  {}", context, cells.join("  ").trim_end());
+        }
+    }
+
+    let failed: Vec<&AccessCheck> = matrix.checks.iter().filter(|c| c.error.is_some()).collect();
+    if !failed.is_empty() {
+        println!();
+        for check in failed {
+            let scope = check.namespace.as_deref().unwrap_or("*");
+            println!(
+                "  {}/{}: {}",
+                check.context,
+                scope,
+                check.error.as_deref().unwrap_or_default()
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resources_resolve_without_discovery() {
+        let resource = |group: &str, resource: &str, sub: Option<&str>| {
+            Some(Target::Resource {
+                group: group.into(),
+                resource: resource.into(),
+                subresource: sub.map(String::from),
+            })
+        };
+        assert_eq!(
+            parse_target("deploy"),
+            resource("apps", "deployments", None)
+        );
+        assert_eq!(parse_target("pods/log"), resource("", "pods", Some("log")));
+        assert_eq!(
+            parse_target("certificates.cert-manager.io"),
+            resource("cert-manager.io", "certificates", None)
+        );
+        assert_eq!(
+            parse_target("/healthz"),
+            Some(Target::NonResource {
+                path: "/healthz".into()
+            })
+        );
+        // A CRD short name needs kubectl's discovery
+        assert_eq!(parse_target("cert"), None);
+    }
+
+    #[test]
+    fn review_api_reads_status_allowed() {
+        use crate::test_http::{spawn_one_shot, HttpResponse};
+        let base = spawn_one_shot(HttpResponse::json(
+            201,
+            r#"{"kind":"SelfSubjectAccessReview","status":{"allowed":true}}"#,
+        ));
+        let dir = tempfile::tempdir().unwrap();
+        let kc = dir.path().join("config");
+        std::fs::write(
+            &kc,
+            format!(
+                "clusters:\n- name: c\n  cluster:\n    server: {base}\n\
+                 contexts:\n- name: dev\n  context:\n    cluster: c\n    user: u\n\
+                 users:\n- name: u\n  user:\n    token: abc\n"
+            ),
+        )
+        .unwrap();
+        let api = BearerApi::for_context("dev", kc.to_str(), 5)
+            .unwrap()
+            .unwrap();
+        let target = parse_target("secrets").unwrap();
+        assert!(review_api(&api, "get", &target, Some("default")).unwrap());
+    }
+}
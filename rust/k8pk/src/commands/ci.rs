@@ -0,0 +1,373 @@
+//! `k8pk ci setup`: turn CI secrets (a base64 kubeconfig, or a server and
+//! token) into an isolated kubeconfig in the workspace and print exports.
+
+use crate::error::{K8pkError, Result};
+use crate::kubeconfig::{self, KubeConfig};
+use base64::Engine;
+use std::path::PathBuf;
+
+/// Variables checked, in order, when no `--*-var` names one
+const KUBECONFIG_VARS: &[&str] = &[
+    "K8PK_KUBECONFIG_DATA",
+    "KUBECONFIG_DATA",
+    "KUBE_CONFIG_DATA",
+];
+const SERVER_VARS: &[&str] = &["K8PK_SERVER", "K8S_SERVER", "KUBE_SERVER"];
+const TOKEN_VARS: &[&str] = &["K8PK_TOKEN", "K8S_TOKEN", "KUBE_TOKEN"];
+const CA_VARS: &[&str] = &["K8PK_CA_DATA", "K8S_CA_DATA", "KUBE_CA_DATA"];
+
+/// Workspace directories set by common CI systems, most specific first
+const WORKSPACE_VARS: &[&str] = &[
+    "GITHUB_WORKSPACE",
+    "CI_PROJECT_DIR",
+    "BUILD_SOURCESDIRECTORY",
+    "WORKSPACE",
+];
+
+/// Context name in token mode without `--context`
+const DEFAULT_CONTEXT: &str = "ci";
+
+const VERIFY_TIMEOUT_SECS: u64 = 10;
+
+/// Secrets shorter than this are not masked (masking one or two characters
+/// would garble the whole log)
+const MIN_MASK_LEN: usize = 4;
+
+#[derive(Debug, Default)]
+pub struct CiSetupOptions {
+    /// Context to keep (kubeconfig mode) or to create (token mode)
+    pub context: Option<String>,
+    pub namespace: Option<String>,
+    /// Where to write the kubeconfig (default: `<workspace>/.k8pk/kubeconfig`)
+    pub out: Option<PathBuf>,
+    pub kubeconfig_var: Option<String>,
+    pub server_var: Option<String>,
+    pub token_var: Option<String>,
+    pub ca_var: Option<String>,
+    /// Check that the cluster accepts the credentials
+    pub verify: bool,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct CiSetupResult {
+    pub kubeconfig: PathBuf,
+    pub context: String,
+    pub namespace: Option<String>,
+    pub server: Option<String>,
+    /// `kubeconfig` (from the base64 data) or `token` (server and token)
+    pub source: &'static str,
+    /// Variable the credentials came from
+    pub variable: String,
+    pub verified: bool,
+}
+
+type Env<'a> = &'a dyn Fn(&str) -> Option<String>;
+
+/// Variable name and value
+type Found = Option<(String, String)>;
+
+/// First non-empty variable of `defaults`, or `explicit` alone when given
+fn lookup(env: Env, explicit: Option<&str>, defaults: &[&str]) -> Result<Found> {
+    if let Some(name) = explicit {
+        return match env(name).filter(|v| !v.trim().is_empty()) {
+            Some(value) => Ok(Some((name.to_string(), value))),
+            None => Err(K8pkError::InvalidArgument(format!("${} is not set", name))),
+        };
+    }
+    Ok(defaults.iter().find_map(|name| {
+        env(name)
+            .filter(|v| !v.trim().is_empty())
+            .map(|v| (name.to_string(), v))
+    }))
+}
+
+/// Kubeconfig from a variable holding base64 (the usual CI secret form) or
+/// plain YAML. Errors never include the value.
+fn decode_kubeconfig(var: &str, value: &str) -> Result<KubeConfig> {
+    let value = value.trim();
+    let yaml = if value.contains("clusters:") || value.contains("apiVersion:") {
+        value.to_string()
+    } else {
+        let compact: String = value.split_whitespace().collect();
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(compact)
+            .map_err(|_| {
+                K8pkError::InvalidKubeconfig(format!(
+                    "${} is neither base64 nor kubeconfig YAML",
+                    var
+                ))
+            })?;
+        String::from_utf8(bytes).map_err(|_| {
+            K8pkError::InvalidKubeconfig(format!("${} does not decode to text", var))
+        })?
+    };
+    serde_yaml_ng::from_str(&yaml)
+        .map_err(|_| K8pkError::InvalidKubeconfig(format!("${} is not a valid kubeconfig", var)))
+}
+
+/// Single-context kubeconfig from the environment, with the variable it came
+/// from and the source kind
+fn build_kubeconfig(
+    opts: &CiSetupOptions,
+    env: Env,
+) -> Result<(KubeConfig, String, &'static str, String)> {
+    if let Some((var, value)) = lookup(env, opts.kubeconfig_var.as_deref(), KUBECONFIG_VARS)? {
+        let cfg = decode_kubeconfig(&var, &value)?;
+        let context = match opts.context.clone().or_else(|| cfg.current_context.clone()) {
+            Some(c) => c,
+            None => match cfg.contexts.as_slice() {
+                [only] => only.name.clone(),
+                _ => {
+                    return Err(K8pkError::InvalidArgument(format!(
+                        "${} has no current-context; pass --context",
+                        var
+                    )))
+                }
+            },
+        };
+        let pruned = kubeconfig::prune_to_context(&cfg, &context)?;
+        return Ok((pruned, context, "kubeconfig", var));
+    }
+
+    let server = lookup(env, opts.server_var.as_deref(), SERVER_VARS)?;
+    let token = lookup(env, opts.token_var.as_deref(), TOKEN_VARS)?;
+    let ((_, server), (var, token)) = match (server, token) {
+        (Some(s), Some(t)) => (s, t),
+        _ => {
+            return Err(K8pkError::InvalidArgument(format!(
+                "no CI credentials found: set a base64 kubeconfig in ${}, \
+                 or a server and token in ${} and ${}",
+                KUBECONFIG_VARS.join(" / $"),
+                SERVER_VARS.join(" / $"),
+                TOKEN_VARS.join(" / $")
+            )))
+        }
+    };
+    let mut cluster = serde_json::json!({ "server": server.trim() });
+    if let Some((_, ca)) = lookup(env, opts.ca_var.as_deref(), CA_VARS)? {
+        let ca = ca.trim();
+        let data = if ca.starts_with("-----BEGIN") {
+            base64::engine::general_purpose::STANDARD.encode(ca)
+        } else {
+            ca.to_string()
+        };
+        cluster["certificate-authority-data"] = data.into();
+    }
+    let context = opts
+        .context
+        .clone()
+        .unwrap_or_else(|| DEFAULT_CONTEXT.to_string());
+    let cfg: KubeConfig = serde_json::from_value(serde_json::json!({
+        "apiVersion": "v1",
+        "kind": "Config",
+        "clusters": [{ "name": context, "cluster": cluster }],
+        "users": [{ "name": context, "user": { "token": token.trim() } }],
+        "contexts": [{ "name": context, "context": { "cluster": context, "user": context } }],
+        "current-context": context,
+    }))?;
+    Ok((cfg, context, "token", var))
+}
+
+fn default_out(env: Env) -> Result<PathBuf> {
+    let workspace = match WORKSPACE_VARS.iter().find_map(|v| env(v)) {
+        Some(dir) => PathBuf::from(dir),
+        None => std::env::current_dir()?,
+    };
+    Ok(workspace.join(".k8pk").join("kubeconfig"))
+}
+
+fn maskable(secrets: &[String]) -> impl Iterator<Item = &str> {
+    secrets
+        .iter()
+        .flat_map(|s| s.lines())
+        .map(str::trim)
+        .filter(|s| s.len() >= MIN_MASK_LEN)
+}
+
+/// `text` with every secret replaced by `***`
+fn scrub(text: &str, secrets: &[String]) -> String {
+    maskable(secrets).fold(text.to_string(), |t, s| t.replace(s, "***"))
+}
+
+/// Write the kubeconfig described by the environment (`env` looks up a
+/// variable) and, unless disabled, check that the cluster accepts it. On
+/// GitHub Actions the secrets are registered for masking first.
+pub fn ci_setup(opts: &CiSetupOptions, env: Env) -> Result<CiSetupResult> {
+    let (mut cfg, context, source, variable) = build_kubeconfig(opts, env)?;
+    let mut secrets = kubeconfig::secret_values(&cfg);
+    secrets.sort_by_key(|s| std::cmp::Reverse(s.len()));
+
+    if env("GITHUB_ACTIONS").as_deref() == Some("true") {
+        // Workflow commands are read from stderr too, which keeps stdout for exports
+        for secret in maskable(&secrets) {
+            eprintln!("::add-mask::{}", secret);
+        }
+    }
+
+    if let Some(ns) = opts.namespace.as_deref() {
+        kubeconfig::set_context_namespace(&mut cfg, &context, ns)?;
+    }
+    let out = match &opts.out {
+        Some(p) => p.clone(),
+        None => default_out(env)?,
+    };
+    if let Some(parent) = out.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    kubeconfig::write_restricted(&out, &serde_yaml_ng::to_string(&cfg)?)?;
+
+    // Offline: the file is still written, just not checked against the cluster
+    let verify = opts.verify && !crate::config::offline();
+    if verify {
+        super::login::test_k8s_auth(&out, &context, VERIFY_TIMEOUT_SECS).map_err(|e| {
+            K8pkError::CommandFailed(format!(
+                "cluster check failed for context '{}': {}",
+                context,
+                scrub(&e.to_string(), &secrets)
+            ))
+        })?;
+    }
+
+    Ok(CiSetupResult {
+        server: kubeconfig::get_server_for_context(&cfg, &context),
+        kubeconfig: out,
+        context,
+        namespace: opts.namespace.clone(),
+        source,
+        variable,
+        verified: verify,
+    })
+}
+
+/// Exports for the rest of the job, in `shell` syntax (a shell, or a `k8pk env --format` file syntax)
+pub fn ci_exports(result: &CiSetupResult, shell: &str) -> String {
+    let cache_dir = super::isolated_cache_dir(&result.kubeconfig, &result.context);
+    let mut vars = vec![
+        ("KUBECONFIG", result.kubeconfig.display().to_string()),
+        ("KUBECACHEDIR", cache_dir.display().to_string()),
+        ("K8PK_CONTEXT", result.context.clone()),
+    ];
+    if let Some(ns) = &result.namespace {
+        vars.push(("K8PK_NAMESPACE", ns.clone()));
+        vars.push(("OC_NAMESPACE", ns.clone()));
+    }
+    super::format_env_vars(shell, &vars)
+}
+
+/// One-line summary for the job log (stderr; stdout carries the exports)
+pub fn print_ci_summary(result: &CiSetupResult) {
+    eprintln!(
+        "k8pk: context {} from ${} ({}) -> {}{}",
+        result.context,
+        result.variable,
+        result.server.as_deref().unwrap_or("no server"),
+        super::display_path(&result.kubeconfig),
+        if result.verified {
+            ", cluster reachable"
+        } else {
+            ""
+        }
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn env_of(vars: &[(&str, &str)]) -> HashMap<String, String> {
+        vars.iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn token_mode_writes_single_context() {
+        let dir = tempfile::tempdir().unwrap();
+        let vars = env_of(&[
+            ("K8S_SERVER", "https://api.ci.example.com:6443\n"),
+            ("K8S_TOKEN", "sha256~secret-token"),
+            ("GITHUB_WORKSPACE", dir.path().to_str().unwrap()),
+        ]);
+        let env = |name: &str| vars.get(name).cloned();
+        let opts = CiSetupOptions {
+            namespace: Some("deploy".into()),
+            ..Default::default()
+        };
+        let result = ci_setup(&opts, &env).unwrap();
+        assert_eq!(result.kubeconfig, dir.path().join(".k8pk/kubeconfig"));
+        assert_eq!(result.context, "ci");
+        assert_eq!(result.variable, "K8S_TOKEN");
+        assert_eq!(
+            result.server.as_deref(),
+            Some("https://api.ci.example.com:6443")
+        );
+
+        let cfg: KubeConfig =
+            serde_yaml_ng::from_str(&std::fs::read_to_string(&result.kubeconfig).unwrap()).unwrap();
+        assert_eq!(cfg.current_context.as_deref(), Some("ci"));
+        assert_eq!(
+            cfg.contexts[0].rest["context"]["namespace"].as_str(),
+            Some("deploy")
+        );
+        let exports = ci_exports(&result, "github-actions");
+        assert!(exports.contains("K8PK_NAMESPACE=deploy\n"));
+        assert!(!exports.contains("secret-token"));
+        assert_eq!(
+            scrub(
+                "error: token sha256~secret-token rejected",
+                &["sha256~secret-token".to_string(), "abc".to_string()]
+            ),
+            "error: token *** rejected"
+        );
+    }
+
+    #[test]
+    fn kubeconfig_mode_decodes_and_prunes() {
+        let yaml = r#"
+clusters:
+- name: a
+  cluster: {server: https://a}
+- name: b
+  cluster: {server: https://b}
+contexts:
+- name: a
+  context: {cluster: a, user: u}
+- name: b
+  context: {cluster: b, user: u}
+users:
+- name: u
+  user: {token: tok-12345}
+current-context: a
+"#;
+        let encoded = base64::engine::general_purpose::STANDARD.encode(yaml);
+        let vars = env_of(&[("KUBE_CONFIG_DATA", &encoded)]);
+        let env = |name: &str| vars.get(name).cloned();
+
+        let (cfg, context, source, var) =
+            build_kubeconfig(&CiSetupOptions::default(), &env).unwrap();
+        assert_eq!(
+            (context.as_str(), source, var.as_str()),
+            ("a", "kubeconfig", "KUBE_CONFIG_DATA")
+        );
+        assert_eq!(cfg.clusters.len(), 1);
+
+        let opts = CiSetupOptions {
+            context: Some("b".into()),
+            ..Default::default()
+        };
+        let (cfg, ..) = build_kubeconfig(&opts, &env).unwrap();
+        assert_eq!(cfg.clusters[0].name, "b");
+
+        let vars = env_of(&[("KUBE_CONFIG_DATA", "not base64!")]);
+        let env = |name: &str| vars.get(name).cloned();
+        let err = build_kubeconfig(&CiSetupOptions::default(), &env).unwrap_err();
+        assert!(!err.to_string().contains("not base64!"));
+
+        let empty = |_: &str| None;
+        assert!(matches!(
+            build_kubeconfig(&CiSetupOptions::default(), &empty),
+            Err(K8pkError::InvalidArgument(_))
+        ));
+    }
+}
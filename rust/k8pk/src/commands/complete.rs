@@ -0,0 +1,271 @@
+//! Dynamic shell completion: `k8pk __complete <shell> <words...>` prints
+//! candidates for the last word, using clap's command model to tell whether
+//! it is a context or namespace argument. Static completion covers the rest.
+
+use crate::config::{self, K8pkConfig};
+use crate::error::Result;
+use crate::kubeconfig;
+use crate::state::CurrentState;
+use clap::{Arg, Command};
+use std::path::PathBuf;
+
+/// Kind of value the word under the cursor expects
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Slot {
+    Context,
+    /// Namespace of the context named earlier on the line, if any
+    Namespace(Option<String>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Candidate {
+    value: String,
+    description: Option<String>,
+}
+
+/// Print completion candidates for `words` (the command line after `k8pk`,
+/// ending with the word being completed, possibly empty). Prints nothing when
+/// the word is not a context or namespace; the shell glue then falls back to
+/// the static completions.
+pub fn print_dynamic_completions(
+    shell: &str,
+    words: &[String],
+    paths: &[PathBuf],
+    cfg: &K8pkConfig,
+) -> Result<()> {
+    let mut cmd = <crate::cli::Cli as clap::CommandFactory>::command();
+    cmd.build();
+    let candidates = match locate(&cmd, words) {
+        Some(Slot::Context) => context_candidates(paths, cfg),
+        Some(Slot::Namespace(context)) => namespace_candidates(context, paths),
+        None => Vec::new(),
+    };
+    for c in &candidates {
+        println!("{}", format_candidate(shell, c));
+    }
+    Ok(())
+}
+
+/// Walk the words through the command tree and classify the last one
+fn locate(root: &Command, words: &[String]) -> Option<Slot> {
+    let (current, done) = match words.split_last() {
+        Some((current, done)) => (current.as_str(), done),
+        None => ("", words),
+    };
+    let mut cmd = root;
+    let mut positional = 0usize;
+    let mut pending: Option<&Arg> = None;
+    let mut context: Option<String> = None;
+    let mut only_positionals = false;
+
+    let mut record = |arg: &Arg, value: &str| {
+        if arg.get_id() == "context" {
+            context = Some(value.to_string());
+        }
+    };
+
+    for word in done {
+        if let Some(arg) = pending.take() {
+            record(arg, word);
+            continue;
+        }
+        if !only_positionals {
+            if word == "--" {
+                only_positionals = true;
+                continue;
+            }
+            if let Some(long) = word.strip_prefix("--") {
+                let (name, inline) = match long.split_once('=') {
+                    Some((n, v)) => (n, Some(v)),
+                    None => (long, None),
+                };
+                if let Some(arg) = find_long(cmd, name) {
+                    match inline {
+                        Some(v) => record(arg, v),
+                        None if takes_value(arg) => pending = Some(arg),
+                        None => {}
+                    }
+                }
+                continue;
+            }
+            if word.len() > 1 && word.starts_with('-') {
+                let short = word.chars().nth(1);
+                if let Some(arg) = short.and_then(|c| find_short(cmd, c)) {
+                    if takes_value(arg) {
+                        if word.len() == 2 {
+                            pending = Some(arg);
+                        } else {
+                            record(arg, &word[2..]);
+                        }
+                    }
+                }
+                continue;
+            }
+            if positional == 0 {
+                if let Some(sub) = cmd.find_subcommand(word) {
+                    cmd = sub;
+                    continue;
+                }
+            }
+        }
+        if let Some(arg) = cmd.get_positionals().nth(positional) {
+            record(arg, word);
+        }
+        positional += 1;
+    }
+
+    let arg = match pending {
+        Some(arg) => arg,
+        None if current.starts_with('-') && !only_positionals => return None,
+        None => cmd.get_positionals().nth(positional)?,
+    };
+    match arg.get_id().as_str() {
+        "context" => Some(Slot::Context),
+        "namespace" => Some(Slot::Namespace(context)),
+        _ => None,
+    }
+}
+
+fn find_long<'a>(cmd: &'a Command, name: &str) -> Option<&'a Arg> {
+    cmd.get_arguments().find(|a| {
+        a.get_long() == Some(name) || a.get_all_aliases().is_some_and(|v| v.contains(&name))
+    })
+}
+
+fn find_short(cmd: &Command, c: char) -> Option<&Arg> {
+    cmd.get_arguments().find(|a| a.get_short() == Some(c))
+}
+
+fn takes_value(arg: &Arg) -> bool {
+    arg.get_action().takes_values()
+}
+
+/// Context names plus configured aliases (described with their target)
+fn context_candidates(paths: &[PathBuf], cfg: &K8pkConfig) -> Vec<Candidate> {
+    let mut out: Vec<Candidate> = kubeconfig::load_merged(paths)
+        .map(|m| m.context_names())
+        .unwrap_or_default()
+        .into_iter()
+        .map(|value| Candidate {
+            value,
+            description: None,
+        })
+        .collect();
+    if let Some(aliases) = &cfg.aliases {
+        let mut names: Vec<_> = aliases.iter().collect();
+        names.sort();
+        for (alias, target) in names {
+            out.push(Candidate {
+                value: alias.clone(),
+                description: Some(format!("alias for {}", target)),
+            });
+        }
+    }
+    out
+}
+
+/// Namespaces of the given context (else the active one), from the namespace
+/// cache only: TAB never waits on the API server, a login, or an exec plugin
+fn namespace_candidates(context: Option<String>, paths: &[PathBuf]) -> Vec<Candidate> {
+    let context = context
+        .map(|c| config::resolve_alias(&c))
+        .or_else(|| CurrentState::from_env().context)
+        .or_else(|| {
+            kubeconfig::load_merged(paths)
+                .ok()
+                .and_then(|m| m.current_context)
+        });
+    let Some(context) = context else {
+        return Vec::new();
+    };
+    kubeconfig::cached_namespaces(&context)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|value| Candidate {
+            value,
+            description: None,
+        })
+        .collect()
+}
+
+/// One output line: bare value for bash, `value:description` for zsh's
+/// `_describe`, `value<TAB>description` for fish
+fn format_candidate(shell: &str, c: &Candidate) -> String {
+    match (shell, &c.description) {
+        ("zsh", Some(d)) => format!("{}:{}", c.value.replace(':', "\\:"), d),
+        ("zsh", None) => c.value.replace(':', "\\:"),
+        ("fish", Some(d)) => format!("{}\t{}", c.value, d),
+        _ => c.value.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slot(words: &[&str]) -> Option<Slot> {
+        let mut cmd = <crate::cli::Cli as clap::CommandFactory>::command();
+        cmd.build();
+        let words: Vec<String> = words.iter().map(|w| w.to_string()).collect();
+        locate(&cmd, &words)
+    }
+
+    #[test]
+    fn locate_context_positionals_and_flags() {
+        assert_eq!(slot(&["ctx", ""]), Some(Slot::Context));
+        assert_eq!(slot(&["ctx", "pro"]), Some(Slot::Context));
+        assert_eq!(slot(&["rm", ""]), Some(Slot::Context));
+        assert_eq!(slot(&["exec", ""]), Some(Slot::Context));
+        assert_eq!(slot(&["gen", "--context", ""]), Some(Slot::Context));
+        assert_eq!(
+            slot(&["--kubeconfig", "/tmp/kc", "ctx", ""]),
+            Some(Slot::Context)
+        );
+    }
+
+    #[test]
+    fn locate_namespace_with_context_from_line() {
+        assert_eq!(slot(&["ns", ""]), Some(Slot::Namespace(None)));
+        assert_eq!(
+            slot(&["ctx", "prod", "-n", ""]),
+            Some(Slot::Namespace(Some("prod".into())))
+        );
+        assert_eq!(
+            slot(&["exec", "dev", "kube"]),
+            Some(Slot::Namespace(Some("dev".into())))
+        );
+        assert_eq!(
+            slot(&["namespaces", "--context=stage", "--json", "--context", "x"]),
+            Some(Slot::Context)
+        );
+    }
+
+    #[test]
+    fn locate_ignores_other_words() {
+        assert_eq!(slot(&[""]), None);
+        assert_eq!(slot(&["ctx", "--"]), None);
+        assert_eq!(slot(&["ctx", "-"]), None);
+        assert_eq!(slot(&["gen", "--out", ""]), None);
+        assert_eq!(slot(&["merge", "--files", ""]), None);
+        assert_eq!(slot(&[]), None);
+    }
+
+    #[test]
+    fn format_per_shell() {
+        let alias = Candidate {
+            value: "p".into(),
+            description: Some("alias for prod".into()),
+        };
+        let arn = Candidate {
+            value: "arn:aws:eks:us-east-1:1:cluster/a".into(),
+            description: None,
+        };
+        assert_eq!(format_candidate("bash", &alias), "p");
+        assert_eq!(format_candidate("zsh", &alias), "p:alias for prod");
+        assert_eq!(format_candidate("fish", &alias), "p\talias for prod");
+        assert_eq!(
+            format_candidate("zsh", &arn),
+            "arn\\:aws\\:eks\\:us-east-1\\:1\\:cluster/a"
+        );
+    }
+}
@@ -1,9 +1,12 @@
 //! Context-related command handlers
 
 use crate::error::{K8pkError, Result};
+use crate::i18n::{tr, tr_with};
 use crate::kubeconfig;
+use crate::prompt::Ask;
+use crate::provenance::{self, Provenance};
 use crate::shell;
-use crate::state::CurrentState;
+use crate::state::{CurrentState, NavEntry, NavStack};
 use std::collections::HashMap;
 use std::fs;
 use std::io::{self, IsTerminal, Write};
@@ -29,6 +32,9 @@ pub fn apply_context_output(
             shell::spawn_shell(ctx, ns, kc)
         }
     };
+    if output != Some("json") {
+        super::show_motd(context, kubeconfig);
+    }
     match output {
         Some("env") => {
             print_env_exports(
@@ -41,7 +47,7 @@ pub fn apply_context_output(
                 "namespace": namespace,
                 "kubeconfig": kubeconfig.to_string_lossy(),
             });
-            println!("{}", serde_json::to_string_pretty(&j)?);
+            println!("{}", crate::render::to_string(&j)?);
         }
         Some("spawn") => {
             do_spawn(context, namespace, kubeconfig)?;
@@ -62,6 +68,41 @@ pub fn apply_context_output(
     Ok(())
 }
 
+/// `k8pk ns --multi`: in tmux, open a window per namespace of `context`
+/// (named `ctx/ns`); elsewhere, or with `no_tmux`, print one `k8pk ctx`
+/// command per namespace to run in separate terminals
+pub fn open_namespaces(
+    context: &str,
+    namespaces: &[String],
+    paths: &[PathBuf],
+    no_tmux: bool,
+    insecure_skip_tls: bool,
+) -> Result<()> {
+    if no_tmux || !super::tmux::is_tmux() {
+        for ns in namespaces {
+            let mut args = vec!["k8pk", "ctx", context, "-n", ns, "--no-tmux"];
+            if insecure_skip_tls {
+                args.push("--insecure");
+            }
+            println!("# {}/{}", context, ns);
+            println!("{}", shell_words::join(args));
+        }
+        return Ok(());
+    }
+
+    let mut windows = Vec::new();
+    for ns in namespaces {
+        let kubeconfig = ensure_isolated_kubeconfig(context, Some(ns), paths)?;
+        if insecure_skip_tls {
+            apply_insecure_to_kubeconfig(&kubeconfig)?;
+        }
+        save_to_history(context, Some(ns))?;
+        windows.push((ns.clone(), kubeconfig));
+    }
+    super::tmux::open_namespace_windows(context, &windows)?;
+    Ok(())
+}
+
 /// Check session liveness and re-login if expired.
 /// Returns the (possibly refreshed) kubeconfig path.
 ///
@@ -90,13 +131,11 @@ pub fn ensure_session_alive(
         }
         Err(K8pkError::TlsCertificateError { .. }) => {
             // TLS error -- offer to retry with insecure if interactive
-            if std::io::stdin().is_terminal() && std::io::stderr().is_terminal() {
-                eprintln!("TLS certificate error for '{}'.", context);
-                let confirm =
-                    inquire::Confirm::new("Enable insecure-skip-tls-verify for this context?")
-                        .with_default(true)
-                        .prompt()
-                        .map_err(|_| K8pkError::Cancelled)?;
+            if crate::prompt::can_prompt() && std::io::stderr().is_terminal() {
+                eprintln!("{}", tr_with("msg-tls-error", &[("context", context)]));
+                let confirm = inquire::Confirm::new(&tr("confirm-insecure"))
+                    .with_default(true)
+                    .ask()?;
 
                 if confirm {
                     apply_insecure_to_kubeconfig(kubeconfig)?;
@@ -104,20 +143,23 @@ pub fn ensure_session_alive(
                     if login::test_k8s_auth(kubeconfig, context, login::SESSION_CHECK_TIMEOUT_SECS)
                         .is_ok()
                     {
-                        eprintln!("Connected (insecure mode).");
+                        eprintln!("{}", tr("msg-connected-insecure"));
                         mark_session_ok(context);
                         // Offer to persist so this context always skips TLS (no prompt next time)
-                        let persist = inquire::Confirm::new(&format!(
-                            "Always skip TLS for '{}'? (saves to insecure_contexts in config)",
-                            context
+                        let persist = inquire::Confirm::new(&tr_with(
+                            "confirm-always-insecure",
+                            &[("context", context)],
                         ))
                         .with_default(true)
-                        .prompt()
+                        .ask()
                         .unwrap_or(false);
                         if persist {
                             match crate::config::add_to_insecure_contexts(context) {
                                 Ok(()) => {
-                                    eprintln!("Saved '{}' to insecure_contexts in config.", context)
+                                    eprintln!(
+                                        "{}",
+                                        tr_with("msg-saved-insecure", &[("context", context)])
+                                    )
                                 }
                                 Err(e) => eprintln!("Warning: could not update config: {}", e),
                             }
@@ -133,13 +175,15 @@ pub fn ensure_session_alive(
                 });
             }
         }
+        // Re-login cannot help when the API server is down or out of reach
+        Err(e @ K8pkError::ClusterUnreachable(_)) => return Err(e),
         Err(_) => {
             // Other errors -- fall through to re-login
         }
     }
 
     // Session expired or still failing -- try to re-login if interactive
-    if std::io::stdin().is_terminal() {
+    if crate::prompt::can_prompt() {
         let written = login::try_relogin(context, namespace, paths)?;
         if let Some(ref p) = written {
             mark_session_ok(context);
@@ -179,12 +223,14 @@ fn should_skip_session_check(
     if std::env::var_os("K8PK_NO_SESSION_CHECK").is_some_and(|v| v != "0" && !v.is_empty()) {
         return true;
     }
+    if crate::config::offline() {
+        return true;
+    }
     recent_session_ok(context, session_check_ttl_secs(session_check_ttl))
 }
 
 fn session_ok_path() -> Option<PathBuf> {
-    let home = dirs_next::home_dir()?;
-    Some(home.join(".local/share/k8pk/session_ok.json"))
+    Some(crate::storage::data_dir().ok()?.join("session_ok.json"))
 }
 
 fn recent_session_ok(context: &str, ttl: u64) -> bool {
@@ -203,10 +249,7 @@ fn recent_session_ok(context: &str, ttl: u64) -> bool {
     let Some(&ts) = map.get(context) else {
         return false;
     };
-    let now = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .map(|d| d.as_secs())
-        .unwrap_or(0);
+    let now = crate::clock::now_secs();
     now.saturating_sub(ts) < ttl
 }
 
@@ -221,10 +264,7 @@ fn mark_session_ok(context: &str) {
         .ok()
         .and_then(|d| serde_json::from_str(&d).ok())
         .unwrap_or_default();
-    let now = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .map(|d| d.as_secs())
-        .unwrap_or(0);
+    let now = crate::clock::now_secs();
     map.insert(context.to_string(), now);
     // Keep map bounded
     if map.len() > 64 {
@@ -244,6 +284,11 @@ pub fn get_history() -> Result<(Vec<String>, Vec<String>)> {
     Ok((history.context_history, history.namespace_history))
 }
 
+/// When each context was last switched to (unix seconds)
+pub fn get_last_used() -> Result<HashMap<String, u64>> {
+    Ok(load_history()?.last_used)
+}
+
 /// Clear all history
 pub fn clear_history() -> Result<()> {
     let _lock = acquire_history_lock()?;
@@ -271,8 +316,22 @@ pub fn save_to_history(context: &str, namespace: Option<&str>) -> Result<()> {
             history.namespace_history.insert(0, ns.to_string());
             history.namespace_history.truncate(10);
         }
+        history
+            .last_namespaces
+            .insert(context.to_string(), ns.to_string());
+        let recent = history
+            .recent_namespaces
+            .entry(context.to_string())
+            .or_default();
+        recent.retain(|n| n != ns);
+        recent.insert(0, ns.to_string());
+        recent.truncate(RECENT_NAMESPACES);
     }
 
+    let now = crate::clock::now_secs();
+    history.last_used.insert(context.to_string(), now);
+    super::stats::record_switch(context, namespace);
+
     // Atomic write: write to temp file then rename
     let yaml = serde_yaml_ng::to_string(&history)?;
     let parent = history_path.parent().ok_or(K8pkError::NoHomeDir)?;
@@ -283,6 +342,67 @@ pub fn save_to_history(context: &str, namespace: Option<&str>) -> Result<()> {
     Ok(())
 }
 
+/// Last namespace used in a context, restored by `k8pk ctx <name>` without `-n`.
+pub fn get_last_namespace(context: &str) -> Result<Option<String>> {
+    let history = load_history()?;
+    Ok(history.last_namespaces.get(context).cloned())
+}
+
+/// Namespaces recently used in a context, most recent first. Falls back to
+/// the last namespace for history written before these were tracked.
+pub fn get_recent_namespaces(context: &str) -> Result<Vec<String>> {
+    let mut history = load_history()?;
+    Ok(match history.recent_namespaces.remove(context) {
+        Some(recent) => recent,
+        None => history
+            .last_namespaces
+            .remove(context)
+            .into_iter()
+            .collect(),
+    })
+}
+
+/// Drop the remembered namespace for a context. Returns the namespace that was forgotten.
+pub fn forget_last_namespace(context: &str) -> Result<Option<String>> {
+    let _lock = acquire_history_lock()?;
+    let history_path = history_file_path()?;
+    let mut history = load_history()?;
+    let removed = history.last_namespaces.remove(context);
+    if removed.is_none() {
+        return Ok(None);
+    }
+    let yaml = serde_yaml_ng::to_string(&history)?;
+    let parent = history_path.parent().ok_or(K8pkError::NoHomeDir)?;
+    let mut temp = tempfile::NamedTempFile::new_in(parent)?;
+    temp.write_all(yaml.as_bytes())?;
+    temp.persist(&history_path)
+        .map_err(|e| K8pkError::Io(e.error))?;
+    Ok(removed)
+}
+
+/// Steps back that a `k8pk ctx` argument asks for: 1 for `-`, N for `-N`
+pub fn back_steps(arg: &str) -> Option<usize> {
+    match arg.strip_prefix('-')? {
+        "" => Some(1),
+        n => n.parse().ok().filter(|&n| n > 0),
+    }
+}
+
+/// `k8pk back` / `forward` / `ctx -N`: the entry `steps` away (negative is
+/// back) in this shell's switch history. Its position is set in the process
+/// environment, so the switch that follows exports it instead of pushing a
+/// new entry.
+pub fn navigate(steps: isize) -> Result<NavEntry> {
+    let mut stack = NavStack::from_env();
+    let pos = stack.step(steps).ok_or(K8pkError::NoHistoryEntry {
+        steps: steps.unsigned_abs(),
+        direction: if steps < 0 { "back" } else { "forward" },
+    })?;
+    stack.pos = pos;
+    stack.set_env();
+    Ok(stack.entries[pos].clone())
+}
+
 /// Get previous context from history
 pub fn get_previous_context() -> Result<Option<String>> {
     let history = load_history()?;
@@ -319,49 +439,6 @@ pub fn save_context_type(context: &str, type_str: &str) -> Result<()> {
     Ok(())
 }
 
-/// Match contexts by pattern with layered fallback:
-///
-/// 1. Exact match
-/// 2. Glob match (if pattern contains *, ?, [)
-/// 3. Substring match (case-insensitive)
-///
-/// This allows `k8pk ctx dev` to match `gke_myproject_us-east1_dev-cluster`.
-pub fn match_pattern(pattern: &str, contexts: &[String]) -> Vec<String> {
-    let is_glob = pattern.contains('*') || pattern.contains('?') || pattern.contains('[');
-
-    // 1. Exact match (always tried first)
-    if !is_glob && contexts.contains(&pattern.to_string()) {
-        return vec![pattern.to_string()];
-    }
-
-    // 2. Glob match (only if pattern has glob metacharacters)
-    if is_glob {
-        let glob = match globset::Glob::new(pattern) {
-            Ok(g) => g.compile_matcher(),
-            Err(_) => return vec![],
-        };
-        let matches: Vec<String> = contexts
-            .iter()
-            .filter(|ctx| glob.is_match(ctx.as_str()))
-            .cloned()
-            .collect();
-        if !matches.is_empty() {
-            return matches;
-        }
-        return vec![];
-    }
-
-    // 3. Substring match (case-insensitive) -- only for non-glob patterns
-    let lower_pattern = pattern.to_lowercase();
-    let matches: Vec<String> = contexts
-        .iter()
-        .filter(|ctx| ctx.to_lowercase().contains(&lower_pattern))
-        .cloned()
-        .collect();
-
-    matches
-}
-
 /// Ensure isolated kubeconfig exists for a context.
 /// If `preloaded` is Some, uses it instead of re-loading from disk.
 pub fn ensure_isolated_kubeconfig(
@@ -375,15 +452,23 @@ pub fn ensure_isolated_kubeconfig(
 
 /// Like ensure_isolated_kubeconfig but accepts an already-loaded KubeConfig,
 /// avoiding redundant disk I/O when the caller already has the merged config.
-/// Pass `config` on hot paths to avoid a second config disk read for insecure_contexts.
+/// Pass `config` on hot paths to avoid a second config disk read (insecure_contexts,
+/// encryption, session_storage).
 pub fn ensure_isolated_kubeconfig_from(
     merged: &kubeconfig::KubeConfig,
     context: &str,
     namespace: Option<&str>,
     config: Option<&crate::config::K8pkConfig>,
 ) -> Result<PathBuf> {
-    let home = dirs_next::home_dir().ok_or(K8pkError::NoHomeDir)?;
-    let base = home.join(".local/share/k8pk");
+    let loaded;
+    let config = match config {
+        Some(c) => c,
+        None => {
+            loaded = crate::config::load().unwrap_or_default();
+            &loaded
+        }
+    };
+    let base = crate::storage::data_dir()?;
     fs::create_dir_all(&base)?;
 
     // ponytail: prune at most once per day
@@ -399,29 +484,121 @@ pub fn ensure_isolated_kubeconfig_from(
         format!("{}_{}.yaml", ctx_sanitized, ns_sanitized)
     };
 
-    let out = base.join(&filename);
-
     let mut pruned = kubeconfig::prune_to_context(merged, context)?;
 
     if let Some(ns) = namespace {
         kubeconfig::set_context_namespace(&mut pruned, context, ns)?;
     }
 
-    let insecure = match config {
-        Some(c) => crate::config::is_context_insecure_with(c, context),
-        None => crate::config::is_context_insecure(context),
-    };
-    if insecure {
+    if let Some(proxy) = crate::config::context_settings_with(config, context).proxy_url()? {
+        kubeconfig::set_cluster_proxy(&mut pruned, proxy);
+    }
+
+    if let Some(ca) = crate::config::ca_override_with(config, context) {
+        if !ca.is_file() {
+            return Err(K8pkError::InvalidArgument(format!(
+                "CA bundle {} (ca_overrides for '{}') does not exist",
+                ca.display(),
+                context
+            )));
+        }
+        kubeconfig::set_cluster_ca(&mut pruned, &ca);
+    }
+
+    if crate::config::is_context_insecure_with(config, context) {
+        // Say so when it is k8pk, not the kubeconfig, turning verification off
+        if !kubeconfig::get_cluster_insecure_for_context(&pruned, context)
+            && !crate::render::quiet()
+            && std::io::stderr().is_terminal()
+        {
+            eprintln!(
+                "warning: TLS verification disabled for '{}' (matches insecure_contexts in the k8pk config)",
+                context
+            );
+        }
         kubeconfig::set_cluster_insecure(&mut pruned);
     }
 
-    let yaml = serde_yaml_ng::to_string(&pruned)?;
+    if config.embed_certs {
+        kubeconfig::embed_certs(&mut pruned)?;
+    }
+
+    let mut provenance = merged
+        .sources
+        .get(context)
+        .and_then(|file| Provenance::for_source(context, file).ok());
+    if let (Some(p), Some(ttl)) = (provenance.as_mut(), config.session_ttl) {
+        p.expires_at = Some(p.generated_at + ttl);
+    }
+    if let Some(p) = &provenance {
+        provenance::write(&mut pruned, p)?;
+    }
+
+    let mut yaml = isolated_yaml(&pruned, context)?;
+    // Shells switching to the same context concurrently write the same file
+    let _lock = kubeconfig::lock_kubeconfig(&base.join(&filename))?;
+    if crate::encryption::enabled(Some(config)) {
+        return crate::encryption::store_isolated(config, &base, &filename, &yaml);
+    }
+
+    // Decrypted SOPS content only ever goes to the runtime directory
+    let from_sops = merged
+        .sources
+        .get(context)
+        .is_some_and(|file| kubeconfig::is_sops_file(file));
+    let dir = if from_sops {
+        crate::encryption::runtime_dir()?
+    } else {
+        crate::storage::isolated_dir(config)?
+    };
+    let out = dir.join(&filename);
+    if dir != base {
+        // Moved to runtime storage: drop the persistent copy
+        let legacy = base.join(&filename);
+        if legacy.exists() {
+            fs::remove_file(&legacy)?;
+        }
+    }
     // Skip rewrite when unchanged
     if out.exists() {
         if let Ok(existing) = fs::read_to_string(&out) {
+            let existing_cfg: Option<kubeconfig::KubeConfig> =
+                serde_yaml_ng::from_str(&existing).ok();
+            let previous = existing_cfg.as_ref().and_then(provenance::read);
+            if provenance::refresh_disabled() {
+                if let Some(old) = previous.as_ref().filter(|p| p.source_changed()) {
+                    eprintln!(
+                        "warning: {} is stale: {} changed since it was generated (kept because of --no-refresh)",
+                        super::display_path(&out),
+                        super::display_path(&old.file)
+                    );
+                }
+                return Ok(out);
+            }
+            if let (Some(new), Some(old)) = (&provenance, &previous) {
+                let kept = new.carried_over(old);
+                if kept != *new {
+                    provenance::write(&mut pruned, &kept)?;
+                    yaml = isolated_yaml(&pruned, context)?;
+                }
+            }
             if existing == yaml {
                 return Ok(out);
             }
+            // Re-login elsewhere (new token in the source): say why shells
+            // using this copy now see different credentials
+            if let (Some(old_cfg), Some(old)) = (&existing_cfg, &previous) {
+                if old.source_changed()
+                    && provenance::credentials_changed(old_cfg, &pruned)
+                    && !crate::render::quiet()
+                {
+                    eprintln!(
+                        "Refreshed credentials for '{}': {} changed since its isolated copy was generated",
+                        context,
+                        super::display_path(&old.file)
+                    );
+                }
+            }
         }
     }
     kubeconfig::write_restricted(&out, &yaml)?;
@@ -429,6 +606,19 @@ pub fn ensure_isolated_kubeconfig_from(
     Ok(out)
 }
 
+/// YAML of an isolated kubeconfig; with a session expiry recorded, its
+/// credentials only work through the session plugin until then
+fn isolated_yaml(cfg: &kubeconfig::KubeConfig, context: &str) -> Result<String> {
+    match provenance::read(cfg).and_then(|p| p.expires_at) {
+        Some(expires_at) => {
+            let mut gated = cfg.clone();
+            super::session_credential::gate_credentials(&mut gated, context, expires_at)?;
+            Ok(serde_yaml_ng::to_string(&gated)?)
+        }
+        None => Ok(serde_yaml_ng::to_string(cfg)?),
+    }
+}
+
 fn maybe_prune_stale(base: &Path) {
     let stamp = base.join(".prune_stamp");
     let day = std::time::Duration::from_secs(86400);
@@ -442,12 +632,17 @@ fn maybe_prune_stale(base: &Path) {
         return;
     }
     let _ = prune_stale_kubeconfigs(base, 7);
+    if let Some(runtime) = crate::storage::runtime_dir_path() {
+        let _ = prune_stale_kubeconfigs(&runtime, 7);
+    }
+    let _ = crate::encryption::sweep_live_copies(false);
     let _ = fs::write(&stamp, b"");
 }
 
 /// Force insecure-skip-tls-verify on an existing isolated kubeconfig file.
 /// Returns the same path for convenience.
 pub fn apply_insecure_to_kubeconfig(path: &Path) -> Result<PathBuf> {
+    let _lock = kubeconfig::lock_kubeconfig(path)?;
     let content = fs::read_to_string(path)?;
     let mut cfg: kubeconfig::KubeConfig = serde_yaml_ng::from_str(&content)?;
     kubeconfig::set_cluster_insecure(&mut cfg);
@@ -456,9 +651,53 @@ pub fn apply_insecure_to_kubeconfig(path: &Path) -> Result<PathBuf> {
     Ok(path.to_path_buf())
 }
 
-/// Remove stale isolated kubeconfig files older than `max_age_days`.
-/// Skips non-yaml files, the history file, and lock files.
+/// Remove stale isolated kubeconfig files (plain or encrypted) older than
+/// `max_age_days`. Skips other files, the history file, and lock files.
 /// Best-effort cleanup -- logs warnings on errors instead of failing.
+/// `k8pk ns NAME --create`: create `namespace` when the cluster doesn't have
+/// it, after asking unless `yes`. Refused on `protected_contexts`. Returns
+/// whether it was created.
+pub fn ensure_namespace(
+    context: &str,
+    namespace: &str,
+    kubeconfig: Option<&str>,
+    yes: bool,
+) -> Result<bool> {
+    if kubeconfig::namespace_exists(context, kubeconfig, namespace)? {
+        return Ok(false);
+    }
+    let config = crate::config::load().unwrap_or_default();
+    if crate::config::is_context_protected_with(&config, context) {
+        return Err(K8pkError::InvalidArgument(format!(
+            "namespace '{}' does not exist and '{}' is a protected context; \
+             create it with kubectl if you mean to",
+            namespace, context
+        )));
+    }
+    if !yes && !crate::prompt::can_prompt() {
+        return Err(K8pkError::InvalidArgument(format!(
+            "namespace '{}' does not exist in {}; pass --yes to create it without a prompt",
+            namespace, context
+        )));
+    }
+    if !yes {
+        let create = inquire::Confirm::new(&tr_with(
+            "confirm-create-namespace",
+            &[("namespace", namespace), ("context", context)],
+        ))
+        .with_default(false)
+        .ask()?;
+        if !create {
+            return Err(K8pkError::Cancelled);
+        }
+    }
+    kubeconfig::create_namespace(context, kubeconfig, namespace)?;
+    if !crate::render::quiet() {
+        eprintln!("Created namespace {} in {}", namespace, context);
+    }
+    Ok(true)
+}
+
 fn prune_stale_kubeconfigs(dir: &Path, max_age_days: u64) -> Result<()> {
     let max_age = std::time::Duration::from_secs(max_age_days * 86400);
     let now = std::time::SystemTime::now();
@@ -473,8 +712,9 @@ fn prune_stale_kubeconfigs(dir: &Path, max_age_days: u64) -> Result<()> {
     for entry in entries.flatten() {
         let path = entry.path();
 
-        // Only prune .yaml files (isolated kubeconfigs)
+        // Only prune .yaml / .yaml.age files (isolated kubeconfigs)
         let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let name = name.trim_end_matches(crate::encryption::SEALED_SUFFIX);
         if !name.ends_with(".yaml") || name == "history.yaml" {
             continue;
         }
@@ -496,6 +736,18 @@ fn prune_stale_kubeconfigs(dir: &Path, max_age_days: u64) -> Result<()> {
     Ok(())
 }
 
+/// `sh -c` (or `fish -c` for fish users) running a command from the config
+pub fn user_command(command: &str) -> StdCommand {
+    let shell = if detect_shell() == "fish" {
+        "fish"
+    } else {
+        "sh"
+    };
+    let mut cmd = StdCommand::new(shell);
+    cmd.arg("-c").arg(command);
+    cmd
+}
+
 /// Detect the current shell type from environment variables.
 /// Returns "fish" for fish shell, "bash" for everything else.
 pub fn detect_shell() -> &'static str {
@@ -523,13 +775,7 @@ pub fn isolated_cache_dir(kubeconfig: &Path, context: &str) -> PathBuf {
 
 /// Run a hook with extra environment (`K8PK_CONTEXT`, `K8PK_HOOK_PHASE`, etc.).
 pub fn run_hook_command_with_env(command: &str, extra: &[(&str, &str)]) -> Result<()> {
-    let (shell, flag) = if detect_shell() == "fish" {
-        ("fish", "-c")
-    } else {
-        ("sh", "-c")
-    };
-    let mut cmd = StdCommand::new(shell);
-    cmd.arg(flag).arg(command);
+    let mut cmd = user_command(command);
     for (k, v) in extra {
         cmd.env(k, v);
     }
@@ -584,8 +830,9 @@ pub fn run_eval_hooks(
     Ok(())
 }
 
-/// Run `stop_ctx` before `k8pk clean` clears the environment.
-pub fn run_stop_hook_before_clean(prior: &CurrentState) -> Result<()> {
+/// Run `stop_ctx` for a context being left for good: before `k8pk clean`
+/// clears the environment, or after a k8pk shell exits.
+pub fn run_stop_hook(prior: &CurrentState) -> Result<()> {
     let hooks = match crate::config::load().ok().and_then(|c| c.hooks.clone()) {
         None => return Ok(()),
         Some(h) => h,
@@ -606,6 +853,43 @@ pub fn run_stop_hook_before_clean(prior: &CurrentState) -> Result<()> {
     )
 }
 
+/// `k8pk env --format` values that produce a file for other tools rather
+/// than shell code
+pub const ENV_FILE_FORMATS: &[&str] = &["make", "dotenv", "github-actions"];
+
+/// The [`ENV_FILE_FORMATS`] entry `format` selects, if any
+pub fn env_file_format(format: crate::cli::OutputFormat) -> Option<&'static str> {
+    use crate::cli::OutputFormat;
+    match format {
+        OutputFormat::Make => Some("make"),
+        OutputFormat::Dotenv => Some("dotenv"),
+        OutputFormat::GithubActions => Some("github-actions"),
+        OutputFormat::Plain | OutputFormat::Json | OutputFormat::Yaml => None,
+    }
+}
+
+/// Variable assignments for `make` (`include k8pk.mk`), `dotenv`,
+/// `github-actions` (lines for `$GITHUB_ENV`), `fish`, or else POSIX shells
+pub(crate) fn format_env_vars(format: &str, vars: &[(&str, String)]) -> String {
+    vars.iter()
+        .map(|(name, value)| match format {
+            "github-actions" => format!("{}={}\n", name, value),
+            "fish" => format!("set -gx {} {};\n", name, shell_words::quote(value)),
+            "make" => format!(
+                "export {} := {}\n",
+                name,
+                value.replace('$', "$$").replace('#', "\\#")
+            ),
+            "dotenv" => format!(
+                "{}=\"{}\"\n",
+                name,
+                value.replace('\\', "\\\\").replace('"', "\\\"")
+            ),
+            _ => format!("export {}={};\n", name, shell_words::quote(value)),
+        })
+        .collect()
+}
+
 /// Print environment exports for a context
 ///
 /// For non-recursive switching: always reset to depth=1 (fresh k8pk session).
@@ -619,33 +903,38 @@ pub fn print_env_exports(
     verbose: bool,
     from_picker: bool,
 ) -> Result<()> {
+    // make/dotenv/github-actions output is written to a file, not eval'd
+    // into a shell: no hooks, session, or hints
+    let env_file = ENV_FILE_FORMATS.contains(&shell);
     let prior = CurrentState::from_env();
-    run_eval_hooks(&prior, context, namespace)?;
+    if !env_file {
+        run_eval_hooks(&prior, context, namespace)?;
+    }
 
     // Always reset to depth 1 for non-recursive context/namespace switching
     // This prevents depth from accumulating when switching contexts
     let new_depth = 1;
 
-    // Isolated kubeconfig is already in hand — use server URL for accurate labels.
-    let display_context = {
-        let server_url = fs::read_to_string(kubeconfig)
-            .ok()
-            .and_then(|c| serde_yaml_ng::from_str::<kubeconfig::KubeConfig>(&c).ok())
-            .and_then(|cfg| {
-                cfg.clusters
-                    .first()
-                    .and_then(|c| kubeconfig::extract_server_url_from_cluster(&c.rest))
-            });
-        kubeconfig::friendly_context_name(
-            context,
-            kubeconfig::detect_cluster_type(context, server_url.as_deref()),
-        )
-    };
+    let display_context = crate::config::display_context(context, namespace, kubeconfig);
 
     // Isolate cache per context to avoid stale API discovery (fixes oc/kubectl cache conflicts)
     let cache_dir = isolated_cache_dir(kubeconfig, context);
+    let stack = NavStack::after_switch(&prior, context, namespace);
 
     let exports = match shell {
+        _ if env_file => {
+            let mut vars = vec![
+                ("KUBECONFIG", kubeconfig.display().to_string()),
+                ("KUBECACHEDIR", cache_dir.display().to_string()),
+                ("K8PK_CONTEXT", context.to_string()),
+                ("K8PK_CONTEXT_DISPLAY", display_context),
+            ];
+            if let Some(ns) = namespace {
+                vars.push(("K8PK_NAMESPACE", ns.to_string()));
+                vars.push(("OC_NAMESPACE", ns.to_string()));
+            }
+            format_env_vars(shell, &vars)
+        }
         "fish" => {
             let mut s = format!(
                 "set -gx KUBECONFIG \"{}\";\n\
@@ -666,6 +955,7 @@ pub fn print_env_exports(
                     ns, ns
                 ));
             }
+            s.push_str(&format_env_vars(shell, &stack.exports()));
             s
         }
         _ => {
@@ -688,10 +978,16 @@ pub fn print_env_exports(
                     ns, ns
                 ));
             }
+            s.push_str(&format_env_vars(shell, &stack.exports()));
             s
         }
     };
 
+    if env_file {
+        print!("{}", exports);
+        return Ok(());
+    }
+
     // Register only when exports are actually consumed (pipe/tempfile eval).
     // TTY stdout means the user is just viewing exports — don't leave a ghost session.
     if !std::io::stdout().is_terminal() {
@@ -700,6 +996,7 @@ pub fn print_env_exports(
             namespace,
             &kubeconfig.display().to_string(),
             None,
+            new_depth,
         );
     }
 
@@ -709,7 +1006,7 @@ pub fn print_env_exports(
 
     // If stdout is a terminal, the user is probably running this directly
     // instead of through eval or the shell aliases. Show a hint.
-    if std::io::stdout().is_terminal() {
+    if std::io::stdout().is_terminal() && !crate::render::quiet() {
         if from_picker {
             eprintln!("# To apply in this shell run: eval \"$(k8pk)\"");
             eprintln!("# Or use aliases: kctx <context>  kns <namespace>");
@@ -728,7 +1025,7 @@ pub fn print_env_exports(
 /// Print commands to exit/cleanup k8pk session
 pub fn print_exit_commands(output: Option<&str>) -> Result<()> {
     let state = CurrentState::from_env();
-    run_stop_hook_before_clean(&state)?;
+    run_stop_hook(&state)?;
 
     match output {
         Some("json") => {
@@ -744,7 +1041,7 @@ pub fn print_exit_commands(output: Option<&str>) -> Result<()> {
                 ],
                 "in_recursive_shell": state.depth > 1
             });
-            println!("{}", serde_json::to_string_pretty(&j)?);
+            println!("{}", crate::render::to_string(&j)?);
         }
         _ => {
             let is_fish = detect_shell() == "fish";
@@ -790,58 +1087,36 @@ struct History {
     /// Context name -> cluster type for re-login: "ocp", "rancher", "gke", "k8s"
     #[serde(default)]
     context_types: HashMap<String, String>,
+    /// Context name -> last namespace used there
+    #[serde(default)]
+    last_namespaces: HashMap<String, String>,
+    /// Context name -> last switch time (unix seconds)
+    #[serde(default)]
+    last_used: HashMap<String, u64>,
+    /// Context name -> namespaces used there, most recent first
+    #[serde(default)]
+    recent_namespaces: HashMap<String, Vec<String>>,
 }
 
+/// Namespaces remembered per context for the namespace picker
+const RECENT_NAMESPACES: usize = 5;
+
 fn history_file_path() -> Result<PathBuf> {
-    let home = dirs_next::home_dir().ok_or(K8pkError::NoHomeDir)?;
-    let base = home.join(".local/share/k8pk");
+    let base = crate::storage::data_dir()?;
     fs::create_dir_all(&base)?;
     Ok(base.join("history.yaml"))
 }
 
 fn lock_file_path() -> Result<PathBuf> {
-    let home = dirs_next::home_dir().ok_or(K8pkError::NoHomeDir)?;
-    let base = home.join(".local/share/k8pk");
+    let base = crate::storage::data_dir()?;
     fs::create_dir_all(&base)?;
     Ok(base.join(".history.lock"))
 }
 
 /// Acquire an advisory file lock for history operations.
 /// Returns the lock file handle (lock is held while handle is alive).
-#[cfg(unix)]
 fn acquire_history_lock() -> Result<fs::File> {
-    use std::os::unix::io::AsRawFd;
-    let lock_path = lock_file_path()?;
-    let file = fs::OpenOptions::new()
-        .create(true)
-        .write(true)
-        .truncate(true)
-        .open(&lock_path)?;
-    // Try to acquire exclusive lock with a timeout: non-blocking first, then retry
-    for _ in 0..50 {
-        let ret = unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) };
-        if ret == 0 {
-            return Ok(file);
-        }
-        std::thread::sleep(std::time::Duration::from_millis(20));
-    }
-    // Final blocking attempt
-    let ret = unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) };
-    if ret != 0 {
-        return Err(K8pkError::Other("failed to acquire history lock".into()));
-    }
-    Ok(file)
-}
-
-#[cfg(not(unix))]
-fn acquire_history_lock() -> Result<fs::File> {
-    let lock_path = lock_file_path()?;
-    let file = fs::OpenOptions::new()
-        .create(true)
-        .write(true)
-        .truncate(true)
-        .open(&lock_path)?;
-    Ok(file) // No locking on non-Unix
+    kubeconfig::acquire_lock(&lock_file_path()?)
 }
 
 fn load_history() -> Result<History> {
@@ -858,37 +1133,23 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_match_pattern_exact() {
-        let contexts = vec!["dev".to_string(), "prod".to_string(), "staging".to_string()];
-        assert_eq!(match_pattern("dev", &contexts), vec!["dev"]);
+    fn env_file_formats() {
+        let vars = vec![
+            ("KUBECONFIG", "/k8pk/dev.yaml".to_string()),
+            ("K8PK_CONTEXT", "a$b#\"c".to_string()),
+        ];
         assert_eq!(
-            match_pattern("nonexistent", &contexts),
-            Vec::<String>::new()
+            format_env_vars("make", &vars),
+            "export KUBECONFIG := /k8pk/dev.yaml\nexport K8PK_CONTEXT := a$$b\\#\"c\n"
+        );
+        assert_eq!(
+            format_env_vars("dotenv", &vars),
+            "KUBECONFIG=\"/k8pk/dev.yaml\"\nK8PK_CONTEXT=\"a$b#\\\"c\"\n"
+        );
+        assert_eq!(
+            format_env_vars("github-actions", &vars),
+            "KUBECONFIG=/k8pk/dev.yaml\nK8PK_CONTEXT=a$b#\"c\n"
         );
-    }
-
-    #[test]
-    fn test_match_pattern_wildcard_prefix() {
-        let contexts = vec![
-            "dev-cluster".to_string(),
-            "dev-local".to_string(),
-            "prod-cluster".to_string(),
-        ];
-        let matched = match_pattern("dev-*", &contexts);
-        assert_eq!(matched.len(), 2);
-        assert!(matched.contains(&"dev-cluster".to_string()));
-        assert!(matched.contains(&"dev-local".to_string()));
-    }
-
-    #[test]
-    fn test_match_pattern_wildcard_middle() {
-        let contexts = vec![
-            "us-east-1-prod".to_string(),
-            "us-west-2-prod".to_string(),
-            "eu-west-1-dev".to_string(),
-        ];
-        let matched = match_pattern("us-*-prod", &contexts);
-        assert_eq!(matched.len(), 2);
     }
 
     #[test]
@@ -897,25 +1158,39 @@ mod tests {
         assert!(history.context_history.is_empty());
         assert!(history.namespace_history.is_empty());
         assert!(history.context_types.is_empty());
+        assert!(history.last_namespaces.is_empty());
     }
 
     #[test]
-    fn test_match_pattern_case_insensitive_substring() {
-        let contexts = vec![
-            "production-cluster".to_string(),
-            "staging-cluster".to_string(),
-            "dev".to_string(),
-        ];
-        // Case-insensitive substring fallback
-        let matched = match_pattern("Production", &contexts);
-        assert_eq!(matched, vec!["production-cluster"]);
+    fn test_history_last_namespaces_roundtrip() {
+        // Older history files have no last_namespaces key
+        let loaded: History = serde_yaml_ng::from_str("context_history: [a]\n").unwrap();
+        assert!(loaded.last_namespaces.is_empty());
+
+        let mut history = History::default();
+        history
+            .last_namespaces
+            .insert("dev".to_string(), "payments".to_string());
+        let yaml = serde_yaml_ng::to_string(&history).unwrap();
+        let loaded: History = serde_yaml_ng::from_str(&yaml).unwrap();
+        assert_eq!(
+            loaded.last_namespaces.get("dev").map(String::as_str),
+            Some("payments")
+        );
     }
 
     #[test]
-    fn test_match_pattern_no_match() {
-        let contexts = vec!["dev".to_string(), "staging".to_string()];
-        let matched = match_pattern("nonexistent", &contexts);
-        assert!(matched.is_empty());
+    fn test_history_recent_namespaces() {
+        let loaded: History = serde_yaml_ng::from_str("last_namespaces: {dev: web}\n").unwrap();
+        assert!(loaded.recent_namespaces.is_empty());
+
+        let mut history = History::default();
+        history
+            .recent_namespaces
+            .insert("dev".to_string(), vec!["api".into(), "web".into()]);
+        let yaml = serde_yaml_ng::to_string(&history).unwrap();
+        let loaded: History = serde_yaml_ng::from_str(&yaml).unwrap();
+        assert_eq!(loaded.recent_namespaces["dev"], vec!["api", "web"]);
     }
 
     #[test]
@@ -0,0 +1,359 @@
+//! `k8pk create-context`: assemble a new context in a kubeconfig from flags
+//! or a YAML spec, reusing an existing user's credentials.
+
+use crate::error::{K8pkError, Result};
+use crate::kubeconfig::{self, KubeConfig, NamedItem};
+use serde_yaml_ng::{Mapping, Value as Yaml};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// What to create. `--template FILE` reads it as YAML (kebab-case keys, as
+/// the flags); flags given on the command line override its fields.
+#[derive(Debug, Default, Clone, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ContextSpec {
+    pub name: Option<String>,
+    /// API server of a new cluster; without it `cluster_name` must exist
+    pub server: Option<String>,
+    pub cluster_name: Option<String>,
+    /// Existing user whose credentials the context uses
+    pub user_from: Option<String>,
+    pub namespace: Option<String>,
+}
+
+impl ContextSpec {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)?;
+        serde_yaml_ng::from_str(&content)
+            .map_err(|e| K8pkError::InvalidArgument(format!("template {}: {}", path.display(), e)))
+    }
+
+    /// Fields set in `other` win
+    pub fn overlay(self, other: ContextSpec) -> Self {
+        ContextSpec {
+            name: other.name.or(self.name),
+            server: other.server.or(self.server),
+            cluster_name: other.cluster_name.or(self.cluster_name),
+            user_from: other.user_from.or(self.user_from),
+            namespace: other.namespace.or(self.namespace),
+        }
+    }
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct CreateContextResult {
+    pub file: PathBuf,
+    pub context: String,
+    pub cluster: String,
+    pub user: String,
+    pub namespace: Option<String>,
+    /// Whether the cluster entry was written (new, copied in, or replaced)
+    pub cluster_added: bool,
+    /// Whether the user was copied in from another kubeconfig
+    pub user_added: bool,
+    pub replaced: bool,
+    pub dry_run: bool,
+}
+
+/// Add the context described by `spec` to `to_file`. Clusters and users not
+/// already in `to_file` are copied from `available` (normally the merged
+/// kubeconfig). The new entries are checked with [`super::lint_config`]
+/// before anything is written.
+pub fn create_context(
+    spec: &ContextSpec,
+    to_file: &Path,
+    available: &KubeConfig,
+    force: bool,
+    dry_run: bool,
+) -> Result<CreateContextResult> {
+    let name = spec.name.as_deref().ok_or_else(|| {
+        K8pkError::InvalidArgument(
+            "a context name is required (NAME or name: in the template)".into(),
+        )
+    })?;
+    let user_name = spec.user_from.as_deref().ok_or_else(|| {
+        K8pkError::InvalidArgument("--user-from is required (or user-from: in the template)".into())
+    })?;
+
+    let _lock = if dry_run {
+        None
+    } else {
+        Some(kubeconfig::lock_kubeconfig(to_file)?)
+    };
+    let mut target: KubeConfig = if to_file.exists() {
+        serde_yaml_ng::from_str(&fs::read_to_string(to_file)?)?
+    } else {
+        KubeConfig::default()
+    };
+
+    let replaced = target.find_context(name).is_some();
+    if replaced && !force {
+        return Err(K8pkError::InvalidArgument(format!(
+            "context '{}' already exists in {} (use --force to replace it)",
+            name,
+            to_file.display()
+        )));
+    }
+
+    let (cluster, cluster_added) = match spec.server.as_deref() {
+        Some(server) => {
+            let cluster_name = spec
+                .cluster_name
+                .clone()
+                .unwrap_or_else(|| format!("{}-cluster", name));
+            match target.find_cluster(&cluster_name) {
+                Some(existing)
+                    if kubeconfig::extract_server_url_from_cluster(&existing.rest).as_deref()
+                        == Some(server) =>
+                {
+                    (existing.clone(), false)
+                }
+                Some(_) if !force => {
+                    return Err(K8pkError::InvalidArgument(format!(
+                        "cluster '{}' already exists in {} with a different server \
+                         (use --force to replace it, or choose another --cluster-name)",
+                        cluster_name,
+                        to_file.display()
+                    )))
+                }
+                _ => (new_cluster(&cluster_name, server), true),
+            }
+        }
+        None => {
+            let cluster_name = spec.cluster_name.as_deref().ok_or_else(|| {
+                K8pkError::InvalidArgument(
+                    "pass --server for a new cluster, or --cluster-name for an existing one".into(),
+                )
+            })?;
+            if let Some(existing) = target.find_cluster(cluster_name) {
+                (existing.clone(), false)
+            } else {
+                let copied = available
+                    .find_cluster(cluster_name)
+                    .ok_or_else(|| K8pkError::ClusterNotFound(cluster_name.to_string()))?;
+                (copied.clone(), true)
+            }
+        }
+    };
+
+    let (user, user_added) = if let Some(existing) = target.find_user(user_name) {
+        (existing.clone(), false)
+    } else {
+        let copied = available
+            .find_user(user_name)
+            .ok_or_else(|| K8pkError::UserNotFound(user_name.to_string()))?;
+        (copied.clone(), true)
+    };
+
+    let cluster_name = cluster.name.clone();
+    let mut entries = KubeConfig {
+        contexts: vec![new_context(name, &cluster_name, user_name)],
+        clusters: vec![cluster],
+        users: vec![user],
+        ..Default::default()
+    };
+    if let Some(ns) = spec.namespace.as_deref() {
+        kubeconfig::set_context_namespace(&mut entries, name, ns)?;
+    }
+    let issues = super::lint_config(to_file, &entries);
+    if !issues.is_empty() {
+        let messages: Vec<String> = issues.into_iter().map(|i| i.message).collect();
+        return Err(K8pkError::InvalidKubeconfig(format!(
+            "refusing to create context '{}': {}",
+            name,
+            messages.join("; ")
+        )));
+    }
+
+    let result = CreateContextResult {
+        file: to_file.to_path_buf(),
+        context: name.to_string(),
+        cluster: cluster_name,
+        user: user_name.to_string(),
+        namespace: spec.namespace.clone(),
+        cluster_added,
+        user_added,
+        replaced,
+        dry_run,
+    };
+    if dry_run {
+        return Ok(result);
+    }
+
+    for (list, entry) in [
+        (&mut target.clusters, entries.clusters.remove(0)),
+        (&mut target.users, entries.users.remove(0)),
+        (&mut target.contexts, entries.contexts.remove(0)),
+    ] {
+        match list.iter_mut().find(|e| e.name == entry.name) {
+            Some(existing) => *existing = entry,
+            None => list.push(entry),
+        }
+    }
+    target.ensure_defaults(None);
+    kubeconfig::write_restricted(to_file, &serde_yaml_ng::to_string(&target)?)?;
+    Ok(result)
+}
+
+fn named(name: &str, key: &str, fields: Mapping) -> NamedItem {
+    let mut rest = Mapping::new();
+    rest.insert(Yaml::from(key), Yaml::Mapping(fields));
+    NamedItem {
+        name: name.to_string(),
+        rest: Yaml::Mapping(rest),
+    }
+}
+
+fn new_cluster(name: &str, server: &str) -> NamedItem {
+    let mut fields = Mapping::new();
+    fields.insert(Yaml::from("server"), Yaml::from(server));
+    named(name, "cluster", fields)
+}
+
+fn new_context(name: &str, cluster: &str, user: &str) -> NamedItem {
+    let mut fields = Mapping::new();
+    fields.insert(Yaml::from("cluster"), Yaml::from(cluster));
+    fields.insert(Yaml::from("user"), Yaml::from(user));
+    named(name, "context", fields)
+}
+
+pub fn print_create_context_summary(result: &CreateContextResult) {
+    let verb = match (result.dry_run, result.replaced) {
+        (true, true) => "Would replace",
+        (true, false) => "Would create",
+        (false, true) => "Replaced",
+        (false, false) => "Created",
+    };
+    println!(
+        "{} context {} ({})",
+        verb,
+        result.context,
+        super::display_path(&result.file)
+    );
+    let added = |added: bool| if added { " (added)" } else { "" };
+    println!(
+        "  cluster:   {}{}",
+        result.cluster,
+        added(result.cluster_added)
+    );
+    println!("  user:      {}{}", result.user, added(result.user_added));
+    if let Some(ns) = &result.namespace {
+        println!("  namespace: {}", ns);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OTHER: &str = r#"
+clusters:
+- name: prod
+  cluster: {server: https://prod.example.com}
+contexts:
+- name: prod
+  context: {cluster: prod, user: admin}
+users:
+- name: admin
+  user: {token: secret}
+"#;
+
+    fn spec(name: &str) -> ContextSpec {
+        ContextSpec {
+            name: Some(name.into()),
+            user_from: Some("admin".into()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn creates_context_with_new_cluster_and_copied_user() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("config");
+        let available: KubeConfig = serde_yaml_ng::from_str(OTHER).unwrap();
+        let spec = ContextSpec {
+            server: Some("https://staging.example.com:6443".into()),
+            namespace: Some("apps".into()),
+            ..spec("staging")
+        };
+
+        let result = create_context(&spec, &file, &available, false, false).unwrap();
+        assert_eq!(result.cluster, "staging-cluster");
+        assert!(result.cluster_added && result.user_added);
+
+        let cfg: KubeConfig = serde_yaml_ng::from_str(&fs::read_to_string(&file).unwrap()).unwrap();
+        assert!(super::super::lint_config(&file, &cfg).is_empty());
+        assert_eq!(
+            kubeconfig::get_server_for_context(&cfg, "staging").as_deref(),
+            Some("https://staging.example.com:6443")
+        );
+        assert_eq!(
+            cfg.contexts[0].rest["context"]["namespace"].as_str(),
+            Some("apps")
+        );
+        assert_eq!(cfg.users[0].rest["user"]["token"].as_str(), Some("secret"));
+
+        // Existing contexts need --force
+        assert!(matches!(
+            create_context(&spec, &file, &available, false, false),
+            Err(K8pkError::InvalidArgument(_))
+        ));
+        assert!(
+            create_context(&spec, &file, &available, true, false)
+                .unwrap()
+                .replaced
+        );
+    }
+
+    #[test]
+    fn existing_cluster_and_validation() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("config");
+        let available: KubeConfig = serde_yaml_ng::from_str(OTHER).unwrap();
+
+        let reuse = ContextSpec {
+            cluster_name: Some("prod".into()),
+            ..spec("prod-apps")
+        };
+        let result = create_context(&reuse, &file, &available, false, true).unwrap();
+        assert!(result.dry_run && result.cluster_added);
+        assert!(!file.exists());
+
+        let missing = ContextSpec {
+            cluster_name: Some("nope".into()),
+            ..spec("x")
+        };
+        assert!(matches!(
+            create_context(&missing, &file, &available, false, false),
+            Err(K8pkError::ClusterNotFound(_))
+        ));
+
+        let bad_server = ContextSpec {
+            server: Some("staging.example.com".into()),
+            ..spec("x")
+        };
+        let err = create_context(&bad_server, &file, &available, false, false).unwrap_err();
+        assert!(err.to_string().contains("not an http(s) URL"), "{}", err);
+        assert!(!file.exists());
+    }
+
+    #[test]
+    fn template_is_overridden_by_flags() {
+        let dir = tempfile::tempdir().unwrap();
+        let template = dir.path().join("spec.yaml");
+        fs::write(
+            &template,
+            "name: staging\nserver: https://s\nuser-from: admin\nnamespace: web\n",
+        )
+        .unwrap();
+        let spec = ContextSpec::load(&template).unwrap().overlay(ContextSpec {
+            namespace: Some("api".into()),
+            ..Default::default()
+        });
+        assert_eq!(spec.name.as_deref(), Some("staging"));
+        assert_eq!(spec.namespace.as_deref(), Some("api"));
+
+        fs::write(&template, "name: x\nusr-from: admin\n").unwrap();
+        assert!(ContextSpec::load(&template).is_err());
+    }
+}
@@ -0,0 +1,403 @@
+//! `k8pk dashboard`: a read-only terminal view of the active k8pk sessions,
+//! whether each context's server answers, when its credentials expire, and
+//! the latest switches. A background thread collects a fresh [`Snapshot`]
+//! every `interval` seconds; server probes run in parallel, one per distinct
+//! server, and are skipped in offline mode.
+
+use super::sessions::{self, SessionEntry};
+use super::SwitchEvent;
+use crate::clock::now_secs;
+use crate::error::{K8pkError, Result};
+use crate::kubeconfig::{self, KubeConfig};
+use crate::theme::{Role, Theme};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Cell, Row, Table};
+use ratatui::Frame;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+
+/// Seconds to wait for each server
+const PROBE_TIMEOUT_SECS: u64 = 2;
+/// Switches shown in the history pane
+const HISTORY_LEN: usize = 15;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ContextHealth {
+    pub context: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub server: Option<String>,
+    /// None when not probed (offline mode, or no server)
+    pub reachable: Option<bool>,
+    /// Earliest expiry of the user's client certificate or JWT token (unix seconds)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expiring: Option<&'static str>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Snapshot {
+    /// Unix seconds
+    pub collected_at: u64,
+    pub sessions: Vec<SessionEntry>,
+    pub contexts: Vec<ContextHealth>,
+    /// Newest first
+    pub history: Vec<SwitchEvent>,
+}
+
+/// Earliest credential expiry per context, from the file defining it
+fn context_expiry(paths: &[PathBuf]) -> HashMap<String, (&'static str, u64)> {
+    let mut expiry = HashMap::new();
+    for path in paths.iter().filter(|p| p.is_file()) {
+        let Ok(cfg) = fs::read_to_string(path)
+            .map_err(K8pkError::from)
+            .and_then(|s| Ok(serde_yaml_ng::from_str::<KubeConfig>(&s)?))
+        else {
+            continue;
+        };
+        let base = path.parent().unwrap_or(Path::new("."));
+        for ctx in &cfg.contexts {
+            let Some(user) = kubeconfig::extract_context_refs(&ctx.rest)
+                .ok()
+                .and_then(|(_, user)| cfg.find_user(&user))
+            else {
+                continue;
+            };
+            if let Some(earliest) = super::report::credential_expiry(user, base)
+                .into_iter()
+                .min_by_key(|(_, at)| *at)
+            {
+                expiry.entry(ctx.name.clone()).or_insert(earliest);
+            }
+        }
+    }
+    expiry
+}
+
+/// Collect everything shown on screen. `probe` says whether a server
+/// answers; it is called once per distinct server, in parallel.
+pub fn collect(paths: &[PathBuf], probe: impl Fn(&str) -> bool + Sync) -> Result<Snapshot> {
+    let merged = kubeconfig::load_merged(paths)?;
+    let servers: HashMap<String, String> = merged
+        .context_names()
+        .into_iter()
+        .filter_map(|c| Some((c.clone(), kubeconfig::get_server_for_context(&merged, &c)?)))
+        .collect();
+    let reachable: HashMap<&str, bool> = if crate::config::offline() {
+        HashMap::new()
+    } else {
+        let unique: HashSet<&str> = servers.values().map(String::as_str).collect();
+        std::thread::scope(|scope| {
+            let probe = &probe;
+            let handles: Vec<_> = unique
+                .into_iter()
+                .map(|server| (server, scope.spawn(move || probe(server))))
+                .collect();
+            handles
+                .into_iter()
+                .map(|(server, h)| (server, h.join().unwrap_or(false)))
+                .collect()
+        })
+    };
+    let expiry = context_expiry(paths);
+    let contexts = merged
+        .context_names()
+        .into_iter()
+        .map(|context| {
+            let server = servers.get(&context).cloned();
+            let (kind, expires_at) = match expiry.get(&context) {
+                Some((kind, at)) => (Some(*kind), Some(*at)),
+                None => (None, None),
+            };
+            ContextHealth {
+                reachable: server.as_deref().and_then(|s| reachable.get(s).copied()),
+                server,
+                expires_at,
+                expiring: kind,
+                context,
+            }
+        })
+        .collect();
+
+    let history: Vec<SwitchEvent> = super::load_events()
+        .into_iter()
+        .rev()
+        .filter(|e| !e.exit)
+        .take(HISTORY_LEN)
+        .collect();
+
+    Ok(Snapshot {
+        collected_at: now_secs(),
+        sessions: sessions::list_active().unwrap_or_default(),
+        contexts,
+        history,
+    })
+}
+
+fn probe_server(server: &str) -> bool {
+    super::login::check_server_reachable(server, PROBE_TIMEOUT_SECS).is_ok()
+}
+
+/// `in 3h`, `in 2d`, or `expired 5m ago`
+pub fn countdown(expires_at: u64, now: u64) -> String {
+    let span = |secs: u64| {
+        if secs < 3600 {
+            format!("{}m", secs / 60)
+        } else if secs < 86400 {
+            format!("{}h {}m", secs / 3600, secs % 3600 / 60)
+        } else {
+            format!("{}d", secs / 86400)
+        }
+    };
+    if expires_at > now {
+        format!("in {}", span(expires_at - now))
+    } else {
+        format!("expired {} ago", span(now - expires_at))
+    }
+}
+
+/// Error color once expired, warning color within a day
+fn expiry_style(theme: Theme, expires_at: u64, now: u64) -> Style {
+    if expires_at <= now {
+        Style::default().fg(theme.tui_color(Role::Error))
+    } else if expires_at - now < 86400 {
+        Style::default().fg(theme.tui_color(Role::Warning))
+    } else {
+        Style::default()
+    }
+}
+
+fn draw(frame: &mut Frame, snapshot: Option<&Snapshot>, interval: u64) {
+    let [title, sessions_area, contexts_area, history_area] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Percentage(30),
+        Constraint::Percentage(45),
+        Constraint::Fill(1),
+    ])
+    .areas(frame.area());
+    let bold = Style::default().add_modifier(Modifier::BOLD);
+    let theme = crate::theme::theme();
+    let block = |name: &str| {
+        Block::default()
+            .borders(Borders::ALL)
+            .title(name.to_string())
+    };
+
+    let Some(snap) = snapshot else {
+        frame.render_widget(Line::from("k8pk dashboard: collecting..."), title);
+        return;
+    };
+    let now = now_secs();
+    frame.render_widget(
+        Line::from(format!(
+            "k8pk dashboard  refreshed {} ago, every {}s  (r: refresh, q: quit)",
+            sessions::format_age(snap.collected_at),
+            interval
+        )),
+        title,
+    );
+
+    let rows = snap.sessions.iter().map(|s| {
+        Row::new(vec![
+            s.pid.to_string(),
+            s.context.clone(),
+            s.namespace.clone(),
+            s.terminal.clone(),
+            sessions::format_age(s.started_at),
+        ])
+    });
+    frame.render_widget(
+        Table::new(
+            rows,
+            [
+                Constraint::Length(8),
+                Constraint::Fill(2),
+                Constraint::Fill(1),
+                Constraint::Fill(1),
+                Constraint::Length(6),
+            ],
+        )
+        .header(Row::new(["PID", "CONTEXT", "NAMESPACE", "TERMINAL", "AGE"]).style(bold))
+        .block(block(&format!(" Sessions ({}) ", snap.sessions.len()))),
+        sessions_area,
+    );
+
+    let rows = snap.contexts.iter().map(|c| {
+        let (status, color) = match c.reachable {
+            Some(true) => ("up", theme.tui_color(Role::Ok)),
+            Some(false) => ("down", theme.tui_color(Role::Error)),
+            None => ("-", theme.tui_color(Role::Muted)),
+        };
+        let expiry = match (c.expires_at, c.expiring) {
+            (Some(at), Some(kind)) => Cell::from(format!("{} {}", kind, countdown(at, now)))
+                .style(expiry_style(theme, at, now)),
+            _ => Cell::from("-"),
+        };
+        Row::new(vec![
+            Cell::from(c.context.clone()),
+            Cell::from(status).style(Style::default().fg(color)),
+            expiry,
+            Cell::from(c.server.clone().unwrap_or_default()),
+        ])
+    });
+    let down = snap
+        .contexts
+        .iter()
+        .filter(|c| c.reachable == Some(false))
+        .count();
+    frame.render_widget(
+        Table::new(
+            rows,
+            [
+                Constraint::Fill(2),
+                Constraint::Length(5),
+                Constraint::Length(32),
+                Constraint::Fill(2),
+            ],
+        )
+        .header(Row::new(["CONTEXT", "API", "CREDENTIALS", "SERVER"]).style(bold))
+        .block(block(&format!(
+            " Contexts ({}, {} down) ",
+            snap.contexts.len(),
+            down
+        ))),
+        contexts_area,
+    );
+
+    let rows = snap.history.iter().map(|e| {
+        Row::new(vec![
+            format!("{} ago", sessions::format_age(e.ts)),
+            e.context.clone(),
+            e.namespace.clone().unwrap_or_default(),
+        ])
+    });
+    frame.render_widget(
+        Table::new(
+            rows,
+            [
+                Constraint::Length(10),
+                Constraint::Fill(2),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(Row::new(["WHEN", "CONTEXT", "NAMESPACE"]).style(bold))
+        .block(block(" Recent switches ")),
+        history_area,
+    );
+}
+
+/// Run the dashboard until `q`, Esc, or Ctrl-C
+pub fn dashboard(paths: &[PathBuf], interval: u64) -> Result<()> {
+    if !std::io::stdout().is_terminal() {
+        return Err(K8pkError::InvalidArgument(
+            "k8pk dashboard needs a terminal; use --json for a one-off snapshot".into(),
+        ));
+    }
+    let interval = interval.max(1);
+    let (tx, rx) = mpsc::channel::<Result<Snapshot>>();
+    let (refresh_tx, refresh_rx) = mpsc::channel::<()>();
+    let paths = paths.to_vec();
+    std::thread::spawn(move || loop {
+        if tx.send(collect(&paths, probe_server)).is_err() {
+            return;
+        }
+        // Wait for the interval, or a refresh request
+        if let Err(mpsc::RecvTimeoutError::Disconnected) =
+            refresh_rx.recv_timeout(Duration::from_secs(interval))
+        {
+            return;
+        }
+    });
+
+    let mut terminal = ratatui::init();
+    let result = (|| -> Result<()> {
+        let mut snapshot: Option<Snapshot> = None;
+        loop {
+            match rx.try_recv() {
+                Ok(Ok(s)) => snapshot = Some(s),
+                Ok(Err(e)) => return Err(e),
+                Err(_) => {}
+            }
+            terminal.draw(|f| draw(f, snapshot.as_ref(), interval))?;
+            if event::poll(Duration::from_millis(250))? {
+                if let Event::Key(key) = event::read()? {
+                    if key.kind != KeyEventKind::Press {
+                        continue;
+                    }
+                    match key.code {
+                        KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            return Ok(())
+                        }
+                        KeyCode::Char('r') => {
+                            let _ = refresh_tx.send(());
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+    })();
+    ratatui::restore();
+    result
+}
+
+/// One snapshot, for `k8pk dashboard --json`
+pub fn dashboard_snapshot(paths: &[PathBuf]) -> Result<Snapshot> {
+    collect(paths, probe_server)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::backend::TestBackend;
+
+    #[test]
+    fn test_countdown() {
+        assert_eq!(countdown(1000 + 90 * 60, 1000), "in 1h 30m");
+        assert_eq!(countdown(1000 + 3 * 86400, 1000), "in 3d");
+        assert_eq!(countdown(1000, 1000 + 300), "expired 5m ago");
+    }
+
+    #[test]
+    fn test_draw() {
+        let snapshot = Snapshot {
+            collected_at: now_secs(),
+            sessions: Vec::new(),
+            contexts: vec![ContextHealth {
+                context: "prod".into(),
+                server: Some("https://prod:6443".into()),
+                reachable: Some(false),
+                expires_at: Some(now_secs() + 7200),
+                expiring: Some("token"),
+            }],
+            history: vec![SwitchEvent {
+                ts: now_secs(),
+                context: "dev".into(),
+                namespace: Some("web".into()),
+                exit: false,
+            }],
+        };
+        let mut terminal = ratatui::Terminal::new(TestBackend::new(100, 30)).unwrap();
+        terminal.draw(|f| draw(f, Some(&snapshot), 10)).unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|c| c.symbol())
+            .collect();
+        assert!(screen.contains("Contexts (1, 1 down)"));
+        assert!(screen.contains("token in 2h 0m") || screen.contains("token in 1h 59m"));
+        assert!(screen.contains("down"));
+        assert!(screen.contains("Recent switches"));
+    }
+}
@@ -0,0 +1,200 @@
+//! `k8pk direnv`: an `.envrc` stanza exporting a context's isolated
+//! kubeconfig, so direnv selects the cluster when you enter the directory.
+
+use crate::error::{K8pkError, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+const BEGIN: &str = "# >>> k8pk >>>";
+const END: &str = "# <<< k8pk <<<";
+
+/// How often `--watch` checks the kubeconfig files
+const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Debug, serde::Serialize)]
+pub struct DirenvResult {
+    /// `.envrc` written (None with `--print`)
+    pub envrc: Option<PathBuf>,
+    pub context: String,
+    pub namespace: Option<String>,
+    pub kubeconfig: PathBuf,
+    /// An earlier k8pk stanza in the file was replaced
+    pub replaced: bool,
+    pub stanza: String,
+}
+
+/// The k8pk block for `.envrc`
+pub fn direnv_stanza(context: &str, namespace: Option<&str>, kubeconfig: &Path) -> String {
+    let cache_dir = super::isolated_cache_dir(kubeconfig, context);
+    let mut vars = vec![
+        ("KUBECONFIG", kubeconfig.display().to_string()),
+        ("KUBECACHEDIR", cache_dir.display().to_string()),
+        ("K8PK_CONTEXT", context.to_string()),
+    ];
+    if let Some(ns) = namespace {
+        vars.push(("K8PK_NAMESPACE", ns.to_string()));
+        vars.push(("OC_NAMESPACE", ns.to_string()));
+    }
+    let mut stanza = format!(
+        "{}\n# Generated by k8pk direnv; regenerate instead of editing\n",
+        BEGIN
+    );
+    for (name, value) in vars {
+        stanza.push_str(&format!("export {}={}\n", name, shell_words::quote(&value)));
+    }
+    stanza.push_str(END);
+    stanza.push('\n');
+    stanza
+}
+
+/// `existing` with its k8pk block replaced by `stanza`, or `stanza` appended.
+/// Returns whether a block was replaced.
+fn upsert_stanza(existing: &str, stanza: &str) -> (String, bool) {
+    if let Some(start) = existing.find(BEGIN) {
+        if let Some(end) = existing[start..].find(END) {
+            let mut after = start + end + END.len();
+            if existing[after..].starts_with('\n') {
+                after += 1;
+            }
+            let updated = format!("{}{}{}", &existing[..start], stanza, &existing[after..]);
+            return (updated, true);
+        }
+    }
+    let mut updated = existing.to_string();
+    if !updated.is_empty() && !updated.ends_with('\n') {
+        updated.push('\n');
+    }
+    updated.push_str(stanza);
+    (updated, false)
+}
+
+/// Write `stanza` into `dir/.envrc`, keeping the rest of the file
+fn write_envrc(dir: &Path, stanza: &str) -> Result<(PathBuf, bool)> {
+    let path = dir.join(".envrc");
+    let existing = match fs::read_to_string(&path) {
+        Ok(s) => s,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e.into()),
+    };
+    let (updated, replaced) = upsert_stanza(&existing, stanza);
+    if updated != existing {
+        fs::write(&path, updated)?;
+    }
+    Ok((path, replaced))
+}
+
+/// Materialize the isolated kubeconfig for `context` and write (or, with
+/// `print`, only build) the `.envrc` stanza pointing at it
+pub fn direnv(
+    context: &str,
+    namespace: Option<&str>,
+    dir: &Path,
+    print: bool,
+    paths: &[PathBuf],
+) -> Result<DirenvResult> {
+    if !print && !dir.is_dir() {
+        return Err(K8pkError::InvalidArgument(format!(
+            "not a directory: {}",
+            dir.display()
+        )));
+    }
+    let kubeconfig = super::ensure_isolated_kubeconfig(context, namespace, paths)?;
+    let stanza = direnv_stanza(context, namespace, &kubeconfig);
+    let (envrc, replaced) = if print {
+        (None, false)
+    } else {
+        let (path, replaced) = write_envrc(dir, &stanza)?;
+        (Some(path), replaced)
+    };
+    Ok(DirenvResult {
+        envrc,
+        context: context.to_string(),
+        namespace: namespace.map(String::from),
+        kubeconfig,
+        replaced,
+        stanza,
+    })
+}
+
+fn modified_times(paths: &[PathBuf]) -> Vec<Option<SystemTime>> {
+    paths
+        .iter()
+        .map(|p| fs::metadata(p).and_then(|m| m.modified()).ok())
+        .collect()
+}
+
+/// `k8pk direnv --watch`: regenerate the isolated kubeconfig and `.envrc`
+/// whenever one of the kubeconfig files changes. Runs until interrupted.
+pub fn watch_direnv(
+    context: &str,
+    namespace: Option<&str>,
+    dir: &Path,
+    paths: &[PathBuf],
+) -> Result<()> {
+    let quiet = crate::render::quiet();
+    let mut seen = modified_times(paths);
+    if !quiet {
+        eprintln!(
+            "Watching {} kubeconfig file(s) for changes to {} (Ctrl-C to stop)",
+            paths.len(),
+            context
+        );
+    }
+    loop {
+        std::thread::sleep(WATCH_INTERVAL);
+        let now = modified_times(paths);
+        if now == seen {
+            continue;
+        }
+        seen = now;
+        match direnv(context, namespace, dir, false, paths) {
+            Ok(_) if !quiet => eprintln!(
+                "Regenerated {} for {}",
+                dir.join(".envrc").display(),
+                context
+            ),
+            Ok(_) => {}
+            Err(e) => eprintln!("warning: could not regenerate {}: {}", context, e),
+        }
+    }
+}
+
+pub fn print_direnv_result(result: &DirenvResult) {
+    match &result.envrc {
+        None => print!("{}", result.stanza),
+        Some(path) => {
+            let verb = if result.replaced { "Updated" } else { "Wrote" };
+            println!(
+                "{} k8pk stanza in {} ({})",
+                verb,
+                super::display_path(path),
+                result.context
+            );
+            println!("Run 'direnv allow' to load it.");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stanza_is_replaced_in_place() {
+        let kc = Path::new("/data/k8pk/dev_web.yaml");
+        let first = direnv_stanza("dev", Some("web"), kc);
+        assert!(first.contains("export KUBECONFIG=/data/k8pk/dev_web.yaml\n"));
+        assert!(first.contains("export K8PK_NAMESPACE=web\n"));
+
+        let (envrc, replaced) = upsert_stanza("use nix\n", &first);
+        assert!(!replaced);
+        let envrc = format!("{}layout python\n", envrc);
+
+        let second = direnv_stanza("prod eu", None, Path::new("/data/k8pk/prod.yaml"));
+        assert!(second.contains("export K8PK_CONTEXT='prod eu'\n"));
+        let (envrc, replaced) = upsert_stanza(&envrc, &second);
+        assert!(replaced);
+        assert_eq!(envrc, format!("use nix\n{}layout python\n", second));
+    }
+}
@@ -0,0 +1,263 @@
+//! AKS discovery via the Azure CLI (`az aks list` / `az aks get-credentials`)
+
+use super::{
+    build_exec_kubeconfig, output_dir_for, require_cli, run_cli, run_cli_json, select_clusters,
+    write_discovered, ClusterEndpoint, DiscoverResult, DiscoveredCluster,
+};
+use crate::commands::login::{apply_exec_preset, ExecAuthConfig};
+use crate::error::{K8pkError, Result};
+use crate::kubeconfig::{self, KubeConfig};
+use std::collections::HashMap;
+use std::path::Path;
+
+const AZ_INSTALL_HINT: &str =
+    "Install the Azure CLI: https://learn.microsoft.com/cli/azure/install-azure-cli";
+
+const KUBELOGIN_HINT: &str = "Install it with:\n  az aks install-cli\n\nOr via Homebrew:\n  brew install Azure/kubelogin/kubelogin";
+
+/// Server ID of the AKS AAD server application (same for every AAD-enabled cluster)
+const AKS_AAD_SERVER_ID: &str = "6dae42f8-4368-4678-94ff-3960e28e3630";
+
+/// Where to fetch credentials for a discovered AKS cluster
+struct AksCluster {
+    subscription: Option<String>,
+    resource_group: String,
+    aad: bool,
+}
+
+/// Discover AKS clusters and write kubelogin-based kubeconfigs.
+///
+/// With `admin`, fetches the cluster admin credentials (client certificates)
+/// instead; Azure refuses this when local accounts are disabled.
+#[allow(clippy::too_many_arguments)]
+pub fn discover_aks(
+    subscriptions: &[String],
+    admin: bool,
+    pattern: Option<&str>,
+    all: bool,
+    output_dir: Option<&Path>,
+    dry_run: bool,
+    quiet: bool,
+) -> Result<DiscoverResult> {
+    require_cli("az", AZ_INSTALL_HINT)?;
+    if !admin {
+        require_cli("kubelogin", KUBELOGIN_HINT)?;
+    }
+
+    // No --subscription: search the CLI's current subscription only
+    let subscriptions: Vec<Option<&str>> = if subscriptions.is_empty() {
+        vec![None]
+    } else {
+        subscriptions.iter().map(|s| Some(s.as_str())).collect()
+    };
+
+    let mut found = Vec::new();
+    let mut details = HashMap::new();
+    for subscription in subscriptions {
+        if !quiet {
+            eprintln!(
+                "Listing AKS clusters ({})...",
+                subscription.unwrap_or("current subscription")
+            );
+        }
+        let mut args = vec!["aks", "list", "--output", "json"];
+        if let Some(sub) = subscription {
+            args.extend(["--subscription", sub]);
+        }
+        match run_cli_json("az", &args) {
+            Ok(json) => {
+                for (name, location, resource_group, aad) in parse_aks_list(&json) {
+                    let context_name = if admin {
+                        format!("{}-admin", name)
+                    } else {
+                        name.clone()
+                    };
+                    details.insert(
+                        context_name.clone(),
+                        AksCluster {
+                            subscription: subscription.map(String::from),
+                            resource_group: resource_group.clone(),
+                            aad,
+                        },
+                    );
+                    found.push(DiscoveredCluster {
+                        name,
+                        location,
+                        scope: resource_group,
+                        context_name,
+                        kubeconfig_path: None,
+                    });
+                }
+            }
+            Err(e) => eprintln!("warning: {}", e),
+        }
+    }
+
+    let selected = select_clusters(found, pattern, all)?;
+    let out_dir = output_dir_for("aks", output_dir)?;
+
+    let mut clusters = Vec::new();
+    for mut cluster in selected {
+        if !dry_run {
+            let info = details.get(&cluster.context_name).ok_or_else(|| {
+                K8pkError::CommandFailed(format!("no details for {}", cluster.context_name))
+            })?;
+            let fetched = get_credentials(&cluster.name, info, admin)?;
+            let cfg = if admin || !info.aad {
+                // Client certificate credentials: keep what Azure issued
+                fetched
+            } else {
+                let endpoint = endpoint_from(&fetched, &cluster.context_name)?;
+                build_exec_kubeconfig(&cluster.context_name, &endpoint, &aks_exec()?)?
+            };
+            cluster.kubeconfig_path =
+                Some(write_discovered(&out_dir, &cfg, &cluster.context_name)?);
+        }
+        clusters.push(cluster);
+    }
+
+    Ok(DiscoverResult {
+        provider: "aks".into(),
+        dry_run,
+        clusters,
+    })
+}
+
+/// kubelogin exec config reusing the `az login` session (like `kubelogin convert-kubeconfig -l azurecli`)
+fn aks_exec() -> Result<ExecAuthConfig> {
+    let mut exec = ExecAuthConfig::default();
+    apply_exec_preset("aks", None, Some(AKS_AAD_SERVER_ID), None, &mut exec)?;
+    exec.args
+        .extend(["--login".to_string(), "azurecli".to_string()]);
+    Ok(exec)
+}
+
+/// `az aks get-credentials --file -` for one cluster
+fn get_credentials(name: &str, info: &AksCluster, admin: bool) -> Result<KubeConfig> {
+    let mut args = vec![
+        "aks",
+        "get-credentials",
+        "--name",
+        name,
+        "--resource-group",
+        &info.resource_group,
+        "--file",
+        "-",
+    ];
+    if let Some(ref sub) = info.subscription {
+        args.extend(["--subscription", sub]);
+    }
+    if admin {
+        args.push("--admin");
+    }
+    let raw = run_cli("az", &args)?;
+    Ok(serde_yaml_ng::from_slice(&raw)?)
+}
+
+/// Server and CA data of the context's cluster in fetched credentials
+fn endpoint_from(cfg: &KubeConfig, context_name: &str) -> Result<ClusterEndpoint> {
+    let missing =
+        || K8pkError::InvalidKubeconfig(format!("az returned no cluster for {}", context_name));
+    let ctx = cfg
+        .find_context(context_name)
+        .or_else(|| cfg.contexts.first())
+        .ok_or_else(missing)?;
+    let (cluster_name, _) = kubeconfig::extract_context_refs(&ctx.rest)?;
+    let cluster = cfg.find_cluster(&cluster_name).ok_or_else(missing)?;
+    let server = kubeconfig::extract_server_url_from_cluster(&cluster.rest).ok_or_else(missing)?;
+    let ca_data = cluster.rest["cluster"]["certificate-authority-data"]
+        .as_str()
+        .map(String::from);
+    Ok(ClusterEndpoint { server, ca_data })
+}
+
+/// (name, location, resource group, AAD-enabled) for each cluster in `az aks list` output
+fn parse_aks_list(json: &serde_json::Value) -> Vec<(String, String, String, bool)> {
+    json.as_array()
+        .map(|a| {
+            a.iter()
+                .filter_map(|c| {
+                    Some((
+                        c.get("name")?.as_str()?.to_string(),
+                        c.get("location")?.as_str()?.to_string(),
+                        c.get("resourceGroup")?.as_str()?.to_string(),
+                        c.get("aadProfile").is_some_and(|p| !p.is_null()),
+                    ))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_aks_list_output() {
+        let json = serde_json::json!([
+            {
+                "name": "prod",
+                "location": "westeurope",
+                "resourceGroup": "rg-prod",
+                "aadProfile": { "managed": true }
+            },
+            { "name": "legacy", "location": "eastus", "resourceGroup": "rg-old", "aadProfile": null },
+            { "name": "broken" }
+        ]);
+        let clusters = parse_aks_list(&json);
+        assert_eq!(clusters.len(), 2);
+        assert_eq!(
+            clusters[0],
+            ("prod".into(), "westeurope".into(), "rg-prod".into(), true)
+        );
+        assert!(!clusters[1].3);
+    }
+
+    #[test]
+    fn endpoint_from_az_credentials() {
+        let cfg: KubeConfig = serde_yaml_ng::from_str(
+            r#"
+apiVersion: v1
+kind: Config
+clusters:
+- name: prod
+  cluster:
+    server: https://prod-abc.hcp.westeurope.azmk8s.io:443
+    certificate-authority-data: LS0tLS1CRUdJTg==
+contexts:
+- name: prod
+  context:
+    cluster: prod
+    user: clusterUser_rg-prod_prod
+users:
+- name: clusterUser_rg-prod_prod
+  user:
+    token: placeholder
+"#,
+        )
+        .unwrap();
+        let endpoint = endpoint_from(&cfg, "prod").unwrap();
+        assert_eq!(
+            endpoint.server,
+            "https://prod-abc.hcp.westeurope.azmk8s.io:443"
+        );
+        assert_eq!(endpoint.ca_data.as_deref(), Some("LS0tLS1CRUdJTg=="));
+    }
+
+    #[test]
+    fn aks_exec_uses_azurecli_login() {
+        let exec = aks_exec().unwrap();
+        assert_eq!(exec.command.as_deref(), Some("kubelogin"));
+        assert_eq!(
+            exec.args,
+            vec![
+                "get-token",
+                "--server-id",
+                AKS_AAD_SERVER_ID,
+                "--login",
+                "azurecli"
+            ]
+        );
+    }
+}
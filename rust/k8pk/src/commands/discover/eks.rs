@@ -0,0 +1,224 @@
+//! EKS discovery via the AWS CLI (`aws eks list-clusters` / `describe-cluster`)
+
+use super::{
+    build_exec_kubeconfig, output_dir_for, require_cli, run_cli_json, select_clusters,
+    write_discovered, ClusterEndpoint, DiscoverResult, DiscoveredCluster,
+};
+use crate::commands::login::{apply_exec_preset, ExecAuthConfig};
+use crate::error::{K8pkError, Result};
+use std::path::Path;
+use std::process::Command;
+
+const AWS_INSTALL_HINT: &str = "Install the AWS CLI v2: https://docs.aws.amazon.com/cli/latest/userguide/getting-started-install.html";
+
+/// Discover EKS clusters across AWS profiles/regions and write exec-auth kubeconfigs.
+///
+/// Profiles default to every profile from `aws configure list-profiles`; regions
+/// default to each profile's configured region.
+#[allow(clippy::too_many_arguments)]
+pub fn discover_eks(
+    profiles: &[String],
+    regions: &[String],
+    pattern: Option<&str>,
+    all: bool,
+    output_dir: Option<&Path>,
+    dry_run: bool,
+    quiet: bool,
+) -> Result<DiscoverResult> {
+    require_cli("aws", AWS_INSTALL_HINT)?;
+
+    let profiles = if profiles.is_empty() {
+        aws_profiles()
+    } else {
+        profiles.to_vec()
+    };
+
+    let mut found = Vec::new();
+    for profile in &profiles {
+        let profile_regions = if regions.is_empty() {
+            match aws_profile_region(profile) {
+                Some(r) => vec![r],
+                None => {
+                    eprintln!(
+                        "warning: profile '{}' has no default region; pass --region",
+                        profile
+                    );
+                    continue;
+                }
+            }
+        } else {
+            regions.to_vec()
+        };
+
+        for region in &profile_regions {
+            if !quiet {
+                eprintln!("Listing EKS clusters ({}, {})...", profile, region);
+            }
+            let listed = run_cli_json("aws", &aws_args(&["eks", "list-clusters"], profile, region));
+            match listed {
+                Ok(json) => {
+                    for name in parse_list_clusters(&json) {
+                        found.push(DiscoveredCluster {
+                            name,
+                            location: region.clone(),
+                            scope: profile.clone(),
+                            context_name: String::new(),
+                            kubeconfig_path: None,
+                        });
+                    }
+                }
+                Err(e) => eprintln!("warning: {}", e),
+            }
+        }
+    }
+
+    let selected = select_clusters(found, pattern, all)?;
+    let out_dir = output_dir_for("eks", output_dir)?;
+
+    let mut clusters = Vec::new();
+    for mut cluster in selected {
+        let json = run_cli_json(
+            "aws",
+            &aws_args(
+                &["eks", "describe-cluster", "--name", &cluster.name],
+                &cluster.scope,
+                &cluster.location,
+            ),
+        )?;
+        let (arn, endpoint) = parse_describe_cluster(&json).ok_or_else(|| {
+            K8pkError::CommandFailed(format!(
+                "unexpected describe-cluster output for {}",
+                cluster.name
+            ))
+        })?;
+        cluster.context_name = arn;
+
+        if !dry_run {
+            let exec = eks_exec(&cluster.name, &cluster.location, &cluster.scope)?;
+            let cfg = build_exec_kubeconfig(&cluster.context_name, &endpoint, &exec)?;
+            cluster.kubeconfig_path =
+                Some(write_discovered(&out_dir, &cfg, &cluster.context_name)?);
+        }
+        clusters.push(cluster);
+    }
+
+    Ok(DiscoverResult {
+        provider: "eks".into(),
+        dry_run,
+        clusters,
+    })
+}
+
+fn aws_args<'a>(base: &[&'a str], profile: &'a str, region: &'a str) -> Vec<&'a str> {
+    let mut args = base.to_vec();
+    args.extend(["--output", "json", "--region", region, "--profile", profile]);
+    args
+}
+
+/// Exec config for `aws eks get-token`, pinned to the profile it was discovered with
+fn eks_exec(cluster: &str, region: &str, profile: &str) -> Result<ExecAuthConfig> {
+    let mut exec = ExecAuthConfig::default();
+    apply_exec_preset("aws-eks", Some(cluster), None, Some(region), &mut exec)?;
+    if profile != "default" {
+        exec.env.push(format!("AWS_PROFILE={}", profile));
+    }
+    Ok(exec)
+}
+
+fn aws_profiles() -> Vec<String> {
+    let listed = Command::new("aws")
+        .args(["configure", "list-profiles"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).into_owned())
+        .unwrap_or_default();
+    let profiles: Vec<String> = listed
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(String::from)
+        .collect();
+    if profiles.is_empty() {
+        vec![std::env::var("AWS_PROFILE").unwrap_or_else(|_| "default".into())]
+    } else {
+        profiles
+    }
+}
+
+fn aws_profile_region(profile: &str) -> Option<String> {
+    Command::new("aws")
+        .args(["configure", "get", "region", "--profile", profile])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+        .filter(|r| !r.is_empty())
+}
+
+fn parse_list_clusters(json: &serde_json::Value) -> Vec<String> {
+    json.get("clusters")
+        .and_then(|c| c.as_array())
+        .map(|a| {
+            a.iter()
+                .filter_map(|v| v.as_str().map(String::from))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// (cluster ARN, endpoint) from `aws eks describe-cluster`
+fn parse_describe_cluster(json: &serde_json::Value) -> Option<(String, ClusterEndpoint)> {
+    let cluster = json.get("cluster")?;
+    let arn = cluster.get("arn")?.as_str()?.to_string();
+    let server = cluster.get("endpoint")?.as_str()?.to_string();
+    let ca_data = cluster
+        .get("certificateAuthority")
+        .and_then(|c| c.get("data"))
+        .and_then(|d| d.as_str())
+        .map(String::from);
+    Some((arn, ClusterEndpoint { server, ca_data }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_list_clusters_output() {
+        let json = serde_json::json!({ "clusters": ["prod", "dev"] });
+        assert_eq!(parse_list_clusters(&json), vec!["prod", "dev"]);
+        assert!(parse_list_clusters(&serde_json::json!({})).is_empty());
+    }
+
+    #[test]
+    fn parse_describe_cluster_output() {
+        let json = serde_json::json!({
+            "cluster": {
+                "name": "prod",
+                "arn": "arn:aws:eks:us-east-1:123456789012:cluster/prod",
+                "endpoint": "https://ABC.gr7.us-east-1.eks.amazonaws.com",
+                "certificateAuthority": { "data": "LS0tLS1CRUdJTg==" }
+            }
+        });
+        let (arn, endpoint) = parse_describe_cluster(&json).unwrap();
+        assert_eq!(arn, "arn:aws:eks:us-east-1:123456789012:cluster/prod");
+        assert_eq!(
+            endpoint.server,
+            "https://ABC.gr7.us-east-1.eks.amazonaws.com"
+        );
+        assert_eq!(endpoint.ca_data.as_deref(), Some("LS0tLS1CRUdJTg=="));
+    }
+
+    #[test]
+    fn eks_exec_pins_profile_and_region() {
+        let exec = eks_exec("prod", "eu-west-1", "staging").unwrap();
+        assert_eq!(exec.command.as_deref(), Some("aws"));
+        assert!(exec.args.windows(2).any(|w| w == ["--region", "eu-west-1"]));
+        assert_eq!(exec.env, vec!["AWS_PROFILE=staging"]);
+        assert!(eks_exec("prod", "eu-west-1", "default")
+            .unwrap()
+            .env
+            .is_empty());
+    }
+}
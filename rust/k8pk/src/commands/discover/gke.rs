@@ -0,0 +1,214 @@
+//! GKE discovery via the Google Cloud SDK (`gcloud container clusters list`)
+
+use super::{
+    build_exec_kubeconfig, output_dir_for, require_cli, run_cli_json, select_clusters,
+    write_discovered, ClusterEndpoint, DiscoverResult, DiscoveredCluster,
+};
+use crate::commands::login::{apply_exec_preset, ExecAuthConfig};
+use crate::error::{K8pkError, Result};
+use std::collections::HashMap;
+use std::path::Path;
+
+const GCLOUD_INSTALL_HINT: &str =
+    "Install the Google Cloud SDK: https://cloud.google.com/sdk/docs/install";
+
+const AUTH_PLUGIN_HINT: &str =
+    "Install it with:\n  gcloud components install gke-gcloud-auth-plugin";
+
+/// Discover GKE clusters across GCP projects and write exec-auth kubeconfigs.
+///
+/// Projects default to those set in `gcloud config configurations list`.
+pub fn discover_gke(
+    projects: &[String],
+    pattern: Option<&str>,
+    all: bool,
+    output_dir: Option<&Path>,
+    dry_run: bool,
+    quiet: bool,
+) -> Result<DiscoverResult> {
+    require_cli("gcloud", GCLOUD_INSTALL_HINT)?;
+    require_cli("gke-gcloud-auth-plugin", AUTH_PLUGIN_HINT)?;
+
+    let projects = if projects.is_empty() {
+        gcloud_projects()?
+    } else {
+        projects.to_vec()
+    };
+    if projects.is_empty() {
+        return Err(K8pkError::InvalidArgument(
+            "no GCP project configured; pass --project or run `gcloud config set project`".into(),
+        ));
+    }
+
+    let mut found = Vec::new();
+    let mut endpoints = HashMap::new();
+    for project in &projects {
+        if !quiet {
+            eprintln!("Listing GKE clusters ({})...", project);
+        }
+        match run_cli_json(
+            "gcloud",
+            &[
+                "container",
+                "clusters",
+                "list",
+                "--project",
+                project,
+                "--format",
+                "json",
+            ],
+        ) {
+            Ok(json) => {
+                for (name, location, endpoint) in parse_clusters_list(&json) {
+                    let context_name = gke_context_name(project, &location, &name);
+                    endpoints.insert(context_name.clone(), endpoint);
+                    found.push(DiscoveredCluster {
+                        name,
+                        location,
+                        scope: project.clone(),
+                        context_name,
+                        kubeconfig_path: None,
+                    });
+                }
+            }
+            Err(e) => eprintln!("warning: {}", e),
+        }
+    }
+
+    let selected = select_clusters(found, pattern, all)?;
+    let out_dir = output_dir_for("gke", output_dir)?;
+
+    let mut clusters = Vec::new();
+    for mut cluster in selected {
+        if !dry_run {
+            let endpoint = endpoints.remove(&cluster.context_name).ok_or_else(|| {
+                K8pkError::CommandFailed(format!("no endpoint for {}", cluster.context_name))
+            })?;
+            let cfg = build_exec_kubeconfig(&cluster.context_name, &endpoint, &gke_exec()?)?;
+            cluster.kubeconfig_path =
+                Some(write_discovered(&out_dir, &cfg, &cluster.context_name)?);
+        }
+        clusters.push(cluster);
+    }
+
+    Ok(DiscoverResult {
+        provider: "gke".into(),
+        dry_run,
+        clusters,
+    })
+}
+
+/// Same naming as `gcloud container clusters get-credentials`
+fn gke_context_name(project: &str, location: &str, name: &str) -> String {
+    format!("gke_{}_{}_{}", project, location, name)
+}
+
+fn gke_exec() -> Result<ExecAuthConfig> {
+    let mut exec = ExecAuthConfig::default();
+    apply_exec_preset("gke", None, None, None, &mut exec)?;
+    Ok(exec)
+}
+
+/// Projects set in any gcloud configuration, active configuration first
+fn gcloud_projects() -> Result<Vec<String>> {
+    let json = run_cli_json(
+        "gcloud",
+        &["config", "configurations", "list", "--format", "json"],
+    )?;
+    Ok(parse_configurations(&json))
+}
+
+fn parse_configurations(json: &serde_json::Value) -> Vec<String> {
+    let mut entries: Vec<(bool, String)> = json
+        .as_array()
+        .map(|a| {
+            a.iter()
+                .filter_map(|c| {
+                    let project = c.pointer("/properties/core/project")?.as_str()?;
+                    let active = c.get("is_active").and_then(|v| v.as_bool()) == Some(true);
+                    Some((active, project.to_string()))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    entries.sort_by_key(|(active, _)| !active);
+    let mut projects = Vec::new();
+    for (_, p) in entries {
+        if !projects.contains(&p) {
+            projects.push(p);
+        }
+    }
+    projects
+}
+
+/// (name, location, endpoint) for each cluster in `gcloud container clusters list` output
+fn parse_clusters_list(json: &serde_json::Value) -> Vec<(String, String, ClusterEndpoint)> {
+    json.as_array()
+        .map(|a| {
+            a.iter()
+                .filter_map(|c| {
+                    let name = c.get("name")?.as_str()?.to_string();
+                    let location = c
+                        .get("location")
+                        .or_else(|| c.get("zone"))?
+                        .as_str()?
+                        .to_string();
+                    let endpoint = c.get("endpoint")?.as_str()?;
+                    let ca_data = c
+                        .pointer("/masterAuth/clusterCaCertificate")
+                        .and_then(|v| v.as_str())
+                        .map(String::from);
+                    Some((
+                        name,
+                        location,
+                        ClusterEndpoint {
+                            server: format!("https://{}", endpoint),
+                            ca_data,
+                        },
+                    ))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_clusters_list_output() {
+        let json = serde_json::json!([
+            {
+                "name": "prod",
+                "location": "europe-west1",
+                "endpoint": "34.1.2.3",
+                "masterAuth": { "clusterCaCertificate": "LS0tLS1CRUdJTg==" }
+            },
+            { "name": "legacy", "zone": "us-central1-a", "endpoint": "10.0.0.1" },
+            { "name": "broken" }
+        ]);
+        let clusters = parse_clusters_list(&json);
+        assert_eq!(clusters.len(), 2);
+        assert_eq!(clusters[0].0, "prod");
+        assert_eq!(clusters[0].2.server, "https://34.1.2.3");
+        assert_eq!(clusters[0].2.ca_data.as_deref(), Some("LS0tLS1CRUdJTg=="));
+        assert_eq!(clusters[1].1, "us-central1-a");
+        assert!(clusters[1].2.ca_data.is_none());
+        assert_eq!(
+            gke_context_name("acme", "europe-west1", "prod"),
+            "gke_acme_europe-west1_prod"
+        );
+    }
+
+    #[test]
+    fn parse_configurations_active_first() {
+        let json = serde_json::json!([
+            { "name": "other", "is_active": false, "properties": { "core": { "project": "b" } } },
+            { "name": "default", "is_active": true, "properties": { "core": { "project": "a" } } },
+            { "name": "dup", "is_active": false, "properties": { "core": { "project": "a" } } },
+            { "name": "empty", "is_active": false, "properties": {} }
+        ]);
+        assert_eq!(parse_configurations(&json), vec!["a", "b"]);
+    }
+}
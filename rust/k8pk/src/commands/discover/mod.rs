@@ -0,0 +1,285 @@
+//! Cloud cluster discovery: list clusters through provider CLIs, pick some,
+//! and write one exec-auth kubeconfig per cluster.
+
+mod aks;
+mod eks;
+mod gke;
+mod teleport;
+
+pub use aks::discover_aks;
+pub use eks::discover_eks;
+pub use gke::discover_gke;
+pub use teleport::discover_teleport;
+
+use super::login::{build_exec_auth, ExecAuthConfig};
+use crate::error::{K8pkError, Result};
+use crate::i18n::tr;
+use crate::kubeconfig::{self, KubeConfig, NamedItem};
+use crate::prompt::Ask;
+use inquire::MultiSelect;
+use serde_yaml_ng::{Mapping, Value as Yaml};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// A cluster found by discovery (and, unless dry run, written to disk)
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct DiscoveredCluster {
+    pub name: String,
+    /// Region or zone
+    pub location: String,
+    /// AWS profile, GCP project, Azure subscription, ...
+    pub scope: String,
+    pub context_name: String,
+    pub kubeconfig_path: Option<PathBuf>,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct DiscoverResult {
+    pub provider: String,
+    pub dry_run: bool,
+    pub clusters: Vec<DiscoveredCluster>,
+}
+
+/// Connection details needed to write a kubeconfig for a discovered cluster
+pub(super) struct ClusterEndpoint {
+    pub server: String,
+    /// Base64 PEM, as returned by the provider APIs
+    pub ca_data: Option<String>,
+}
+
+/// Run a provider CLI and parse its JSON stdout
+pub(super) fn run_cli_json(program: &str, args: &[&str]) -> Result<serde_json::Value> {
+    Ok(serde_json::from_slice(&run_cli(program, args)?)?)
+}
+
+/// Run a provider CLI and return its stdout
+pub(super) fn run_cli(program: &str, args: &[&str]) -> Result<Vec<u8>> {
+    let output = Command::new(program).args(args).output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(K8pkError::CommandFailed(format!(
+            "{} {} failed: {}",
+            program,
+            args.join(" "),
+            stderr.trim()
+        )));
+    }
+    Ok(output.stdout)
+}
+
+/// Fail early with an install hint when the provider CLI is missing
+pub(super) fn require_cli(program: &str, hint: &str) -> Result<()> {
+    if which::which(program).is_err() {
+        return Err(K8pkError::CommandFailed(format!(
+            "{} command not found.\n\n{}",
+            program, hint
+        )));
+    }
+    Ok(())
+}
+
+/// Narrow discovered clusters by `pattern`, then let the user pick (or take all with `all`)
+pub(super) fn select_clusters(
+    found: Vec<DiscoveredCluster>,
+    pattern: Option<&str>,
+    all: bool,
+) -> Result<Vec<DiscoveredCluster>> {
+    let found = match pattern {
+        Some(p) => {
+            let names: Vec<String> = found.iter().map(|c| c.name.clone()).collect();
+            let matched = crate::commands::match_pattern(p, &names);
+            found
+                .into_iter()
+                .filter(|c| matched.contains(&c.name))
+                .collect()
+        }
+        None => found,
+    };
+
+    if found.is_empty() {
+        return Err(K8pkError::InvalidArgument(match pattern {
+            Some(p) => format!("no discovered clusters matched pattern '{}'", p),
+            None => "no clusters discovered".into(),
+        }));
+    }
+
+    if all {
+        return Ok(found);
+    }
+    if !crate::prompt::can_prompt() {
+        return Err(K8pkError::InvalidArgument(
+            "multiple clusters discovered; pass --all (optionally with --pattern) when not on a TTY"
+                .into(),
+        ));
+    }
+
+    let labels: Vec<String> = found
+        .iter()
+        .map(|c| format!("{} ({}, {})", c.name, c.location, c.scope))
+        .collect();
+    let picked = MultiSelect::new(&tr("pick-clusters-add"), labels.clone()).ask()?;
+    Ok(found
+        .into_iter()
+        .zip(labels)
+        .filter(|(_, label)| picked.contains(label))
+        .map(|(c, _)| c)
+        .collect())
+}
+
+/// Single-context kubeconfig that authenticates through an exec plugin.
+/// Cluster, user, and context all share `context_name`, like the provider CLIs do.
+pub(super) fn build_exec_kubeconfig(
+    context_name: &str,
+    endpoint: &ClusterEndpoint,
+    exec: &ExecAuthConfig,
+) -> Result<KubeConfig> {
+    let mut cluster = Mapping::new();
+    cluster.insert("server".into(), endpoint.server.clone().into());
+    if let Some(ref ca) = endpoint.ca_data {
+        cluster.insert("certificate-authority-data".into(), ca.clone().into());
+    }
+    let mut cluster_rest = Mapping::new();
+    cluster_rest.insert("cluster".into(), Yaml::Mapping(cluster));
+
+    let mut user = Mapping::new();
+    user.insert("exec".into(), build_exec_auth(exec)?);
+    let mut user_rest = Mapping::new();
+    user_rest.insert("user".into(), Yaml::Mapping(user));
+
+    let mut context = Mapping::new();
+    context.insert("cluster".into(), context_name.into());
+    context.insert("user".into(), context_name.into());
+    let mut context_rest = Mapping::new();
+    context_rest.insert("context".into(), Yaml::Mapping(context));
+
+    let mut cfg = KubeConfig::default();
+    cfg.ensure_defaults(Some(context_name));
+    cfg.clusters.push(NamedItem {
+        name: context_name.to_string(),
+        rest: Yaml::Mapping(cluster_rest),
+    });
+    cfg.users.push(NamedItem {
+        name: context_name.to_string(),
+        rest: Yaml::Mapping(user_rest),
+    });
+    cfg.contexts.push(NamedItem {
+        name: context_name.to_string(),
+        rest: Yaml::Mapping(context_rest),
+    });
+    Ok(cfg)
+}
+
+/// Output directory for a provider: `--output-dir` or `~/.kube/<provider>`
+pub(super) fn output_dir_for(provider: &str, output_dir: Option<&Path>) -> Result<PathBuf> {
+    match output_dir {
+        Some(dir) => Ok(dir.to_path_buf()),
+        None => {
+            let home = dirs_next::home_dir().ok_or(K8pkError::NoHomeDir)?;
+            Ok(home.join(".kube").join(provider))
+        }
+    }
+}
+
+/// Write a discovered cluster's kubeconfig (0600) and return its path
+pub(super) fn write_discovered(
+    out_dir: &Path,
+    cfg: &KubeConfig,
+    context_name: &str,
+) -> Result<PathBuf> {
+    fs::create_dir_all(out_dir)?;
+    let path = out_dir.join(format!(
+        "{}.yaml",
+        kubeconfig::sanitize_filename(context_name)
+    ));
+    kubeconfig::write_restricted(&path, &serde_yaml_ng::to_string(cfg)?)?;
+    Ok(path)
+}
+
+pub fn print_discover_summary(result: &DiscoverResult) {
+    for c in &result.clusters {
+        match &c.kubeconfig_path {
+            Some(path) => eprintln!("  added {} ({}) -> {}", c.name, c.location, path.display()),
+            None => eprintln!(
+                "  would add {} ({}) as {}",
+                c.name, c.location, c.context_name
+            ),
+        }
+    }
+    if result.dry_run {
+        eprintln!(
+            "Dry run: {} {} cluster(s) would be added.",
+            result.clusters.len(),
+            result.provider
+        );
+    } else {
+        eprintln!(
+            "Added {} {} cluster kubeconfig{}.",
+            result.clusters.len(),
+            result.provider,
+            if result.clusters.len() == 1 { "" } else { "s" }
+        );
+        eprintln!("Run `k8pk` (or `kpick`) to pick one of the new contexts.");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_exec_kubeconfig_shape() {
+        let exec = ExecAuthConfig {
+            command: Some("aws".into()),
+            args: vec!["eks".into(), "get-token".into()],
+            env: vec!["AWS_PROFILE=dev".into()],
+            api_version: None,
+        };
+        let endpoint = ClusterEndpoint {
+            server: "https://example.eks.amazonaws.com".into(),
+            ca_data: Some("LS0tLS1CRUdJTg==".into()),
+        };
+        let cfg =
+            build_exec_kubeconfig("arn:aws:eks:us-east-1:1:cluster/dev", &endpoint, &exec).unwrap();
+        assert_eq!(
+            cfg.current_context.as_deref(),
+            Some("arn:aws:eks:us-east-1:1:cluster/dev")
+        );
+        let cluster = &cfg.clusters[0].rest["cluster"];
+        assert_eq!(
+            cluster["certificate-authority-data"].as_str(),
+            Some("LS0tLS1CRUdJTg==")
+        );
+        let exec = &cfg.users[0].rest["user"]["exec"];
+        assert_eq!(exec["command"].as_str(), Some("aws"));
+        assert_eq!(exec["env"][0]["name"].as_str(), Some("AWS_PROFILE"));
+        let (cluster_ref, user_ref) =
+            kubeconfig::extract_context_refs(&cfg.contexts[0].rest).unwrap();
+        assert_eq!(cluster_ref, cfg.clusters[0].name);
+        assert_eq!(user_ref, cfg.users[0].name);
+    }
+
+    #[test]
+    fn select_clusters_pattern_and_all() {
+        let found = vec![
+            DiscoveredCluster {
+                name: "prod".into(),
+                location: "us-east-1".into(),
+                scope: "default".into(),
+                context_name: "a".into(),
+                kubeconfig_path: None,
+            },
+            DiscoveredCluster {
+                name: "dev".into(),
+                location: "us-east-1".into(),
+                scope: "default".into(),
+                context_name: "b".into(),
+                kubeconfig_path: None,
+            },
+        ];
+        let picked = select_clusters(found.clone(), Some("prod"), true).unwrap();
+        assert_eq!(picked.len(), 1);
+        assert_eq!(picked[0].name, "prod");
+        assert!(select_clusters(found, Some("nope"), true).is_err());
+    }
+}
@@ -0,0 +1,118 @@
+//! Teleport discovery via `tsh kube ls` / `tsh kube login`
+
+use super::{output_dir_for, require_cli, run_cli_json, select_clusters, write_discovered};
+use super::{DiscoverResult, DiscoveredCluster};
+use crate::commands::login::ensure_tsh_session;
+use crate::error::{K8pkError, Result};
+use crate::kubeconfig::KubeConfig;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+const TSH_INSTALL_HINT: &str =
+    "Install the Teleport client: https://goteleport.com/docs/installation/";
+
+/// Discover kube clusters behind a Teleport proxy and write one kubeconfig per
+/// cluster, as generated by `tsh kube login` (exec auth via `tsh kube credentials`).
+///
+/// Runs `tsh login` first when the Teleport certificate is missing or expired.
+pub fn discover_teleport(
+    proxy: Option<&str>,
+    pattern: Option<&str>,
+    all: bool,
+    output_dir: Option<&Path>,
+    dry_run: bool,
+    quiet: bool,
+) -> Result<DiscoverResult> {
+    require_cli("tsh", TSH_INSTALL_HINT)?;
+    let session = ensure_tsh_session(proxy, quiet)?;
+
+    if !quiet {
+        eprintln!("Listing Teleport kube clusters ({})...", session.cluster);
+    }
+    let json = run_cli_json("tsh", &["kube", "ls", "--format", "json"])?;
+    let found = parse_kube_ls(&json)
+        .into_iter()
+        .map(|name| DiscoveredCluster {
+            context_name: format!("{}-{}", session.cluster, name),
+            name,
+            location: session.cluster.clone(),
+            scope: session.proxy.clone(),
+            kubeconfig_path: None,
+        })
+        .collect();
+
+    let selected = select_clusters(found, pattern, all)?;
+    let out_dir = output_dir_for("teleport", output_dir)?;
+
+    let mut clusters = Vec::new();
+    for mut cluster in selected {
+        if !dry_run {
+            let cfg = tsh_kube_login(&cluster.name)?;
+            if let Some(ref ctx) = cfg.current_context {
+                cluster.context_name = ctx.clone();
+            }
+            cluster.kubeconfig_path =
+                Some(write_discovered(&out_dir, &cfg, &cluster.context_name)?);
+            // Lets the session check renew the Teleport certificate instead of asking for a token
+            let _ = crate::commands::context::save_context_type(&cluster.context_name, "teleport");
+        }
+        clusters.push(cluster);
+    }
+
+    Ok(DiscoverResult {
+        provider: "teleport".into(),
+        dry_run,
+        clusters,
+    })
+}
+
+/// Run `tsh kube login` against a scratch KUBECONFIG and return what it wrote
+fn tsh_kube_login(kube_cluster: &str) -> Result<KubeConfig> {
+    let scratch = tempfile::tempdir()?;
+    let path = scratch.path().join("config");
+    let output = Command::new("tsh")
+        .args(["kube", "login", kube_cluster])
+        .env("KUBECONFIG", &path)
+        .output()?;
+    if !output.status.success() {
+        return Err(K8pkError::CommandFailed(format!(
+            "tsh kube login {} failed: {}",
+            kube_cluster,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    let mut cfg: KubeConfig = serde_yaml_ng::from_str(&fs::read_to_string(&path)?)?;
+    cfg.ensure_defaults(None);
+    Ok(cfg)
+}
+
+fn parse_kube_ls(json: &serde_json::Value) -> Vec<String> {
+    json.as_array()
+        .map(|a| {
+            a.iter()
+                .filter_map(|c| {
+                    c.get("kube_cluster_name")
+                        .or_else(|| c.get("name"))
+                        .and_then(|n| n.as_str())
+                        .map(String::from)
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_kube_ls_output() {
+        let json = serde_json::json!([
+            { "kube_cluster_name": "prod", "labels": { "env": "prod" }, "selected": false },
+            { "name": "legacy" },
+            { "labels": {} }
+        ]);
+        assert_eq!(parse_kube_ls(&json), vec!["prod", "legacy"]);
+    }
+}
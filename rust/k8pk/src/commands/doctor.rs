@@ -3,9 +3,10 @@
 use crate::config;
 use crate::error::Result;
 use crate::kubeconfig::{self, KubeConfig};
-use std::collections::HashSet;
+use crate::theme::Role;
+use std::collections::{BTreeMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
 #[derive(Debug)]
@@ -52,13 +53,15 @@ impl DiagnosticResult {
     }
 }
 
-pub fn run(fix: bool, json: bool) -> Result<()> {
+/// Seconds to wait for each API server with `--connectivity`
+const CONNECT_TIMEOUT_SECS: u64 = 3;
+
+pub fn run(fix: bool, json: bool, connectivity: bool) -> Result<()> {
     let mut results = vec![check_kubectl(), check_oc(), check_k8pk_config()];
 
-    // ponytail: only probe gcloud/GKE plugin when relevant
-    if should_check_gke() {
-        results.push(check_gcloud());
-        results.push(check_gke_auth_plugin());
+    // Config still at ~/.kube/k8pk.yaml, or shadowed by the XDG one
+    if let Some(r) = check_legacy_config() {
+        results.push(r);
     }
 
     // Check kubeconfig files
@@ -70,6 +73,24 @@ pub fn run(fix: bool, json: bool) -> Result<()> {
     // Check for orphaned contexts
     results.push(check_orphaned_contexts());
 
+    // One check per exec credential plugin referenced by a user
+    results.extend(check_exec_plugins());
+
+    // Kerberos ticket, when a user logs in with `login --auth gssapi`
+    if let Some(r) = check_kerberos_ticket() {
+        results.push(r);
+    }
+
+    // One check per API server, only on request: it touches the network
+    if connectivity {
+        results.extend(check_connectivity());
+    }
+
+    // Client vs. server versions, from cached server versions only
+    if let Some(r) = check_version_skew() {
+        results.push(r);
+    }
+
     // Check K8PK environment variables
     results.push(check_k8pk_env());
 
@@ -88,6 +109,10 @@ pub fn run(fix: bool, json: bool) -> Result<()> {
         results.push(r);
     }
 
+    if let Some(r) = check_encryption() {
+        results.push(r);
+    }
+
     if fix {
         let fixed = apply_fixes(&mut results);
         if !json && fixed > 0 {
@@ -163,66 +188,20 @@ fn check_oc() -> DiagnosticResult {
     }
 }
 
-fn should_check_gke() -> bool {
-    if which::which("gcloud").is_ok() {
-        return true;
-    }
-    // Any context that looks like GKE?
-    let Ok(config) = config::load() else {
-        return false;
-    };
-    let Ok(paths) = kubeconfig::resolve_paths(None, &[], &config) else {
-        return false;
-    };
-    let Ok(merged) = kubeconfig::load_merged(&paths) else {
-        return false;
-    };
-    merged
-        .context_names()
-        .iter()
-        .any(|n| n.starts_with("gke_") || n.starts_with("gke-") || n.to_lowercase().contains("gke"))
-}
-
-fn check_gcloud() -> DiagnosticResult {
-    match Command::new("gcloud").arg("version").output() {
-        Ok(output) if output.status.success() => {
-            let version = String::from_utf8_lossy(&output.stdout);
-            let version_str = version
-                .lines()
-                .find(|l| l.contains("Google Cloud SDK"))
-                .unwrap_or("unknown")
-                .trim();
-            DiagnosticResult::ok("gcloud", &format!("Found: {}", version_str))
-        }
-        _ => DiagnosticResult::warning(
-            "gcloud",
-            "Not installed (optional, needed for GKE login)",
-            Some("Install gcloud: https://cloud.google.com/sdk/docs/install"),
-        ),
-    }
-}
-
-fn check_gke_auth_plugin() -> DiagnosticResult {
-    match Command::new("gke-gcloud-auth-plugin")
-        .arg("--version")
-        .output()
-    {
-        Ok(output) if output.status.success() => {
-            let version = String::from_utf8_lossy(&output.stdout);
-            DiagnosticResult::ok(
-                "gke-gcloud-auth-plugin",
-                &format!("Found: {}", version.trim()),
-            )
+fn check_k8pk_config() -> DiagnosticResult {
+    if let Some(team) = config::team_config_path() {
+        if let Err(e) = config::load() {
+            return DiagnosticResult::error(
+                "k8pk config",
+                &format!(
+                    "Invalid config (team defaults at {}): {}",
+                    team.display(),
+                    e
+                ),
+                Some("Check the team config and your own config for bad keys"),
+            );
         }
-        _ => DiagnosticResult::warning(
-            "gke-gcloud-auth-plugin",
-            "Not installed (required for GKE clusters)",
-            Some("Install: gcloud components install gke-gcloud-auth-plugin"),
-        ),
     }
-}
-
-fn check_k8pk_config() -> DiagnosticResult {
     match config::config_path() {
         Ok(path) => {
             if path.exists() {
@@ -253,6 +232,113 @@ fn check_k8pk_config() -> DiagnosticResult {
     }
 }
 
+const LEGACY_CONFIG_CHECK: &str = "legacy config";
+
+/// True once `--fix` has replaced the legacy file with a pointer comment
+/// (or it holds nothing but comments anyway)
+fn is_migrated(content: &str) -> bool {
+    content
+        .lines()
+        .all(|l| l.trim().is_empty() || l.trim_start().starts_with('#'))
+}
+
+/// Top-level settings of the legacy config that the XDG config doesn't
+/// share: set differently there, or not at all
+fn legacy_conflicts(legacy: &serde_yaml_ng::Value, current: &serde_yaml_ng::Value) -> Vec<String> {
+    let Some(legacy) = legacy.as_mapping() else {
+        return Vec::new();
+    };
+    legacy
+        .iter()
+        .filter_map(|(key, value)| {
+            let key = key.as_str()?;
+            match current.get(key) {
+                Some(v) if v == value => None,
+                Some(_) => Some(format!("{} differs", key)),
+                None => Some(format!("{} only in the legacy file", key)),
+            }
+        })
+        .collect()
+}
+
+fn check_legacy_config() -> Option<DiagnosticResult> {
+    let legacy = config::legacy_config_path().ok()?;
+    let xdg = config::xdg_config_path().ok()?;
+    let content = fs::read_to_string(&legacy).ok()?;
+    if is_migrated(&content) {
+        return None;
+    }
+    let (legacy_shown, xdg_shown) = (super::display_path(&legacy), super::display_path(&xdg));
+    if !xdg.exists() {
+        return Some(DiagnosticResult::warning(
+            LEGACY_CONFIG_CHECK,
+            &format!("Config at legacy location {}", legacy_shown),
+            Some(&format!(
+                "Run: k8pk doctor --fix to move it to {}",
+                xdg_shown
+            )),
+        ));
+    }
+
+    let parse = |text: &str| serde_yaml_ng::from_str(text).unwrap_or_default();
+    let current = fs::read_to_string(&xdg).unwrap_or_default();
+    let conflicts = legacy_conflicts(&parse(&content), &parse(&current));
+    let message = if conflicts.is_empty() {
+        format!(
+            "{} is ignored because {} exists; it has no settings of its own",
+            legacy_shown, xdg_shown
+        )
+    } else {
+        format!(
+            "{} is ignored because {} exists; ignored settings: {}",
+            legacy_shown,
+            xdg_shown,
+            conflicts.join(", ")
+        )
+    };
+    Some(DiagnosticResult::warning(
+        LEGACY_CONFIG_CHECK,
+        &message,
+        Some(&format!(
+            "Move any settings you need into {}, then delete {}",
+            xdg_shown, legacy_shown
+        )),
+    ))
+}
+
+/// Copy the legacy config to the XDG path, check the copy parses, and leave
+/// a pointer comment (with the old settings commented out) behind
+fn migrate_legacy_config(legacy: &Path, xdg: &Path) -> Result<()> {
+    if let Some(parent) = xdg.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    // fs::copy keeps the permission bits
+    fs::copy(legacy, xdg)?;
+    let copied = fs::read_to_string(xdg)?;
+    if !copied.trim().is_empty() {
+        if let Err(e) = serde_yaml_ng::from_str::<config::K8pkConfig>(&copied) {
+            let _ = fs::remove_file(xdg);
+            return Err(e.into());
+        }
+    }
+
+    let mut pointer = format!(
+        "# k8pk's config moved to {} (k8pk doctor --fix).\n\
+         # This file is no longer read; the old settings are kept below for reference.\n#\n",
+        super::display_path(xdg)
+    );
+    for line in copied.lines() {
+        let line = line.trim_end();
+        pointer.push_str(&if line.is_empty() {
+            "#\n".to_string()
+        } else {
+            format!("# {}\n", line)
+        });
+    }
+    fs::write(legacy, pointer)?;
+    Ok(())
+}
+
 fn check_kubeconfig_files() -> Vec<DiagnosticResult> {
     let mut results = Vec::new();
 
@@ -395,6 +481,305 @@ fn check_orphaned_contexts() -> DiagnosticResult {
     }
 }
 
+/// Version arguments and install hint of well-known exec plugins, by file
+/// name; anything else is asked for `--version`
+const KNOWN_PLUGINS: &[(&str, &[&str], &str)] = &[
+    (
+        "aws",
+        &["--version"],
+        "Install the AWS CLI: https://aws.amazon.com/cli/",
+    ),
+    (
+        "aws-iam-authenticator",
+        &["version"],
+        "Install aws-iam-authenticator: https://github.com/kubernetes-sigs/aws-iam-authenticator",
+    ),
+    (
+        "gke-gcloud-auth-plugin",
+        &["--version"],
+        "Install: gcloud components install gke-gcloud-auth-plugin",
+    ),
+    (
+        "kubelogin",
+        &["--version"],
+        "Install kubelogin: az aks install-cli (Azure) or https://github.com/int128/kubelogin",
+    ),
+    (
+        "kubectl-oidc_login",
+        &["--version"],
+        "Install: kubectl krew install oidc-login",
+    ),
+    (
+        "tsh",
+        &["version"],
+        "Install Teleport: https://goteleport.com/download/",
+    ),
+    (
+        "az",
+        &["--version"],
+        "Install the Azure CLI: https://learn.microsoft.com/cli/azure/install-azure-cli",
+    ),
+    (
+        "gcloud",
+        &["version"],
+        "Install gcloud: https://cloud.google.com/sdk/docs/install",
+    ),
+    (
+        "doctl",
+        &["version"],
+        "Install doctl: https://docs.digitalocean.com/reference/doctl/",
+    ),
+];
+
+fn known_plugin(
+    command: &str,
+) -> Option<&'static (&'static str, &'static [&'static str], &'static str)> {
+    let name = std::path::Path::new(command).file_name()?.to_str()?;
+    let name = name.strip_suffix(".exe").unwrap_or(name);
+    KNOWN_PLUGINS.iter().find(|(n, _, _)| *n == name)
+}
+
+/// First line a plugin prints for its version flag (some print to stderr)
+fn plugin_version(path: &std::path::Path, command: &str) -> Option<String> {
+    let args = known_plugin(command).map_or(&["--version"][..], |(_, args, _)| *args);
+    let output = Command::new(path)
+        .args(args)
+        .stdin(std::process::Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    first_line(&output.stdout).or_else(|| first_line(&output.stderr))
+}
+
+fn first_line(bytes: &[u8]) -> Option<String> {
+    let text = String::from_utf8_lossy(bytes);
+    let line = text.lines().map(str::trim).find(|l| !l.is_empty())?;
+    Some(line.chars().take(80).collect())
+}
+
+/// One check per exec credential plugin referenced by any user in the
+/// resolved kubeconfigs: found (with its version) or not
+fn check_kerberos_ticket() -> Option<DiagnosticResult> {
+    let k8pk_config = config::load().unwrap_or_default();
+    let paths = kubeconfig::resolve_paths(None, &[], &k8pk_config).ok()?;
+    let merged = kubeconfig::load_merged(&paths).ok()?;
+    let users: Vec<&str> = merged
+        .users
+        .iter()
+        .filter(|u| super::is_gssapi_user(&u.rest))
+        .map(|u| u.name.as_str())
+        .collect();
+    if users.is_empty() {
+        return None;
+    }
+
+    let name = "kerberos ticket";
+    // `klist -s` (MIT and Heimdal): silent, exit status says whether a valid TGT exists
+    let status = Command::new("klist")
+        .arg("-s")
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status();
+    Some(match status {
+        Ok(s) if s.success() => {
+            DiagnosticResult::ok(name, &format!("Valid ticket (users: {})", users.join(", ")))
+        }
+        Ok(_) => DiagnosticResult::error(
+            name,
+            &format!(
+                "No valid Kerberos ticket; gssapi logins will fail (users: {})",
+                users.join(", ")
+            ),
+            Some("Run: kinit"),
+        ),
+        Err(_) => DiagnosticResult::warning(
+            name,
+            "klist not found; cannot check for a Kerberos ticket",
+            Some("Install the Kerberos client tools (krb5-user, krb5-workstation, or heimdal)"),
+        ),
+    })
+}
+
+fn check_exec_plugins() -> Vec<DiagnosticResult> {
+    let k8pk_config = config::load().unwrap_or_default();
+    let hints = config::exec_path_dirs_with(&k8pk_config);
+    let Ok(paths) = kubeconfig::resolve_paths(None, &[], &k8pk_config) else {
+        return Vec::new();
+    };
+
+    // command -> (kubeconfig directory for relative commands, users)
+    let mut plugins: BTreeMap<String, (PathBuf, Vec<String>)> = BTreeMap::new();
+    for path in &paths {
+        // One file at a time, so relative commands resolve against their own directory
+        let Ok(cfg) = kubeconfig::load_merged(std::slice::from_ref(path)) else {
+            continue;
+        };
+        let base = path.parent().map(PathBuf::from).unwrap_or_default();
+        for (user, command) in kubeconfig::exec_plugins(&cfg) {
+            let entry = plugins.entry(command).or_insert((base.clone(), Vec::new()));
+            if !entry.1.contains(&user) {
+                entry.1.push(user);
+            }
+        }
+    }
+
+    plugins
+        .into_iter()
+        .map(|(command, (base, users))| {
+            let name = format!("exec plugin {}", command);
+            match kubeconfig::find_exec_plugin(&command, &hints, &base) {
+                Some(found) => {
+                    let version = plugin_version(&found, &command)
+                        .unwrap_or_else(|| "version unknown".to_string());
+                    DiagnosticResult::ok(
+                        &name,
+                        &format!(
+                            "Found: {} ({}; users: {})",
+                            found.display(),
+                            version,
+                            users.join(", ")
+                        ),
+                    )
+                }
+                None => {
+                    let install = known_plugin(&command).map(|(_, _, hint)| *hint);
+                    let fix = match install {
+                        Some(hint) => format!(
+                            "{}, or add its directory to exec_path_hints in the k8pk config",
+                            hint
+                        ),
+                        None => {
+                            "Install it, or add its directory to exec_path_hints in the k8pk config"
+                                .to_string()
+                        }
+                    };
+                    DiagnosticResult::warning(
+                        &name,
+                        &format!("Not found on PATH (used by: {})", users.join(", ")),
+                        Some(&fix),
+                    )
+                }
+            }
+        })
+        .collect()
+}
+
+/// kubectl/oc against the server versions cached by `which --peek` and
+/// `version-skew` (doctor doesn't contact clusters); None when nothing is cached
+/// Contexts of each API server in the resolved kubeconfigs
+fn servers_by_context() -> BTreeMap<String, Vec<String>> {
+    let k8pk_config = config::load().unwrap_or_default();
+    let Ok(merged) = kubeconfig::resolve_paths(None, &[], &k8pk_config)
+        .and_then(|paths| kubeconfig::load_merged(&paths))
+    else {
+        return BTreeMap::new();
+    };
+    let mut servers: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for context in merged.context_names() {
+        if let Some(server) = kubeconfig::get_server_for_context(&merged, &context) {
+            servers.entry(server).or_default().push(context);
+        }
+    }
+    servers
+}
+
+/// Open a TCP connection to every API server (in parallel)
+fn check_connectivity() -> Vec<DiagnosticResult> {
+    if let Err(e) = config::require_online("the connectivity check") {
+        return vec![DiagnosticResult::warning(
+            "connectivity",
+            &format!("Skipped: {}", e),
+            None,
+        )];
+    }
+    let servers = servers_by_context();
+    if servers.is_empty() {
+        return vec![DiagnosticResult::warning(
+            "connectivity",
+            "No contexts with a server to check",
+            None,
+        )];
+    }
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = servers
+            .iter()
+            .map(|(server, contexts)| {
+                let handle = scope.spawn(move || {
+                    super::login::check_server_reachable(server, CONNECT_TIMEOUT_SECS).is_ok()
+                });
+                (server, contexts, handle)
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|(server, contexts, handle)| {
+                let name = format!("connectivity: {}", server);
+                let contexts = format!("contexts: {}", contexts.join(", "));
+                if handle.join().unwrap_or(false) {
+                    DiagnosticResult::ok(&name, &format!("Reachable ({})", contexts))
+                } else {
+                    DiagnosticResult::error(
+                        &name,
+                        &format!(
+                            "Unreachable within {}s ({})",
+                            CONNECT_TIMEOUT_SECS, contexts
+                        ),
+                        Some("Check the server URL, VPN, and proxy settings"),
+                    )
+                }
+            })
+            .collect()
+    })
+}
+
+fn check_version_skew() -> Option<DiagnosticResult> {
+    let k8pk_config = config::load().unwrap_or_default();
+    let paths = kubeconfig::resolve_paths(None, &[], &k8pk_config).ok()?;
+    let contexts = kubeconfig::load_merged(&paths).ok()?.context_names();
+    let clients = super::client_versions();
+    if clients.is_empty() {
+        return None;
+    }
+
+    let mut checked = 0;
+    let mut skewed = Vec::new();
+    for context in &contexts {
+        let Some(version) = kubeconfig::cached_cluster_facts(context).and_then(|f| f.version)
+        else {
+            continue;
+        };
+        checked += 1;
+        for s in super::client_skews(&clients, &version) {
+            if !s.supported {
+                skewed.push(format!(
+                    "{} {:+} on {} ({})",
+                    s.tool, s.skew, context, version
+                ));
+            }
+        }
+    }
+    if checked == 0 {
+        return None;
+    }
+    Some(if skewed.is_empty() {
+        DiagnosticResult::ok(
+            "version skew",
+            &format!("Clients within one minor version of {} cluster(s)", checked),
+        )
+    } else {
+        DiagnosticResult::warning(
+            "version skew",
+            &format!("Outside the supported skew: {}", skewed.join("; ")),
+            Some(
+                "Install a kubectl within one minor version of the cluster; see k8pk version-skew",
+            ),
+        )
+    })
+}
+
 fn check_k8pk_env() -> DiagnosticResult {
     let k8pk_ctx = std::env::var("K8PK_CONTEXT").ok();
     let k8pk_ns = std::env::var("K8PK_NAMESPACE").ok();
@@ -418,13 +803,10 @@ fn check_k8pk_env() -> DiagnosticResult {
 }
 
 fn check_kubeconfig_env() -> DiagnosticResult {
-    match std::env::var("KUBECONFIG") {
-        Ok(val) => {
-            let paths: Vec<_> = val.split(':').collect();
-            let existing: Vec<_> = paths
-                .iter()
-                .filter(|p| std::path::Path::new(p).exists())
-                .collect();
+    match std::env::var_os("KUBECONFIG") {
+        Some(val) => {
+            let paths = crate::kubeconfig::split_kubeconfig_env(&val);
+            let existing: Vec<_> = paths.iter().filter(|p| p.exists()).collect();
 
             if existing.len() == paths.len() {
                 DiagnosticResult::ok(
@@ -439,7 +821,7 @@ fn check_kubeconfig_env() -> DiagnosticResult {
                 )
             }
         }
-        Err(_) => DiagnosticResult::ok("KUBECONFIG env", "Not set (using ~/.kube/config)"),
+        None => DiagnosticResult::ok("KUBECONFIG env", "Not set (using ~/.kube/config)"),
     }
 }
 
@@ -447,6 +829,12 @@ fn check_shell_integration() -> DiagnosticResult {
     // Check if the shell integration appears to be sourced by looking for
     // common indicators: the kctx/kns functions or the k8pk.sh source line.
     // We check the shell config files for the presence of k8pk integration.
+    if let Ok(shell) = std::env::var(super::init::SHELL_INTEGRATION_ENV) {
+        return DiagnosticResult::ok(
+            "shell integration",
+            &format!("Loaded via `k8pk init {}`", shell),
+        );
+    }
     let home = match dirs_next::home_dir() {
         Some(h) => h,
         None => {
@@ -487,7 +875,7 @@ fn check_shell_integration() -> DiagnosticResult {
     DiagnosticResult::warning(
         "shell integration",
         "k8pk shell integration not detected",
-        Some("Source shell/k8pk.sh in your shell rc, or set up eval wrappers manually"),
+        Some("Run `k8pk init --install`, or add `eval \"$(k8pk init bash)\"` to your shell rc"),
     )
 }
 
@@ -496,6 +884,7 @@ fn print_results(results: &[DiagnosticResult], _fix: bool) {
     println!("===========");
     println!();
 
+    let theme = crate::theme::stdout();
     let mut ok_count = 0;
     let mut warn_count = 0;
     let mut err_count = 0;
@@ -504,15 +893,15 @@ fn print_results(results: &[DiagnosticResult], _fix: bool) {
         let icon = match result.status {
             DiagStatus::Ok => {
                 ok_count += 1;
-                "OK"
+                theme.paint(Role::Ok, "OK")
             }
             DiagStatus::Warning => {
                 warn_count += 1;
-                "WARN"
+                theme.paint(Role::Warning, "WARN")
             }
             DiagStatus::Error => {
                 err_count += 1;
-                "ERR"
+                theme.paint(Role::Error, "ERR")
             }
         };
 
@@ -562,7 +951,7 @@ fn print_json(results: &[DiagnosticResult]) {
 
     println!(
         "{}",
-        serde_json::to_string_pretty(&json_results).unwrap_or_default()
+        crate::render::to_string(&json_results).unwrap_or_default()
     );
 }
 
@@ -634,6 +1023,28 @@ fn check_vault_file_permissions() -> Option<DiagnosticResult> {
     None
 }
 
+/// age tooling when kubeconfig encryption at rest is enabled.
+fn check_encryption() -> Option<DiagnosticResult> {
+    if !crate::encryption::enabled(None) {
+        return None;
+    }
+    let missing: Vec<&str> = ["age", "age-keygen"]
+        .into_iter()
+        .filter(|tool| which::which(tool).is_err())
+        .collect();
+    if missing.is_empty() {
+        return Some(DiagnosticResult::ok(
+            "kubeconfig encryption",
+            "enabled (age found)",
+        ));
+    }
+    Some(DiagnosticResult::error(
+        "kubeconfig encryption",
+        &format!("enabled, but {} not found on PATH", missing.join(" and ")),
+        Some("Install age (https://age-encryption.org) or set encryption.enabled: false"),
+    ))
+}
+
 /// Apply automatic fixes for issues that can be safely corrected.
 fn apply_fixes(results: &mut [DiagnosticResult]) -> usize {
     for result in results.iter_mut() {
@@ -641,6 +1052,27 @@ fn apply_fixes(results: &mut [DiagnosticResult]) -> usize {
             continue;
         }
 
+        // Move a legacy config to the XDG path; with both present the
+        // settings need a person to reconcile them
+        if result.name == LEGACY_CONFIG_CHECK {
+            if let (Ok(legacy), Ok(xdg)) = (config::legacy_config_path(), config::xdg_config_path())
+            {
+                if !xdg.exists() {
+                    match migrate_legacy_config(&legacy, &xdg) {
+                        Ok(()) => {
+                            result.status = DiagStatus::Ok;
+                            result.message =
+                                format!("fixed: moved to {}", super::display_path(&xdg));
+                            result.fix_hint = None;
+                        }
+                        Err(e) => {
+                            result.message = format!("{} (move failed: {})", result.message, e)
+                        }
+                    }
+                }
+            }
+        }
+
         // Fix kubeconfig permissions
         #[cfg(unix)]
         if result.name.starts_with("file permissions:") {
@@ -665,3 +1097,53 @@ fn apply_fixes(results: &mut [DiagnosticResult]) -> usize {
         .filter(|r| r.message.starts_with("fixed:"))
         .count()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_legacy_config_migration() {
+        let tmp = tempfile::tempdir().unwrap();
+        let legacy = tmp.path().join(".kube/k8pk.yaml");
+        let xdg = tmp.path().join(".config/k8pk/config.yaml");
+        fs::create_dir_all(legacy.parent().unwrap()).unwrap();
+
+        fs::write(&legacy, "picker: [not, a, string]\n").unwrap();
+        assert!(migrate_legacy_config(&legacy, &xdg).is_err());
+        assert!(!xdg.exists());
+        assert!(!is_migrated(&fs::read_to_string(&legacy).unwrap()));
+
+        let settings = "picker: fzf\n\nconfigs:\n  include: [\"~/.kube/*.yaml\"]\n";
+        fs::write(&legacy, settings).unwrap();
+        migrate_legacy_config(&legacy, &xdg).unwrap();
+        assert_eq!(fs::read_to_string(&xdg).unwrap(), settings);
+        let pointer = fs::read_to_string(&legacy).unwrap();
+        assert!(is_migrated(&pointer));
+        assert!(pointer.contains("# picker: fzf\n#\n# configs:"));
+
+        let yaml = |s: &str| serde_yaml_ng::from_str::<serde_yaml_ng::Value>(s).unwrap();
+        assert_eq!(
+            legacy_conflicts(
+                &yaml("picker: fzf\ntheme: mono\nlanguage: de\n"),
+                &yaml("picker: sk\ntheme: mono\n")
+            ),
+            ["picker differs", "language only in the legacy file"]
+        );
+    }
+
+    #[test]
+    fn test_known_plugins_and_version_line() {
+        assert_eq!(known_plugin("/usr/local/bin/aws").map(|p| p.0), Some("aws"));
+        assert_eq!(
+            known_plugin("kubelogin.exe").map(|p| p.1),
+            Some(&["--version"][..])
+        );
+        assert!(known_plugin("./bin/custom-auth").is_none());
+        assert_eq!(
+            first_line(b"\n  tsh v15.1.0 go1.21\nProxy: x\n").as_deref(),
+            Some("tsh v15.1.0 go1.21")
+        );
+        assert_eq!(first_line(b"  \n"), None);
+    }
+}
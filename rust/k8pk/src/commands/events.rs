@@ -0,0 +1,395 @@
+//! `k8pk events`: tail the events of the current context and namespace.
+//!
+//! Users with a bearer token are watched directly through the API server
+//! ([`kubeconfig::BearerApi`]): list, then watch from the list's
+//! resourceVersion, reconnecting where the last watch stopped and relisting
+//! when the server says that version is gone (HTTP 410). Everyone else gets
+//! `kubectl get events --watch`, restarted when it exits.
+
+use crate::error::{K8pkError, Result};
+use crate::kubeconfig;
+use crate::theme::{self, Role, Theme};
+use serde::Serialize;
+use serde_json::Value;
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// Seconds the server keeps one watch open before we reconnect
+const WATCH_TIMEOUT_SECS: u64 = 300;
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+pub struct EventsOptions {
+    pub all_namespaces: bool,
+    /// Skip the events that already exist
+    pub watch_only: bool,
+    /// One JSON object per line instead of columns
+    pub json: bool,
+}
+
+/// How an event is colored
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Normal,
+    Warning,
+    /// Warnings about something broken (failed, back-off, OOM, eviction)
+    Error,
+}
+
+/// The columns of one event
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EventLine {
+    pub time: String,
+    pub namespace: String,
+    pub severity: Severity,
+    pub reason: String,
+    /// `pod/web-1`
+    pub object: String,
+    pub message: String,
+    pub count: u64,
+}
+
+const ERROR_REASONS: &[&str] = &["fail", "backoff", "error", "oom", "evict", "unhealthy"];
+
+fn severity(kind: &str, reason: &str) -> Severity {
+    if kind != "Warning" {
+        return Severity::Normal;
+    }
+    let reason = reason.to_lowercase();
+    if ERROR_REASONS.iter().any(|r| reason.contains(r)) {
+        Severity::Error
+    } else {
+        Severity::Warning
+    }
+}
+
+/// When an event last happened (RFC 3339)
+fn timestamp(event: &Value) -> &str {
+    ["lastTimestamp", "eventTime", "deprecatedLastTimestamp"]
+        .iter()
+        .find_map(|k| event[k].as_str())
+        .or_else(|| event["metadata"]["creationTimestamp"].as_str())
+        .unwrap_or_default()
+}
+
+/// Read a core/v1 Event (or events.k8s.io/v1, which renames some fields)
+pub fn event_line(event: &Value) -> EventLine {
+    let s = |v: &Value| v.as_str().unwrap_or_default().to_string();
+    let time = timestamp(event);
+    let object = if event["involvedObject"].is_object() {
+        &event["involvedObject"]
+    } else {
+        &event["regarding"]
+    };
+    let kind = event["type"].as_str().unwrap_or("Normal");
+    let reason = s(&event["reason"]);
+    EventLine {
+        // 2024-05-01T12:34:56Z -> 12:34:56
+        time: time.get(11..19).unwrap_or(time).to_string(),
+        namespace: s(&event["metadata"]["namespace"]),
+        severity: severity(kind, &reason),
+        object: format!(
+            "{}/{}",
+            object["kind"].as_str().unwrap_or_default().to_lowercase(),
+            object["name"].as_str().unwrap_or_default()
+        ),
+        message: s(if event["message"].is_string() {
+            &event["message"]
+        } else {
+            &event["note"]
+        })
+        .trim()
+        .to_string(),
+        count: event["count"]
+            .as_u64()
+            .or_else(|| event["series"]["count"].as_u64())
+            .unwrap_or(1),
+        reason,
+    }
+}
+
+pub fn format_event(e: &EventLine, all_namespaces: bool, theme: Theme) -> String {
+    let label = match e.severity {
+        Severity::Normal => "Normal",
+        _ => "Warning",
+    };
+    let head = format!("{:<8} {:<20}", label, e.reason);
+    let head = match e.severity {
+        Severity::Error => theme.paint(Role::Error, &head),
+        Severity::Warning => theme.paint(Role::Warning, &head),
+        Severity::Normal => head,
+    };
+    let ns = if all_namespaces {
+        format!("{:<20} ", e.namespace)
+    } else {
+        String::new()
+    };
+    let count = if e.count > 1 {
+        format!(" (x{})", e.count)
+    } else {
+        String::new()
+    };
+    format!(
+        "{} {}{} {:<40} {}{}",
+        e.time, ns, head, e.object, e.message, count
+    )
+}
+
+struct Printer {
+    all_namespaces: bool,
+    json: bool,
+    theme: Theme,
+}
+
+impl Printer {
+    fn print(&self, event: &Value) {
+        let line = event_line(event);
+        if self.json {
+            if let Ok(s) = serde_json::to_string(&line) {
+                println!("{}", s);
+            }
+        } else {
+            println!("{}", format_event(&line, self.all_namespaces, self.theme));
+        }
+    }
+}
+
+fn sleep_backoff(backoff: &mut Duration, started: Instant) {
+    if started.elapsed() > Duration::from_secs(WATCH_TIMEOUT_SECS / 2) {
+        *backoff = Duration::from_secs(1);
+    }
+    std::thread::sleep(*backoff);
+    *backoff = (*backoff * 2).min(MAX_BACKOFF);
+}
+
+/// Tail events until interrupted, in the current session or `context`
+pub fn events(
+    opts: &EventsOptions,
+    context: Option<&str>,
+    namespace: Option<&str>,
+    paths: &[PathBuf],
+) -> Result<()> {
+    let target = super::shell_pod::resolve_target(context, namespace, paths)?;
+    let printer = Printer {
+        all_namespaces: opts.all_namespaces,
+        json: opts.json,
+        theme: theme::stdout(),
+    };
+    let kubeconfig = target.kubeconfig.to_string_lossy().into_owned();
+    match kubeconfig::BearerApi::for_context(
+        &target.context,
+        Some(&kubeconfig),
+        WATCH_TIMEOUT_SECS + 30,
+    )? {
+        Some(api) => {
+            let path = match (&target.namespace, opts.all_namespaces) {
+                (_, true) => "/api/v1/events".to_string(),
+                (Some(ns), false) => format!("/api/v1/namespaces/{}/events", ns),
+                (None, false) => "/api/v1/namespaces/default/events".to_string(),
+            };
+            watch_api(&api, &path, opts.watch_only, &printer)
+        }
+        None => watch_kubectl(&target, opts, &printer),
+    }
+}
+
+/// List the events at `path`, print them oldest first unless `quiet`, and
+/// return the list's resourceVersion
+fn list_events(
+    api: &kubeconfig::BearerApi,
+    path: &str,
+    quiet: bool,
+    printer: &Printer,
+) -> Result<String> {
+    let resp = api
+        .get(path)
+        .send()
+        .map_err(|e| K8pkError::HttpError(e.to_string()))?;
+    if !resp.status().is_success() {
+        return Err(K8pkError::HttpError(format!(
+            "listing events failed: HTTP {}",
+            resp.status()
+        )));
+    }
+    let body: Value = resp
+        .json()
+        .map_err(|e| K8pkError::HttpError(e.to_string()))?;
+    if !quiet {
+        let mut items: Vec<&Value> = body["items"].as_array().into_iter().flatten().collect();
+        items.sort_by_key(|e| timestamp(e));
+        for item in items {
+            printer.print(item);
+        }
+    }
+    Ok(body["metadata"]["resourceVersion"]
+        .as_str()
+        .unwrap_or_default()
+        .to_string())
+}
+
+fn watch_api(
+    api: &kubeconfig::BearerApi,
+    path: &str,
+    watch_only: bool,
+    printer: &Printer,
+) -> Result<()> {
+    let mut version = list_events(api, path, watch_only, printer)?;
+    let mut backoff = Duration::from_secs(1);
+    'watch: loop {
+        let started = Instant::now();
+        let resp = api
+            .get(path)
+            .query(&[
+                ("watch", "1"),
+                ("allowWatchBookmarks", "true"),
+                ("resourceVersion", version.as_str()),
+                ("timeoutSeconds", &WATCH_TIMEOUT_SECS.to_string()),
+            ])
+            .send();
+        let resp = match resp {
+            Ok(r) if r.status().is_success() => r,
+            Ok(r) if r.status() == reqwest::StatusCode::GONE => {
+                version = list_events(api, path, true, printer)?;
+                continue;
+            }
+            Ok(r) => {
+                eprintln!("watch failed: HTTP {}; reconnecting", r.status());
+                sleep_backoff(&mut backoff, started);
+                continue;
+            }
+            Err(e) => {
+                eprintln!("watch failed: {}; reconnecting", e);
+                sleep_backoff(&mut backoff, started);
+                continue;
+            }
+        };
+        for line in BufReader::new(resp).lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            let Ok(event) = serde_json::from_str::<Value>(&line) else {
+                continue;
+            };
+            let object = &event["object"];
+            match event["type"].as_str() {
+                Some("ADDED") | Some("MODIFIED") => printer.print(object),
+                Some("ERROR") if object["code"].as_u64() == Some(410) => {
+                    // Our resourceVersion is too old: relist without replaying
+                    version = list_events(api, path, true, printer)?;
+                    continue 'watch;
+                }
+                _ => {}
+            }
+            if let Some(v) = object["metadata"]["resourceVersion"].as_str() {
+                version = v.to_string();
+            }
+        }
+        // A watch that ran its full timeout ends normally; reconnect at once
+        if started.elapsed() < Duration::from_secs(WATCH_TIMEOUT_SECS) {
+            sleep_backoff(&mut backoff, started);
+        }
+    }
+}
+
+fn watch_kubectl(
+    target: &super::shell_pod::Target,
+    opts: &EventsOptions,
+    printer: &Printer,
+) -> Result<()> {
+    let cli = kubeconfig::find_fast_cli()?;
+    let mut watch_only = opts.watch_only;
+    let mut backoff = Duration::from_secs(1);
+    loop {
+        let mut cmd = Command::new(&cli);
+        cmd.env("KUBECONFIG", &target.kubeconfig);
+        if let Some(path) = crate::config::hinted_path() {
+            cmd.env("PATH", path);
+        }
+        cmd.args([
+            "get",
+            "events",
+            "--watch",
+            "--output-watch-events",
+            "-o",
+            "json",
+        ]);
+        if opts.all_namespaces {
+            cmd.arg("--all-namespaces");
+        } else if let Some(ns) = &target.namespace {
+            cmd.args(["-n", ns]);
+        }
+        if watch_only {
+            cmd.arg("--watch-only");
+        }
+        let started = Instant::now();
+        let mut child = cmd.stdout(Stdio::piped()).spawn()?;
+        if let Some(stdout) = child.stdout.take() {
+            let stream = serde_json::Deserializer::from_reader(BufReader::new(stdout));
+            for event in stream.into_iter::<Value>().map_while(|e| e.ok()) {
+                if matches!(event["type"].as_str(), Some("ADDED") | Some("MODIFIED")) {
+                    printer.print(&event["object"]);
+                }
+            }
+        }
+        let status = child.wait()?;
+        eprintln!(
+            "{} exited ({}); reconnecting",
+            cli,
+            status
+                .code()
+                .map_or("signal".to_string(), |c| format!("code {}", c))
+        );
+        // Don't replay what was already shown
+        watch_only = true;
+        sleep_backoff(&mut backoff, started);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_line() {
+        let event = serde_json::json!({
+            "metadata": {"namespace": "web", "creationTimestamp": "2024-05-01T10:00:00Z"},
+            "involvedObject": {"kind": "Pod", "name": "api-1"},
+            "type": "Warning",
+            "reason": "BackOff",
+            "message": "Back-off restarting failed container\n",
+            "lastTimestamp": "2024-05-01T12:34:56Z",
+            "count": 4
+        });
+        let line = event_line(&event);
+        assert_eq!(line.time, "12:34:56");
+        assert_eq!(line.object, "pod/api-1");
+        assert_eq!(line.severity, Severity::Error);
+        assert_eq!(line.count, 4);
+        let text = format_event(&line, false, Theme::Mono);
+        assert!(text.starts_with("12:34:56 Warning  BackOff"));
+        assert!(text.ends_with("Back-off restarting failed container (x4)"));
+        assert!(format_event(&line, true, Theme::Default).contains("web"));
+        assert!(format_event(&line, false, Theme::Default).contains("\x1b[31m"));
+    }
+
+    #[test]
+    fn test_event_line_events_v1() {
+        let event = serde_json::json!({
+            "metadata": {"namespace": "web"},
+            "regarding": {"kind": "Node", "name": "n1"},
+            "type": "Normal",
+            "reason": "Starting",
+            "note": "Starting kubelet.",
+            "eventTime": "2024-05-01T08:00:00.000000Z"
+        });
+        let line = event_line(&event);
+        assert_eq!(line.time, "08:00:00");
+        assert_eq!(line.object, "node/n1");
+        assert_eq!(line.message, "Starting kubelet.");
+        assert_eq!(line.severity, Severity::Normal);
+        assert_eq!(severity("Warning", "FailedScheduling"), Severity::Error);
+        assert_eq!(severity("Warning", "Rebooted"), Severity::Warning);
+    }
+}
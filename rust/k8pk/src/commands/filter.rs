@@ -0,0 +1,364 @@
+//! Context selection shared by listings (`contexts`, `which`), the pickers,
+//! and every command taking a context name: alias expansion, name patterns,
+//! cluster-type filters, sort orders, and the "not found, did you mean" error.
+
+use super::organize::{context_rows, ContextRow};
+pub use crate::cli::SortKey;
+use crate::error::{K8pkError, Result};
+use crate::kubeconfig;
+use std::path::PathBuf;
+
+/// Match contexts by pattern with layered fallback:
+///
+/// 1. Exact match
+/// 2. Glob match (if pattern contains *, ?, [)
+/// 3. Substring match (case-insensitive)
+///
+/// This allows `k8pk ctx dev` to match `gke_myproject_us-east1_dev-cluster`.
+pub fn match_pattern(pattern: &str, contexts: &[String]) -> Vec<String> {
+    let is_glob = pattern.contains('*') || pattern.contains('?') || pattern.contains('[');
+
+    // 1. Exact match (always tried first)
+    if !is_glob && contexts.contains(&pattern.to_string()) {
+        return vec![pattern.to_string()];
+    }
+
+    // 2. Glob match (only if pattern has glob metacharacters)
+    if is_glob {
+        let glob = match globset::Glob::new(pattern) {
+            Ok(g) => g.compile_matcher(),
+            Err(_) => return vec![],
+        };
+        let matches: Vec<String> = contexts
+            .iter()
+            .filter(|ctx| glob.is_match(ctx.as_str()))
+            .cloned()
+            .collect();
+        if !matches.is_empty() {
+            return matches;
+        }
+        return vec![];
+    }
+
+    // 3. Substring match (case-insensitive) -- only for non-glob patterns
+    let lower_pattern = pattern.to_lowercase();
+    let matches: Vec<String> = contexts
+        .iter()
+        .filter(|ctx| ctx.to_lowercase().contains(&lower_pattern))
+        .cloned()
+        .collect();
+
+    matches
+}
+
+/// Contexts `name` stands for: an alias from the k8pk config is expanded
+/// first (its target may itself be a glob), then matched with
+/// [`match_pattern`]
+pub fn expand_context(name: &str, all: &[String]) -> Vec<String> {
+    let config = crate::config::load().unwrap_or_default();
+    expand_context_with(&config, name, all)
+}
+
+fn expand_context_with(
+    config: &crate::config::K8pkConfig,
+    name: &str,
+    all: &[String],
+) -> Vec<String> {
+    match_pattern(&crate::config::resolve_alias_with(config, name), all)
+}
+
+/// The one context `name` refers to (see [`expand_context`]), for commands
+/// that must not prompt; several matches are an error listing them
+pub fn unique_context(name: &str, all: &[String]) -> Result<String> {
+    let mut matches = expand_context(name, all);
+    match matches.len() {
+        0 => Err(no_match_error(&crate::config::resolve_alias(name), all)),
+        1 => Ok(matches.remove(0)),
+        _ => Err(ambiguous_error(name, &matches)),
+    }
+}
+
+/// Like [`unique_context`], but several matches open the context picker
+/// narrowed to them when one can be shown
+pub fn resolve_context(cfg: &kubeconfig::KubeConfig, name: &str) -> Result<String> {
+    let all = cfg.context_names();
+    let matches = expand_context(name, &all);
+    if matches.len() > 1 && crate::prompt::can_prompt() {
+        eprintln!("'{}' matched {} contexts:", name, matches.len());
+        return super::select_context(cfg, matches);
+    }
+    unique_context(name, &all)
+}
+
+fn ambiguous_error(pattern: &str, matches: &[String]) -> K8pkError {
+    K8pkError::AmbiguousContext {
+        pattern: pattern.to_string(),
+        matches: matches
+            .iter()
+            .map(|m| format!("    - {}", m))
+            .collect::<Vec<_>>()
+            .join("\n"),
+    }
+}
+
+/// Error for a pattern that matched no context, with close names as suggestions
+pub fn no_match_error(pattern: &str, all: &[String]) -> K8pkError {
+    let suggestions = crate::error::closest_matches(pattern, all, 3);
+    if suggestions.is_empty() {
+        K8pkError::ContextNotFound(pattern.to_string())
+    } else {
+        K8pkError::ContextNotFoundSuggestions {
+            pattern: pattern.to_string(),
+            suggestions: suggestions
+                .iter()
+                .map(|s| format!("    - {}", s))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
+    }
+}
+
+/// `--filter` / `--type` for context listings
+#[derive(Debug, Clone, Default)]
+pub struct ContextFilter {
+    /// Name pattern with [`match_pattern`] semantics
+    pub pattern: Option<String>,
+    /// Cluster type as reported by `k8pk which` (eks, gke, ocp, aks, rancher, k8s)
+    pub cluster_type: Option<String>,
+    /// Tag from the `contexts:` settings in the k8pk config
+    pub tag: Option<String>,
+}
+
+impl ContextFilter {
+    /// Names passing the pattern (or the alias it names), in their original
+    /// order (all when unset). A pattern that matches nothing is an error.
+    pub fn names(&self, all: &[String]) -> Result<Vec<String>> {
+        let Some(pattern) = self.pattern.as_deref() else {
+            return Ok(all.to_vec());
+        };
+        let matched = expand_context(pattern, all);
+        if matched.is_empty() {
+            return Err(no_match_error(pattern, all));
+        }
+        Ok(all
+            .iter()
+            .filter(|n| matched.contains(n))
+            .cloned()
+            .collect())
+    }
+
+    /// `names` carrying the tag (all when unset). A tag on no context is an
+    /// error.
+    pub fn tagged(&self, names: Vec<String>) -> Result<Vec<String>> {
+        let Some(tag) = self.tag.as_deref() else {
+            return Ok(names);
+        };
+        let config = crate::config::load().unwrap_or_default();
+        let tagged: Vec<String> = names
+            .into_iter()
+            .filter(|n| {
+                crate::config::context_settings_with(&config, n)
+                    .tags
+                    .iter()
+                    .any(|t| t == tag)
+            })
+            .collect();
+        if tagged.is_empty() {
+            return Err(K8pkError::InvalidArgument(format!(
+                "no contexts tagged '{}' (set tags under `contexts:` in the k8pk config)",
+                tag
+            )));
+        }
+        Ok(tagged)
+    }
+
+    fn keeps(&self, row: &ContextRow) -> bool {
+        self.cluster_type
+            .as_deref()
+            .is_none_or(|t| t.eq_ignore_ascii_case(row.cluster_type))
+    }
+}
+
+/// Order rows by `key`. Names and types sort alphabetically, files by path,
+/// last use most recent first (never-used last); ties fall back to the name.
+pub fn sort_rows(rows: &mut [ContextRow], key: SortKey) {
+    rows.sort_by(|a, b| {
+        let primary = match key {
+            SortKey::Name => std::cmp::Ordering::Equal,
+            SortKey::Type => a.cluster_type.cmp(b.cluster_type),
+            SortKey::File => a.source.cmp(&b.source),
+            SortKey::LastUsed => b.last_used.cmp(&a.last_used),
+        };
+        primary.then_with(|| a.context.cmp(&b.context))
+    });
+}
+
+/// Rows for `contexts` / `which`: pattern, then type, then sort (kubeconfig
+/// order when `sort` is None)
+pub fn select_context_rows(
+    paths: &[PathBuf],
+    filter: &ContextFilter,
+    sort: Option<SortKey>,
+) -> Result<Vec<ContextRow>> {
+    let all = kubeconfig::load_merged(paths)?.context_names();
+    if all.is_empty() {
+        return Err(K8pkError::NoContexts);
+    }
+    let names = filter.tagged(filter.names(&all)?)?;
+    let mut rows: Vec<ContextRow> = context_rows(paths, Some(&names))?
+        .into_iter()
+        .filter(|r| filter.keeps(r))
+        .collect();
+    if let Some(key) = sort {
+        sort_rows(&mut rows, key);
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_pattern_exact() {
+        let contexts = vec!["dev".to_string(), "prod".to_string(), "staging".to_string()];
+        assert_eq!(match_pattern("dev", &contexts), vec!["dev"]);
+        assert_eq!(
+            match_pattern("nonexistent", &contexts),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn test_match_pattern_wildcard_prefix() {
+        let contexts = vec![
+            "dev-cluster".to_string(),
+            "dev-local".to_string(),
+            "prod-cluster".to_string(),
+        ];
+        let matched = match_pattern("dev-*", &contexts);
+        assert_eq!(matched.len(), 2);
+        assert!(matched.contains(&"dev-cluster".to_string()));
+        assert!(matched.contains(&"dev-local".to_string()));
+    }
+
+    #[test]
+    fn test_match_pattern_wildcard_middle() {
+        let contexts = vec![
+            "us-east-1-prod".to_string(),
+            "us-west-2-prod".to_string(),
+            "eu-west-1-dev".to_string(),
+        ];
+        let matched = match_pattern("us-*-prod", &contexts);
+        assert_eq!(matched.len(), 2);
+    }
+
+    #[test]
+    fn test_match_pattern_case_insensitive_substring() {
+        let contexts = vec![
+            "production-cluster".to_string(),
+            "staging-cluster".to_string(),
+            "dev".to_string(),
+        ];
+        // Case-insensitive substring fallback
+        let matched = match_pattern("Production", &contexts);
+        assert_eq!(matched, vec!["production-cluster"]);
+    }
+
+    #[test]
+    fn test_match_pattern_no_match() {
+        let contexts = vec!["dev".to_string(), "staging".to_string()];
+        let matched = match_pattern("nonexistent", &contexts);
+        assert!(matched.is_empty());
+    }
+
+    fn row(context: &str, cluster_type: &'static str, last_used: Option<u64>) -> ContextRow {
+        ContextRow {
+            context: context.to_string(),
+            friendly_name: context.to_string(),
+            cluster_type,
+            server: None,
+            namespace: None,
+            source: Some(PathBuf::from(format!("/kube/{}", cluster_type))),
+            last_used,
+            current: false,
+            tls: kubeconfig::TlsMode::SystemCa,
+            peek: None,
+        }
+    }
+
+    #[test]
+    fn test_sort_rows() {
+        let mut rows = vec![
+            row("b", "gke", Some(10)),
+            row("a", "gke", None),
+            row("c", "eks", Some(20)),
+        ];
+        let names = |rows: &[ContextRow]| -> Vec<String> {
+            rows.iter().map(|r| r.context.clone()).collect()
+        };
+        sort_rows(&mut rows, SortKey::Name);
+        assert_eq!(names(&rows), ["a", "b", "c"]);
+        sort_rows(&mut rows, SortKey::Type);
+        assert_eq!(names(&rows), ["c", "a", "b"]);
+        sort_rows(&mut rows, SortKey::LastUsed);
+        assert_eq!(names(&rows), ["c", "b", "a"]);
+        sort_rows(&mut rows, SortKey::File);
+        assert_eq!(names(&rows), ["c", "a", "b"]);
+    }
+
+    #[test]
+    fn test_expand_context_aliases() {
+        let all = vec![
+            "prod-eks".to_string(),
+            "dev".to_string(),
+            "prod-gke".to_string(),
+        ];
+        let config = crate::config::K8pkConfig {
+            aliases: Some(
+                [("p", "prod-*"), ("d", "dev")]
+                    .into_iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect(),
+            ),
+            ..Default::default()
+        };
+        assert_eq!(
+            expand_context_with(&config, "p", &all),
+            ["prod-eks", "prod-gke"]
+        );
+        assert_eq!(expand_context_with(&config, "d", &all), ["dev"]);
+        assert_eq!(expand_context_with(&config, "gke", &all), ["prod-gke"]);
+        assert!(matches!(
+            ambiguous_error("prod-*", &expand_context_with(&config, "p", &all)),
+            K8pkError::AmbiguousContext { matches, .. } if matches.contains("- prod-gke")
+        ));
+    }
+
+    #[test]
+    fn test_filter_pattern_and_type() {
+        let all = vec![
+            "prod-eks".to_string(),
+            "dev".to_string(),
+            "prod-gke".to_string(),
+        ];
+        let filter = ContextFilter {
+            pattern: Some("prod-*".into()),
+            cluster_type: Some("EKS".into()),
+            ..Default::default()
+        };
+        assert_eq!(filter.names(&all).unwrap(), ["prod-eks", "prod-gke"]);
+        assert!(filter.keeps(&row("prod-eks", "eks", None)));
+        assert!(!filter.keeps(&row("prod-gke", "gke", None)));
+        assert!(ContextFilter::default().keeps(&row("x", "k8s", None)));
+
+        let missing = ContextFilter {
+            pattern: Some("prdo-eks".into()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            missing.names(&all),
+            Err(K8pkError::ContextNotFoundSuggestions { .. })
+        ));
+    }
+}
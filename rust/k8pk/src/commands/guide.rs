@@ -13,10 +13,12 @@ Common tasks
   Pick cluster, open a shell (default)           k8pk          or  kpick  (shell alias)
   Switch context (named)                       k8pk ctx NAME   or  kctx NAME
   Previous context / namespace                 k8pk ctx -      /  k8pk ns -
+  Back / forward through this shell's switches k8pk back [N]   /  k8pk forward [N]
   List contexts (with source files)            k8pk contexts --path
   Remove a dead / unwanted context             k8pk rm NAME    (auto-finds kubeconfig file)
   Pick context to remove (multi-select)        k8pk rm
   Skip confirm when scripting                  k8pk rm NAME --yes
+  Undo the last rm / rename                    k8pk undo
   TLS issues (one shot)                        k8pk ctx NAME --insecure
   TLS for whole class of clusters              ~/.config/k8pk/config.yaml → insecure_contexts:
   Multi-shell / tmux sessions                  k8pk sessions
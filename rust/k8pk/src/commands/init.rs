@@ -0,0 +1,365 @@
+//! `k8pk init <shell>`: shell integration printed for `eval` (or appended to
+//! the shell rc file with `--install`). It wraps `k8pk ctx` / `ns` / `back` /
+//! `forward` / `clean` so they change the current shell, loads completions,
+//! and keeps `$K8PK_PROMPT` up to date for the prompt.
+
+use crate::error::{K8pkError, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Env var the integration exports so `k8pk doctor` can tell it is loaded
+pub const SHELL_INTEGRATION_ENV: &str = "K8PK_SHELL_INTEGRATION";
+
+/// Shared by bash and zsh; `__SHELL__` is replaced with the shell name
+const POSIX_SCRIPT: &str = r#"# k8pk shell integration (__SHELL__). Load it from your rc file with:
+#   eval "$(k8pk init __SHELL__)"
+export K8PK_SHELL_INTEGRATION=__SHELL__
+
+# Run k8pk and eval its stdout (export statements) in this shell
+_k8pk_eval() {
+  local out
+  out="$(command k8pk "$@")" || return $?
+  eval "$out"
+}
+
+# `k8pk ctx`, `ns`, `back`, `forward`, and `clean` switch this shell instead of
+# spawning a subshell. An explicit -o/--output (or --help) runs k8pk as is.
+k8pk() {
+  case "${1:-}" in
+    ctx|ns|back|forward|clean)
+      local arg
+      for arg in "$@"; do
+        case "$arg" in
+          -o|-o*|--output|--output=*|-h|--help) command k8pk "$@"; return $? ;;
+        esac
+      done
+      _k8pk_eval "$@" -o env
+      ;;
+    *) command k8pk "$@" ;;
+  esac
+}
+
+kpick()  { _k8pk_eval pick --output env "$@"; }
+kctx()   { if [ $# -ge 2 ]; then k8pk ctx "$1" --namespace "$2"; else k8pk ctx "$@"; fi; }
+kns()    { k8pk ns "$@"; }
+kprev()  { k8pk ctx -; }
+kclean() { k8pk clean; }
+
+# [context:namespace:depth] for the prompt, with ":expired" once the
+# session_ttl recorded in the kubeconfig ran out; also kept in $K8PK_PROMPT
+_k8pk_prompt() {
+  if [ -n "${K8PK_CONTEXT:-}" ]; then
+    local prompt="[${K8PK_CONTEXT_DISPLAY:-$K8PK_CONTEXT}" expires
+    if [ -n "${K8PK_NAMESPACE:-}" ]; then
+      prompt="${prompt}:${K8PK_NAMESPACE}"
+    fi
+    if [ -n "${K8PK_DEPTH:-}" ] && [ "${K8PK_DEPTH}" -gt 0 ]; then
+      prompt="${prompt}:${K8PK_DEPTH}"
+    fi
+    if [ -f "${KUBECONFIG%%:*}" ]; then
+      expires="$(sed -n 's/^ *expires-at: *\([0-9][0-9]*\).*/\1/p' "${KUBECONFIG%%:*}")"
+      if [ -n "$expires" ] && [ "$(date +%s)" -ge "$expires" ]; then
+        prompt="${prompt}:expired"
+      fi
+    fi
+    echo "${prompt}]"
+  fi
+}
+_k8pk_prompt_hook() { K8PK_PROMPT="$(_k8pk_prompt)"; }
+
+# Drop this shell from `k8pk sessions` when it exits
+_k8pk_exit_cleanup() {
+  if [ -n "${K8PK_CONTEXT:-}" ]; then
+    command k8pk sessions deregister 2>/dev/null || true
+  fi
+}
+"#;
+
+const BASH_HOOKS: &str = r#"
+case ";${PROMPT_COMMAND:-};" in
+  *";_k8pk_prompt_hook;"*) ;;
+  *) PROMPT_COMMAND="_k8pk_prompt_hook${PROMPT_COMMAND:+;$PROMPT_COMMAND}" ;;
+esac
+# Chain onto an EXIT trap already set instead of replacing it
+_k8pk_exit_trap="$(trap -p EXIT)"
+_k8pk_exit_trap="${_k8pk_exit_trap#trap -- }"
+_k8pk_exit_trap="${_k8pk_exit_trap% EXIT}"
+case "$_k8pk_exit_trap" in
+  *_k8pk_exit_cleanup*) ;;
+  '') trap _k8pk_exit_cleanup EXIT ;;
+  *) eval "trap -- \"_k8pk_exit_cleanup; \"$_k8pk_exit_trap EXIT" ;;
+esac
+unset _k8pk_exit_trap
+
+eval "$(command k8pk completions bash 2>/dev/null)"
+_k8pk_complete_first() {
+  local cur="${COMP_WORDS[COMP_CWORD]}" dyn
+  [ "$COMP_CWORD" -eq 1 ] || return 0
+  dyn="$(command k8pk __complete bash "$1" "$cur" 2>/dev/null)"
+  local IFS=$'\n'
+  COMPREPLY=($(compgen -W "$dyn" -- "$cur"))
+}
+_k8pk_complete_kctx() { _k8pk_complete_first ctx; }
+_k8pk_complete_kns() { _k8pk_complete_first ns; }
+complete -F _k8pk_complete_kctx kctx
+complete -F _k8pk_complete_kns kns
+"#;
+
+const ZSH_HOOKS: &str = r#"
+autoload -Uz add-zsh-hook
+add-zsh-hook precmd _k8pk_prompt_hook
+add-zsh-hook zshexit _k8pk_exit_cleanup
+
+# Completions need compinit; skip them quietly when it has not run
+if (( $+functions[compdef] )); then
+  eval "$(command k8pk completions zsh 2>/dev/null)"
+  _k8pk_complete_first() {
+    (( CURRENT == 2 )) || return 0
+    local -a candidates
+    candidates=(${(f)"$(command k8pk __complete zsh "$1" "${words[CURRENT]}" 2>/dev/null)"})
+    _describe 'value' candidates
+  }
+  _k8pk_complete_kctx() { _k8pk_complete_first ctx; }
+  _k8pk_complete_kns() { _k8pk_complete_first ns; }
+  compdef _k8pk_complete_kctx kctx
+  compdef _k8pk_complete_kns kns
+fi
+"#;
+
+const FISH_SCRIPT: &str = r#"# k8pk shell integration (fish). Load it from config.fish with:
+#   k8pk init fish | source
+set -gx K8PK_SHELL_INTEGRATION fish
+
+# Run k8pk and source its stdout (set -gx statements) in this shell
+function _k8pk_eval
+  set -l out (command k8pk $argv); or return $status
+  string join \n -- $out | source
+end
+
+# `k8pk ctx`, `ns`, `back`, `forward`, and `clean` switch this shell instead of
+# spawning a subshell. An explicit -o/--output (or --help) runs k8pk as is.
+function k8pk
+  switch "$argv[1]"
+    case ctx ns back forward clean
+      for arg in $argv
+        switch $arg
+          case -o '-o*' --output '--output=*' -h --help
+            command k8pk $argv
+            return $status
+        end
+      end
+      _k8pk_eval $argv -o env
+    case '*'
+      command k8pk $argv
+  end
+end
+
+function kpick
+  _k8pk_eval pick --output env $argv
+end
+function kctx --wraps 'k8pk ctx'
+  if test (count $argv) -ge 2
+    k8pk ctx $argv[1] --namespace $argv[2]
+  else
+    k8pk ctx $argv
+  end
+end
+function kns --wraps 'k8pk ns'
+  k8pk ns $argv
+end
+function kprev
+  k8pk ctx -
+end
+function kclean
+  k8pk clean
+end
+
+# [context:namespace:depth] for the prompt, with ":expired" once the
+# session_ttl recorded in the kubeconfig ran out; also kept in $K8PK_PROMPT
+function _k8pk_prompt
+  if test -n "$K8PK_CONTEXT"
+    set -l prompt "["(test -n "$K8PK_CONTEXT_DISPLAY"; and echo $K8PK_CONTEXT_DISPLAY; or echo $K8PK_CONTEXT)
+    if test -n "$K8PK_NAMESPACE"
+      set prompt "$prompt:$K8PK_NAMESPACE"
+    end
+    if test -n "$K8PK_DEPTH"; and test "$K8PK_DEPTH" -gt 0
+      set prompt "$prompt:$K8PK_DEPTH"
+    end
+    set -l kubeconfig (string split -m1 : -- "$KUBECONFIG")[1]
+    if test -f "$kubeconfig"
+      set -l expires (string replace -rf '^\s*expires-at:\s*(\d+).*' '$1' < $kubeconfig)
+      if test -n "$expires[1]"; and test (date +%s) -ge $expires[1]
+        set prompt "$prompt:expired"
+      end
+    end
+    echo "$prompt]"
+  end
+end
+function _k8pk_prompt_hook --on-event fish_prompt
+  set -g K8PK_PROMPT (_k8pk_prompt)
+end
+
+# Drop this shell from `k8pk sessions` when it exits
+function _k8pk_exit_cleanup --on-event fish_exit
+  if test -n "$K8PK_CONTEXT"
+    command k8pk sessions deregister 2>/dev/null; or true
+  end
+end
+
+command k8pk completions fish 2>/dev/null | source
+"#;
+
+#[derive(Debug, serde::Serialize)]
+pub struct InitInstallResult {
+    pub shell: String,
+    pub rc_file: PathBuf,
+    pub line: String,
+    /// False when the rc file already loaded the integration
+    pub installed: bool,
+}
+
+/// Shell from the argument, else `$SHELL` (bash when unknown)
+pub fn resolve_init_shell(shell: Option<&str>) -> Result<&'static str> {
+    let name = match shell {
+        Some(s) => s.to_string(),
+        None => std::env::var("SHELL")
+            .ok()
+            .and_then(|s| s.rsplit('/').next().map(str::to_string))
+            .unwrap_or_default(),
+    };
+    match name.as_str() {
+        "bash" => Ok("bash"),
+        "zsh" => Ok("zsh"),
+        "fish" => Ok("fish"),
+        _ if shell.is_none() => Ok("bash"),
+        other => Err(K8pkError::UnsupportedShell(other.to_string())),
+    }
+}
+
+/// Integration script for `shell` (bash, zsh, or fish)
+pub fn init_script(shell: &str) -> Result<String> {
+    match shell {
+        "bash" => Ok(POSIX_SCRIPT.replace("__SHELL__", "bash") + BASH_HOOKS),
+        "zsh" => Ok(POSIX_SCRIPT.replace("__SHELL__", "zsh") + ZSH_HOOKS),
+        "fish" => Ok(FISH_SCRIPT.to_string()),
+        other => Err(K8pkError::UnsupportedShell(other.to_string())),
+    }
+}
+
+/// Line that loads the integration from the rc file
+fn init_line(shell: &str) -> String {
+    match shell {
+        "fish" => "k8pk init fish | source".to_string(),
+        _ => format!("eval \"$(k8pk init {})\"", shell),
+    }
+}
+
+fn rc_file(shell: &str, home: &Path) -> PathBuf {
+    match shell {
+        "zsh" => std::env::var_os("ZDOTDIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| home.to_path_buf())
+            .join(".zshrc"),
+        "fish" => std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| home.join(".config"))
+            .join("fish/config.fish"),
+        _ => home.join(".bashrc"),
+    }
+}
+
+/// `init --install` / `alias --install`: add the init line to the rc file
+/// of `shell` (default from `$SHELL`) and report the result
+pub fn run_init_install(shell: Option<&str>, quiet: bool) -> Result<()> {
+    let shell = resolve_init_shell(shell)?;
+    let home = dirs_next::home_dir().ok_or(K8pkError::NoHomeDir)?;
+    let result = install_init_into(shell, &rc_file(shell, &home))?;
+    if crate::render::structured(false) {
+        println!("{}", crate::render::to_string(&result)?);
+    } else if !quiet {
+        print_install_summary(&result);
+    }
+    Ok(())
+}
+
+fn install_init_into(shell: &str, path: &Path) -> Result<InitInstallResult> {
+    let line = init_line(shell);
+    let existing = fs::read_to_string(path).unwrap_or_default();
+    let installed = !existing.contains(&format!("k8pk init {}", shell));
+    if installed {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut content = existing;
+        if !content.is_empty() && !content.ends_with('\n') {
+            content.push('\n');
+        }
+        content.push_str(&format!("\n# k8pk shell integration\n{}\n", line));
+        fs::write(path, content)?;
+    }
+    Ok(InitInstallResult {
+        shell: shell.to_string(),
+        rc_file: path.to_path_buf(),
+        line,
+        installed,
+    })
+}
+
+fn print_install_summary(result: &InitInstallResult) {
+    if result.installed {
+        println!("Added `{}` to {}", result.line, result.rc_file.display());
+        eprintln!("Open a new shell (or run the line above) to load it.");
+    } else {
+        println!(
+            "{} already loads k8pk init {}",
+            result.rc_file.display(),
+            result.shell
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scripts_wrap_ctx_and_load_completions() {
+        for shell in ["bash", "zsh", "fish"] {
+            let script = init_script(shell).unwrap();
+            assert!(script.contains("-o env"), "{shell}");
+            assert!(script.contains(&format!("k8pk completions {}", shell)));
+            assert!(script.contains("K8PK_SHELL_INTEGRATION"));
+            assert!(!script.contains("__SHELL__"));
+        }
+        let bash = init_script("bash").unwrap();
+        assert!(bash.contains("PROMPT_COMMAND"));
+        assert!(bash.contains("trap -p EXIT"));
+        assert!(init_script("zsh").unwrap().contains("add-zsh-hook precmd"));
+        assert!(init_script("tcsh").is_err());
+    }
+
+    #[test]
+    fn install_appends_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let rc = dir.path().join("fish/config.fish");
+        let first = install_init_into("fish", &rc).unwrap();
+        assert!(first.installed);
+        let second = install_init_into("fish", &rc).unwrap();
+        assert!(!second.installed);
+        let content = fs::read_to_string(&rc).unwrap();
+        assert_eq!(content.matches("k8pk init fish | source").count(), 1);
+
+        let bashrc = dir.path().join(".bashrc");
+        fs::write(&bashrc, "export FOO=1").unwrap();
+        install_init_into("bash", &bashrc).unwrap();
+        let content = fs::read_to_string(&bashrc).unwrap();
+        assert!(content.starts_with("export FOO=1\n\n# k8pk shell integration\n"));
+        assert!(content.ends_with("eval \"$(k8pk init bash)\"\n"));
+    }
+
+    #[test]
+    fn resolve_shell_names() {
+        assert_eq!(resolve_init_shell(Some("zsh")).unwrap(), "zsh");
+        assert!(resolve_init_shell(Some("csh")).is_err());
+    }
+}
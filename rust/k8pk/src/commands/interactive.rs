@@ -1,10 +1,11 @@
 //! Interactive picker commands
 
 use crate::error::{K8pkError, Result};
+use crate::i18n::{tr, tr_with};
 use crate::kubeconfig::{self, KubeConfig};
-use inquire::Select;
-use std::collections::{HashMap, HashSet};
-use std::io::{self, IsTerminal};
+use crate::prompt::Ask;
+use inquire::MultiSelect;
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 /// Interactive context picker (no namespace selection)
 pub fn pick_context_namespace(
@@ -22,217 +23,301 @@ pub fn pick_context_namespace(
     }
 }
 
-/// Pick a cluster (grouped contexts) and optionally a namespace
-fn pick_cluster_with_namespace(
-    cfg: &KubeConfig,
-    _kubeconfig_env: Option<&str>,
-    filter: Option<&str>,
-) -> Result<(String, Option<String>)> {
-    if !io::stdin().is_terminal() {
-        return Err(K8pkError::NoTty);
-    }
-
-    let current = cfg.current_context.as_deref();
+/// Last entry of the cluster list: leave clusters_only mode for this pick
+const SHOW_ALL_CONTEXTS: &str = "Show all contexts...";
+/// Last entry of a cluster's context list: pick from the cluster's namespaces
+const BROWSE_NAMESPACES: &str = "Browse live namespaces...";
 
-    // Group contexts by cluster server URL (primary) or base cluster name (fallback)
-    // This ensures contexts pointing to the same cluster are grouped together
-    let mut cluster_groups: HashMap<String, Vec<(&str, Option<String>)>> = HashMap::new();
-    let mut seen_contexts = HashSet::new();
-
-    let allowed: Option<HashSet<String>> = filter.map(|f| {
-        let names = cfg.context_names();
-        super::context::match_pattern(f, &names)
-            .into_iter()
-            .collect()
-    });
+/// Contexts (name, namespace set in the context) by base cluster name
+type ClusterGroups = BTreeMap<String, Vec<(String, Option<String>)>>;
 
+/// Group contexts by [`kubeconfig::extract_base_cluster_name`], keeping only
+/// `allowed` ones when given. Contexts from the same logical cluster end up
+/// together even if they point at different servers (like Rancher Prime
+/// node endpoints).
+fn cluster_groups(cfg: &KubeConfig, allowed: Option<&HashSet<String>>) -> ClusterGroups {
+    let mut groups = ClusterGroups::new();
+    let mut seen = HashSet::new();
     for ctx in &cfg.contexts {
-        if !seen_contexts.insert(ctx.name.clone()) {
-            continue; // Skip duplicates
-        }
-        if let Some(ref allow) = allowed {
-            if !allow.contains(&ctx.name) {
-                continue;
-            }
+        if !seen.insert(ctx.name.as_str()) || allowed.is_some_and(|a| !a.contains(&ctx.name)) {
+            continue;
         }
+        let server_url =
+            kubeconfig::extract_context_refs(&ctx.rest)
+                .ok()
+                .and_then(|(cluster_name, _)| {
+                    cfg.clusters
+                        .iter()
+                        .find(|c| c.name == cluster_name)
+                        .and_then(|c| kubeconfig::extract_server_url_from_cluster(&c.rest))
+                });
+        let key = kubeconfig::extract_base_cluster_name(&ctx.name, server_url.as_deref());
+        groups.entry(key).or_default().push((
+            ctx.name.clone(),
+            kubeconfig::get_namespace_for_context(cfg, &ctx.name),
+        ));
+    }
+    groups
+}
 
-        // Get server URL for better cluster detection
-        let server_url = if let Ok((cluster_name, _)) = kubeconfig::extract_context_refs(&ctx.rest)
-        {
-            cfg.clusters
+/// Cluster list entries: `name  (N contexts)`, `*` on the current cluster
+fn cluster_items(groups: &ClusterGroups, current: Option<&str>) -> Vec<(String, String)> {
+    groups
+        .iter()
+        .map(|(key, contexts)| {
+            let noun = if contexts.len() == 1 {
+                "context"
+            } else {
+                "contexts"
+            };
+            let marker = if contexts
                 .iter()
-                .find(|c| c.name == cluster_name)
-                .and_then(|c| kubeconfig::extract_server_url_from_cluster(&c.rest))
-        } else {
-            None
-        };
-
-        // For clusters_only mode, always use base cluster name extraction for grouping
-        // This ensures contexts from the same logical cluster are grouped together,
-        // even if they point to different physical nodes/servers (like Rancher Prime)
-        let cluster_key = kubeconfig::extract_base_cluster_name(&ctx.name, server_url.as_deref());
-
-        // Extract namespace from context if present
-        let namespace = if let serde_yaml_ng::Value::Mapping(map) = &ctx.rest {
-            if let Some(serde_yaml_ng::Value::Mapping(ctx_map)) =
-                map.get(serde_yaml_ng::Value::String("context".to_string()))
+                .any(|(name, _)| Some(name.as_str()) == current)
             {
-                ctx_map
-                    .get(serde_yaml_ng::Value::String("namespace".to_string()))
-                    .and_then(|v| v.as_str())
-                    .map(|s| s.to_string())
+                " *"
             } else {
-                None
-            }
-        } else {
-            None
-        };
+                ""
+            };
+            (
+                format!("{}  ({} {}){}", key, contexts.len(), noun, marker),
+                key.clone(),
+            )
+        })
+        .collect()
+}
+
+/// Context list entries of one cluster: `name  (ns: x)` when the context
+/// sets a namespace
+fn context_items(contexts: &[(String, Option<String>)]) -> Vec<(String, usize)> {
+    contexts
+        .iter()
+        .enumerate()
+        .map(|(i, (name, ns))| match ns {
+            Some(ns) => (format!("{}  (ns: {})", name, ns), i),
+            None => (name.clone(), i),
+        })
+        .collect()
+}
 
-        cluster_groups
-            .entry(cluster_key)
-            .or_default()
-            .push((&ctx.name, namespace));
+/// Two-level pick for clusters_only mode: a cluster first, then one of its
+/// contexts or a namespace listed live from the cluster. "Show all contexts"
+/// falls back to the flat context picker.
+fn pick_cluster_with_namespace(
+    cfg: &KubeConfig,
+    kubeconfig_env: Option<&str>,
+    filter: Option<&str>,
+) -> Result<(String, Option<String>)> {
+    if !crate::prompt::can_prompt() {
+        return Err(K8pkError::NoTty);
     }
 
-    if cluster_groups.is_empty() {
+    let allowed: Option<HashSet<String>> = filter.map(|f| {
+        let names = cfg.context_names();
+        super::match_pattern(f, &names).into_iter().collect()
+    });
+    let groups = cluster_groups(cfg, allowed.as_ref());
+
+    if groups.is_empty() {
         if let Some(f) = filter {
             let all = cfg.context_names();
-            return Err(filter_not_found(f, &all));
+            return Err(super::no_match_error(f, &all));
         }
         return Err(K8pkError::NoContexts);
     }
 
-    // Build cluster list with display names
-    // Use the cluster key (base cluster name) as the display name
-    // This ensures we show the grouped cluster name, not individual context names
-    let mut cluster_choices: Vec<(String, String)> = cluster_groups
-        .keys()
-        .map(|cluster_key| (cluster_key.clone(), cluster_key.clone()))
-        .collect();
-
-    // Sort by display name
-    cluster_choices.sort_by(|a, b| a.1.cmp(&b.1));
+    let selected_key = if groups.len() == 1 {
+        let key = groups.keys().next().cloned().unwrap_or_default();
+        if !crate::render::quiet() {
+            eprintln!("Auto-selected the only matching cluster: {}", key);
+        }
+        key
+    } else {
+        let clusters = cluster_items(&groups, cfg.current_context.as_deref());
+        let mut items: Vec<String> = clusters.iter().map(|(item, _)| item.clone()).collect();
+        items.push(SHOW_ALL_CONTEXTS.to_string());
+        let selected = crate::picker::select(&tr("pick-cluster"), items, None)?;
+        if selected == SHOW_ALL_CONTEXTS {
+            return Ok((pick_context(cfg, filter)?, None));
+        }
+        clusters
+            .into_iter()
+            .find(|(item, _)| *item == selected)
+            .map(|(_, key)| key)
+            .ok_or_else(|| K8pkError::Other("Selected cluster not found".into()))?
+    };
 
-    // Auto-select when filter leaves one cluster
-    if cluster_choices.len() == 1 {
-        let selected_key = cluster_choices[0].0.clone();
-        let cluster_contexts = &cluster_groups[&selected_key];
-        let default_ns = cluster_contexts
-            .iter()
-            .find_map(|(_, ns)| ns.clone())
-            .or(Some("default".to_string()));
-        let selected_context = cluster_contexts
-            .first()
-            .map(|(name, _)| (*name).to_string())
-            .ok_or_else(|| K8pkError::Other("No contexts found for cluster".into()))?;
-        eprintln!("Auto-selected the only matching cluster: {}", selected_key);
-        return Ok((selected_context, default_ns));
+    let contexts = &groups[&selected_key];
+    let entries = context_items(contexts);
+    let mut items: Vec<String> = entries.iter().map(|(item, _)| item.clone()).collect();
+    items.push(BROWSE_NAMESPACES.to_string());
+    let selected = crate::picker::select(
+        &tr_with("pick-context-in", &[("cluster", selected_key.as_str())]),
+        items,
+        None,
+    )?;
+
+    if selected == BROWSE_NAMESPACES {
+        // Any context of the cluster will do; the namespace overrides its own
+        let (context, _) = &contexts[0];
+        let namespace = pick_namespace(context, kubeconfig_env, false)?;
+        return Ok((context.clone(), Some(namespace)));
     }
-
-    // Mark current cluster if any (use same logic as grouping)
-    let current_cluster_key = current.map(|ctx| kubeconfig::extract_base_cluster_name(ctx, None));
-
-    let cluster_display: Vec<String> = cluster_choices
-        .iter()
-        .map(|(key, display)| {
-            if current_cluster_key
-                .as_ref()
-                .map(|k| k == key)
-                .unwrap_or(false)
-            {
-                format!("{} *", display)
-            } else {
-                display.clone()
-            }
-        })
-        .collect();
-
-    // Select cluster
-    let selected_display = Select::new("Select cluster:", cluster_display)
-        .with_page_size(20)
-        .prompt()
-        .map_err(|_| K8pkError::Cancelled)?;
-
-    let selected_key = cluster_choices
-        .iter()
-        .find(|(_, display)| {
-            display
-                == selected_display
-                    .strip_suffix(" *")
-                    .unwrap_or(&selected_display)
-        })
-        .map(|(key, _)| key.clone())
-        .ok_or_else(|| K8pkError::Other("Selected cluster not found".into()))?;
-
-    // Get contexts for this cluster
-    let cluster_contexts = &cluster_groups[&selected_key];
-
-    // Find default namespace (context with namespace set, or use "default")
-    // Note: We no longer call list_namespaces here as it can hang for unreachable clusters
-    let default_ns = cluster_contexts
+    let index = entries
         .iter()
-        .find_map(|(_, ns)| ns.clone())
-        .or(Some("default".to_string()));
+        .find(|(item, _)| *item == selected)
+        .map(|(_, i)| *i)
+        .ok_or_else(|| K8pkError::Other("Selected context not found".into()))?;
+    Ok(contexts[index].clone())
+}
 
-    // Use first context from the cluster group
-    let selected_context = cluster_contexts
-        .first()
-        .map(|(name, _)| *name)
-        .ok_or_else(|| K8pkError::Other("No contexts found for cluster".into()))?;
+/// Interactive namespace picker for a given context. With `details`, each
+/// namespace shows its pod count and phases.
+pub fn pick_namespace(
+    context: &str,
+    kubeconfig_env: Option<&str>,
+    details: bool,
+) -> Result<String> {
+    if !crate::prompt::can_prompt() {
+        return Err(K8pkError::NoTty);
+    }
 
-    // If we have a default namespace, use it; otherwise return None (use context default)
-    Ok((selected_context.to_string(), default_ns))
+    let items = namespace_choices(context, kubeconfig_env, details)?;
+    let selected = crate::picker::select(&tr("pick-namespace"), items, None)?;
+    Ok(strip_recent(&selected))
 }
 
-/// Interactive namespace picker for a given context
-pub fn pick_namespace(context: &str, kubeconfig_env: Option<&str>) -> Result<String> {
-    if !io::stdin().is_terminal() {
+/// Pick several namespaces of `context` at once (recent ones listed first)
+pub fn pick_namespaces(
+    context: &str,
+    kubeconfig_env: Option<&str>,
+    details: bool,
+) -> Result<Vec<String>> {
+    if !crate::prompt::can_prompt() {
         return Err(K8pkError::NoTty);
     }
 
-    let namespaces = kubeconfig::list_namespaces(context, kubeconfig_env)?;
-    if namespaces.is_empty() {
-        return Err(K8pkError::NoNamespaces(context.to_string()));
+    let items = namespace_choices(context, kubeconfig_env, details)?;
+    let selected = MultiSelect::new(&tr("pick-namespaces"), items)
+        .with_help_message(&tr("pick-namespaces-help"))
+        .ask()?;
+    if selected.is_empty() {
+        return Err(K8pkError::Cancelled);
     }
-
-    Select::new("Select namespace:", namespaces)
-        .with_page_size(20) // Better for navigation
-        .prompt()
-        .map_err(|_| K8pkError::Cancelled)
+    Ok(selected.iter().map(|s| strip_recent(s)).collect())
 }
 
-fn filter_not_found(filter: &str, all: &[String]) -> K8pkError {
-    let suggestions = crate::error::closest_matches(filter, all, 3);
-    if suggestions.is_empty() {
-        K8pkError::ContextNotFound(filter.to_string())
-    } else {
-        K8pkError::ContextNotFoundSuggestions {
-            pattern: filter.to_string(),
-            suggestions: suggestions
-                .iter()
-                .map(|s| format!("    - {}", s))
-                .collect::<Vec<_>>()
-                .join("\n"),
+/// Namespaces to offer for `context`, for [`namespace_items`]. Falls back to
+/// the recently used ones when the cluster can't be reached. With `details`,
+/// entries carry pod counts ([`detail_items`]) when the pods can be listed.
+fn namespace_choices(
+    context: &str,
+    kubeconfig_env: Option<&str>,
+    details: bool,
+) -> Result<Vec<String>> {
+    let recent = super::context::get_recent_namespaces(context).unwrap_or_default();
+    let namespaces = match kubeconfig::list_namespaces(context, kubeconfig_env) {
+        Ok(ns) => Some(ns),
+        // Offline: still offer the namespaces used here before
+        Err(e) if !recent.is_empty() => {
+            if !crate::render::quiet() {
+                let reason = e.to_string();
+                eprintln!(
+                    "warning: could not list namespaces ({}); showing recently used ones",
+                    reason.lines().next().unwrap_or_default()
+                );
+            }
+            None
         }
+        Err(e) => return Err(e),
+    };
+    let items = namespace_items(&recent, namespaces.as_deref());
+    if items.is_empty() {
+        return Err(K8pkError::NoNamespaces(context.to_string()));
+    }
+    if !details || namespaces.is_none() {
+        return Ok(items);
     }
+    match kubeconfig::pod_phases_by_namespace(context, kubeconfig_env) {
+        Ok(phases) => Ok(detail_items(&items, &phases)),
+        Err(e) => {
+            if !crate::render::quiet() {
+                let reason = e.to_string();
+                eprintln!(
+                    "warning: could not count pods ({}); showing names only",
+                    reason.lines().next().unwrap_or_default()
+                );
+            }
+            Ok(items)
+        }
+    }
+}
+
+/// Namespace of a picker entry, without the recent marker or pod details
+fn strip_recent(item: &str) -> String {
+    item.split_whitespace().next().unwrap_or(item).to_string()
+}
+
+/// Picker entries with a pod summary aligned after each name, e.g.
+/// `web  (recent)   12 pods: 10 Running, 2 Pending`
+fn detail_items(
+    items: &[String],
+    phases: &HashMap<String, BTreeMap<String, usize>>,
+) -> Vec<String> {
+    let width = items.iter().map(|i| i.len()).max().unwrap_or(0);
+    items
+        .iter()
+        .map(|item| {
+            let summary = match phases.get(&strip_recent(item)) {
+                Some(counts) => {
+                    let total: usize = counts.values().sum();
+                    let parts: Vec<String> = counts
+                        .iter()
+                        .map(|(phase, n)| format!("{} {}", n, phase))
+                        .collect();
+                    let noun = if total == 1 { "pod" } else { "pods" };
+                    format!("{} {}: {}", total, noun, parts.join(", "))
+                }
+                None => "no pods".to_string(),
+            };
+            format!("{:<width$}   {}", item, summary)
+        })
+        .collect()
+}
+
+/// Suffix on recently used namespaces in the picker
+const RECENT_MARKER: &str = "  (recent)";
+
+/// Picker entries: recently used namespaces first (marked, and only those the
+/// cluster still has), then the rest. With no cluster list (`None`), only the
+/// recent ones.
+fn namespace_items(recent: &[String], all: Option<&[String]>) -> Vec<String> {
+    let recent: Vec<&String> = recent
+        .iter()
+        .filter(|r| all.is_none_or(|all| all.contains(r)))
+        .collect();
+    let mut items: Vec<String> = recent
+        .iter()
+        .map(|r| format!("{}{}", r, RECENT_MARKER))
+        .collect();
+    items.extend(
+        all.unwrap_or_default()
+            .iter()
+            .filter(|ns| !recent.contains(ns))
+            .cloned(),
+    );
+    items
 }
 
 /// Pick a context interactively (without namespace selection)
 /// Returns the selected context name (without the " *" marker).
 /// Recent contexts from history are shown at the top for quick access.
-/// Optional `filter` pre-filters via `match_pattern` (exact / glob / substring).
+/// Optional `filter` pre-filters via `expand_context` (alias / exact / glob /
+/// substring).
 pub fn pick_context(cfg: &KubeConfig, filter: Option<&str>) -> Result<String> {
-    if !io::stdin().is_terminal() {
+    if !crate::prompt::can_prompt() {
         return Err(K8pkError::NoTty);
     }
 
-    let current = cfg.current_context.as_deref();
-
-    // Load recent history to prioritize those contexts
-    let recent = super::context::get_history()
-        .map(|(ctxs, _)| ctxs)
-        .unwrap_or_default();
-
     // Deduplicate context names
     let mut seen = HashSet::new();
     let all_names: Vec<String> = cfg
@@ -252,9 +337,9 @@ pub fn pick_context(cfg: &KubeConfig, filter: Option<&str>) -> Result<String> {
     }
 
     let all_names = if let Some(f) = filter {
-        let matched = super::context::match_pattern(f, &all_names);
+        let matched = super::expand_context(f, &all_names);
         match matched.len() {
-            0 => return Err(filter_not_found(f, &all_names)),
+            0 => return Err(super::no_match_error(f, &all_names)),
             1 => {
                 let name = matched.into_iter().next().unwrap();
                 eprintln!("Auto-selected the only matching context: {}", name);
@@ -272,6 +357,18 @@ pub fn pick_context(cfg: &KubeConfig, filter: Option<&str>) -> Result<String> {
         eprintln!("Auto-selected the only available context: {}", name);
         return Ok(name);
     }
+    select_context(cfg, all_names)
+}
+
+/// Choose one of `names` in the context picker: recent contexts first, the
+/// current one marked
+pub fn select_context(cfg: &KubeConfig, all_names: Vec<String>) -> Result<String> {
+    let current = cfg.current_context.as_deref();
+
+    // Load recent history to prioritize those contexts
+    let recent = super::context::get_history()
+        .map(|(ctxs, _)| ctxs)
+        .unwrap_or_default();
 
     // Build ordered list: recent contexts first (that still exist), then the rest
     let all_set: HashSet<&str> = all_names.iter().map(|s| s.as_str()).collect();
@@ -301,11 +398,167 @@ pub fn pick_context(cfg: &KubeConfig, filter: Option<&str>) -> Result<String> {
         })
         .collect();
 
-    let selected = Select::new("Select context:", contexts)
-        .with_page_size(20)
-        .prompt()
-        .map_err(|_| K8pkError::Cancelled)?;
+    let preview = crate::picker::which_preview();
+    let config = crate::config::load().unwrap_or_default();
+    let groups = config
+        .pick
+        .as_ref()
+        .and_then(|p| p.group_by)
+        .map(|by| group_contexts(cfg, &config, &ordered, &contexts, by))
+        .filter(|groups| groups.len() > 1);
+    let selected = match groups {
+        Some(groups) => {
+            crate::picker::select_grouped(&tr("pick-context"), &groups, Some(&preview))?
+        }
+        None => crate::picker::select(&tr("pick-context"), contexts, Some(&preview))?,
+    };
 
     // Strip the " *" marker if present
     Ok(selected.strip_suffix(" *").unwrap_or(&selected).to_string())
 }
+
+/// Split the picker lines (`labels[i]` shows `names[i]`) into groups for
+/// `pick.group_by`, keeping their order within each group. Groups are sorted
+/// by name, with contexts that fit none (no tags, unknown file) last; a
+/// context with several tags is listed under each.
+fn group_contexts(
+    cfg: &KubeConfig,
+    config: &crate::config::K8pkConfig,
+    names: &[String],
+    labels: &[String],
+    by: crate::config::PickGroupBy,
+) -> Vec<(String, Vec<String>)> {
+    use crate::config::PickGroupBy;
+    const NONE: &str = "(none)";
+    let mut groups: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (name, label) in names.iter().zip(labels) {
+        let keys = match by {
+            PickGroupBy::Type => vec![kubeconfig::detect_cluster_type(
+                name,
+                kubeconfig::get_server_for_context(cfg, name).as_deref(),
+            )
+            .to_string()],
+            PickGroupBy::File => vec![cfg
+                .sources
+                .get(name)
+                .map(|p| super::display_path(p))
+                .unwrap_or_else(|| NONE.to_string())],
+            PickGroupBy::Tag => {
+                let tags = crate::config::context_settings_with(config, name).tags;
+                if tags.is_empty() {
+                    vec![NONE.to_string()]
+                } else {
+                    tags
+                }
+            }
+        };
+        for key in keys {
+            groups.entry(key).or_default().push(label.clone());
+        }
+    }
+    let none = groups.remove(NONE);
+    let mut groups: Vec<(String, Vec<String>)> = groups.into_iter().collect();
+    groups.extend(none.map(|items| (NONE.to_string(), items)));
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_namespace_items_recent_first() {
+        let recent = vec!["web".to_string(), "gone".to_string()];
+        let all = vec!["api".to_string(), "default".to_string(), "web".to_string()];
+        assert_eq!(
+            namespace_items(&recent, Some(&all)),
+            vec!["web  (recent)", "api", "default"]
+        );
+        // Offline: history only
+        assert_eq!(
+            namespace_items(&recent, None),
+            vec!["web  (recent)", "gone  (recent)"]
+        );
+    }
+
+    #[test]
+    fn test_detail_items_summarise_pods() {
+        let items = vec!["web  (recent)".to_string(), "empty".to_string()];
+        let phases = HashMap::from([(
+            "web".to_string(),
+            BTreeMap::from([("Pending".to_string(), 1), ("Running".to_string(), 2)]),
+        )]);
+        let detailed = detail_items(&items, &phases);
+        assert_eq!(
+            detailed,
+            [
+                "web  (recent)   3 pods: 1 Pending, 2 Running",
+                "empty           no pods"
+            ]
+        );
+        assert_eq!(strip_recent(&detailed[0]), "web");
+    }
+
+    #[test]
+    fn test_group_contexts() {
+        use crate::config::PickGroupBy;
+        let cfg: KubeConfig = serde_yaml_ng::from_str(
+            "clusters:\n- name: e\n  cluster: {server: \"https://x.eks.amazonaws.com\"}\n\
+             contexts:\n- name: arn:aws:eks:eu:1:cluster/a\n  context: {cluster: e}\n\
+             - name: plain\n  context: {cluster: none}\n",
+        )
+        .unwrap();
+        let config: crate::config::K8pkConfig =
+            serde_yaml_ng::from_str("contexts:\n  \"arn:*\":\n    tags: [aws, prod]\n").unwrap();
+        let names = vec![
+            "plain".to_string(),
+            "arn:aws:eks:eu:1:cluster/a".to_string(),
+        ];
+        let labels = vec!["plain *".to_string(), names[1].clone()];
+
+        let by_type = group_contexts(&cfg, &config, &names, &labels, PickGroupBy::Type);
+        assert_eq!(by_type[0], ("eks".to_string(), vec![names[1].clone()]));
+        assert_eq!(by_type[1].1, ["plain *"]);
+
+        let by_tag = group_contexts(&cfg, &config, &names, &labels, PickGroupBy::Tag);
+        let keys: Vec<&str> = by_tag.iter().map(|(k, _)| k.as_str()).collect();
+        assert_eq!(keys, ["aws", "prod", "(none)"]);
+        assert_eq!(by_tag[2].1, ["plain *"]);
+
+        let by_file = group_contexts(&cfg, &config, &names, &labels, PickGroupBy::File);
+        assert_eq!(by_file, [("(none)".to_string(), labels.clone())]);
+    }
+
+    #[test]
+    fn test_cluster_groups() {
+        let cfg: KubeConfig = serde_yaml_ng::from_str(
+            "current-context: test-cluster-01-dc01-hw-k8s-controller-02\n\
+             contexts:\n\
+             - name: test-cluster-01\n  context: {cluster: c, namespace: web}\n\
+             - name: test-cluster-01-dc01-hw-k8s-controller-02\n  context: {cluster: c}\n\
+             - name: test-cluster-02\n  context: {cluster: c}\n",
+        )
+        .unwrap();
+        let groups = cluster_groups(&cfg, None);
+        assert_eq!(
+            groups["test-cluster-01"],
+            [
+                ("test-cluster-01".to_string(), Some("web".to_string())),
+                (
+                    "test-cluster-01-dc01-hw-k8s-controller-02".to_string(),
+                    None
+                ),
+            ]
+        );
+
+        let items = cluster_items(&groups, cfg.current_context.as_deref());
+        assert_eq!(items[0].0, "test-cluster-01  (2 contexts) *");
+        assert_eq!(items[1].0, "test-cluster-02  (1 context)");
+        let entries = context_items(&groups["test-cluster-01"]);
+        assert_eq!(entries[0].0, "test-cluster-01  (ns: web)");
+
+        let allowed = HashSet::from(["test-cluster-02".to_string()]);
+        let filtered = cluster_groups(&cfg, Some(&allowed));
+        assert_eq!(filtered.keys().collect::<Vec<_>>(), ["test-cluster-02"]);
+    }
+}
@@ -1,13 +1,18 @@
 //! Kubeconfig file operations: merge, diff, lint, cleanup
 
+use super::{backup_kubeconfig, display_path};
 use crate::config;
 use crate::error::{K8pkError, Result};
-use crate::kubeconfig::{self, KubeConfig};
+use crate::i18n::tr;
+use crate::kubeconfig::{self, KubeConfig, NamedItem};
+use crate::prompt::Ask;
+use crate::theme::Role;
+use crate::yaml_edit;
 use inquire::{Confirm, MultiSelect, Select};
-use std::collections::HashSet;
+use serde_yaml_ng::Value as Yaml;
+use std::collections::{HashMap, HashSet};
 use std::env;
 use std::fs;
-use std::io::{self, IsTerminal};
 use std::path::{Path, PathBuf};
 use std::time::{Duration, SystemTime};
 
@@ -16,9 +21,26 @@ pub struct MergeResult {
     pub files: Vec<PathBuf>,
     pub output: Option<PathBuf>,
     pub overwrite: bool,
+    /// [`MergePrefer::as_str`]
+    pub prefer: &'static str,
     pub yaml: Option<String>,
 }
 
+/// Name of the extension entry recording how a merged file was produced
+pub const MERGE_EXTENSION_NAME: &str = "k8pk.io/merge";
+
+pub use crate::cli::MergePrefer;
+
+impl MergePrefer {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::First => "first",
+            Self::Last => "last",
+            Self::Union => "union",
+        }
+    }
+}
+
 #[derive(Debug, serde::Serialize)]
 pub struct DiffResult {
     pub file1: PathBuf,
@@ -41,6 +63,18 @@ pub struct LintResult {
     pub warnings: usize,
     pub issues: Vec<LintIssue>,
     pub failed: bool,
+    /// Files rewritten by `--fix` (before the issues above were collected)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fixes: Vec<LintFix>,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct LintFix {
+    pub file: PathBuf,
+    /// One line per change, e.g. "removed orphaned cluster: old"
+    pub changes: Vec<String>,
+    pub backup: Option<PathBuf>,
+    pub dry_run: bool,
 }
 
 #[derive(Debug, serde::Serialize)]
@@ -54,11 +88,77 @@ pub struct CleanupResult {
     pub found: bool,
 }
 
-/// Merge multiple kubeconfig files
+/// Combine two YAML values for `--prefer union`.
+/// Mappings merge key by key, lists of named entries merge by name, and
+/// anything else keeps the existing (earlier) value.
+fn union_yaml(existing: Yaml, incoming: Yaml) -> Yaml {
+    match (existing, incoming) {
+        (Yaml::Mapping(mut a), Yaml::Mapping(b)) => {
+            for (k, v) in b {
+                let merged = match a.remove(&k) {
+                    Some(prev) => union_yaml(prev, v),
+                    None => v,
+                };
+                a.insert(k, merged);
+            }
+            Yaml::Mapping(a)
+        }
+        (Yaml::Sequence(mut a), Yaml::Sequence(b)) => {
+            for item in b {
+                let name = item.get("name").cloned();
+                let dup = match &name {
+                    Some(n) => a.iter().any(|x| x.get("name") == Some(n)),
+                    None => a.contains(&item),
+                };
+                if !dup {
+                    a.push(item);
+                }
+            }
+            Yaml::Sequence(a)
+        }
+        (Yaml::Null, incoming) => incoming,
+        (existing, _) => existing,
+    }
+}
+
+/// Fold one file's top-level block into the accumulated value
+fn merge_block(acc: Option<Yaml>, incoming: Option<Yaml>, prefer: MergePrefer) -> Option<Yaml> {
+    match (acc, incoming) {
+        (acc, None) => acc,
+        (None, incoming) => incoming,
+        (Some(acc), Some(incoming)) => Some(match prefer {
+            MergePrefer::First => acc,
+            MergePrefer::Last => incoming,
+            MergePrefer::Union => union_yaml(acc, incoming),
+        }),
+    }
+}
+
+/// Record the merge strategy as a named entry in the top-level extensions list
+fn record_merge_extension(extensions: Option<Yaml>, prefer: MergePrefer) -> Yaml {
+    let mut list = match extensions {
+        Some(Yaml::Sequence(list)) => list,
+        _ => Vec::new(),
+    };
+    list.retain(|e| e.get("name").and_then(|n| n.as_str()) != Some(MERGE_EXTENSION_NAME));
+
+    let mut ext = serde_yaml_ng::Mapping::new();
+    ext.insert("prefer".into(), prefer.as_str().into());
+    let mut entry = serde_yaml_ng::Mapping::new();
+    entry.insert("name".into(), MERGE_EXTENSION_NAME.into());
+    entry.insert("extension".into(), Yaml::Mapping(ext));
+    list.push(Yaml::Mapping(entry));
+    Yaml::Sequence(list)
+}
+
+/// Merge multiple kubeconfig files.
+/// `prefer` decides which file's preferences, extensions, and current-context
+/// survive; contexts, clusters, and users are governed by `overwrite`.
 pub fn merge_files(
     files: &[PathBuf],
     output: Option<&Path>,
     overwrite: bool,
+    prefer: MergePrefer,
 ) -> Result<MergeResult> {
     if files.is_empty() {
         return Err(K8pkError::InvalidArgument("no files specified".into()));
@@ -107,22 +207,30 @@ pub fn merge_files(
             }
         }
 
-        // First-wins for current-context
-        if result.current_context.is_none() && cfg.current_context.is_some() {
+        // Union has no meaning for a scalar, so it keeps the first like `first`
+        if cfg.current_context.is_some()
+            && (result.current_context.is_none() || prefer == MergePrefer::Last)
+        {
             result.current_context = cfg.current_context;
         }
+
+        result.preferences = merge_block(result.preferences.take(), cfg.preferences, prefer);
+        result.extensions = merge_block(result.extensions.take(), cfg.extensions, prefer);
     }
 
+    result.extensions = Some(record_merge_extension(result.extensions.take(), prefer));
     result.ensure_defaults(None);
 
     let yaml = serde_yaml_ng::to_string(&result)?;
 
     if let Some(out) = output {
+        let _lock = kubeconfig::lock_kubeconfig(out)?;
         kubeconfig::write_restricted(out, &yaml)?;
         Ok(MergeResult {
             files: files.to_vec(),
             output: Some(out.to_path_buf()),
             overwrite,
+            prefer: prefer.as_str(),
             yaml: None,
         })
     } else {
@@ -130,6 +238,7 @@ pub fn merge_files(
             files: files.to_vec(),
             output: None,
             overwrite,
+            prefer: prefer.as_str(),
             yaml: Some(yaml),
         })
     }
@@ -176,9 +285,26 @@ pub fn lint(file: Option<&Path>, all_paths: &[PathBuf], strict: bool) -> Result<
         all_paths.to_vec()
     };
 
-    let mut warnings = 0;
-    let mut errors = 0;
     let mut issues = Vec::new();
+    let hints = crate::config::load()
+        .map(|c| crate::config::exec_path_dirs_with(&c))
+        .unwrap_or_default();
+    let theme = crate::theme::stderr();
+    let report = |issue: LintIssue, issues: &mut Vec<LintIssue>| {
+        let role = match issue.level.as_str() {
+            "error" => Role::Error,
+            "warning" => Role::Warning,
+            _ => Role::Muted,
+        };
+        eprintln!(
+            "{}: {}: {}",
+            theme.paint(role, &issue.level),
+            issue.path.display(),
+            issue.message
+        );
+        issues.push(issue);
+    };
+    let mut parsed = Vec::new();
 
     for path in &paths {
         if !path.exists() {
@@ -187,11 +313,15 @@ pub fn lint(file: Option<&Path>, all_paths: &[PathBuf], strict: bool) -> Result<
                 level: "error".into(),
                 message: "file not found".into(),
             });
-            errors += 1;
             continue;
         }
 
-        let content = match fs::read_to_string(path) {
+        let read = if kubeconfig::is_sops_file(path) {
+            crate::encryption::sops_decrypt(path)
+        } else {
+            fs::read_to_string(path).map_err(Into::into)
+        };
+        let content = match read {
             Ok(c) => c,
             Err(e) => {
                 issues.push(LintIssue {
@@ -199,7 +329,6 @@ pub fn lint(file: Option<&Path>, all_paths: &[PathBuf], strict: bool) -> Result<
                     level: "error".into(),
                     message: format!("read error: {}", e),
                 });
-                errors += 1;
                 continue;
             }
         };
@@ -212,96 +341,496 @@ pub fn lint(file: Option<&Path>, all_paths: &[PathBuf], strict: bool) -> Result<
                     level: "error".into(),
                     message: format!("parse error: {}", e),
                 });
-                errors += 1;
                 continue;
             }
         };
 
-        // Check for empty contexts
-        if cfg.contexts.is_empty() {
-            eprintln!("warning: {} has no contexts", path.display());
-            issues.push(LintIssue {
-                path: path.to_path_buf(),
-                level: "warning".into(),
-                message: "file has no contexts".into(),
-            });
-            warnings += 1;
+        for issue in lint_config(path, &cfg)
+            .into_iter()
+            .chain(lint_exec_plugins(path, &cfg, &hints))
+        {
+            report(issue, &mut issues);
         }
+        parsed.push((path.clone(), cfg));
+    }
 
-        // Check for orphaned clusters/users
-        let referenced_clusters: HashSet<_> = cfg
-            .contexts
-            .iter()
-            .filter_map(|c| {
-                kubeconfig::extract_context_refs(&c.rest)
-                    .ok()
-                    .map(|(cluster, _)| cluster)
-            })
-            .collect();
+    for issue in lint_duplicate_contexts(&parsed, kubeconfig::merge_strategy()) {
+        report(issue, &mut issues);
+    }
 
-        let referenced_users: HashSet<_> = cfg
-            .contexts
+    // Info (identical duplicates) counts as neither
+    let count = |level: &str| issues.iter().filter(|i| i.level == level).count();
+    let (errors, warnings) = (count("error"), count("warning"));
+    let failed = errors > 0 || (strict && warnings > 0);
+    Ok(LintResult {
+        errors,
+        warnings,
+        issues,
+        failed,
+        fixes: Vec::new(),
+    })
+}
+
+/// Remove exact duplicates (same name and content) from `items`, keeping the
+/// first, and return the removed names
+fn dedupe_items(items: &mut Vec<NamedItem>) -> Vec<String> {
+    let mut seen: Vec<NamedItem> = Vec::new();
+    let mut removed = Vec::new();
+    items.retain(|item| {
+        if seen
             .iter()
-            .filter_map(|c| {
-                kubeconfig::extract_context_refs(&c.rest)
-                    .ok()
-                    .map(|(_, user)| user)
-            })
-            .collect();
+            .any(|s| s.name == item.name && s.rest == item.rest)
+        {
+            removed.push(item.name.clone());
+            false
+        } else {
+            seen.push(item.clone());
+            true
+        }
+    });
+    removed
+}
 
-        for cluster in &cfg.clusters {
-            if !referenced_clusters.contains(&cluster.name) {
-                eprintln!(
-                    "warning: {} orphaned cluster: {}",
-                    path.display(),
-                    cluster.name
-                );
-                issues.push(LintIssue {
-                    path: path.to_path_buf(),
-                    level: "warning".into(),
-                    message: format!("orphaned cluster: {}", cluster.name),
-                });
-                warnings += 1;
+/// What the other files of the merged KUBECONFIG define and reference. A
+/// cluster or user one file only uses from another is not orphaned, and a
+/// current-context may name a context in another file.
+#[derive(Debug, Default)]
+pub struct OtherRefs {
+    clusters: HashSet<String>,
+    users: HashSet<String>,
+    contexts: HashSet<String>,
+    /// Some file could not be read or parsed, so its references are unknown
+    unknown: bool,
+}
+
+impl OtherRefs {
+    fn add(&mut self, cfg: &KubeConfig) {
+        for ctx in &cfg.contexts {
+            self.contexts.insert(ctx.name.clone());
+            if let Ok((cluster, user)) = kubeconfig::extract_context_refs(&ctx.rest) {
+                self.clusters.insert(cluster);
+                self.users.insert(user);
             }
         }
+    }
+}
 
-        for user in &cfg.users {
-            if !referenced_users.contains(&user.name) {
-                eprintln!("warning: {} orphaned user: {}", path.display(), user.name);
-                issues.push(LintIssue {
-                    path: path.to_path_buf(),
-                    level: "warning".into(),
-                    message: format!("orphaned user: {}", user.name),
-                });
-                warnings += 1;
+/// Apply the `lint --fix` repairs to `cfg`: normalize apiVersion/kind, drop
+/// exact duplicate entries, with `strict` drop contexts whose cluster or
+/// user references a missing file (relative to `base`), then drop orphaned
+/// clusters/users and unset a dangling current-context. References from
+/// `others` count; a file without contexts of its own (credentials for
+/// another file's contexts) keeps its clusters and users, and nothing is
+/// dropped while some file's references are unknown. Returns one line per
+/// change.
+pub fn fix_config(
+    cfg: &mut KubeConfig,
+    base: &Path,
+    strict: bool,
+    others: &OtherRefs,
+) -> Vec<String> {
+    let mut changes = Vec::new();
+    let has_contexts = !cfg.contexts.is_empty();
+
+    if cfg.api_version.as_deref() != Some("v1") {
+        changes.push(match &cfg.api_version {
+            Some(v) => format!("apiVersion: {} -> v1", v),
+            None => "added apiVersion: v1".to_string(),
+        });
+        cfg.api_version = Some("v1".into());
+    }
+    if cfg.kind.as_deref() != Some("Config") {
+        changes.push(match &cfg.kind {
+            Some(k) => format!("kind: {} -> Config", k),
+            None => "added kind: Config".to_string(),
+        });
+        cfg.kind = Some("Config".into());
+    }
+
+    for (section, items) in [
+        ("context", &mut cfg.contexts),
+        ("cluster", &mut cfg.clusters),
+        ("user", &mut cfg.users),
+    ] {
+        for name in dedupe_items(items) {
+            changes.push(format!("removed duplicate {}: {}", section, name));
+        }
+    }
+
+    if strict {
+        let missing = kubeconfig::missing_file_refs(cfg, base);
+        cfg.contexts.retain(|ctx| {
+            let Ok((cluster, user)) = kubeconfig::extract_context_refs(&ctx.rest) else {
+                return true;
+            };
+            let file = missing
+                .get(&("cluster", cluster))
+                .or_else(|| missing.get(&("user", user)));
+            if let Some(file) = file {
+                changes.push(format!(
+                    "removed context {} (missing file: {})",
+                    ctx.name, file
+                ));
+            }
+            file.is_none()
+        });
+    }
+
+    if others.unknown {
+        return changes;
+    }
+
+    if has_contexts {
+        let mut referenced_clusters = others.clusters.clone();
+        let mut referenced_users = others.users.clone();
+        for ctx in &cfg.contexts {
+            if let Ok((cluster, user)) = kubeconfig::extract_context_refs(&ctx.rest) {
+                referenced_clusters.insert(cluster);
+                referenced_users.insert(user);
+            }
+        }
+        cfg.clusters.retain(|c| {
+            let keep = referenced_clusters.contains(&c.name);
+            if !keep {
+                changes.push(format!("removed orphaned cluster: {}", c.name));
+            }
+            keep
+        });
+        cfg.users.retain(|u| {
+            let keep = referenced_users.contains(&u.name);
+            if !keep {
+                changes.push(format!("removed orphaned user: {}", u.name));
             }
+            keep
+        });
+    }
+
+    if let Some(current) = &cfg.current_context {
+        if cfg.find_context(current).is_none() && !others.contexts.contains(current) {
+            changes.push(format!(
+                "unset current-context: {} (no such context)",
+                current
+            ));
+            cfg.current_context = None;
+        }
+    }
+
+    changes
+}
+
+/// Every existing file of `paths`, parsed (`None` when it can't be read or
+/// parsed), keyed by its canonical path
+fn parse_all(paths: &[PathBuf]) -> Vec<(PathBuf, Option<KubeConfig>)> {
+    let mut parsed: Vec<(PathBuf, Option<KubeConfig>)> = Vec::new();
+    for path in paths.iter().filter(|p| p.exists()) {
+        let key = fs::canonicalize(path).unwrap_or_else(|_| path.clone());
+        if parsed.iter().any(|(k, _)| *k == key) {
+            continue;
         }
+        let read = if kubeconfig::is_sops_file(path) {
+            crate::encryption::sops_decrypt(path)
+        } else {
+            fs::read_to_string(path).map_err(Into::into)
+        };
+        let cfg = read
+            .ok()
+            .and_then(|content| serde_yaml_ng::from_str(&content).ok());
+        parsed.push((key, cfg));
+    }
+    parsed
+}
 
-        // Check for current-context reference
-        if let Some(ref current) = cfg.current_context {
-            if !cfg.contexts.iter().any(|c| c.name == *current) {
+/// `k8pk lint --fix`: repair each kubeconfig (see [`fix_config`]), backing
+/// it up first. Orphans are judged against every file of `all_paths`, not
+/// the file alone. Files that can't be parsed or rewritten (SOPS) are left
+/// for lint to report.
+pub fn lint_fix(
+    file: Option<&Path>,
+    all_paths: &[PathBuf],
+    strict: bool,
+    dry_run: bool,
+    backups: &Path,
+) -> Result<Vec<LintFix>> {
+    let paths: Vec<PathBuf> = match file {
+        Some(f) => vec![f.to_path_buf()],
+        None => all_paths.to_vec(),
+    };
+    let known = parse_all(&[all_paths, &paths].concat());
+    let mut fixes = Vec::new();
+    for path in paths.iter().filter(|p| p.exists()) {
+        if kubeconfig::is_sops_file(path) {
+            if !crate::render::quiet() {
                 eprintln!(
-                    "warning: {} current-context not found: {}",
-                    path.display(),
-                    current
+                    "Skipping {} (SOPS-encrypted; edit it with sops)",
+                    display_path(path)
                 );
-                issues.push(LintIssue {
-                    path: path.to_path_buf(),
-                    level: "error".into(),
-                    message: format!("current-context not found: {}", current),
-                });
-                errors += 1;
             }
+            continue;
+        }
+        let _lock = if dry_run {
+            None
+        } else {
+            Some(kubeconfig::lock_kubeconfig(path)?)
+        };
+        let content = fs::read_to_string(path)?;
+        let Ok(original) = serde_yaml_ng::from_str::<KubeConfig>(&content) else {
+            continue;
+        };
+        let mut cfg = original.clone();
+        let key = fs::canonicalize(path).unwrap_or_else(|_| path.clone());
+        let mut others = OtherRefs::default();
+        for (_, other) in known.iter().filter(|(k, _)| *k != key) {
+            match other {
+                Some(other) => others.add(other),
+                None => others.unknown = true,
+            }
+        }
+        let base = path.parent().unwrap_or(Path::new("."));
+        let changes = fix_config(&mut cfg, base, strict, &others);
+        if changes.is_empty() {
+            continue;
+        }
+
+        let mut backup = None;
+        if !dry_run {
+            backup = backup_kubeconfig(path, backups)?;
+            let removed = |old: &[NamedItem], new: &[NamedItem]| -> Vec<String> {
+                old.iter()
+                    .filter(|o| !new.iter().any(|n| n.name == o.name))
+                    .map(|o| o.name.clone())
+                    .collect()
+            };
+            let yaml = yaml_edit::render(&content, &cfg, |text| {
+                let text = yaml_edit::set_top_level(text, "apiVersion", Some("v1"))?;
+                let text = yaml_edit::set_top_level(&text, "kind", Some("Config"))?;
+                let text = yaml_edit::remove_items(
+                    &text,
+                    "contexts",
+                    &removed(&original.contexts, &cfg.contexts),
+                )?;
+                let text = yaml_edit::remove_items(
+                    &text,
+                    "clusters",
+                    &removed(&original.clusters, &cfg.clusters),
+                )?;
+                let text =
+                    yaml_edit::remove_items(&text, "users", &removed(&original.users, &cfg.users))?;
+                if cfg.current_context.is_none() && original.current_context.is_some() {
+                    yaml_edit::set_current_context(&text, None)
+                } else {
+                    Some(text)
+                }
+            })?;
+            kubeconfig::write_restricted(path, &yaml)?;
+        }
+        fixes.push(LintFix {
+            file: path.to_path_buf(),
+            changes,
+            backup,
+            dry_run,
+        });
+    }
+    Ok(fixes)
+}
+
+pub fn print_lint_fixes(fixes: &[LintFix]) {
+    let theme = crate::theme::stderr();
+    for fix in fixes {
+        let verb = if fix.dry_run { "Would fix" } else { "Fixed" };
+        match &fix.backup {
+            Some(bak) => eprintln!(
+                "{} {} (backup: {}):",
+                verb,
+                display_path(&fix.file),
+                display_path(bak)
+            ),
+            None => eprintln!("{} {}:", verb, display_path(&fix.file)),
+        }
+        for change in &fix.changes {
+            eprintln!("  {}", theme.paint(Role::Ok, change));
+        }
+    }
+    if fixes.is_empty() {
+        eprintln!("Nothing to fix");
+    }
+}
+
+/// Check one parsed kubeconfig: missing contexts, dangling or orphaned
+/// cluster/user references, clusters without a usable server, and a
+/// current-context that doesn't exist. `path` is only used to label issues.
+pub fn lint_config(path: &Path, cfg: &KubeConfig) -> Vec<LintIssue> {
+    let mut issues = Vec::new();
+    let mut issue = |level: &str, message: String| {
+        issues.push(LintIssue {
+            path: path.to_path_buf(),
+            level: level.into(),
+            message,
+        })
+    };
+
+    if cfg.contexts.is_empty() {
+        issue("warning", "file has no contexts".into());
+    }
+
+    let mut referenced_clusters = HashSet::new();
+    let mut referenced_users = HashSet::new();
+    for ctx in &cfg.contexts {
+        let Ok((cluster, user)) = kubeconfig::extract_context_refs(&ctx.rest) else {
+            issue(
+                "warning",
+                format!("context {} has no cluster or user", ctx.name),
+            );
+            continue;
+        };
+        // A warning, not an error: kubectl merges KUBECONFIG files, so the
+        // entry may live in another one
+        if cfg.find_cluster(&cluster).is_none() {
+            issue(
+                "warning",
+                format!(
+                    "context {} references missing cluster: {}",
+                    ctx.name, cluster
+                ),
+            );
+        }
+        if cfg.find_user(&user).is_none() {
+            issue(
+                "warning",
+                format!("context {} references missing user: {}", ctx.name, user),
+            );
+        }
+        referenced_clusters.insert(cluster);
+        referenced_users.insert(user);
+    }
+
+    for cluster in &cfg.clusters {
+        if !referenced_clusters.contains(&cluster.name) {
+            issue("warning", format!("orphaned cluster: {}", cluster.name));
+        }
+        match kubeconfig::extract_server_url_from_cluster(&cluster.rest) {
+            None => issue("error", format!("cluster {} has no server", cluster.name)),
+            Some(server) if !server.starts_with("https://") && !server.starts_with("http://") => {
+                issue(
+                    "warning",
+                    format!(
+                        "cluster {} server is not an http(s) URL: {}",
+                        cluster.name, server
+                    ),
+                )
+            }
+            Some(_) => {}
         }
     }
 
-    let failed = errors > 0 || (strict && warnings > 0);
-    Ok(LintResult {
-        errors,
-        warnings,
-        issues,
-        failed,
-    })
+    for user in &cfg.users {
+        if !referenced_users.contains(&user.name) {
+            issue("warning", format!("orphaned user: {}", user.name));
+        }
+    }
+
+    if let Some(ref current) = cfg.current_context {
+        if cfg.find_context(current).is_none() {
+            issue("error", format!("current-context not found: {}", current));
+        }
+    }
+
+    issues
+}
+
+/// Contexts defined in more than one of `files` (in merge order): an error
+/// when the definitions point at different clusters (name or server), a
+/// warning when they differ otherwise, info when they are identical. Each
+/// issue is on the file whose definition loses under `strategy`.
+pub fn lint_duplicate_contexts(
+    files: &[(PathBuf, KubeConfig)],
+    strategy: kubeconfig::MergeStrategy,
+) -> Vec<LintIssue> {
+    // (file, context entry, cluster name, cluster server) per context name
+    type Definition<'a> = (&'a Path, &'a NamedItem, Option<String>, Option<String>);
+    let mut by_name: Vec<(&str, Vec<Definition>)> = Vec::new();
+    for (path, cfg) in files {
+        for ctx in &cfg.contexts {
+            let cluster = kubeconfig::extract_context_refs(&ctx.rest)
+                .ok()
+                .map(|(c, _)| c);
+            let server = cluster
+                .as_deref()
+                .and_then(|c| cfg.find_cluster(c))
+                .and_then(|c| kubeconfig::extract_server_url_from_cluster(&c.rest));
+            let def = (path.as_path(), ctx, cluster, server);
+            match by_name.iter_mut().find(|(n, _)| *n == ctx.name) {
+                Some((_, defs)) if defs.iter().all(|d| d.0 != path.as_path()) => defs.push(def),
+                Some(_) => {}
+                None => by_name.push((ctx.name.as_str(), vec![def])),
+            }
+        }
+    }
+
+    let mut issues = Vec::new();
+    for (name, defs) in by_name.iter().filter(|(_, defs)| defs.len() > 1) {
+        let winner = match strategy {
+            kubeconfig::MergeStrategy::Last => defs.len() - 1,
+            _ => 0,
+        };
+        let (win_path, win_ctx, win_cluster, win_server) = &defs[winner];
+        let outcome = match strategy {
+            kubeconfig::MergeStrategy::Strict => {
+                "merge strategy strict refuses these files".to_string()
+            }
+            _ => format!("{} wins", win_path.display()),
+        };
+        for (i, (path, ctx, cluster, server)) in defs.iter().enumerate() {
+            if i == winner {
+                continue;
+            }
+            let (level, what) = if cluster != win_cluster || server != win_server {
+                (
+                    "error",
+                    format!(
+                        "points at cluster {} here and {} in {}",
+                        cluster.as_deref().unwrap_or("(none)"),
+                        win_cluster.as_deref().unwrap_or("(none)"),
+                        win_path.display()
+                    ),
+                )
+            } else if ctx.rest != win_ctx.rest {
+                (
+                    "warning",
+                    format!("differs from the one in {}", win_path.display()),
+                )
+            } else {
+                (
+                    "info",
+                    format!("is identical to the one in {}", win_path.display()),
+                )
+            };
+            issues.push(LintIssue {
+                path: path.to_path_buf(),
+                level: level.into(),
+                message: format!("duplicate context {} {}; {}", name, what, outcome),
+            });
+        }
+    }
+    issues
+}
+
+/// Warn about exec credential plugins that can't be found, looking in
+/// `hints` (`exec_path_hints`) before PATH. Kept out of [`lint_config`]: a
+/// plugin missing on this machine doesn't make the entries invalid.
+pub fn lint_exec_plugins(path: &Path, cfg: &KubeConfig, hints: &[PathBuf]) -> Vec<LintIssue> {
+    let base = path.parent().unwrap_or(Path::new("."));
+    kubeconfig::exec_plugins(cfg)
+        .into_iter()
+        .filter(|(_, command)| kubeconfig::find_exec_plugin(command, hints, base).is_none())
+        .map(|(user, command)| LintIssue {
+            path: path.to_path_buf(),
+            level: "warning".into(),
+            message: format!(
+                "user {} exec plugin not found: {} (install it or add its directory to exec_path_hints)",
+                user, command
+            ),
+        })
+        .collect()
 }
 
 /// Cleanup old generated kubeconfig files
@@ -313,10 +842,10 @@ pub fn cleanup_generated(
     from_file: Option<&Path>,
     allowed_contexts: &[String],
 ) -> Result<CleanupResult> {
-    let home = dirs_next::home_dir().ok_or(K8pkError::NoHomeDir)?;
-    let base = home.join(".local/share/k8pk");
+    let base = crate::storage::data_dir()?;
+    let dirs = crate::storage::isolated_dirs();
 
-    if !base.exists() {
+    if dirs.is_empty() {
         return Ok(CleanupResult {
             removed: Vec::new(),
             skipped: 0,
@@ -347,7 +876,16 @@ pub fn cleanup_generated(
     let mut removed = Vec::new();
     let mut skipped = 0;
 
-    for entry in fs::read_dir(&base)? {
+    // With session_storage: runtime, plain copies left in the persistent
+    // directory are leftovers from before the switch
+    let migrated = crate::storage::runtime_active(&config::load().unwrap_or_default());
+
+    let mut entries = Vec::new();
+    for dir in &dirs {
+        entries.extend(fs::read_dir(dir)?);
+    }
+
+    for entry in entries {
         let entry = entry?;
         let path = entry.path();
 
@@ -356,6 +894,7 @@ pub fn cleanup_generated(
         }
 
         let filename = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        let filename = filename.trim_end_matches(crate::encryption::SEALED_SUFFIX);
         if !filename.ends_with(".yaml") && !filename.ends_with(".yml") {
             continue;
         }
@@ -366,13 +905,26 @@ pub fn cleanup_generated(
 
         let base_name = filename.trim_end_matches(".yaml").trim_end_matches(".yml");
         let ctx_part = base_name.split('_').next().unwrap_or(base_name);
+        // Recorded origin when present; otherwise guess from the filename
+        // ({context}.yaml or {context}_{namespace}.yaml)
+        let origin_allowed = (orphaned || from_file.is_some())
+            && match crate::provenance::read_file(&path) {
+                Some(p) => allowed_contexts.contains(&p.context),
+                None => allowed_sanitized.contains(ctx_part),
+            };
 
-        if from_file.is_some() && !allowed_sanitized.contains(ctx_part) {
+        if from_file.is_some() && !origin_allowed {
             skipped += 1;
             continue;
         }
 
-        let should_remove = if all {
+        let leftover = migrated
+            && path.parent() == Some(base.as_path())
+            && !path
+                .to_string_lossy()
+                .ends_with(crate::encryption::SEALED_SUFFIX);
+
+        let should_remove = if all || leftover {
             true
         } else {
             let metadata = entry.metadata()?;
@@ -382,12 +934,7 @@ pub fn cleanup_generated(
             let is_old = modified < cutoff;
 
             // Check orphaned if requested
-            // Filename format: {context}.yaml or {context}_{namespace}.yaml
-            let is_orphaned = if orphaned {
-                !allowed_sanitized.contains(ctx_part)
-            } else {
-                false
-            };
+            let is_orphaned = orphaned && !origin_allowed;
 
             is_old || is_orphaned
         };
@@ -404,6 +951,9 @@ pub fn cleanup_generated(
         }
     }
 
+    // Plaintext copies of encrypted kubeconfigs that no live session uses
+    removed.extend(crate::encryption::sweep_live_copies(dry_run)?);
+
     Ok(CleanupResult {
         removed,
         skipped,
@@ -450,48 +1000,29 @@ pub fn print_merge_summary(result: &MergeResult) {
 }
 
 pub fn print_diff_summary(result: &DiffResult, diff_only: bool) {
+    let theme = crate::theme::stdout();
     if !result.only_in_1.is_empty() {
         println!("Only in {}:", result.file1.display());
         for name in &result.only_in_1 {
-            println!("  - {}", name);
+            println!("  {}", theme.paint(Role::Error, &format!("- {}", name)));
         }
     }
     if !result.only_in_2.is_empty() {
         println!("Only in {}:", result.file2.display());
         for name in &result.only_in_2 {
-            println!("  + {}", name);
+            println!("  {}", theme.paint(Role::Ok, &format!("+ {}", name)));
         }
     }
     if !diff_only && !result.in_both.is_empty() {
         println!("In both ({} contexts):", result.in_both.len());
         for name in &result.in_both {
-            println!("  = {}", name);
+            println!("  {}", theme.paint(Role::Muted, &format!("= {}", name)));
         }
     }
 }
 
 // --- Context manipulation operations (moved from main.rs) ---
 
-/// Create a timestamped backup of a kubeconfig file before destructive operations.
-/// Returns the backup path, or None if the source file doesn't exist.
-pub fn backup_kubeconfig(file_path: &Path) -> Result<Option<PathBuf>> {
-    if !file_path.exists() {
-        return Ok(None);
-    }
-    let timestamp = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_secs();
-    let file_name = file_path.file_name().unwrap_or_default().to_string_lossy();
-    let backup_name = format!("{}.bak.{}", file_name, timestamp);
-    let backup_path = file_path
-        .parent()
-        .unwrap_or(Path::new("."))
-        .join(backup_name);
-    fs::copy(file_path, &backup_path)?;
-    Ok(Some(backup_path))
-}
-
 #[derive(Debug, serde::Serialize)]
 pub struct RemoveContextResult {
     pub file: PathBuf,
@@ -507,6 +1038,157 @@ pub struct RenameContextResult {
     pub old_name: String,
     pub new_name: String,
     pub dry_run: bool,
+    pub aliases_updated: Vec<String>,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct RenamedContext {
+    pub old_name: String,
+    pub new_name: String,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct BulkRenameResult {
+    pub file: PathBuf,
+    pub dry_run: bool,
+    pub renames: Vec<RenamedContext>,
+    pub unchanged: usize,
+    pub current_context: Option<String>,
+    pub aliases_updated: Vec<String>,
+}
+
+/// How bulk rename derives new context names
+pub enum RenameRule {
+    /// sed-style substitution: `s/REGEX/REPLACEMENT/[gi]`
+    Pattern {
+        regex: regex::Regex,
+        replacement: String,
+        global: bool,
+    },
+    /// Template with `{type}`, `{cluster}`, `{name}`, `{namespace}`, `{user}`
+    Template(String),
+}
+
+impl RenameRule {
+    /// Parse a sed-style `s/REGEX/REPLACEMENT/FLAGS` expression.
+    /// Any delimiter works (`s|a|b|`); `\1` and `&` in the replacement refer to groups.
+    pub fn parse_pattern(expr: &str) -> Result<Self> {
+        let invalid = |why: &str| {
+            K8pkError::InvalidArgument(format!(
+                "invalid --pattern '{}': {} (expected s/REGEX/REPLACEMENT/[gi])",
+                expr, why
+            ))
+        };
+        let rest = expr
+            .strip_prefix('s')
+            .ok_or_else(|| invalid("must start with 's'"))?;
+        let mut chars = rest.chars();
+        let delim = chars.next().ok_or_else(|| invalid("missing delimiter"))?;
+        if delim.is_alphanumeric() || delim == '\\' {
+            return Err(invalid("delimiter must be punctuation"));
+        }
+
+        // Split on unescaped delimiters; `\<delim>` becomes a literal delimiter
+        let mut fields = vec![String::new()];
+        let mut escaped = false;
+        for c in chars {
+            if escaped {
+                if c != delim {
+                    fields.last_mut().expect("non-empty").push('\\');
+                }
+                fields.last_mut().expect("non-empty").push(c);
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == delim {
+                fields.push(String::new());
+            } else {
+                fields.last_mut().expect("non-empty").push(c);
+            }
+        }
+        if escaped {
+            fields.last_mut().expect("non-empty").push('\\');
+        }
+        if fields.len() != 3 {
+            return Err(invalid("wrong number of delimiters"));
+        }
+
+        let flags = &fields[2];
+        if let Some(bad) = flags.chars().find(|c| !matches!(c, 'g' | 'i')) {
+            return Err(invalid(&format!("unknown flag '{}'", bad)));
+        }
+        let regex = regex::RegexBuilder::new(&fields[0])
+            .case_insensitive(flags.contains('i'))
+            .build()
+            .map_err(|e| invalid(&e.to_string()))?;
+
+        Ok(Self::Pattern {
+            regex,
+            replacement: sed_replacement(&fields[1]),
+            global: flags.contains('g'),
+        })
+    }
+
+    fn apply(&self, ctx: &kubeconfig::NamedItem, cfg: &KubeConfig) -> String {
+        match self {
+            Self::Pattern {
+                regex,
+                replacement,
+                global,
+            } => {
+                let limit = if *global { 0 } else { 1 };
+                regex
+                    .replacen(&ctx.name, limit, replacement.as_str())
+                    .into_owned()
+            }
+            Self::Template(template) => {
+                let (cluster_ref, user) =
+                    kubeconfig::extract_context_refs(&ctx.rest).unwrap_or_default();
+                let server = cfg
+                    .clusters
+                    .iter()
+                    .find(|c| c.name == cluster_ref)
+                    .and_then(|c| kubeconfig::extract_server_url_from_cluster(&c.rest));
+                let cluster_type = kubeconfig::detect_cluster_type(&ctx.name, server.as_deref());
+                let namespace = ctx
+                    .rest
+                    .get("context")
+                    .and_then(|c| c.get("namespace"))
+                    .and_then(|n| n.as_str())
+                    .unwrap_or("");
+                template
+                    .replace("{type}", cluster_type)
+                    .replace(
+                        "{cluster}",
+                        &kubeconfig::friendly_context_name(&ctx.name, cluster_type),
+                    )
+                    .replace("{name}", &ctx.name)
+                    .replace("{namespace}", namespace)
+                    .replace("{user}", &user)
+            }
+        }
+    }
+}
+
+/// Translate sed replacement syntax (`\1`, `&`) into the regex crate's (`${1}`, `${0}`)
+fn sed_replacement(repl: &str) -> String {
+    let mut out = String::new();
+    let mut chars = repl.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(d) if d.is_ascii_digit() => out.push_str(&format!("${{{}}}", d)),
+                Some('&') => out.push('&'),
+                Some('$') => out.push_str("$$"),
+                Some(other) => out.push(other),
+                None => out.push('\\'),
+            },
+            '&' => out.push_str("${0}"),
+            '$' => out.push_str("$$"),
+            other => out.push(other),
+        }
+    }
+    out
 }
 
 #[derive(Debug, serde::Serialize)]
@@ -525,15 +1207,26 @@ pub fn remove_contexts_from_file(
     interactive: bool,
     remove_orphaned: bool,
     dry_run: bool,
+    backups: &Path,
 ) -> Result<RemoveContextResult> {
     if !file_path.exists() {
         return Err(K8pkError::KubeconfigNotFound(file_path.to_path_buf()));
     }
+    kubeconfig::ensure_editable(file_path)?;
+
+    // Serialise with other k8pk writers of this file until we are done
+    let _lock = if dry_run {
+        None
+    } else {
+        Some(kubeconfig::lock_kubeconfig(file_path)?)
+    };
 
     // Backup before destructive operation
     if !dry_run {
-        if let Some(bak) = backup_kubeconfig(file_path)? {
-            eprintln!("Backup saved to {}", bak.display());
+        if let Some(bak) = backup_kubeconfig(file_path, backups)? {
+            if !crate::render::quiet() {
+                eprintln!("Backup saved to {}", bak.display());
+            }
         }
     }
 
@@ -552,9 +1245,7 @@ pub fn remove_contexts_from_file(
                 dry_run,
             });
         }
-        MultiSelect::new("Select contexts to remove:", names)
-            .prompt()
-            .map_err(|_| K8pkError::Cancelled)?
+        MultiSelect::new(&tr("pick-contexts-remove"), names).ask()?
     } else if let Some(ctx) = context {
         vec![ctx.to_string()]
     } else {
@@ -625,7 +1316,11 @@ pub fn remove_contexts_from_file(
     }
 
     if !dry_run {
-        let yaml = serde_yaml_ng::to_string(&cfg)?;
+        let yaml = yaml_edit::render(&content, &cfg, |text| {
+            let text = yaml_edit::remove_items(text, "contexts", &removed_contexts)?;
+            let text = yaml_edit::remove_items(&text, "clusters", &removed_clusters)?;
+            yaml_edit::remove_items(&text, "users", &removed_users)
+        })?;
         kubeconfig::write_restricted(file_path, &yaml)?;
     }
 
@@ -648,15 +1343,26 @@ pub fn rename_context_in_file(
     old_name: &str,
     new_name: &str,
     dry_run: bool,
+    backups: &Path,
 ) -> Result<RenameContextResult> {
     if !file_path.exists() {
         return Err(K8pkError::KubeconfigNotFound(file_path.to_path_buf()));
     }
+    kubeconfig::ensure_editable(file_path)?;
+
+    // Serialise with other k8pk writers of this file until we are done
+    let _lock = if dry_run {
+        None
+    } else {
+        Some(kubeconfig::lock_kubeconfig(file_path)?)
+    };
 
     // Backup before destructive operation
     if !dry_run {
-        if let Some(bak) = backup_kubeconfig(file_path)? {
-            eprintln!("Backup saved to {}", bak.display());
+        if let Some(bak) = backup_kubeconfig(file_path, backups)? {
+            if !crate::render::quiet() {
+                eprintln!("Backup saved to {}", bak.display());
+            }
         }
     }
 
@@ -669,37 +1375,157 @@ pub fn rename_context_in_file(
         .find(|c| c.name == old_name)
         .ok_or_else(|| K8pkError::ContextNotFound(old_name.to_string()))?;
 
+    let retarget = config::retarget_aliases(
+        &HashMap::from([(old_name.to_string(), new_name.to_string())]),
+        dry_run,
+    )?;
+
     if dry_run {
         Ok(RenameContextResult {
             file: file_path.to_path_buf(),
             old_name: old_name.to_string(),
             new_name: new_name.to_string(),
             dry_run,
+            aliases_updated: retarget,
         })
     } else {
         ctx.name = new_name.to_string();
 
-        if cfg.current_context.as_deref() == Some(old_name) {
+        let was_current = cfg.current_context.as_deref() == Some(old_name);
+        if was_current {
             cfg.current_context = Some(new_name.to_string());
         }
 
-        let yaml = serde_yaml_ng::to_string(&cfg)?;
+        let mapping = HashMap::from([(old_name.to_string(), new_name.to_string())]);
+        let yaml = yaml_edit::render(&content, &cfg, |text| {
+            let text = yaml_edit::rename_items(text, "contexts", &mapping)?;
+            if was_current {
+                yaml_edit::set_current_context(&text, Some(new_name))
+            } else {
+                Some(text)
+            }
+        })?;
         kubeconfig::write_restricted(file_path, &yaml)?;
         Ok(RenameContextResult {
             file: file_path.to_path_buf(),
             old_name: old_name.to_string(),
             new_name: new_name.to_string(),
             dry_run,
+            aliases_updated: retarget,
         })
     }
 }
 
-/// Copy a context between kubeconfig files
-pub fn copy_context_between_files(
-    from_file: &Path,
-    to_file: &Path,
-    context: &str,
-    new_name: Option<&str>,
+/// Rename every context in a file whose name changes under `rule`.
+/// current-context and k8pk config aliases follow the renamed contexts.
+pub fn rename_contexts_bulk(
+    file_path: &Path,
+    rule: &RenameRule,
+    dry_run: bool,
+    backups: &Path,
+) -> Result<BulkRenameResult> {
+    if !file_path.exists() {
+        return Err(K8pkError::KubeconfigNotFound(file_path.to_path_buf()));
+    }
+    kubeconfig::ensure_editable(file_path)?;
+
+    let _lock = if dry_run {
+        None
+    } else {
+        Some(kubeconfig::lock_kubeconfig(file_path)?)
+    };
+    let content = fs::read_to_string(file_path)?;
+    let mut cfg: KubeConfig = serde_yaml_ng::from_str(&content)?;
+
+    let mut renames = Vec::new();
+    let mut unchanged = 0;
+    for ctx in &cfg.contexts {
+        let new_name = rule.apply(ctx, &cfg);
+        if new_name == ctx.name {
+            unchanged += 1;
+        } else if new_name.trim().is_empty() {
+            return Err(K8pkError::InvalidArgument(format!(
+                "rename would give context '{}' an empty name",
+                ctx.name
+            )));
+        } else {
+            renames.push(RenamedContext {
+                old_name: ctx.name.clone(),
+                new_name,
+            });
+        }
+    }
+
+    // Resulting names must stay unique across renamed and untouched contexts
+    let mut final_names: HashSet<&str> = HashSet::new();
+    for ctx in &cfg.contexts {
+        let name = renames
+            .iter()
+            .find(|r| r.old_name == ctx.name)
+            .map(|r| r.new_name.as_str())
+            .unwrap_or(ctx.name.as_str());
+        if !final_names.insert(name) {
+            return Err(K8pkError::InvalidArgument(format!(
+                "rename would produce duplicate context name '{}'",
+                name
+            )));
+        }
+    }
+
+    let mapping: HashMap<String, String> = renames
+        .iter()
+        .map(|r| (r.old_name.clone(), r.new_name.clone()))
+        .collect();
+
+    let current_context = cfg
+        .current_context
+        .as_ref()
+        .and_then(|c| mapping.get(c))
+        .cloned();
+
+    if !dry_run && !renames.is_empty() {
+        if let Some(bak) = backup_kubeconfig(file_path, backups)? {
+            if !crate::render::quiet() {
+                eprintln!("Backup saved to {}", bak.display());
+            }
+        }
+        for ctx in cfg.contexts.iter_mut() {
+            if let Some(new_name) = mapping.get(&ctx.name) {
+                ctx.name = new_name.clone();
+            }
+        }
+        if current_context.is_some() {
+            cfg.current_context = current_context.clone();
+        }
+        let yaml = yaml_edit::render(&content, &cfg, |text| {
+            let text = yaml_edit::rename_items(text, "contexts", &mapping)?;
+            match &current_context {
+                Some(c) => yaml_edit::set_current_context(&text, Some(c)),
+                None => Some(text),
+            }
+        })?;
+        kubeconfig::write_restricted(file_path, &yaml)?;
+    }
+
+    let aliases_updated = config::retarget_aliases(&mapping, dry_run)?;
+
+    Ok(BulkRenameResult {
+        file: file_path.to_path_buf(),
+        dry_run,
+        renames,
+        unchanged,
+        current_context,
+        aliases_updated,
+    })
+}
+
+/// Copy a context between kubeconfig files
+pub fn copy_context_between_files(
+    from_file: &Path,
+    to_file: &Path,
+    context: &str,
+    new_name: Option<&str>,
+    embed_certs: bool,
     dry_run: bool,
 ) -> Result<CopyContextResult> {
     if !from_file.exists() {
@@ -707,7 +1533,11 @@ pub fn copy_context_between_files(
     }
 
     let source_content = fs::read_to_string(from_file)?;
-    let source_cfg: KubeConfig = serde_yaml_ng::from_str(&source_content)?;
+    let mut source_cfg: KubeConfig = serde_yaml_ng::from_str(&source_content)?;
+    // Relative cert paths are relative to the source file, not the destination
+    if let Some(dir) = kubeconfig::normalize_path(&from_file.to_string_lossy()).parent() {
+        kubeconfig::resolve_relative_paths(&mut source_cfg, dir);
+    }
 
     let ctx = source_cfg
         .find_context(context)
@@ -723,6 +1553,18 @@ pub fn copy_context_between_files(
         .find_user(&user_name)
         .ok_or_else(|| K8pkError::UserNotFound(user_name.clone()))?;
 
+    let (mut cluster, mut user) = (cluster.clone(), user.clone());
+    if embed_certs {
+        let mut entry = KubeConfig {
+            clusters: vec![cluster],
+            users: vec![user],
+            ..Default::default()
+        };
+        kubeconfig::embed_certs(&mut entry)?;
+        cluster = entry.clusters.remove(0);
+        user = entry.users.remove(0);
+    }
+
     let target_name = new_name.unwrap_or(context);
 
     if dry_run {
@@ -735,6 +1577,7 @@ pub fn copy_context_between_files(
         });
     }
 
+    let _lock = kubeconfig::lock_kubeconfig(to_file)?;
     let mut dest_cfg: KubeConfig = if to_file.exists() {
         let content = fs::read_to_string(to_file)?;
         serde_yaml_ng::from_str(&content)?
@@ -743,10 +1586,10 @@ pub fn copy_context_between_files(
     };
 
     dest_cfg.clusters.retain(|c| c.name != cluster_name);
-    dest_cfg.clusters.push(cluster.clone());
+    dest_cfg.clusters.push(cluster);
 
     dest_cfg.users.retain(|u| u.name != user_name);
-    dest_cfg.users.push(user.clone());
+    dest_cfg.users.push(user);
 
     let mut new_ctx = ctx.clone();
     new_ctx.name = target_name.to_string();
@@ -766,11 +1609,15 @@ pub fn copy_context_between_files(
     })
 }
 
-/// Edit a kubeconfig file
+/// Edit the kubeconfig file holding `context` (or a picked file).
+/// With `goto`, the editor opens at the context's entry when it supports a line argument.
+/// Unless `no_review` is set, the edited file is linted and diffed against the
+/// original, and the user can roll the edit back before it is kept.
 pub fn edit_kubeconfig(
     context: Option<&str>,
     editor: Option<&str>,
-    _merged: &KubeConfig,
+    goto: bool,
+    no_review: bool,
     paths: &[PathBuf],
 ) -> Result<()> {
     let ctx_paths = kubeconfig::list_contexts_with_paths(paths)?;
@@ -789,13 +1636,18 @@ pub fn edit_kubeconfig(
         }
 
         let display: Vec<String> = files.iter().map(|p| p.display().to_string()).collect();
-        let selected = Select::new("Select file to edit:", display)
-            .prompt()
-            .map_err(|_| K8pkError::Cancelled)?;
+        let selected = Select::new(&tr("pick-file-edit"), display).ask()?;
 
         PathBuf::from(selected)
     };
 
+    kubeconfig::ensure_editable(&file_to_edit)?;
+    let original = fs::read_to_string(&file_to_edit)?;
+    let line = match (goto, context) {
+        (true, Some(ctx)) => find_context_line(&original, ctx),
+        _ => None,
+    };
+
     let editor_cmd = editor
         .map(String::from)
         .or_else(|| env::var("EDITOR").ok())
@@ -811,7 +1663,7 @@ pub fn edit_kubeconfig(
 
     let status = std::process::Command::new(&cmd)
         .args(parts)
-        .arg(&file_to_edit)
+        .args(editor_file_args(&cmd, &file_to_edit, line))
         .status()?;
 
     if !status.success() {
@@ -821,9 +1673,134 @@ pub fn edit_kubeconfig(
         )));
     }
 
+    if no_review {
+        return Ok(());
+    }
+
+    let edited = fs::read_to_string(&file_to_edit)?;
+    if edited == original {
+        eprintln!("No changes to {}", file_to_edit.display());
+        return Ok(());
+    }
+
+    for l in line_diff(&original, &edited) {
+        println!("{}", l);
+    }
+
+    let lint_result = lint(Some(&file_to_edit), &[], false)?;
+    for issue in lint_result.issues.iter().filter(|i| i.level == "error") {
+        eprintln!("error: {} {}", issue.path.display(), issue.message);
+    }
+
+    if crate::prompt::can_prompt() {
+        let keep = Confirm::new(&tr("confirm-keep-changes"))
+            .with_default(!lint_result.failed)
+            .ask()?;
+        if !keep {
+            fs::write(&file_to_edit, &original)?;
+            eprintln!("Reverted {}", file_to_edit.display());
+        }
+    }
+
     Ok(())
 }
 
+/// 1-based line of the `name: <context>` entry inside the top-level `contexts:` list
+fn find_context_line(content: &str, context: &str) -> Option<usize> {
+    let mut in_contexts = false;
+    for (idx, raw) in content.lines().enumerate() {
+        let starts_top_level = !raw.starts_with(' ') && !raw.starts_with('-') && !raw.is_empty();
+        if starts_top_level {
+            in_contexts = raw.trim_end() == "contexts:";
+            continue;
+        }
+        if !in_contexts {
+            continue;
+        }
+        let trimmed = raw.trim_start().trim_start_matches("- ").trim();
+        if let Some(value) = trimmed.strip_prefix("name:") {
+            let value = value.trim().trim_matches('"').trim_matches('\'');
+            if value == context {
+                return Some(idx + 1);
+            }
+        }
+    }
+    None
+}
+
+/// Arguments that open `file` in `editor`, positioned at `line` when the editor supports it
+fn editor_file_args(editor: &str, file: &Path, line: Option<usize>) -> Vec<std::ffi::OsString> {
+    let Some(line) = line else {
+        return vec![file.as_os_str().to_owned()];
+    };
+    let name = Path::new(editor)
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let with_suffix = || format!("{}:{}", file.display(), line).into();
+    match name.as_str() {
+        "vim" | "vi" | "nvim" | "nano" | "emacs" | "emacsclient" | "micro" | "kak" | "mg" => {
+            vec![format!("+{}", line).into(), file.as_os_str().to_owned()]
+        }
+        "code" | "code-insiders" | "codium" | "cursor" => vec!["--goto".into(), with_suffix()],
+        "subl" | "zed" | "hx" | "helix" => vec![with_suffix()],
+        _ => vec![file.as_os_str().to_owned()],
+    }
+}
+
+/// Minimal line diff between two texts, as `-`/`+` lines grouped under `@@` headers
+fn line_diff(old: &str, new: &str) -> Vec<String> {
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+
+    // Trim the common prefix/suffix so the LCS table only covers the edited region
+    let prefix = a.iter().zip(&b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let a_mid = &a[prefix..a.len() - suffix];
+    let b_mid = &b[prefix..b.len() - suffix];
+
+    let (n, m) = (a_mid.len(), b_mid.len());
+    let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if a_mid[i] == b_mid[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut out = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    let mut in_hunk = false;
+    while i < n || j < m {
+        if i < n && j < m && a_mid[i] == b_mid[j] {
+            in_hunk = false;
+            i += 1;
+            j += 1;
+            continue;
+        }
+        if !in_hunk {
+            out.push(format!("@@ line {} @@", prefix + j + 1));
+            in_hunk = true;
+        }
+        if i < n && (j == m || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            out.push(format!("-{}", a_mid[i]));
+            i += 1;
+        } else {
+            out.push(format!("+{}", b_mid[j]));
+            j += 1;
+        }
+    }
+    out
+}
+
 /// Open k8pk config in $EDITOR (falls back to vim).
 pub fn edit_config() -> Result<()> {
     let (path, _) = crate::config::init_config()?;
@@ -878,6 +1855,58 @@ pub fn print_rename_context_summary(result: &RenameContextResult) {
             result.old_name, result.new_name
         );
     }
+    print_aliases_updated(&result.aliases_updated, result.dry_run);
+}
+
+pub fn print_bulk_rename_summary(result: &BulkRenameResult) {
+    if result.renames.is_empty() {
+        println!(
+            "No contexts in {} match; nothing to rename.",
+            result.file.display()
+        );
+        return;
+    }
+
+    let width = result
+        .renames
+        .iter()
+        .map(|r| r.old_name.len())
+        .max()
+        .unwrap_or(0)
+        .max("CONTEXT".len());
+    println!("{:<width$}  NEW NAME", "CONTEXT", width = width);
+    for r in &result.renames {
+        println!("{:<width$}  {}", r.old_name, r.new_name, width = width);
+    }
+    println!();
+
+    let verb = if result.dry_run {
+        "Would rename"
+    } else {
+        "Renamed"
+    };
+    println!(
+        "{} {} context(s) in {} ({} unchanged)",
+        verb,
+        result.renames.len(),
+        result.file.display(),
+        result.unchanged
+    );
+    if let Some(ref current) = result.current_context {
+        println!("current-context -> {}", current);
+    }
+    print_aliases_updated(&result.aliases_updated, result.dry_run);
+}
+
+fn print_aliases_updated(aliases: &[String], dry_run: bool) {
+    if aliases.is_empty() {
+        return;
+    }
+    println!(
+        "{} alias(es): {}",
+        if dry_run { "Would update" } else { "Updated" },
+        aliases.join(", ")
+    );
 }
 
 pub fn print_copy_context_summary(result: &CopyContextResult) {
@@ -898,6 +1927,136 @@ pub fn print_copy_context_summary(result: &CopyContextResult) {
     }
 }
 
+/// Remove one or more contexts from kubeconfig files (and isolated caches).
+pub fn run_rm(
+    paths: &[PathBuf],
+    context: Option<String>,
+    dry_run: bool,
+    yes: bool,
+    backups: &Path,
+) -> Result<Vec<RemoveContextResult>> {
+    let ctx_paths = kubeconfig::list_contexts_with_paths(paths)?;
+    if ctx_paths.is_empty() {
+        return Err(K8pkError::NoContexts);
+    }
+
+    let contexts_to_remove: Vec<String> = if let Some(ref c) = context {
+        let all: Vec<String> = ctx_paths.keys().cloned().collect();
+        let matches = crate::commands::expand_context(c, &all);
+        if matches.is_empty() {
+            return Err(crate::commands::no_match_error(
+                &config::resolve_alias(c),
+                &all,
+            ));
+        }
+        if matches.len() == 1 {
+            matches
+        } else if crate::prompt::can_prompt() {
+            eprintln!("'{}' matched {} contexts:", c, matches.len());
+            let selected = MultiSelect::new(&tr("pick-contexts-remove"), matches).ask()?;
+            if selected.is_empty() {
+                return Err(K8pkError::Cancelled);
+            }
+            selected
+        } else {
+            return Err(K8pkError::InvalidArgument(format!(
+                "'{}' matches multiple contexts: {}. Be more specific.",
+                c,
+                matches.join(", ")
+            )));
+        }
+    } else if crate::prompt::can_prompt() {
+        let mut names: Vec<String> = ctx_paths.keys().cloned().collect();
+        names.sort();
+        let selected = MultiSelect::new(&tr("pick-contexts-remove"), names).ask()?;
+        if selected.is_empty() {
+            return Err(K8pkError::Cancelled);
+        }
+        selected
+    } else {
+        return Err(K8pkError::InvalidArgument(
+            "specify a context name, or run interactively".into(),
+        ));
+    };
+
+    if !dry_run && crate::prompt::can_prompt() && !yes {
+        eprintln!("Will remove {} context(s):", contexts_to_remove.len());
+        for c in &contexts_to_remove {
+            let file = ctx_paths
+                .get(c)
+                .map(|p| p.display().to_string())
+                .unwrap_or_default();
+            eprintln!("  {} (from {})", c, file);
+        }
+        let confirm = Confirm::new(&tr("confirm-proceed"))
+            .with_default(false)
+            .ask()?;
+        if !confirm {
+            return Err(K8pkError::Cancelled);
+        }
+    }
+
+    let mut by_file: std::collections::HashMap<PathBuf, Vec<String>> =
+        std::collections::HashMap::new();
+    for c in &contexts_to_remove {
+        if let Some(file) = ctx_paths.get(c) {
+            by_file.entry(file.clone()).or_default().push(c.clone());
+        }
+    }
+
+    let mut total_removed = Vec::new();
+    let mut results = Vec::new();
+    for (file, ctxs) in &by_file {
+        for ctx_name in ctxs {
+            results.push(remove_contexts_from_file(
+                file,
+                Some(ctx_name.as_str()),
+                false,
+                false,
+                dry_run,
+                backups,
+            )?);
+            total_removed.push(ctx_name.clone());
+        }
+    }
+
+    if !dry_run {
+        remove_isolated_caches(&total_removed)?;
+    }
+    Ok(results)
+}
+
+/// Delete the isolated per-context kubeconfigs k8pk generated for `contexts`
+/// (plain, encrypted, and live copies)
+pub(crate) fn remove_isolated_caches(contexts: &[String]) -> Result<()> {
+    let dirs = crate::storage::isolated_dirs();
+    for c in contexts {
+        let sanitized = kubeconfig::sanitize_filename(c);
+        for entries in dirs.iter().filter_map(|d| fs::read_dir(d).ok()) {
+            for entry in entries.flatten() {
+                let fname = entry.file_name();
+                let name = fname.to_string_lossy();
+                let name = name.trim_end_matches(crate::encryption::SEALED_SUFFIX);
+                if name.starts_with(&sanitized) && name.ends_with(".yaml") {
+                    if let Err(e) = fs::remove_file(entry.path()) {
+                        eprintln!(
+                            "warning: failed to remove {}: {}",
+                            entry.path().display(),
+                            e
+                        );
+                    }
+                }
+            }
+        }
+        for live in crate::encryption::live_copies_for(c) {
+            if let Err(e) = fs::remove_file(&live) {
+                eprintln!("warning: failed to remove {}: {}", live.display(), e);
+            }
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -952,7 +2111,7 @@ current-context: ctx-b
         let file_a = write_kubeconfig(dir.path(), "a.yaml", KUBECONFIG_A);
         let file_b = write_kubeconfig(dir.path(), "b.yaml", KUBECONFIG_B);
 
-        let result = merge_files(&[file_a, file_b], None, false).unwrap();
+        let result = merge_files(&[file_a, file_b], None, false, MergePrefer::First).unwrap();
         assert!(result.yaml.is_some());
         assert!(result.output.is_none());
 
@@ -973,7 +2132,7 @@ current-context: ctx-b
         let file_b = write_kubeconfig(dir.path(), "b.yaml", KUBECONFIG_B);
         let out = dir.path().join("merged.yaml");
 
-        let result = merge_files(&[file_a, file_b], Some(&out), false).unwrap();
+        let result = merge_files(&[file_a, file_b], Some(&out), false, MergePrefer::First).unwrap();
         assert!(result.output.is_some());
         assert!(out.exists());
 
@@ -984,33 +2143,231 @@ current-context: ctx-b
 
     #[test]
     fn test_merge_empty_list() {
-        let result = merge_files(&[], None, false);
+        let result = merge_files(&[], None, false, MergePrefer::First);
         assert!(result.is_err());
     }
 
+    const PREFS_A: &str = r#"
+apiVersion: v1
+kind: Config
+preferences:
+  colors: true
+extensions:
+  - name: team
+    extension:
+      owner: a
+current-context: ctx-a
+"#;
+
+    const PREFS_B: &str = r#"
+apiVersion: v1
+kind: Config
+preferences:
+  colors: false
+  editor: nano
+extensions:
+  - name: team
+    extension:
+      owner: b
+  - name: extra
+    extension: {}
+current-context: ctx-b
+"#;
+
+    fn merge_prefer(prefer: MergePrefer) -> kubeconfig::KubeConfig {
+        let dir = tempfile::tempdir().unwrap();
+        let file_a = write_kubeconfig(dir.path(), "a.yaml", PREFS_A);
+        let file_b = write_kubeconfig(dir.path(), "b.yaml", PREFS_B);
+        let result = merge_files(&[file_a, file_b], None, false, prefer).unwrap();
+        serde_yaml_ng::from_str(result.yaml.as_ref().unwrap()).unwrap()
+    }
+
+    fn extension_names(cfg: &kubeconfig::KubeConfig) -> Vec<String> {
+        cfg.extensions
+            .as_ref()
+            .and_then(|e| e.as_sequence())
+            .unwrap()
+            .iter()
+            .filter_map(|e| e.get("name").and_then(|n| n.as_str()).map(String::from))
+            .collect()
+    }
+
+    #[test]
+    fn test_merge_prefer_first() {
+        let merged = merge_prefer(MergePrefer::First);
+        assert_eq!(merged.current_context.as_deref(), Some("ctx-a"));
+        let prefs = merged.preferences.as_ref().unwrap();
+        assert_eq!(prefs.get("colors").and_then(|v| v.as_bool()), Some(true));
+        assert!(prefs.get("editor").is_none());
+        assert_eq!(extension_names(&merged), vec!["team", MERGE_EXTENSION_NAME]);
+    }
+
+    #[test]
+    fn test_merge_prefer_last() {
+        let merged = merge_prefer(MergePrefer::Last);
+        assert_eq!(merged.current_context.as_deref(), Some("ctx-b"));
+        let prefs = merged.preferences.as_ref().unwrap();
+        assert_eq!(prefs.get("colors").and_then(|v| v.as_bool()), Some(false));
+        assert_eq!(
+            extension_names(&merged),
+            vec!["team", "extra", MERGE_EXTENSION_NAME]
+        );
+    }
+
     #[test]
-    fn test_backup_kubeconfig() {
+    fn test_merge_prefer_union() {
+        let merged = merge_prefer(MergePrefer::Union);
+        assert_eq!(merged.current_context.as_deref(), Some("ctx-a"));
+        let prefs = merged.preferences.as_ref().unwrap();
+        assert_eq!(prefs.get("colors").and_then(|v| v.as_bool()), Some(true));
+        assert_eq!(prefs.get("editor").and_then(|v| v.as_str()), Some("nano"));
+
+        let exts = merged.extensions.as_ref().unwrap().as_sequence().unwrap();
+        let team = exts
+            .iter()
+            .find(|e| e.get("name").and_then(|n| n.as_str()) == Some("team"))
+            .unwrap();
+        assert_eq!(team["extension"]["owner"].as_str(), Some("a"));
+        assert_eq!(
+            extension_names(&merged),
+            vec!["team", "extra", MERGE_EXTENSION_NAME]
+        );
+        let record = exts.last().unwrap();
+        assert_eq!(record["extension"]["prefer"].as_str(), Some("union"));
+    }
+
+    const KUBECONFIG_EKS: &str = r#"
+apiVersion: v1
+kind: Config
+clusters:
+  - name: prod
+    cluster:
+      server: https://ABC.gr7.us-east-1.eks.amazonaws.com
+contexts:
+  - name: arn:aws:eks:us-east-1:123456789012:cluster/prod
+    context:
+      cluster: prod
+      user: prod-user
+      namespace: payments
+  - name: local
+    context:
+      cluster: prod
+      user: prod-user
+users:
+  - name: prod-user
+    user: {}
+current-context: arn:aws:eks:us-east-1:123456789012:cluster/prod
+"#;
+
+    #[test]
+    fn test_rename_rule_parse_pattern() {
+        assert!(RenameRule::parse_pattern("s/a/b/").is_ok());
+        assert!(RenameRule::parse_pattern("s|a/b|c|g").is_ok());
+        assert!(RenameRule::parse_pattern("a/b/").is_err());
+        assert!(RenameRule::parse_pattern("s/a/b").is_err());
+        assert!(RenameRule::parse_pattern("s/a/b/x").is_err());
+        assert!(RenameRule::parse_pattern("s/(/b/").is_err());
+    }
+
+    #[test]
+    fn test_sed_replacement() {
+        assert_eq!(sed_replacement(r"\1-&"), "${1}-${0}");
+        assert_eq!(sed_replacement(r"a\&b$"), "a&b$$");
+    }
+
+    #[test]
+    fn test_rename_contexts_bulk_pattern() {
         let dir = tempfile::tempdir().unwrap();
-        let path = write_kubeconfig(dir.path(), "test.yaml", KUBECONFIG_A);
+        let backups = dir.path().join("backups");
+        let path = write_kubeconfig(dir.path(), "eks.yaml", KUBECONFIG_EKS);
+        let rule = RenameRule::parse_pattern(r"s/^arn:aws:eks:.*cluster\///").unwrap();
+
+        let preview = rename_contexts_bulk(&path, &rule, true, &backups).unwrap();
+        assert_eq!(preview.renames.len(), 1);
+        assert_eq!(preview.renames[0].new_name, "prod");
+        assert_eq!(preview.unchanged, 1);
+        assert_eq!(preview.current_context.as_deref(), Some("prod"));
+        // Dry run leaves the file alone
+        assert_eq!(fs::read_to_string(&path).unwrap(), KUBECONFIG_EKS);
+
+        rename_contexts_bulk(&path, &rule, false, &backups).unwrap();
+        let cfg: KubeConfig = serde_yaml_ng::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(cfg.context_names(), vec!["prod", "local"]);
+        assert_eq!(cfg.current_context.as_deref(), Some("prod"));
+    }
 
-        let backup = backup_kubeconfig(&path).unwrap();
-        assert!(backup.is_some());
-        let bak_path = backup.unwrap();
-        assert!(bak_path.exists());
-        assert!(bak_path.to_string_lossy().contains(".bak."));
+    #[test]
+    fn test_rename_contexts_bulk_template() {
+        let dir = tempfile::tempdir().unwrap();
+        let backups = dir.path().join("backups");
+        let path = write_kubeconfig(dir.path(), "eks.yaml", KUBECONFIG_EKS);
+        let rule = RenameRule::Template("{type}-{cluster}-{namespace}".into());
 
-        // Content should match
-        let original = fs::read_to_string(&path).unwrap();
-        let backed_up = fs::read_to_string(&bak_path).unwrap();
-        assert_eq!(original, backed_up);
+        let preview = rename_contexts_bulk(&path, &rule, true, &backups).unwrap();
+        let names: Vec<&str> = preview
+            .renames
+            .iter()
+            .map(|r| r.new_name.as_str())
+            .collect();
+        assert_eq!(names, vec!["eks-prod-payments", "eks-local-"]);
     }
 
     #[test]
-    fn test_backup_nonexistent_file() {
+    fn test_rename_contexts_bulk_rejects_duplicates() {
         let dir = tempfile::tempdir().unwrap();
-        let path = dir.path().join("nonexistent.yaml");
-        let backup = backup_kubeconfig(&path).unwrap();
-        assert!(backup.is_none());
+        let backups = dir.path().join("backups");
+        let path = write_kubeconfig(dir.path(), "eks.yaml", KUBECONFIG_EKS);
+        let rule = RenameRule::Template("same".into());
+        assert!(rename_contexts_bulk(&path, &rule, true, &backups).is_err());
+    }
+
+    #[test]
+    fn test_find_context_line() {
+        let content = "apiVersion: v1\nclusters:\n- name: ctx-b\n  cluster: {}\ncontexts:\n- context:\n    cluster: a\n  name: ctx-a\n- name: \"ctx-b\"\n  context: {}\n";
+        assert_eq!(find_context_line(content, "ctx-a"), Some(8));
+        // Cluster with the same name is skipped; the context entry is found
+        assert_eq!(find_context_line(content, "ctx-b"), Some(9));
+        assert_eq!(find_context_line(content, "missing"), None);
+    }
+
+    #[test]
+    fn test_editor_file_args() {
+        let file = Path::new("/tmp/kc.yaml");
+        assert_eq!(editor_file_args("vim", file, None), vec!["/tmp/kc.yaml"]);
+        assert_eq!(
+            editor_file_args("/usr/bin/nvim", file, Some(12)),
+            vec!["+12", "/tmp/kc.yaml"]
+        );
+        assert_eq!(
+            editor_file_args("code", file, Some(3)),
+            vec!["--goto", "/tmp/kc.yaml:3"]
+        );
+        assert_eq!(
+            editor_file_args("unknown-editor", file, Some(3)),
+            vec!["/tmp/kc.yaml"]
+        );
+    }
+
+    #[test]
+    fn test_line_diff() {
+        let old = "a\nb\nc\nd\n";
+        let new = "a\nB\nc\nd\ne\n";
+        assert_eq!(
+            line_diff(old, new),
+            vec!["@@ line 2 @@", "-b", "+B", "@@ line 5 @@", "+e"]
+        );
+        assert!(line_diff(old, old).is_empty());
+    }
+
+    #[test]
+    fn test_merge_prefer_values() {
+        use clap::ValueEnum;
+        // The merge extension records the same names --prefer accepts
+        for prefer in MergePrefer::value_variants() {
+            let value = prefer.to_possible_value().unwrap();
+            assert_eq!(value.get_name(), prefer.as_str());
+        }
+        assert!(MergePrefer::from_str("newest", false).is_err());
     }
 
     #[test]
@@ -1028,6 +2385,7 @@ current-context: ctx-b
     #[test]
     fn test_remove_contexts_dry_run() {
         let dir = tempfile::tempdir().unwrap();
+        let backups = dir.path().join("backups");
         let path = write_kubeconfig(dir.path(), "test.yaml", KUBECONFIG_A);
 
         let result = remove_contexts_from_file(
@@ -1036,6 +2394,7 @@ current-context: ctx-b
             false, // interactive
             false, // remove_orphans
             true,  // dry_run
+            &backups,
         )
         .unwrap();
 
@@ -1050,6 +2409,7 @@ current-context: ctx-b
     #[test]
     fn test_remove_contexts_actual() {
         let dir = tempfile::tempdir().unwrap();
+        let backups = dir.path().join("backups");
         let path = write_kubeconfig(dir.path(), "test.yaml", KUBECONFIG_A);
 
         let result = remove_contexts_from_file(
@@ -1058,6 +2418,7 @@ current-context: ctx-b
             false, // interactive
             true,  // remove_orphans
             false, // dry_run
+            &backups,
         )
         .unwrap();
 
@@ -1168,6 +2529,68 @@ current-context: nonexistent-ctx
             .any(|i| i.message.contains("current-context not found")));
     }
 
+    #[test]
+    fn test_lint_dangling_refs_and_servers() {
+        let yaml = r#"
+clusters:
+  - name: no-server
+    cluster: {}
+  - name: bare-host
+    cluster:
+      server: k8s.example.com:6443
+contexts:
+  - name: a
+    context: {cluster: no-server, user: ghost}
+  - name: b
+    context: {cluster: bare-host, user: ghost}
+"#;
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_kubeconfig(dir.path(), "refs.yaml", yaml);
+        let result = lint(Some(&path), &[], false).unwrap();
+        let messages: Vec<&str> = result.issues.iter().map(|i| i.message.as_str()).collect();
+        assert_eq!(result.errors, 1, "{:?}", messages);
+        assert!(messages.contains(&"cluster no-server has no server"));
+        assert!(messages.contains(&"context a references missing user: ghost"));
+        assert!(messages
+            .iter()
+            .any(|m| m.starts_with("cluster bare-host server is not an http(s) URL")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_lint_exec_plugins_uses_hints_and_kubeconfig_dir() {
+        use std::os::unix::fs::PermissionsExt;
+        let yaml = r#"
+users:
+  - name: oidc
+    user: {exec: {command: k8pk-test-kubelogin}}
+  - name: local
+    user: {exec: {command: ./bin/auth}}
+  - name: token
+    user: {token: abc}
+"#;
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_kubeconfig(dir.path(), "exec.yaml", yaml);
+        let cfg: KubeConfig = serde_yaml_ng::from_str(yaml).unwrap();
+        let plugins = dir.path().join("plugins");
+        fs::create_dir_all(dir.path().join("bin")).unwrap();
+        fs::create_dir_all(&plugins).unwrap();
+        for exe in [
+            plugins.join("k8pk-test-kubelogin"),
+            dir.path().join("bin/auth"),
+        ] {
+            fs::write(&exe, "#!/bin/sh\n").unwrap();
+            fs::set_permissions(&exe, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+
+        let issues = lint_exec_plugins(&path, &cfg, &[]);
+        assert_eq!(issues.len(), 1, "{:?}", issues);
+        assert!(issues[0]
+            .message
+            .starts_with("user oidc exec plugin not found: k8pk-test-kubelogin"));
+        assert!(lint_exec_plugins(&path, &cfg, &[plugins]).is_empty());
+    }
+
     #[test]
     fn test_lint_strict_fails_on_warnings() {
         let empty_contexts_yaml = r#"
@@ -1195,138 +2618,172 @@ users: []
         assert_eq!(result.warnings, 0);
         assert!(!result.failed);
     }
-}
-
-/// Remove one or more contexts from kubeconfig files (and isolated caches).
-pub fn run_rm(
-    paths: &[PathBuf],
-    context: Option<String>,
-    dry_run: bool,
-    yes: bool,
-    json: bool,
-) -> Result<()> {
-    let ctx_paths = kubeconfig::list_contexts_with_paths(paths)?;
-    if ctx_paths.is_empty() {
-        return Err(K8pkError::NoContexts);
-    }
 
-    let contexts_to_remove: Vec<String> = if let Some(ref c) = context {
-        let resolved = config::resolve_alias(c);
-        let all: Vec<String> = ctx_paths.keys().cloned().collect();
-        let matches = crate::commands::context::match_pattern(&resolved, &all);
-        if matches.is_empty() {
-            let suggestions = crate::error::closest_matches(&resolved, &all, 3);
-            if suggestions.is_empty() {
-                return Err(K8pkError::ContextNotFound(resolved));
-            }
-            return Err(K8pkError::ContextNotFoundSuggestions {
-                pattern: resolved,
-                suggestions: suggestions
-                    .iter()
-                    .map(|s| format!("    - {}", s))
-                    .collect::<Vec<_>>()
-                    .join("\n"),
-            });
-        }
-        if matches.len() == 1 {
-            matches
-        } else if io::stdin().is_terminal() {
-            eprintln!("'{}' matched {} contexts:", c, matches.len());
-            let selected = MultiSelect::new("Select contexts to remove:", matches)
-                .prompt()
-                .map_err(|_| K8pkError::Cancelled)?;
-            if selected.is_empty() {
-                return Err(K8pkError::Cancelled);
-            }
-            selected
-        } else {
-            return Err(K8pkError::InvalidArgument(format!(
-                "'{}' matches multiple contexts: {}. Be more specific.",
-                c,
-                matches.join(", ")
-            )));
-        }
-    } else if io::stdin().is_terminal() {
-        let mut names: Vec<String> = ctx_paths.keys().cloned().collect();
-        names.sort();
-        let selected = MultiSelect::new("Select contexts to remove:", names)
-            .prompt()
-            .map_err(|_| K8pkError::Cancelled)?;
-        if selected.is_empty() {
-            return Err(K8pkError::Cancelled);
-        }
-        selected
-    } else {
-        return Err(K8pkError::InvalidArgument(
-            "specify a context name, or run interactively".into(),
-        ));
-    };
+    #[test]
+    fn test_lint_duplicate_contexts_across_files() {
+        let kc = |server: &str, ns: &str| {
+            format!(
+                "clusters:\n- name: c\n  cluster: {{server: \"{}\"}}\n\
+                 users:\n- name: u\n  user: {{token: t}}\n\
+                 contexts:\n- name: shared\n  context: {{cluster: c, user: u, namespace: {}}}\n",
+                server, ns
+            )
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let a = write_kubeconfig(dir.path(), "a.yaml", &kc("https://a", "x"));
+        let b = write_kubeconfig(dir.path(), "b.yaml", &kc("https://a", "x"));
+        let c = write_kubeconfig(dir.path(), "c.yaml", &kc("https://a", "y"));
+        let d = write_kubeconfig(dir.path(), "d.yaml", &kc("https://d", "x"));
+        let parse = |p: &PathBuf| {
+            (
+                p.clone(),
+                serde_yaml_ng::from_str(&fs::read_to_string(p).unwrap()).unwrap(),
+            )
+        };
+        let files: Vec<(PathBuf, KubeConfig)> = [&a, &b, &c, &d].into_iter().map(parse).collect();
 
-    if !dry_run && io::stdin().is_terminal() && !yes {
-        eprintln!("Will remove {} context(s):", contexts_to_remove.len());
-        for c in &contexts_to_remove {
-            let file = ctx_paths
-                .get(c)
-                .map(|p| p.display().to_string())
-                .unwrap_or_default();
-            eprintln!("  {} (from {})", c, file);
-        }
-        let confirm = Confirm::new("Proceed?")
-            .with_default(false)
-            .prompt()
-            .map_err(|_| K8pkError::Cancelled)?;
-        if !confirm {
-            return Err(K8pkError::Cancelled);
-        }
+        let issues = lint_duplicate_contexts(&files, kubeconfig::MergeStrategy::First);
+        let levels: Vec<(&Path, &str)> = issues
+            .iter()
+            .map(|i| (i.path.as_path(), i.level.as_str()))
+            .collect();
+        assert_eq!(
+            levels,
+            [
+                (b.as_path(), "info"),
+                (c.as_path(), "warning"),
+                (d.as_path(), "error")
+            ]
+        );
+        assert!(issues[2]
+            .message
+            .ends_with(&format!("; {} wins", a.display())));
+
+        // Under `last` the last file's definition wins and the others lose
+        let issues = lint_duplicate_contexts(&files, kubeconfig::MergeStrategy::Last);
+        assert_eq!(issues.len(), 3);
+        assert!(issues.iter().all(|i| i.level == "error" && i.path != d));
+
+        // Identical copies are info: they fail neither lint nor --strict
+        let result = lint(None, &[a, b], true).unwrap();
+        assert_eq!((result.errors, result.warnings), (0, 0));
+        assert_eq!(result.issues.len(), 1);
+        assert!(!result.failed);
     }
 
-    let mut by_file: std::collections::HashMap<PathBuf, Vec<String>> =
-        std::collections::HashMap::new();
-    for c in &contexts_to_remove {
-        if let Some(file) = ctx_paths.get(c) {
-            by_file.entry(file.clone()).or_default().push(c.clone());
-        }
-    }
+    #[test]
+    fn test_lint_fix() {
+        let yaml = r#"# team clusters
+apiVersion: v1beta1
+clusters:
+  - name: a
+    cluster: {server: "https://a"}
+  - name: a
+    cluster: {server: "https://a"}
+  - name: b
+    cluster: {server: "https://b", certificate-authority: missing-ca.crt}
+  - name: old
+    cluster: {server: "https://old"}
+contexts:
+  - name: ctx-a
+    context: {cluster: a, user: u}
+  - name: ctx-b
+    context: {cluster: b, user: u}
+users:
+  - name: u
+    user: {token: t}
+  - name: gone
+    user: {token: t}
+current-context: ctx-x
+"#;
+        let dir = tempfile::tempdir().unwrap();
+        let backups = dir.path().join("backups");
+        let path = write_kubeconfig(dir.path(), "fix.yaml", yaml);
+
+        let mut cfg: KubeConfig = serde_yaml_ng::from_str(yaml).unwrap();
+        let changes = fix_config(&mut cfg, dir.path(), false, &OtherRefs::default());
+        assert_eq!(
+            changes,
+            [
+                "apiVersion: v1beta1 -> v1",
+                "added kind: Config",
+                "removed duplicate cluster: a",
+                "removed orphaned cluster: old",
+                "removed orphaned user: gone",
+                "unset current-context: ctx-x (no such context)",
+            ]
+        );
+        // Nothing left to fix
+        assert!(fix_config(&mut cfg, dir.path(), false, &OtherRefs::default()).is_empty());
+        // --strict also drops the context whose CA file is missing
+        let strict = fix_config(&mut cfg, dir.path(), true, &OtherRefs::default());
+        assert_eq!(
+            strict,
+            [
+                "removed context ctx-b (missing file: missing-ca.crt)",
+                "removed orphaned cluster: b",
+            ]
+        );
 
-    let mut total_removed = Vec::new();
-    let mut json_results = Vec::new();
-    for (file, ctxs) in &by_file {
-        for ctx_name in ctxs {
-            let result =
-                remove_contexts_from_file(file, Some(ctx_name.as_str()), false, false, dry_run)?;
-            if json {
-                json_results.push(serde_json::to_value(&result)?);
-            } else {
-                print_remove_context_summary(&result);
-            }
-            total_removed.push(ctx_name.clone());
-        }
-    }
-    if json {
-        println!("{}", serde_json::to_string_pretty(&json_results)?);
+        let dry = lint_fix(Some(&path), &[], false, true, &backups).unwrap();
+        assert_eq!(dry[0].changes.len(), 6);
+        assert_eq!(fs::read_to_string(&path).unwrap(), yaml);
+
+        let fixed = lint_fix(Some(&path), &[], false, false, &backups).unwrap();
+        assert!(fixed[0].backup.is_some());
+        let content = fs::read_to_string(&path).unwrap();
+        let cfg: KubeConfig = serde_yaml_ng::from_str(&content).unwrap();
+        assert_eq!(cfg.clusters.len(), 2);
+        assert_eq!(cfg.current_context, None);
+        assert!(lint(Some(&path), &[], false).unwrap().issues.is_empty());
+        assert!(lint_fix(Some(&path), &[], false, false, &backups)
+            .unwrap()
+            .is_empty());
     }
 
-    if !dry_run {
-        let home = dirs_next::home_dir().ok_or(K8pkError::NoHomeDir)?;
-        let base = home.join(".local/share/k8pk");
-        for c in &total_removed {
-            let sanitized = kubeconfig::sanitize_filename(c);
-            if let Ok(entries) = fs::read_dir(&base) {
-                for entry in entries.flatten() {
-                    let fname = entry.file_name();
-                    let name = fname.to_string_lossy();
-                    if name.starts_with(&sanitized) && name.ends_with(".yaml") {
-                        if let Err(e) = fs::remove_file(entry.path()) {
-                            eprintln!(
-                                "warning: failed to remove {}: {}",
-                                entry.path().display(),
-                                e
-                            );
-                        }
-                    }
-                }
-            }
-        }
+    #[test]
+    fn test_lint_fix_across_files() {
+        // Contexts in one file, the clusters and users they use in another
+        let contexts = r#"apiVersion: v1
+kind: Config
+clusters:
+  - name: stale
+    cluster: {server: "https://stale"}
+contexts:
+  - name: dev
+    context: {cluster: dev, user: dev}
+"#;
+        let creds = r#"apiVersion: v1
+kind: Config
+clusters:
+  - name: dev
+    cluster: {server: "https://dev"}
+  - name: spare
+    cluster: {server: "https://spare"}
+users:
+  - name: dev
+    user: {token: t}
+current-context: dev
+"#;
+        let dir = tempfile::tempdir().unwrap();
+        let backups = dir.path().join("backups");
+        let a = write_kubeconfig(dir.path(), "contexts.yaml", contexts);
+        let b = write_kubeconfig(dir.path(), "creds.yaml", creds);
+        let all = [a.clone(), b.clone()];
+
+        let fixes = lint_fix(None, &all, false, true, &backups).unwrap();
+        assert_eq!(fixes.len(), 1);
+        assert_eq!(fixes[0].file, a);
+        assert_eq!(fixes[0].changes, ["removed orphaned cluster: stale"]);
+
+        // Fixing one file alone still sees the other's contexts
+        assert!(lint_fix(Some(&b), &all, false, true, &backups)
+            .unwrap()
+            .is_empty());
+
+        // While the creds file is unreadable, nothing is judged orphaned
+        fs::write(&b, "clusters: [").unwrap();
+        let fixes = lint_fix(Some(&a), &all, false, true, &backups).unwrap();
+        assert!(fixes.is_empty());
     }
-    Ok(())
 }
@@ -0,0 +1,335 @@
+//! `k8pk set-context`, `use-context`, and `view`: the `kubectl config`
+//! subcommands of the same names, applied to the kubeconfig files k8pk
+//! resolved. Edits go to the file kubectl would pick and keep its comments
+//! and formatting where possible.
+
+use super::backup_kubeconfig;
+use crate::error::{K8pkError, Result};
+use crate::kubeconfig::{self, KubeConfig, MergeStrategy, NamedItem};
+use crate::yaml_edit;
+use serde_yaml_ng::{Mapping, Value as Yaml};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Written in place of certificate and key data by `view` without `--raw`
+const DATA_OMITTED: &str = "DATA+OMITTED";
+
+#[derive(Debug, serde::Serialize)]
+pub struct SetContextResult {
+    pub context: String,
+    pub file: PathBuf,
+    pub created: bool,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct UseContextResult {
+    pub context: String,
+    pub file: PathBuf,
+}
+
+/// Fields `set-context` writes under `context:`
+#[derive(Debug, Default)]
+pub struct ContextFields {
+    pub cluster: Option<String>,
+    pub user: Option<String>,
+    pub namespace: Option<String>,
+}
+
+impl ContextFields {
+    fn pairs(&self) -> Vec<(&'static str, &str)> {
+        [
+            ("cluster", &self.cluster),
+            ("user", &self.user),
+            ("namespace", &self.namespace),
+        ]
+        .into_iter()
+        .filter_map(|(k, v)| Some((k, v.as_deref()?)))
+        .collect()
+    }
+}
+
+fn first_path(paths: &[PathBuf]) -> Result<&Path> {
+    paths
+        .first()
+        .map(PathBuf::as_path)
+        .ok_or_else(|| K8pkError::InvalidArgument("no kubeconfig files configured".into()))
+}
+
+/// Read one kubeconfig as written (no relative-path resolution); a missing
+/// file reads as empty
+fn read_file(path: &Path) -> Result<(String, KubeConfig)> {
+    let content = if path.exists() {
+        fs::read_to_string(path)?
+    } else {
+        String::new()
+    };
+    let cfg = if content.trim().is_empty() {
+        KubeConfig::default()
+    } else {
+        serde_yaml_ng::from_str(&content)?
+    };
+    Ok((content, cfg))
+}
+
+/// Lock and back up `path`, then write `cfg` over it with `edit` applied to
+/// the original text (see [`yaml_edit::render`]); a new file gets the
+/// apiVersion/kind header
+fn rewrite(
+    path: &Path,
+    backups: &Path,
+    content: &str,
+    cfg: &mut KubeConfig,
+    edit: impl FnOnce(&str) -> Option<String>,
+) -> Result<()> {
+    kubeconfig::ensure_editable(path)?;
+    let _lock = kubeconfig::lock_kubeconfig(path)?;
+    if let Some(bak) = backup_kubeconfig(path, backups)? {
+        if !crate::render::quiet() {
+            eprintln!("Backup saved to {}", bak.display());
+        }
+    }
+    if content.trim().is_empty() {
+        cfg.ensure_defaults(None);
+    }
+    let yaml = yaml_edit::render(content, cfg, edit)?;
+    kubeconfig::write_restricted(path, &yaml)
+}
+
+/// `kubectl config set-context`: set cluster, user, and/or namespace of
+/// `context` in the file that defines it, or create it in the first file
+pub fn set_context(
+    paths: &[PathBuf],
+    context: &str,
+    fields: &ContextFields,
+    backups: &Path,
+) -> Result<SetContextResult> {
+    let sources = kubeconfig::list_contexts_with_paths(paths)?;
+    let file = match sources.get(context) {
+        Some(file) => file.clone(),
+        None => first_path(paths)?.to_path_buf(),
+    };
+    let (content, mut cfg) = read_file(&file)?;
+    let pairs = fields.pairs();
+
+    let created = match cfg.contexts.iter_mut().find(|c| c.name == context) {
+        Some(item) => {
+            if !matches!(item.rest, Yaml::Mapping(_)) {
+                item.rest = Yaml::Mapping(Mapping::new());
+            }
+            let Yaml::Mapping(map) = &mut item.rest else {
+                unreachable!()
+            };
+            let inner = map
+                .entry(Yaml::from("context"))
+                .or_insert_with(|| Yaml::Mapping(Mapping::new()));
+            if !inner.is_mapping() {
+                *inner = Yaml::Mapping(Mapping::new());
+            }
+            if let Yaml::Mapping(inner) = inner {
+                for (k, v) in &pairs {
+                    inner.insert(Yaml::from(*k), Yaml::from(*v));
+                }
+            }
+            false
+        }
+        None => {
+            let inner: Mapping = pairs
+                .iter()
+                .map(|(k, v)| (Yaml::from(*k), Yaml::from(*v)))
+                .collect();
+            let mut rest = Mapping::new();
+            rest.insert(Yaml::from("context"), Yaml::Mapping(inner));
+            cfg.contexts.push(NamedItem {
+                name: context.to_string(),
+                rest: Yaml::Mapping(rest),
+            });
+            true
+        }
+    };
+
+    let new_item = cfg.contexts.last().cloned();
+    rewrite(&file, backups, &content, &mut cfg, |text| {
+        if created {
+            yaml_edit::append_item(text, "contexts", &new_item?)
+        } else {
+            yaml_edit::set_item_fields(text, "contexts", context, "context", &pairs)
+        }
+    })?;
+    Ok(SetContextResult {
+        context: context.to_string(),
+        file,
+        created,
+    })
+}
+
+/// `kubectl config use-context`: write `current-context` to the file it is
+/// read from (the first that sets one, the last under `--merge-strategy
+/// last`), or the first file when none does
+pub fn use_context(paths: &[PathBuf], context: &str, backups: &Path) -> Result<UseContextResult> {
+    let merged = kubeconfig::load_merged(paths)?;
+    if merged.find_context(context).is_none() {
+        return Err(K8pkError::ContextNotFound(context.to_string()));
+    }
+
+    let mut setters = paths.iter().filter(|p| {
+        read_file(p).is_ok_and(|(_, cfg)| cfg.current_context.is_some_and(|c| !c.is_empty()))
+    });
+    let setter = if kubeconfig::merge_strategy() == MergeStrategy::Last {
+        setters.next_back()
+    } else {
+        setters.next()
+    };
+    let file = match setter {
+        Some(file) => file.clone(),
+        None => first_path(paths)?.to_path_buf(),
+    };
+
+    let (content, mut cfg) = read_file(&file)?;
+    cfg.current_context = Some(context.to_string());
+    rewrite(&file, backups, &content, &mut cfg, |text| {
+        yaml_edit::set_current_context(text, Some(context))
+    })?;
+    Ok(UseContextResult {
+        context: context.to_string(),
+        file,
+    })
+}
+
+/// Options for [`view`], as in `kubectl config view`
+#[derive(Debug, Default)]
+pub struct ViewOptions {
+    /// Only the current context (or `context`) and its cluster and user
+    pub minify: bool,
+    /// Inline referenced certificate and key files
+    pub flatten: bool,
+    /// Keep secrets and certificate data instead of masking them
+    pub raw: bool,
+    /// Treat this context as the current one
+    pub context: Option<String>,
+}
+
+/// `kubectl config view`: the merged kubeconfig, with secrets replaced by
+/// `REDACTED` and certificate data by `DATA+OMITTED` unless `raw`
+pub fn view(paths: &[PathBuf], opts: &ViewOptions) -> Result<KubeConfig> {
+    let mut cfg = kubeconfig::load_merged(paths)?;
+    if let Some(context) = &opts.context {
+        cfg.current_context = Some(context.clone());
+    }
+    if opts.minify {
+        let current = cfg
+            .current_context
+            .clone()
+            .filter(|c| !c.is_empty())
+            .ok_or_else(|| {
+                K8pkError::InvalidArgument(
+                    "current-context is not set; pass --context to pick one to minify to".into(),
+                )
+            })?;
+        let preferences = cfg.preferences.take();
+        cfg = kubeconfig::prune_to_context(&cfg, &current)?;
+        cfg.preferences = preferences;
+    }
+    if opts.flatten {
+        kubeconfig::embed_certs(&mut cfg)?;
+    }
+    if !opts.raw {
+        kubeconfig::redact_secrets(&mut cfg);
+        omit_data(&mut cfg);
+    }
+    cfg.ensure_defaults(None);
+    Ok(cfg)
+}
+
+/// Replace every `*-data` value of clusters and users with [`DATA_OMITTED`]
+fn omit_data(cfg: &mut KubeConfig) {
+    let entries = cfg
+        .clusters
+        .iter_mut()
+        .filter_map(|c| c.rest.get_mut("cluster"))
+        .chain(cfg.users.iter_mut().filter_map(|u| u.rest.get_mut("user")));
+    for entry in entries {
+        if let Yaml::Mapping(map) = entry {
+            for (k, v) in map.iter_mut() {
+                if k.as_str().is_some_and(|k| k.ends_with("-data")) && v.is_string() {
+                    *v = Yaml::from(DATA_OMITTED);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_and_use_context_edit_the_right_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let backups = dir.path().join("backups");
+        let first = dir.path().join("first");
+        let second = dir.path().join("second");
+        fs::write(&first, "# team clusters\napiVersion: v1\nkind: Config\nclusters: []\ncontexts:\n- name: a\n  context:\n    cluster: c\n    user: u\nusers: []\n").unwrap();
+        fs::write(&second, "apiVersion: v1\nkind: Config\ncontexts:\n- name: b\n  context:\n    cluster: c\n    user: u\ncurrent-context: b\n").unwrap();
+        let paths = vec![first.clone(), second.clone()];
+
+        let fields = ContextFields {
+            namespace: Some("web".into()),
+            ..Default::default()
+        };
+        let result = set_context(&paths, "b", &fields, &backups).unwrap();
+        assert_eq!(
+            (result.file.as_path(), result.created),
+            (second.as_path(), false)
+        );
+        let result = set_context(&paths, "new", &fields, &backups).unwrap();
+        assert_eq!(
+            (result.file.as_path(), result.created),
+            (first.as_path(), true)
+        );
+        let text = fs::read_to_string(&first).unwrap();
+        assert!(text.starts_with("# team clusters\n"));
+
+        // current-context lives in `second`, so that is where it changes
+        assert_eq!(use_context(&paths, "new", &backups).unwrap().file, second);
+        let merged = kubeconfig::load_merged(&paths).unwrap();
+        assert_eq!(merged.current_context.as_deref(), Some("new"));
+        assert_eq!(
+            kubeconfig::get_namespace_for_context(&merged, "b").as_deref(),
+            Some("web")
+        );
+        assert!(use_context(&paths, "missing", &backups).is_err());
+    }
+
+    #[test]
+    fn view_masks_unless_raw() {
+        let dir = tempfile::tempdir().unwrap();
+        let kc = dir.path().join("config");
+        fs::write(&kc, "clusters:\n- name: c\n  cluster:\n    server: https://c\n    certificate-authority-data: Q0E=\n- name: other\n  cluster:\n    server: https://o\ncontexts:\n- name: dev\n  context:\n    cluster: c\n    user: u\ncurrent-context: dev\nusers:\n- name: u\n  user:\n    token: secret\n").unwrap();
+        let paths = vec![kc];
+
+        let masked = view(
+            &paths,
+            &ViewOptions {
+                minify: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let out = serde_yaml_ng::to_string(&masked).unwrap();
+        assert!(out.contains("certificate-authority-data: DATA+OMITTED"));
+        assert!(out.contains("token: REDACTED"));
+        assert_eq!(masked.clusters.len(), 1);
+
+        let raw = view(
+            &paths,
+            &ViewOptions {
+                raw: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let out = serde_yaml_ng::to_string(&raw).unwrap();
+        assert!(out.contains("token: secret") && out.contains("Q0E="));
+        assert_eq!(raw.clusters.len(), 2);
+    }
+}
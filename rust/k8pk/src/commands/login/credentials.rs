@@ -0,0 +1,300 @@
+//! Password-manager credential sources for `k8pk login`: pass
+//! (password-store), 1Password (`op`), and Bitwarden (`bw`). Each entry is
+//! read into the same field map (`token`, `username`, `password`,
+//! `__password__` for the entry's main secret, `rancher_auth_provider`), so
+//! [`apply_credentials`] works the same for all of them.
+
+use super::AuthMode;
+use crate::error::{K8pkError, Result};
+use std::collections::HashMap;
+use std::process::Command;
+
+/// Where a login reads its credentials from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CredentialSource {
+    /// `pass show ENTRY`
+    Pass(String),
+    /// `op item get ITEM` (name, ID, or share link)
+    OnePassword(String),
+    /// `bw get item ITEM` (needs an unlocked vault: `BW_SESSION`)
+    Bitwarden(String),
+}
+
+impl CredentialSource {
+    /// The source chosen with `--pass-entry`, `--op-item`, or `--bw-item`
+    pub fn from_flags(
+        pass_entry: Option<String>,
+        op_item: Option<String>,
+        bw_item: Option<String>,
+    ) -> Option<Self> {
+        pass_entry
+            .map(Self::Pass)
+            .or(op_item.map(Self::OnePassword))
+            .or(bw_item.map(Self::Bitwarden))
+    }
+
+    /// CLI the source shells out to
+    fn program(&self) -> &'static str {
+        match self {
+            Self::Pass(_) => "pass",
+            Self::OnePassword(_) => "op",
+            Self::Bitwarden(_) => "bw",
+        }
+    }
+
+    fn flag(&self) -> &'static str {
+        match self {
+            Self::Pass(_) => "--pass-entry",
+            Self::OnePassword(_) => "--op-item",
+            Self::Bitwarden(_) => "--bw-item",
+        }
+    }
+
+    /// Read the entry's fields
+    pub fn fetch(&self) -> Result<HashMap<String, String>> {
+        let program = self.program();
+        if which::which(program).is_err() {
+            return Err(K8pkError::CommandFailed(format!(
+                "{} not found on PATH. Install it or omit {}.",
+                program,
+                self.flag()
+            )));
+        }
+        let mut cmd = Command::new(program);
+        let entry = match self {
+            Self::Pass(entry) => {
+                cmd.args(["show", entry]);
+                entry
+            }
+            Self::OnePassword(item) => {
+                cmd.args(["item", "get", item, "--format", "json"]);
+                item
+            }
+            Self::Bitwarden(item) => {
+                cmd.args(["get", "item", item]);
+                item
+            }
+        };
+        let output = cmd.output()?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let reason = stderr.lines().next().unwrap_or_default().trim();
+            return Err(K8pkError::CommandFailed(format!(
+                "{} could not read {}{}{}",
+                program,
+                entry,
+                if reason.is_empty() { "" } else { ": " },
+                reason
+            )));
+        }
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        match self {
+            Self::Pass(_) => Ok(parse_pass_store_output(&stdout)),
+            Self::OnePassword(_) => Ok(parse_op_item(&serde_json::from_str(&stdout)?)),
+            Self::Bitwarden(_) => Ok(parse_bw_item(&serde_json::from_str(&stdout)?)),
+        }
+    }
+}
+
+/// Fields of a `pass show` entry: the first line as `__password__`, then
+/// `key: value` lines with lowercase keys
+fn parse_pass_store_output(stdout: &str) -> HashMap<String, String> {
+    let mut values: HashMap<String, String> = HashMap::new();
+    for (i, line) in stdout.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        if i == 0 {
+            values.insert("__password__".to_string(), trimmed.to_string());
+            continue;
+        }
+        if let Some((k, v)) = trimmed.split_once(':') {
+            values.insert(k.trim().to_lowercase(), v.trim().to_string());
+        }
+    }
+    values
+}
+
+/// Fill whichever of token, username, and password are still unset from
+/// the entry's `values`, following `auth_mode`; Rancher logins also take the
+/// auth provider from it
+pub(super) fn apply_credentials(
+    token: &mut Option<String>,
+    username: &mut Option<String>,
+    password: &mut Option<String>,
+    values: &HashMap<String, String>,
+    auth_mode: AuthMode,
+    rancher_auth_provider: Option<&mut String>,
+) {
+    let user_key = values
+        .get("username")
+        .or_else(|| values.get("user"))
+        .cloned();
+
+    match auth_mode {
+        AuthMode::Token => {
+            if token.is_none() {
+                if let Some(t) = values.get("token") {
+                    *token = Some(t.to_string());
+                } else if let Some(p) = values.get("__password__") {
+                    *token = Some(p.to_string());
+                }
+            }
+        }
+        AuthMode::UserPass => {
+            if username.is_none() {
+                if let Some(u) = user_key.clone() {
+                    *username = Some(u);
+                }
+            }
+            if password.is_none() {
+                if let Some(p) = values
+                    .get("password")
+                    .or_else(|| values.get("__password__"))
+                {
+                    *password = Some(p.to_string());
+                }
+            }
+        }
+        AuthMode::Auto => {
+            if username.is_none() {
+                if let Some(u) = user_key.clone() {
+                    *username = Some(u);
+                }
+            }
+
+            if token.is_none() {
+                if let Some(t) = values.get("token") {
+                    *token = Some(t.to_string());
+                }
+            }
+
+            if password.is_none() {
+                if user_key.is_some() || username.is_some() {
+                    if let Some(p) = values
+                        .get("password")
+                        .or_else(|| values.get("__password__"))
+                    {
+                        *password = Some(p.to_string());
+                    }
+                } else if token.is_none() {
+                    if let Some(p) = values.get("__password__") {
+                        *token = Some(p.to_string());
+                    }
+                }
+            }
+        }
+        AuthMode::ClientCert | AuthMode::Exec | AuthMode::Gssapi => {}
+    }
+
+    if let Some(r) = rancher_auth_provider {
+        if let Some(v) = values
+            .get("rancher_auth_provider")
+            .or_else(|| values.get("rancher_provider"))
+        {
+            let trimmed = v.trim();
+            if !trimmed.is_empty() {
+                *r = trimmed.to_string();
+            }
+        }
+    }
+}
+
+/// Fields of `op item get --format json`: the USERNAME and PASSWORD purpose
+/// fields, plus every field by lowercase label (e.g. a `token` field)
+fn parse_op_item(item: &serde_json::Value) -> HashMap<String, String> {
+    let mut values = HashMap::new();
+    for field in item["fields"].as_array().into_iter().flatten() {
+        let Some(value) = field["value"].as_str().filter(|v| !v.is_empty()) else {
+            continue;
+        };
+        match field["purpose"].as_str() {
+            Some("USERNAME") => {
+                values.insert("username".to_string(), value.to_string());
+            }
+            Some("PASSWORD") => {
+                values.insert("__password__".to_string(), value.to_string());
+            }
+            _ => {}
+        }
+        if let Some(label) = field["label"].as_str() {
+            values
+                .entry(label.trim().to_lowercase())
+                .or_insert_with(|| value.to_string());
+        }
+    }
+    values
+}
+
+/// Fields of `bw get item`: the login's username and password, plus custom
+/// fields by lowercase name (e.g. a `token` field)
+fn parse_bw_item(item: &serde_json::Value) -> HashMap<String, String> {
+    let mut values = HashMap::new();
+    let login = &item["login"];
+    if let Some(user) = login["username"].as_str().filter(|v| !v.is_empty()) {
+        values.insert("username".to_string(), user.to_string());
+    }
+    if let Some(pass) = login["password"].as_str().filter(|v| !v.is_empty()) {
+        values.insert("__password__".to_string(), pass.to_string());
+        values.insert("password".to_string(), pass.to_string());
+    }
+    for field in item["fields"].as_array().into_iter().flatten() {
+        if let (Some(name), Some(value)) = (field["name"].as_str(), field["value"].as_str()) {
+            values.insert(name.trim().to_lowercase(), value.to_string());
+        }
+    }
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pass_store_output() {
+        let m = parse_pass_store_output(
+            "firstline\nusername: alice\npassword: bob\nrancher_auth_provider: openldap\n",
+        );
+        assert_eq!(m.get("__password__").map(|s| s.as_str()), Some("firstline"));
+        assert_eq!(m.get("username").map(|s| s.as_str()), Some("alice"));
+        assert_eq!(m.get("password").map(|s| s.as_str()), Some("bob"));
+        assert_eq!(
+            m.get("rancher_auth_provider").map(|s| s.as_str()),
+            Some("openldap")
+        );
+        let m2 = parse_pass_store_output("x\nrancher_provider: activedirectory\n");
+        assert_eq!(
+            m2.get("rancher_provider").map(|s| s.as_str()),
+            Some("activedirectory")
+        );
+    }
+
+    #[test]
+    fn test_parse_op_and_bw_items() {
+        let op = parse_op_item(&serde_json::json!({"fields": [
+            {"id": "username", "purpose": "USERNAME", "label": "username", "value": "alice"},
+            {"id": "password", "purpose": "PASSWORD", "label": "password", "value": "s3cret"},
+            {"id": "x1", "label": "Token", "value": "tok"},
+            {"id": "notesPlain", "purpose": "NOTES", "label": "notesPlain"}
+        ]}));
+        assert_eq!(op["username"], "alice");
+        assert_eq!(op["__password__"], "s3cret");
+        assert_eq!(op["password"], "s3cret");
+        assert_eq!(op["token"], "tok");
+        assert!(!op.contains_key("notesplain"));
+
+        let bw = parse_bw_item(&serde_json::json!({
+            "login": {"username": "bob", "password": "pw"},
+            "fields": [{"name": "rancher_auth_provider", "value": "openldap", "type": 0}]
+        }));
+        assert_eq!(bw["username"], "bob");
+        assert_eq!(bw["__password__"], "pw");
+        assert_eq!(bw["rancher_auth_provider"], "openldap");
+
+        let mut token = None;
+        let (mut user, mut pass) = (None, None);
+        apply_credentials(&mut token, &mut user, &mut pass, &bw, AuthMode::Token, None);
+        assert_eq!(token.as_deref(), Some("pw"));
+    }
+}
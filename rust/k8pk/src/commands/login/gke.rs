@@ -41,6 +41,9 @@ pub(super) fn gke_login(req: &LoginRequest) -> Result<LoginResult> {
             context_name,
             namespace: None,
             kubeconfig_path: None,
+            imported: Vec::new(),
+            auth_mode: None,
+            expires_at: None,
         });
     }
 
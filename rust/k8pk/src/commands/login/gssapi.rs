@@ -0,0 +1,170 @@
+//! Kerberos (SPNEGO) login for clusters behind a Kerberos-authenticated
+//! proxy. kubectl can't speak Negotiate, so `--auth gssapi` writes an exec
+//! credential plugin that runs `k8pk gssapi-token --url URL`: it asks curl
+//! to fetch a bearer token from URL with the user's Kerberos ticket and
+//! hands it to kubectl as an ExecCredential.
+
+use crate::error::{K8pkError, Result};
+use serde_json::Value as Json;
+use std::process::{Command, Stdio};
+
+use super::ExecAuthConfig;
+
+/// Hidden subcommand the kubeconfig's exec plugin runs
+pub const TOKEN_COMMAND: &str = "gssapi-token";
+
+const DEFAULT_API_VERSION: &str = "client.authentication.k8s.io/v1beta1";
+
+/// Exec plugin config that trades a Kerberos ticket for a token at `url`
+pub(super) fn exec_auth(url: &str) -> ExecAuthConfig {
+    ExecAuthConfig {
+        command: Some("k8pk".to_string()),
+        args: vec![
+            TOKEN_COMMAND.to_string(),
+            "--url".to_string(),
+            url.to_string(),
+        ],
+        env: Vec::new(),
+        api_version: Some(DEFAULT_API_VERSION.to_string()),
+    }
+}
+
+/// True for a kubeconfig user that logs in through [`exec_auth`]
+pub fn is_gssapi_user(user: &serde_yaml_ng::Value) -> bool {
+    user.get("user")
+        .and_then(|u| u.get("exec"))
+        .and_then(|e| e.get("args"))
+        .and_then(|a| a.as_sequence())
+        .and_then(|a| a.first())
+        .and_then(|a| a.as_str())
+        == Some(TOKEN_COMMAND)
+}
+
+/// Token and expiry (RFC 3339) from the token endpoint's response: JSON
+/// with `token`, `access_token`, or `id_token` (optionally `expires_in`
+/// seconds or an `expirationTimestamp`), or the bare token
+fn parse_token_response(body: &str, now: u64) -> Result<(String, Option<String>)> {
+    let body = body.trim();
+    let Ok(json) = serde_json::from_str::<Json>(body) else {
+        if body.is_empty() || body.contains(char::is_whitespace) {
+            return Err(K8pkError::LoginFailed(
+                "token endpoint returned neither JSON nor a bare token".into(),
+            ));
+        }
+        return Ok((body.to_string(), None));
+    };
+    // An ExecCredential from another plugin keeps its fields under status
+    let json = json.get("status").unwrap_or(&json);
+    let token = ["token", "access_token", "id_token"]
+        .iter()
+        .find_map(|k| json.get(k)?.as_str())
+        .ok_or_else(|| {
+            K8pkError::LoginFailed("no token in the token endpoint's response".into())
+        })?;
+    let expiry = json
+        .get("expirationTimestamp")
+        .and_then(Json::as_str)
+        .map(String::from)
+        .or_else(|| {
+            Some(crate::clock::rfc3339(
+                now + json.get("expires_in")?.as_u64()?,
+            ))
+        });
+    Ok((token.to_string(), expiry))
+}
+
+/// ExecCredential JSON for kubectl. The apiVersion follows the one kubectl
+/// sends in KUBERNETES_EXEC_INFO.
+fn exec_credential(token: &str, expiry: Option<&str>, exec_info: Option<&str>) -> Json {
+    let mut status = serde_json::json!({ "token": token });
+    if let Some(expiry) = expiry {
+        status["expirationTimestamp"] = Json::from(expiry);
+    }
+    exec_credential_with(status, exec_info)
+}
+
+/// ExecCredential JSON around `status`, in kubectl's apiVersion
+pub(crate) fn exec_credential_with(status: Json, exec_info: Option<&str>) -> Json {
+    let api_version = exec_info
+        .and_then(|info| serde_json::from_str::<Json>(info).ok())
+        .and_then(|info| info.get("apiVersion")?.as_str().map(String::from))
+        .unwrap_or_else(|| DEFAULT_API_VERSION.to_string());
+    serde_json::json!({
+        "apiVersion": api_version,
+        "kind": "ExecCredential",
+        "status": status,
+    })
+}
+
+/// `k8pk gssapi-token --url URL`: print an ExecCredential for kubectl
+pub fn print_exec_credential(url: &str) -> Result<()> {
+    let output = Command::new("curl")
+        .args(["--negotiate", "-u", ":", "-fsS", "--max-time", "30", url])
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| K8pkError::CommandFailed(format!("curl: {}", e)))?;
+    if !output.status.success() {
+        return Err(K8pkError::LoginFailed(format!(
+            "Kerberos token request to {} failed: {} (is your ticket valid? run: kinit)",
+            url,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    let now = crate::clock::now_secs();
+    let (token, expiry) = parse_token_response(&String::from_utf8_lossy(&output.stdout), now)?;
+    let exec_info = std::env::var("KUBERNETES_EXEC_INFO").ok();
+    println!(
+        "{}",
+        exec_credential(&token, expiry.as_deref(), exec_info.as_deref())
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_response_and_exec_credential() {
+        assert_eq!(
+            parse_token_response("abc.def\n", 0).unwrap(),
+            ("abc.def".to_string(), None)
+        );
+        assert_eq!(
+            parse_token_response(
+                r#"{"access_token": "t", "expires_in": 3600}"#,
+                1_700_000_000
+            )
+            .unwrap(),
+            ("t".to_string(), Some("2023-11-14T23:13:20Z".to_string()))
+        );
+        assert_eq!(
+            parse_token_response(
+                r#"{"status": {"token": "t", "expirationTimestamp": "2030-01-01T00:00:00Z"}}"#,
+                0
+            )
+            .unwrap()
+            .1
+            .as_deref(),
+            Some("2030-01-01T00:00:00Z")
+        );
+        assert!(parse_token_response("<html>login</html> page", 0).is_err());
+        assert!(parse_token_response(r#"{"error": "denied"}"#, 0).is_err());
+
+        let cred = exec_credential(
+            "t",
+            None,
+            Some(r#"{"apiVersion": "client.authentication.k8s.io/v1"}"#),
+        );
+        assert_eq!(cred["apiVersion"], "client.authentication.k8s.io/v1");
+        assert_eq!(cred["status"]["token"], "t");
+        assert!(cred["status"].get("expirationTimestamp").is_none());
+
+        let user: serde_yaml_ng::Value = serde_yaml_ng::to_value(serde_json::json!({
+            "user": {"exec": {"command": "k8pk", "args": ["gssapi-token", "--url", "u"]}}
+        }))
+        .unwrap();
+        assert!(is_gssapi_user(&user));
+        assert_eq!(exec_auth("u").args, ["gssapi-token", "--url", "u"]);
+    }
+}
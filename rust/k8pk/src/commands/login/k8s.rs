@@ -1,6 +1,9 @@
 //! Generic Kubernetes login (covers EKS, AKS, and plain K8s clusters)
 
 use crate::error::{K8pkError, Result};
+use crate::i18n::tr;
+use crate::kubeconfig;
+use crate::prompt::Ask;
 use inquire::{Password, Text};
 
 use super::{
@@ -50,18 +53,13 @@ pub(super) fn k8s_login(req: &LoginRequest) -> Result<LoginResult> {
         let mut final_password = req.password.clone();
 
         if final_username.is_none() {
-            final_username = Some(
-                Text::new("Username:")
-                    .prompt()
-                    .map_err(|_| K8pkError::Cancelled)?,
-            );
+            final_username = Some(Text::new(&tr("prompt-username")).ask()?);
         }
         if final_password.is_none() {
             final_password = Some(
-                Password::new("Password:")
+                Password::new(&tr("prompt-password"))
                     .without_confirmation()
-                    .prompt()
-                    .map_err(|_| K8pkError::Cancelled)?,
+                    .ask()?,
             );
         }
 
@@ -92,7 +90,7 @@ pub(super) fn k8s_login(req: &LoginRequest) -> Result<LoginResult> {
         );
     }
 
-    if auth_mode == AuthMode::Exec {
+    if matches!(auth_mode, AuthMode::Exec | AuthMode::Gssapi) {
         let exec_cfg = build_exec_auth(&req.exec)?;
         user_map.insert(serde_yaml_ng::Value::String("exec".to_string()), exec_cfg);
     }
@@ -106,12 +104,29 @@ pub(super) fn k8s_login(req: &LoginRequest) -> Result<LoginResult> {
     );
     cfg.ensure_defaults(None);
 
-    write_login_kubeconfig(
+    if let Some(ref data) = req.ca_data {
+        if let Some(serde_yaml_ng::Value::Mapping(cluster)) =
+            cfg.clusters[0].rest.get_mut("cluster")
+        {
+            cluster.remove("insecure-skip-tls-verify");
+            cluster.insert(
+                serde_yaml_ng::Value::String("certificate-authority-data".to_string()),
+                serde_yaml_ng::Value::String(data.clone()),
+            );
+        }
+    }
+    if let Some(ref ns) = req.namespace {
+        kubeconfig::set_context_namespace(&mut cfg, &context_name, ns)?;
+    }
+
+    let mut result = write_login_kubeconfig(
         &kubeconfig_path,
         &cfg,
         &context_name,
         req.dry_run,
         req.test,
         req.test_timeout,
-    )
+    )?;
+    result.namespace = req.namespace.clone();
+    Ok(result)
 }
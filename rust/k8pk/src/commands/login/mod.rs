@@ -1,12 +1,21 @@
 //! Login commands for different cluster types
 
+mod credentials;
 mod gke;
+mod gssapi;
 mod k8s;
 mod ocp;
+mod profiles;
 mod rancher;
+mod teleport;
 
 use crate::error::{K8pkError, Result};
+use crate::i18n::{tr, tr_with};
 use crate::kubeconfig::{self, KubeConfig};
+use crate::prompt::Ask;
+pub use credentials::CredentialSource;
+pub(crate) use gssapi::exec_credential_with;
+pub use gssapi::{is_gssapi_user, print_exec_credential as gssapi_token};
 use inquire::{Confirm, Password, Select, Text};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -151,6 +160,16 @@ pub struct LoginResult {
     pub context_name: String,
     pub namespace: Option<String>,
     pub kubeconfig_path: Option<PathBuf>,
+    /// Clusters imported when logging in to a Rancher server URL (one kubeconfig each)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub imported: Vec<PulledCluster>,
+    /// How k8pk authenticated: token, userpass, client-cert, or exec
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auth_mode: Option<String>,
+    /// Unix time the written credentials expire (client certificate or JWT
+    /// token), when they say
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u64>,
 }
 
 /// Authentication mode for login
@@ -161,6 +180,8 @@ pub enum AuthMode {
     UserPass,
     ClientCert,
     Exec,
+    /// Kerberos ticket traded for a token by a k8pk exec plugin
+    Gssapi,
 }
 
 impl AuthMode {
@@ -171,6 +192,7 @@ impl AuthMode {
             AuthMode::UserPass => "userpass",
             AuthMode::ClientCert => "client-cert",
             AuthMode::Exec => "exec",
+            AuthMode::Gssapi => "gssapi",
         }
     }
 }
@@ -185,8 +207,9 @@ impl std::str::FromStr for AuthMode {
             "userpass" | "basic" => Ok(AuthMode::UserPass),
             "client-cert" | "cert" => Ok(AuthMode::ClientCert),
             "exec" => Ok(AuthMode::Exec),
+            "gssapi" | "kerberos" | "spnego" => Ok(AuthMode::Gssapi),
             _ => Err(K8pkError::InvalidArgument(format!(
-                "unknown auth mode: '{}'. Use: auto, token, userpass, client-cert, exec",
+                "unknown auth mode: '{}'. Use: auto, token, userpass, client-cert, exec, gssapi",
                 s
             ))),
         }
@@ -205,8 +228,13 @@ pub struct LoginRequest {
     pub output_dir: Option<PathBuf>,
     pub insecure: bool,
     pub use_vault: bool,
-    pub pass_entry: Option<String>,
+    /// Password manager entry to read missing credentials from
+    pub credentials: Option<CredentialSource>,
     pub certificate_authority: Option<PathBuf>,
+    /// Base64 PEM written as `certificate-authority-data` (k8s)
+    pub ca_data: Option<String>,
+    /// Default namespace for the new context (k8s)
+    pub namespace: Option<String>,
     pub client_certificate: Option<PathBuf>,
     pub client_key: Option<PathBuf>,
     pub auth: String,
@@ -279,6 +307,7 @@ impl LoginRequest {
 /// Login to a cluster based on type.
 /// If credentials are missing and stdin is a TTY, prompts interactively.
 pub fn login(req: &LoginRequest) -> Result<LoginResult> {
+    crate::config::require_online("login")?;
     let login_type = req
         .login_type
         .ok_or_else(|| K8pkError::InvalidArgument("login type is required".into()))?;
@@ -289,15 +318,16 @@ pub fn login(req: &LoginRequest) -> Result<LoginResult> {
         auth_mode = AuthMode::Exec;
     }
 
-    if let Some(ref entry) = req.pass_entry.clone() {
-        apply_pass_credentials(
+    if let Some(source) = req.credentials.clone() {
+        let values = source.fetch()?;
+        credentials::apply_credentials(
             &mut req.token,
             &mut req.username,
             &mut req.password,
-            entry,
+            &values,
             auth_mode,
             Some(&mut req.rancher_auth_provider),
-        )?;
+        );
     }
 
     let has_creds = req.token.is_some()
@@ -306,17 +336,18 @@ pub fn login(req: &LoginRequest) -> Result<LoginResult> {
         || req.client_certificate.is_some()
         || req.exec.command.is_some();
 
-    if !has_creds && std::io::stdin().is_terminal() && login_type != LoginType::Gke {
+    if !has_creds && crate::prompt::can_prompt() && login_type != LoginType::Gke {
         let needs_prompt = match auth_mode {
             AuthMode::Auto | AuthMode::Token | AuthMode::UserPass => true,
-            AuthMode::ClientCert | AuthMode::Exec => false,
+            AuthMode::ClientCert | AuthMode::Exec | AuthMode::Gssapi => false,
         };
         if needs_prompt {
             let mode = if auth_mode == AuthMode::Auto {
-                let choice =
-                    Select::new("Authentication method:", vec!["token", "username/password"])
-                        .prompt()
-                        .map_err(|_| K8pkError::Cancelled)?;
+                let choice = Select::new(
+                    &tr("prompt-auth-method"),
+                    vec!["token", "username/password"],
+                )
+                .ask()?;
                 if choice == "token" {
                     AuthMode::Token
                 } else {
@@ -329,24 +360,18 @@ pub fn login(req: &LoginRequest) -> Result<LoginResult> {
             match mode {
                 AuthMode::Token => {
                     req.token = Some(
-                        Password::new("Token:")
+                        Password::new(&tr("prompt-token"))
                             .without_confirmation()
-                            .prompt()
-                            .map_err(|_| K8pkError::Cancelled)?,
+                            .ask()?,
                     );
                     auth_mode = AuthMode::Token;
                 }
                 AuthMode::UserPass | AuthMode::Auto => {
-                    req.username = Some(
-                        Text::new("Username:")
-                            .prompt()
-                            .map_err(|_| K8pkError::Cancelled)?,
-                    );
+                    req.username = Some(Text::new(&tr("prompt-username")).ask()?);
                     req.password = Some(
-                        Password::new("Password:")
+                        Password::new(&tr("prompt-password"))
                             .without_confirmation()
-                            .prompt()
-                            .map_err(|_| K8pkError::Cancelled)?,
+                            .ask()?,
                     );
                     auth_mode = AuthMode::UserPass;
                 }
@@ -368,15 +393,50 @@ pub fn login(req: &LoginRequest) -> Result<LoginResult> {
         req.exec.command.as_deref(),
     )?;
 
-    match login_type {
-        LoginType::Ocp => ocp::ocp_login(&req),
-        LoginType::K8s => k8s::k8s_login(&req),
-        LoginType::Gke => gke::gke_login(&req),
-        LoginType::Rancher => rancher::rancher_login(&req),
+    let mut result = match login_type {
+        LoginType::Ocp => ocp::ocp_login(&req)?,
+        LoginType::K8s => k8s::k8s_login(&req)?,
+        LoginType::Gke => gke::gke_login(&req)?,
+        LoginType::Rancher => rancher::rancher_login(&req)?,
+    };
+    result.auth_mode = effective_auth(&req, login_type, auth_mode).map(|m| m.as_str().to_string());
+    if let Some(path) = &result.kubeconfig_path {
+        result.expires_at = credential_expires_at(path, &result.context_name);
+    }
+    Ok(result)
+}
+
+/// The auth mode a login ended up using: `auto` resolved from the
+/// credentials given (GKE always goes through gke-gcloud-auth-plugin)
+fn effective_auth(req: &LoginRequest, login_type: LoginType, mode: AuthMode) -> Option<AuthMode> {
+    if mode == AuthMode::Gssapi {
+        return Some(mode);
+    }
+    if login_type == LoginType::Gke || req.exec.command.is_some() {
+        return Some(AuthMode::Exec);
     }
+    match mode {
+        AuthMode::Auto if req.client_certificate.is_some() => Some(AuthMode::ClientCert),
+        AuthMode::Auto if req.token.is_some() => Some(AuthMode::Token),
+        AuthMode::Auto if req.username.is_some() => Some(AuthMode::UserPass),
+        AuthMode::Auto => None,
+        mode => Some(mode),
+    }
+}
+
+/// Earliest expiry of the credentials `context` uses in the kubeconfig at
+/// `path`: its client certificate or JWT token
+fn credential_expires_at(path: &Path, context: &str) -> Option<u64> {
+    let cfg: KubeConfig = serde_yaml_ng::from_str(&fs::read_to_string(path).ok()?).ok()?;
+    let (_, user) = kubeconfig::extract_context_refs(&cfg.find_context(context)?.rest).ok()?;
+    crate::commands::report::credential_expiry(cfg.find_user(&user)?, path.parent()?)
+        .into_iter()
+        .map(|(_, at)| at)
+        .min()
 }
 
 pub use rancher::PulledCluster;
+pub(crate) use teleport::ensure_tsh_session;
 
 #[allow(clippy::too_many_arguments)]
 pub fn rancher_pull(
@@ -391,6 +451,7 @@ pub fn rancher_pull(
     pattern: Option<&str>,
     quiet: bool,
 ) -> Result<Vec<PulledCluster>> {
+    crate::config::require_online("rancher pull")?;
     let (base, _) = rancher::rancher_server_base_url(server);
     let vault_key = format!("rancher:{}", base);
 
@@ -404,16 +465,13 @@ pub fn rancher_pull(
     } else if username.is_some() || password.is_some() {
         let u = match username {
             Some(u) => u.to_string(),
-            None => Text::new("Rancher username:")
-                .prompt()
-                .map_err(|_| K8pkError::Cancelled)?,
+            None => Text::new(&tr("prompt-rancher-username")).ask()?,
         };
         let p = match password {
             Some(p) => p.to_string(),
-            None => Password::new("Rancher password:")
+            None => Password::new(&tr("prompt-rancher-password"))
                 .without_confirmation()
-                .prompt()
-                .map_err(|_| K8pkError::Cancelled)?,
+                .ask()?,
         };
         if !quiet {
             eprintln!("Authenticating with Rancher API...");
@@ -423,7 +481,7 @@ pub fn rancher_pull(
         used_username = Some(u);
         used_password = Some(p);
         tok
-    } else if !use_vault && !std::io::stdin().is_terminal() {
+    } else if !use_vault && !crate::prompt::can_prompt() {
         return Err(K8pkError::InvalidArgument(
             "Rancher credentials required: pass --token, or -u/-p, or run interactively".into(),
         ));
@@ -432,8 +490,8 @@ pub fn rancher_pull(
             &[&vault_key],
             use_vault,
             quiet,
-            "Rancher username:",
-            "Rancher password:",
+            &tr("prompt-rancher-username"),
+            &tr("prompt-rancher-password"),
         )?;
         if let Some(prov) = provider {
             used_provider = prov;
@@ -499,9 +557,27 @@ pub fn apply_exec_preset(
                 server_id.to_string(),
             ];
         }
+        "teleport" => {
+            let cluster = cluster.ok_or_else(|| {
+                K8pkError::InvalidArgument(
+                    "teleport preset requires --exec-cluster (the Teleport kube cluster)".into(),
+                )
+            })?;
+            exec.command = Some("tsh".to_string());
+            exec.args = vec![
+                "kube".to_string(),
+                "credentials".to_string(),
+                format!("--kube-cluster={}", cluster),
+            ];
+            // server_id doubles as the Teleport cluster name for leaf clusters
+            if let Some(teleport_cluster) = server_id {
+                exec.args
+                    .push(format!("--teleport-cluster={}", teleport_cluster));
+            }
+        }
         _ => {
             return Err(K8pkError::InvalidArgument(format!(
-                "unknown exec preset: '{}'. Use: aws-eks, gke, aks",
+                "unknown exec preset: '{}'. Use: aws-eks, gke, aks, teleport",
                 preset
             )));
         }
@@ -509,15 +585,15 @@ pub fn apply_exec_preset(
     Ok(())
 }
 
-pub fn login_wizard() -> Result<LoginResult> {
-    let login_type = Select::new("Cluster type:", vec!["ocp", "k8s", "gke", "rancher"])
-        .prompt()
-        .map_err(|_| K8pkError::Cancelled)?;
+pub fn login_wizard(quiet: bool) -> Result<LoginResult> {
+    let login_type = Select::new(
+        &tr("prompt-cluster-type"),
+        vec!["ocp", "k8s", "gke", "rancher"],
+    )
+    .ask()?;
 
     if login_type == "ocp" && !kubeconfig::oc_available() {
-        let path = Text::new("Path to oc (not on PATH):")
-            .prompt()
-            .map_err(|_| K8pkError::Cancelled)?;
+        let path = Text::new(&tr("prompt-oc-path")).ask()?;
         let path = path.trim();
         if path.is_empty() {
             return Err(K8pkError::CommandFailed(
@@ -528,9 +604,7 @@ pub fn login_wizard() -> Result<LoginResult> {
         std::env::set_var("K8PK_OC", path);
     }
 
-    let server = Text::new("Server URL:")
-        .prompt()
-        .map_err(|_| K8pkError::Cancelled)?;
+    let server = Text::new(&tr("prompt-server")).ask()?;
 
     let auth_choices = match login_type {
         "ocp" => vec!["token", "userpass"],
@@ -538,99 +612,81 @@ pub fn login_wizard() -> Result<LoginResult> {
         "rancher" => vec!["token", "userpass"],
         _ => vec!["token", "userpass", "client-cert", "exec"],
     };
-    let auth = Select::new("Authentication method:", auth_choices)
-        .prompt()
-        .map_err(|_| K8pkError::Cancelled)?;
+    let auth = Select::new(&tr("prompt-auth-method"), auth_choices).ask()?;
 
     let mut token = None;
     let mut username = None;
     let mut password = None;
-    let mut pass_entry = None;
+    let mut credential_source = None;
     let mut client_certificate = None;
     let mut client_key = None;
     let mut certificate_authority = None;
     let mut exec = ExecAuthConfig::default();
     let mut auth_mode = auth;
 
-    if (auth == "token" || auth == "userpass")
-        && Confirm::new("Use pass (password-store)?")
-            .with_default(false)
-            .prompt()
-            .unwrap_or(false)
-    {
-        pass_entry = Some(
-            Text::new("pass entry name:")
-                .prompt()
-                .map_err(|_| K8pkError::Cancelled)?,
-        );
+    if auth == "token" || auth == "userpass" {
+        let type_in = tr("credentials-type-in");
+        let source = Select::new(
+            &tr("prompt-credentials"),
+            vec![
+                type_in.as_str(),
+                "pass (password-store)",
+                "1Password (op)",
+                "Bitwarden (bw)",
+            ],
+        )
+        .ask()?;
+        credential_source = match source {
+            "pass (password-store)" => Some(CredentialSource::Pass(
+                Text::new(&tr("prompt-pass-entry")).ask()?,
+            )),
+            "1Password (op)" => Some(CredentialSource::OnePassword(
+                Text::new(&tr("prompt-op-item")).ask()?,
+            )),
+            "Bitwarden (bw)" => Some(CredentialSource::Bitwarden(
+                Text::new(&tr("prompt-bw-item")).ask()?,
+            )),
+            _ => None,
+        };
     }
 
     match auth {
-        "token" => {
-            if pass_entry.is_none() {
-                token = Some(
-                    Password::new("Token:")
-                        .without_confirmation()
-                        .prompt()
-                        .map_err(|_| K8pkError::Cancelled)?,
-                );
-            }
+        "token" if credential_source.is_none() => {
+            token = Some(
+                Password::new(&tr("prompt-token"))
+                    .without_confirmation()
+                    .ask()?,
+            );
         }
-        "userpass" => {
-            if pass_entry.is_none() {
-                username = Some(
-                    Text::new("Username:")
-                        .prompt()
-                        .map_err(|_| K8pkError::Cancelled)?,
-                );
-                password = Some(
-                    Password::new("Password:")
-                        .without_confirmation()
-                        .prompt()
-                        .map_err(|_| K8pkError::Cancelled)?,
-                );
-            }
+        "userpass" if credential_source.is_none() => {
+            username = Some(Text::new(&tr("prompt-username")).ask()?);
+            password = Some(
+                Password::new(&tr("prompt-password"))
+                    .without_confirmation()
+                    .ask()?,
+            );
         }
         "client-cert" => {
-            client_certificate = Some(
-                Text::new("Client certificate path:")
-                    .prompt()
-                    .map_err(|_| K8pkError::Cancelled)?,
-            );
-            client_key = Some(
-                Text::new("Client key path:")
-                    .prompt()
-                    .map_err(|_| K8pkError::Cancelled)?,
-            );
-            let ca = Text::new("Certificate authority path (optional):")
-                .with_default("")
-                .prompt()
-                .map_err(|_| K8pkError::Cancelled)?;
+            client_certificate = Some(Text::new(&tr("prompt-client-cert")).ask()?);
+            client_key = Some(Text::new(&tr("prompt-client-key")).ask()?);
+            let ca = Text::new(&tr("prompt-ca")).with_default("").ask()?;
             if !ca.trim().is_empty() {
                 certificate_authority = Some(ca);
             }
         }
         "exec" => {
-            let preset = Select::new("Exec preset:", vec!["aws-eks", "gke", "aks", "custom"])
-                .prompt()
-                .map_err(|_| K8pkError::Cancelled)?;
+            let preset = Select::new(
+                &tr("prompt-exec-preset"),
+                vec!["aws-eks", "gke", "aks", "teleport", "custom"],
+            )
+            .ask()?;
             if preset == "custom" {
-                exec.command = Some(
-                    Text::new("Exec command:")
-                        .prompt()
-                        .map_err(|_| K8pkError::Cancelled)?,
-                );
-                let args = Text::new("Exec args (space-separated, optional):")
-                    .with_default("")
-                    .prompt()
-                    .map_err(|_| K8pkError::Cancelled)?;
+                exec.command = Some(Text::new(&tr("prompt-exec-command")).ask()?);
+                let args = Text::new(&tr("prompt-exec-args")).with_default("").ask()?;
                 if !args.trim().is_empty() {
                     exec.args = args.split_whitespace().map(|s| s.to_string()).collect();
                 }
-                let env = Text::new("Exec env (KEY=VALUE, comma-separated, optional):")
-                    .with_default("")
-                    .prompt()
-                    .map_err(|_| K8pkError::Cancelled)?;
+                let env = Text::new(&tr("prompt-exec-env")).with_default("").ask()?;
                 if !env.trim().is_empty() {
                     exec.env = env
                         .split(',')
@@ -638,37 +694,25 @@ pub fn login_wizard() -> Result<LoginResult> {
                         .filter(|s| !s.is_empty())
                         .collect();
                 }
-                let api_version = Text::new("Exec apiVersion (optional):")
+                let api_version = Text::new(&tr("prompt-exec-api-version"))
                     .with_default("")
-                    .prompt()
-                    .map_err(|_| K8pkError::Cancelled)?;
+                    .ask()?;
                 if !api_version.trim().is_empty() {
                     exec.api_version = Some(api_version);
                 }
             } else {
-                let cluster = if preset == "aws-eks" {
-                    Some(
-                        Text::new("EKS cluster name:")
-                            .prompt()
-                            .map_err(|_| K8pkError::Cancelled)?,
-                    )
-                } else {
-                    None
+                let cluster = match preset {
+                    "aws-eks" => Some(Text::new(&tr("prompt-eks-cluster")).ask()?),
+                    "teleport" => Some(Text::new(&tr("prompt-teleport-cluster")).ask()?),
+                    _ => None,
                 };
                 let server_id = if preset == "aks" {
-                    Some(
-                        Text::new("AKS server ID:")
-                            .prompt()
-                            .map_err(|_| K8pkError::Cancelled)?,
-                    )
+                    Some(Text::new(&tr("prompt-aks-server-id")).ask()?)
                 } else {
                     None
                 };
                 let region = if preset == "aws-eks" {
-                    let r = Text::new("AWS region (optional):")
-                        .with_default("")
-                        .prompt()
-                        .map_err(|_| K8pkError::Cancelled)?;
+                    let r = Text::new(&tr("prompt-aws-region")).with_default("").ask()?;
                     if r.trim().is_empty() {
                         None
                     } else {
@@ -696,43 +740,35 @@ pub fn login_wizard() -> Result<LoginResult> {
         "local".to_string()
     };
 
-    let name = if Confirm::new("Set custom context name?")
+    let name = if Confirm::new(&tr("confirm-custom-name"))
         .with_default(false)
-        .prompt()
+        .ask()
         .unwrap_or(false)
     {
-        Some(
-            Text::new("Context name:")
-                .prompt()
-                .map_err(|_| K8pkError::Cancelled)?,
-        )
+        Some(Text::new(&tr("prompt-context-name")).ask()?)
     } else {
         None
     };
 
-    let output_dir = if Confirm::new("Set custom output directory?")
+    let output_dir = if Confirm::new(&tr("confirm-custom-output-dir"))
         .with_default(false)
-        .prompt()
+        .ask()
         .unwrap_or(false)
     {
-        Some(
-            Text::new("Output directory:")
-                .prompt()
-                .map_err(|_| K8pkError::Cancelled)?,
-        )
+        Some(Text::new(&tr("prompt-output-dir")).ask()?)
     } else {
         None
     };
 
-    let insecure = Confirm::new("Skip TLS verification?")
+    let insecure = Confirm::new(&tr("confirm-skip-tls"))
         .with_default(false)
-        .prompt()
+        .ask()
         .unwrap_or(false);
 
     let use_vault = if (login_type == "ocp" || login_type == "rancher") && auth == "userpass" {
-        Confirm::new("Use vault to store/retrieve credentials?")
+        Confirm::new(&tr("confirm-use-vault"))
             .with_default(false)
-            .prompt()
+            .ask()
             .unwrap_or(false)
     } else {
         false
@@ -750,30 +786,48 @@ pub fn login_wizard() -> Result<LoginResult> {
     req.output_dir = output_dir.map(PathBuf::from);
     req.insecure = insecure;
     req.use_vault = use_vault;
-    req.pass_entry = pass_entry;
+    req.credentials = credential_source;
     req.certificate_authority = certificate_authority.map(PathBuf::from);
     req.client_certificate = client_certificate.map(PathBuf::from);
     req.client_key = client_key.map(PathBuf::from);
     req.auth = auth_mode.to_string();
     req.exec = exec;
     req.rancher_auth_provider = rancher_auth_provider;
+    req.quiet = quiet;
 
     login(&req)
 }
 
 /// CLI entry for `k8pk login` (wizard or flag-driven).
-pub fn run_login_cli(paths: &[PathBuf], args: crate::cli::LoginArgs) -> Result<()> {
+pub fn run_login_cli(paths: &[PathBuf], mut args: crate::cli::LoginArgs) -> Result<()> {
+    if args.list {
+        let entries = profiles::list_profiles()?;
+        if crate::render::structured(args.json) {
+            println!("{}", crate::render::to_string(&entries)?);
+        } else {
+            profiles::print_profiles(&entries);
+        }
+        return Ok(());
+    }
+    if let Some(profile) = args.profile.take() {
+        args = profiles::resolve(args, &profile)?;
+    }
     let crate::cli::LoginArgs {
         login_type,
         auth,
         auth_help,
         wizard,
+        profile: _,
+        list: _,
         server,
         server_pos,
         token,
+        from_clipboard,
         username,
         password,
         pass_entry,
+        op_item,
+        bw_item,
         exec_command,
         exec_arg,
         exec_env,
@@ -782,20 +836,27 @@ pub fn run_login_cli(paths: &[PathBuf], args: crate::cli::LoginArgs) -> Result<(
         exec_cluster,
         exec_server_id,
         exec_region,
+        gssapi_token_url,
         name,
         output_dir,
         insecure_skip_tls_verify,
         use_vault,
         certificate_authority,
+        ca_data,
+        from_secret,
         client_certificate,
         client_key,
         dry_run,
+        force_new,
         test,
         test_timeout,
         rancher_auth_provider,
-        quiet,
+        auto_alias,
         json,
     } = args;
+    let json = crate::render::structured(json);
+    // JSON output is the result only; progress messages would get in the way
+    let quiet = crate::render::quiet() || json;
 
     if auth_help {
         print_auth_help();
@@ -803,8 +864,46 @@ pub fn run_login_cli(paths: &[PathBuf], args: crate::cli::LoginArgs) -> Result<(
     }
 
     let finish = |login_result: LoginResult, paths: &[PathBuf]| -> Result<()> {
+        let written: Vec<(PathBuf, String)> = if login_result.imported.is_empty() {
+            login_result
+                .kubeconfig_path
+                .iter()
+                .map(|p| (p.clone(), login_result.context_name.clone()))
+                .collect()
+        } else {
+            login_result
+                .imported
+                .iter()
+                .map(|c| (c.kubeconfig_path.clone(), c.context_name.clone()))
+                .collect()
+        };
+        if !written.is_empty() {
+            let mut candidates = Vec::new();
+            for (path, context) in &written {
+                let only = [context.clone()];
+                candidates.extend(crate::commands::alias_candidates_from_file(
+                    path,
+                    Some(&only),
+                )?);
+            }
+            let known = crate::kubeconfig::load_merged(paths)
+                .map(|m| m.context_names().into_iter().collect())
+                .unwrap_or_default();
+            crate::commands::register_friendly_aliases(&candidates, &known, auto_alias, quiet)?;
+        }
         if json {
-            println!("{}", serde_json::to_string_pretty(&login_result)?);
+            println!("{}", crate::render::to_string(&login_result)?);
+            return Ok(());
+        }
+        if login_result.imported.len() > 1 {
+            if quiet {
+                return Ok(());
+            }
+            eprintln!(
+                "Imported {} cluster kubeconfigs.",
+                login_result.imported.len()
+            );
+            eprintln!("Run `k8pk` (or `kpick`) to pick one of the new contexts.");
             return Ok(());
         }
         let kubeconfig_path = match login_result.kubeconfig_path {
@@ -813,11 +912,13 @@ pub fn run_login_cli(paths: &[PathBuf], args: crate::cli::LoginArgs) -> Result<(
         };
         let context_name = login_result.context_name;
         let namespace = login_result.namespace;
-        let ns_display = namespace.as_deref().unwrap_or("default");
-        eprintln!(
-            "Login successful. Switching to context '{}' (namespace: {})...",
-            context_name, ns_display
-        );
+        if !quiet {
+            eprintln!(
+                "Login successful. Switching to context '{}' (namespace: {})...",
+                context_name,
+                namespace.as_deref().unwrap_or("default")
+            );
+        }
         crate::commands::context::save_to_history(&context_name, namespace.as_deref())?;
 
         let kubeconfig = if let Some(ns) = namespace.as_deref() {
@@ -845,9 +946,32 @@ pub fn run_login_cli(paths: &[PathBuf], args: crate::cli::LoginArgs) -> Result<(
     };
 
     if wizard {
-        return finish(login_wizard()?, paths);
+        return finish(login_wizard(quiet)?, paths);
     }
 
+    let (server, token, login_type, insecure_skip_tls_verify) = if from_clipboard {
+        if !matches!(login_type.as_str(), "auto" | "ocp") {
+            return Err(K8pkError::InvalidArgument(
+                "--from-clipboard is only supported with --type ocp".into(),
+            ));
+        }
+        let command = ocp::parse_login_command(&ocp::read_clipboard()?)?;
+        if !quiet {
+            eprintln!(
+                "Using the oc login command from the clipboard ({})",
+                command.server
+            );
+        }
+        (
+            Some(command.server),
+            Some(command.token),
+            "ocp".to_string(),
+            insecure_skip_tls_verify || command.insecure,
+        )
+    } else {
+        (server, token, login_type, insecure_skip_tls_verify)
+    };
+
     let server_url = server.or(server_pos).ok_or_else(|| {
         K8pkError::InvalidArgument(
             "server URL is required (use --server or provide as positional argument)".into(),
@@ -856,20 +980,22 @@ pub fn run_login_cli(paths: &[PathBuf], args: crate::cli::LoginArgs) -> Result<(
 
     let login_type = if login_type == "auto" {
         if let Some(detected) = detect_login_type_from_url(&server_url) {
-            eprintln!(
-                "Auto-detected cluster type: {}",
-                match detected {
-                    LoginType::Ocp => "ocp",
-                    LoginType::K8s => "k8s",
-                    LoginType::Gke => "gke",
-                    LoginType::Rancher => "rancher",
-                }
-            );
+            if !quiet {
+                eprintln!(
+                    "Auto-detected cluster type: {}",
+                    match detected {
+                        LoginType::Ocp => "ocp",
+                        LoginType::K8s => "k8s",
+                        LoginType::Gke => "gke",
+                        LoginType::Rancher => "rancher",
+                    }
+                );
+            }
             detected
-        } else if std::io::stdin().is_terminal() {
-            eprintln!("Could not detect cluster type from URL. Please select:");
+        } else if crate::prompt::can_prompt() {
+            eprintln!("{}", tr("msg-detect-type-failed"));
             let choice = Select::new(
-                "Cluster type:",
+                &tr("prompt-cluster-type"),
                 vec![
                     "ocp (OpenShift)",
                     "k8s (generic Kubernetes)",
@@ -877,8 +1003,7 @@ pub fn run_login_cli(paths: &[PathBuf], args: crate::cli::LoginArgs) -> Result<(
                     "rancher",
                 ],
             )
-            .prompt()
-            .map_err(|_| K8pkError::Cancelled)?;
+            .ask()?;
             match choice {
                 "ocp (OpenShift)" => LoginType::Ocp,
                 "gke (Google)" => LoginType::Gke,
@@ -902,6 +1027,30 @@ pub fn run_login_cli(paths: &[PathBuf], args: crate::cli::LoginArgs) -> Result<(
         ));
     }
 
+    if (ca_data.is_some() || from_secret.is_some()) && login_type != LoginType::K8s {
+        return Err(K8pkError::InvalidArgument(
+            "--ca-data and --from-secret are only supported with --type k8s".into(),
+        ));
+    }
+    let mut ca_data = ca_data.as_deref().map(normalize_ca_data).transpose()?;
+    let mut token = token;
+    let mut namespace = None;
+    if let Some(ref secret_path) = from_secret {
+        let raw = if secret_path.as_os_str() == "-" {
+            let mut buf = String::new();
+            std::io::Read::read_to_string(&mut std::io::stdin(), &mut buf)?;
+            buf
+        } else {
+            fs::read_to_string(secret_path)?
+        };
+        let secret = parse_service_account_secret(&raw)?;
+        token = Some(secret.token);
+        if ca_data.is_none() && certificate_authority.is_none() {
+            ca_data = secret.ca_data;
+        }
+        namespace = secret.namespace;
+    }
+
     if exec_preset.is_some() && exec_command.is_some() {
         return Err(K8pkError::InvalidArgument(
             "use either --exec-preset or --exec-command, not both".into(),
@@ -927,6 +1076,17 @@ pub fn run_login_cli(paths: &[PathBuf], args: crate::cli::LoginArgs) -> Result<(
             auth_mode = "exec".to_string();
         }
     }
+    if auth_mode.parse::<AuthMode>().ok() == Some(AuthMode::Gssapi) {
+        if exec.command.is_some() {
+            return Err(K8pkError::InvalidArgument(
+                "--auth gssapi sets up its own exec plugin; drop --exec-command / --exec-preset"
+                    .into(),
+            ));
+        }
+        if let Some(url) = gssapi_token_url.as_deref() {
+            exec = gssapi::exec_auth(url);
+        }
+    }
 
     let mut req = LoginRequest::new(&server_url);
     req.login_type = Some(login_type);
@@ -937,8 +1097,10 @@ pub fn run_login_cli(paths: &[PathBuf], args: crate::cli::LoginArgs) -> Result<(
     req.output_dir = output_dir;
     req.insecure = insecure_skip_tls_verify;
     req.use_vault = use_vault;
-    req.pass_entry = pass_entry;
+    req.credentials = CredentialSource::from_flags(pass_entry, op_item, bw_item);
     req.certificate_authority = certificate_authority;
+    req.ca_data = ca_data;
+    req.namespace = namespace;
     req.client_certificate = client_certificate;
     req.client_key = client_key;
     req.auth = auth_mode;
@@ -947,7 +1109,43 @@ pub fn run_login_cli(paths: &[PathBuf], args: crate::cli::LoginArgs) -> Result<(
     req.test = test;
     req.test_timeout = test_timeout;
     req.rancher_auth_provider = rancher_auth_provider;
-    req.quiet = quiet || json;
+    req.quiet = quiet;
+
+    if !force_new && !dry_run && matches!(login_type, LoginType::Ocp | LoginType::K8s) {
+        let prefix = if login_type == LoginType::Ocp {
+            "ocp"
+        } else {
+            "k8s"
+        };
+        let target = login_output(
+            prefix,
+            &req.server,
+            req.name.as_deref(),
+            req.output_dir.as_deref(),
+        )?;
+        if let Some(existing) =
+            find_existing_login(paths, &req.server, req.username.as_deref(), &target)
+        {
+            match choose_existing_login(&existing, &req.server, quiet)? {
+                ExistingChoice::Reuse => {
+                    let result = LoginResult {
+                        expires_at: credential_expires_at(&existing.path, &existing.context),
+                        context_name: existing.context,
+                        namespace: existing.namespace,
+                        kubeconfig_path: Some(existing.path),
+                        imported: Vec::new(),
+                        auth_mode: None,
+                    };
+                    return finish(result, paths);
+                }
+                ExistingChoice::Refresh => {
+                    req.output_dir = existing.path.parent().map(Path::to_path_buf);
+                    req.name = Some(existing.context);
+                }
+                ExistingChoice::New => {}
+            }
+        }
+    }
 
     let login_result = login(&req)?;
     if dry_run {
@@ -967,6 +1165,10 @@ pub fn print_auth_help() {
     --exec-command aws --exec-arg eks --exec-arg get-token --exec-arg --cluster-name --exec-arg prod\n\
   k8pk login --type k8s --auth exec https://k8s.example.com:6443 \\\n\
     --exec-preset aws-eks --exec-cluster prod --exec-region us-east-1\n\
+  k8pk login --type k8s --auth exec https://teleport.example.com:443 \\\n\
+    --exec-preset teleport --exec-cluster prod\n\
+  k8pk login --type k8s --auth gssapi https://k8s.corp.example.com:6443 \\\n\
+    --gssapi-token-url https://k8s-auth.corp.example.com/token   # Kerberos ticket from kinit\n\
   k8pk login --type ocp --auth token https://api.ocp.example.com:6443 --token $TOKEN\n\
   k8pk --oc /path/to/oc login --type ocp --auth token https://api.ocp.example.com:6443 --token $TOKEN\n\
   k8pk login --type ocp --auth userpass https://api.ocp.example.com:6443 -u admin\n\
@@ -991,6 +1193,8 @@ pub fn print_auth_help() {
   \n\
   # Rancher userpass - optional pass line: rancher_auth_provider: openldap\n\
   k8pk login --type rancher --auth userpass https://rancher.example.com --pass-entry k8pk/rancher\n\
+  k8pk login --type k8s --auth token https://k8s.example.com:6443 --op-item \"dev cluster\"\n\
+  k8pk login --type ocp --auth userpass https://api.ocp.example.com:6443 --bw-item ocp-prod\n\
   \n\
   k8pk login --wizard"
     );
@@ -1034,7 +1238,8 @@ fn parse_server_host_port(server: &str) -> Option<(String, u16)> {
     Some((authority.to_string(), default_port))
 }
 
-fn check_server_reachable(server: &str, timeout_secs: u64) -> Result<()> {
+pub(crate) fn check_server_reachable(server: &str, timeout_secs: u64) -> Result<()> {
+    crate::config::require_online("checking the server")?;
     let (host, port) = parse_server_host_port(server)
         .ok_or_else(|| K8pkError::LoginFailed("invalid server URL".into()))?;
     let addr = (host.as_str(), port)
@@ -1057,13 +1262,10 @@ fn is_auth_error(e: &K8pkError) -> bool {
 }
 
 fn prompt_userpass(user_prompt: &str) -> Result<(String, String)> {
-    let username = Text::new(user_prompt)
-        .prompt()
-        .map_err(|_| K8pkError::Cancelled)?;
-    let password = Password::new("Password:")
+    let username = Text::new(user_prompt).ask()?;
+    let password = Password::new(&tr("prompt-password"))
         .without_confirmation()
-        .prompt()
-        .map_err(|_| K8pkError::Cancelled)?;
+        .ask()?;
     Ok((username, password))
 }
 
@@ -1077,9 +1279,9 @@ fn login_or_retry_userpass(
         Ok(r) => Ok(r),
         Err(e) if is_auth_error(&e) => {
             eprintln!("{}", fail_msg);
-            let retry = Confirm::new("Retry with different credentials?")
+            let retry = Confirm::new(&tr("confirm-retry-credentials"))
                 .with_default(true)
-                .prompt()
+                .ask()
                 .unwrap_or(false);
             if retry {
                 let (u, p) = prompt_userpass(user_prompt)?;
@@ -1176,7 +1378,15 @@ pub fn try_relogin(
     const REACHABILITY_TIMEOUT_SECS: u64 = 2;
     check_server_reachable(&server, REACHABILITY_TIMEOUT_SECS)?;
 
-    let mut login_type = context::get_context_type(context)?
+    let saved_type = context::get_context_type(context)?;
+    if saved_type.as_deref() == Some("teleport") {
+        // Credentials come from `tsh kube credentials`; only the Teleport session needs renewing
+        eprintln!("Teleport session expired for '{}'.", context);
+        teleport::tsh_relogin(&server)?;
+        return Ok(None);
+    }
+
+    let mut login_type = saved_type
         .as_ref()
         .and_then(|s| s.parse::<LoginType>().ok())
         .or_else(|| infer_login_type_from_context(context))
@@ -1184,15 +1394,14 @@ pub fn try_relogin(
 
     if login_type.is_none() {
         eprintln!(
-            "Unknown cluster type for '{}'. Choose type for re-login (saved for next time):",
-            context
+            "{}",
+            tr_with("msg-unknown-type-relogin", &[("context", context)])
         );
         let choice = Select::new(
-            "Cluster type:",
+            &tr("prompt-cluster-type"),
             vec!["ocp (OpenShift)", "rancher", "gke", "k8s (generic)"],
         )
-        .prompt()
-        .map_err(|_| K8pkError::Cancelled)?;
+        .ask()?;
         login_type = match choice {
             "ocp (OpenShift)" => Some(LoginType::Ocp),
             "rancher" => Some(LoginType::Rancher),
@@ -1244,20 +1453,18 @@ pub fn try_relogin(
             }
 
             eprintln!(
-                "Session expired for '{}'. Re-login (username and password).",
-                context
+                "{}",
+                tr_with("msg-relogin-userpass", &[("context", context)])
             );
             let rancher_server = if is_proxy_url {
                 base
             } else {
                 eprintln!("Cluster URL does not appear to be a Rancher proxy URL.");
-                Text::new("Rancher server URL (e.g., https://rancher.example.com):")
-                    .prompt()
-                    .map_err(|_| K8pkError::Cancelled)?
+                Text::new(&tr("prompt-rancher-server")).ask()?
             };
             let rancher_provider = select_rancher_provider()?;
-            let user_prompt = "Username (for AD try DOMAIN\\user or user@domain.com):";
-            let (username, password) = prompt_userpass(user_prompt)?;
+            let user_prompt = tr("prompt-username-ad");
+            let (username, password) = prompt_userpass(&user_prompt)?;
             let build = |u: &str, p: &str| {
                 relogin_base(
                     &rancher_server,
@@ -1270,9 +1477,13 @@ pub fn try_relogin(
                 .with_rancher_auth_provider(&rancher_provider)
                 .with_rancher_cluster_server(&server)
             };
-            let fail_msg = "Authentication failed. Common issues:\n  - For AD: try DOMAIN\\username or username@domain.com\n  - Check if your account has Rancher access\n  - Verify password is correct";
-            let res =
-                login_or_retry_userpass(build(&username, &password), fail_msg, user_prompt, build)?;
+            let fail_msg = tr("msg-auth-failed-rancher");
+            let res = login_or_retry_userpass(
+                build(&username, &password),
+                &fail_msg,
+                &user_prompt,
+                build,
+            )?;
 
             if let Ok(mut v) = Vault::new() {
                 let _ = v.set(
@@ -1304,10 +1515,10 @@ pub fn try_relogin(
             }
 
             eprintln!(
-                "Session expired for '{}'. Re-login (username and password).",
-                context
+                "{}",
+                tr_with("msg-relogin-userpass", &[("context", context)])
             );
-            let (username, password) = prompt_userpass("Username:")?;
+            let (username, password) = prompt_userpass(&tr("prompt-username"))?;
             let build = |u: &str, p: &str| {
                 relogin_base(
                     &server,
@@ -1320,8 +1531,8 @@ pub fn try_relogin(
             };
             let res = login_or_retry_userpass(
                 build(&username, &password),
-                "Authentication failed. Check your username and password.",
-                "Username:",
+                &tr("msg-auth-failed-userpass"),
+                &tr("prompt-username"),
                 build,
             )?;
 
@@ -1354,32 +1565,29 @@ pub fn try_relogin(
         }
         Some(LoginType::K8s) | None => {
             eprintln!(
-                "Session expired for '{}'. Re-login (token or username/password).",
-                context
+                "{}",
+                tr_with("msg-relogin-token-or-userpass", &[("context", context)])
             );
-            let auth_choice = Select::new("Auth:", vec!["token", "userpass"])
-                .prompt()
-                .map_err(|_| K8pkError::Cancelled)?;
+            let auth_choice =
+                Select::new(&tr("prompt-auth-method"), vec!["token", "userpass"]).ask()?;
             let res = if auth_choice == "token" {
-                let token = Password::new("Token:")
+                let token = Password::new(&tr("prompt-token"))
                     .without_confirmation()
-                    .prompt()
-                    .map_err(|_| K8pkError::Cancelled)?;
+                    .ask()?;
                 let req = relogin_base(&server, LoginType::K8s, context, relogin_insecure, "token")
                     .with_token(&token);
                 match login(&req) {
                     Ok(r) => r,
                     Err(e) if is_auth_error(&e) => {
-                        eprintln!("Authentication failed. Check your token.");
-                        let retry = Confirm::new("Retry with a different token?")
+                        eprintln!("{}", tr("msg-auth-failed-token"));
+                        let retry = Confirm::new(&tr("confirm-retry-token"))
                             .with_default(true)
-                            .prompt()
+                            .ask()
                             .unwrap_or(false);
                         if retry {
-                            let token = Password::new("Token:")
+                            let token = Password::new(&tr("prompt-token"))
                                 .without_confirmation()
-                                .prompt()
-                                .map_err(|_| K8pkError::Cancelled)?;
+                                .ask()?;
                             login(
                                 &relogin_base(
                                     &server,
@@ -1397,7 +1605,7 @@ pub fn try_relogin(
                     Err(e) => return Err(e),
                 }
             } else {
-                let (username, password) = prompt_userpass("Username:")?;
+                let (username, password) = prompt_userpass(&tr("prompt-username"))?;
                 let build = |u: &str, p: &str| {
                     relogin_base(
                         &server,
@@ -1410,8 +1618,8 @@ pub fn try_relogin(
                 };
                 login_or_retry_userpass(
                     build(&username, &password),
-                    "Authentication failed. Check your username and password.",
-                    "Username:",
+                    &tr("msg-auth-failed-userpass"),
+                    &tr("prompt-username"),
                     build,
                 )?
             };
@@ -1472,6 +1680,11 @@ fn validate_auth(
             "exec auth is not supported for --type rancher".into(),
         ));
     }
+    if login_type != LoginType::K8s && auth_mode == AuthMode::Gssapi {
+        return Err(K8pkError::InvalidArgument(
+            "gssapi auth is only supported for --type k8s".into(),
+        ));
+    }
 
     let has_token = token.is_some();
     let has_userpass = username.is_some() || password.is_some();
@@ -1481,7 +1694,7 @@ fn validate_auth(
 
     if has_userpass && (username.is_none() || password.is_none()) {
         return Err(K8pkError::InvalidArgument(
-            "username/password auth requires both --username and --password (or use --pass-entry)"
+            "username/password auth requires both --username and --password (or use --pass-entry, --op-item, or --bw-item)"
                 .into(),
         ));
     }
@@ -1511,7 +1724,7 @@ fn validate_auth(
         AuthMode::Token => {
             if !has_token {
                 return Err(K8pkError::InvalidArgument(
-                    "auth mode token requires --token or --pass-entry".into(),
+                    "auth mode token requires --token or a password manager entry (--pass-entry, --op-item, --bw-item)".into(),
                 ));
             }
             if has_userpass || has_cert || has_exec {
@@ -1552,123 +1765,17 @@ fn validate_auth(
                 ));
             }
         }
-    }
-
-    Ok(())
-}
-
-fn parse_pass_store_output(stdout: &str) -> HashMap<String, String> {
-    let mut values: HashMap<String, String> = HashMap::new();
-    for (i, line) in stdout.lines().enumerate() {
-        let trimmed = line.trim();
-        if trimmed.is_empty() {
-            continue;
-        }
-        if i == 0 {
-            values.insert("__password__".to_string(), trimmed.to_string());
-            continue;
-        }
-        if let Some((k, v)) = trimmed.split_once(':') {
-            values.insert(k.trim().to_lowercase(), v.trim().to_string());
-        }
-    }
-    values
-}
-
-fn apply_pass_credentials(
-    token: &mut Option<String>,
-    username: &mut Option<String>,
-    password: &mut Option<String>,
-    entry: &str,
-    auth_mode: AuthMode,
-    rancher_auth_provider: Option<&mut String>,
-) -> Result<()> {
-    if which::which("pass").is_err() {
-        return Err(K8pkError::CommandFailed(
-            "pass not found on PATH. Install pass or omit --pass-entry.".into(),
-        ));
-    }
-
-    let output = Command::new("pass").args(["show", entry]).output()?;
-    if !output.status.success() {
-        return Err(K8pkError::CommandFailed(format!(
-            "failed to read pass entry: {}",
-            entry
-        )));
-    }
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let values = parse_pass_store_output(&stdout);
-
-    let user_key = values
-        .get("username")
-        .or_else(|| values.get("user"))
-        .cloned();
-
-    match auth_mode {
-        AuthMode::Token => {
-            if token.is_none() {
-                if let Some(t) = values.get("token") {
-                    *token = Some(t.to_string());
-                } else if let Some(p) = values.get("__password__") {
-                    *token = Some(p.to_string());
-                }
-            }
-        }
-        AuthMode::UserPass => {
-            if username.is_none() {
-                if let Some(u) = user_key.clone() {
-                    *username = Some(u);
-                }
-            }
-            if password.is_none() {
-                if let Some(p) = values
-                    .get("password")
-                    .or_else(|| values.get("__password__"))
-                {
-                    *password = Some(p.to_string());
-                }
-            }
-        }
-        AuthMode::Auto => {
-            if username.is_none() {
-                if let Some(u) = user_key.clone() {
-                    *username = Some(u);
-                }
-            }
-
-            if token.is_none() {
-                if let Some(t) = values.get("token") {
-                    *token = Some(t.to_string());
-                }
-            }
-
-            if password.is_none() {
-                if user_key.is_some() || username.is_some() {
-                    if let Some(p) = values
-                        .get("password")
-                        .or_else(|| values.get("__password__"))
-                    {
-                        *password = Some(p.to_string());
-                    }
-                } else if token.is_none() {
-                    if let Some(p) = values.get("__password__") {
-                        *token = Some(p.to_string());
-                    }
-                }
+        AuthMode::Gssapi => {
+            if !has_exec {
+                return Err(K8pkError::InvalidArgument(
+                    "auth mode gssapi requires --gssapi-token-url (the endpoint behind the Kerberos proxy that issues bearer tokens)"
+                        .into(),
+                ));
             }
-        }
-        AuthMode::ClientCert | AuthMode::Exec => {}
-    }
-
-    if let Some(r) = rancher_auth_provider {
-        if let Some(v) = values
-            .get("rancher_auth_provider")
-            .or_else(|| values.get("rancher_provider"))
-        {
-            let trimmed = v.trim();
-            if !trimmed.is_empty() {
-                *r = trimmed.to_string();
+            if has_token || has_userpass || has_cert {
+                return Err(K8pkError::InvalidArgument(
+                    "auth mode gssapi does not allow other auth options".into(),
+                ));
             }
         }
     }
@@ -1676,7 +1783,7 @@ fn apply_pass_credentials(
     Ok(())
 }
 
-fn build_exec_auth(exec: &ExecAuthConfig) -> Result<serde_yaml_ng::Value> {
+pub(crate) fn build_exec_auth(exec: &ExecAuthConfig) -> Result<serde_yaml_ng::Value> {
     let command = exec.command.as_ref().ok_or_else(|| {
         K8pkError::InvalidArgument(
             "exec auth requires --exec-command (use repeated --exec-arg and --exec-env KEY=VALUE)"
@@ -1741,27 +1848,28 @@ fn build_exec_auth(exec: &ExecAuthConfig) -> Result<serde_yaml_ng::Value> {
 }
 
 fn handle_post_login_check(kc_path: &Path, context: &str, msg: &str) {
-    if is_tls_error(msg) && std::io::stdin().is_terminal() && std::io::stderr().is_terminal() {
+    if is_tls_error(msg) && crate::prompt::can_prompt() && std::io::stderr().is_terminal() {
         eprintln!("Warning: {}", msg);
-        let confirm = Confirm::new("Enable insecure-skip-tls-verify for this context?")
+        let confirm = Confirm::new(&tr("confirm-insecure"))
             .with_default(true)
-            .prompt()
+            .ask()
             .unwrap_or(false);
         if confirm {
             match crate::commands::context::apply_insecure_to_kubeconfig(kc_path) {
                 Ok(_) => {
-                    eprintln!("Applied insecure-skip-tls-verify to kubeconfig.");
-                    let persist = Confirm::new(&format!(
-                        "Always skip TLS for '{}'? (saves to insecure_contexts in config)",
-                        context
-                    ))
-                    .with_default(true)
-                    .prompt()
-                    .unwrap_or(false);
+                    eprintln!("{}", tr("msg-applied-insecure"));
+                    let persist =
+                        Confirm::new(&tr_with("confirm-always-insecure", &[("context", context)]))
+                            .with_default(true)
+                            .ask()
+                            .unwrap_or(false);
                     if persist {
                         match crate::config::add_to_insecure_contexts(context) {
                             Ok(()) => {
-                                eprintln!("Saved '{}' to insecure_contexts in config.", context)
+                                eprintln!(
+                                    "{}",
+                                    tr_with("msg-saved-insecure", &[("context", context)])
+                                )
                             }
                             Err(e) => eprintln!("Warning: could not update config: {}", e),
                         }
@@ -1849,6 +1957,45 @@ fn is_tls_error(stderr: &str) -> bool {
     TLS_ERROR_PATTERNS.iter().any(|p| lower.contains(p))
 }
 
+const UNREACHABLE_PATTERNS: &[&str] = &[
+    "unable to connect to the server",
+    "connection refused",
+    "no such host",
+    "i/o timeout",
+    "network is unreachable",
+    "no route to host",
+    "context deadline exceeded",
+    "connection reset by peer",
+];
+
+const AUTH_ERROR_PATTERNS: &[&str] = &[
+    "unauthorized",
+    "must be logged in",
+    "provide credentials",
+    "token has expired",
+    "invalid bearer token",
+];
+
+/// Map kubectl/oc stderr from a failed API call to the error kind behind it
+/// (unreachable or auth), so callers exit with the matching code.
+pub(crate) fn classify_cli_failure(context: &str, stderr: &str) -> Option<K8pkError> {
+    let lower = stderr.to_lowercase();
+    let detail = stderr.lines().last().unwrap_or_default().trim();
+    if UNREACHABLE_PATTERNS.iter().any(|p| lower.contains(p)) {
+        return Some(K8pkError::ClusterUnreachable(format!(
+            "'{}': {}",
+            context, detail
+        )));
+    }
+    if AUTH_ERROR_PATTERNS.iter().any(|p| lower.contains(p)) {
+        return Some(K8pkError::LoginFailed(format!(
+            "credential test failed for '{}': {}",
+            context, detail
+        )));
+    }
+    None
+}
+
 pub(crate) fn test_k8s_auth(
     kubeconfig_path: &Path,
     context_name: &str,
@@ -1856,6 +2003,8 @@ pub(crate) fn test_k8s_auth(
 ) -> Result<()> {
     use std::time::{Duration, Instant};
 
+    crate::config::require_online("checking the session")?;
+
     // ponytail: kubectl starts faster than oc; light SAR (no --all-namespaces)
     let cli = crate::kubeconfig::find_fast_cli()?;
     let timeout_arg = format!("--request-timeout={}s", timeout_secs);
@@ -1898,6 +2047,9 @@ pub(crate) fn test_k8s_auth(
                             hint: "Retry with: k8pk ctx <context> --insecure\n  Or add to config: insecure_contexts: [\"<pattern>\"]".to_string(),
                         });
                     }
+                    if let Some(e) = classify_cli_failure(context_name, &stderr_output) {
+                        return Err(e);
+                    }
                     return Err(K8pkError::CommandFailed("credential test failed".into()));
                 }
                 return Ok(());
@@ -1906,9 +2058,10 @@ pub(crate) fn test_k8s_auth(
                 if start.elapsed() > timeout {
                     let _ = child.kill();
                     let _ = child.wait();
-                    return Err(K8pkError::CommandFailed(
-                        "session check timed out (cluster unreachable?)".into(),
-                    ));
+                    return Err(K8pkError::ClusterUnreachable(format!(
+                        "'{}': session check timed out after {}s",
+                        context_name, timeout_secs
+                    )));
                 }
                 std::thread::sleep(Duration::from_millis(100));
             }
@@ -1922,12 +2075,26 @@ pub(super) fn prepare_login_output(
     server: &str,
     name: Option<&str>,
     output_dir: Option<&Path>,
+) -> Result<(String, PathBuf)> {
+    let (context_name, kubeconfig_path) = login_output(prefix, server, name, output_dir)?;
+    if let Some(dir) = kubeconfig_path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    Ok((context_name, kubeconfig_path))
+}
+
+/// Context name and kubeconfig file a login writes: `<prefix>-<server>` in
+/// `~/.kube/<prefix>/` unless `--name` / `--output-dir` say otherwise
+fn login_output(
+    prefix: &str,
+    server: &str,
+    name: Option<&str>,
+    output_dir: Option<&Path>,
 ) -> Result<(String, PathBuf)> {
     let home = dirs_next::home_dir().ok_or(K8pkError::NoHomeDir)?;
     let out_dir = output_dir
         .map(PathBuf::from)
         .unwrap_or_else(|| home.join(format!(".kube/{}", prefix)));
-    fs::create_dir_all(&out_dir)?;
     let context_name = name.map(String::from).unwrap_or_else(|| {
         let sanitized = server
             .trim_start_matches("https://")
@@ -1942,6 +2109,126 @@ pub(super) fn prepare_login_output(
     Ok((context_name, kubeconfig_path))
 }
 
+/// A context in the resolved kubeconfigs that already logs in to the server
+#[derive(Debug, Clone, PartialEq)]
+struct ExistingLogin {
+    context: String,
+    path: PathBuf,
+    namespace: Option<String>,
+    /// Logging in again with `--name context` rewrites this file in place
+    refreshable: bool,
+}
+
+enum ExistingChoice {
+    Reuse,
+    Refresh,
+    New,
+}
+
+fn same_server(a: &str, b: &str) -> bool {
+    a.trim_end_matches('/')
+        .eq_ignore_ascii_case(b.trim_end_matches('/'))
+}
+
+/// First context (by name) in `paths` on `server` for `username` (any user
+/// when it isn't known yet, e.g. token logins), other than `target`: the
+/// context and file this login writes anyway
+fn find_existing_login(
+    paths: &[PathBuf],
+    server: &str,
+    username: Option<&str>,
+    target: &(String, PathBuf),
+) -> Option<ExistingLogin> {
+    let files = kubeconfig::list_contexts_with_paths(paths).ok()?;
+    let merged = kubeconfig::load_merged(paths).ok()?;
+    let mut contexts = merged.context_names();
+    contexts.sort();
+    contexts.into_iter().find_map(|context| {
+        let path = files.get(&context)?.clone();
+        if context == target.0 && path == target.1 {
+            return None;
+        }
+        if !same_server(
+            &kubeconfig::get_server_for_context(&merged, &context)?,
+            server,
+        ) {
+            return None;
+        }
+        let entry = merged.find_context(&context)?;
+        if let Some(username) = username {
+            let (_, user) = kubeconfig::extract_context_refs(&entry.rest).ok()?;
+            // oc names users `<username>/<host>`; k8s logins store `username:`
+            let stored = merged
+                .find_user(&user)
+                .and_then(|u| u.rest.get("user")?.get("username")?.as_str());
+            if stored != Some(username) && !user.starts_with(&format!("{}/", username)) {
+                return None;
+            }
+        }
+        let namespace = entry
+            .rest
+            .get("context")
+            .and_then(|c| c.get("namespace"))
+            .and_then(|n| n.as_str())
+            .map(String::from);
+        let refreshable = path.file_name()
+            == Some(std::ffi::OsStr::new(&format!(
+                "{}.yaml",
+                kubeconfig::sanitize_filename(&context)
+            )));
+        Some(ExistingLogin {
+            context,
+            path,
+            namespace,
+            refreshable,
+        })
+    })
+}
+
+/// Ask what to do about an existing login; without a terminal, log in anew
+fn choose_existing_login(
+    existing: &ExistingLogin,
+    server: &str,
+    quiet: bool,
+) -> Result<ExistingChoice> {
+    if !crate::prompt::can_prompt() {
+        if !quiet {
+            eprintln!(
+                "note: context '{}' ({}) already logs in to {}; creating another (--force-new skips this check)",
+                existing.context,
+                crate::commands::display_path(&existing.path),
+                server
+            );
+        }
+        return Ok(ExistingChoice::New);
+    }
+    let (reuse, refresh, new) = (
+        tr("choice-login-reuse"),
+        tr("choice-login-refresh"),
+        tr("choice-login-new"),
+    );
+    let mut choices = vec![reuse.clone()];
+    if existing.refreshable {
+        choices.push(refresh.clone());
+    }
+    choices.push(new);
+    let choice = Select::new(
+        &tr_with(
+            "prompt-existing-login",
+            &[("context", existing.context.as_str()), ("server", server)],
+        ),
+        choices,
+    )
+    .ask()?;
+    Ok(if choice == reuse {
+        ExistingChoice::Reuse
+    } else if choice == refresh {
+        ExistingChoice::Refresh
+    } else {
+        ExistingChoice::New
+    })
+}
+
 /// Serialize, optionally dry-run print, write with 0o600, and optionally test auth.
 pub(super) fn write_login_kubeconfig(
     path: &Path,
@@ -1958,6 +2245,9 @@ pub(super) fn write_login_kubeconfig(
             context_name: context_name.to_string(),
             namespace: None,
             kubeconfig_path: None,
+            imported: Vec::new(),
+            auth_mode: None,
+            expires_at: None,
         });
     }
     kubeconfig::write_restricted(path, &yaml)?;
@@ -1968,6 +2258,9 @@ pub(super) fn write_login_kubeconfig(
         context_name: context_name.to_string(),
         namespace: None,
         kubeconfig_path: Some(path.to_path_buf()),
+        imported: Vec::new(),
+        auth_mode: None,
+        expires_at: None,
     })
 }
 
@@ -1997,18 +2290,15 @@ pub(super) fn resolve_vault_userpass(
             }
         }
     }
-    if !std::io::stdin().is_terminal() {
+    if !crate::prompt::can_prompt() {
         return Err(K8pkError::InvalidArgument(
             "credentials required: pass --token/-u/-p, or run interactively".into(),
         ));
     }
-    let u = Text::new(username_prompt)
-        .prompt()
-        .map_err(|_| K8pkError::Cancelled)?;
+    let u = Text::new(username_prompt).ask()?;
     let p = Password::new(password_prompt)
         .without_confirmation()
-        .prompt()
-        .map_err(|_| K8pkError::Cancelled)?;
+        .ask()?;
     Ok((u, p, None, false))
 }
 
@@ -2018,10 +2308,10 @@ pub(super) fn prompt_save_vault(
     password: &str,
     rancher_provider: Option<&str>,
 ) -> Result<()> {
-    let save = !std::io::stdin().is_terminal()
-        || Confirm::new("Save credentials to vault?")
+    let save = !crate::prompt::can_prompt()
+        || Confirm::new(&tr("confirm-save-vault"))
             .with_default(true)
-            .prompt()
+            .ask()
             .unwrap_or(false);
     if save {
         if let Ok(mut v) = Vault::new() {
@@ -2040,7 +2330,7 @@ pub(super) fn prompt_save_vault(
 
 fn select_rancher_provider() -> Result<String> {
     let choice = Select::new(
-        "Rancher account type:",
+        &tr("prompt-rancher-account-type"),
         vec![
             "local (built-in users)",
             "Active Directory",
@@ -2050,8 +2340,7 @@ fn select_rancher_provider() -> Result<String> {
             "auto-detect (try common providers)",
         ],
     )
-    .prompt()
-    .map_err(|_| K8pkError::Cancelled)?;
+    .ask()?;
     Ok(match choice {
         "local (built-in users)" => "local".to_string(),
         "Active Directory" => "activedirectory".to_string(),
@@ -2063,6 +2352,90 @@ fn select_rancher_provider() -> Result<String> {
 }
 
 /// Build a one-cluster / one-user / one-context kubeconfig.
+/// Credentials pulled out of a `kubernetes.io/service-account-token` Secret
+#[derive(Debug)]
+pub(super) struct ServiceAccountSecret {
+    pub token: String,
+    /// `ca.crt` as base64 PEM, ready for `certificate-authority-data`
+    pub ca_data: Option<String>,
+    pub namespace: Option<String>,
+}
+
+fn decode_base64(value: &str, what: &str) -> Result<Vec<u8>> {
+    use base64::Engine;
+    let compact: String = value.split_whitespace().collect();
+    base64::engine::general_purpose::STANDARD
+        .decode(compact)
+        .map_err(|e| K8pkError::InvalidArgument(format!("{} is not valid base64: {}", what, e)))
+}
+
+fn encode_base64(bytes: &[u8]) -> String {
+    use base64::Engine;
+    base64::engine::general_purpose::STANDARD.encode(bytes)
+}
+
+/// Accept CA data as base64 PEM, PEM text, or a path to a PEM file; return base64 PEM.
+pub(super) fn normalize_ca_data(input: &str) -> Result<String> {
+    let trimmed = input.trim();
+    let pem = if trimmed.starts_with("-----BEGIN") {
+        trimmed.as_bytes().to_vec()
+    } else if Path::new(trimmed).is_file() {
+        fs::read(trimmed)?
+    } else {
+        decode_base64(trimmed, "--ca-data")?
+    };
+    if !String::from_utf8_lossy(&pem).contains("-----BEGIN CERTIFICATE-----") {
+        return Err(K8pkError::InvalidArgument(
+            "--ca-data does not contain a PEM certificate".into(),
+        ));
+    }
+    Ok(encode_base64(&pem))
+}
+
+/// Parse a Secret as printed by `kubectl get secret NAME -o yaml|json`.
+/// `data` values are base64; `stringData` (hand-written manifests) is plain text.
+pub(super) fn parse_service_account_secret(raw: &str) -> Result<ServiceAccountSecret> {
+    let doc: serde_yaml_ng::Value = serde_yaml_ng::from_str(raw)?;
+    if doc["kind"].as_str().is_some_and(|k| k != "Secret") {
+        return Err(K8pkError::InvalidArgument(format!(
+            "expected a Secret, got {}",
+            doc["kind"].as_str().unwrap_or_default()
+        )));
+    }
+    if let Some(t) = doc["type"].as_str() {
+        if t != "kubernetes.io/service-account-token" {
+            eprintln!("warning: Secret type is {}, not a service-account token", t);
+        }
+    }
+
+    let field = |key: &str| -> Result<Option<Vec<u8>>> {
+        if let Some(v) = doc["data"][key].as_str() {
+            return decode_base64(v, &format!("data.{}", key)).map(Some);
+        }
+        Ok(doc["stringData"][key]
+            .as_str()
+            .map(|v| v.as_bytes().to_vec()))
+    };
+
+    let token = field("token")?
+        .map(|t| String::from_utf8_lossy(&t).trim().to_string())
+        .filter(|t| !t.is_empty())
+        .ok_or_else(|| {
+            K8pkError::InvalidArgument(
+                "Secret has no token yet (is the token controller running?)".into(),
+            )
+        })?;
+    let ca_data = field("ca.crt")?.map(|pem| encode_base64(&pem));
+    let namespace = field("namespace")?
+        .map(|ns| String::from_utf8_lossy(&ns).trim().to_string())
+        .or_else(|| doc["metadata"]["namespace"].as_str().map(String::from));
+    Ok(ServiceAccountSecret {
+        token,
+        ca_data,
+        namespace,
+    })
+}
+
 pub(super) fn assemble_kubeconfig(
     context_name: &str,
     server: &str,
@@ -2224,6 +2597,106 @@ mod tests {
         assert!("invalid".parse::<LoginType>().is_err());
     }
 
+    #[test]
+    fn test_login_result_expiry() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("dev.yaml");
+        // {"sub":"dev","exp":1700000000}
+        let jwt = "eyJhbGciOiJub25lIn0.eyJzdWIiOiJkZXYiLCJleHAiOjE3MDAwMDAwMDB9.";
+        fs::write(
+            &path,
+            format!(
+                "clusters:\n- name: c\n  cluster: {{server: \"https://c\"}}\n\
+                 users:\n- name: jwt\n  user: {{token: {}}}\n- name: opaque\n  user: {{token: sha256~x}}\n\
+                 contexts:\n- name: dev\n  context: {{cluster: c, user: jwt}}\n\
+                 - name: ocp\n  context: {{cluster: c, user: opaque}}\n",
+                jwt
+            ),
+        )
+        .unwrap();
+        assert_eq!(credential_expires_at(&path, "dev"), Some(1_700_000_000));
+        assert_eq!(credential_expires_at(&path, "ocp"), None);
+        assert_eq!(credential_expires_at(&path, "missing"), None);
+
+        let mut req = LoginRequest::new("https://c").with_token("t");
+        assert_eq!(
+            effective_auth(&req, LoginType::K8s, AuthMode::Auto),
+            Some(AuthMode::Token)
+        );
+        assert_eq!(
+            effective_auth(&req, LoginType::Gke, AuthMode::Auto),
+            Some(AuthMode::Exec)
+        );
+        req.token = None;
+        assert_eq!(effective_auth(&req, LoginType::Ocp, AuthMode::Auto), None);
+
+        let result = LoginResult {
+            context_name: "dev".into(),
+            namespace: None,
+            kubeconfig_path: Some(path),
+            imported: Vec::new(),
+            auth_mode: Some("token".into()),
+            expires_at: None,
+        };
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(json["auth_mode"], "token");
+        assert!(json.get("expires_at").is_none() && json.get("imported").is_none());
+    }
+
+    #[test]
+    fn test_find_existing_login() {
+        let tmp = tempfile::tempdir().unwrap();
+        let ocp = tmp.path().join("ocp-api-x-6443.yaml");
+        fs::write(
+            &ocp,
+            "clusters:\n- name: x\n  cluster: {server: \"https://api.x:6443\"}\n\
+             users:\n- name: admin/api-x:6443\n  user: {token: sha256~t}\n\
+             contexts:\n- name: ocp-api-x-6443\n  context: {cluster: x, user: admin/api-x:6443, namespace: apps}\n",
+        )
+        .unwrap();
+        let config = tmp.path().join("config");
+        fs::write(
+            &config,
+            "clusters:\n- name: y\n  cluster: {server: \"https://api.y:6443\"}\n\
+             users:\n- name: bob\n  user: {username: bob, password: p}\n\
+             contexts:\n- name: other\n  context: {cluster: y, user: bob}\n",
+        )
+        .unwrap();
+        let paths = [ocp.clone(), config.clone()];
+        let elsewhere = ("prod".to_string(), tmp.path().join("prod.yaml"));
+
+        // The file this login rewrites anyway is not a duplicate
+        let target = ("ocp-api-x-6443".to_string(), ocp.clone());
+        assert_eq!(
+            find_existing_login(&paths, "https://api.x:6443/", None, &target),
+            None
+        );
+        assert_eq!(
+            find_existing_login(&paths, "https://API.x:6443", None, &elsewhere),
+            Some(ExistingLogin {
+                context: "ocp-api-x-6443".into(),
+                path: ocp,
+                namespace: Some("apps".into()),
+                refreshable: true,
+            })
+        );
+        assert!(
+            find_existing_login(&paths, "https://api.x:6443", Some("admin"), &elsewhere).is_some()
+        );
+        assert_eq!(
+            find_existing_login(&paths, "https://api.x:6443", Some("dev"), &elsewhere),
+            None
+        );
+
+        let bob =
+            find_existing_login(&paths, "https://api.y:6443", Some("bob"), &elsewhere).unwrap();
+        assert_eq!((bob.context.as_str(), bob.refreshable), ("other", false));
+        assert_eq!(
+            find_existing_login(&paths, "https://api.z:6443", None, &elsewhere),
+            None
+        );
+    }
+
     #[test]
     fn test_vault_crud() {
         let dir = tempfile::tempdir().unwrap();
@@ -2326,25 +2799,6 @@ mod tests {
         );
     }
 
-    #[test]
-    fn test_parse_pass_store_output() {
-        let m = parse_pass_store_output(
-            "firstline\nusername: alice\npassword: bob\nrancher_auth_provider: openldap\n",
-        );
-        assert_eq!(m.get("__password__").map(|s| s.as_str()), Some("firstline"));
-        assert_eq!(m.get("username").map(|s| s.as_str()), Some("alice"));
-        assert_eq!(m.get("password").map(|s| s.as_str()), Some("bob"));
-        assert_eq!(
-            m.get("rancher_auth_provider").map(|s| s.as_str()),
-            Some("openldap")
-        );
-        let m2 = parse_pass_store_output("x\nrancher_provider: activedirectory\n");
-        assert_eq!(
-            m2.get("rancher_provider").map(|s| s.as_str()),
-            Some("activedirectory")
-        );
-    }
-
     #[test]
     fn test_rancher_get_token_single_mock_http() {
         let base = spawn_one_shot(HttpResponse::json(200, r#"{"token":"mock-token-xyz"}"#));
@@ -2468,6 +2922,23 @@ mod tests {
         assert!(!is_tls_error("connection refused"));
     }
 
+    #[test]
+    fn test_classify_cli_failure() {
+        let e = classify_cli_failure(
+            "prod",
+            "E0101 memcache.go\nUnable to connect to the server: dial tcp 10.0.0.1:6443: i/o timeout\n",
+        );
+        assert!(
+            matches!(e, Some(K8pkError::ClusterUnreachable(ref m)) if m.contains("i/o timeout"))
+        );
+        let e = classify_cli_failure(
+            "prod",
+            "error: You must be logged in to the server (Unauthorized)",
+        );
+        assert!(matches!(e, Some(K8pkError::LoginFailed(_))));
+        assert!(classify_cli_failure("prod", "something else").is_none());
+    }
+
     #[test]
     fn test_parse_server_host_port() {
         assert_eq!(
@@ -2519,6 +2990,28 @@ mod tests {
         assert!(err.to_string().contains("exec auth is only supported"));
     }
 
+    #[test]
+    fn validate_auth_gssapi() {
+        let validate = |login_type, token, exec| {
+            validate_auth(
+                login_type,
+                token,
+                None,
+                None,
+                None,
+                None,
+                AuthMode::Gssapi,
+                exec,
+            )
+        };
+        assert!(validate(LoginType::K8s, None, Some("k8pk")).is_ok());
+        let err = validate(LoginType::K8s, None, None).unwrap_err();
+        assert!(err.to_string().contains("--gssapi-token-url"));
+        assert!(validate(LoginType::K8s, Some("t"), Some("k8pk")).is_err());
+        assert!(validate(LoginType::Ocp, None, Some("k8pk")).is_err());
+        assert_eq!("kerberos".parse::<AuthMode>().unwrap(), AuthMode::Gssapi);
+    }
+
     #[test]
     fn validate_auth_ocp_rejects_client_cert() {
         let cert = std::path::Path::new("/tmp/cert");
@@ -2755,6 +3248,74 @@ mod tests {
         assert!(exec.args.contains(&"us-west-2".to_string()));
     }
 
+    #[test]
+    fn test_parse_service_account_secret() {
+        // token "abc.def", ca.crt PEM, namespace "ci"
+        let pem = "-----BEGIN CERTIFICATE-----\nMIIB\n-----END CERTIFICATE-----\n";
+        let ca_b64 = encode_base64(pem.as_bytes());
+        let raw = format!(
+            "apiVersion: v1\nkind: Secret\ntype: kubernetes.io/service-account-token\n\
+             metadata:\n  name: deployer-token\n  namespace: other\n\
+             data:\n  token: YWJjLmRlZg==\n  ca.crt: {}\n  namespace: Y2k=\n",
+            ca_b64
+        );
+        let secret = parse_service_account_secret(&raw).unwrap();
+        assert_eq!(secret.token, "abc.def");
+        assert_eq!(secret.ca_data.as_deref(), Some(ca_b64.as_str()));
+        assert_eq!(secret.namespace.as_deref(), Some("ci"));
+
+        // JSON export without a populated token
+        let err =
+            parse_service_account_secret(r#"{"kind":"Secret","metadata":{"name":"x"},"data":{}}"#)
+                .unwrap_err();
+        assert!(err.to_string().contains("no token"));
+        assert!(parse_service_account_secret("kind: ConfigMap\n").is_err());
+    }
+
+    #[test]
+    fn test_normalize_ca_data() {
+        let pem = "-----BEGIN CERTIFICATE-----\nMIIB\n-----END CERTIFICATE-----\n";
+        let b64 = encode_base64(pem.as_bytes());
+        assert_eq!(
+            normalize_ca_data(pem).unwrap(),
+            encode_base64(pem.trim().as_bytes())
+        );
+        assert_eq!(normalize_ca_data(&b64).unwrap(), b64);
+
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("ca.crt");
+        fs::write(&file, pem).unwrap();
+        assert_eq!(normalize_ca_data(&file.to_string_lossy()).unwrap(), b64);
+
+        assert!(normalize_ca_data(&encode_base64(b"not a cert")).is_err());
+        assert!(normalize_ca_data("%%%").is_err());
+    }
+
+    #[test]
+    fn test_apply_exec_preset_teleport() {
+        let mut exec = ExecAuthConfig::default();
+        apply_exec_preset(
+            "teleport",
+            Some("prod"),
+            Some("leaf.example.com"),
+            None,
+            &mut exec,
+        )
+        .unwrap();
+        assert_eq!(exec.command.as_deref(), Some("tsh"));
+        assert_eq!(
+            exec.args,
+            vec![
+                "kube",
+                "credentials",
+                "--kube-cluster=prod",
+                "--teleport-cluster=leaf.example.com"
+            ]
+        );
+        let mut exec = ExecAuthConfig::default();
+        assert!(apply_exec_preset("teleport", None, None, None, &mut exec).is_err());
+    }
+
     #[test]
     fn test_apply_exec_preset_aks() {
         let mut exec = ExecAuthConfig::default();
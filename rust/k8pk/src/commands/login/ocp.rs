@@ -1,7 +1,9 @@
 //! OpenShift Container Platform login
 
 use crate::error::{K8pkError, Result};
+use crate::i18n::tr;
 use crate::kubeconfig::{self, KubeConfig};
+use crate::prompt::Ask;
 use inquire::Password;
 use std::fs;
 use std::process::Command;
@@ -11,11 +13,75 @@ use super::{
     LoginRequest, LoginResult,
 };
 
+/// Server and token from an `oc login` command line
+#[derive(Debug, PartialEq)]
+pub(super) struct LoginCommand {
+    pub server: String,
+    pub token: String,
+    pub insecure: bool,
+}
+
+pub(super) fn read_clipboard() -> Result<String> {
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.get_text())
+        .map_err(|e| K8pkError::CommandFailed(format!("cannot read the clipboard: {}", e)))
+}
+
+/// Parse the `oc login --token=... --server=...` line the console copies
+/// (alone, or within the whole "Display Token" page)
+pub(super) fn parse_login_command(text: &str) -> Result<LoginCommand> {
+    let line = text
+        .lines()
+        .map(str::trim)
+        .find(|l| l.starts_with("oc login"))
+        .ok_or_else(|| {
+            K8pkError::InvalidArgument(
+                "no `oc login` command on the clipboard; use Copy login command in the OpenShift console"
+                    .into(),
+            )
+        })?;
+    let words = shell_words::split(line)
+        .map_err(|e| K8pkError::InvalidArgument(format!("cannot parse oc login command: {}", e)))?;
+
+    let (mut server, mut token, mut insecure) = (None, None, false);
+    let mut args = words.into_iter().skip(2);
+    while let Some(arg) = args.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => {
+                (flag.to_string(), Some(value.to_string()))
+            }
+            _ => (arg.clone(), None),
+        };
+        match flag.as_str() {
+            "--token" => token = inline.or_else(|| args.next()),
+            "--server" | "-s" => server = inline.or_else(|| args.next()),
+            "--insecure-skip-tls-verify" => {
+                insecure = inline.is_none_or(|v| v == "true");
+            }
+            _ if !flag.starts_with('-') && server.is_none() => server = Some(arg),
+            _ => {}
+        }
+    }
+    match (server, token) {
+        (Some(server), Some(token)) => Ok(LoginCommand {
+            server,
+            token,
+            insecure,
+        }),
+        _ => Err(K8pkError::InvalidArgument(
+            "the oc login command on the clipboard needs both --token and --server".into(),
+        )),
+    }
+}
+
 pub(super) fn ocp_login(req: &LoginRequest) -> Result<LoginResult> {
     let auth_mode = req.auth.parse::<AuthMode>()?;
-    if auth_mode == AuthMode::Exec || auth_mode == AuthMode::ClientCert {
+    if matches!(
+        auth_mode,
+        AuthMode::Exec | AuthMode::ClientCert | AuthMode::Gssapi
+    ) {
         return Err(K8pkError::InvalidArgument(
-            "exec or client-cert auth is not supported for --type ocp".into(),
+            "exec, client-cert, or gssapi auth is not supported for --type ocp".into(),
         ));
     }
     if req.dry_run {
@@ -47,18 +113,13 @@ pub(super) fn ocp_login(req: &LoginRequest) -> Result<LoginResult> {
         // Token auth -- skip username/password entirely
     } else if final_username.is_some() || final_password.is_some() {
         if final_username.is_none() {
-            final_username = Some(
-                inquire::Text::new("Username:")
-                    .prompt()
-                    .map_err(|_| K8pkError::Cancelled)?,
-            );
+            final_username = Some(inquire::Text::new(&tr("prompt-username")).ask()?);
         }
         if final_password.is_none() {
             final_password = Some(
-                Password::new("Password:")
+                Password::new(&tr("prompt-password"))
                     .without_confirmation()
-                    .prompt()
-                    .map_err(|_| K8pkError::Cancelled)?,
+                    .ask()?,
             );
         }
     } else {
@@ -67,8 +128,8 @@ pub(super) fn ocp_login(req: &LoginRequest) -> Result<LoginResult> {
             &[&vault_key],
             req.use_vault,
             req.quiet,
-            "Username:",
-            "Password:",
+            &tr("prompt-username"),
+            &tr("prompt-password"),
         )?;
         final_username = Some(u.clone());
         final_password = Some(p.clone());
@@ -129,6 +190,9 @@ pub(super) fn ocp_login(req: &LoginRequest) -> Result<LoginResult> {
                 ),
             });
         }
+        if let Some(e) = super::classify_cli_failure(&context_name, &combined) {
+            return Err(e);
+        }
         return Err(K8pkError::CommandFailed(format!(
             "oc login failed (binary: {}). \
              If `oc` is not on PATH, use: export K8PK_OC=/path/to/oc  or  k8pk --oc /path/to/oc login ...",
@@ -178,6 +242,9 @@ pub(super) fn ocp_login(req: &LoginRequest) -> Result<LoginResult> {
         context_name,
         namespace,
         kubeconfig_path: Some(kubeconfig_path),
+        imported: Vec::new(),
+        auth_mode: None,
+        expires_at: None,
     })
 }
 
@@ -188,7 +255,9 @@ pub(super) fn test_ocp_auth(kubeconfig_path: &std::path::Path, timeout_secs: u64
         .env("OC_REQUEST_TIMEOUT", format!("{}s", timeout_secs))
         .status()?;
     if !status.success() {
-        return Err(K8pkError::CommandFailed("credential test failed".into()));
+        return Err(K8pkError::LoginFailed(
+            "credential test failed (oc whoami)".into(),
+        ));
     }
     Ok(())
 }
@@ -249,3 +318,37 @@ pub(super) fn refresh_ocp_token(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_login_command() {
+        let expected = LoginCommand {
+            server: "https://api.ocp.example.com:6443".into(),
+            token: "sha256~abc".into(),
+            insecure: false,
+        };
+        assert_eq!(
+            parse_login_command(
+                "oc login --token=sha256~abc --server=https://api.ocp.example.com:6443"
+            )
+            .unwrap(),
+            expected
+        );
+        // The whole "Display Token" page, with the flags spelled differently
+        let page = "Your API token is\nsha256~abc\n\nLog in with this token\n\
+                    oc login https://api.ocp.example.com:6443 --token sha256~abc \
+                    --insecure-skip-tls-verify\n";
+        assert_eq!(
+            parse_login_command(page).unwrap(),
+            LoginCommand {
+                insecure: true,
+                ..expected
+            }
+        );
+        assert!(parse_login_command("sha256~abc").is_err());
+        assert!(parse_login_command("oc login --server=https://api:6443").is_err());
+    }
+}
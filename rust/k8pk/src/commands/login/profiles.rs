@@ -0,0 +1,250 @@
+//! Login profiles: the config's `clusters:` section names servers together
+//! with the `k8pk login` flags they need, so `k8pk login prod` logs in
+//! without asking. Flags on the command line win over the profile.
+
+use crate::cli::LoginArgs;
+use crate::config::{self, ClusterProfile};
+use crate::error::{K8pkError, Result};
+use serde::Serialize;
+
+/// A profile from `clusters:`, as `k8pk login --list` shows it. The key is
+/// `profile` because `name` is the profile's context name template.
+#[derive(Serialize, Debug)]
+pub struct ProfileEntry {
+    #[serde(rename = "profile")]
+    pub name: String,
+    #[serde(flatten)]
+    pub settings: ClusterProfile,
+}
+
+/// Profiles from the config (and team config), sorted by name
+pub fn list_profiles() -> Result<Vec<ProfileEntry>> {
+    let mut entries: Vec<ProfileEntry> = config::load()?
+        .clusters
+        .into_iter()
+        .map(|(name, settings)| ProfileEntry { name, settings })
+        .collect();
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(entries)
+}
+
+pub fn print_profiles(entries: &[ProfileEntry]) {
+    if entries.is_empty() {
+        println!(
+            "No login profiles configured. Add servers under `clusters:` in the k8pk config \
+             (see `k8pk config init`)."
+        );
+        return;
+    }
+    let width = entries.iter().map(|e| e.name.len()).max().unwrap_or(0);
+    for e in entries {
+        let p = &e.settings;
+        println!(
+            "{:width$}  {:7}  {:11}  {}",
+            e.name,
+            p.login_type.as_deref().unwrap_or("auto"),
+            p.auth.as_deref().unwrap_or("auto"),
+            p.server,
+            width = width
+        );
+    }
+}
+
+/// Context name for a profile: its `name` template with {profile}, {host}
+/// (the server's host name), and {user} filled in, or the profile name
+fn context_name(template: Option<&str>, profile: &str, server: &str, user: Option<&str>) -> String {
+    let Some(template) = template else {
+        return profile.to_string();
+    };
+    let host = server
+        .split_once("://")
+        .map_or(server, |(_, rest)| rest)
+        .split(['/', ':'])
+        .next()
+        .unwrap_or_default();
+    template
+        .replace("{profile}", profile)
+        .replace("{host}", host)
+        .replace("{user}", user.unwrap_or_default())
+}
+
+/// `args` with what the command line left unset taken from `profile`
+fn apply_profile(mut args: LoginArgs, name: &str, profile: &ClusterProfile) -> Result<LoginArgs> {
+    if profile.server.trim().is_empty() {
+        return Err(K8pkError::InvalidArgument(format!(
+            "login profile '{}' has no server",
+            name
+        )));
+    }
+    let server = match args.server.take().or(args.server_pos.take()) {
+        Some(server) => server,
+        None => profile.server.clone(),
+    };
+    if args.login_type == "auto" {
+        if let Some(t) = &profile.login_type {
+            args.login_type = t.clone();
+        }
+    }
+    if args.auth == "auto" {
+        if let Some(auth) = &profile.auth {
+            args.auth = auth.clone();
+        }
+    }
+    args.username = args.username.or_else(|| profile.username.clone());
+    if args.certificate_authority.is_none() && args.ca_data.is_none() {
+        args.certificate_authority = profile
+            .certificate_authority
+            .as_deref()
+            .map(config::expand_home);
+    }
+    args.insecure_skip_tls_verify |= profile.insecure_skip_tls_verify;
+    args.client_certificate = args.client_certificate.or_else(|| {
+        profile
+            .client_certificate
+            .as_deref()
+            .map(config::expand_home)
+    });
+    args.client_key = args
+        .client_key
+        .or_else(|| profile.client_key.as_deref().map(config::expand_home));
+    args.output_dir = args
+        .output_dir
+        .or_else(|| profile.output_dir.as_deref().map(config::expand_home));
+    args.name = args.name.or_else(|| {
+        Some(context_name(
+            profile.name.as_deref(),
+            name,
+            &server,
+            args.username.as_deref(),
+        ))
+    });
+    if args.pass_entry.is_none() && args.op_item.is_none() && args.bw_item.is_none() {
+        args.pass_entry = profile.pass_entry.clone();
+        args.op_item = profile.op_item.clone();
+        args.bw_item = profile.bw_item.clone();
+    }
+    args.use_vault |= profile.use_vault;
+    if args.exec_preset.is_none() && args.exec_command.is_none() {
+        args.exec_preset = profile.exec_preset.clone();
+    }
+    args.exec_cluster = args.exec_cluster.or_else(|| profile.exec_cluster.clone());
+    args.exec_server_id = args
+        .exec_server_id
+        .or_else(|| profile.exec_server_id.clone());
+    args.exec_region = args.exec_region.or_else(|| profile.exec_region.clone());
+    args.gssapi_token_url = args
+        .gssapi_token_url
+        .or_else(|| profile.gssapi_token_url.clone());
+    if args.rancher_auth_provider == "local" {
+        if let Some(provider) = &profile.rancher_auth_provider {
+            args.rancher_auth_provider = provider.clone();
+        }
+    }
+    args.server = Some(server);
+    // The profile names the context, so logging in again refreshes it
+    // rather than asking about other contexts for the same server
+    args.force_new = true;
+    Ok(args)
+}
+
+/// Resolve `login <PROFILE>`: a server URL stands for itself, anything else
+/// names a profile in `clusters:`
+pub(super) fn resolve(mut args: LoginArgs, profile: &str) -> Result<LoginArgs> {
+    if profile.contains("://") {
+        if args.server.is_some() || args.server_pos.is_some() {
+            return Err(K8pkError::InvalidArgument(
+                "server URL given more than once".into(),
+            ));
+        }
+        args.server = Some(profile.to_string());
+        return Ok(args);
+    }
+    let clusters = config::load()?.clusters;
+    let Some(settings) = clusters.get(profile) else {
+        let mut known: Vec<&str> = clusters.keys().map(String::as_str).collect();
+        known.sort_unstable();
+        return Err(K8pkError::InvalidArgument(if known.is_empty() {
+            format!(
+                "unknown login profile '{}': no profiles under `clusters:` in the k8pk config",
+                profile
+            )
+        } else {
+            format!(
+                "unknown login profile '{}' (known: {})",
+                profile,
+                known.join(", ")
+            )
+        }));
+    };
+    apply_profile(args, profile, settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    fn login_args(argv: &[&str]) -> LoginArgs {
+        #[derive(Parser)]
+        struct Wrapper {
+            #[command(flatten)]
+            args: LoginArgs,
+        }
+        Wrapper::parse_from(std::iter::once("login").chain(argv.iter().copied())).args
+    }
+
+    #[test]
+    fn test_apply_profile() {
+        let profile = ClusterProfile {
+            server: "https://api.lab.example.com:6443".into(),
+            login_type: Some("k8s".into()),
+            auth: Some("client-cert".into()),
+            username: Some("ops".into()),
+            certificate_authority: Some("/etc/lab-ca.pem".into()),
+            output_dir: Some("/tmp/kube-lab".into()),
+            name: Some("{profile}-{host}-{user}".into()),
+            pass_entry: Some("k8pk/lab".into()),
+            ..Default::default()
+        };
+
+        let args = apply_profile(login_args(&["lab"]), "lab", &profile).unwrap();
+        assert_eq!(
+            args.server.as_deref(),
+            Some("https://api.lab.example.com:6443")
+        );
+        assert_eq!(args.login_type, "k8s");
+        assert_eq!(args.auth, "client-cert");
+        assert_eq!(args.name.as_deref(), Some("lab-api.lab.example.com-ops"));
+        assert_eq!(
+            args.certificate_authority.as_deref(),
+            Some(std::path::Path::new("/etc/lab-ca.pem"))
+        );
+        assert_eq!(args.pass_entry.as_deref(), Some("k8pk/lab"));
+        assert!(args.force_new);
+
+        // Flags win; a password manager flag replaces the profile's
+        let args = apply_profile(
+            login_args(&["lab", "--type", "ocp", "-u", "me", "--op-item", "lab"]),
+            "lab",
+            &profile,
+        )
+        .unwrap();
+        assert_eq!(args.login_type, "ocp");
+        assert_eq!(args.name.as_deref(), Some("lab-api.lab.example.com-me"));
+        assert_eq!(args.op_item.as_deref(), Some("lab"));
+        assert!(args.pass_entry.is_none());
+
+        let bare = ClusterProfile {
+            server: "https://10.0.0.5:6443".into(),
+            ..Default::default()
+        };
+        let args = apply_profile(login_args(&["edge"]), "edge", &bare).unwrap();
+        assert_eq!(args.name.as_deref(), Some("edge"));
+        assert_eq!(args.login_type, "auto");
+        assert!(apply_profile(login_args(&[]), "x", &ClusterProfile::default()).is_err());
+
+        let args = resolve(login_args(&[]), "https://k8s:6443").unwrap();
+        assert_eq!(args.server.as_deref(), Some("https://k8s:6443"));
+        assert!(resolve(login_args(&["--server", "https://a"]), "https://b").is_err());
+    }
+}
@@ -1,7 +1,9 @@
 //! Rancher-managed cluster login
 
 use crate::error::{K8pkError, Result};
+use crate::i18n::tr;
 use crate::kubeconfig::{self, KubeConfig};
+use crate::prompt::Ask;
 use inquire::Password;
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -23,6 +25,16 @@ pub(super) fn rancher_proxy_url_if_cluster_url(server: &str) -> Option<String> {
     Some(format!("{}/k8s/clusters/{}", base, id))
 }
 
+/// Connection failures and timeouts mean the server is unreachable; anything
+/// else stays a generic HTTP error
+fn send_error(what: &str, e: reqwest::Error) -> K8pkError {
+    if e.is_connect() || e.is_timeout() {
+        K8pkError::ClusterUnreachable(format!("{}: {}", what, e))
+    } else {
+        K8pkError::HttpError(format!("{}: {}", what, e))
+    }
+}
+
 pub(super) fn rancher_auth_error_is_401(e: &K8pkError) -> bool {
     match e {
         K8pkError::LoginFailed(msg) => msg.contains("401") || msg.contains("Unauthorized"),
@@ -143,7 +155,7 @@ pub(super) fn rancher_list_clusters(
             .header("Authorization", format!("Bearer {}", token))
             .header("Accept", "application/json")
             .send()
-            .map_err(|e| K8pkError::HttpError(format!("failed to list Rancher clusters: {}", e)))?;
+            .map_err(|e| send_error("failed to list Rancher clusters", e))?;
 
         let status = response.status();
         if status.as_u16() == 401 {
@@ -228,7 +240,7 @@ pub fn rancher_pull_all(
     let selected: Vec<RancherClusterInfo> = match pattern {
         Some(p) => {
             let names: Vec<String> = clusters.iter().map(|c| c.name.clone()).collect();
-            let matched = crate::commands::context::match_pattern(p, &names);
+            let matched = crate::commands::match_pattern(p, &names);
             clusters
                 .into_iter()
                 .filter(|c| matched.contains(&c.name))
@@ -338,9 +350,7 @@ pub(super) fn rancher_get_token_single(
         .header("Accept", "application/json")
         .json(&request_body)
         .send()
-        .map_err(|e| {
-            K8pkError::HttpError(format!("failed to send request to Rancher API: {}", e))
-        })?;
+        .map_err(|e| send_error("failed to send request to Rancher API", e))?;
 
     let status = response.status();
     let response_text = response
@@ -487,18 +497,13 @@ pub(super) fn rancher_login(req: &LoginRequest) -> Result<LoginResult> {
         // Token auth - proceed
     } else if final_username.is_some() || final_password.is_some() {
         if final_username.is_none() {
-            final_username = Some(
-                inquire::Text::new("Rancher username:")
-                    .prompt()
-                    .map_err(|_| K8pkError::Cancelled)?,
-            );
+            final_username = Some(inquire::Text::new(&tr("prompt-rancher-username")).ask()?);
         }
         if final_password.is_none() {
             final_password = Some(
-                Password::new("Rancher password:")
+                Password::new(&tr("prompt-rancher-password"))
                     .without_confirmation()
-                    .prompt()
-                    .map_err(|_| K8pkError::Cancelled)?,
+                    .ask()?,
             );
         }
 
@@ -526,8 +531,8 @@ pub(super) fn rancher_login(req: &LoginRequest) -> Result<LoginResult> {
             &[&vault_key_primary, &vault_key_legacy],
             req.use_vault,
             req.quiet,
-            "Rancher username:",
-            "Rancher password:",
+            &tr("prompt-rancher-username"),
+            &tr("prompt-rancher-password"),
         )?;
         if let Some(prov) = provider {
             rancher_provider_used = prov;
@@ -545,8 +550,17 @@ pub(super) fn rancher_login(req: &LoginRequest) -> Result<LoginResult> {
         }
     }
 
+    // A bare Rancher server URL is not a Kubernetes API endpoint: import every
+    // cluster the credentials can reach instead, one kubeconfig per cluster.
+    let (base, is_proxy) = rancher_server_base_url(&cluster_server_initial);
+    if !is_proxy && req.rancher_cluster_server.is_none() {
+        let token = final_token.ok_or_else(|| {
+            K8pkError::LoginFailed("Rancher authentication token is missing".into())
+        })?;
+        return rancher_import_all(req, &base, &token, context_name);
+    }
+
     let cluster_server = {
-        let (_, is_proxy) = rancher_server_base_url(&cluster_server_initial);
         if !is_proxy && req.rancher_cluster_server.is_some() {
             if let Some(ref tok) = final_token {
                 match rancher_find_cluster_proxy_url(
@@ -588,6 +602,9 @@ pub(super) fn rancher_login(req: &LoginRequest) -> Result<LoginResult> {
             context_name,
             namespace: None,
             kubeconfig_path: None,
+            imported: Vec::new(),
+            auth_mode: None,
+            expires_at: None,
         });
     }
 
@@ -615,3 +632,56 @@ pub(super) fn rancher_login(req: &LoginRequest) -> Result<LoginResult> {
         req.test_timeout,
     )
 }
+
+/// `login --type rancher <server>`: write a proxy kubeconfig for every cluster
+/// the token can access. A single cluster is returned as the login result so
+/// the caller switches to it.
+fn rancher_import_all(
+    req: &LoginRequest,
+    base: &str,
+    token: &str,
+    server_context_name: String,
+) -> Result<LoginResult> {
+    if req.dry_run {
+        let clusters = rancher_list_clusters(base, token, req.insecure)?;
+        eprintln!("Would import {} cluster(s) from {}:", clusters.len(), base);
+        for c in &clusters {
+            eprintln!("  {} ({})", c.name, c.id);
+        }
+        return Ok(LoginResult {
+            context_name: server_context_name,
+            namespace: None,
+            kubeconfig_path: None,
+            imported: Vec::new(),
+            auth_mode: None,
+            expires_at: None,
+        });
+    }
+
+    if !req.quiet {
+        eprintln!("Importing clusters from {}...", base);
+    }
+    let imported = rancher_pull_all(
+        base,
+        token,
+        req.insecure,
+        req.output_dir.as_deref(),
+        None,
+        req.quiet,
+    )?;
+    let (context_name, kubeconfig_path) = match imported.as_slice() {
+        [only] => (
+            only.context_name.clone(),
+            Some(only.kubeconfig_path.clone()),
+        ),
+        _ => (server_context_name, None),
+    };
+    Ok(LoginResult {
+        context_name,
+        namespace: None,
+        kubeconfig_path,
+        imported,
+        auth_mode: None,
+        expires_at: None,
+    })
+}
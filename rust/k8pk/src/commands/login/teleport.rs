@@ -0,0 +1,145 @@
+//! Teleport (`tsh`) session handling shared by the teleport exec preset,
+//! `k8pk discover teleport`, and re-login.
+
+use crate::error::{K8pkError, Result};
+use std::process::Command;
+
+/// Active `tsh` profile, from `tsh status --format json`
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct TshStatus {
+    /// Proxy address (`host:port`)
+    pub proxy: String,
+    /// Teleport cluster name
+    pub cluster: String,
+    /// Certificate expiry (unix seconds), when reported
+    pub valid_until: Option<u64>,
+}
+
+impl TshStatus {
+    fn expired(&self) -> bool {
+        let now = crate::clock::now_secs();
+        self.valid_until.is_some_and(|t| t <= now)
+    }
+}
+
+fn tsh_status() -> Option<TshStatus> {
+    let output = Command::new("tsh")
+        .args(["status", "--format", "json"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_tsh_status(&serde_json::from_slice(&output.stdout).ok()?)
+}
+
+fn parse_tsh_status(json: &serde_json::Value) -> Option<TshStatus> {
+    let active = json.get("active")?;
+    let proxy = active
+        .get("profile_url")?
+        .as_str()?
+        .trim_start_matches("https://")
+        .trim_end_matches('/')
+        .to_string();
+    let cluster = active
+        .get("cluster")
+        .and_then(|c| c.as_str())
+        .map(String::from)
+        .unwrap_or_else(|| proxy.split(':').next().unwrap_or(&proxy).to_string());
+    let valid_until = active
+        .get("valid_until")
+        .and_then(|v| v.as_str())
+        .and_then(crate::clock::parse_rfc3339);
+    Some(TshStatus {
+        proxy,
+        cluster,
+        valid_until,
+    })
+}
+
+/// Return a live Teleport session, running `tsh login` first (on a TTY) when
+/// there is none or its certificate has expired.
+pub(crate) fn ensure_tsh_session(proxy: Option<&str>, quiet: bool) -> Result<TshStatus> {
+    let status = tsh_status();
+    if let Some(ref s) = status {
+        if !s.expired() && proxy.is_none_or(|p| same_proxy(p, &s.proxy)) {
+            return Ok(s.clone());
+        }
+    }
+
+    let proxy = proxy
+        .map(String::from)
+        .or_else(|| status.as_ref().map(|s| s.proxy.clone()))
+        .ok_or_else(|| {
+            K8pkError::InvalidArgument(
+                "not logged in to Teleport; pass --proxy or run `tsh login --proxy <host>`".into(),
+            )
+        })?;
+    if !quiet {
+        eprintln!("Teleport session for {} is missing or expired.", proxy);
+    }
+    tsh_login(&proxy)?;
+    tsh_status().ok_or_else(|| {
+        K8pkError::LoginFailed("tsh login finished but no active Teleport profile".into())
+    })
+}
+
+/// Renew the Teleport session for a context routed through `server` (the proxy URL)
+pub(super) fn tsh_relogin(server: &str) -> Result<()> {
+    let proxy = server
+        .trim_start_matches("https://")
+        .trim_end_matches('/')
+        .to_string();
+    tsh_login(&proxy)
+}
+
+/// Interactive `tsh login`; it drives SSO/MFA prompts itself
+fn tsh_login(proxy: &str) -> Result<()> {
+    if !crate::prompt::can_prompt() {
+        return Err(K8pkError::LoginFailed(format!(
+            "Teleport session expired; run `tsh login --proxy {}`",
+            proxy
+        )));
+    }
+    let status = Command::new("tsh")
+        .args(["login", "--proxy", proxy])
+        .status()?;
+    if !status.success() {
+        return Err(K8pkError::LoginFailed(format!(
+            "tsh login --proxy {} failed",
+            proxy
+        )));
+    }
+    Ok(())
+}
+
+/// Proxies compare equal with or without the default :443 port
+fn same_proxy(a: &str, b: &str) -> bool {
+    let norm = |s: &str| {
+        let s = s.trim_start_matches("https://").trim_end_matches('/');
+        s.strip_suffix(":443").unwrap_or(s).to_string()
+    };
+    norm(a) == norm(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_tsh_status_active_profile() {
+        let json = serde_json::json!({
+            "active": {
+                "profile_url": "https://teleport.example.com:443",
+                "cluster": "teleport.example.com",
+                "valid_until": "1970-01-01T00:00:10Z"
+            }
+        });
+        let status = parse_tsh_status(&json).unwrap();
+        assert_eq!(status.proxy, "teleport.example.com:443");
+        assert_eq!(status.cluster, "teleport.example.com");
+        assert!(status.expired());
+        assert!(same_proxy("teleport.example.com", &status.proxy));
+        assert!(parse_tsh_status(&serde_json::json!({})).is_none());
+    }
+}
@@ -1,21 +1,67 @@
 //! Command handlers for k8pk
 
+mod alias;
+mod can_i;
+mod ci;
+mod complete;
 mod context;
+mod create_context;
+mod dashboard;
+mod direnv;
+mod discover;
 mod doctor;
+mod events;
+mod filter;
 pub mod guide;
+mod init;
 mod interactive;
 mod kubeconfig_ops;
+mod kubectl_config;
 mod login;
+mod motd;
 mod organize;
+pub mod port_forward;
+mod prune;
+mod refresh;
+mod report;
+pub mod session_credential;
 pub mod sessions;
+mod shell_pod;
+mod sources;
+mod stats;
+mod sync;
 pub mod tmux;
+mod undo;
 mod update;
+mod version_skew;
 
+pub use alias::*;
+pub use can_i::*;
+pub use ci::*;
+pub use complete::*;
 pub use context::*;
+pub use create_context::*;
+pub use dashboard::*;
+pub use direnv::*;
+pub use discover::*;
 pub use doctor::run as doctor;
+pub use events::*;
+pub use filter::*;
 pub use guide::print_guide;
+pub use init::*;
 pub use interactive::*;
 pub use kubeconfig_ops::*;
+pub use kubectl_config::*;
 pub use login::*;
+pub use motd::*;
 pub use organize::*;
+pub use prune::*;
+pub use refresh::*;
+pub use report::*;
+pub use shell_pod::*;
+pub use sources::*;
+pub use stats::*;
+pub use sync::*;
+pub use undo::*;
 pub use update::*;
+pub use version_skew::*;
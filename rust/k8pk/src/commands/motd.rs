@@ -0,0 +1,154 @@
+//! Context banners ("message of the day"): text from the `motd` context
+//! setting or a `k8pk.io/motd` context extension, shown on stderr when a
+//! shell switches into the context, e.g. "PROD: change freeze until Friday".
+
+use crate::config::{self, MotdColor};
+use crate::kubeconfig::KubeConfig;
+use crate::state::CurrentState;
+use serde_yaml_ng::Value as Yaml;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Name of the context extension holding a banner: either the text itself
+/// or `{message: ..., color: ...}`
+pub const EXTENSION_NAME: &str = "k8pk.io/motd";
+
+/// Env var that hides banners (like `--no-motd`)
+pub const NO_MOTD_ENV: &str = "K8PK_NO_MOTD";
+
+// `--no-motd` lives in the process, not the environment, so shells k8pk
+// starts still show banners on their next switch
+static NO_MOTD_FLAG: AtomicBool = AtomicBool::new(false);
+
+/// Record the global `--no-motd` for this process
+pub fn set_no_motd() {
+    NO_MOTD_FLAG.store(true, Ordering::Relaxed);
+}
+
+fn disabled() -> bool {
+    NO_MOTD_FLAG.load(Ordering::Relaxed)
+        || std::env::var(NO_MOTD_ENV)
+            .is_ok_and(|v| !matches!(v.trim().to_lowercase().as_str(), "" | "0" | "false"))
+}
+
+/// The `k8pk.io/motd` extension of `context` in `cfg`
+fn extension_motd(cfg: &KubeConfig, context: &str) -> Option<(String, Option<MotdColor>)> {
+    let entry = cfg.find_context(context)?;
+    let extensions = entry
+        .rest
+        .get("context")?
+        .get("extensions")?
+        .as_sequence()?;
+    let value = extensions
+        .iter()
+        .find(|e| e.get("name").and_then(Yaml::as_str) == Some(EXTENSION_NAME))?
+        .get("extension")?;
+    match value {
+        Yaml::String(text) => Some((text.clone(), None)),
+        Yaml::Mapping(_) => {
+            let text = value.get("message")?.as_str()?.to_string();
+            let color = value
+                .get("color")
+                .and_then(|c| serde_yaml_ng::from_value(c.clone()).ok());
+            Some((text, color))
+        }
+        _ => None,
+    }
+}
+
+/// Banner text and color for `context`: the config's `motd` wins over the
+/// kubeconfig extension
+pub fn motd_for(
+    settings: &config::ContextSettings,
+    cfg: Option<&KubeConfig>,
+    context: &str,
+) -> Option<(String, MotdColor)> {
+    let (text, color) = match &settings.motd {
+        Some(text) => (text.clone(), settings.motd_color),
+        None => {
+            let (text, color) = extension_motd(cfg?, context)?;
+            (text, settings.motd_color.or(color))
+        }
+    };
+    let text = text.trim();
+    (!text.is_empty()).then(|| (text.to_string(), color.unwrap_or_default()))
+}
+
+/// Each line behind a thick bar, bold and colored when `colored`
+pub fn format_banner(text: &str, color: MotdColor, colored: bool) -> String {
+    text.lines()
+        .map(|line| {
+            if colored {
+                format!("\x1b[1;{}m\u{2503} {}\x1b[0m", color.ansi(), line)
+            } else {
+                format!("\u{2503} {}", line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Print the banner for `context` unless it is off (`--no-motd`, quiet) or
+/// the shell is already in that context (a namespace switch)
+pub fn show_motd(context: &str, kubeconfig: &Path) {
+    if disabled() || crate::render::quiet() {
+        return;
+    }
+    if CurrentState::from_env().context.as_deref() == Some(context) {
+        return;
+    }
+    let cfg = std::fs::read_to_string(kubeconfig)
+        .ok()
+        .and_then(|s| serde_yaml_ng::from_str::<KubeConfig>(&s).ok());
+    let settings = config::context_settings(context);
+    if let Some((text, color)) = motd_for(&settings, cfg.as_ref(), context) {
+        let colored = crate::theme::stderr() != crate::theme::Theme::Mono;
+        eprintln!("{}", format_banner(&text, color, colored));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_motd_sources_and_banner() {
+        let cfg: KubeConfig = serde_yaml_ng::from_str(
+            "contexts:\n\
+             - name: prod\n  context:\n    cluster: c\n    extensions:\n    \
+             - name: k8pk.io/motd\n      extension: {message: \"change freeze\", color: red}\n\
+             - name: dev\n  context:\n    cluster: c\n    extensions:\n    \
+             - name: k8pk.io/motd\n      extension: shared cluster\n\
+             - name: stage\n  context: {cluster: c}\n",
+        )
+        .unwrap();
+        let none = config::ContextSettings::default();
+        assert_eq!(
+            motd_for(&none, Some(&cfg), "prod"),
+            Some(("change freeze".to_string(), MotdColor::Red))
+        );
+        assert_eq!(
+            motd_for(&none, Some(&cfg), "dev"),
+            Some(("shared cluster".to_string(), MotdColor::Yellow))
+        );
+        assert_eq!(motd_for(&none, Some(&cfg), "stage"), None);
+
+        let configured = config::ContextSettings {
+            motd: Some("PROD".into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            motd_for(&configured, Some(&cfg), "prod"),
+            Some(("PROD".to_string(), MotdColor::Yellow))
+        );
+
+        assert_eq!(
+            format_banner("a\nb", MotdColor::Red, false),
+            "\u{2503} a\n\u{2503} b"
+        );
+        assert_eq!(
+            format_banner("a", MotdColor::Red, true),
+            "\x1b[1;31m\u{2503} a\x1b[0m"
+        );
+    }
+}
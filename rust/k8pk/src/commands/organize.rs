@@ -1,7 +1,9 @@
 //! Organize kubeconfigs by cluster type
 
+use super::alias::AliasEntry;
 use crate::error::{K8pkError, Result};
 use crate::kubeconfig::{self, KubeConfig, NamedItem};
+use crate::yaml_edit;
 use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -20,6 +22,47 @@ pub struct OrganizeResult {
     pub dry_run: bool,
     pub remove_from_source: bool,
     pub groups: Vec<OrganizeGroup>,
+    /// Friendly aliases registered afterwards (`--auto-alias`)
+    pub aliases: Vec<AliasEntry>,
+    /// Manifest for `organize --rollback` (None on dry run)
+    pub manifest: Option<PathBuf>,
+}
+
+/// One context moved by organize, and where it came from
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct MovedContext {
+    pub context: String,
+    pub cluster_type: String,
+    pub origin: PathBuf,
+    pub destination: PathBuf,
+}
+
+/// An output file written by organize, with its content beforehand (None = newly created)
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct ManifestOutput {
+    pub path: PathBuf,
+    pub previous: Option<String>,
+}
+
+/// Everything needed to undo one organize run.
+/// Holds kubeconfig content (credentials), so it is written 0600.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct OrganizeManifest {
+    pub created_at: u64,
+    pub source: PathBuf,
+    /// Source content before organize, when `--remove-from-source` rewrote it
+    pub source_original: Option<String>,
+    pub outputs: Vec<ManifestOutput>,
+    pub moved: Vec<MovedContext>,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct RollbackResult {
+    pub manifest: PathBuf,
+    pub dry_run: bool,
+    pub restored: Vec<PathBuf>,
+    pub removed: Vec<PathBuf>,
+    pub contexts: usize,
 }
 
 /// Organize a kubeconfig file into separate files by cluster type
@@ -28,6 +71,8 @@ pub fn organize_by_cluster_type(
     output_dir: Option<&Path>,
     dry_run: bool,
     remove_from_source: bool,
+    embed_certs: bool,
+    backups: &Path,
 ) -> Result<OrganizeResult> {
     let home = dirs_next::home_dir().ok_or(K8pkError::NoHomeDir)?;
 
@@ -39,6 +84,8 @@ pub fn organize_by_cluster_type(
     if !source_path.exists() {
         return Err(K8pkError::KubeconfigNotFound(source_path));
     }
+    // Organized copies would hold the plaintext on disk
+    kubeconfig::ensure_editable(&source_path)?;
 
     // Output directory
     let out_dir = output_dir
@@ -49,9 +96,21 @@ pub fn organize_by_cluster_type(
         fs::create_dir_all(&out_dir)?;
     }
 
+    // Hold the source while it is read and possibly emptied below
+    let _source_lock = if dry_run {
+        None
+    } else {
+        Some(kubeconfig::lock_kubeconfig(&source_path)?)
+    };
+
     // Load source kubeconfig
     let content = fs::read_to_string(&source_path)?;
     let mut cfg: KubeConfig = serde_yaml_ng::from_str(&content)?;
+    // The organized files live elsewhere, so relative cert paths must not
+    // stay relative to the source
+    if let Some(dir) = kubeconfig::normalize_path(&source_path.to_string_lossy()).parent() {
+        kubeconfig::resolve_relative_paths(&mut cfg, dir);
+    }
 
     // Group contexts by cluster type
     let mut by_type: HashMap<&str, Vec<&NamedItem>> = HashMap::new();
@@ -73,6 +132,8 @@ pub fn organize_by_cluster_type(
     }
 
     let mut groups = Vec::new();
+    let mut outputs = Vec::new();
+    let mut moved = Vec::new();
 
     for (cluster_type, contexts) in &by_type {
         let filename = format!("{}.yaml", cluster_type);
@@ -104,6 +165,12 @@ pub fn organize_by_cluster_type(
                     continue;
                 }
                 type_cfg.contexts.push((*ctx).clone());
+                moved.push(MovedContext {
+                    context: ctx.name.clone(),
+                    cluster_type: cluster_type.to_string(),
+                    origin: source_path.clone(),
+                    destination: dest_path.clone(),
+                });
                 if !type_cfg.clusters.iter().any(|c| c.name == cluster_name) {
                     if let Some(cluster) = cfg.clusters.iter().find(|c| c.name == cluster_name) {
                         type_cfg.clusters.push(cluster.clone());
@@ -120,10 +187,27 @@ pub fn organize_by_cluster_type(
         }
 
         type_cfg.ensure_defaults(None);
+        if embed_certs {
+            kubeconfig::embed_certs(&mut type_cfg)?;
+        }
 
-        // Write file
+        // Write file, remembering what it replaced
+        let _dest_lock = if dest_path == source_path {
+            None
+        } else {
+            Some(kubeconfig::lock_kubeconfig(&dest_path)?)
+        };
+        let previous = if dest_path.exists() {
+            Some(fs::read_to_string(&dest_path)?)
+        } else {
+            None
+        };
         let yaml = serde_yaml_ng::to_string(&type_cfg)?;
         kubeconfig::write_restricted(&dest_path, &yaml)?;
+        outputs.push(ManifestOutput {
+            path: dest_path.clone(),
+            previous,
+        });
         groups.push(OrganizeGroup {
             cluster_type: cluster_type.to_string(),
             contexts: context_names,
@@ -137,29 +221,140 @@ pub fn organize_by_cluster_type(
     // Optionally remove organized contexts from the source file (with backup).
     // Since every context is assigned a cluster type, all of them get organized
     // out, leaving the source empty.
+    let mut source_original = None;
     if remove_from_source && !dry_run {
-        if let Some(bak) = super::backup_kubeconfig(&source_path)? {
-            eprintln!("Backup saved to {}", bak.display());
+        if let Some(bak) = super::backup_kubeconfig(&source_path, backups)? {
+            if !crate::render::quiet() {
+                eprintln!("Backup saved to {}", bak.display());
+            }
         }
+        source_original = Some(content.clone());
+        let names =
+            |items: &[NamedItem]| -> Vec<String> { items.iter().map(|i| i.name.clone()).collect() };
+        let removed = [
+            ("contexts", names(&cfg.contexts)),
+            ("clusters", names(&cfg.clusters)),
+            ("users", names(&cfg.users)),
+        ];
         cfg.contexts.clear();
         cfg.clusters.clear();
         cfg.users.clear();
         cfg.current_context = None;
         cfg.ensure_defaults(None);
 
-        let yaml = serde_yaml_ng::to_string(&cfg)?;
+        // Keeps the source's comments and any other keys
+        let yaml = yaml_edit::render(&content, &cfg, |text| {
+            let mut text = yaml_edit::set_current_context(text, None)?;
+            for (key, names) in &removed {
+                text = yaml_edit::remove_items(&text, key, names)?;
+            }
+            Some(text)
+        })?;
         kubeconfig::write_restricted(&source_path, &yaml)?;
     }
 
+    let manifest = if dry_run {
+        None
+    } else {
+        let created_at = crate::clock::now_secs();
+        let manifest = OrganizeManifest {
+            created_at,
+            source: source_path.clone(),
+            source_original,
+            outputs,
+            moved,
+        };
+        let path = out_dir.join(format!("organize-manifest-{}.json", created_at));
+        kubeconfig::write_restricted(&path, &serde_json::to_string_pretty(&manifest)?)?;
+        Some(path)
+    };
+
     Ok(OrganizeResult {
         source: source_path,
         output_dir: out_dir,
         dry_run,
         remove_from_source,
         groups,
+        aliases: Vec::new(),
+        manifest,
     })
 }
 
+/// Undo an organize run: restore overwritten outputs, delete created ones,
+/// and put back the source file if it was emptied.
+pub fn rollback_organize(manifest_path: &Path, dry_run: bool) -> Result<RollbackResult> {
+    if !manifest_path.exists() {
+        return Err(K8pkError::InvalidArgument(format!(
+            "organize manifest not found: {}",
+            manifest_path.display()
+        )));
+    }
+    let manifest: OrganizeManifest = serde_json::from_str(&fs::read_to_string(manifest_path)?)?;
+
+    let mut restored = Vec::new();
+    let mut removed = Vec::new();
+
+    for output in &manifest.outputs {
+        match &output.previous {
+            Some(previous) => {
+                if !dry_run {
+                    let _lock = kubeconfig::lock_kubeconfig(&output.path)?;
+                    kubeconfig::write_restricted(&output.path, previous)?;
+                }
+                restored.push(output.path.clone());
+            }
+            None => {
+                if output.path.exists() {
+                    if !dry_run {
+                        fs::remove_file(&output.path)?;
+                    }
+                    removed.push(output.path.clone());
+                }
+            }
+        }
+    }
+
+    if let Some(ref original) = manifest.source_original {
+        if !dry_run {
+            let _lock = kubeconfig::lock_kubeconfig(&manifest.source)?;
+            kubeconfig::write_restricted(&manifest.source, original)?;
+        }
+        restored.push(manifest.source.clone());
+    }
+
+    Ok(RollbackResult {
+        manifest: manifest_path.to_path_buf(),
+        dry_run,
+        restored,
+        removed,
+        contexts: manifest.moved.len(),
+    })
+}
+
+pub fn print_rollback_summary(result: &RollbackResult) {
+    let (restore_verb, remove_verb) = if result.dry_run {
+        ("Would restore", "Would remove")
+    } else {
+        ("Restored", "Removed")
+    };
+    for path in &result.restored {
+        println!("{} {}", restore_verb, path.display());
+    }
+    for path in &result.removed {
+        println!("{} {}", remove_verb, path.display());
+    }
+    println!(
+        "\nRollback of {} context(s) from {} {}.",
+        result.contexts,
+        result.manifest.display(),
+        if result.dry_run {
+            "previewed"
+        } else {
+            "complete"
+        }
+    );
+}
+
 pub fn print_organize_summary(result: &OrganizeResult) {
     println!(
         "Organizing {} contexts:",
@@ -185,6 +380,19 @@ pub fn print_organize_summary(result: &OrganizeResult) {
     if result.remove_from_source && !result.dry_run {
         println!("Source file updated: {}", result.source.display());
     }
+    if let Some(ref manifest) = result.manifest {
+        println!(
+            "Manifest: {} (undo with: k8pk organize --rollback {})",
+            manifest.display(),
+            manifest.display()
+        );
+    }
+    if !result.aliases.is_empty() {
+        println!("Aliases added:");
+        for entry in &result.aliases {
+            println!("  {} -> {}", entry.alias, entry.context);
+        }
+    }
     if result.dry_run {
         println!("\nDry run complete. Use without --dry-run to create files.");
     } else {
@@ -195,74 +403,267 @@ pub fn print_organize_summary(result: &OrganizeResult) {
     }
 }
 
-/// Display info about contexts (the `which` command)
-pub fn display_context_info(
-    pattern: Option<&str>,
-    paths: &[PathBuf],
-    json_output: bool,
-) -> Result<()> {
+/// One context with everything `contexts --wide` and `which` show about it
+#[derive(Debug, serde::Serialize)]
+pub struct ContextRow {
+    pub context: String,
+    pub friendly_name: String,
+    pub cluster_type: &'static str,
+    pub server: Option<String>,
+    /// Namespace set on the context entry (the default for kubectl)
+    pub namespace: Option<String>,
+    pub source: Option<PathBuf>,
+    /// Last switch via k8pk (unix seconds), from the switch history
+    pub last_used: Option<u64>,
+    pub current: bool,
+    /// How the server certificate is checked in k8pk shells
+    pub tls: kubeconfig::TlsMode,
+    /// Node count, version, and namespace count (`which --peek`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub peek: Option<kubeconfig::ClusterFacts>,
+}
+
+/// Rows for the given contexts in that order (all, in kubeconfig order, when
+/// `names` is None)
+pub fn context_rows(paths: &[PathBuf], names: Option<&[String]>) -> Result<Vec<ContextRow>> {
     let context_paths = kubeconfig::list_contexts_with_paths(paths)?;
     let merged = kubeconfig::load_merged(paths)?;
-
-    let contexts: Vec<_> = if let Some(p) = pattern {
-        let all: Vec<String> = context_paths.keys().cloned().collect();
-        crate::commands::context::match_pattern(p, &all)
-    } else {
-        context_paths.keys().cloned().collect()
+    let last_used = super::context::get_last_used().unwrap_or_default();
+    let config = crate::config::load().unwrap_or_default();
+
+    let all;
+    let names = match names {
+        Some(names) => names,
+        None => {
+            all = merged.context_names();
+            &all
+        }
     };
+    let mut seen = std::collections::HashSet::new();
+
+    Ok(names
+        .iter()
+        .filter(|name| seen.insert(name.as_str()))
+        .map(|name| {
+            let server = kubeconfig::get_server_for_context(&merged, name);
+            let cluster_type = kubeconfig::detect_cluster_type(name, server.as_deref());
+            ContextRow {
+                context: name.clone(),
+                friendly_name: kubeconfig::friendly_context_name(name, cluster_type),
+                cluster_type,
+                server,
+                namespace: kubeconfig::get_namespace_for_context(&merged, name),
+                source: context_paths.get(name).cloned(),
+                last_used: last_used.get(name).copied(),
+                current: merged.current_context.as_deref() == Some(name.as_str()),
+                tls: kubeconfig::tls_mode(&merged, name, &config),
+                peek: None,
+            }
+        })
+        .collect())
+}
 
-    if contexts.is_empty() {
-        return Err(K8pkError::NoContexts);
+/// Path for display, with the home directory shortened to `~`
+pub fn display_path(path: &Path) -> String {
+    match dirs_next::home_dir() {
+        Some(home) if path.starts_with(&home) => {
+            format!("~/{}", path.strip_prefix(&home).unwrap_or(path).display())
+        }
+        _ => path.display().to_string(),
     }
+}
 
-    let mut results = Vec::new();
-
-    for ctx_name in &contexts {
-        let source_file = context_paths.get(ctx_name);
-
-        let server_url = merged
-            .contexts
+/// Aligned table for `k8pk contexts --wide`
+pub fn print_context_table(rows: &[ContextRow]) {
+    let table: Vec<[String; 7]> = rows
+        .iter()
+        .map(|r| {
+            let source = r.source.as_deref().map(display_path);
+            [
+                format!("{}{}", r.context, if r.current { " *" } else { "" }),
+                r.friendly_name.clone(),
+                r.cluster_type.to_string(),
+                r.server.clone().unwrap_or_else(|| "-".into()),
+                r.namespace.clone().unwrap_or_else(|| "-".into()),
+                source.unwrap_or_else(|| "-".into()),
+                r.last_used
+                    .map(|t| format!("{} ago", super::sessions::format_age(t)))
+                    .unwrap_or_else(|| "-".into()),
+            ]
+        })
+        .collect();
+
+    let header = [
+        "CONTEXT",
+        "FRIENDLY",
+        "TYPE",
+        "SERVER",
+        "NAMESPACE",
+        "SOURCE",
+        "LAST USED",
+    ];
+    let mut widths: Vec<usize> = header.iter().map(|h| h.len()).collect();
+    for row in &table {
+        for (w, cell) in widths.iter_mut().zip(row) {
+            *w = (*w).max(cell.chars().count());
+        }
+    }
+    let line = |cells: Vec<&str>| {
+        let last = cells.len() - 1;
+        let out: Vec<String> = cells
             .iter()
-            .find(|c| c.name == *ctx_name)
-            .and_then(|ctx| kubeconfig::extract_context_refs(&ctx.rest).ok())
-            .and_then(|(cluster_name, _)| {
-                merged
-                    .clusters
-                    .iter()
-                    .find(|c| c.name == cluster_name)
-                    .and_then(|c| kubeconfig::extract_server_url_from_cluster(&c.rest))
-            });
+            .enumerate()
+            .map(|(i, c)| {
+                if i == last {
+                    c.to_string()
+                } else {
+                    format!("{:<width$}", c, width = widths[i])
+                }
+            })
+            .collect();
+        println!("{}", out.join("  "));
+    };
+    line(header.to_vec());
+    for row in &table {
+        line(row.iter().map(String::as_str).collect());
+    }
+}
 
-        let cluster_type = kubeconfig::detect_cluster_type(ctx_name, server_url.as_deref());
-        let friendly = kubeconfig::friendly_context_name(ctx_name, cluster_type);
-
-        if json_output {
-            results.push(serde_json::json!({
-                "context": ctx_name,
-                "friendly_name": friendly,
-                "cluster_type": cluster_type,
-                "server": server_url,
-                "source": source_file.map(|p| p.to_string_lossy().to_string()),
-            }));
-        } else {
-            println!("Context: {}", ctx_name);
-            println!("  Type: {}", cluster_type);
-            println!("  Friendly name: {}", friendly);
-            if let Some(url) = &server_url {
-                println!("  Server: {}", url);
-            }
-            if let Some(f) = source_file {
-                println!("  Source: {}", f.display());
+/// Display info about contexts (the `which` command)
+pub fn display_context_info(
+    filter: &super::ContextFilter,
+    sort: Option<super::SortKey>,
+    paths: &[PathBuf],
+    peek: bool,
+    json_output: bool,
+) -> Result<()> {
+    let mut rows = super::select_context_rows(paths, filter, sort)?;
+    if rows.is_empty() {
+        return Err(K8pkError::NoContexts);
+    }
+    if peek {
+        peek_rows(&mut rows, paths);
+    }
+
+    if json_output {
+        println!("{}", crate::render::to_string(&rows)?);
+        return Ok(());
+    }
+    for row in &rows {
+        println!("Context: {}", row.context);
+        println!("  Type: {}", row.cluster_type);
+        println!("  Friendly name: {}", row.friendly_name);
+        if let Some(url) = &row.server {
+            println!("  Server: {}", url);
+            if url.starts_with("https://") {
+                println!("  TLS: {}", row.tls.describe());
             }
-            println!();
         }
+        if let Some(f) = &row.source {
+            println!("  Source: {}", f.display());
+        }
+        if peek {
+            print_cluster_facts(row.peek.as_ref());
+        }
+        println!();
     }
+    Ok(())
+}
 
-    if json_output {
-        println!("{}", serde_json::to_string_pretty(&results)?);
+/// Fill in [`ContextRow::peek`], querying the clusters in parallel
+pub fn peek_rows(rows: &mut [ContextRow], paths: &[PathBuf]) {
+    let kubeconfig = std::env::join_paths(paths)
+        .ok()
+        .map(|p| p.to_string_lossy().into_owned());
+    std::thread::scope(|s| {
+        for row in rows.iter_mut() {
+            let kubeconfig = kubeconfig.as_deref();
+            s.spawn(move || row.peek = kubeconfig::cluster_facts(&row.context, kubeconfig));
+        }
+    });
+}
+
+fn print_cluster_facts(facts: Option<&kubeconfig::ClusterFacts>) {
+    let Some(facts) = facts else {
+        println!("  Cluster: unreachable");
+        return;
+    };
+    let unknown = || "?".to_string();
+    println!(
+        "  Version: {}",
+        facts.version.clone().unwrap_or_else(unknown)
+    );
+    println!(
+        "  Nodes: {}",
+        facts.nodes.map_or_else(unknown, |n| n.to_string())
+    );
+    println!(
+        "  Namespaces: {}",
+        facts.namespaces.map_or_else(unknown, |n| n.to_string())
+    );
+    if facts.is_stale() {
+        println!(
+            "  (cluster unreachable; as of {} ago)",
+            super::sessions::format_age(facts.fetched_at)
+        );
     }
+}
 
-    Ok(())
+/// `k8pk which FILE`: where a generated kubeconfig came from
+#[derive(Debug, serde::Serialize)]
+pub struct GeneratedFileInfo {
+    pub path: PathBuf,
+    /// As recorded in the file's `k8pk.io/source` extension
+    pub provenance: crate::provenance::Provenance,
+    /// The source file is gone or has been edited since
+    pub source_changed: bool,
+    /// The context still exists under its recorded name
+    pub context_exists: bool,
+}
+
+/// Provenance of the generated kubeconfig at `path`
+pub fn generated_file_info(path: &Path, paths: &[PathBuf]) -> Result<GeneratedFileInfo> {
+    let provenance = crate::provenance::read_file(path).ok_or_else(|| {
+        K8pkError::InvalidArgument(format!(
+            "{} has no k8pk provenance (not generated by k8pk, encrypted, or from an older version)",
+            path.display()
+        ))
+    })?;
+    let merged = kubeconfig::load_merged(paths)?;
+    Ok(GeneratedFileInfo {
+        path: path.to_path_buf(),
+        source_changed: provenance.source_changed(),
+        context_exists: merged.find_context(&provenance.context).is_some(),
+        provenance,
+    })
+}
+
+pub fn print_generated_file_info(info: &GeneratedFileInfo) {
+    let p = &info.provenance;
+    println!("File: {}", info.path.display());
+    println!(
+        "  Context: {}{}",
+        p.context,
+        if info.context_exists {
+            ""
+        } else {
+            " (no longer in your kubeconfigs: renamed or removed)"
+        }
+    );
+    println!(
+        "  Source: {}{}",
+        p.file.display(),
+        if info.source_changed {
+            " (changed since)"
+        } else {
+            ""
+        }
+    );
+    println!(
+        "  Generated: {} ago by k8pk {}",
+        super::sessions::format_age(p.generated_at),
+        p.k8pk_version
+    );
 }
 
 #[cfg(test)]
@@ -297,16 +698,47 @@ users:
       token: ocp-token
 "#;
 
+    #[test]
+    fn test_context_rows() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("config");
+        fs::write(&source, MIXED_KUBECONFIG).unwrap();
+
+        let rows = context_rows(std::slice::from_ref(&source), None).unwrap();
+        assert_eq!(rows[0].context, "arn:aws:eks:us-east-1:123:cluster/prod");
+
+        let eks = rows
+            .iter()
+            .find(|r| r.context == "arn:aws:eks:us-east-1:123:cluster/prod")
+            .unwrap();
+        assert_eq!(eks.cluster_type, "eks");
+        assert_eq!(eks.friendly_name, "prod");
+        assert_eq!(eks.server.as_deref(), Some("https://abc.eks.amazonaws.com"));
+        assert_eq!(eks.source.as_deref(), Some(source.as_path()));
+
+        let only = vec!["admin/api-ocp-example-com:6443/admin".to_string()];
+        let rows = context_rows(std::slice::from_ref(&source), Some(&only)).unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].cluster_type, "ocp");
+    }
+
     #[test]
     fn test_organize_dry_run() {
         let dir = tempfile::tempdir().unwrap();
+        let backups = dir.path().join("backups");
         let source = dir.path().join("config");
         fs::write(&source, MIXED_KUBECONFIG).unwrap();
 
         let out_dir = dir.path().join("organized");
-        let result =
-            organize_by_cluster_type(Some(source.as_path()), Some(out_dir.as_path()), true, false)
-                .unwrap();
+        let result = organize_by_cluster_type(
+            Some(source.as_path()),
+            Some(out_dir.as_path()),
+            true,
+            false,
+            false,
+            &backups,
+        )
+        .unwrap();
 
         assert!(result.dry_run);
         assert!(
@@ -320,6 +752,7 @@ users:
     #[test]
     fn test_organize_creates_files() {
         let dir = tempfile::tempdir().unwrap();
+        let backups = dir.path().join("backups");
         let source = dir.path().join("config");
         fs::write(&source, MIXED_KUBECONFIG).unwrap();
 
@@ -329,6 +762,8 @@ users:
             Some(out_dir.as_path()),
             false,
             false,
+            false,
+            &backups,
         )
         .unwrap();
 
@@ -351,13 +786,20 @@ users:
     #[test]
     fn test_organize_remove_from_source() {
         let dir = tempfile::tempdir().unwrap();
+        let backups = dir.path().join("backups");
         let source = dir.path().join("config");
         fs::write(&source, MIXED_KUBECONFIG).unwrap();
 
         let out_dir = dir.path().join("organized");
-        let result =
-            organize_by_cluster_type(Some(source.as_path()), Some(out_dir.as_path()), false, true)
-                .unwrap();
+        let result = organize_by_cluster_type(
+            Some(source.as_path()),
+            Some(out_dir.as_path()),
+            false,
+            true,
+            false,
+            &backups,
+        )
+        .unwrap();
 
         assert!(!result.groups.is_empty());
 
@@ -368,13 +810,74 @@ users:
         assert!(cfg.clusters.is_empty(), "source clusters should be cleared");
         assert!(cfg.users.is_empty(), "source users should be cleared");
 
-        // Backup should exist
+        // Backups go to k8pk's data dir, not next to the source
         let backups: Vec<_> = fs::read_dir(dir.path())
             .unwrap()
             .filter_map(|e| e.ok())
             .filter(|e| e.file_name().to_string_lossy().contains(".bak."))
             .collect();
-        assert!(!backups.is_empty(), "backup file should exist");
+        assert!(backups.is_empty(), "no backup file beside the source");
+    }
+
+    #[test]
+    fn test_organize_rollback_restores_layout() {
+        let dir = tempfile::tempdir().unwrap();
+        let backups = dir.path().join("backups");
+        let source = dir.path().join("config");
+        fs::write(&source, MIXED_KUBECONFIG).unwrap();
+
+        let out_dir = dir.path().join("organized");
+        fs::create_dir_all(&out_dir).unwrap();
+        // Pre-existing output file that organize will overwrite
+        let existing = out_dir.join("eks.yaml");
+        fs::write(&existing, "previous: content\n").unwrap();
+
+        let result = organize_by_cluster_type(
+            Some(source.as_path()),
+            Some(out_dir.as_path()),
+            false,
+            true,
+            false,
+            &backups,
+        )
+        .unwrap();
+        let manifest = result.manifest.expect("manifest written");
+        let parsed: OrganizeManifest =
+            serde_json::from_str(&fs::read_to_string(&manifest).unwrap()).unwrap();
+        assert_eq!(parsed.moved.len(), 2);
+        assert!(parsed.moved.iter().all(|m| m.origin == source));
+
+        let preview = rollback_organize(&manifest, true).unwrap();
+        assert!(preview.dry_run);
+        assert_ne!(fs::read_to_string(&source).unwrap(), MIXED_KUBECONFIG);
+
+        let undone = rollback_organize(&manifest, false).unwrap();
+        assert_eq!(undone.contexts, 2);
+        assert_eq!(fs::read_to_string(&source).unwrap(), MIXED_KUBECONFIG);
+        assert_eq!(
+            fs::read_to_string(&existing).unwrap(),
+            "previous: content\n"
+        );
+        assert!(!out_dir.join("ocp.yaml").exists());
+    }
+
+    #[test]
+    fn test_organize_dry_run_writes_no_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let backups = dir.path().join("backups");
+        let source = dir.path().join("config");
+        fs::write(&source, MIXED_KUBECONFIG).unwrap();
+        let out_dir = dir.path().join("organized");
+        let result = organize_by_cluster_type(
+            Some(source.as_path()),
+            Some(out_dir.as_path()),
+            true,
+            false,
+            false,
+            &backups,
+        )
+        .unwrap();
+        assert!(result.manifest.is_none());
     }
 
     /// Two contexts share cluster type `k8s`; one has refs to missing cluster/user and must be omitted from written output.
@@ -403,6 +906,7 @@ users:
     #[test]
     fn test_organize_skips_broken_context_refs() {
         let dir = tempfile::tempdir().unwrap();
+        let backups = dir.path().join("backups");
         let source = dir.path().join("config");
         fs::write(&source, BROKEN_REFS_KUBECONFIG).unwrap();
 
@@ -412,6 +916,8 @@ users:
             Some(out_dir.as_path()),
             false,
             false,
+            false,
+            &backups,
         )
         .unwrap();
 
@@ -436,4 +942,51 @@ users:
 
         assert_eq!(result.groups.len(), 1);
     }
+
+    #[test]
+    fn test_organize_resolves_and_embeds_certs() {
+        let dir = tempfile::tempdir().unwrap();
+        let backups = dir.path().join("backups");
+        fs::write(dir.path().join("ca.crt"), "CA").unwrap();
+        let source = dir.path().join("config");
+        let kubeconfig = BROKEN_REFS_KUBECONFIG.replace(
+            "server: https://127.0.0.1:443",
+            "server: https://127.0.0.1:443\n      certificate-authority: ca.crt",
+        );
+        fs::write(&source, kubeconfig).unwrap();
+
+        let plain = dir.path().join("plain");
+        organize_by_cluster_type(
+            Some(source.as_path()),
+            Some(plain.as_path()),
+            false,
+            false,
+            false,
+            &backups,
+        )
+        .unwrap();
+        let cfg: KubeConfig =
+            serde_yaml_ng::from_str(&fs::read_to_string(plain.join("k8s.yaml")).unwrap()).unwrap();
+        assert_eq!(
+            cfg.clusters[0].rest["cluster"]["certificate-authority"].as_str(),
+            dir.path().join("ca.crt").to_str()
+        );
+
+        let embedded = dir.path().join("embedded");
+        organize_by_cluster_type(
+            Some(source.as_path()),
+            Some(embedded.as_path()),
+            false,
+            false,
+            true,
+            &backups,
+        )
+        .unwrap();
+        let cfg: KubeConfig =
+            serde_yaml_ng::from_str(&fs::read_to_string(embedded.join("k8s.yaml")).unwrap())
+                .unwrap();
+        let cluster = &cfg.clusters[0].rest["cluster"];
+        assert!(cluster.get("certificate-authority").is_none());
+        assert_eq!(cluster["certificate-authority-data"].as_str(), Some("Q0E="));
+    }
 }
@@ -0,0 +1,546 @@
+//! Long-running port-forwards (`k8pk pf`).
+//!
+//! Definitions are kept in `~/.local/share/k8pk/port-forwards.json`, so they
+//! survive a reboot and can be brought back with `k8pk pf start`. Each running
+//! forward is a detached `k8pk pf supervise <name>` process that runs
+//! `kubectl port-forward` against the context's isolated kubeconfig and
+//! restarts it with backoff whenever it exits (pod rescheduled, network
+//! dropped, token expired). The supervisor holds a lock on
+//! `port-forwards/<name>.lock` for as long as it runs; its PID and restart
+//! count live in `port-forwards/<name>.json`, and kubectl's output in
+//! `port-forwards/<name>.log`. A state file only counts while its lock is
+//! held, so a PID that was reused by another process is never signalled.
+
+use crate::clock::now_secs;
+use crate::error::{K8pkError, Result};
+use crate::kubeconfig;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// A run that lasted this long counts as healthy and resets the backoff
+const HEALTHY_RUN: Duration = Duration::from_secs(60);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// A saved port-forward definition.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PortForward {
+    pub name: String,
+    pub context: String,
+    pub namespace: String,
+    /// `svc/x`, `pod/x`, `deploy/x`, ...
+    pub target: String,
+    /// `LOCAL:REMOTE` pairs as given to kubectl
+    pub ports: Vec<String>,
+    /// Local address to listen on (kubectl's default is localhost)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+}
+
+impl PortForward {
+    /// Local ports this forward listens on
+    pub fn local_ports(&self) -> Vec<u16> {
+        self.ports
+            .iter()
+            .filter_map(|p| p.split(':').next()?.parse().ok())
+            .collect()
+    }
+}
+
+/// Supervisor state of a running forward
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RunState {
+    pid: u32,
+    started_at: u64,
+    #[serde(default)]
+    restarts: u32,
+}
+
+/// A definition with its supervisor state, for `k8pk pf list`
+#[derive(Debug, Clone, Serialize)]
+pub struct PortForwardStatus {
+    #[serde(flatten)]
+    pub forward: PortForward,
+    pub running: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pid: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<u64>,
+    pub restarts: u32,
+}
+
+fn store_path() -> Result<PathBuf> {
+    Ok(crate::storage::data_dir()?.join("port-forwards.json"))
+}
+
+fn run_dir() -> Result<PathBuf> {
+    let dir = crate::storage::data_dir()?.join("port-forwards");
+    crate::storage::create_private_dir(&dir)?;
+    Ok(dir)
+}
+
+fn state_path(dir: &Path, name: &str) -> PathBuf {
+    dir.join(format!("{}.json", name))
+}
+
+fn lock_path(dir: &Path, name: &str) -> PathBuf {
+    dir.join(format!("{}.lock", name))
+}
+
+/// Take the supervisor lock of a forward without waiting; `None` while
+/// another supervisor holds it
+#[cfg(unix)]
+fn try_lock(dir: &Path, name: &str) -> Result<Option<fs::File>> {
+    use std::os::unix::io::AsRawFd;
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(lock_path(dir, name))?;
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } == 0 {
+        return Ok(Some(file));
+    }
+    let err = std::io::Error::last_os_error();
+    if err.raw_os_error() == Some(libc::EWOULDBLOCK) {
+        Ok(None)
+    } else {
+        Err(err.into())
+    }
+}
+
+#[cfg(not(unix))]
+fn try_lock(dir: &Path, name: &str) -> Result<Option<fs::File>> {
+    Ok(Some(fs::File::create(lock_path(dir, name))?))
+}
+
+/// Whether a supervisor for `name` is running (holds its lock)
+#[cfg(unix)]
+fn is_supervised(dir: &Path, name: &str) -> bool {
+    matches!(try_lock(dir, name), Ok(None))
+}
+
+#[cfg(not(unix))]
+fn is_supervised(_dir: &Path, _name: &str) -> bool {
+    true
+}
+
+/// Log file of a forward's supervisor and kubectl
+pub fn log_path(name: &str) -> Result<PathBuf> {
+    Ok(run_dir()?.join(format!("{}.log", name)))
+}
+
+/// All saved definitions
+pub fn load() -> Result<Vec<PortForward>> {
+    let path = store_path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(&path)?;
+    if content.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    serde_json::from_str(&content).map_err(|e| {
+        K8pkError::Other(format!(
+            "corrupt port-forward list at {}: {}",
+            path.display(),
+            e
+        ))
+    })
+}
+
+fn save(forwards: &[PortForward]) -> Result<()> {
+    let path = store_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    kubeconfig::write_restricted(&path, &serde_json::to_string_pretty(forwards)?)
+}
+
+/// Check `PORT` / `LOCAL:REMOTE` specs. A random local port (`:REMOTE`) is
+/// refused, as it would change on every restart.
+pub fn parse_ports(ports: &[String]) -> Result<Vec<String>> {
+    if ports.is_empty() {
+        return Err(K8pkError::InvalidArgument(
+            "at least one port (e.g. 8080:80) is required".into(),
+        ));
+    }
+    let port = |s: &str, spec: &str| -> Result<u16> {
+        s.parse::<u16>().ok().filter(|p| *p != 0).ok_or_else(|| {
+            K8pkError::InvalidArgument(format!(
+                "invalid port mapping '{}': expected PORT or LOCAL:REMOTE",
+                spec
+            ))
+        })
+    };
+    ports
+        .iter()
+        .map(|spec| match spec.split_once(':') {
+            Some((local, remote)) => {
+                port(local, spec)?;
+                port(remote, spec)?;
+                Ok(spec.clone())
+            }
+            None => {
+                port(spec, spec)?;
+                Ok(format!("{}:{}", spec, spec))
+            }
+        })
+        .collect()
+}
+
+/// `svc/web` forwarding 8080 is named `web-8080`
+pub fn default_name(target: &str, ports: &[String]) -> String {
+    let resource = target.rsplit('/').next().unwrap_or(target);
+    let local = ports
+        .first()
+        .and_then(|p| p.split(':').next())
+        .unwrap_or_default();
+    format!("{}-{}", resource, local)
+}
+
+/// Check a new definition against the saved ones: names must be unique and
+/// no two forwards may listen on the same local port.
+fn check_conflicts(forward: &PortForward, existing: &[PortForward]) -> Result<()> {
+    if forward.name.is_empty()
+        || forward.name.contains(['/', '\\'])
+        || forward.name.starts_with('.')
+    {
+        return Err(K8pkError::InvalidArgument(format!(
+            "invalid port-forward name '{}'",
+            forward.name
+        )));
+    }
+    if existing.iter().any(|f| f.name == forward.name) {
+        return Err(K8pkError::InvalidArgument(format!(
+            "a port-forward named '{}' already exists (remove it with `k8pk pf rm {}`)",
+            forward.name, forward.name
+        )));
+    }
+    let local = forward.local_ports();
+    if let Some((other, port)) = existing.iter().find_map(|f| {
+        f.local_ports()
+            .into_iter()
+            .find(|p| local.contains(p))
+            .map(|p| (f, p))
+    }) {
+        return Err(K8pkError::InvalidArgument(format!(
+            "local port {} is already used by port-forward '{}'",
+            port, other.name
+        )));
+    }
+    Ok(())
+}
+
+/// Save a definition and start it. `kubeconfig_env` is the KUBECONFIG the
+/// supervisor resolves the context from.
+pub fn add(forward: PortForward, kubeconfig_env: Option<&str>) -> Result<PortForwardStatus> {
+    let mut forwards = load()?;
+    check_conflicts(&forward, &forwards)?;
+    forwards.push(forward.clone());
+    save(&forwards)?;
+    spawn_supervisor(&forward.name, kubeconfig_env)?;
+    Ok(status_of(&run_dir()?, forward))
+}
+
+fn status_of(dir: &Path, forward: PortForward) -> PortForwardStatus {
+    let state = read_state(dir, &forward.name);
+    PortForwardStatus {
+        running: state.is_some(),
+        pid: state.as_ref().map(|s| s.pid),
+        started_at: state.as_ref().map(|s| s.started_at),
+        restarts: state.map(|s| s.restarts).unwrap_or(0),
+        forward,
+    }
+}
+
+/// The state of a live supervisor; a state file left by a dead one is removed.
+/// The supervisor writes its state only once it holds the lock, so while the
+/// lock is held the PID is the supervisor's.
+fn read_state(dir: &Path, name: &str) -> Option<RunState> {
+    let path = state_path(dir, name);
+    let state: RunState = serde_json::from_str(&fs::read_to_string(&path).ok()?).ok()?;
+    if is_supervised(dir, name) && super::sessions::is_pid_alive(state.pid) {
+        Some(state)
+    } else {
+        let _ = fs::remove_file(&path);
+        None
+    }
+}
+
+fn write_state(dir: &Path, name: &str, state: &RunState) -> Result<()> {
+    kubeconfig::write_restricted(&state_path(dir, name), &serde_json::to_string(state)?)
+}
+
+/// All definitions with their supervisor state
+pub fn list() -> Result<Vec<PortForwardStatus>> {
+    let dir = run_dir()?;
+    Ok(load()?.into_iter().map(|f| status_of(&dir, f)).collect())
+}
+
+/// Definitions selected by `name`, or all of them
+fn select(name: Option<&str>) -> Result<Vec<PortForward>> {
+    let forwards = load()?;
+    match name {
+        None => Ok(forwards),
+        Some(name) => match forwards.into_iter().find(|f| f.name == name) {
+            Some(f) => Ok(vec![f]),
+            None => Err(K8pkError::InvalidArgument(format!(
+                "no port-forward named '{}' (see `k8pk pf list`)",
+                name
+            ))),
+        },
+    }
+}
+
+/// Start the named forward, or every stopped one. Returns those started.
+pub fn start(name: Option<&str>, kubeconfig_env: Option<&str>) -> Result<Vec<PortForwardStatus>> {
+    let dir = run_dir()?;
+    let mut started = Vec::new();
+    for forward in select(name)? {
+        if read_state(&dir, &forward.name).is_none() {
+            spawn_supervisor(&forward.name, kubeconfig_env)?;
+            started.push(status_of(&dir, forward));
+        }
+    }
+    Ok(started)
+}
+
+/// Stop the named forward, or every running one. Returns those stopped.
+pub fn stop(name: Option<&str>) -> Result<Vec<PortForward>> {
+    let dir = run_dir()?;
+    let mut stopped = Vec::new();
+    for forward in select(name)? {
+        if let Some(state) = read_state(&dir, &forward.name) {
+            kill_group(state.pid)?;
+            let _ = fs::remove_file(state_path(&dir, &forward.name));
+            stopped.push(forward);
+        }
+    }
+    Ok(stopped)
+}
+
+/// Stop a forward and forget its definition
+pub fn remove(name: &str) -> Result<PortForward> {
+    stop(Some(name))?;
+    let mut forwards = load()?;
+    let pos = forwards
+        .iter()
+        .position(|f| f.name == name)
+        .ok_or_else(|| K8pkError::InvalidArgument(format!("no port-forward named '{}'", name)))?;
+    let removed = forwards.remove(pos);
+    save(&forwards)?;
+    let _ = fs::remove_file(log_path(name)?);
+    let _ = fs::remove_file(lock_path(&run_dir()?, name));
+    Ok(removed)
+}
+
+/// Launch `k8pk pf supervise <name>` in its own session, detached from the
+/// terminal, with output going to the forward's log.
+fn spawn_supervisor(name: &str, kubeconfig_env: Option<&str>) -> Result<()> {
+    let dir = run_dir()?;
+    let log = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(log_path(name)?)?;
+    let mut cmd = Command::new(std::env::current_exe()?);
+    cmd.args(["pf", "supervise", name])
+        .stdin(Stdio::null())
+        .stdout(log.try_clone()?)
+        .stderr(log);
+    if let Some(env) = kubeconfig_env {
+        cmd.env("KUBECONFIG", env);
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        // SAFETY: setsid is async-signal-safe and touches no Rust state
+        unsafe {
+            cmd.pre_exec(|| {
+                libc::setsid();
+                Ok(())
+            });
+        }
+    }
+    let mut child = cmd.spawn()?;
+    // Wait for the supervisor to take its lock and record itself, so the
+    // forward shows as running once this returns
+    for _ in 0..100 {
+        if read_state(&dir, name).is_some_and(|s| s.pid == child.id()) {
+            break;
+        }
+        if child.try_wait()?.is_some() {
+            return Err(K8pkError::CommandFailed(format!(
+                "port-forward supervisor for '{}' exited; see {}",
+                name,
+                log_path(name)?.display()
+            )));
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    Ok(())
+}
+
+/// Terminate a supervisor and its kubectl (they share a process group).
+#[cfg(unix)]
+fn kill_group(pid: u32) -> Result<()> {
+    // The supervisor is a session leader, so its PID is also the group's
+    if unsafe { libc::kill(-(pid as libc::pid_t), libc::SIGTERM) } != 0 {
+        let err = std::io::Error::last_os_error();
+        if err.raw_os_error() != Some(libc::ESRCH) {
+            return Err(err.into());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn kill_group(_pid: u32) -> Result<()> {
+    Err(K8pkError::Other(
+        "stopping port-forwards is only supported on Unix".into(),
+    ))
+}
+
+fn backoff_after(ran: Duration, previous: Duration) -> Duration {
+    if ran >= HEALTHY_RUN {
+        Duration::from_secs(1)
+    } else {
+        (previous * 2).min(MAX_BACKOFF)
+    }
+}
+
+/// Supervisor loop (`k8pk pf supervise`): run kubectl port-forward until the
+/// definition is removed, restarting it with exponential backoff.
+pub fn supervise(name: &str, paths: &[PathBuf]) -> Result<()> {
+    let dir = run_dir()?;
+    let Some(_lock) = try_lock(&dir, name)? else {
+        eprintln!("[{}] {} is already supervised", now_secs(), name);
+        return Ok(());
+    };
+    let mut state = RunState {
+        pid: std::process::id(),
+        started_at: now_secs(),
+        restarts: 0,
+    };
+    write_state(&dir, name, &state)?;
+    let mut backoff = Duration::from_millis(500);
+    loop {
+        // Re-read on every round so `pf rm` ends the loop and edits apply
+        let Some(forward) = load()?.into_iter().find(|f| f.name == name) else {
+            return Ok(());
+        };
+        let started = Instant::now();
+        match run_once(&forward, paths) {
+            Ok(code) => eprintln!(
+                "[{}] kubectl port-forward exited ({})",
+                now_secs(),
+                code.map_or("signal".to_string(), |c| format!("code {}", c))
+            ),
+            Err(e) => eprintln!("[{}] error: {}", now_secs(), e),
+        }
+        backoff = backoff_after(started.elapsed(), backoff);
+        std::thread::sleep(backoff);
+        state.restarts += 1;
+        let _ = write_state(&dir, name, &state);
+    }
+}
+
+fn run_once(forward: &PortForward, paths: &[PathBuf]) -> Result<Option<i32>> {
+    let kubeconfig =
+        super::ensure_isolated_kubeconfig(&forward.context, Some(&forward.namespace), paths)?;
+    let mut cmd = Command::new(kubeconfig::find_fast_cli()?);
+    cmd.args(["port-forward", "-n", &forward.namespace, &forward.target]);
+    cmd.args(&forward.ports);
+    if let Some(address) = &forward.address {
+        cmd.args(["--address", address]);
+    }
+    cmd.env("KUBECONFIG", &kubeconfig);
+    if let Some(path) = crate::config::hinted_path() {
+        cmd.env("PATH", path);
+    }
+    eprintln!(
+        "[{}] {} {}/{}: forwarding {}",
+        now_secs(),
+        forward.name,
+        forward.context,
+        forward.namespace,
+        forward.ports.join(" ")
+    );
+    Ok(cmd.status()?.code())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn forward(name: &str, ports: &[&str]) -> PortForward {
+        PortForward {
+            name: name.into(),
+            context: "dev".into(),
+            namespace: "web".into(),
+            target: "svc/api".into(),
+            ports: ports.iter().map(|p| p.to_string()).collect(),
+            address: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_ports() {
+        let ports = |p: &[&str]| parse_ports(&p.iter().map(|s| s.to_string()).collect::<Vec<_>>());
+        assert_eq!(
+            ports(&["8080:80", "9090"]).unwrap(),
+            vec!["8080:80", "9090:9090"]
+        );
+        assert!(ports(&[":80"]).is_err());
+        assert!(ports(&["http"]).is_err());
+        assert!(ports(&["0:80"]).is_err());
+        assert!(ports(&[]).is_err());
+    }
+
+    #[test]
+    fn test_conflicts() {
+        let existing = vec![forward("api-8080", &["8080:80"])];
+        assert_eq!(default_name("svc/api", &["8080:80".into()]), "api-8080");
+        assert!(check_conflicts(&forward("api-8080", &["8081:80"]), &existing).is_err());
+        assert!(check_conflicts(&forward("other", &["9000:90", "8080:80"]), &existing).is_err());
+        assert!(check_conflicts(&forward("../x", &["9000:90"]), &existing).is_err());
+        assert!(check_conflicts(&forward("other", &["9000:90"]), &existing).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_state_needs_the_supervisor_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = RunState {
+            pid: std::process::id(),
+            started_at: now_secs(),
+            restarts: 0,
+        };
+        // A live PID without the lock is a reused PID, not a supervisor
+        write_state(dir.path(), "api", &state).unwrap();
+        assert!(read_state(dir.path(), "api").is_none());
+        assert!(!state_path(dir.path(), "api").exists());
+
+        let lock = try_lock(dir.path(), "api").unwrap().unwrap();
+        write_state(dir.path(), "api", &state).unwrap();
+        assert_eq!(read_state(dir.path(), "api").unwrap().pid, state.pid);
+        assert!(try_lock(dir.path(), "api").unwrap().is_none());
+        drop(lock);
+        assert!(read_state(dir.path(), "api").is_none());
+    }
+
+    #[test]
+    fn test_backoff() {
+        let short = Duration::from_secs(2);
+        assert_eq!(
+            backoff_after(short, Duration::from_secs(1)),
+            Duration::from_secs(2)
+        );
+        assert_eq!(backoff_after(short, Duration::from_secs(20)), MAX_BACKOFF);
+        assert_eq!(
+            backoff_after(HEALTHY_RUN, Duration::from_secs(20)),
+            Duration::from_secs(1)
+        );
+    }
+}
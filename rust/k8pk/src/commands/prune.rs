@@ -0,0 +1,337 @@
+//! Guided cleanup of stale contexts (`k8pk prune`). A context is proposed when
+//! k8pk has not seen it used for N days and its API server does not accept a
+//! TCP connection; the current context is never proposed.
+
+use super::kubeconfig_ops::{remove_contexts_from_file, remove_isolated_caches};
+use super::{RemoveContextResult, StaleContext};
+use crate::error::{K8pkError, Result};
+use crate::i18n::tr;
+use crate::kubeconfig;
+use crate::prompt::Ask;
+use inquire::MultiSelect;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct PruneCandidate {
+    pub context: String,
+    /// Kubeconfig file the context would be removed from
+    pub file: PathBuf,
+    pub server: Option<String>,
+    /// Last use (unix seconds); None if never used via k8pk
+    pub last_used: Option<u64>,
+    /// None when the server was not checked (`--include-reachable`)
+    pub reachable: Option<bool>,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct PruneResult {
+    pub days: u64,
+    pub candidates: Vec<PruneCandidate>,
+    pub removed: Vec<RemoveContextResult>,
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PruneOptions {
+    /// Propose contexts not used for this many days
+    pub days: u64,
+    /// Propose stale contexts even when their server answers
+    pub include_reachable: bool,
+    /// Seconds to wait for each server
+    pub timeout_secs: u64,
+    pub dry_run: bool,
+    pub yes: bool,
+}
+
+/// Pick candidates from the stale list. `probe` is asked once per distinct
+/// server (in parallel); a context without a server counts as unreachable.
+fn select_candidates(
+    stale: &[StaleContext],
+    files: &HashMap<String, PathBuf>,
+    servers: &HashMap<String, String>,
+    current: Option<&str>,
+    include_reachable: bool,
+    probe: impl Fn(&str) -> bool + Sync,
+) -> Vec<PruneCandidate> {
+    let stale: Vec<&StaleContext> = stale
+        .iter()
+        .filter(|s| Some(s.context.as_str()) != current && files.contains_key(&s.context))
+        .collect();
+
+    let reachable: HashMap<&str, bool> = if include_reachable {
+        HashMap::new()
+    } else {
+        let unique: HashSet<&str> = stale
+            .iter()
+            .filter_map(|s| servers.get(&s.context).map(String::as_str))
+            .collect();
+        std::thread::scope(|scope| {
+            let probe = &probe;
+            let handles: Vec<_> = unique
+                .into_iter()
+                .map(|server| (server, scope.spawn(move || probe(server))))
+                .collect();
+            handles
+                .into_iter()
+                .map(|(server, h)| (server, h.join().unwrap_or(false)))
+                .collect()
+        })
+    };
+
+    stale
+        .into_iter()
+        .filter_map(|s| {
+            let server = servers.get(&s.context).cloned();
+            let status = if include_reachable {
+                None
+            } else {
+                Some(
+                    server
+                        .as_deref()
+                        .and_then(|srv| reachable.get(srv).copied())
+                        .unwrap_or(false),
+                )
+            };
+            if status == Some(true) {
+                return None;
+            }
+            Some(PruneCandidate {
+                context: s.context.clone(),
+                file: files[&s.context].clone(),
+                server,
+                last_used: s.last_used,
+                reachable: status,
+            })
+        })
+        .collect()
+}
+
+/// "unreachable", "no server", or "not checked"
+fn reason(c: &PruneCandidate) -> &'static str {
+    match (c.reachable, &c.server) {
+        (None, _) => "not checked",
+        (_, None) => "no server",
+        _ => "unreachable",
+    }
+}
+
+fn last_used_label(last_used: Option<u64>) -> String {
+    last_used
+        .map(|t| format!("{} ago", super::sessions::format_age(t)))
+        .unwrap_or_else(|| "never".into())
+}
+
+/// Propose stale contexts and remove the ones the user keeps selected (all of
+/// them with `--yes`), along with clusters and users left unreferenced in
+/// their files and k8pk's isolated copies.
+pub fn run_prune(paths: &[PathBuf], opts: PruneOptions, backups: &Path) -> Result<PruneResult> {
+    if !opts.include_reachable {
+        // Every server would look unreachable and so be proposed for removal
+        crate::config::require_online("k8pk prune without --include-reachable")?;
+    }
+    let files = kubeconfig::list_contexts_with_paths(paths)?;
+    if files.is_empty() {
+        return Err(K8pkError::NoContexts);
+    }
+    let merged = kubeconfig::load_merged(paths)?;
+    let servers: HashMap<String, String> = merged
+        .context_names()
+        .into_iter()
+        .filter_map(|c| Some((c.clone(), kubeconfig::get_server_for_context(&merged, &c)?)))
+        .collect();
+    let stats = super::usage_stats(paths, opts.days, 0)?;
+
+    let candidates = select_candidates(
+        &stats.stale,
+        &files,
+        &servers,
+        merged.current_context.as_deref(),
+        opts.include_reachable,
+        |server| super::login::check_server_reachable(server, opts.timeout_secs).is_ok(),
+    );
+
+    let mut result = PruneResult {
+        days: opts.days,
+        candidates,
+        removed: Vec::new(),
+        dry_run: opts.dry_run,
+    };
+    if result.candidates.is_empty() || opts.dry_run {
+        return Ok(result);
+    }
+
+    let selected: Vec<&PruneCandidate> = if opts.yes {
+        result.candidates.iter().collect()
+    } else if crate::prompt::can_prompt() {
+        let labels: Vec<String> = result
+            .candidates
+            .iter()
+            .map(|c| {
+                format!(
+                    "{}  ({}, last used {}, {})",
+                    c.context,
+                    super::display_path(&c.file),
+                    last_used_label(c.last_used),
+                    reason(c)
+                )
+            })
+            .collect();
+        let picked = MultiSelect::new(&tr("pick-contexts-prune"), labels.clone())
+            .with_all_selected_by_default()
+            .ask()?;
+        if picked.is_empty() {
+            return Err(K8pkError::Cancelled);
+        }
+        result
+            .candidates
+            .iter()
+            .zip(&labels)
+            .filter(|(_, label)| picked.contains(label))
+            .map(|(c, _)| c)
+            .collect()
+    } else {
+        return Err(K8pkError::InvalidArgument(
+            "refusing to prune without confirmation; pass --yes, or --dry-run to preview".into(),
+        ));
+    };
+
+    let mut by_file: BTreeMap<&PathBuf, Vec<&str>> = BTreeMap::new();
+    for c in &selected {
+        by_file.entry(&c.file).or_default().push(&c.context);
+    }
+    let mut removed = Vec::new();
+    for (file, contexts) in by_file {
+        for ctx in contexts {
+            result.removed.push(remove_contexts_from_file(
+                file,
+                Some(ctx),
+                false,
+                true,
+                false,
+                backups,
+            )?);
+            removed.push(ctx.to_string());
+        }
+    }
+    remove_isolated_caches(&removed)?;
+    Ok(result)
+}
+
+pub fn print_prune_result(result: &PruneResult) {
+    if result.candidates.is_empty() {
+        println!(
+            "Nothing to prune: no unreachable contexts unused for {} days.",
+            result.days
+        );
+        return;
+    }
+    if !result.dry_run {
+        for r in &result.removed {
+            println!("{}:", super::display_path(&r.file));
+            super::print_remove_context_summary(r);
+        }
+        return;
+    }
+
+    println!(
+        "Would prune {} context(s) not used in {} days:",
+        result.candidates.len(),
+        result.days
+    );
+    let table: Vec<[String; 4]> = result
+        .candidates
+        .iter()
+        .map(|c| {
+            [
+                c.context.clone(),
+                super::display_path(&c.file),
+                last_used_label(c.last_used),
+                reason(c).to_string(),
+            ]
+        })
+        .collect();
+    let header = ["CONTEXT", "FILE", "LAST USED", "SERVER"];
+    let mut widths: Vec<usize> = header.iter().map(|h| h.len()).collect();
+    for row in &table {
+        for (w, cell) in widths.iter_mut().zip(row) {
+            *w = (*w).max(cell.chars().count());
+        }
+    }
+    println!(
+        "  {:<w0$}  {:<w1$}  {:<w2$}  {}",
+        header[0],
+        header[1],
+        header[2],
+        header[3],
+        w0 = widths[0],
+        w1 = widths[1],
+        w2 = widths[2]
+    );
+    for row in &table {
+        println!(
+            "  {:<w0$}  {:<w1$}  {:<w2$}  {}",
+            row[0],
+            row[1],
+            row[2],
+            row[3],
+            w0 = widths[0],
+            w1 = widths[1],
+            w2 = widths[2]
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stale(context: &str, last_used: Option<u64>) -> StaleContext {
+        StaleContext {
+            context: context.into(),
+            last_used,
+        }
+    }
+
+    fn map(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn candidates_skip_reachable_and_current() {
+        let stale = vec![
+            stale("gone", Some(10)),
+            stale("alive", None),
+            stale("noserver", None),
+            stale("current", None),
+            stale("unknown-file", None),
+        ];
+        let files: HashMap<String, PathBuf> = ["gone", "alive", "noserver", "current"]
+            .iter()
+            .map(|c| (c.to_string(), PathBuf::from("/kube/config")))
+            .collect();
+        let servers = map(&[
+            ("gone", "https://gone:6443"),
+            ("alive", "https://alive:6443"),
+            ("current", "https://gone:6443"),
+        ]);
+
+        let got = select_candidates(&stale, &files, &servers, Some("current"), false, |s| {
+            s.contains("alive")
+        });
+        let names: Vec<&str> = got.iter().map(|c| c.context.as_str()).collect();
+        assert_eq!(names, ["gone", "noserver"]);
+        assert_eq!(reason(&got[0]), "unreachable");
+        assert_eq!(reason(&got[1]), "no server");
+        assert_eq!(got[0].last_used, Some(10));
+
+        let all = select_candidates(&stale, &files, &servers, Some("current"), true, |_| {
+            panic!("no probes with include_reachable")
+        });
+        assert_eq!(all.len(), 3);
+        assert!(all.iter().all(|c| reason(c) == "not checked"));
+    }
+}
@@ -0,0 +1,142 @@
+//! `k8pk refresh`: regenerate the current session's isolated kubeconfig in
+//! place, so a running k8pk shell picks up new credentials without being
+//! re-spawned.
+
+use crate::error::{K8pkError, Result};
+use crate::kubeconfig;
+use crate::provenance::{self, Provenance};
+use crate::state::CurrentState;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, serde::Serialize)]
+pub struct RefreshResult {
+    pub context: String,
+    pub namespace: Option<String>,
+    /// The session's `KUBECONFIG`, rewritten in place
+    pub kubeconfig: PathBuf,
+    /// The file content changed
+    pub changed: bool,
+    /// The session check failed and a re-login wrote new credentials
+    pub relogged_in: bool,
+}
+
+/// Kubeconfig files to regenerate from: `paths` without k8pk-generated
+/// files (inside a k8pk shell `$KUBECONFIG` is the isolated copy itself),
+/// plus the source recorded in the session file
+fn source_paths(paths: &[PathBuf], recorded: Option<&Provenance>) -> Vec<PathBuf> {
+    let mut sources: Vec<PathBuf> = paths
+        .iter()
+        .filter(|p| provenance::read_file(p).is_none())
+        .cloned()
+        .collect();
+    if let Some(p) = recorded {
+        if !sources.contains(&p.file) {
+            sources.push(p.file.clone());
+        }
+    }
+    sources
+}
+
+/// Re-run isolation for `K8PK_CONTEXT`/`K8PK_NAMESPACE` from the source
+/// kubeconfigs, re-login when the session check fails (unless
+/// `no_session_check`), and write the result over the session's
+/// `KUBECONFIG`
+pub fn refresh_session(paths: &[PathBuf], no_session_check: bool) -> Result<RefreshResult> {
+    let state = CurrentState::from_env();
+    let context = state.context.filter(|c| !c.is_empty()).ok_or_else(|| {
+        K8pkError::InvalidArgument(
+            "not in a k8pk session (K8PK_CONTEXT is not set); run it from a shell started by k8pk ctx".into(),
+        )
+    })?;
+    let namespace = state.namespace.as_deref();
+    let session_file = state.config_path;
+    let recorded = session_file.as_deref().and_then(provenance::read_file);
+    if let (Some(file), None) = (&session_file, &recorded) {
+        return Err(K8pkError::InvalidArgument(format!(
+            "KUBECONFIG ({}) was not generated by k8pk; refusing to rewrite it",
+            super::display_path(file)
+        )));
+    }
+    let before = session_file.as_deref().and_then(|f| fs::read(f).ok());
+
+    // An explicit refresh always regenerates, whatever K8PK_NO_REFRESH says
+    provenance::set_force_refresh();
+
+    let mut sources = source_paths(paths, recorded.as_ref());
+    let mut isolated = super::ensure_isolated_kubeconfig(&context, namespace, &sources)?;
+    let mut relogged_in = false;
+    if !no_session_check {
+        // TTL 0: always probe, a cached "ok" is what we are second-guessing
+        let checked =
+            super::ensure_session_alive(&isolated, &context, namespace, &sources, false, Some(0))?;
+        if checked != isolated {
+            // Re-login wrote a new kubeconfig; it takes precedence over the old source
+            relogged_in = true;
+            sources.retain(|p| p != &checked);
+            sources.insert(0, checked);
+            isolated = super::ensure_isolated_kubeconfig(&context, namespace, &sources)?;
+        }
+    }
+
+    let kubeconfig = match session_file {
+        Some(file) if !same_file(&file, &isolated) => {
+            kubeconfig::write_restricted(&file, &fs::read_to_string(&isolated)?)?;
+            file
+        }
+        Some(file) => file,
+        None => isolated,
+    };
+    let changed = before.as_deref() != fs::read(&kubeconfig).ok().as_deref();
+    Ok(RefreshResult {
+        context,
+        namespace: namespace.map(String::from),
+        kubeconfig,
+        changed,
+        relogged_in,
+    })
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+pub fn print_refresh_result(result: &RefreshResult) {
+    let file = super::display_path(&result.kubeconfig);
+    if result.changed {
+        println!("Refreshed '{}' in {}", result.context, file);
+    } else {
+        println!("'{}' is already up to date ({})", result.context, file);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sources_skip_generated_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("config");
+        fs::write(&source, "clusters: []\n").unwrap();
+        let recorded = Provenance::for_source("dev", &source).unwrap();
+
+        let mut generated: kubeconfig::KubeConfig = serde_yaml_ng::from_str("{}").unwrap();
+        provenance::write(&mut generated, &recorded).unwrap();
+        let isolated = dir.path().join("dev.yaml");
+        fs::write(&isolated, serde_yaml_ng::to_string(&generated).unwrap()).unwrap();
+        let other = dir.path().join("other");
+        fs::write(&other, "clusters: []\n").unwrap();
+
+        let paths = vec![isolated, other.clone()];
+        assert_eq!(
+            source_paths(&paths, Some(&recorded)),
+            vec![other.clone(), source.clone()]
+        );
+        let paths = vec![source.clone(), other.clone()];
+        assert_eq!(source_paths(&paths, Some(&recorded)), vec![source, other]);
+    }
+}
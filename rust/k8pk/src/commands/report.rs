@@ -0,0 +1,442 @@
+//! `k8pk report`: a local summary of the kubeconfigs on this machine (clusters,
+//! auth methods, credentials about to expire, lint findings) for platform
+//! teams to collect. Nothing is sent anywhere and no secrets are included.
+
+use super::LintIssue;
+use crate::clock::format_date;
+use crate::error::Result;
+use crate::kubeconfig::{self, KubeConfig, NamedItem};
+use base64::Engine;
+use serde::Serialize;
+use serde_yaml_ng::Value as Yaml;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Serialize)]
+pub struct ClusterSummary {
+    pub name: String,
+    pub server: Option<String>,
+    pub contexts: Vec<String>,
+    pub file: PathBuf,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExpiringCredential {
+    pub user: String,
+    /// `client certificate` or `token`
+    pub kind: String,
+    /// Unix seconds
+    pub expires_at: u64,
+    pub expired: bool,
+    pub file: PathBuf,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Report {
+    /// Unix seconds
+    pub generated_at: u64,
+    pub k8pk_version: String,
+    pub kubeconfigs: Vec<PathBuf>,
+    pub clusters: Vec<ClusterSummary>,
+    /// Users per auth method (`exec: aws`, `client certificate`, ...)
+    pub auth_methods: BTreeMap<String, usize>,
+    /// Window for `expiring`, in days
+    pub expiring_days: u64,
+    /// Credentials expired or expiring within `expiring_days`
+    pub expiring: Vec<ExpiringCredential>,
+    pub lint: Vec<LintIssue>,
+}
+
+/// Build the report from the kubeconfig files in `paths`
+pub fn report(paths: &[PathBuf], expiring_days: u64) -> Result<Report> {
+    let now = crate::clock::now_secs();
+    let horizon = now + expiring_days * 86400;
+    let hints = crate::config::load()
+        .map(|c| crate::config::exec_path_dirs_with(&c))
+        .unwrap_or_default();
+
+    let mut report = Report {
+        generated_at: now,
+        k8pk_version: env!("CARGO_PKG_VERSION").to_string(),
+        kubeconfigs: Vec::new(),
+        clusters: Vec::new(),
+        auth_methods: BTreeMap::new(),
+        expiring_days,
+        expiring: Vec::new(),
+        lint: Vec::new(),
+    };
+
+    for path in paths.iter().filter(|p| p.is_file()) {
+        report.kubeconfigs.push(path.clone());
+        let cfg: KubeConfig = match serde_yaml_ng::from_str(&fs::read_to_string(path)?) {
+            Ok(cfg) => cfg,
+            Err(e) => {
+                report.lint.push(LintIssue {
+                    path: path.clone(),
+                    level: "error".into(),
+                    message: format!("parse error: {}", e),
+                });
+                continue;
+            }
+        };
+        report.lint.extend(super::lint_config(path, &cfg));
+        report
+            .lint
+            .extend(super::lint_exec_plugins(path, &cfg, &hints));
+
+        for cluster in &cfg.clusters {
+            let contexts = cfg
+                .contexts
+                .iter()
+                .filter(|c| {
+                    kubeconfig::extract_context_refs(&c.rest)
+                        .is_ok_and(|(cl, _)| cl == cluster.name)
+                })
+                .map(|c| c.name.clone())
+                .collect();
+            report.clusters.push(ClusterSummary {
+                name: cluster.name.clone(),
+                server: kubeconfig::extract_server_url_from_cluster(&cluster.rest),
+                contexts,
+                file: path.clone(),
+            });
+        }
+
+        let base = path.parent().unwrap_or(Path::new("."));
+        for user in &cfg.users {
+            *report.auth_methods.entry(auth_method(user)).or_default() += 1;
+            for (kind, expires_at) in credential_expiry(user, base) {
+                if expires_at <= horizon {
+                    report.expiring.push(ExpiringCredential {
+                        user: user.name.clone(),
+                        kind: kind.into(),
+                        expires_at,
+                        expired: expires_at <= now,
+                        file: path.clone(),
+                    });
+                }
+            }
+        }
+    }
+    report.expiring.sort_by_key(|c| c.expires_at);
+    Ok(report)
+}
+
+/// How a kubeconfig user authenticates, without any of its secrets
+fn auth_method(user: &NamedItem) -> String {
+    let Some(u) = user.rest.get("user") else {
+        return "none".into();
+    };
+    if let Some(command) = u
+        .get("exec")
+        .and_then(|e| e.get("command"))
+        .and_then(Yaml::as_str)
+    {
+        let name = Path::new(command)
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| command.to_string());
+        return format!("exec: {}", name);
+    }
+    if let Some(provider) = u
+        .get("auth-provider")
+        .and_then(|p| p.get("name"))
+        .and_then(Yaml::as_str)
+    {
+        return format!("auth-provider: {}", provider);
+    }
+    let has = |field: &str| u.get(field).is_some();
+    if has("client-certificate-data") || has("client-certificate") {
+        "client certificate".into()
+    } else if has("token") || has("tokenFile") {
+        "token".into()
+    } else if has("username") {
+        "basic auth".into()
+    } else {
+        "none".into()
+    }
+}
+
+/// Expiry of the user's client certificate and of its token when that is a
+/// JWT. File references are resolved against `base`.
+pub(super) fn credential_expiry(user: &NamedItem, base: &Path) -> Vec<(&'static str, u64)> {
+    let Some(u) = user.rest.get("user") else {
+        return Vec::new();
+    };
+    let field = |data: &str, file: &str| -> Option<Vec<u8>> {
+        match u.get(data).and_then(Yaml::as_str) {
+            Some(encoded) => base64::engine::general_purpose::STANDARD
+                .decode(encoded.trim())
+                .ok(),
+            None => fs::read(base.join(u.get(file)?.as_str()?)).ok(),
+        }
+    };
+
+    let mut expiry = Vec::new();
+    if let Some(at) = field("client-certificate-data", "client-certificate")
+        .and_then(|pem| cert_not_after(&pem_to_der(&pem)?))
+    {
+        expiry.push(("client certificate", at));
+    }
+    let token = u
+        .get("token")
+        .and_then(Yaml::as_str)
+        .map(|t| t.as_bytes().to_vec())
+        .or_else(|| fs::read(base.join(u.get("tokenFile")?.as_str()?)).ok());
+    if let Some(at) = token.and_then(|t| jwt_expiry(String::from_utf8_lossy(&t).trim())) {
+        expiry.push(("token", at));
+    }
+    expiry
+}
+
+/// The first certificate in a PEM bundle, as DER
+fn pem_to_der(pem: &[u8]) -> Option<Vec<u8>> {
+    let text = String::from_utf8_lossy(pem);
+    let body: String = text
+        .lines()
+        .skip_while(|l| !l.starts_with("-----BEGIN CERTIFICATE"))
+        .skip(1)
+        .take_while(|l| !l.starts_with("-----END"))
+        .collect();
+    base64::engine::general_purpose::STANDARD.decode(body).ok()
+}
+
+/// One DER element: its tag, its contents, and what follows it
+fn der_element(data: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, rest) = data.split_first()?;
+    let (&len, mut rest) = rest.split_first()?;
+    let len = if len < 0x80 {
+        usize::from(len)
+    } else {
+        let n = usize::from(len & 0x7f);
+        if n == 0 || n > 4 || rest.len() < n {
+            return None;
+        }
+        let (bytes, tail) = rest.split_at(n);
+        rest = tail;
+        bytes.iter().fold(0, |acc, &b| (acc << 8) | usize::from(b))
+    };
+    (rest.len() >= len).then(|| (tag, &rest[..len], &rest[len..]))
+}
+
+/// `notAfter` of an X.509 certificate, in unix seconds
+fn cert_not_after(der: &[u8]) -> Option<u64> {
+    let (_, certificate, _) = der_element(der)?;
+    let (_, tbs, _) = der_element(certificate)?;
+    let (tag, _, mut rest) = der_element(tbs)?;
+    if tag != 0xa0 {
+        // No explicit version: that element was the serial number
+        rest = tbs;
+    }
+    // serial number, signature algorithm, issuer
+    for _ in 0..3 {
+        rest = der_element(rest)?.2;
+    }
+    let (_, validity, _) = der_element(rest)?;
+    let (_, _, validity) = der_element(validity)?;
+    let (tag, time, _) = der_element(validity)?;
+    asn1_time(tag, std::str::from_utf8(time).ok()?)
+}
+
+/// UTCTime (`YYMMDDHHMMSSZ`) or GeneralizedTime (`YYYYMMDDHHMMSSZ`)
+fn asn1_time(tag: u8, time: &str) -> Option<u64> {
+    let digits = time.strip_suffix('Z')?;
+    let (year, rest) = match tag {
+        0x17 => {
+            let yy: i64 = digits.get(..2)?.parse().ok()?;
+            (if yy < 50 { 2000 + yy } else { 1900 + yy }, &digits[2..])
+        }
+        0x18 => (digits.get(..4)?.parse().ok()?, &digits[4..]),
+        _ => return None,
+    };
+    let field = |i: usize| rest.get(i..i + 2);
+    crate::clock::parse_rfc3339(&format!(
+        "{:04}-{}-{}T{}:{}:{}Z",
+        year,
+        field(0)?,
+        field(2)?,
+        field(4)?,
+        field(6)?,
+        field(8)?
+    ))
+}
+
+/// The `exp` claim of a JWT bearer token
+fn jwt_expiry(token: &str) -> Option<u64> {
+    let mut parts = token.split('.');
+    let (_, payload, _) = (parts.next()?, parts.next()?, parts.next()?);
+    let bytes = base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(payload.trim_end_matches('='))
+        .ok()?;
+    serde_json::from_slice::<serde_json::Value>(&bytes).ok()?["exp"].as_u64()
+}
+
+/// Markdown table cell: pipes escaped, newlines flattened
+fn cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+/// The report as Markdown, ready to paste into an issue or a wiki
+pub fn render_report_markdown(report: &Report) -> String {
+    let mut out = String::new();
+    let mut line = |s: String| {
+        out.push_str(&s);
+        out.push('\n');
+    };
+    let path = |p: &Path| cell(&super::display_path(p));
+
+    line("# k8pk report".into());
+    line(String::new());
+    line(format!(
+        "Generated {} by k8pk {} from {} kubeconfig file(s).",
+        format_date(report.generated_at),
+        report.k8pk_version,
+        report.kubeconfigs.len()
+    ));
+    line(String::new());
+    for file in &report.kubeconfigs {
+        line(format!("- `{}`", super::display_path(file)));
+    }
+
+    line(String::new());
+    line(format!("## Clusters ({})", report.clusters.len()));
+    line(String::new());
+    if report.clusters.is_empty() {
+        line("None.".into());
+    } else {
+        line("| Cluster | Server | Contexts | File |".into());
+        line("|---|---|---|---|".into());
+        for c in &report.clusters {
+            line(format!(
+                "| {} | {} | {} | {} |",
+                cell(&c.name),
+                cell(c.server.as_deref().unwrap_or("-")),
+                cell(&c.contexts.join(", ")),
+                path(&c.file)
+            ));
+        }
+    }
+
+    line(String::new());
+    line("## Auth methods".into());
+    line(String::new());
+    if report.auth_methods.is_empty() {
+        line("None.".into());
+    } else {
+        line("| Method | Users |".into());
+        line("|---|---|".into());
+        for (method, users) in &report.auth_methods {
+            line(format!("| {} | {} |", cell(method), users));
+        }
+    }
+
+    line(String::new());
+    line(format!(
+        "## Expiring credentials (next {} days)",
+        report.expiring_days
+    ));
+    line(String::new());
+    if report.expiring.is_empty() {
+        line("None.".into());
+    } else {
+        line("| User | Credential | Expires | File |".into());
+        line("|---|---|---|---|".into());
+        for c in &report.expiring {
+            let expires = if c.expired {
+                format!("{} (expired)", format_date(c.expires_at))
+            } else {
+                format_date(c.expires_at)
+            };
+            line(format!(
+                "| {} | {} | {} | {} |",
+                cell(&c.user),
+                c.kind,
+                expires,
+                path(&c.file)
+            ));
+        }
+    }
+
+    let errors = report.lint.iter().filter(|i| i.level == "error").count();
+    line(String::new());
+    line(format!(
+        "## Lint findings ({} errors, {} warnings)",
+        errors,
+        report.lint.len() - errors
+    ));
+    line(String::new());
+    if report.lint.is_empty() {
+        line("None.".into());
+    } else {
+        line("| Level | File | Finding |".into());
+        line("|---|---|---|".into());
+        for issue in &report.lint {
+            line(format!(
+                "| {} | {} | {} |",
+                issue.level,
+                path(&issue.path),
+                cell(&issue.message)
+            ));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CERT: &str = "-----BEGIN CERTIFICATE-----
+MIIBcDCCARegAwIBAgIUS3UTxOlzZsKv3UT2MeezoW2qJnEwCgYIKoZIzj0EAwIw
+DjEMMAoGA1UEAwwDZGV2MB4XDTI2MTAxNjE3MjYxN1oXDTI2MTExNTE3MjYxN1ow
+DjEMMAoGA1UEAwwDZGV2MFkwEwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAEZMQVqaAT
+3MkDTwPrTt/xTUX6Wkpkj6QttZQFswaf0hYYeWvLZht+8JIfhLWC9LalpB6KYZSt
+U+kq+Mt4ayTo2KNTMFEwHQYDVR0OBBYEFKBatVPTsB+Kj6VDzHIf1mG9necSMB8G
+A1UdIwQYMBaAFKBatVPTsB+Kj6VDzHIf1mG9necSMA8GA1UdEwEB/wQFMAMBAf8w
+CgYIKoZIzj0EAwIDRwAwRAIgc5LFUPzc3Ks3wl6VdrhkLv/cuJl7ycO3gVEhpqdz
+s7QCIA/J7snPPl0GTgp1u2arRzzV2sVMiC4m1od6XcaEBeU1
+-----END CERTIFICATE-----
+";
+
+    #[test]
+    fn auth_methods_and_credential_expiry() {
+        let cert_data = base64::engine::general_purpose::STANDARD.encode(CERT);
+        // {"sub":"dev","exp":1700000000}
+        let jwt = "eyJhbGciOiJub25lIn0.eyJzdWIiOiJkZXYiLCJleHAiOjE3MDAwMDAwMDB9.";
+        let cfg: KubeConfig = serde_yaml_ng::from_str(&format!(
+            r#"
+users:
+  - name: cert
+    user:
+      client-certificate-data: {cert_data}
+      client-key-data: a2V5
+  - name: sa
+    user:
+      token: {jwt}
+  - name: eks
+    user:
+      exec:
+        command: /usr/local/bin/aws
+"#
+        ))
+        .unwrap();
+        let methods: Vec<String> = cfg.users.iter().map(auth_method).collect();
+        assert_eq!(methods, ["client certificate", "token", "exec: aws"]);
+
+        let base = Path::new(".");
+        // notAfter=Nov 15 17:26:17 2026 GMT
+        assert_eq!(
+            credential_expiry(&cfg.users[0], base),
+            [("client certificate", 1_794_763_577)]
+        );
+        assert_eq!(
+            credential_expiry(&cfg.users[1], base),
+            [("token", 1_700_000_000)]
+        );
+        assert!(credential_expiry(&cfg.users[2], base).is_empty());
+
+        assert_eq!(asn1_time(0x18, "20500101000000Z"), Some(2_524_608_000));
+        assert_eq!(asn1_time(0x17, "240301120000Z"), Some(1_709_294_400));
+    }
+}
@@ -0,0 +1,342 @@
+//! `session_ttl` enforced where credentials are used. In an isolated
+//! kubeconfig with an expiry, each user's credentials are moved into an exec
+//! credential plugin running `k8pk session-credential`. The plugin fails once
+//! the session has expired and until then hands kubectl the original token,
+//! client certificate, or exec plugin output, so kubectl, helm, and anything
+//! else reading the file stop authenticating at expiry, not only k8pk.
+//! Users logging in through an `auth-provider` or basic auth can't be handed
+//! over this way and are left as they are.
+
+use crate::clock;
+use crate::error::{K8pkError, Result};
+use crate::kubeconfig::KubeConfig;
+use base64::Engine;
+use serde_json::Value as Json;
+use serde_yaml_ng::{Mapping, Value as Yaml};
+use std::fs;
+use std::process::{Command, Stdio};
+
+/// Hidden subcommand the wrapped users' exec plugin runs
+pub const CREDENTIAL_COMMAND: &str = "session-credential";
+
+const CONTEXT_ENV: &str = "K8PK_SESSION_CONTEXT";
+const EXPIRES_ENV: &str = "K8PK_SESSION_EXPIRES_AT";
+/// The original credential fields, as JSON
+const USER_ENV: &str = "K8PK_SESSION_USER";
+
+const API_VERSION: &str = "client.authentication.k8s.io/v1beta1";
+
+/// User fields the plugin takes over; others (impersonation, extensions) stay
+const CREDENTIAL_KEYS: &[&str] = &[
+    "token",
+    "tokenFile",
+    "client-certificate",
+    "client-certificate-data",
+    "client-key",
+    "client-key-data",
+    "exec",
+];
+
+/// Users with one of these are left unwrapped
+const UNSUPPORTED_KEYS: &[&str] = &["auth-provider", "username", "password"];
+
+/// Move the credentials of each user in `cfg` into the session plugin for
+/// `context`, expiring at `expires_at` (Unix seconds)
+pub fn gate_credentials(cfg: &mut KubeConfig, context: &str, expires_at: u64) -> Result<()> {
+    for user in &mut cfg.users {
+        let Some(Yaml::Mapping(fields)) = user.rest.get_mut("user") else {
+            continue;
+        };
+        if UNSUPPORTED_KEYS.iter().any(|k| fields.contains_key(*k)) {
+            continue;
+        }
+        let mut original = Mapping::new();
+        for key in CREDENTIAL_KEYS {
+            if let Some(value) = fields.remove(*key) {
+                original.insert(Yaml::from(*key), value);
+            }
+        }
+        if original.is_empty() {
+            continue;
+        }
+        // Plugins that need the cluster's details still get them
+        let cluster_info = original
+            .get("exec")
+            .and_then(|e| e.get("provideClusterInfo"))
+            .and_then(Yaml::as_bool)
+            .unwrap_or(false);
+        let exec = serde_json::json!({
+            "apiVersion": API_VERSION,
+            "command": "k8pk",
+            "args": [CREDENTIAL_COMMAND],
+            "env": [
+                {"name": CONTEXT_ENV, "value": context},
+                {"name": EXPIRES_ENV, "value": expires_at.to_string()},
+                {"name": USER_ENV, "value": serde_json::to_string(&original)?},
+            ],
+            "interactiveMode": "IfAvailable",
+            "provideClusterInfo": cluster_info,
+        });
+        fields.insert(Yaml::from("exec"), serde_yaml_ng::to_value(exec)?);
+    }
+    Ok(())
+}
+
+/// A kubeconfig user entry with credentials moved by [`gate_credentials`]
+/// put back
+pub fn ungated(user: &Yaml) -> Yaml {
+    let mut user = user.clone();
+    if let Some(Yaml::Mapping(fields)) = user.get_mut("user") {
+        let original = fields
+            .get("exec")
+            .filter(|exec| {
+                exec.get("args")
+                    .and_then(Yaml::as_sequence)
+                    .and_then(|a| a.first())
+                    .and_then(Yaml::as_str)
+                    == Some(CREDENTIAL_COMMAND)
+            })
+            .and_then(|exec| plugin_env(exec, USER_ENV))
+            .and_then(|json| serde_json::from_str::<Mapping>(&json).ok());
+        if let Some(original) = original {
+            fields.remove("exec");
+            fields.extend(original);
+        }
+    }
+    user
+}
+
+/// Every user of `cfg` with its original credentials (for copies that leave
+/// this machine, where k8pk may not be installed)
+pub fn ungate_credentials(cfg: &mut KubeConfig) {
+    for user in &mut cfg.users {
+        user.rest = ungated(&user.rest);
+    }
+}
+
+fn plugin_env(exec: &Yaml, name: &str) -> Option<String> {
+    exec.get("env")?
+        .as_sequence()?
+        .iter()
+        .find(|e| e.get("name").and_then(Yaml::as_str) == Some(name))?
+        .get("value")?
+        .as_str()
+        .map(String::from)
+}
+
+fn str_field<'a>(user: &'a Json, key: &str) -> Option<&'a str> {
+    user.get(key).and_then(Json::as_str)
+}
+
+/// PEM of a client certificate or key: the `<key>-data` field, or the file
+fn pem(user: &Json, key: &str) -> Result<Option<String>> {
+    if let Some(data) = str_field(user, &format!("{}-data", key)) {
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(data.trim())
+            .map_err(|e| K8pkError::Other(format!("invalid {}-data: {}", key, e)))?;
+        return Ok(Some(String::from_utf8_lossy(&bytes).into_owned()));
+    }
+    match str_field(user, key) {
+        Some(path) => Ok(Some(fs::read_to_string(path)?)),
+        None => Ok(None),
+    }
+}
+
+/// Run the user's own exec plugin; returns the `status` of its ExecCredential
+fn run_plugin(exec: &Json, exec_info: Option<&str>) -> Result<Json> {
+    let command = str_field(exec, "command")
+        .ok_or_else(|| K8pkError::Other("exec credential plugin without a command".into()))?;
+    // Pass on kubectl's request in the apiVersion this plugin speaks
+    let mut info = exec_info
+        .and_then(|i| serde_json::from_str::<Json>(i).ok())
+        .filter(Json::is_object)
+        .unwrap_or_else(|| serde_json::json!({"kind": "ExecCredential", "spec": {}}));
+    info["apiVersion"] = Json::from(str_field(exec, "apiVersion").unwrap_or(API_VERSION));
+    let interactive = info["spec"]["interactive"].as_bool().unwrap_or(false);
+
+    let mut cmd = Command::new(command);
+    if let Some(args) = exec.get("args").and_then(Json::as_array) {
+        cmd.args(args.iter().filter_map(Json::as_str));
+    }
+    for var in exec
+        .get("env")
+        .and_then(Json::as_array)
+        .into_iter()
+        .flatten()
+    {
+        if let (Some(name), Some(value)) = (str_field(var, "name"), str_field(var, "value")) {
+            cmd.env(name, value);
+        }
+    }
+    let output = cmd
+        .env("KUBERNETES_EXEC_INFO", info.to_string())
+        .stdin(if interactive {
+            Stdio::inherit()
+        } else {
+            Stdio::null()
+        })
+        .stderr(Stdio::inherit())
+        .output()
+        .map_err(|e| K8pkError::CommandFailed(format!("failed to run {}: {}", command, e)))?;
+    if !output.status.success() {
+        return Err(K8pkError::CommandFailed(format!(
+            "{} failed ({})",
+            command, output.status
+        )));
+    }
+    let credential: Json = serde_json::from_slice(&output.stdout).map_err(|e| {
+        K8pkError::CommandFailed(format!("{} printed no ExecCredential: {}", command, e))
+    })?;
+    credential
+        .get("status")
+        .filter(|s| s.is_object())
+        .cloned()
+        .ok_or_else(|| K8pkError::CommandFailed(format!("{} returned no credentials", command)))
+}
+
+/// ExecCredential status for the original credentials, expiring no later
+/// than the session
+fn credential_status(user: &Json, expires_at: u64, exec_info: Option<&str>) -> Result<Json> {
+    let mut status = match user.get("exec") {
+        Some(exec) => run_plugin(exec, exec_info)?,
+        None => serde_json::json!({}),
+    };
+    let token = match (str_field(user, "token"), str_field(user, "tokenFile")) {
+        (Some(token), _) => Some(token.to_string()),
+        (None, Some(file)) => Some(fs::read_to_string(file)?.trim().to_string()),
+        (None, None) => None,
+    };
+    if let Some(token) = token {
+        status["token"] = Json::from(token);
+    }
+    if let Some(cert) = pem(user, "client-certificate")? {
+        status["clientCertificateData"] = Json::from(cert);
+    }
+    if let Some(key) = pem(user, "client-key")? {
+        status["clientKeyData"] = Json::from(key);
+    }
+    // Both in the same UTC format, so the earlier one sorts first
+    let session_end = clock::rfc3339(expires_at);
+    let earlier = status["expirationTimestamp"]
+        .as_str()
+        .is_some_and(|t| t.len() == session_end.len() && t.ends_with('Z') && *t < *session_end);
+    if !earlier {
+        status["expirationTimestamp"] = Json::from(session_end);
+    }
+    Ok(status)
+}
+
+/// `k8pk session-credential`: fail once the session has expired, otherwise
+/// print an ExecCredential with the original credentials
+pub fn print_session_credential() -> Result<()> {
+    let context = std::env::var(CONTEXT_ENV).unwrap_or_default();
+    let expires_at = std::env::var(EXPIRES_ENV)
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .ok_or_else(|| {
+            K8pkError::InvalidArgument(format!(
+                "{} is only run by kubectl, from a kubeconfig k8pk generated",
+                CREDENTIAL_COMMAND
+            ))
+        })?;
+    let left = expires_at as i64 - clock::now_secs() as i64;
+    if left <= 0 {
+        return Err(K8pkError::SessionTtlExpired {
+            context,
+            ago: crate::clock::format_duration(left.unsigned_abs()),
+        });
+    }
+    let user: Json = serde_json::from_str(&std::env::var(USER_ENV).unwrap_or_default())
+        .map_err(|e| K8pkError::Other(format!("{}: {}", USER_ENV, e)))?;
+    let exec_info = std::env::var("KUBERNETES_EXEC_INFO").ok();
+    let status = credential_status(&user, expires_at, exec_info.as_deref())?;
+    println!(
+        "{}",
+        super::exec_credential_with(status, exec_info.as_deref())
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gate_and_restore_credentials() {
+        let mut cfg: KubeConfig = serde_yaml_ng::from_str(
+            r#"
+users:
+- name: token-user
+  user:
+    token: abc
+    as: admin
+- name: cert-user
+  user:
+    client-certificate-data: Y2VydA==
+    client-key-data: a2V5
+- name: oidc-user
+  user:
+    auth-provider: {name: oidc}
+"#,
+        )
+        .unwrap();
+        let original = cfg.clone();
+        gate_credentials(&mut cfg, "prod", 1_900_000_000).unwrap();
+
+        let token_user = &cfg.users[0].rest["user"];
+        assert!(token_user.get("token").is_none());
+        assert_eq!(token_user["as"], Yaml::from("admin"));
+        let exec = &token_user["exec"];
+        assert_eq!(exec["args"][0], Yaml::from(CREDENTIAL_COMMAND));
+        assert_eq!(plugin_env(exec, EXPIRES_ENV).as_deref(), Some("1900000000"));
+        assert!(cfg.users[1].rest["user"].get("client-key-data").is_none());
+        // Nothing the plugin could stand in for
+        assert_eq!(cfg.users[2].rest, original.users[2].rest);
+
+        ungate_credentials(&mut cfg);
+        for (restored, original) in cfg.users.iter().zip(&original.users) {
+            assert_eq!(restored.rest, original.rest);
+        }
+    }
+
+    #[test]
+    fn test_credential_status() {
+        let user = serde_json::json!({
+            "token": "abc",
+            "client-certificate-data": "Y2VydA==",
+            "client-key-data": "a2V5",
+        });
+        let status = credential_status(&user, 1_700_000_000, None).unwrap();
+        assert_eq!(status["token"], "abc");
+        assert_eq!(status["clientCertificateData"], "cert");
+        assert_eq!(status["clientKeyData"], "key");
+        assert_eq!(status["expirationTimestamp"], "2023-11-14T22:13:20Z");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_credential_status_from_plugin() {
+        // A plugin whose token outlives the session, and one expiring first
+        let plugin = |expiry: &str| {
+            serde_json::json!({
+                "exec": {
+                    "command": "sh",
+                    "args": ["-c", format!(
+                        r#"echo '{{"status": {{"token": "t", "expirationTimestamp": "{}"}}}}'"#,
+                        expiry
+                    )],
+                }
+            })
+        };
+        let status =
+            credential_status(&plugin("2030-01-01T00:00:00Z"), 1_700_000_000, None).unwrap();
+        assert_eq!(status["token"], "t");
+        assert_eq!(status["expirationTimestamp"], "2023-11-14T22:13:20Z");
+        let status =
+            credential_status(&plugin("2023-11-14T20:00:00Z"), 1_700_000_000, None).unwrap();
+        assert_eq!(status["expirationTimestamp"], "2023-11-14T20:00:00Z");
+
+        let failing = serde_json::json!({"exec": {"command": "false"}});
+        assert!(credential_status(&failing, 1_700_000_000, None).is_err());
+    }
+}
@@ -1,15 +1,17 @@
 //! Session registry for tracking active k8pk sessions across terminals.
 //!
-//! Stores session metadata in `~/.local/share/k8pk/sessions.json`.
-//! Sessions are identified by PID and pruned lazily (dead PIDs are
-//! removed on each `list_active()` call).
+//! Each session has its own state file, `~/.local/share/k8pk/sessions/<pid>.json`,
+//! keyed by the PID of the shell that owns it: written when the shell is
+//! spawned (or registers itself) and removed by the exit trap `k8pk init`
+//! installs. Files of dead PIDs are pruned lazily on each `list_active()`.
+//! The single `sessions.json` of older versions is migrated on first use.
 
 use crate::error::{K8pkError, Result};
 use crate::kubeconfig;
+use crate::state::CurrentState;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::time::{SystemTime, UNIX_EPOCH};
 
 /// A registered k8pk session.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -26,17 +28,39 @@ pub struct SessionEntry {
     pub started_at: u64,
     /// Terminal identifier (e.g. "tmux", "tty:/dev/ttys003").
     pub terminal: String,
+    /// Nesting depth of the shell (1 = spawned from a plain shell).
+    #[serde(default)]
+    pub depth: u32,
 }
 
-/// Path to the session registry file.
-fn registry_path() -> Result<PathBuf> {
-    let home = dirs_next::home_dir().ok_or(K8pkError::NoHomeDir)?;
-    let dir = home.join(".local/share/k8pk");
+impl SessionEntry {
+    /// The session as seen from inside its shell
+    pub fn state(&self) -> CurrentState {
+        CurrentState {
+            context: Some(self.context.clone()),
+            context_display: None,
+            namespace: Some(self.namespace.clone()),
+            depth: self.depth,
+            config_path: Some(PathBuf::from(&self.kubeconfig)),
+        }
+    }
+}
+
+/// Directory holding one state file per session, with the legacy
+/// `sessions.json` migrated into it.
+fn registry_dir() -> Result<PathBuf> {
+    let base = crate::storage::data_dir()?;
+    let dir = base.join("sessions");
     fs::create_dir_all(&dir)?;
-    Ok(dir.join("sessions.json"))
+    migrate_legacy(&base.join("sessions.json"), &dir);
+    Ok(dir)
+}
+
+fn state_path(dir: &Path, pid: u32) -> PathBuf {
+    dir.join(format!("{}.json", pid))
 }
 
-/// Read all entries from the registry file.
+/// Read the legacy single-file registry.
 /// Returns `Ok(entries)` on success, or `Err` if the file exists but is corrupt.
 /// Missing file returns `Ok(vec![])`.
 fn read_registry(path: &Path) -> Result<Vec<SessionEntry>> {
@@ -57,22 +81,55 @@ fn read_registry(path: &Path) -> Result<Vec<SessionEntry>> {
     Ok(entries)
 }
 
-/// Atomically write entries to the registry file with restricted permissions.
-fn write_registry(path: &Path, entries: &[SessionEntry]) -> Result<()> {
-    let json = serde_json::to_string_pretty(entries)?;
-    kubeconfig::write_restricted(path, &json)?;
+/// Move the entries of a legacy `sessions.json` into state files (a corrupt
+/// one is dropped, as it could not be read before either).
+fn migrate_legacy(legacy: &Path, dir: &Path) {
+    if !legacy.exists() {
+        return;
+    }
+    for entry in read_registry(legacy).unwrap_or_default() {
+        if !state_path(dir, entry.pid).exists() {
+            let _ = write_state(dir, &entry);
+        }
+    }
+    let _ = fs::remove_file(legacy);
+}
+
+/// Write a session's state file with restricted permissions.
+fn write_state(dir: &Path, entry: &SessionEntry) -> Result<()> {
+    let json = serde_json::to_string_pretty(entry)?;
+    kubeconfig::write_restricted(&state_path(dir, entry.pid), &json)?;
     Ok(())
 }
 
+fn read_state(path: &Path) -> Option<SessionEntry> {
+    serde_json::from_str(&fs::read_to_string(path).ok()?).ok()
+}
+
+/// All state files in `dir`, oldest first. Unreadable files are skipped.
+fn read_states(dir: &Path) -> Vec<SessionEntry> {
+    let Ok(files) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut entries: Vec<SessionEntry> = files
+        .flatten()
+        .map(|f| f.path())
+        .filter(|p| p.extension().is_some_and(|e| e == "json"))
+        .filter_map(|p| read_state(&p))
+        .collect();
+    entries.sort_by_key(|e| (e.started_at, e.pid));
+    entries
+}
+
 /// Check whether a process with the given PID is still alive.
 #[cfg(unix)]
-fn is_pid_alive(pid: u32) -> bool {
+pub(crate) fn is_pid_alive(pid: u32) -> bool {
     // kill(pid, 0) checks if the process exists without sending a signal.
     unsafe { libc::kill(pid as libc::pid_t, 0) == 0 }
 }
 
 #[cfg(not(unix))]
-fn is_pid_alive(_pid: u32) -> bool {
+pub(crate) fn is_pid_alive(_pid: u32) -> bool {
     // Conservative: assume alive on non-Unix (sessions will not auto-prune).
     true
 }
@@ -108,69 +165,69 @@ fn detect_terminal() -> String {
 /// Register a session in the registry.
 ///
 /// If `pid_override` is provided it is used; otherwise the parent PID is used
-/// (which is the shell that invoked `k8pk sessions register`).
+/// (which is the shell that invoked `k8pk sessions register`). Re-registering
+/// the same context and namespace keeps the original start time.
 pub fn register(
     context: &str,
     namespace: Option<&str>,
     kubeconfig_path: &str,
     pid_override: Option<u32>,
+    depth: u32,
 ) -> Result<()> {
-    let path = registry_path()?;
-    let mut entries = read_registry(&path)?;
-
+    let dir = registry_dir()?;
     let pid = pid_override.unwrap_or_else(parent_pid);
-
-    // Remove stale entry for this PID (re-registration / context switch).
-    entries.retain(|e| e.pid != pid);
-
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_secs();
-
-    entries.push(SessionEntry {
-        pid,
-        context: context.to_string(),
-        namespace: namespace.unwrap_or("default").to_string(),
-        kubeconfig: kubeconfig_path.to_string(),
-        started_at: now,
-        terminal: detect_terminal(),
-    });
-
-    write_registry(&path, &entries)?;
-    Ok(())
+    let namespace = namespace.unwrap_or("default").to_string();
+
+    let started_at = read_state(&state_path(&dir, pid))
+        .filter(|e| e.context == context && e.namespace == namespace)
+        .map(|e| e.started_at)
+        .unwrap_or_else(crate::clock::now_secs);
+
+    write_state(
+        &dir,
+        &SessionEntry {
+            pid,
+            context: context.to_string(),
+            namespace,
+            kubeconfig: kubeconfig_path.to_string(),
+            started_at,
+            terminal: detect_terminal(),
+            depth,
+        },
+    )
 }
 
-/// Remove a session from the registry by PID.
-pub fn deregister(pid: u32) -> Result<()> {
-    let path = registry_path()?;
-    let mut entries = read_registry(&path)?;
-    let before = entries.len();
-    entries.retain(|e| e.pid != pid);
-    if entries.len() < before {
-        write_registry(&path, &entries)?;
+/// Remove a session from the registry by PID, returning the removed entries.
+pub fn deregister(pid: u32) -> Result<Vec<SessionEntry>> {
+    let path = state_path(&registry_dir()?, pid);
+    let removed: Vec<SessionEntry> = read_state(&path).into_iter().collect();
+    if path.exists() {
+        fs::remove_file(&path)?;
     }
-    Ok(())
+    Ok(removed)
 }
 
 /// Deregister the current session (uses parent PID).
-pub fn deregister_current() -> Result<()> {
+pub fn deregister_current() -> Result<Vec<SessionEntry>> {
     deregister(parent_pid())
 }
 
+/// The session of the shell that ran k8pk (parent PID), if it is registered.
+/// Unlike the environment, this is not inherited by unrelated programs
+/// started from a k8pk shell.
+pub fn current_session() -> Option<SessionEntry> {
+    read_state(&state_path(&registry_dir().ok()?, parent_pid())).filter(|e| is_pid_alive(e.pid))
+}
+
 /// List all active sessions, pruning dead PIDs.
 pub fn list_active() -> Result<Vec<SessionEntry>> {
-    let path = registry_path()?;
-    let entries = read_registry(&path).unwrap_or_default();
-
-    let alive: Vec<SessionEntry> = entries
+    let dir = registry_dir()?;
+    let (alive, dead): (Vec<SessionEntry>, Vec<SessionEntry>) = read_states(&dir)
         .into_iter()
-        .filter(|e| is_pid_alive(e.pid))
-        .collect();
-
-    // Write pruned list back.
-    write_registry(&path, &alive)?;
-
+        .partition(|e| is_pid_alive(e.pid));
+    for e in dead {
+        let _ = fs::remove_file(state_path(&dir, e.pid));
+    }
     Ok(alive)
 }
 
@@ -282,10 +339,7 @@ pub fn deduplicated_sessions(
 
 /// Format a duration in seconds into a human-readable age string.
 pub fn format_age(started_at: u64) -> String {
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_secs();
+    let now = crate::clock::now_secs();
     let elapsed = now.saturating_sub(started_at);
     if elapsed < 60 {
         format!("{}s", elapsed)
@@ -304,40 +358,28 @@ mod tests {
 
     #[test]
     fn test_format_age_seconds() {
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        let now = crate::clock::now_secs();
         assert_eq!(format_age(now), "0s");
         assert_eq!(format_age(now - 30), "30s");
     }
 
     #[test]
     fn test_format_age_minutes() {
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        let now = crate::clock::now_secs();
         assert_eq!(format_age(now - 120), "2m");
         assert_eq!(format_age(now - 3599), "59m");
     }
 
     #[test]
     fn test_format_age_hours() {
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        let now = crate::clock::now_secs();
         assert_eq!(format_age(now - 3600), "1h");
         assert_eq!(format_age(now - 7200), "2h");
     }
 
     #[test]
     fn test_format_age_days() {
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        let now = crate::clock::now_secs();
         assert_eq!(format_age(now - 86400), "1d");
     }
 
@@ -350,6 +392,7 @@ mod tests {
             kubeconfig: "/tmp/test.yaml".to_string(),
             started_at: 1700000000,
             terminal: "tty:/dev/ttys003".to_string(),
+            depth: 1,
         };
         let json = serde_json::to_string(&entry).unwrap();
         let restored: SessionEntry = serde_json::from_str(&json).unwrap();
@@ -379,6 +422,7 @@ mod tests {
                 kubeconfig: "/tmp/kc1.yaml".to_string(),
                 started_at: 1000,
                 terminal: "tty:/dev/ttys001".to_string(),
+                depth: 1,
             },
             SessionEntry {
                 pid: 200,
@@ -387,6 +431,7 @@ mod tests {
                 kubeconfig: "/tmp/kc2.yaml".to_string(),
                 started_at: 2000,
                 terminal: "tty:/dev/ttys002".to_string(),
+                depth: 1,
             },
             SessionEntry {
                 pid: 300,
@@ -395,6 +440,7 @@ mod tests {
                 kubeconfig: "/tmp/kc3.yaml".to_string(),
                 started_at: 1500,
                 terminal: "tmux".to_string(),
+                depth: 1,
             },
         ];
 
@@ -420,6 +466,7 @@ mod tests {
                 kubeconfig: "/tmp/kc1.yaml".to_string(),
                 started_at: 100,
                 terminal: "unknown".to_string(),
+                depth: 1,
             },
             SessionEntry {
                 pid: 200,
@@ -428,6 +475,7 @@ mod tests {
                 kubeconfig: "/tmp/kc2.yaml".to_string(),
                 started_at: 9000,
                 terminal: "unknown".to_string(),
+                depth: 1,
             },
         ];
 
@@ -489,6 +537,7 @@ mod tests {
             kubeconfig: "/tmp/kc.yaml".to_string(),
             started_at: 1000,
             terminal: "unknown".to_string(),
+            depth: 0,
         }];
         let json = serde_json::to_string(&entries).unwrap();
         fs::write(&path, json).unwrap();
@@ -498,32 +547,35 @@ mod tests {
     }
 
     #[test]
-    fn test_write_registry_roundtrip() {
+    fn test_state_files_roundtrip_and_legacy_migration() {
         let dir = tempfile::tempdir().unwrap();
-        let path = dir.path().join("sessions.json");
-        let entries = vec![
-            SessionEntry {
-                pid: 1,
-                context: "ctx1".to_string(),
-                namespace: "ns1".to_string(),
-                kubeconfig: "/a".to_string(),
-                started_at: 100,
-                terminal: "tty:x".to_string(),
-            },
-            SessionEntry {
-                pid: 2,
-                context: "ctx2".to_string(),
-                namespace: "ns2".to_string(),
-                kubeconfig: "/b".to_string(),
-                started_at: 200,
-                terminal: "tmux".to_string(),
-            },
-        ];
-        write_registry(&path, &entries).unwrap();
-        let loaded = read_registry(&path).unwrap();
-        assert_eq!(loaded.len(), 2);
-        assert_eq!(loaded[0].context, "ctx1");
-        assert_eq!(loaded[1].context, "ctx2");
+        let sessions = dir.path().join("sessions");
+        fs::create_dir_all(&sessions).unwrap();
+        let entry = |pid: u32, context: &str, started_at: u64| SessionEntry {
+            pid,
+            context: context.to_string(),
+            namespace: "default".to_string(),
+            kubeconfig: format!("/kc/{}.yaml", context),
+            started_at,
+            terminal: "tmux".to_string(),
+            depth: 1,
+        };
+        write_state(&sessions, &entry(2, "ctx2", 200)).unwrap();
+
+        let legacy = dir.path().join("sessions.json");
+        let old = vec![entry(1, "ctx1", 100), entry(2, "stale", 50)];
+        fs::write(&legacy, serde_json::to_string(&old).unwrap()).unwrap();
+        migrate_legacy(&legacy, &sessions);
+        assert!(!legacy.exists());
+
+        let loaded = read_states(&sessions);
+        let contexts: Vec<&str> = loaded.iter().map(|e| e.context.as_str()).collect();
+        assert_eq!(contexts, ["ctx1", "ctx2"]);
+        assert_eq!(loaded[1].state().context.as_deref(), Some("ctx2"));
+        assert_eq!(loaded[1].state().depth, 1);
+
+        fs::write(sessions.join("3.json"), "{{not json").unwrap();
+        assert_eq!(read_states(&sessions).len(), 2);
     }
 
     #[test]
@@ -533,10 +585,7 @@ mod tests {
             namespace: "default".to_string(),
             kubeconfig: "/tmp/kc.yaml".to_string(),
             count: 3,
-            newest_at: SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_secs(),
+            newest_at: crate::clock::now_secs(),
             terminal: "tmux".to_string(),
             is_current: true,
         };
@@ -0,0 +1,251 @@
+//! `k8pk shell-pod`: a throwaway debug pod in the session's namespace.
+//!
+//! The pod only runs `sleep`, and shells are `kubectl exec`-ed into it, so a
+//! second terminal can attach to the same pod. The run that created the pod
+//! deletes it when its shell exits (unless `--keep`). If cleanup never runs,
+//! the sleep ends after a day and the pod stops by itself.
+
+use crate::error::{K8pkError, Result};
+use crate::i18n::tr_with;
+use crate::kubeconfig;
+use crate::prompt::Ask;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Label put on every pod k8pk creates
+const MANAGED_BY: &str = "app.kubernetes.io/managed-by=k8pk";
+const POD_LIFETIME_SECS: u32 = 86400;
+const READY_TIMEOUT: &str = "120s";
+
+pub struct ShellPodOptions {
+    pub image: String,
+    pub name: Option<String>,
+    /// Command run inside the pod
+    pub shell: String,
+    /// Leave the pod running after the shell exits
+    pub keep: bool,
+    /// Skip the confirmation on protected contexts
+    pub yes: bool,
+}
+
+/// `k8pk-shell-<user>`, cut down to a valid pod name
+pub fn default_pod_name() -> String {
+    let user = std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_default();
+    pod_name_for(&user)
+}
+
+fn pod_name_for(user: &str) -> String {
+    let user: String = user
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    let mut name = format!("k8pk-shell-{}", user.trim_matches('-'));
+    name.truncate(63);
+    name.trim_end_matches('-').to_string()
+}
+
+/// The current session, or `context` when given (also used by `k8pk events`)
+pub(super) struct Target {
+    pub context: String,
+    pub namespace: Option<String>,
+    pub kubeconfig: PathBuf,
+}
+
+pub(super) fn resolve_target(
+    context: Option<&str>,
+    namespace: Option<&str>,
+    paths: &[PathBuf],
+) -> Result<Target> {
+    if let Some(name) = context {
+        let merged = kubeconfig::load_merged(paths)?;
+        let context = super::resolve_context(&merged, name)?;
+        let kubeconfig = super::ensure_isolated_kubeconfig(&context, namespace, paths)?;
+        return Ok(Target {
+            context,
+            namespace: namespace.map(String::from),
+            kubeconfig,
+        });
+    }
+    let state = crate::state::CurrentState::from_env();
+    state.ensure_not_expired()?;
+    match (state.context.filter(|c| !c.is_empty()), state.config_path) {
+        (Some(context), Some(kubeconfig)) => Ok(Target {
+            context,
+            namespace: namespace.map(String::from).or(state.namespace),
+            kubeconfig,
+        }),
+        _ => Err(K8pkError::NotInContext),
+    }
+}
+
+fn kubectl(cli: &str, kubeconfig: &Path, namespace: Option<&str>) -> Command {
+    let mut cmd = Command::new(cli);
+    cmd.env("KUBECONFIG", kubeconfig);
+    if let Some(path) = crate::config::hinted_path() {
+        cmd.env("PATH", path);
+    }
+    if let Some(ns) = namespace {
+        cmd.args(["-n", ns]);
+    }
+    cmd
+}
+
+/// Phase of the pod, or `None` when it doesn't exist
+fn pod_phase(cli: &str, target: &Target, name: &str) -> Option<String> {
+    let output = kubectl(cli, &target.kubeconfig, target.namespace.as_deref())
+        .args(["get", "pod", name, "-o", "jsonpath={.status.phase}"])
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn run_checked(mut cmd: Command, what: &str) -> Result<()> {
+    let output = cmd.stdout(Stdio::null()).output()?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(K8pkError::CommandFailed(format!(
+            "{}: {}",
+            what,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )))
+    }
+}
+
+fn create_pod(cli: &str, target: &Target, name: &str, image: &str) -> Result<()> {
+    let mut cmd = kubectl(cli, &target.kubeconfig, target.namespace.as_deref());
+    cmd.args(["run", name, "--image", image, "--restart=Never", "--labels"])
+        .arg(MANAGED_BY)
+        .args(["--command", "--", "sleep"])
+        .arg(POD_LIFETIME_SECS.to_string());
+    run_checked(cmd, &format!("failed to create pod {}", name))?;
+
+    let mut wait = kubectl(cli, &target.kubeconfig, target.namespace.as_deref());
+    wait.args(["wait", "--for=condition=Ready"])
+        .arg(format!("pod/{}", name))
+        .arg(format!("--timeout={}", READY_TIMEOUT));
+    if let Err(e) = run_checked(wait, &format!("pod {} did not become ready", name)) {
+        delete_pod(cli, target, name);
+        return Err(e);
+    }
+    Ok(())
+}
+
+fn delete_pod(cli: &str, target: &Target, name: &str) {
+    let _ = kubectl(cli, &target.kubeconfig, target.namespace.as_deref())
+        .args(["delete", "pod", name, "--wait=false", "--ignore-not-found"])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+}
+
+/// Create (or reuse) the debug pod, exec the shell in it, and delete the pod
+/// afterwards if this run created it. Returns the shell's exit code.
+pub fn shell_pod(
+    opts: &ShellPodOptions,
+    context: Option<&str>,
+    namespace: Option<&str>,
+    paths: &[PathBuf],
+) -> Result<i32> {
+    let target = resolve_target(context, namespace, paths)?;
+    let name = opts.name.clone().unwrap_or_else(default_pod_name);
+    let cli = kubeconfig::find_fast_cli()?;
+    let quiet = crate::render::quiet();
+    let ns_display = target.namespace.as_deref().unwrap_or("(default)");
+
+    let created = match pod_phase(&cli, &target, &name).as_deref() {
+        Some("Running") => {
+            if !quiet {
+                eprintln!(
+                    "Attaching to pod {} in {}/{}",
+                    name, target.context, ns_display
+                );
+            }
+            false
+        }
+        phase => {
+            let config = crate::config::load().unwrap_or_default();
+            if crate::config::is_context_protected_with(&config, &target.context) && !opts.yes {
+                if !crate::prompt::can_prompt() {
+                    return Err(K8pkError::InvalidArgument(format!(
+                        "'{}' is a protected context; pass --yes to start a debug pod in it",
+                        target.context
+                    )));
+                }
+                let go = inquire::Confirm::new(&tr_with(
+                    "confirm-protected-shell-pod",
+                    &[
+                        ("context", &target.context),
+                        ("pod", &name),
+                        ("image", &opts.image),
+                        ("namespace", ns_display),
+                    ],
+                ))
+                .with_default(false)
+                .ask()?;
+                if !go {
+                    return Err(K8pkError::Cancelled);
+                }
+            }
+            if phase.is_some() {
+                // Finished or stuck pod of the same name from an earlier run
+                let _ = kubectl(&cli, &target.kubeconfig, target.namespace.as_deref())
+                    .args(["delete", "pod", &name, "--wait=true"])
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
+                    .status();
+            }
+            if !quiet {
+                eprintln!(
+                    "Starting pod {} ({}) in {}/{}...",
+                    name, opts.image, target.context, ns_display
+                );
+            }
+            create_pod(&cli, &target, &name, &opts.image)?;
+            true
+        }
+    };
+
+    let tty = std::io::stdin().is_terminal() && std::io::stdout().is_terminal();
+    let mut exec = kubectl(&cli, &target.kubeconfig, target.namespace.as_deref());
+    exec.args(["exec", if tty { "-it" } else { "-i" }, &name, "--"])
+        .args(shell_words::split(&opts.shell).map_err(|e| {
+            K8pkError::InvalidArgument(format!("invalid --shell '{}': {}", opts.shell, e))
+        })?);
+    let status = exec.status();
+
+    if created && !opts.keep {
+        delete_pod(&cli, &target, &name);
+        if !quiet {
+            eprintln!("Deleted pod {}", name);
+        }
+    } else if created && !quiet {
+        eprintln!(
+            "Pod {} keeps running; delete it with: kubectl delete pod {}",
+            name, name
+        );
+    }
+    Ok(status?.code().unwrap_or(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pod_name_for() {
+        assert_eq!(pod_name_for("alice"), "k8pk-shell-alice");
+        assert_eq!(pod_name_for("Jane.Doe@CORP"), "k8pk-shell-jane-doe-corp");
+        assert_eq!(pod_name_for(""), "k8pk-shell");
+        let long = pod_name_for(&"x".repeat(100));
+        assert_eq!(long.len(), 63);
+    }
+}
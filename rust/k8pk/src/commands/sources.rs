@@ -0,0 +1,145 @@
+//! `k8pk sources`: list the kubeconfig files k8pk merges, and switch single
+//! files off and on (`configs.disabled`) without deleting them
+
+use super::organize::display_path;
+use crate::config::{self, K8pkConfig};
+use crate::error::{K8pkError, Result};
+use crate::kubeconfig;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SourceEntry {
+    pub path: String,
+    pub enabled: bool,
+    /// Contexts defined in the file (`None` when it is missing or unreadable)
+    pub contexts: Option<usize>,
+}
+
+/// Every kubeconfig file k8pk would merge with nothing disabled, plus
+/// disabled entries that no longer match a file on disk
+pub fn list_sources(
+    override_path: Option<&Path>,
+    kubeconfig_dirs: &[PathBuf],
+    k8pk_config: &K8pkConfig,
+) -> Result<Vec<SourceEntry>> {
+    let disabled = &k8pk_config.configs.disabled;
+    let mut all = k8pk_config.clone();
+    all.configs.disabled.clear();
+    let paths = kubeconfig::resolve_paths(override_path, kubeconfig_dirs, &all)?;
+
+    let mut entries: Vec<SourceEntry> = paths
+        .iter()
+        .map(|p| SourceEntry {
+            path: display_path(p),
+            enabled: !kubeconfig::is_source_disabled(p, disabled),
+            contexts: kubeconfig::load_merged(std::slice::from_ref(p))
+                .ok()
+                .map(|cfg| cfg.context_names().len()),
+        })
+        .collect();
+    for d in disabled {
+        let path = config::expand_home(d);
+        if !paths
+            .iter()
+            .any(|p| kubeconfig::is_source_disabled(p, std::slice::from_ref(d)))
+        {
+            entries.push(SourceEntry {
+                path: display_path(&path),
+                enabled: false,
+                contexts: None,
+            });
+        }
+    }
+    Ok(entries)
+}
+
+pub fn print_sources(entries: &[SourceEntry]) {
+    if entries.is_empty() {
+        println!("No kubeconfig files found");
+        return;
+    }
+    let width = entries.iter().map(|e| e.path.len()).max().unwrap_or(0);
+    for e in entries {
+        let state = if e.enabled { "enabled " } else { "disabled" };
+        let contexts = match e.contexts {
+            Some(1) => "1 context".to_string(),
+            Some(n) => format!("{} contexts", n),
+            None if e.enabled => "unreadable".to_string(),
+            None => "missing".to_string(),
+        };
+        println!("{}  {:<width$}  {}", state, e.path, contexts);
+    }
+}
+
+/// Absolute form of a path given on the command line (`~/` expanded, relative
+/// to the working directory)
+fn absolute(path: &str) -> Result<PathBuf> {
+    let expanded = config::expand_home(path);
+    if expanded.is_absolute() {
+        Ok(expanded)
+    } else {
+        Ok(std::env::current_dir()?.join(expanded))
+    }
+}
+
+/// Record `path` in `configs.disabled`. Returns the path as written and
+/// whether it was newly disabled.
+pub fn disable_source(path: &str) -> Result<(String, bool)> {
+    let path = absolute(path)?;
+    if !path.is_file() {
+        return Err(K8pkError::InvalidArgument(format!(
+            "no kubeconfig file at {}",
+            path.display()
+        )));
+    }
+    let path = std::fs::canonicalize(&path).unwrap_or(path);
+    let entry = display_path(&path);
+    let changed = config::set_source_disabled(&path, &entry, true)?;
+    Ok((entry, changed))
+}
+
+/// Drop `path` from `configs.disabled`. Errors when it isn't disabled.
+pub fn enable_source(path: &str) -> Result<String> {
+    let path = absolute(path)?;
+    let entry = display_path(&path);
+    if !config::set_source_disabled(&path, &entry, false)? {
+        return Err(K8pkError::InvalidArgument(format!(
+            "{} is not disabled (see `k8pk sources list`)",
+            entry
+        )));
+    }
+    Ok(entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_list_sources_marks_disabled() {
+        let dir = tempfile::tempdir().unwrap();
+        let kc = "apiVersion: v1\nkind: Config\ncontexts:\n- name: a\n  context: {cluster: c}\n";
+        fs::write(dir.path().join("acme.yaml"), kc).unwrap();
+        fs::write(dir.path().join("own.yaml"), kc).unwrap();
+        let gone = dir.path().join("gone.yaml");
+
+        let mut config = K8pkConfig::default();
+        config.configs.include = vec![format!("{}/*.yaml", dir.path().display())];
+        config.configs.disabled = vec![
+            dir.path().join("acme.yaml").display().to_string(),
+            gone.display().to_string(),
+        ];
+
+        let paths = kubeconfig::resolve_paths(None, &[], &config).unwrap();
+        assert!(paths.iter().all(|p| !p.ends_with("acme.yaml")));
+
+        let mut entries = list_sources(None, &[], &config).unwrap();
+        entries.retain(|e| e.path.starts_with(&dir.path().display().to_string()));
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+        let summary: Vec<(bool, Option<usize>)> =
+            entries.iter().map(|e| (e.enabled, e.contexts)).collect();
+        // acme, gone, own
+        assert_eq!(summary, [(false, Some(1)), (false, None), (true, Some(1))]);
+    }
+}
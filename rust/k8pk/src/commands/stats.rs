@@ -0,0 +1,413 @@
+//! Context usage statistics (`k8pk stats`). Every switch is appended to a
+//! small event log; time spent is the gap until the next switch, capped so an
+//! idle terminal overnight does not count as a day of work.
+
+use crate::clock::{format_duration, now_secs};
+use crate::error::Result;
+use crate::kubeconfig::{self, KubeConfig};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Longest stretch credited to one switch (4h)
+const MAX_SPAN_SECS: u64 = 4 * 60 * 60;
+/// Events older than this are dropped when the log is compacted (90 days)
+const RETENTION_SECS: u64 = 90 * 24 * 60 * 60;
+/// Compact the log once it grows past this size
+const COMPACT_BYTES: u64 = 512 * 1024;
+const WEEK_SECS: u64 = 7 * 24 * 60 * 60;
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SwitchEvent {
+    /// Unix seconds
+    pub ts: u64,
+    pub context: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+    /// A wrapped shell in `context` exited here: ends the previous span
+    /// without counting as a switch or being credited time itself
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub exit: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct ContextUsage {
+    pub context: String,
+    pub switches: u64,
+    /// Approximate seconds spent in the context
+    pub seconds: u64,
+    /// Switches per namespace
+    pub namespaces: BTreeMap<String, u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct ClusterTime {
+    pub cluster: String,
+    pub seconds: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct StaleContext {
+    pub context: String,
+    /// Last use (unix seconds); None if never used via k8pk
+    pub last_used: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct UsageStats {
+    /// Since this time (unix seconds), the oldest event in the log
+    pub since: Option<u64>,
+    pub most_used: Vec<ContextUsage>,
+    /// Time per cluster over the last 7 days
+    pub this_week: Vec<ClusterTime>,
+    pub stale_days: u64,
+    pub stale: Vec<StaleContext>,
+}
+
+fn usage_log_path() -> Result<PathBuf> {
+    Ok(crate::storage::data_dir()?.join("usage.jsonl"))
+}
+
+/// Append a switch to the usage log (best effort; compacts old events)
+pub fn record_switch(context: &str, namespace: Option<&str>) {
+    append_event(SwitchEvent {
+        ts: now_secs(),
+        context: context.to_string(),
+        namespace: namespace.map(str::to_string),
+        exit: false,
+    });
+}
+
+/// Record that a (wrapped) shell in `context` exited, so the time after it
+/// is not credited to the context
+pub fn record_session_end(context: &str, namespace: Option<&str>) {
+    append_event(SwitchEvent {
+        ts: now_secs(),
+        context: context.to_string(),
+        namespace: namespace.map(str::to_string),
+        exit: true,
+    });
+}
+
+fn append_event(event: SwitchEvent) {
+    let Ok(path) = usage_log_path() else {
+        return;
+    };
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    let Ok(line) = serde_json::to_string(&event) else {
+        return;
+    };
+    if let Ok(mut file) = fs::OpenOptions::new().create(true).append(true).open(&path) {
+        let _ = writeln!(file, "{}", line);
+    }
+    if fs::metadata(&path).is_ok_and(|m| m.len() > COMPACT_BYTES) {
+        let cutoff = event.ts.saturating_sub(RETENTION_SECS);
+        let kept: Vec<String> = load_events()
+            .into_iter()
+            .filter(|e| e.ts >= cutoff)
+            .filter_map(|e| serde_json::to_string(&e).ok())
+            .collect();
+        let _ = kubeconfig::write_restricted(&path, &(kept.join("\n") + "\n"));
+    }
+}
+
+/// Switch events in log order; unreadable lines are skipped
+pub fn load_events() -> Vec<SwitchEvent> {
+    let Ok(content) = usage_log_path().and_then(|p| Ok(fs::read_to_string(p)?)) else {
+        return Vec::new();
+    };
+    content
+        .lines()
+        .filter_map(|l| serde_json::from_str(l).ok())
+        .collect()
+}
+
+/// Time credited to each event: until the next switch (or `now`), capped
+fn spans(events: &[SwitchEvent], now: u64) -> Vec<(u64, u64)> {
+    events
+        .iter()
+        .enumerate()
+        .map(|(i, e)| {
+            if e.exit {
+                return (e.ts, e.ts);
+            }
+            let end = events.get(i + 1).map_or(now, |next| next.ts);
+            let end = end.min(e.ts + MAX_SPAN_SECS).max(e.ts);
+            (e.ts, end)
+        })
+        .collect()
+}
+
+/// Build the report from `events` (sorted by time). `clusters` maps context to
+/// cluster name for the weekly view; `contexts` are the current kubeconfig
+/// contexts checked for staleness, with `last_used` from the switch history.
+pub fn compute_stats(
+    events: &[SwitchEvent],
+    now: u64,
+    clusters: &HashMap<String, String>,
+    contexts: &[String],
+    last_used: &HashMap<String, u64>,
+    stale_days: u64,
+    top: usize,
+) -> UsageStats {
+    let mut usage: HashMap<&str, ContextUsage> = HashMap::new();
+    let mut week: HashMap<String, u64> = HashMap::new();
+    let mut latest: HashMap<&str, u64> = HashMap::new();
+    let week_start = now.saturating_sub(WEEK_SECS);
+
+    for (e, (start, end)) in events.iter().zip(spans(events, now)) {
+        if e.exit {
+            continue;
+        }
+        let entry = usage.entry(&e.context).or_insert_with(|| ContextUsage {
+            context: e.context.clone(),
+            switches: 0,
+            seconds: 0,
+            namespaces: BTreeMap::new(),
+        });
+        entry.switches += 1;
+        entry.seconds += end - start;
+        if let Some(ns) = &e.namespace {
+            *entry.namespaces.entry(ns.clone()).or_default() += 1;
+        }
+
+        let in_week = end.saturating_sub(start.max(week_start));
+        if in_week > 0 {
+            let cluster = clusters
+                .get(&e.context)
+                .cloned()
+                .unwrap_or_else(|| e.context.clone());
+            *week.entry(cluster).or_default() += in_week;
+        }
+        let seen = latest.entry(&e.context).or_default();
+        *seen = (*seen).max(e.ts);
+    }
+
+    let mut most_used: Vec<ContextUsage> = usage.into_values().collect();
+    most_used.sort_by(|a, b| {
+        b.switches
+            .cmp(&a.switches)
+            .then(b.seconds.cmp(&a.seconds))
+            .then_with(|| a.context.cmp(&b.context))
+    });
+    most_used.truncate(top);
+
+    let mut this_week: Vec<ClusterTime> = week
+        .into_iter()
+        .map(|(cluster, seconds)| ClusterTime { cluster, seconds })
+        .collect();
+    this_week.sort_by(|a, b| {
+        b.seconds
+            .cmp(&a.seconds)
+            .then_with(|| a.cluster.cmp(&b.cluster))
+    });
+
+    let stale_cutoff = now.saturating_sub(stale_days * 24 * 60 * 60);
+    let mut stale: Vec<StaleContext> = contexts
+        .iter()
+        .map(|c| StaleContext {
+            context: c.clone(),
+            last_used: latest
+                .get(c.as_str())
+                .copied()
+                .max(last_used.get(c).copied()),
+        })
+        .filter(|s| s.last_used.is_none_or(|t| t < stale_cutoff))
+        .collect();
+    stale.sort_by(|a, b| {
+        a.last_used
+            .cmp(&b.last_used)
+            .then_with(|| a.context.cmp(&b.context))
+    });
+
+    UsageStats {
+        since: events.first().map(|e| e.ts),
+        most_used,
+        this_week,
+        stale_days,
+        stale,
+    }
+}
+
+/// Context -> cluster name from the kubeconfig context entries
+fn cluster_names(cfg: &KubeConfig) -> HashMap<String, String> {
+    cfg.contexts
+        .iter()
+        .filter_map(|c| {
+            let (cluster, _) = kubeconfig::extract_context_refs(&c.rest).ok()?;
+            Some((c.name.clone(), cluster))
+        })
+        .collect()
+}
+
+/// Usage report for the contexts in `paths`
+pub fn usage_stats(paths: &[PathBuf], stale_days: u64, top: usize) -> Result<UsageStats> {
+    let merged = kubeconfig::load_merged(paths)?;
+    let mut events = load_events();
+    events.sort_by_key(|e| e.ts);
+    let last_used = super::context::get_last_used().unwrap_or_default();
+    Ok(compute_stats(
+        &events,
+        now_secs(),
+        &cluster_names(&merged),
+        &merged.context_names(),
+        &last_used,
+        stale_days,
+        top,
+    ))
+}
+
+pub fn print_usage_stats(stats: &UsageStats) {
+    if stats.most_used.is_empty() {
+        println!("No switches recorded yet. Usage is tracked from now on.");
+    } else {
+        println!("Most used contexts");
+        let width = stats
+            .most_used
+            .iter()
+            .map(|u| u.context.len())
+            .max()
+            .unwrap_or(0)
+            .max("CONTEXT".len());
+        println!("  {:<width$}  SWITCHES  TIME", "CONTEXT", width = width);
+        for u in &stats.most_used {
+            println!(
+                "  {:<width$}  {:<8}  {}",
+                u.context,
+                u.switches,
+                format_duration(u.seconds),
+                width = width
+            );
+        }
+    }
+
+    if !stats.this_week.is_empty() {
+        println!();
+        println!("Time per cluster (last 7 days)");
+        let width = stats
+            .this_week
+            .iter()
+            .map(|c| c.cluster.len())
+            .max()
+            .unwrap_or(0);
+        for c in &stats.this_week {
+            println!(
+                "  {:<width$}  {}",
+                c.cluster,
+                format_duration(c.seconds),
+                width = width
+            );
+        }
+    }
+
+    if !stats.stale.is_empty() {
+        println!();
+        println!("Stale contexts (not used in {} days)", stats.stale_days);
+        for s in &stats.stale {
+            match s.last_used {
+                Some(t) => println!(
+                    "  {}  (last used {} ago)",
+                    s.context,
+                    super::sessions::format_age(t)
+                ),
+                None => println!("  {}  (never used via k8pk)", s.context),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: u64 = 24 * 60 * 60;
+
+    fn ev(ts: u64, context: &str, namespace: Option<&str>) -> SwitchEvent {
+        SwitchEvent {
+            ts,
+            context: context.into(),
+            namespace: namespace.map(Into::into),
+            exit: false,
+        }
+    }
+
+    #[test]
+    fn spans_are_capped() {
+        let events = vec![ev(0, "a", None), ev(600, "b", None), ev(100_000, "a", None)];
+        assert_eq!(
+            spans(&events, 100_060),
+            vec![(0, 600), (600, 600 + MAX_SPAN_SECS), (100_000, 100_060)]
+        );
+    }
+
+    #[test]
+    fn session_end_stops_the_clock() {
+        let exit = SwitchEvent {
+            exit: true,
+            ..ev(600, "a", None)
+        };
+        let events = vec![ev(0, "a", None), exit, ev(5000, "b", None)];
+        assert_eq!(
+            spans(&events, 5060),
+            vec![(0, 600), (600, 600), (5000, 5060)]
+        );
+        let stats = compute_stats(&events, 5060, &HashMap::new(), &[], &HashMap::new(), 30, 10);
+        let a = stats.most_used.iter().find(|u| u.context == "a").unwrap();
+        assert_eq!((a.switches, a.seconds), (1, 600));
+    }
+
+    #[test]
+    fn stats_rank_week_and_stale() {
+        let now = 30 * DAY;
+        let events = vec![
+            ev(now - 20 * DAY, "old", None),
+            ev(now - 2 * DAY, "prod", Some("web")),
+            ev(now - 2 * DAY + 1800, "dev", None),
+            ev(now - DAY, "prod", Some("api")),
+            ev(now - DAY + 3600, "prod-admin", None),
+            ev(now - DAY + 7200, "dev", None),
+        ];
+        let clusters = HashMap::from([
+            ("prod".to_string(), "prod-cluster".to_string()),
+            ("prod-admin".to_string(), "prod-cluster".to_string()),
+        ]);
+        let contexts: Vec<String> = ["prod", "dev", "old", "unused"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let stats = compute_stats(&events, now, &clusters, &contexts, &HashMap::new(), 14, 2);
+
+        assert_eq!(stats.since, Some(now - 20 * DAY));
+        let names: Vec<&str> = stats.most_used.iter().map(|u| u.context.as_str()).collect();
+        assert_eq!(names, ["dev", "prod"]);
+        let prod = &stats.most_used[1];
+        assert_eq!(prod.seconds, 1800 + 3600);
+        assert_eq!(prod.namespaces.get("web"), Some(&1));
+
+        assert_eq!(stats.this_week[0].cluster, "dev");
+        assert_eq!(
+            stats.this_week[1],
+            ClusterTime {
+                cluster: "prod-cluster".into(),
+                seconds: 1800 + 3600 + 3600
+            }
+        );
+        assert!(!stats.this_week.iter().any(|c| c.cluster == "old"));
+
+        let stale: Vec<&str> = stats.stale.iter().map(|s| s.context.as_str()).collect();
+        assert_eq!(stale, ["unused", "old"]);
+    }
+
+    #[test]
+    fn history_last_used_counts_against_staleness() {
+        let now = 30 * DAY;
+        let contexts = vec!["dev".to_string()];
+        let last_used = HashMap::from([("dev".to_string(), now - DAY)]);
+        let stats = compute_stats(&[], now, &HashMap::new(), &contexts, &last_used, 7, 10);
+        assert!(stats.stale.is_empty());
+        assert!(stats.since.is_none());
+    }
+}
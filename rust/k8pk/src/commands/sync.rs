@@ -0,0 +1,303 @@
+//! `k8pk sync`: check out a team's git repository of kubeconfigs into
+//! `~/.kube/managed` (shallow, optionally sparse), lint it, and report the
+//! contexts it added or removed. A revision that fails lint is rolled back,
+//! so a bad push never breaks everyone's pickers.
+
+use crate::config::SyncSection;
+use crate::error::{K8pkError, Result};
+use crate::kubeconfig;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+#[derive(Debug, serde::Serialize)]
+pub struct SyncReport {
+    pub repo: String,
+    pub dir: PathBuf,
+    /// Short commit id now checked out
+    pub revision: String,
+    /// True for the first checkout
+    pub cloned: bool,
+    pub files: usize,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub warnings: usize,
+}
+
+/// Run git in `dir` (or the working directory), returning trimmed stdout
+fn git(dir: Option<&Path>, args: &[&str]) -> Result<String> {
+    let mut cmd = Command::new("git");
+    if let Some(dir) = dir {
+        cmd.arg("-C").arg(dir);
+    }
+    let output = cmd
+        .args(args)
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| K8pkError::CommandFailed(format!("git: {}", e)))?;
+    if !output.status.success() {
+        return Err(K8pkError::CommandFailed(format!(
+            "git {}: {}",
+            args.first().copied().unwrap_or_default(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn context_names(files: &[PathBuf]) -> BTreeSet<String> {
+    files
+        .iter()
+        .filter_map(|f| kubeconfig::load_merged(std::slice::from_ref(f)).ok())
+        .flat_map(|cfg| cfg.context_names())
+        .collect()
+}
+
+/// Pull `settings.repo` into the managed directory and report what changed
+pub fn sync(settings: &SyncSection) -> Result<SyncReport> {
+    crate::config::require_online("k8pk sync")?;
+    let dir = kubeconfig::managed_dir().ok_or(K8pkError::NoHomeDir)?;
+    sync_into(settings, &dir)
+}
+
+/// Refuse a branch or path git would read as an option
+fn check_settings(settings: &SyncSection) -> Result<()> {
+    if let Some(branch) = settings.branch.as_deref() {
+        if branch.is_empty() || branch.starts_with('-') {
+            return Err(K8pkError::InvalidArgument(format!(
+                "sync.branch: '{}' is not a branch",
+                branch
+            )));
+        }
+    }
+    if let Some(path) = settings.paths.iter().find(|p| p.starts_with('-')) {
+        return Err(K8pkError::InvalidArgument(format!(
+            "sync.paths: '{}' is not a path",
+            path
+        )));
+    }
+    Ok(())
+}
+
+fn sync_into(settings: &SyncSection, dir: &Path) -> Result<SyncReport> {
+    check_settings(settings)?;
+    let before = context_names(&kubeconfig::scan_tree(dir)?);
+
+    let previous = if dir.join(".git").exists() {
+        let origin = git(Some(dir), &["remote", "get-url", "origin"])?;
+        if origin != settings.repo {
+            return Err(K8pkError::InvalidArgument(format!(
+                "{} is a checkout of {}; remove it to sync from {}",
+                dir.display(),
+                origin,
+                settings.repo
+            )));
+        }
+        let head = git(Some(dir), &["rev-parse", "HEAD"])?;
+        let refspec = settings.branch.as_deref().unwrap_or("HEAD");
+        git(
+            Some(dir),
+            &["fetch", "--quiet", "--depth", "1", "origin", refspec],
+        )?;
+        git(Some(dir), &["reset", "--quiet", "--hard", "FETCH_HEAD"])?;
+        // Local edits would never reach the team; the repo is the source
+        git(Some(dir), &["clean", "--quiet", "-fd"])?;
+        Some(head)
+    } else {
+        if dir.exists() && fs::read_dir(dir)?.next().is_some() {
+            return Err(K8pkError::InvalidArgument(format!(
+                "{} exists and is not a git checkout; move it away to sync into it",
+                dir.display()
+            )));
+        }
+        if let Some(parent) = dir.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let target = dir.to_string_lossy();
+        let mut args = vec!["clone", "--quiet", "--depth", "1"];
+        if let Some(branch) = settings.branch.as_deref() {
+            args.extend(["--branch", branch]);
+        }
+        if !settings.paths.is_empty() {
+            args.extend(["--filter=blob:none", "--sparse"]);
+        }
+        args.extend(["--", settings.repo.as_str(), target.as_ref()]);
+        git(None, &args)?;
+        None
+    };
+
+    if !settings.paths.is_empty() {
+        let mut args = vec!["sparse-checkout", "set", "--"];
+        args.extend(settings.paths.iter().map(String::as_str));
+        git(Some(dir), &args)?;
+    } else if git(Some(dir), &["config", "--get", "core.sparseCheckout"]).is_ok_and(|v| v == "true")
+    {
+        git(Some(dir), &["sparse-checkout", "disable"])?;
+    }
+
+    let files = kubeconfig::scan_tree(dir)?;
+    let lint = super::lint(None, &files, false)?;
+    if lint.failed {
+        match &previous {
+            Some(head) => {
+                git(Some(dir), &["reset", "--quiet", "--hard", head])?;
+                eprintln!(
+                    "Synced revision has lint errors; kept {}",
+                    &head[..head.len().min(12)]
+                );
+            }
+            None => {
+                fs::remove_dir_all(dir)?;
+                eprintln!("{} has lint errors; nothing checked out", settings.repo);
+            }
+        }
+        return Err(K8pkError::LintFailed);
+    }
+
+    let after = context_names(&files);
+    Ok(SyncReport {
+        repo: settings.repo.clone(),
+        dir: dir.to_path_buf(),
+        revision: git(Some(dir), &["rev-parse", "--short", "HEAD"])?,
+        cloned: previous.is_none(),
+        files: files.len(),
+        added: after.difference(&before).cloned().collect(),
+        removed: before.difference(&after).cloned().collect(),
+        warnings: lint.warnings,
+    })
+}
+
+pub fn print_sync_report(report: &SyncReport) {
+    let verb = if report.cloned { "Cloned" } else { "Synced" };
+    println!(
+        "{} {} at {} into {} ({} file{})",
+        verb,
+        report.repo,
+        report.revision,
+        super::display_path(&report.dir),
+        report.files,
+        if report.files == 1 { "" } else { "s" }
+    );
+    for name in &report.added {
+        println!("  + {}", name);
+    }
+    for name in &report.removed {
+        println!("  - {}", name);
+    }
+    if report.added.is_empty() && report.removed.is_empty() {
+        println!("  no context changes");
+    }
+    if report.warnings > 0 {
+        eprintln!(
+            "{} lint warning(s); run 'k8pk lint' for details",
+            report.warnings
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kubeconfig(contexts: &[&str]) -> String {
+        let mut yaml = String::from(
+            "apiVersion: v1\nkind: Config\nclusters:\n- name: c\n  cluster: {server: \"https://c\"}\nusers:\n- name: u\n  user: {token: t}\ncontexts:\n",
+        );
+        for name in contexts {
+            yaml.push_str(&format!(
+                "- name: {}\n  context: {{cluster: c, user: u}}\n",
+                name
+            ));
+        }
+        yaml
+    }
+
+    fn commit(repo: &Path, file: &str, content: &str) {
+        let path = repo.join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+        git(Some(repo), &["add", "-A"]).unwrap();
+        git(
+            Some(repo),
+            &[
+                "-c",
+                "user.name=t",
+                "-c",
+                "user.email=t@t",
+                "commit",
+                "-qm",
+                file,
+            ],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_sync_reports_and_rolls_back() {
+        if which::which("git").is_err() {
+            return;
+        }
+        let tmp = tempfile::tempdir().unwrap();
+        let repo = tmp.path().join("repo");
+        fs::create_dir(&repo).unwrap();
+        git(Some(&repo), &["init", "-q"]).unwrap();
+        commit(&repo, "teams/a.yaml", &kubeconfig(&["a-dev", "a-prod"]));
+
+        let settings = SyncSection {
+            repo: format!("file://{}", repo.display()),
+            ..Default::default()
+        };
+        let dir = tmp.path().join("managed");
+        let first = sync_into(&settings, &dir).unwrap();
+        assert!(first.cloned);
+        assert_eq!(first.added, ["a-dev", "a-prod"]);
+
+        commit(&repo, "teams/a.yaml", &kubeconfig(&["a-dev", "a-stage"]));
+        let second = sync_into(&settings, &dir).unwrap();
+        assert!(!second.cloned);
+        assert_eq!(second.added, ["a-stage"]);
+        assert_eq!(second.removed, ["a-prod"]);
+
+        // A broken push keeps the last good revision
+        commit(
+            &repo,
+            "teams/a.yaml",
+            "clusters:\n- name: c\n  cluster: {}\n",
+        );
+        assert!(matches!(
+            sync_into(&settings, &dir),
+            Err(K8pkError::LintFailed)
+        ));
+        assert_eq!(
+            context_names(&kubeconfig::scan_tree(&dir).unwrap()),
+            BTreeSet::from(["a-dev".to_string(), "a-stage".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_option_like_settings_are_rejected() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().join("managed");
+        let branch = SyncSection {
+            repo: "https://git.example.com/kube.git".into(),
+            branch: Some("--upload-pack=touch pwned".into()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            sync_into(&branch, &dir),
+            Err(K8pkError::InvalidArgument(_))
+        ));
+        let paths = SyncSection {
+            repo: "https://git.example.com/kube.git".into(),
+            paths: vec!["teams".into(), "--no-cone".into()],
+            ..Default::default()
+        };
+        assert!(matches!(
+            sync_into(&paths, &dir),
+            Err(K8pkError::InvalidArgument(_))
+        ));
+        assert!(!dir.exists());
+    }
+}
@@ -32,13 +32,17 @@ pub fn tmux_mode() -> String {
         .unwrap_or_else(|| "windows".to_string())
 }
 
-/// Format the window/session name from context name using the config template
+/// Format the window/session name from context name using the config
+/// template, which takes the `display.template` placeholders
 fn format_name(context: &str) -> String {
-    let template = config::load()
-        .ok()
-        .and_then(|c| c.tmux.and_then(|t| t.name_template))
-        .unwrap_or_else(|| "{context}".to_string());
-    template.replace("{context}", context)
+    let config = config::load().unwrap_or_default();
+    let template = config
+        .tmux
+        .as_ref()
+        .and_then(|t| t.name_template.as_deref())
+        .unwrap_or("{context}");
+    let cluster_type = kubeconfig::detect_cluster_type(context, None);
+    config::expand_display_template(&config, template, context, cluster_type, None)
 }
 
 /// Sanitize a name for tmux (no dots or colons which tmux treats specially)
@@ -213,8 +217,6 @@ pub fn switch_or_create_window(
     kubeconfig: &Path,
 ) -> Result<()> {
     let name = sanitize_tmux_name(&format_name(context));
-    let display_context = friendly_display(context, kubeconfig);
-    let ns = namespace.unwrap_or("default");
 
     // Check if a window with this name already exists
     let existing = Command::new("tmux")
@@ -243,14 +245,71 @@ pub fn switch_or_create_window(
         }
     }
 
-    // Create new window with k8pk environment
+    new_window(&name, context, namespace, kubeconfig, false)?;
+    eprintln!("Created tmux window '{}' for context '{}'", name, context);
+    Ok(())
+}
+
+/// Open one tmux window per `(namespace, kubeconfig)` of `context`, named
+/// `ctx/ns`, in the background; windows that already exist are left alone.
+/// Returns the window names.
+pub fn open_namespace_windows(
+    context: &str,
+    windows: &[(String, std::path::PathBuf)],
+) -> Result<Vec<String>> {
+    let existing = Command::new("tmux")
+        .args(["list-windows", "-F", "#{window_name}"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).to_string())
+        .unwrap_or_default();
+
+    let mut names = Vec::new();
+    for (ns, kubeconfig) in windows {
+        let name = namespace_window_name(context, ns);
+        if existing.lines().any(|w| w == name) {
+            eprintln!("tmux window '{}' already exists", name);
+        } else {
+            new_window(&name, context, Some(ns), kubeconfig, true)?;
+            eprintln!("Created tmux window '{}'", name);
+        }
+        names.push(name);
+    }
+    Ok(names)
+}
+
+/// `ctx/ns`, with the context part from the name template
+fn namespace_window_name(context: &str, namespace: &str) -> String {
+    sanitize_tmux_name(&format!("{}/{}", format_name(context), namespace))
+}
+
+/// `tmux new-window` with the k8pk environment for `context`; `detached`
+/// keeps the current window selected
+fn new_window(
+    name: &str,
+    context: &str,
+    namespace: Option<&str>,
+    kubeconfig: &Path,
+    detached: bool,
+) -> Result<()> {
+    let display_context = config::display_context(context, namespace, kubeconfig);
+    let ns = namespace.unwrap_or("default");
     let cache_dir = kubeconfig
         .parent()
         .unwrap_or(Path::new("/tmp"))
         .join("cache")
         .join(kubeconfig::sanitize_filename(context));
 
-    let mut args: Vec<String> = vec!["new-window".to_string(), "-n".to_string(), name.clone()];
+    let mut args: Vec<String> = vec!["new-window".to_string(), "-n".to_string(), name.to_string()];
+    if detached {
+        args.push("-d".to_string());
+    }
+
+    let settings = crate::config::context_settings(context);
+    if let Some(dir) = crate::shell::start_dir(&settings, context, namespace) {
+        args.extend(["-c".to_string(), dir.display().to_string()]);
+    }
 
     // tmux new-window -e sets environment variables
     args.extend([
@@ -269,19 +328,32 @@ pub fn switch_or_create_window(
         "-e".to_string(),
         format!("OC_NAMESPACE={}", ns),
     ]);
+    if let Some(path) = crate::config::hinted_path() {
+        args.extend(["-e".to_string(), format!("PATH={}", path.to_string_lossy())]);
+    }
+    if let Some(proxy) = settings.proxy_url()? {
+        args.extend(["-e".to_string(), format!("HTTPS_PROXY={}", proxy)]);
+    }
 
-    let status = Command::new("tmux")
+    // Print the new pane's shell PID so the session registry can track it
+    args.extend([
+        "-P".to_string(),
+        "-F".to_string(),
+        "#{pane_pid}".to_string(),
+    ]);
+
+    let output = Command::new("tmux")
         .args(&args)
-        .status()
+        .output()
         .map_err(|e| K8pkError::CommandFailed(format!("tmux new-window: {}", e)))?;
 
-    if !status.success() {
+    if !output.status.success() {
         return Err(K8pkError::CommandFailed(
             "failed to create tmux window".into(),
         ));
     }
-
-    eprintln!("Created tmux window '{}' for context '{}'", name, context);
+    register_pane(context, namespace, kubeconfig, &output.stdout);
+    send_init_cmd(name, &settings, context, namespace);
     Ok(())
 }
 
@@ -292,7 +364,7 @@ pub fn switch_or_create_session(
     kubeconfig: &Path,
 ) -> Result<()> {
     let name = sanitize_tmux_name(&format_name(context));
-    let display_context = friendly_display(context, kubeconfig);
+    let display_context = config::display_context(context, namespace, kubeconfig);
     let ns = namespace.unwrap_or("default");
 
     // Check if session exists
@@ -328,6 +400,11 @@ pub fn switch_or_create_session(
         name.clone(),
     ];
 
+    let settings = crate::config::context_settings(context);
+    if let Some(dir) = crate::shell::start_dir(&settings, context, namespace) {
+        args.extend(["-c".to_string(), dir.display().to_string()]);
+    }
+
     args.extend([
         "-e".to_string(),
         format!("KUBECONFIG={}", kubeconfig.display()),
@@ -344,17 +421,32 @@ pub fn switch_or_create_session(
         "-e".to_string(),
         format!("OC_NAMESPACE={}", ns),
     ]);
+    if let Some(path) = crate::config::hinted_path() {
+        args.extend(["-e".to_string(), format!("PATH={}", path.to_string_lossy())]);
+    }
+    if let Some(proxy) = settings.proxy_url()? {
+        args.extend(["-e".to_string(), format!("HTTPS_PROXY={}", proxy)]);
+    }
+
+    // Print the new pane's shell PID so the session registry can track it
+    args.extend([
+        "-P".to_string(),
+        "-F".to_string(),
+        "#{pane_pid}".to_string(),
+    ]);
 
-    let status = Command::new("tmux")
+    let output = Command::new("tmux")
         .args(&args)
-        .status()
+        .output()
         .map_err(|e| K8pkError::CommandFailed(format!("tmux new-session: {}", e)))?;
 
-    if !status.success() {
+    if !output.status.success() {
         return Err(K8pkError::CommandFailed(
             "failed to create tmux session".into(),
         ));
     }
+    register_pane(context, namespace, kubeconfig, &output.stdout);
+    send_init_cmd(&name, &settings, context, namespace);
 
     // Now switch to it
     Command::new("tmux")
@@ -366,19 +458,212 @@ pub fn switch_or_create_session(
     Ok(())
 }
 
-/// Resolve the friendly display name for a context
-fn friendly_display(context: &str, kubeconfig: &Path) -> String {
-    if let Ok(content) = std::fs::read_to_string(kubeconfig) {
-        if let Ok(cfg) = serde_yaml_ng::from_str::<kubeconfig::KubeConfig>(&content) {
-            let server_url = cfg
-                .clusters
-                .first()
-                .and_then(|c| kubeconfig::extract_server_url_from_cluster(&c.rest));
-            let cluster_type = kubeconfig::detect_cluster_type(context, server_url.as_deref());
-            return kubeconfig::friendly_context_name(context, cluster_type);
+/// Type the context's `init_cmd` into the new window/session's shell
+fn send_init_cmd(
+    target: &str,
+    settings: &crate::config::ContextSettings,
+    context: &str,
+    namespace: Option<&str>,
+) {
+    if let Some(init) = settings.init_command(context, namespace) {
+        let _ = Command::new("tmux")
+            .args(["send-keys", "-t", target, &init, "Enter"])
+            .status();
+    }
+}
+
+/// Record a new tmux pane (its shell PID from `-P -F #{pane_pid}`) in the
+/// session registry, as spawned shells are
+fn register_pane(context: &str, namespace: Option<&str>, kubeconfig: &Path, stdout: &[u8]) {
+    if let Ok(pid) = String::from_utf8_lossy(stdout).trim().parse::<u32>() {
+        let _ = super::sessions::register(
+            context,
+            namespace,
+            &kubeconfig.display().to_string(),
+            Some(pid),
+            1,
+        );
+    }
+}
+
+/// One side of a `k8pk swap`: a k8pk-managed window and its active pane
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SwapWindow {
+    #[serde(skip)]
+    id: String,
+    #[serde(skip)]
+    pane: String,
+    #[serde(skip)]
+    command: String,
+    pub window_index: String,
+    pub window_name: String,
+    pub context: String,
+    pub namespace: Option<String>,
+}
+
+/// Shells `k8pk swap` can type a context switch into
+const SWAP_SHELLS: &[&str] = &["bash", "zsh", "fish", "sh", "dash", "ksh"];
+
+/// Exchange the contexts, namespaces, and names of two k8pk-managed windows
+/// in the current tmux session. A window is given by index or name; with
+/// one given it pairs with the current window, with none the session must
+/// have exactly two k8pk windows. Each window's shell has the other's
+/// context typed into it, so both must be at a prompt.
+pub fn swap_windows(a: Option<&str>, b: Option<&str>) -> Result<(SwapWindow, SwapWindow)> {
+    if !is_tmux() {
+        return Err(K8pkError::InvalidArgument(
+            "k8pk swap works inside tmux only".into(),
+        ));
+    }
+    if tmux_mode() == "sessions" {
+        return Err(K8pkError::InvalidArgument(
+            "k8pk swap exchanges tmux windows; tmux.mode is \"sessions\"".into(),
+        ));
+    }
+
+    let output = Command::new("tmux")
+        .args([
+            "list-windows",
+            "-F",
+            "#{window_id}\t#{window_index}\t#{window_name}\t#{pane_id}\t#{pane_pid}\t#{pane_current_command}\t#{window_active}",
+        ])
+        .output()
+        .map_err(|e| K8pkError::CommandFailed(format!("failed to run tmux: {}", e)))?;
+    if !output.status.success() {
+        return Err(K8pkError::CommandFailed(format!(
+            "tmux list-windows: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    let mut windows = Vec::new();
+    let mut current = None;
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let parts: Vec<&str> = line.split('\t').collect();
+        if parts.len() < 7 {
+            continue;
+        }
+        let env = read_pane_k8pk_env(parts[4]);
+        if parts[6] == "1" {
+            current = Some(parts[1].to_string());
+        }
+        let Some((context, namespace)) = env else {
+            continue;
+        };
+        windows.push(SwapWindow {
+            id: parts[0].to_string(),
+            window_index: parts[1].to_string(),
+            window_name: parts[2].to_string(),
+            pane: parts[3].to_string(),
+            command: parts[5].to_string(),
+            context,
+            namespace: (namespace != "(default)").then_some(namespace),
+        });
+    }
+
+    let (i, j) = resolve_swap_pair(&windows, a, b, current.as_deref())?;
+    let (first, second) = (windows[i].clone(), windows[j].clone());
+    // The pane k8pk runs in reads the typed line once k8pk exits
+    let own_pane = std::env::var("TMUX_PANE").ok();
+    for w in [&first, &second] {
+        if own_pane.as_deref() != Some(w.pane.as_str())
+            && !SWAP_SHELLS.contains(&w.command.trim_start_matches('-'))
+        {
+            return Err(K8pkError::InvalidArgument(format!(
+                "window '{}' is running {}; return it to a shell prompt first",
+                w.window_name, w.command
+            )));
         }
     }
-    context.to_string()
+
+    let exe = std::env::current_exe()
+        .map(|p| p.display().to_string())
+        .unwrap_or_else(|_| "k8pk".to_string());
+    for (target, source) in [(&first, &second), (&second, &first)] {
+        let shell = if own_pane.as_deref() == Some(target.pane.as_str()) {
+            let shell = std::env::var("SHELL").unwrap_or_default();
+            shell.rsplit('/').next().unwrap_or_default().to_string()
+        } else {
+            target.command.clone()
+        };
+        let cmd = switch_command(&exe, &shell, &source.context, source.namespace.as_deref());
+        Command::new("tmux")
+            .args(["send-keys", "-t", &target.pane, "-l", &cmd])
+            .status()
+            .map_err(|e| K8pkError::CommandFailed(format!("tmux send-keys: {}", e)))?;
+        let _ = Command::new("tmux")
+            .args(["send-keys", "-t", &target.pane, "Enter"])
+            .status();
+        let _ = Command::new("tmux")
+            .args(["rename-window", "-t", &target.id, &source.window_name])
+            .status();
+    }
+    Ok((first, second))
+}
+
+/// Indexes into `windows` of the pair to swap: the windows named by `a` and
+/// `b` (index or name), or the only two there are
+fn resolve_swap_pair(
+    windows: &[SwapWindow],
+    a: Option<&str>,
+    b: Option<&str>,
+    current: Option<&str>,
+) -> Result<(usize, usize)> {
+    let find = |target: &str| {
+        windows
+            .iter()
+            .position(|w| w.window_index == target)
+            .or_else(|| windows.iter().position(|w| w.window_name == target))
+            .ok_or_else(|| {
+                let what = if current == Some(target) {
+                    "the current window".to_string()
+                } else {
+                    format!("window '{}'", target)
+                };
+                K8pkError::InvalidArgument(format!(
+                    "{} is not a k8pk-managed window in this session",
+                    what
+                ))
+            })
+    };
+    let (i, j) = match (a, b) {
+        (Some(a), Some(b)) => (find(a)?, find(b)?),
+        (Some(one), None) | (None, Some(one)) => {
+            let current = current.ok_or_else(|| {
+                K8pkError::InvalidArgument("no current tmux window to swap with".into())
+            })?;
+            (find(current)?, find(one)?)
+        }
+        (None, None) if windows.len() == 2 => (0, 1),
+        (None, None) => {
+            return Err(K8pkError::InvalidArgument(format!(
+                "found {} k8pk-managed windows; name the two to swap",
+                windows.len()
+            )))
+        }
+    };
+    if i == j {
+        return Err(K8pkError::InvalidArgument(
+            "cannot swap a window with itself".into(),
+        ));
+    }
+    Ok((i, j))
+}
+
+/// The line typed into a pane's shell to switch it to `context`: an eval of
+/// `<exe> ctx -o env`, with a leading space to keep it out of shell history
+fn switch_command(exe: &str, shell: &str, context: &str, namespace: Option<&str>) -> String {
+    let mut args = vec![exe, "ctx", context];
+    if let Some(ns) = namespace {
+        args.extend(["-n", ns]);
+    }
+    args.extend(["-o", "env"]);
+    let line = shell_words::join(args);
+    if shell.trim_start_matches('-') == "fish" {
+        format!(" {} | source", line)
+    } else {
+        format!(" eval \"$({})\"", line)
+    }
 }
 
 #[cfg(test)]
@@ -409,6 +694,46 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_resolve_swap_pair() {
+        let window = |index: &str, name: &str| SwapWindow {
+            id: format!("@{}", index),
+            pane: format!("%{}", index),
+            command: "bash".into(),
+            window_index: index.into(),
+            window_name: name.into(),
+            context: name.into(),
+            namespace: None,
+        };
+        let two = [window("1", "staging"), window("2", "prod")];
+        assert_eq!(resolve_swap_pair(&two, None, None, None).unwrap(), (0, 1));
+        assert_eq!(
+            resolve_swap_pair(&two, Some("prod"), Some("1"), None).unwrap(),
+            (1, 0)
+        );
+        assert_eq!(
+            resolve_swap_pair(&two, Some("staging"), None, Some("2")).unwrap(),
+            (1, 0)
+        );
+        assert!(resolve_swap_pair(&two, Some("1"), Some("staging"), None).is_err());
+        assert!(resolve_swap_pair(&two, Some("3"), Some("1"), None).is_err());
+
+        let three = [window("1", "a"), window("2", "b"), window("3", "c")];
+        assert!(resolve_swap_pair(&three, None, None, None).is_err());
+    }
+
+    #[test]
+    fn test_switch_command() {
+        assert_eq!(
+            switch_command("k8pk", "-zsh", "prod", Some("web")),
+            " eval \"$(k8pk ctx prod -n web -o env)\""
+        );
+        assert_eq!(
+            switch_command("/opt/bin/k8pk", "fish", "my ctx", None),
+            " /opt/bin/k8pk ctx 'my ctx' -o env | source"
+        );
+    }
+
     #[test]
     fn test_sanitize_tmux_name_clean() {
         assert_eq!(sanitize_tmux_name("dev-cluster"), "dev-cluster");
@@ -0,0 +1,268 @@
+//! Backups of kubeconfigs k8pk rewrites (rm, rename-context, prune,
+//! organize --remove-from-source) and `k8pk undo`. Copies live in
+//! `~/.local/share/k8pk/backups`, indexed by `log.jsonl` (oldest first).
+
+use crate::error::{K8pkError, Result};
+use crate::kubeconfig;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const LOG_FILE: &str = "log.jsonl";
+
+/// Backups kept; older ones are deleted as new ones are taken
+const KEEP: usize = 50;
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct BackupEntry {
+    /// Kubeconfig that was about to be rewritten
+    pub file: PathBuf,
+    /// Copy of its previous content
+    pub backup: PathBuf,
+    /// Unix seconds
+    pub created_at: u64,
+    /// Taken by `k8pk undo` of the content it replaced; undo skips these
+    #[serde(default)]
+    pub before_undo: bool,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct UndoResult {
+    pub file: PathBuf,
+    pub backup: PathBuf,
+    pub created_at: u64,
+    /// Copy of the content the restore replaced, when it differed
+    pub saved: Option<PathBuf>,
+    pub dry_run: bool,
+}
+
+/// Where k8pk keeps its backups and their log
+pub fn backups_dir() -> Result<PathBuf> {
+    Ok(crate::storage::data_dir()?.join("backups"))
+}
+
+fn lock_log(dir: &Path) -> Result<fs::File> {
+    crate::storage::create_private_dir(dir)?;
+    kubeconfig::acquire_lock(&dir.join(".log.lock"))
+}
+
+/// Entries whose backup file still exists, oldest first
+fn read_log(dir: &Path) -> Vec<BackupEntry> {
+    let Ok(content) = fs::read_to_string(dir.join(LOG_FILE)) else {
+        return Vec::new();
+    };
+    content
+        .lines()
+        .filter_map(|l| serde_json::from_str::<BackupEntry>(l).ok())
+        .filter(|e| e.backup.exists())
+        .collect()
+}
+
+fn write_log(dir: &Path, entries: &[BackupEntry]) -> Result<()> {
+    let mut out = String::new();
+    for e in entries {
+        out.push_str(&serde_json::to_string(e)?);
+        out.push('\n');
+    }
+    kubeconfig::write_restricted(&dir.join(LOG_FILE), &out)
+}
+
+/// Copy `file_path` into `backups` (normally [`backups_dir`]) before a
+/// destructive edit, so `k8pk undo` can put it back. Returns the backup
+/// path, or None if the file doesn't exist.
+pub fn backup_kubeconfig(file_path: &Path, backups: &Path) -> Result<Option<PathBuf>> {
+    backup_into(backups, file_path, false)
+}
+
+fn backup_into(dir: &Path, file_path: &Path, before_undo: bool) -> Result<Option<PathBuf>> {
+    if !file_path.exists() {
+        return Ok(None);
+    }
+    let file = fs::canonicalize(file_path)?;
+    let content = fs::read_to_string(&file)?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+
+    let _lock = lock_log(dir)?;
+    let file_name = file.file_name().unwrap_or_default().to_string_lossy();
+    let mut backup = dir.join(format!("{}.bak.{}", file_name, now.as_millis()));
+    let mut n = 1;
+    while backup.exists() {
+        backup = dir.join(format!("{}.bak.{}-{}", file_name, now.as_millis(), n));
+        n += 1;
+    }
+    kubeconfig::write_restricted(&backup, &content)?;
+
+    let mut entries = read_log(dir);
+    entries.push(BackupEntry {
+        file,
+        backup: backup.clone(),
+        created_at: now.as_secs(),
+        before_undo,
+    });
+    if entries.len() > KEEP {
+        for old in entries.drain(..entries.len() - KEEP) {
+            let _ = fs::remove_file(&old.backup);
+        }
+        write_log(dir, &entries)?;
+    } else {
+        let mut log = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(dir.join(LOG_FILE))?;
+        log.write_all(
+            format!("{}\n", serde_json::to_string(&entries[entries.len() - 1])?).as_bytes(),
+        )?;
+    }
+    Ok(Some(backup))
+}
+
+/// Backups k8pk holds, newest first
+pub fn list_backups() -> Result<Vec<BackupEntry>> {
+    let mut entries = read_log(&backups_dir()?);
+    entries.reverse();
+    Ok(entries)
+}
+
+/// Restore the most recently backed-up kubeconfig to its previous content.
+/// The backup is consumed, so repeated calls step further back. What the
+/// file holds before the restore (including edits made since k8pk rewrote
+/// it) is backed up first, so the undo itself can be reverted by hand.
+pub fn undo_last(dry_run: bool) -> Result<UndoResult> {
+    undo_in(&backups_dir()?, dry_run)
+}
+
+fn undo_in(dir: &Path, dry_run: bool) -> Result<UndoResult> {
+    // Writers hold the kubeconfig lock while taking the log lock, so never
+    // hold the log lock while waiting for a kubeconfig
+    let entry = {
+        let _lock = lock_log(dir)?;
+        read_log(dir)
+            .into_iter()
+            .rev()
+            .find(|e| !e.before_undo)
+            .ok_or(K8pkError::NothingToUndo)?
+    };
+    let mut saved = None;
+    if !dry_run {
+        let _file_lock = kubeconfig::lock_kubeconfig(&entry.file)?;
+        let content = fs::read_to_string(&entry.backup)?;
+        if fs::read_to_string(&entry.file).is_ok_and(|current| current != content) {
+            saved = backup_into(dir, &entry.file, true)?;
+        }
+        kubeconfig::write_restricted(&entry.file, &content)?;
+
+        let _lock = lock_log(dir)?;
+        let entries: Vec<BackupEntry> = read_log(dir).into_iter().filter(|e| *e != entry).collect();
+        // Taking `saved` may already have rotated it out
+        if entry.backup.exists() {
+            fs::remove_file(&entry.backup)?;
+        }
+        write_log(dir, &entries)?;
+    }
+    Ok(UndoResult {
+        file: entry.file,
+        backup: entry.backup,
+        created_at: entry.created_at,
+        saved,
+        dry_run,
+    })
+}
+
+pub fn print_undo_result(result: &UndoResult) {
+    let verb = if result.dry_run {
+        "Would restore"
+    } else {
+        "Restored"
+    };
+    println!(
+        "{} {} to its state from {} ago",
+        verb,
+        super::display_path(&result.file),
+        super::sessions::format_age(result.created_at)
+    );
+    if let Some(saved) = &result.saved {
+        println!(
+            "The content it replaced is in {}",
+            super::display_path(saved)
+        );
+    }
+}
+
+pub fn print_backups(entries: &[BackupEntry]) {
+    if entries.is_empty() {
+        println!("No backups.");
+        return;
+    }
+    for e in entries {
+        println!(
+            "  {:>8} ago  {}{}",
+            super::sessions::format_age(e.created_at),
+            super::display_path(&e.file),
+            if e.before_undo {
+                format!("  (replaced by undo: {})", super::display_path(&e.backup))
+            } else {
+                String::new()
+            }
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn undo_walks_back_through_backups() {
+        let dir = tempfile::tempdir().unwrap();
+        let backups = dir.path().join("backups");
+        let file = dir.path().join("config");
+        fs::write(&file, "v1").unwrap();
+
+        backup_into(&backups, &file, false).unwrap();
+        fs::write(&file, "v2").unwrap();
+        backup_into(&backups, &file, false).unwrap();
+        // An edit made after k8pk's last rewrite
+        fs::write(&file, "v3 edited").unwrap();
+        assert_eq!(read_log(&backups).len(), 2);
+
+        let preview = undo_in(&backups, true).unwrap();
+        assert_eq!(preview.file, fs::canonicalize(&file).unwrap());
+        assert_eq!(fs::read_to_string(&file).unwrap(), "v3 edited");
+
+        let undone = undo_in(&backups, false).unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "v2");
+        assert_eq!(
+            fs::read_to_string(undone.saved.unwrap()).unwrap(),
+            "v3 edited"
+        );
+        // The saved copy is not undone into: the next undo steps back
+        undo_in(&backups, false).unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "v1");
+        assert_eq!(read_log(&backups).len(), 2);
+        assert!(matches!(
+            undo_in(&backups, false),
+            Err(K8pkError::NothingToUndo)
+        ));
+    }
+
+    #[test]
+    fn backups_are_capped() {
+        let dir = tempfile::tempdir().unwrap();
+        let backups = dir.path().join("backups");
+        let file = dir.path().join("config");
+        fs::write(&file, "x").unwrap();
+        for _ in 0..KEEP + 3 {
+            backup_into(&backups, &file, false).unwrap();
+        }
+        assert_eq!(read_log(&backups).len(), KEEP);
+        let files = fs::read_dir(&backups)
+            .unwrap()
+            .flatten()
+            .filter(|e| e.file_name().to_string_lossy().contains(".bak."))
+            .count();
+        assert_eq!(files, KEEP);
+    }
+}
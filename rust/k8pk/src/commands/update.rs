@@ -1,45 +1,73 @@
 //! Self-update command
 
+use crate::cli::UpdateChannel;
 use crate::error::{K8pkError, Result};
+use crate::install_source::{self, InstallSource};
+use sha2::{Digest, Sha256};
 use std::fs;
 use std::io::Write;
+use std::path::Path;
 use std::process::Command;
 use std::time::Duration;
 
+const RELEASES_API: &str = "https://api.github.com/repos/vee-sh/k8pk/releases";
+
 #[derive(Debug, serde::Serialize)]
 pub struct UpdateResult {
     pub current_version: String,
     pub latest_version: Option<String>,
+    /// `stable`, `nightly`, or `pinned` (`--version`)
+    pub channel: String,
+    /// The release found differs from the running version
+    pub update_available: bool,
     pub updated: bool,
+    /// Signatures checked besides the sha256 checksum (`cosign`, `minisign`)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub verified: Vec<String>,
+    /// How the running binary was installed
+    pub install_source: InstallSource,
     pub message: String,
 }
 
+/// What `k8pk update` installs and how it verifies the download
+#[derive(Debug, Default)]
+pub struct UpdateOptions {
+    pub check_only: bool,
+    pub force: bool,
+    pub channel: UpdateChannel,
+    /// `--version`: this release instead of the channel's latest
+    pub version: Option<String>,
+    /// Verify the Sigstore signature with `cosign verify-blob`
+    pub cosign: bool,
+    /// Verify `<asset>.minisig` with `minisign` and this public key
+    pub minisign_key: Option<String>,
+    /// Overwrite the binary even when a package manager installed it
+    pub force_binary: bool,
+}
+
+impl UpdateOptions {
+    fn channel_name(&self) -> &'static str {
+        match (&self.version, self.channel) {
+            (Some(_), _) => "pinned",
+            (None, UpdateChannel::Stable) => "stable",
+            (None, UpdateChannel::Nightly) => "nightly",
+        }
+    }
+}
+
 /// Check for and optionally install k8pk updates
-pub fn check_and_update(check_only: bool, force: bool, quiet: bool) -> Result<UpdateResult> {
+pub fn check_and_update(opts: &UpdateOptions, quiet: bool) -> Result<UpdateResult> {
+    crate::config::require_online("k8pk update")?;
     let current_version = env!("CARGO_PKG_VERSION");
+    let source = install_source::detect();
 
-    // Get latest version from GitHub API
     let client = reqwest::blocking::Client::builder()
         .user_agent("k8pk-updater")
         .timeout(Duration::from_secs(20))
         .build()
         .map_err(|e| K8pkError::HttpError(format!("failed to create HTTP client: {}", e)))?;
 
-    let response = client
-        .get("https://api.github.com/repos/vee-sh/k8pk/releases/latest")
-        .send()
-        .map_err(|e| K8pkError::HttpError(format!("failed to fetch release info: {}", e)))?;
-
-    if !response.status().is_success() {
-        return Err(K8pkError::HttpError(format!(
-            "failed to fetch release info: HTTP {}",
-            response.status()
-        )));
-    }
-
-    let release: serde_json::Value = response
-        .json()
-        .map_err(|e| K8pkError::HttpError(format!("failed to parse release info: {}", e)))?;
+    let release = fetch_release(&client, opts)?;
 
     let latest_tag = release
         .get("tag_name")
@@ -47,9 +75,20 @@ pub fn check_and_update(check_only: bool, force: bool, quiet: bool) -> Result<Up
         .ok_or_else(|| K8pkError::HttpError("invalid release info: missing tag_name".into()))?;
 
     let latest_version = latest_tag.trim_start_matches('v');
+    let update_available = latest_version != current_version;
+    let result = |updated: bool, verified: Vec<String>, message: String| UpdateResult {
+        current_version: current_version.to_string(),
+        latest_version: Some(latest_tag.to_string()),
+        channel: opts.channel_name().to_string(),
+        update_available,
+        updated,
+        verified,
+        install_source: source.clone(),
+        message,
+    };
 
-    if latest_version == current_version && !force {
-        let message = if check_only {
+    if !update_available && !opts.force {
+        let message = if opts.check_only {
             format!("k8pk is already up to date (v{})", current_version)
         } else {
             format!(
@@ -60,27 +99,30 @@ pub fn check_and_update(check_only: bool, force: bool, quiet: bool) -> Result<Up
         if !quiet {
             println!("{}", message);
         }
-        return Ok(UpdateResult {
-            current_version: current_version.to_string(),
-            latest_version: Some(latest_tag.to_string()),
-            updated: false,
-            message,
-        });
+        return Ok(result(false, Vec::new(), message));
     }
 
-    if check_only {
-        let message = format!(
+    let upgrade_command = source.upgrade_command();
+    if opts.check_only {
+        let mut message = format!(
             "Current version: v{}\nLatest version:  {}\nUpdate available!",
             current_version, latest_tag
         );
+        if let Some(command) = &upgrade_command {
+            message.push_str(&format!("\nUpgrade with: {}", command));
+        }
         if !quiet {
             println!("{}", message);
         }
-        return Ok(UpdateResult {
-            current_version: current_version.to_string(),
-            latest_version: Some(latest_tag.to_string()),
-            updated: false,
-            message,
+        return Ok(result(false, Vec::new(), message));
+    }
+
+    // Overwriting a package manager's file leaves its records wrong and is
+    // undone by its next upgrade
+    if let (Some(command), false) = (upgrade_command, opts.force_binary) {
+        return Err(K8pkError::ManagedInstall {
+            manager: source.name().to_string(),
+            command,
         });
     }
 
@@ -88,8 +130,9 @@ pub fn check_and_update(check_only: bool, force: bool, quiet: bool) -> Result<Up
         println!("Updating from v{} to {}", current_version, latest_tag);
     }
 
-    // Detect platform
-    let (os, arch) = detect_platform();
+    let target = release_target().ok_or_else(|| {
+        K8pkError::CommandFailed("no release builds for this platform; build from source".into())
+    })?;
 
     // Find the asset
     let assets = release
@@ -97,38 +140,53 @@ pub fn check_and_update(check_only: bool, force: bool, quiet: bool) -> Result<Up
         .and_then(|v| v.as_array())
         .ok_or_else(|| K8pkError::HttpError("no assets in release".into()))?;
 
-    let pattern = format!("k8pk-{}-{}", os, arch);
-    let asset = assets
+    let suffix = format!("-{}.tar.gz", target);
+    let asset_url = |name: &str| {
+        assets
+            .iter()
+            .find(|a| a.get("name").and_then(|n| n.as_str()) == Some(name))
+            .and_then(|a| a.get("browser_download_url"))
+            .and_then(|v| v.as_str())
+    };
+    let asset_name = assets
         .iter()
-        .find(|a| {
-            a.get("name")
-                .and_then(|n| n.as_str())
-                .map(|n| n.contains(&pattern))
-                .unwrap_or(false)
-        })
-        .ok_or_else(|| {
-            K8pkError::HttpError(format!("no binary found for platform: {}-{}", os, arch))
-        })?;
-
-    let download_url = asset
-        .get("browser_download_url")
-        .and_then(|v| v.as_str())
-        .ok_or_else(|| K8pkError::HttpError("invalid asset URL".into()))?;
-
-    let asset_name = asset
-        .get("name")
-        .and_then(|v| v.as_str())
-        .unwrap_or("k8pk.tar.gz");
+        .filter_map(|a| a.get("name").and_then(|n| n.as_str()))
+        .find(|n| n.starts_with("k8pk-") && n.ends_with(&suffix))
+        .ok_or_else(|| K8pkError::HttpError(format!("no binary found for platform: {}", target)))?;
+    let download_url =
+        asset_url(asset_name).ok_or_else(|| K8pkError::HttpError("invalid asset URL".into()))?;
+
+    let download = |url: &str| -> Result<Vec<u8>> {
+        let resp = client
+            .get(url)
+            .send()
+            .map_err(|e| K8pkError::HttpError(format!("download failed: {}", e)))?;
+        if !resp.status().is_success() {
+            return Err(K8pkError::HttpError(format!(
+                "download failed: HTTP {}",
+                resp.status()
+            )));
+        }
+        Ok(resp
+            .bytes()
+            .map_err(|e| K8pkError::HttpError(format!("download failed: {}", e)))?
+            .to_vec())
+    };
 
     eprintln!("downloading {}", asset_name);
-
-    // Download
-    let bytes = client
-        .get(download_url)
-        .send()
-        .map_err(|e| K8pkError::HttpError(format!("download failed: {}", e)))?
-        .bytes()
-        .map_err(|e| K8pkError::HttpError(format!("download failed: {}", e)))?;
+    let bytes = download(download_url)?;
+
+    // Every release ships <asset>.sha256; refuse to install without a match
+    let checksum_name = format!("{}.sha256", asset_name);
+    let checksum_url = asset_url(&checksum_name).ok_or_else(|| {
+        K8pkError::CommandFailed(format!(
+            "release has no {}; refusing to install an unverified download",
+            checksum_name
+        ))
+    })?;
+    let checksum_file = String::from_utf8_lossy(&download(checksum_url)?).into_owned();
+    verify_checksum(&bytes, &checksum_file, asset_name)?;
+    eprintln!("sha256 checksum verified");
 
     // Save to temp and extract
     let temp_dir = tempfile::tempdir()?;
@@ -137,6 +195,33 @@ pub fn check_and_update(check_only: bool, force: bool, quiet: bool) -> Result<Up
     let mut file = fs::File::create(&archive_path)?;
     file.write_all(&bytes)?;
 
+    let mut verified = Vec::new();
+    if opts.cosign {
+        let fetch_to = |suffix: &str| -> Result<std::path::PathBuf> {
+            let name = format!("{}{}", asset_name, suffix);
+            let url = asset_url(&name).ok_or_else(|| {
+                K8pkError::CommandFailed(format!("release has no {} for cosign", name))
+            })?;
+            let path = temp_dir.path().join(&name);
+            fs::write(&path, download(url)?)?;
+            Ok(path)
+        };
+        verify_cosign(&archive_path, &fetch_to(".sig")?, &fetch_to(".pem")?)?;
+        eprintln!("cosign signature verified");
+        verified.push("cosign".to_string());
+    }
+    if let Some(key) = &opts.minisign_key {
+        let name = format!("{}.minisig", asset_name);
+        let url = asset_url(&name).ok_or_else(|| {
+            K8pkError::CommandFailed(format!("release has no {} for minisign", name))
+        })?;
+        let sig_path = temp_dir.path().join(&name);
+        fs::write(&sig_path, download(url)?)?;
+        verify_minisign(&archive_path, &sig_path, key)?;
+        eprintln!("minisign signature verified");
+        verified.push("minisign".to_string());
+    }
+
     eprintln!("extracting archive");
 
     // Extract using tar
@@ -149,13 +234,15 @@ pub fn check_and_update(check_only: bool, force: bool, quiet: bool) -> Result<Up
         return Err(K8pkError::CommandFailed("failed to extract archive".into()));
     }
 
-    // Find binary and install
-    let binary_path = temp_dir.path().join("k8pk");
-    if !binary_path.exists() {
-        return Err(K8pkError::CommandFailed(
-            "binary not found in archive".into(),
-        ));
-    }
+    // Release archives hold k8pk-v<version>-<target>/k8pk; older ones had it at the top
+    let pkg_dir = asset_name.trim_end_matches(".tar.gz");
+    let binary_path = [
+        temp_dir.path().join(pkg_dir).join("k8pk"),
+        temp_dir.path().join("k8pk"),
+    ]
+    .into_iter()
+    .find(|p| p.is_file())
+    .ok_or_else(|| K8pkError::CommandFailed("binary not found in archive".into()))?;
 
     // Try to find current binary location
     let install_path =
@@ -178,30 +265,222 @@ pub fn check_and_update(check_only: bool, force: bool, quiet: bool) -> Result<Up
     if !quiet {
         println!("{}", message);
     }
-    Ok(UpdateResult {
-        current_version: current_version.to_string(),
-        latest_version: Some(latest_tag.to_string()),
-        updated: true,
-        message,
-    })
+    Ok(result(true, verified, message))
 }
 
-fn detect_platform() -> (&'static str, &'static str) {
-    let os = if cfg!(target_os = "macos") {
-        "darwin"
-    } else if cfg!(target_os = "linux") {
-        "linux"
-    } else {
-        "unknown"
+/// Release JSON for the pinned version, or the channel's newest release
+fn fetch_release(
+    client: &reqwest::blocking::Client,
+    opts: &UpdateOptions,
+) -> Result<serde_json::Value> {
+    let url = match (&opts.version, opts.channel) {
+        (Some(v), _) => format!("{}/tags/v{}", RELEASES_API, v.trim_start_matches('v')),
+        (None, UpdateChannel::Stable) => format!("{}/latest", RELEASES_API),
+        (None, UpdateChannel::Nightly) => format!("{}?per_page=30", RELEASES_API),
     };
+    let response = client
+        .get(&url)
+        .send()
+        .map_err(|e| K8pkError::HttpError(format!("failed to fetch release info: {}", e)))?;
+
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        if let Some(v) = &opts.version {
+            return Err(K8pkError::InvalidArgument(format!(
+                "no release v{} found",
+                v.trim_start_matches('v')
+            )));
+        }
+    }
+    if !response.status().is_success() {
+        return Err(K8pkError::HttpError(format!(
+            "failed to fetch release info: HTTP {}",
+            response.status()
+        )));
+    }
+
+    let body: serde_json::Value = response
+        .json()
+        .map_err(|e| K8pkError::HttpError(format!("failed to parse release info: {}", e)))?;
+    if opts.version.is_none() && opts.channel == UpdateChannel::Nightly {
+        return newest_prerelease(&body)
+            .cloned()
+            .ok_or_else(|| K8pkError::HttpError("no nightly (pre-release) build found".into()));
+    }
+    Ok(body)
+}
 
-    let arch = if cfg!(target_arch = "x86_64") {
-        "amd64"
-    } else if cfg!(target_arch = "aarch64") {
-        "arm64"
+/// First pre-release in a release list (GitHub returns newest first)
+fn newest_prerelease(releases: &serde_json::Value) -> Option<&serde_json::Value> {
+    releases
+        .as_array()?
+        .iter()
+        .find(|r| r["prerelease"].as_bool() == Some(true) && r["draft"].as_bool() != Some(true))
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Check `bytes` against the `shasum -a 256` line for `asset_name`
+/// (`<hex>  <name>`; a lone hash is accepted too)
+fn verify_checksum(bytes: &[u8], checksum_file: &str, asset_name: &str) -> Result<()> {
+    let expected = checksum_file
+        .lines()
+        .filter_map(|l| {
+            let mut parts = l.split_whitespace();
+            let hash = parts.next()?;
+            match parts.next() {
+                Some(name) if name.trim_start_matches('*') != asset_name => None,
+                _ => Some(hash.to_lowercase()),
+            }
+        })
+        .next()
+        .ok_or_else(|| {
+            K8pkError::CommandFailed(format!(
+                "no checksum for {} in its .sha256 file",
+                asset_name
+            ))
+        })?;
+    let actual = sha256_hex(bytes);
+    if actual != expected {
+        return Err(K8pkError::CommandFailed(format!(
+            "checksum mismatch for {}: expected {}, got {}; not installing",
+            asset_name, expected, actual
+        )));
+    }
+    Ok(())
+}
+
+/// Keyless signing identity of release archives: the CI workflow run for a
+/// version tag. Anchored and escaped, as cosign matches it as a regex.
+const COSIGN_IDENTITY_REGEXP: &str =
+    r"^https://github\.com/vee-sh/k8pk/\.github/workflows/ci\.yml@refs/tags/v[0-9][^/]*$";
+
+/// `cosign verify-blob` against the release workflow's keyless identity
+fn verify_cosign(archive: &Path, signature: &Path, certificate: &Path) -> Result<()> {
+    let cosign = which::which("cosign").map_err(|_| {
+        K8pkError::CommandFailed(
+            "cosign not found on PATH (https://docs.sigstore.dev/cosign/system_config/installation/)"
+                .into(),
+        )
+    })?;
+    let output = Command::new(cosign)
+        .arg("verify-blob")
+        .arg("--certificate")
+        .arg(certificate)
+        .arg("--signature")
+        .arg(signature)
+        .args([
+            "--certificate-identity-regexp",
+            COSIGN_IDENTITY_REGEXP,
+            "--certificate-oidc-issuer",
+            "https://token.actions.githubusercontent.com",
+        ])
+        .arg(archive)
+        .output()?;
+    if !output.status.success() {
+        return Err(K8pkError::CommandFailed(format!(
+            "cosign signature verification failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+/// `minisign -V` with the public key given on the command line
+fn verify_minisign(archive: &Path, signature: &Path, public_key: &str) -> Result<()> {
+    let minisign = which::which("minisign").map_err(|_| {
+        K8pkError::CommandFailed(
+            "minisign not found on PATH (https://jedisct1.github.io/minisign/)".into(),
+        )
+    })?;
+    let output = Command::new(minisign)
+        .arg("-V")
+        .arg("-m")
+        .arg(archive)
+        .arg("-x")
+        .arg(signature)
+        .arg("-P")
+        .arg(public_key)
+        .output()?;
+    if !output.status.success() {
+        return Err(K8pkError::CommandFailed(format!(
+            "minisign signature verification failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+/// Target triple of the release archives built for this platform
+/// (`k8pk-v<version>-<target>.tar.gz`)
+fn release_target() -> Option<&'static str> {
+    if cfg!(all(target_os = "linux", target_arch = "x86_64")) {
+        Some("x86_64-unknown-linux-gnu")
+    } else if cfg!(all(target_os = "macos", target_arch = "x86_64")) {
+        Some("x86_64-apple-darwin")
+    } else if cfg!(all(target_os = "macos", target_arch = "aarch64")) {
+        Some("aarch64-apple-darwin")
+    } else if cfg!(all(target_os = "windows", target_arch = "x86_64")) {
+        Some("x86_64-pc-windows-msvc")
     } else {
-        "unknown"
-    };
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cosign_identity_is_the_release_workflow() {
+        let re = regex::Regex::new(COSIGN_IDENTITY_REGEXP).unwrap();
+        assert!(
+            re.is_match("https://github.com/vee-sh/k8pk/.github/workflows/ci.yml@refs/tags/v0.9.1")
+        );
+        for identity in [
+            "https://github.com/evil/x?https://github.com/vee-sh/k8pk/",
+            "https://github.com/vee-sh/k8pk/.github/workflows/ci.yml@refs/heads/main",
+            "https://githubXcom/vee-sh/k8pk/.github/workflows/ci.yml@refs/tags/v1",
+            "https://github.com/vee-sh/k8pk/.github/workflows/other.yml@refs/tags/v1",
+        ] {
+            assert!(!re.is_match(identity), "{}", identity);
+        }
+    }
 
-    (os, arch)
+    #[test]
+    fn checksum_lines_are_matched_by_asset_name() {
+        let bytes = b"k8pk";
+        let hash = sha256_hex(bytes);
+        assert_eq!(hash.len(), 64);
+        assert_eq!(
+            sha256_hex(b""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+
+        let asset = "k8pk-v1.2.3-x86_64-unknown-linux-gnu.tar.gz";
+        let file = format!("{}  {}\n", hash, asset);
+        assert!(verify_checksum(bytes, &file, asset).is_ok());
+        assert!(verify_checksum(bytes, &hash, asset).is_ok());
+        assert!(verify_checksum(b"tampered", &file, asset).is_err());
+        assert!(verify_checksum(bytes, &format!("{}  other.tar.gz", hash), asset).is_err());
+    }
+
+    #[test]
+    fn nightly_is_the_newest_prerelease() {
+        let releases = serde_json::json!([
+            {"tag_name": "v2.0.0-rc.1", "prerelease": true, "draft": true},
+            {"tag_name": "v1.15.0-nightly.2", "prerelease": true},
+            {"tag_name": "v1.14.14", "prerelease": false},
+            {"tag_name": "v1.15.0-nightly.1", "prerelease": true},
+        ]);
+        assert_eq!(
+            newest_prerelease(&releases).unwrap()["tag_name"],
+            "v1.15.0-nightly.2"
+        );
+        assert!(newest_prerelease(&serde_json::json!([])).is_none());
+    }
 }
@@ -0,0 +1,253 @@
+//! `k8pk version-skew`: compare the installed kubectl/oc against each
+//! cluster's API server version. kubectl is supported within one minor
+//! version (older or newer) of the server.
+
+use super::{ContextFilter, ContextRow};
+use crate::error::Result;
+use crate::kubeconfig;
+use serde::Serialize;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Supported distance in minor versions between client and server
+const SUPPORTED_SKEW: i64 = 1;
+
+/// An installed client and the Kubernetes version it corresponds to
+#[derive(Debug, Clone, Serialize)]
+pub struct ClientVersion {
+    pub tool: String,
+    /// As reported by the tool
+    pub version: String,
+    /// `major.minor` of the Kubernetes client it is
+    pub kubernetes: String,
+    #[serde(skip)]
+    minor: (u32, u32),
+}
+
+#[derive(Debug, Serialize)]
+pub struct ClientSkew {
+    pub tool: String,
+    /// Client minor minus server minor
+    pub skew: i64,
+    pub supported: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ContextSkew {
+    pub context: String,
+    /// None when the cluster could not be reached
+    pub server_version: Option<String>,
+    pub clients: Vec<ClientSkew>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VersionSkewReport {
+    pub clients: Vec<ClientVersion>,
+    pub contexts: Vec<ContextSkew>,
+    /// Contexts where some client is outside the supported skew
+    pub unsupported: usize,
+}
+
+/// `(major, minor)` from a version such as `v1.29.3+k3s1` or `4.14.5`
+fn parse_minor(version: &str) -> Option<(u32, u32)> {
+    let mut parts = version.trim().trim_start_matches('v').split('.');
+    let major = parts.next()?.parse().ok()?;
+    let minor: String = parts
+        .next()?
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    Some((major, minor.parse().ok()?))
+}
+
+/// Client minor minus server minor (None across major versions)
+fn skew(client: (u32, u32), server: (u32, u32)) -> Option<i64> {
+    (client.0 == server.0).then(|| i64::from(client.1) - i64::from(server.1))
+}
+
+/// The client version from `<tool> version --client -o json`. For oc 4.N,
+/// whose own version is not a Kubernetes one, the Kubernetes 1.(N+13) it
+/// bundles (OpenShift 4.14 ships Kubernetes 1.27).
+fn parse_client_version(tool: &str, json: &str) -> Option<ClientVersion> {
+    let value: serde_json::Value = serde_json::from_str(json).ok()?;
+    let git_version = value["clientVersion"]["gitVersion"].as_str();
+    let (version, minor) = match value["releaseClientVersion"].as_str() {
+        Some(release) => {
+            let (major, minor) = parse_minor(release)?;
+            (
+                release.to_string(),
+                (1, (major == 4).then_some(minor + 13)?),
+            )
+        }
+        None => {
+            let version = git_version?;
+            (version.to_string(), parse_minor(version)?)
+        }
+    };
+    Some(ClientVersion {
+        tool: tool.to_string(),
+        version,
+        kubernetes: format!("{}.{}", minor.0, minor.1),
+        minor,
+    })
+}
+
+/// kubectl and oc, where installed
+pub fn client_versions() -> Vec<ClientVersion> {
+    let mut tools: Vec<(&str, PathBuf)> = Vec::new();
+    if let Ok(path) = which::which("kubectl") {
+        tools.push(("kubectl", path));
+    }
+    if kubeconfig::oc_available() {
+        tools.push(("oc", kubeconfig::oc_cli_path()));
+    }
+    tools
+        .into_iter()
+        .filter_map(|(tool, path)| {
+            let output = Command::new(path)
+                .args(["version", "--client", "-o", "json"])
+                .output()
+                .ok()?;
+            parse_client_version(tool, &String::from_utf8_lossy(&output.stdout))
+        })
+        .collect()
+}
+
+/// Each client's skew against `server_version`
+pub fn client_skews(clients: &[ClientVersion], server_version: &str) -> Vec<ClientSkew> {
+    let Some(server) = parse_minor(server_version) else {
+        return Vec::new();
+    };
+    clients
+        .iter()
+        .filter_map(|c| {
+            let skew = skew(c.minor, server)?;
+            Some(ClientSkew {
+                tool: c.tool.clone(),
+                skew,
+                supported: skew.abs() <= SUPPORTED_SKEW,
+            })
+        })
+        .collect()
+}
+
+/// Server versions of the contexts passing `filter` (reachable ones queried
+/// in parallel; cached for five minutes like `which --peek`) against the
+/// installed clients
+pub fn version_skew(paths: &[PathBuf], filter: &ContextFilter) -> Result<VersionSkewReport> {
+    let clients = client_versions();
+    let mut rows: Vec<ContextRow> = super::select_context_rows(paths, filter, None)?;
+    super::peek_rows(&mut rows, paths);
+
+    let contexts: Vec<ContextSkew> = rows
+        .into_iter()
+        .map(|row| {
+            let server_version = row.peek.and_then(|f| f.version);
+            ContextSkew {
+                clients: server_version
+                    .as_deref()
+                    .map(|v| client_skews(&clients, v))
+                    .unwrap_or_default(),
+                context: row.context,
+                server_version,
+            }
+        })
+        .collect();
+    let unsupported = contexts
+        .iter()
+        .filter(|c| c.clients.iter().any(|s| !s.supported))
+        .count();
+    Ok(VersionSkewReport {
+        clients,
+        contexts,
+        unsupported,
+    })
+}
+
+pub fn print_version_skew(report: &VersionSkewReport) {
+    if report.clients.is_empty() {
+        println!("Clients: none found (install kubectl or oc)");
+    } else {
+        let clients: Vec<String> = report
+            .clients
+            .iter()
+            .map(|c| {
+                if parse_minor(&c.version) == Some(c.minor) {
+                    format!("{} {}", c.tool, c.version)
+                } else {
+                    format!("{} {} (Kubernetes {})", c.tool, c.version, c.kubernetes)
+                }
+            })
+            .collect();
+        println!("Clients: {}", clients.join(", "));
+    }
+    println!();
+
+    let width = report
+        .contexts
+        .iter()
+        .map(|c| c.context.len())
+        .max()
+        .unwrap_or(0);
+    for ctx in &report.contexts {
+        let Some(server) = &ctx.server_version else {
+            println!("  {:<width$}  unreachable", ctx.context);
+            continue;
+        };
+        let skews: Vec<String> = ctx
+            .clients
+            .iter()
+            .map(|s| {
+                let note = if s.supported { "" } else { " (unsupported)" };
+                format!("{} {:+}{}", s.tool, s.skew, note)
+            })
+            .collect();
+        let mark = if ctx.clients.iter().all(|s| s.supported) {
+            "ok"
+        } else {
+            "SKEW"
+        };
+        println!(
+            "  {:<width$}  {:<14}  {:<4}  {}",
+            ctx.context,
+            server,
+            mark,
+            skews.join(", ")
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn client_versions_and_skew() {
+        assert_eq!(parse_minor("v1.29.3+k3s1"), Some((1, 29)));
+        assert_eq!(parse_minor("v1.27.6-eks-f8587cb"), Some((1, 27)));
+        assert_eq!(parse_minor("1.30+"), Some((1, 30)));
+        assert_eq!(parse_minor("garbage"), None);
+
+        let kubectl = parse_client_version(
+            "kubectl",
+            r#"{"clientVersion":{"gitVersion":"v1.30.1"},"kustomizeVersion":"v5.0.4"}"#,
+        )
+        .unwrap();
+        assert_eq!(kubectl.kubernetes, "1.30");
+        let oc = parse_client_version(
+            "oc",
+            r#"{"clientVersion":{"gitVersion":"v4.2.0-alpha.0-2003-g1b5e"},"releaseClientVersion":"4.14.5"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            (oc.version.as_str(), oc.kubernetes.as_str()),
+            ("4.14.5", "1.27")
+        );
+
+        let skews = client_skews(&[kubectl, oc], "v1.29.3");
+        assert_eq!(skews[0].skew, 1);
+        assert!(skews[0].supported);
+        assert_eq!(skews[1].skew, -2);
+        assert!(!skews[1].supported);
+    }
+}
@@ -4,8 +4,11 @@ use crate::error::{K8pkError, Result};
 use crate::kubeconfig;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
 
 /// K8pk configuration structure
 #[derive(Deserialize, Serialize, Debug, Clone, Default)]
@@ -18,14 +21,134 @@ pub struct K8pkConfig {
     pub aliases: Option<HashMap<String, String>>,
     #[serde(default)]
     pub pick: Option<PickSection>,
+    /// Interactive picker: "builtin" (default), "fzf", or "sk".
+    /// Overridden by `--picker` and K8PK_PICKER.
+    #[serde(default)]
+    pub picker: Option<String>,
+    /// Output colors: "default", "colorblind", or "mono".
+    /// Overridden by K8PK_THEME; NO_COLOR / `--no-color` turn colors off.
+    #[serde(default)]
+    pub theme: Option<String>,
+    /// Language of prompts and messages, e.g. "de". Overridden by K8PK_LANG;
+    /// unset follows LC_ALL / LC_MESSAGES / LANG.
+    #[serde(default)]
+    pub language: Option<String>,
     #[serde(default)]
     pub tmux: Option<TmuxSection>,
     #[serde(default)]
     pub shell: Option<ShellSection>,
+    #[serde(default)]
+    pub namespaces: Option<NamespacesSection>,
+    #[serde(default)]
+    pub display: Option<DisplaySection>,
     /// Context name patterns that should always use insecure-skip-tls-verify.
     /// Supports simple glob patterns (* matches any sequence, ? matches single char).
     #[serde(default)]
     pub insecure_contexts: Vec<String>,
+    /// Context name patterns (same syntax as insecure_contexts) where k8pk
+    /// refuses shortcuts that change the cluster, such as `ns --create`.
+    #[serde(default)]
+    pub protected_contexts: Vec<String>,
+    /// Context name patterns (same syntax as insecure_contexts) mapped to a
+    /// CA bundle that replaces certificate-authority(-data) in isolated
+    /// kubeconfigs, e.g. behind a TLS-intercepting proxy.
+    #[serde(default)]
+    pub ca_overrides: HashMap<String, String>,
+    #[serde(default)]
+    pub encryption: Option<EncryptionSection>,
+    /// Where isolated kubeconfigs are written: "persistent" (default,
+    /// ~/.local/share/k8pk) or "runtime" ($XDG_RUNTIME_DIR/k8pk, wiped on reboot).
+    #[serde(default)]
+    pub session_storage: Option<crate::storage::SessionStorage>,
+    /// Inline certificate and key files into isolated kubeconfigs, so they
+    /// keep working if the originals move.
+    #[serde(default)]
+    pub embed_certs: bool,
+    /// Seconds an isolated kubeconfig stays usable after it is generated;
+    /// then commands in shells using it fail until `k8pk refresh`
+    #[serde(default)]
+    pub session_ttl: Option<u64>,
+    /// Per-context settings, keyed by context name or glob pattern
+    #[serde(default)]
+    pub contexts: HashMap<String, ContextSettings>,
+    /// Directories prepended to PATH in spawned shells and `k8pk exec`, so
+    /// exec credential plugins installed off the usual PATH are found
+    #[serde(default)]
+    pub exec_path_hints: Vec<String>,
+    /// Never contact clusters or other servers; use caches or skip
+    #[serde(default)]
+    pub offline: bool,
+    /// Git repository of kubeconfigs pulled into ~/.kube/managed by `k8pk sync`
+    #[serde(default)]
+    pub sync: Option<SyncSection>,
+    /// Login profiles for `k8pk login <name>`, keyed by profile name
+    #[serde(default)]
+    pub clusters: HashMap<String, ClusterProfile>,
+}
+
+/// `clusters:` entry: a server and how `k8pk login <name>` logs in to it.
+/// Flags given on the command line win over these.
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct ClusterProfile {
+    pub server: String,
+    /// ocp, k8s, gke, or rancher (default: detected from the server URL)
+    #[serde(default, rename = "type")]
+    pub login_type: Option<String>,
+    /// Authentication mode, as `login --auth`
+    #[serde(default)]
+    pub auth: Option<String>,
+    #[serde(default)]
+    pub username: Option<String>,
+    /// CA bundle file (~ expands)
+    #[serde(default)]
+    pub certificate_authority: Option<String>,
+    #[serde(default)]
+    pub insecure_skip_tls_verify: bool,
+    /// Client certificate and key files for client-cert auth (~ expands)
+    #[serde(default)]
+    pub client_certificate: Option<String>,
+    #[serde(default)]
+    pub client_key: Option<String>,
+    /// Where the kubeconfig is written (~ expands)
+    #[serde(default)]
+    pub output_dir: Option<String>,
+    /// Context name; {profile}, {host}, and {user} are replaced
+    /// (default: the profile name)
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub pass_entry: Option<String>,
+    #[serde(default)]
+    pub op_item: Option<String>,
+    #[serde(default)]
+    pub bw_item: Option<String>,
+    #[serde(default)]
+    pub use_vault: bool,
+    #[serde(default)]
+    pub exec_preset: Option<String>,
+    #[serde(default)]
+    pub exec_cluster: Option<String>,
+    #[serde(default)]
+    pub exec_server_id: Option<String>,
+    #[serde(default)]
+    pub exec_region: Option<String>,
+    #[serde(default)]
+    pub gssapi_token_url: Option<String>,
+    #[serde(default)]
+    pub rancher_auth_provider: Option<String>,
+}
+
+/// `sync:` section: where `k8pk sync` gets team kubeconfigs from
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct SyncSection {
+    /// Clone URL (https, ssh, or a local path)
+    pub repo: String,
+    /// Branch or tag (default: the remote's default branch)
+    #[serde(default)]
+    pub branch: Option<String>,
+    /// Directories of the repo to check out (sparse checkout); all when empty
+    #[serde(default)]
+    pub paths: Vec<String>,
 }
 
 /// Hooks configuration section
@@ -47,6 +170,10 @@ pub struct PickSection {
     /// Set 0 to probe the API on every pick/ctx. Override: K8PK_SESSION_CHECK_TTL.
     #[serde(default = "default_session_check_ttl")]
     pub session_check_ttl: u64,
+    /// Show the context picker in groups with a header each: by cluster
+    /// "type", source "file", or "tag" (from the `contexts:` section)
+    #[serde(default)]
+    pub group_by: Option<PickGroupBy>,
 }
 
 impl Default for PickSection {
@@ -54,10 +181,20 @@ impl Default for PickSection {
         Self {
             clusters_only: false,
             session_check_ttl: default_session_check_ttl(),
+            group_by: None,
         }
     }
 }
 
+/// Value of `pick.group_by`
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PickGroupBy {
+    Type,
+    File,
+    Tag,
+}
+
 fn default_session_check_ttl() -> u64 {
     300
 }
@@ -68,7 +205,8 @@ pub struct TmuxSection {
     /// Mode: "windows" (default) or "sessions"
     #[serde(default = "default_tmux_mode")]
     pub mode: String,
-    /// Naming template, e.g. "k8pk-{context}" (default: "{context}")
+    /// Naming template, e.g. "k8pk-{context}" or "{type}-{friendly}"
+    /// (default: "{context}")
     #[serde(default)]
     pub name_template: Option<String>,
 }
@@ -85,6 +223,172 @@ pub struct ShellSection {
     /// instead of stacking endless shells. Opt in for kubie-style nesting.
     #[serde(default)]
     pub nested: bool,
+    /// How spawned shells run. See [`SpawnMode`].
+    #[serde(default)]
+    pub spawn_mode: SpawnMode,
+}
+
+/// Value of `shell.spawn_mode:` in the config
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SpawnMode {
+    /// Replace the k8pk process with the shell (nothing runs after it exits)
+    #[default]
+    Exec,
+    /// Wait for the shell, then run `stop_ctx`, record the session's end,
+    /// and offer to clean up stale isolated kubeconfigs
+    Wrap,
+}
+
+/// Settings for shells spawned in a context (`contexts:` in the config).
+/// `{context}` and `{namespace}` are replaced in both fields.
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq)]
+pub struct ContextSettings {
+    /// Directory new shells start in (`~` and `$VAR` are expanded)
+    #[serde(default)]
+    pub cwd: Option<String>,
+    /// Command run in the new shell's environment before the shell starts
+    #[serde(default)]
+    pub init_cmd: Option<String>,
+    /// Labels for selecting contexts (`k8pk can-i --tag prod`); every
+    /// matching pattern contributes its tags
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Proxy to reach the API server through (`http://`, `https://`, or
+    /// `socks5://`): written as `proxy-url` into isolated kubeconfigs and
+    /// exported as HTTPS_PROXY in spawned shells and `k8pk exec`
+    #[serde(default)]
+    pub proxy: Option<String>,
+    /// Banner shown when switching into the context, e.g. "PROD: change
+    /// freeze until Friday" (overrides a `k8pk.io/motd` kubeconfig extension)
+    #[serde(default)]
+    pub motd: Option<String>,
+    /// Color of the `motd` banner (default yellow)
+    #[serde(default)]
+    pub motd_color: Option<MotdColor>,
+}
+
+/// Value of `motd_color`
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum MotdColor {
+    Red,
+    #[default]
+    Yellow,
+    Green,
+    Blue,
+    Magenta,
+    Cyan,
+}
+
+impl MotdColor {
+    /// ANSI foreground color code
+    pub fn ansi(self) -> u8 {
+        match self {
+            MotdColor::Red => 31,
+            MotdColor::Green => 32,
+            MotdColor::Yellow => 33,
+            MotdColor::Blue => 34,
+            MotdColor::Magenta => 35,
+            MotdColor::Cyan => 36,
+        }
+    }
+}
+
+impl ContextSettings {
+    fn fill(template: &str, context: &str, namespace: Option<&str>) -> String {
+        template
+            .replace("{context}", context)
+            .replace("{namespace}", namespace.unwrap_or("default"))
+    }
+
+    /// `cwd` with placeholders, environment variables, and `~` expanded
+    pub fn start_dir(&self, context: &str, namespace: Option<&str>) -> Option<PathBuf> {
+        let cwd = Self::fill(self.cwd.as_deref()?, context, namespace);
+        Some(kubeconfig::normalize_path(&kubeconfig::expand_env_vars(
+            &cwd,
+        )))
+    }
+
+    /// `init_cmd` with placeholders filled in
+    pub fn init_command(&self, context: &str, namespace: Option<&str>) -> Option<String> {
+        Some(Self::fill(self.init_cmd.as_deref()?, context, namespace))
+    }
+
+    /// `proxy`, checked to be a scheme kubectl supports
+    pub fn proxy_url(&self) -> Result<Option<&str>> {
+        let Some(proxy) = self.proxy.as_deref() else {
+            return Ok(None);
+        };
+        if ["http://", "https://", "socks5://"]
+            .iter()
+            .any(|scheme| proxy.starts_with(scheme))
+        {
+            Ok(Some(proxy))
+        } else {
+            Err(K8pkError::InvalidArgument(format!(
+                "proxy '{}' must start with http://, https://, or socks5://",
+                proxy
+            )))
+        }
+    }
+}
+
+/// Encryption at rest for isolated kubeconfigs
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct EncryptionSection {
+    /// Keep isolated kubeconfigs age-encrypted on disk, with plaintext only in
+    /// $XDG_RUNTIME_DIR while a session uses it. Override: K8PK_ENCRYPT.
+    #[serde(default)]
+    pub enabled: bool,
+    /// age identity file (default ~/.config/k8pk/age-identity.txt, created on first use)
+    #[serde(default)]
+    pub identity: Option<String>,
+    /// Recipient to encrypt to (default: the identity's public key)
+    #[serde(default)]
+    pub recipient: Option<String>,
+}
+
+/// Namespace behavior configuration
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct NamespacesSection {
+    /// Restore the last namespace used in a context when switching back to it
+    /// with `k8pk ctx <name>` (no `-n`). Default true.
+    #[serde(default = "default_true")]
+    pub remember: bool,
+}
+
+impl Default for NamespacesSection {
+    fn default() -> Self {
+        Self { remember: true }
+    }
+}
+
+/// How a context is shown in prompts, tmux windows, and `k8pk info ctx
+/// --display` (`K8PK_CONTEXT_DISPLAY`)
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct DisplaySection {
+    /// Shorten provider names (EKS ARNs, `gke_<project>_<zone>_<name>`, ...)
+    /// to the cluster name for `{friendly}`. Default true.
+    #[serde(default = "default_true")]
+    pub normalize: bool,
+    /// Display template with `{context}`, `{friendly}`, `{type}`, and
+    /// `{namespace}` (default "{friendly}")
+    #[serde(default)]
+    pub template: Option<String>,
+}
+
+impl Default for DisplaySection {
+    fn default() -> Self {
+        Self {
+            normalize: true,
+            template: None,
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
 }
 
 /// Configs section for kubeconfig file discovery
@@ -94,6 +398,33 @@ pub struct ConfigsSection {
     pub include: Vec<String>,
     #[serde(default = "default_exclude_patterns")]
     pub exclude: Vec<String>,
+    /// Kubeconfig files left out of merging and pickers without deleting
+    /// them (`k8pk sources disable/enable`)
+    #[serde(default)]
+    pub disabled: Vec<String>,
+    /// Seconds a downloaded `https://` include is used before asking the
+    /// server again (default 3600)
+    #[serde(default)]
+    pub remote_ttl: Option<u64>,
+    /// Request header for `https://` includes, keyed by URL or glob pattern
+    #[serde(default)]
+    pub remote_auth: HashMap<String, RemoteAuth>,
+}
+
+/// Auth header sent when downloading a remote kubeconfig. The token comes
+/// from `token_env` or from the output of `token_cmd` (e.g. `pass show x`).
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct RemoteAuth {
+    /// Header name (default `Authorization`)
+    #[serde(default)]
+    pub header: Option<String>,
+    /// Header value with `{token}` replaced (default `Bearer {token}`)
+    #[serde(default)]
+    pub value: Option<String>,
+    #[serde(default)]
+    pub token_env: Option<String>,
+    #[serde(default)]
+    pub token_cmd: Option<String>,
 }
 
 impl Default for ConfigsSection {
@@ -101,6 +432,9 @@ impl Default for ConfigsSection {
         Self {
             include: default_include_patterns(),
             exclude: default_exclude_patterns(),
+            disabled: Vec::new(),
+            remote_ttl: None,
+            remote_auth: HashMap::new(),
         }
     }
 }
@@ -128,19 +462,14 @@ fn default_exclude_patterns() -> Vec<String> {
 ///
 /// For new installs, prefers the XDG location. Existing legacy configs are found automatically.
 pub fn config_path() -> Result<PathBuf> {
-    let home = dirs_next::home_dir().ok_or(K8pkError::NoHomeDir)?;
-
     // Check XDG location first
-    let xdg_dir = std::env::var("XDG_CONFIG_HOME")
-        .map(PathBuf::from)
-        .unwrap_or_else(|_| home.join(".config"));
-    let xdg_path = xdg_dir.join("k8pk").join("config.yaml");
+    let xdg_path = xdg_config_path()?;
     if xdg_path.exists() {
         return Ok(xdg_path);
     }
 
     // Fall back to legacy location
-    let legacy_path = home.join(".kube").join("k8pk.yaml");
+    let legacy_path = legacy_config_path()?;
     if legacy_path.exists() {
         return Ok(legacy_path);
     }
@@ -149,9 +478,151 @@ pub fn config_path() -> Result<PathBuf> {
     Ok(xdg_path)
 }
 
-/// Load k8pk configuration from disk.
+/// `$XDG_CONFIG_HOME/k8pk/config.yaml`, or `~/.config/k8pk/config.yaml`
+pub fn xdg_config_path() -> Result<PathBuf> {
+    let xdg_dir = match std::env::var("XDG_CONFIG_HOME") {
+        Ok(dir) => PathBuf::from(dir),
+        Err(_) => dirs_next::home_dir()
+            .ok_or(K8pkError::NoHomeDir)?
+            .join(".config"),
+    };
+    Ok(xdg_dir.join("k8pk").join("config.yaml"))
+}
+
+/// `~/.kube/k8pk.yaml`, where older releases kept the config
+pub fn legacy_config_path() -> Result<PathBuf> {
+    let home = dirs_next::home_dir().ok_or(K8pkError::NoHomeDir)?;
+    Ok(home.join(".kube").join("k8pk.yaml"))
+}
+
+/// Env var pointing at the team config (default: [`DEFAULT_TEAM_CONFIG`])
+pub const TEAM_CONFIG_ENV: &str = "K8PK_TEAM_CONFIG";
+
+/// System-wide config shipped by platform teams, layered beneath the user config
+pub const DEFAULT_TEAM_CONFIG: &str = "/etc/k8pk/config.yaml";
+
+/// Path of the team config, if one exists.
+///
+/// `K8PK_TEAM_CONFIG` wins over `/etc/k8pk/config.yaml`; set it to an empty
+/// string to ignore the team config entirely.
+pub fn team_config_path() -> Option<PathBuf> {
+    let path = match std::env::var(TEAM_CONFIG_ENV) {
+        Ok(p) if p.is_empty() => return None,
+        Ok(p) => {
+            let path = expand_home(&p);
+            if !path.exists() {
+                eprintln!(
+                    "warning: {} points to missing file {}",
+                    TEAM_CONFIG_ENV,
+                    path.display()
+                );
+                return None;
+            }
+            path
+        }
+        Err(_) => PathBuf::from(DEFAULT_TEAM_CONFIG),
+    };
+    path.exists().then_some(path)
+}
+
+/// Load k8pk configuration: team config, then the user config over it, then
+/// `K8PK_ALIASES` and `--alias` over `aliases`. The team's include patterns
+/// come after the user's (or the defaults), so the user's files win.
 /// ponytail: no process-lifetime cache; CLI runs are short
 pub fn load() -> Result<K8pkConfig> {
+    let user = read_yaml(&config_path()?)?;
+    let (merged, team_include) = match team_config_path() {
+        Some(team) => {
+            let mut team = read_yaml(&team)?;
+            let include = take_include(&mut team)?;
+            (layer_yaml(team, user), include)
+        }
+        None => (user, Vec::new()),
+    };
+    let mut config: K8pkConfig = if merged.is_null() {
+        K8pkConfig::default()
+    } else {
+        serde_yaml_ng::from_value(merged)?
+    };
+    for pattern in team_include {
+        if !config.configs.include.contains(&pattern) {
+            config.configs.include.push(pattern);
+        }
+    }
+    if let Ok(raw) = std::env::var(ALIASES_ENV) {
+        let overrides = parse_alias_list(&raw);
+        if !overrides.is_empty() {
+            config
+                .aliases
+                .get_or_insert_with(HashMap::new)
+                .extend(overrides);
+        }
+    }
+    if let Some(overrides) = CLI_ALIASES.get() {
+        config
+            .aliases
+            .get_or_insert_with(HashMap::new)
+            .extend(overrides.iter().cloned());
+    }
+    Ok(config)
+}
+
+/// Read a YAML file as an untyped value (`Null` if missing or empty)
+fn read_yaml(path: &Path) -> Result<serde_yaml_ng::Value> {
+    if !path.exists() {
+        return Ok(serde_yaml_ng::Value::Null);
+    }
+    let content = fs::read_to_string(path)?;
+    if content.trim().is_empty() {
+        return Ok(serde_yaml_ng::Value::Null);
+    }
+    Ok(serde_yaml_ng::from_str(&content)?)
+}
+
+/// Remove `configs.include` from a config value, returning its patterns
+fn take_include(value: &mut serde_yaml_ng::Value) -> Result<Vec<String>> {
+    let include = value
+        .get_mut("configs")
+        .and_then(serde_yaml_ng::Value::as_mapping_mut)
+        .and_then(|configs| configs.remove("include"));
+    Ok(match include {
+        Some(include) => serde_yaml_ng::from_value(include)?,
+        None => Vec::new(),
+    })
+}
+
+/// Layer `over` on top of `base`: mappings merge key by key, lists are
+/// concatenated (base first, duplicates dropped) so team insecure_contexts
+/// guardrails survive user additions, and scalars from `over` win.
+fn layer_yaml(base: serde_yaml_ng::Value, over: serde_yaml_ng::Value) -> serde_yaml_ng::Value {
+    use serde_yaml_ng::Value;
+    match (base, over) {
+        (base, Value::Null) => base,
+        (Value::Mapping(mut base), Value::Mapping(over)) => {
+            for (key, value) in over {
+                let merged = match base.remove(&key) {
+                    Some(existing) => layer_yaml(existing, value),
+                    None => value,
+                };
+                base.insert(key, merged);
+            }
+            Value::Mapping(base)
+        }
+        (Value::Sequence(mut base), Value::Sequence(over)) => {
+            for item in over {
+                if !base.contains(&item) {
+                    base.push(item);
+                }
+            }
+            Value::Sequence(base)
+        }
+        (_, over) => over,
+    }
+}
+
+/// Load the user config file as written, without the team config or
+/// environment overrides. Use this before modifying and saving the config.
+fn load_file() -> Result<K8pkConfig> {
     let path = config_path()?;
 
     if !path.exists() {
@@ -163,33 +634,125 @@ pub fn load() -> Result<K8pkConfig> {
     Ok(config)
 }
 
+/// Env var holding extra aliases (`name=context`, comma-separated)
+pub const ALIASES_ENV: &str = "K8PK_ALIASES";
+
+// `--alias` definitions live in the process, not the environment, so shells
+// and programs k8pk starts don't inherit them
+static CLI_ALIASES: OnceLock<Vec<(String, String)>> = OnceLock::new();
+
+/// Record the global `--alias` definitions for this process; they win over
+/// `K8PK_ALIASES` and the config
+pub fn set_aliases(aliases: Vec<(String, String)>) {
+    let _ = CLI_ALIASES.set(aliases);
+}
+
+/// Parse a single `name=context` alias definition
+pub fn parse_alias(def: &str) -> Result<(String, String)> {
+    match def.split_once('=') {
+        Some((name, ctx)) if !name.trim().is_empty() && !ctx.trim().is_empty() => {
+            Ok((name.trim().to_string(), ctx.trim().to_string()))
+        }
+        _ => Err(K8pkError::InvalidArgument(format!(
+            "invalid alias '{}': expected NAME=CONTEXT",
+            def
+        ))),
+    }
+}
+
+/// Parse a comma-separated alias list; malformed entries are skipped with a warning
+fn parse_alias_list(raw: &str) -> Vec<(String, String)> {
+    raw.split(',')
+        .map(str::trim)
+        .filter(|d| !d.is_empty())
+        .filter_map(|d| match parse_alias(d) {
+            Ok(pair) => Some(pair),
+            Err(e) => {
+                eprintln!("warning: {}: {}", ALIASES_ENV, e);
+                None
+            }
+        })
+        .collect()
+}
+
 /// Alias kept for tests / call sites that previously forced a disk re-read.
 pub fn load_uncached() -> Result<K8pkConfig> {
     load()
 }
 
-/// Resolve a context alias to its full name
+/// Resolve a context alias to its full name (or the pattern it stands for)
 pub fn resolve_alias(ctx: &str) -> String {
-    if let Ok(config) = load() {
-        if let Some(ref aliases) = config.aliases {
-            if let Some(resolved) = aliases.get(ctx) {
-                return resolved.clone();
-            }
-        }
+    match load() {
+        Ok(config) => resolve_alias_with(&config, ctx),
+        Err(_) => ctx.to_string(),
     }
-    ctx.to_string()
 }
 
-/// Check if a context name matches any of the configured insecure_contexts patterns.
-/// Supports simple glob: `*` matches any sequence, `?` matches one char.
-pub fn is_context_insecure(ctx: &str) -> bool {
-    let Ok(config) = load() else {
-        return false;
+/// [`resolve_alias`] against an already loaded config
+pub fn resolve_alias_with(config: &K8pkConfig, ctx: &str) -> String {
+    config
+        .aliases
+        .as_ref()
+        .and_then(|aliases| aliases.get(ctx))
+        .cloned()
+        .unwrap_or_else(|| ctx.to_string())
+}
+
+/// Expand the display placeholders in `template` for `context` (its
+/// `cluster_type` as `detect_cluster_type` reports it); `{namespace}` is
+/// empty without one
+pub fn expand_display_template(
+    config: &K8pkConfig,
+    template: &str,
+    context: &str,
+    cluster_type: &str,
+    namespace: Option<&str>,
+) -> String {
+    let normalize = config.display.as_ref().is_none_or(|d| d.normalize);
+    let friendly = if normalize {
+        kubeconfig::friendly_context_name(context, cluster_type)
+    } else {
+        context.to_string()
     };
-    is_context_insecure_with(&config, ctx)
+    template
+        .replace("{context}", context)
+        .replace("{friendly}", &friendly)
+        .replace("{type}", cluster_type)
+        .replace("{namespace}", namespace.unwrap_or(""))
+}
+
+/// `K8PK_CONTEXT_DISPLAY` for `context`: `display.template` expanded, with
+/// the cluster type detected from the server in its isolated `kubeconfig`
+pub fn display_context(context: &str, namespace: Option<&str>, kubeconfig: &Path) -> String {
+    let config = load().unwrap_or_default();
+    let server_url = fs::read_to_string(kubeconfig)
+        .ok()
+        .and_then(|c| serde_yaml_ng::from_str::<kubeconfig::KubeConfig>(&c).ok())
+        .and_then(|cfg| {
+            cfg.clusters
+                .first()
+                .and_then(|c| kubeconfig::extract_server_url_from_cluster(&c.rest))
+        });
+    let cluster_type = kubeconfig::detect_cluster_type(context, server_url.as_deref());
+    let template = config
+        .display
+        .as_ref()
+        .and_then(|d| d.template.as_deref())
+        .unwrap_or("{friendly}");
+    expand_display_template(&config, template, context, cluster_type, namespace)
+}
+
+/// Whether `k8pk ctx` should restore the last namespace used in a context.
+pub fn remember_namespaces(config: &K8pkConfig) -> bool {
+    config
+        .namespaces
+        .as_ref()
+        .map(|n| n.remember)
+        .unwrap_or(true)
 }
 
-/// Same as [`is_context_insecure`] but uses an already-loaded config (hot path).
+/// Check if a context name matches any of the configured insecure_contexts patterns.
+/// Supports simple glob: `*` matches any sequence, `?` matches one char.
 pub fn is_context_insecure_with(config: &K8pkConfig, ctx: &str) -> bool {
     config
         .insecure_contexts
@@ -197,6 +760,135 @@ pub fn is_context_insecure_with(config: &K8pkConfig, ctx: &str) -> bool {
         .any(|pat| glob_match(pat, ctx))
 }
 
+/// Check if a context name matches any of the configured protected_contexts patterns.
+pub fn is_context_protected_with(config: &K8pkConfig, ctx: &str) -> bool {
+    config
+        .protected_contexts
+        .iter()
+        .any(|pat| glob_match(pat, ctx))
+}
+
+/// CA bundle from `ca_overrides` for `ctx`: the exact name's, else the
+/// longest matching pattern's, with `~` and `$VAR` expanded
+pub fn ca_override_with(config: &K8pkConfig, ctx: &str) -> Option<PathBuf> {
+    let (_, path) = config
+        .ca_overrides
+        .iter()
+        .filter(|(pat, _)| *pat == ctx || glob_match(pat, ctx))
+        .min_by_key(|(pat, _)| (*pat != ctx, std::cmp::Reverse(pat.len()), pat.as_str()))?;
+    Some(kubeconfig::normalize_path(&kubeconfig::expand_env_vars(
+        path,
+    )))
+}
+
+/// `configs.remote_auth` entry for `url`: the exact URL's, else the longest
+/// matching pattern's
+pub fn remote_auth_with<'a>(config: &'a K8pkConfig, url: &str) -> Option<&'a RemoteAuth> {
+    config
+        .configs
+        .remote_auth
+        .iter()
+        .filter(|(pat, _)| *pat == url || glob_match(pat, url))
+        .min_by_key(|(pat, _)| (*pat != url, std::cmp::Reverse(pat.len()), pat.as_str()))
+        .map(|(_, auth)| auth)
+}
+
+/// Settings from `contexts:` that apply to `ctx`. Each field comes from the
+/// exact name if it sets it, otherwise from the longest matching pattern.
+pub fn context_settings_with(config: &K8pkConfig, ctx: &str) -> ContextSettings {
+    let mut matching: Vec<(&String, &ContextSettings)> = config
+        .contexts
+        .iter()
+        .filter(|(pat, _)| *pat == ctx || glob_match(pat, ctx))
+        .collect();
+    matching.sort_by_key(|(pat, _)| (*pat != ctx, std::cmp::Reverse(pat.len())));
+    let mut settings = ContextSettings::default();
+    for (_, s) in matching {
+        settings.cwd = settings.cwd.or_else(|| s.cwd.clone());
+        settings.init_cmd = settings.init_cmd.or_else(|| s.init_cmd.clone());
+        settings.proxy = settings.proxy.or_else(|| s.proxy.clone());
+        settings.motd = settings.motd.or_else(|| s.motd.clone());
+        settings.motd_color = settings.motd_color.or(s.motd_color);
+        for tag in &s.tags {
+            if !settings.tags.contains(tag) {
+                settings.tags.push(tag.clone());
+            }
+        }
+    }
+    settings
+}
+
+/// [`context_settings_with`] for the loaded config
+pub fn context_settings(ctx: &str) -> ContextSettings {
+    load()
+        .map(|c| context_settings_with(&c, ctx))
+        .unwrap_or_default()
+}
+
+/// `exec_path_hints` with `~` and environment variables expanded
+pub fn exec_path_dirs_with(config: &K8pkConfig) -> Vec<PathBuf> {
+    config
+        .exec_path_hints
+        .iter()
+        .map(|d| kubeconfig::normalize_path(&kubeconfig::expand_env_vars(d)))
+        .collect()
+}
+
+/// `path` (a PATH value) with `dirs` prepended, skipping ones it already
+/// lists. None when there is nothing to add.
+pub fn path_with_hints(dirs: &[PathBuf], path: Option<&OsStr>) -> Option<OsString> {
+    let current: Vec<PathBuf> = path
+        .map(std::env::split_paths)
+        .into_iter()
+        .flatten()
+        .collect();
+    let missing: Vec<&PathBuf> = dirs.iter().filter(|d| !current.contains(d)).collect();
+    if missing.is_empty() {
+        return None;
+    }
+    std::env::join_paths(missing.into_iter().chain(current.iter())).ok()
+}
+
+/// PATH for spawned shells and commands: the current one with the loaded
+/// config's `exec_path_hints` prepended. None when it needs no change.
+pub fn hinted_path() -> Option<OsString> {
+    let dirs = exec_path_dirs_with(&load().ok()?);
+    path_with_hints(&dirs, std::env::var_os("PATH").as_deref())
+}
+
+/// Env var that turns on offline mode
+pub const OFFLINE_ENV: &str = "K8PK_OFFLINE";
+
+// `--offline` lives in the process, not the environment, so shells and
+// programs k8pk starts don't inherit it
+static OFFLINE_FLAG: AtomicBool = AtomicBool::new(false);
+
+/// Record the global `--offline` for this process
+pub fn set_offline() {
+    OFFLINE_FLAG.store(true, Ordering::Relaxed);
+}
+
+/// Offline mode is on: `--offline`, `K8PK_OFFLINE` set to anything but empty,
+/// `0`, or `false`, or the config has `offline: true`
+pub fn offline() -> bool {
+    if OFFLINE_FLAG.load(Ordering::Relaxed) {
+        return true;
+    }
+    match std::env::var(OFFLINE_ENV) {
+        Ok(v) => !matches!(v.trim().to_lowercase().as_str(), "" | "0" | "false"),
+        Err(_) => load().is_ok_and(|c| c.offline),
+    }
+}
+
+/// Fail with [`K8pkError::Offline`] when offline mode is on; `what` names the
+/// operation that needs the network
+pub fn require_online(what: &str) -> Result<()> {
+    if offline() {
+        return Err(K8pkError::Offline(what.to_string()));
+    }
+    Ok(())
+}
+
 /// Simple glob matcher via globset (already a dep).
 fn glob_match(pattern: &str, text: &str) -> bool {
     // ponytail: globset `*` is path-segment aware in some modes; Glob::new is fine for our patterns
@@ -211,7 +903,7 @@ fn glob_match(pattern: &str, text: &str) -> bool {
 /// Creates the config file if it does not exist yet. No-ops if the pattern is already present.
 pub fn add_to_insecure_contexts(context: &str) -> Result<()> {
     let path = config_path()?;
-    let mut config = load_uncached()?;
+    let mut config = load_file()?;
 
     let pattern = context.to_string();
     if config.insecure_contexts.contains(&pattern) {
@@ -227,6 +919,114 @@ pub fn add_to_insecure_contexts(context: &str) -> Result<()> {
     Ok(())
 }
 
+/// Add (`disabled`) or drop `path` in `configs.disabled` in the config file
+/// and save it; `entry` is how a new entry is written. Returns false when
+/// nothing changed.
+pub fn set_source_disabled(path: &Path, entry: &str, disabled: bool) -> Result<bool> {
+    let config_file = config_path()?;
+    let mut config = load_file()?;
+    let list = &mut config.configs.disabled;
+    let before = list.len();
+    if disabled {
+        if kubeconfig::is_source_disabled(path, list) {
+            return Ok(false);
+        }
+        list.push(entry.to_string());
+    } else {
+        list.retain(|d| !kubeconfig::is_source_disabled(path, std::slice::from_ref(d)));
+        if list.len() == before {
+            return Ok(false);
+        }
+    }
+
+    if let Some(parent) = config_file.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let yaml = serde_yaml_ng::to_string(&config)?;
+    kubeconfig::write_restricted(&config_file, &yaml)?;
+    Ok(true)
+}
+
+/// Add `alias -> context` entries to the config file and save it.
+/// Creates the config file if needed; existing aliases with the same name are replaced.
+pub fn add_aliases(entries: &[(String, String)]) -> Result<()> {
+    if entries.is_empty() {
+        return Ok(());
+    }
+    let path = config_path()?;
+    let mut config = load_file()?;
+    let aliases = config.aliases.get_or_insert_with(HashMap::new);
+    for (alias, context) in entries {
+        aliases.insert(alias.clone(), context.clone());
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let yaml = serde_yaml_ng::to_string(&config)?;
+    kubeconfig::write_restricted(&path, &yaml)?;
+    Ok(())
+}
+
+/// Remove aliases by name from the config file and save it.
+/// Returns the removed `(alias, context)` pairs; unknown names are ignored.
+pub fn remove_aliases(names: &[String]) -> Result<Vec<(String, String)>> {
+    let path = config_path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let mut config = load_file()?;
+    let Some(aliases) = config.aliases.as_mut() else {
+        return Ok(Vec::new());
+    };
+    let removed: Vec<(String, String)> = names
+        .iter()
+        .filter_map(|name| aliases.remove(name).map(|ctx| (name.clone(), ctx)))
+        .collect();
+    if aliases.is_empty() {
+        config.aliases = None;
+    }
+
+    if !removed.is_empty() {
+        let yaml = serde_yaml_ng::to_string(&config)?;
+        kubeconfig::write_restricted(&path, &yaml)?;
+    }
+    Ok(removed)
+}
+
+/// Aliases as written in the config file (no `K8PK_ALIASES` / `--alias` overlay)
+pub fn file_aliases() -> Result<HashMap<String, String>> {
+    Ok(load_file()?.aliases.unwrap_or_default())
+}
+
+/// Point config aliases at renamed contexts (`old name -> new name`) and save.
+/// Returns the alias names that were (or, with `dry_run`, would be) updated.
+pub fn retarget_aliases(renames: &HashMap<String, String>, dry_run: bool) -> Result<Vec<String>> {
+    let path = config_path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let mut config = load_file()?;
+    let Some(aliases) = config.aliases.as_mut() else {
+        return Ok(Vec::new());
+    };
+
+    let mut updated = Vec::new();
+    for (alias, target) in aliases.iter_mut() {
+        if let Some(new_target) = renames.get(target.as_str()) {
+            *target = new_target.clone();
+            updated.push(alias.clone());
+        }
+    }
+    updated.sort();
+
+    if !updated.is_empty() && !dry_run {
+        let yaml = serde_yaml_ng::to_string(&config)?;
+        kubeconfig::write_restricted(&path, &yaml)?;
+    }
+    Ok(updated)
+}
+
 /// Expand ~ to home directory in path strings
 pub fn expand_home(path: &str) -> PathBuf {
     if let Some(stripped) = path.strip_prefix("~/") {
@@ -259,6 +1059,20 @@ configs:
   exclude:
     - "~/.kube/k8pk.yaml"
 
+  # Files switched off with `k8pk sources disable <path>` (kept on disk,
+  # left out of merging and pickers until `k8pk sources enable <path>`)
+  # disabled:
+  #   - "~/.kube/client-acme.yaml"
+
+  # https:// entries in include are downloaded and cached (re-checked with
+  # ETag after remote_ttl seconds; the cached copy is used when offline)
+  # remote_ttl: 3600
+  # remote_auth:
+  #   "https://kube.example.com/*":
+  #     token_env: KUBE_CONFIG_TOKEN     # or token_cmd: "pass show kube/config-token"
+  #     # header: Authorization           # default
+  #     # value: "Bearer {token}"         # default
+
 # Shell hooks (commands to run when entering/leaving contexts)
 # Eval-based switching (k8pk ctx / k8pk / kpick): stop runs when the *context name*
 # changes, then start runs for the new context. Namespace-only changes do not run hooks.
@@ -282,10 +1096,29 @@ configs:
 #   # When true, shows only clusters (groups contexts by base cluster name)
 #   # instead of showing all namespace-specific contexts
 #   # Useful when you have thousands of namespace contexts
+#   # After the cluster, pick one of its contexts or a live namespace
 #   clusters_only: false
 #   # Trust a successful session check for N seconds (default 300). 0 = always probe.
 #   # Override: K8PK_SESSION_CHECK_TTL / --no-session-check / K8PK_NO_SESSION_CHECK=1
 #   session_check_ttl: 300
+#   # Group the context picker under headers with counts: type (eks, gke, ...),
+#   # file (source kubeconfig), or tag (contexts: tags). In the builtin picker,
+#   # choosing a header collapses or expands its group.
+#   group_by: type
+
+# Interactive picker: "builtin" (default), "fzf", or "sk". External finders get
+# a `k8pk which` preview for contexts; falls back to builtin when not installed.
+# Override: --picker / K8PK_PICKER
+# picker: fzf
+
+# Output colors (doctor, lint, diff, events, dashboard, pickers): "default",
+# "colorblind" (blue/orange instead of green/red), or "mono". NO_COLOR=1 or
+# --no-color turns colors off. Override: K8PK_THEME
+# theme: colorblind
+
+# Language of prompts and messages: "en" or "de". Unset follows LC_ALL,
+# LC_MESSAGES, and LANG. Override: K8PK_LANG
+# language: de
 
 # Insecure contexts (skip TLS verification for matching patterns)
 # Glob patterns: * matches any sequence, ? matches a single character.
@@ -296,19 +1129,131 @@ configs:
 #   - "lab-*"
 #   - "*-poc-*"
 
+# CA bundle overrides (same pattern syntax). Matching contexts verify their
+# server against this file instead of the kubeconfig's certificate-authority,
+# e.g. behind a corporate TLS-intercepting proxy. insecure_contexts wins.
+# ca_overrides:
+#   "corp-*": ~/.config/k8pk/corp-proxy-ca.pem
+
+# Protected contexts (same pattern syntax). k8pk refuses to change the cluster
+# from shortcuts on these, e.g. `k8pk ns NAME --create` will not create the
+# namespace; use kubectl directly when you mean it.
+# protected_contexts:
+#   - "prod-*"
+
+# Per-context shell settings, keyed by context name or glob pattern (an exact
+# name wins, then the longest pattern). Applied to spawned shells and new tmux
+# windows/sessions. {context} and {namespace} are replaced; cwd expands ~ and $VAR.
+# contexts:
+#   "prod-*":
+#     cwd: ~/src/infra/terraform/{context}
+#     init_cmd: kubectl get nodes
+#     tags: [prod]          # select with e.g. `k8pk can-i get secrets --tag prod`
+#     proxy: socks5://localhost:1080   # proxy-url in the isolated kubeconfig + HTTPS_PROXY
+#     motd: "PROD: change freeze until Friday"   # banner on switching in (--no-motd hides it)
+#     motd_color: red     # red, yellow (default), green, blue, magenta, cyan
+
+# Login profiles: `k8pk login <name>` logs in to the server with these settings
+# without asking (flags on the command line win), and `k8pk login --list`
+# shows them. Keys are the `k8pk login` flags with underscores: type, auth,
+# username, certificate_authority, insecure_skip_tls_verify, client_certificate,
+# client_key, output_dir, name, pass_entry / op_item / bw_item, use_vault, exec_preset, exec_cluster,
+# exec_server_id, exec_region, gssapi_token_url, rancher_auth_provider.
+# name is a template ({profile}, {host}, {user}); it defaults to the profile name.
+# clusters:
+#   prod:
+#     server: https://api.prod.example.com:6443
+#     type: ocp
+#     pass_entry: k8pk/prod
+#   lab:
+#     server: https://10.0.0.5:6443
+#     type: k8s
+#     auth: client-cert
+#     certificate_authority: ~/.kube/certs/lab-ca.pem
+#     client_certificate: ~/.kube/certs/lab.crt
+#     client_key: ~/.kube/certs/lab.key
+#     output_dir: ~/.kube/lab
+#     name: "lab-{host}"
+
+# Extra directories for exec credential plugins (kubelogin, aws, gke-gcloud-auth-plugin, ...)
+# referenced by kubeconfig users. Prepended to PATH in spawned shells, tmux windows,
+# and `k8pk exec`; `k8pk lint` and `k8pk doctor` look there too. ~ and $VAR expand.
+# exec_path_hints:
+#   - ~/.krew/bin
+#   - /opt/homebrew/bin
+
+# Never contact clusters or other servers (air-gapped machines). Namespace lists
+# and cluster facts come from cache, session checks are skipped, and commands
+# that can't work without the network (login, discover, update) fail fast.
+# Same as --offline or K8PK_OFFLINE=1.
+# offline: true
+
+# Where isolated per-context kubeconfigs are written:
+#   persistent (default)  ~/.local/share/k8pk
+#   runtime               $XDG_RUNTIME_DIR/k8pk (tmpfs, wiped on reboot); falls
+#                         back to persistent when XDG_RUNTIME_DIR is not set
+# session_storage: runtime
+
+# Inline certificate-authority / client-certificate / client-key files into
+# isolated kubeconfigs as base64 *-data fields
+# embed_certs: true
+
+# Sessions expire this many seconds after their kubeconfig is generated
+# (compliance limits on credential lifetime). `k8pk info expiry` shows the time
+# left, the prompt gets an ":expired" marker, and commands in the shell fail
+# (kubectl included) until `k8pk refresh` (or a new `k8pk ctx`) renews it.
+# session_ttl: 28800   # 8 hours
+
+# Encryption at rest (requires age: https://age-encryption.org)
+# Isolated kubeconfigs in ~/.local/share/k8pk are stored age-encrypted; shells
+# get a plaintext copy in $XDG_RUNTIME_DIR/k8pk that is deleted once no live
+# session uses it. Override: K8PK_ENCRYPT=1|0
+# encryption:
+#   enabled: true
+#   identity: "~/.config/k8pk/age-identity.txt"  # created on first use
+#   recipient: "age1..."                         # default: the identity's public key
+
 # Tmux integration (auto-detected when inside tmux)
 # When inside tmux, k8pk creates/switches tmux windows or sessions
 # instead of spawning nested subshells.
 # tmux:
 #   mode: windows           # "windows" (default) or "sessions"
-#   name_template: "{context}"  # naming for tmux windows/sessions
+#   name_template: "{context}"  # naming for tmux windows/sessions (placeholders as in display)
+
+# Context display (prompt, tmux K8PK_CONTEXT_DISPLAY, `k8pk info ctx --display`)
+# Placeholders: {context} (raw name), {friendly} (shortened EKS/GKE/AKS/...
+# name), {type} (eks, gke, ocp, ...), {namespace} (empty without one)
+# display:
+#   normalize: true         # false = {friendly} keeps the raw context name
+#   template: "{friendly}"
 
 # Shell spawning (outside tmux)
 # By default, switching context inside a k8pk shell stays flat (no endless
 # nested subshells). For zero new shells, use the kctx/kpick functions (they
 # switch in place) or tmux. Enable nested to opt into kubie-style recursion.
+# With spawn_mode: wrap, k8pk waits for the spawned shell instead of replacing
+# itself with it, so on exit it runs hooks.stop_ctx, records the session's end for
+# `k8pk stats`, tells you which shell you are back in, and (at most daily) offers
+# to remove stale isolated kubeconfigs.
 # shell:
 #   nested: false           # true = allow recursive nested subshells
+#   spawn_mode: exec        # "exec" (default) or "wrap"
+
+# Namespace memory
+# k8pk remembers the last namespace used in each context and restores it on
+# `k8pk ctx <name>` (unless -n is given). `k8pk ns --forget` clears it for the
+# current context.
+# namespaces:
+#   remember: true          # false = always start in the kubeconfig's namespace
+
+# Team kubeconfigs from git
+# `k8pk sync` clones or updates this repository in ~/.kube/managed (shallow),
+# lints it, and lists the contexts it added or removed. Files there are merged
+# like any other kubeconfig.
+# sync:
+#   repo: git@github.com:acme/kubeconfigs.git
+#   branch: main            # default: the repository's default branch
+#   paths: [platform]       # sparse checkout of these directories only
 "#
     .to_string()
 }
@@ -380,6 +1325,109 @@ mod tests {
         assert_eq!(path, xdg_config);
     }
 
+    #[test]
+    fn test_parse_alias() {
+        assert_eq!(
+            parse_alias("p=prod-cluster").unwrap(),
+            ("p".to_string(), "prod-cluster".to_string())
+        );
+        // Context names may themselves contain '='
+        assert_eq!(parse_alias("x=a=b").unwrap().1, "a=b");
+        assert!(parse_alias("novalue").is_err());
+        assert!(parse_alias("=ctx").is_err());
+        assert!(parse_alias("p=").is_err());
+    }
+
+    #[test]
+    fn test_env_aliases_override_config() {
+        let _lock = ENV_MUTEX.lock().unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let xdg_dir = dir.path().join("k8pk");
+        std::fs::create_dir_all(&xdg_dir).unwrap();
+        std::fs::write(
+            xdg_dir.join("config.yaml"),
+            "aliases:\n  p: from-file\n  d: dev-file\n",
+        )
+        .unwrap();
+
+        std::env::set_var("XDG_CONFIG_HOME", dir.path());
+        std::env::set_var(ALIASES_ENV, "p=from-env, s=staging ,bogus");
+        let config = load();
+        let raw = load_file();
+        std::env::remove_var(ALIASES_ENV);
+        std::env::remove_var("XDG_CONFIG_HOME");
+
+        let aliases = config.unwrap().aliases.unwrap();
+        assert_eq!(aliases.get("p").map(String::as_str), Some("from-env"));
+        assert_eq!(aliases.get("d").map(String::as_str), Some("dev-file"));
+        assert_eq!(aliases.get("s").map(String::as_str), Some("staging"));
+        assert!(!aliases.contains_key("bogus"));
+
+        let raw = raw.unwrap().aliases.unwrap();
+        assert_eq!(raw.get("p").map(String::as_str), Some("from-file"));
+    }
+
+    #[test]
+    fn test_team_config_layered_under_user() {
+        let _lock = ENV_MUTEX.lock().unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let xdg_dir = dir.path().join("k8pk");
+        std::fs::create_dir_all(&xdg_dir).unwrap();
+        std::fs::write(
+            xdg_dir.join("config.yaml"),
+            "aliases:\n  p: my-prod\n  me: mine\ninsecure_contexts:\n  - \"kind-*\"\n",
+        )
+        .unwrap();
+        let team = dir.path().join("team.yaml");
+        std::fs::write(
+            &team,
+            "configs:\n  include:\n    - /srv/kube/*.yaml\naliases:\n  p: team-prod\n  s: staging\ninsecure_contexts:\n  - \"lab-*\"\ntmux:\n  mode: sessions\n",
+        )
+        .unwrap();
+
+        std::env::set_var("XDG_CONFIG_HOME", dir.path());
+        std::env::set_var(TEAM_CONFIG_ENV, &team);
+        let config = load();
+        let raw = load_file();
+        std::fs::write(
+            xdg_dir.join("config.yaml"),
+            "configs:\n  include:\n    - ~/work/*.yaml\n    - /srv/kube/*.yaml\n",
+        )
+        .unwrap();
+        let own_include = load();
+        std::env::set_var(TEAM_CONFIG_ENV, "");
+        let without_team = load();
+        std::env::remove_var(TEAM_CONFIG_ENV);
+        std::env::remove_var("XDG_CONFIG_HOME");
+
+        let config = config.unwrap();
+        let aliases = config.aliases.unwrap();
+        assert_eq!(aliases.get("p").map(String::as_str), Some("my-prod"));
+        assert_eq!(aliases.get("s").map(String::as_str), Some("staging"));
+        assert_eq!(aliases.get("me").map(String::as_str), Some("mine"));
+        assert_eq!(config.insecure_contexts, vec!["lab-*", "kind-*"]);
+        // The user set no include patterns: the defaults, then the team's
+        let mut include = default_include_patterns();
+        include.push("/srv/kube/*.yaml".to_string());
+        assert_eq!(config.configs.include, include);
+        // The user's own patterns come first
+        assert_eq!(
+            own_include.unwrap().configs.include,
+            ["~/work/*.yaml", "/srv/kube/*.yaml"]
+        );
+        assert_eq!(config.tmux.unwrap().mode, "sessions");
+
+        assert!(raw.unwrap().tmux.is_none());
+        let without_team = without_team.unwrap();
+        assert!(without_team.tmux.is_none());
+        assert_eq!(
+            without_team.configs.include,
+            ["~/work/*.yaml", "/srv/kube/*.yaml"]
+        );
+    }
+
     #[test]
     fn test_resolve_alias_passthrough() {
         // When no alias matches, should return the input unchanged
@@ -387,6 +1435,39 @@ mod tests {
         assert_eq!(result, "some-context-that-has-no-alias");
     }
 
+    #[test]
+    fn test_display_template() {
+        let arn = "arn:aws:eks:us-east-1:123:cluster/prod";
+        let mut config = K8pkConfig::default();
+        let expand = |config: &K8pkConfig, template| {
+            expand_display_template(config, template, arn, "eks", Some("web"))
+        };
+        assert_eq!(expand(&config, "{friendly}"), "prod");
+        assert_eq!(
+            expand(&config, "{type}:{friendly}/{namespace}"),
+            "eks:prod/web"
+        );
+        config.display = Some(DisplaySection {
+            normalize: false,
+            template: None,
+        });
+        assert_eq!(expand(&config, "{friendly}"), arn);
+        assert_eq!(expand(&config, "{context}"), arn);
+    }
+
+    #[test]
+    fn test_ca_override_most_specific() {
+        let config: K8pkConfig = serde_yaml_ng::from_str(
+            "ca_overrides:\n  \"*\": /etc/corp.pem\n  \"prod-*\": /etc/prod.pem\n  prod-eu: /etc/eu.pem\n",
+        )
+        .unwrap();
+        let ca = |ctx| ca_override_with(&config, ctx);
+        assert_eq!(ca("prod-eu"), Some(PathBuf::from("/etc/eu.pem")));
+        assert_eq!(ca("prod-us"), Some(PathBuf::from("/etc/prod.pem")));
+        assert_eq!(ca("dev"), Some(PathBuf::from("/etc/corp.pem")));
+        assert_eq!(ca_override_with(&K8pkConfig::default(), "dev"), None);
+    }
+
     #[test]
     fn test_default_config_includes() {
         let config = K8pkConfig::default();
@@ -441,6 +1522,16 @@ mod tests {
         assert!(tpl.contains("hooks:"));
         assert!(tpl.contains("tmux:"));
         assert!(tpl.contains("shell:"));
+        assert!(tpl.contains("namespaces:"));
+    }
+
+    #[test]
+    fn test_remember_namespaces_default_and_opt_out() {
+        assert!(remember_namespaces(&K8pkConfig::default()));
+        let cfg: K8pkConfig = serde_yaml_ng::from_str("namespaces:\n  remember: false\n").unwrap();
+        assert!(!remember_namespaces(&cfg));
+        let cfg: K8pkConfig = serde_yaml_ng::from_str("namespaces: {}\n").unwrap();
+        assert!(remember_namespaces(&cfg));
     }
 
     #[test]
@@ -498,6 +1589,99 @@ mod tests {
             .any(|p| glob_match(p, "gke-us")));
     }
 
+    #[test]
+    fn test_is_context_protected() {
+        let config: K8pkConfig =
+            serde_yaml_ng::from_str("protected_contexts:\n  - \"prod-*\"\n").unwrap();
+        assert!(is_context_protected_with(&config, "prod-eu"));
+        assert!(!is_context_protected_with(&config, "dev-eu"));
+        assert!(!is_context_protected_with(
+            &K8pkConfig::default(),
+            "prod-eu"
+        ));
+    }
+
+    #[test]
+    fn test_context_settings_precedence() {
+        let config: K8pkConfig = serde_yaml_ng::from_str(
+            "contexts:\n  \"*\":\n    init_cmd: kubectl get nodes\n  \"prod-*\":\n    cwd: /srv/{context}/{namespace}\n  prod-eu:\n    init_cmd: echo eu\n",
+        )
+        .unwrap();
+        let eu = context_settings_with(&config, "prod-eu");
+        assert_eq!(eu.init_cmd.as_deref(), Some("echo eu"));
+        assert_eq!(
+            eu.start_dir("prod-eu", None),
+            Some(PathBuf::from("/srv/prod-eu/default"))
+        );
+        let us = context_settings_with(&config, "prod-us");
+        assert_eq!(
+            us.init_command("prod-us", Some("web")).as_deref(),
+            Some("kubectl get nodes")
+        );
+        assert_eq!(
+            us.start_dir("prod-us", Some("web")),
+            Some(PathBuf::from("/srv/prod-us/web"))
+        );
+        assert_eq!(context_settings_with(&config, "dev").cwd, None);
+    }
+
+    #[test]
+    fn test_context_proxy() {
+        let config: K8pkConfig = serde_yaml_ng::from_str(
+            "contexts:\n  \"bastion-*\":\n    proxy: socks5://localhost:1080\n  bastion-old:\n    proxy: localhost:3128\n",
+        )
+        .unwrap();
+        assert_eq!(
+            context_settings_with(&config, "bastion-eu")
+                .proxy_url()
+                .unwrap(),
+            Some("socks5://localhost:1080")
+        );
+        assert!(context_settings_with(&config, "bastion-old")
+            .proxy_url()
+            .is_err());
+        assert_eq!(
+            context_settings_with(&config, "dev").proxy_url().unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_context_tags_accumulate() {
+        let config: K8pkConfig = serde_yaml_ng::from_str(
+            "contexts:\n  \"*\":\n    tags: [all]\n  \"prod-*\":\n    tags: [prod, all]\n",
+        )
+        .unwrap();
+        assert_eq!(
+            context_settings_with(&config, "prod-eu").tags,
+            ["prod", "all"]
+        );
+        assert_eq!(context_settings_with(&config, "dev").tags, ["all"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_exec_path_hints_prepend_missing_dirs() {
+        let config: K8pkConfig = serde_yaml_ng::from_str(
+            "exec_path_hints: [/opt/plugins, /usr/bin]
+",
+        )
+        .unwrap();
+        let dirs = exec_path_dirs_with(&config);
+        assert_eq!(
+            path_with_hints(&dirs, Some(OsStr::new("/usr/bin:/bin"))),
+            Some(OsString::from("/opt/plugins:/usr/bin:/bin"))
+        );
+        assert_eq!(
+            path_with_hints(&dirs, Some(OsStr::new("/opt/plugins:/usr/bin"))),
+            None
+        );
+        assert_eq!(
+            path_with_hints(&dirs[..1], None),
+            Some(OsString::from("/opt/plugins"))
+        );
+    }
+
     #[test]
     fn test_init_config_and_insecure_roundtrip() {
         let _lock = ENV_MUTEX.lock().unwrap();
@@ -0,0 +1,312 @@
+//! Encryption at rest for isolated kubeconfigs (`encryption: enabled: true`).
+//!
+//! The copy under `~/.local/share/k8pk` is age-encrypted (`<name>.yaml.age`);
+//! sessions get a plaintext copy in `$XDG_RUNTIME_DIR/k8pk` (tmpfs on most
+//! Linux systems), which is deleted once no live session points at it. Uses
+//! the `age` / `age-keygen` binaries.
+
+use crate::config::{self, K8pkConfig};
+use crate::error::{K8pkError, Result};
+use crate::kubeconfig;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, SystemTime};
+
+/// Env override for `encryption.enabled` ("1"/"true" or "0"/"false")
+pub const ENCRYPT_ENV: &str = "K8PK_ENCRYPT";
+
+/// Suffix of encrypted isolated kubeconfigs
+pub const SEALED_SUFFIX: &str = ".age";
+
+/// Live copies younger than this are kept by [`sweep_live_copies`], so a copy
+/// written for a shell that has not registered yet is not pulled from under it
+const LIVE_GRACE: Duration = Duration::from_secs(60);
+
+/// Whether isolated kubeconfigs should be encrypted at rest
+pub fn enabled(config: Option<&K8pkConfig>) -> bool {
+    enabled_with(std::env::var(ENCRYPT_ENV).ok().as_deref(), config)
+}
+
+/// [`enabled`] with the value of `K8PK_ENCRYPT` given, which wins over the config
+fn enabled_with(env: Option<&str>, config: Option<&K8pkConfig>) -> bool {
+    if let Some(v) = env {
+        return matches!(v.trim().to_lowercase().as_str(), "1" | "true" | "yes");
+    }
+    let from = |c: &K8pkConfig| c.encryption.as_ref().is_some_and(|e| e.enabled);
+    match config {
+        Some(c) => from(c),
+        None => config::load().map(|c| from(&c)).unwrap_or(false),
+    }
+}
+
+fn tool(name: &str) -> Result<PathBuf> {
+    which::which(name).map_err(|_| K8pkError::EncryptionToolNotFound(name.to_string()))
+}
+
+/// Identity file: `encryption.identity`, else `~/.config/k8pk/age-identity.txt`
+fn identity_path(config: &K8pkConfig) -> Result<PathBuf> {
+    if let Some(path) = config
+        .encryption
+        .as_ref()
+        .and_then(|e| e.identity.as_deref())
+    {
+        return Ok(config::expand_home(path));
+    }
+    let home = dirs_next::home_dir().ok_or(K8pkError::NoHomeDir)?;
+    Ok(home.join(".config/k8pk/age-identity.txt"))
+}
+
+/// Recipient to encrypt to: `encryption.recipient`, else the public key of the
+/// identity (generated with `age-keygen` on first use)
+fn recipient(config: &K8pkConfig) -> Result<String> {
+    if let Some(r) = config.encryption.as_ref().and_then(|e| e.recipient.clone()) {
+        return Ok(r);
+    }
+    let identity = identity_path(config)?;
+    let keygen = tool("age-keygen")?;
+    if !identity.exists() {
+        if let Some(dir) = identity.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let out = Command::new(&keygen).output()?;
+        if !out.status.success() {
+            return Err(K8pkError::CommandFailed(format!(
+                "age-keygen: {}",
+                String::from_utf8_lossy(&out.stderr).trim()
+            )));
+        }
+        kubeconfig::write_restricted(&identity, &String::from_utf8_lossy(&out.stdout))?;
+        eprintln!("Created age identity at {}", identity.display());
+    }
+    let out = Command::new(&keygen).arg("-y").arg(&identity).output()?;
+    if !out.status.success() {
+        return Err(K8pkError::CommandFailed(format!(
+            "age-keygen -y {}: {}",
+            identity.display(),
+            String::from_utf8_lossy(&out.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&out.stdout).trim().to_string())
+}
+
+/// Encrypt `plaintext` (ASCII-armored) into `out` with owner-only permissions
+fn encrypt(config: &K8pkConfig, plaintext: &str, out: &Path) -> Result<()> {
+    let recipient = recipient(config)?;
+    let mut child = Command::new(tool("age")?)
+        .args(["--encrypt", "--armor", "--recipient", &recipient])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(plaintext.as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(K8pkError::CommandFailed(format!(
+            "age --encrypt: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    kubeconfig::write_restricted(out, &String::from_utf8_lossy(&output.stdout))
+}
+
+/// Decrypt `path` with the identity; age prompts on the terminal itself for
+/// passphrase-protected identities
+fn decrypt(config: &K8pkConfig, path: &Path) -> Result<String> {
+    let output = Command::new(tool("age")?)
+        .arg("--decrypt")
+        .arg("--identity")
+        .arg(identity_path(config)?)
+        .arg(path)
+        .stderr(Stdio::piped())
+        .output()?;
+    if !output.status.success() {
+        return Err(K8pkError::CommandFailed(format!(
+            "age --decrypt {}: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Where live plaintext copies go: `$XDG_RUNTIME_DIR/k8pk`, else a per-user
+/// directory under the system temp dir
+fn runtime_dir_path() -> PathBuf {
+    crate::storage::runtime_dir_path().unwrap_or_else(|| {
+        #[cfg(unix)]
+        let user = unsafe { libc::getuid() }.to_string();
+        #[cfg(not(unix))]
+        let user = std::env::var("USERNAME").unwrap_or_default();
+        std::env::temp_dir().join(format!("k8pk-{}", user))
+    })
+}
+
+/// Directory for plaintext copies that must not reach persistent storage
+/// (live copies, and isolated kubeconfigs from SOPS-encrypted sources)
+pub fn runtime_dir() -> Result<PathBuf> {
+    let dir = runtime_dir_path();
+    crate::storage::create_private_dir(&dir)?;
+    Ok(dir)
+}
+
+/// Store an isolated kubeconfig encrypted as `<base>/<filename>.age` and
+/// return the live plaintext copy for the session. Nothing is re-encrypted
+/// when the live or the encrypted copy already holds `yaml`; a plaintext copy
+/// left in `base` from before encryption was enabled is removed.
+pub fn store_isolated(
+    config: &K8pkConfig,
+    base: &Path,
+    filename: &str,
+    yaml: &str,
+) -> Result<PathBuf> {
+    let live = runtime_dir()?.join(filename);
+    let sealed = base.join(format!("{}{}", filename, SEALED_SUFFIX));
+    let legacy = base.join(filename);
+    if legacy.exists() {
+        fs::remove_file(&legacy)?;
+    }
+
+    if sealed.exists() {
+        if fs::read_to_string(&live).is_ok_and(|c| c == yaml) {
+            return Ok(live);
+        }
+        if decrypt(config, &sealed).is_ok_and(|c| c == yaml) {
+            kubeconfig::write_restricted(&live, yaml)?;
+            return Ok(live);
+        }
+    }
+    encrypt(config, yaml, &sealed)?;
+    kubeconfig::write_restricted(&live, yaml)?;
+    Ok(live)
+}
+
+/// Decrypted content of a SOPS-encrypted kubeconfig, via `sops --decrypt`
+/// (keys from the usual SOPS_AGE_KEY_FILE, gpg, or cloud KMS setup). Only
+/// ever held in memory.
+pub fn sops_decrypt(path: &Path) -> Result<String> {
+    let sops = which::which("sops").map_err(|_| {
+        K8pkError::CommandFailed("'sops' not found on PATH (needed for *.sops.yaml)".into())
+    })?;
+    let output = Command::new(sops)
+        .args(["--decrypt", "--input-type", "yaml", "--output-type", "yaml"])
+        .arg(path)
+        .stdin(Stdio::null())
+        .output()?;
+    if !output.status.success() {
+        return Err(K8pkError::CommandFailed(format!(
+            "sops --decrypt: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Files in `dir` not in `in_use` and not modified within the grace period
+fn unused_copies(dir: &Path, in_use: &[PathBuf], now: SystemTime) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.is_file() && !in_use.contains(p))
+        .filter(|p| {
+            fs::metadata(p)
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|t| now.duration_since(t).ok())
+                .is_some_and(|age| age >= LIVE_GRACE)
+        })
+        .collect()
+}
+
+/// Delete live plaintext copies that no live session uses. Returns the paths
+/// removed (or that would be, with `dry_run`).
+pub fn sweep_live_copies(dry_run: bool) -> Result<Vec<PathBuf>> {
+    let dir = runtime_dir_path();
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let in_use: Vec<PathBuf> = crate::commands::sessions::list_active()?
+        .into_iter()
+        .map(|s| PathBuf::from(s.kubeconfig))
+        .collect();
+    let unused = unused_copies(&dir, &in_use, SystemTime::now());
+    if !dry_run {
+        for path in &unused {
+            if let Err(e) = fs::remove_file(path) {
+                eprintln!("warning: failed to remove {}: {}", path.display(), e);
+            }
+        }
+    }
+    Ok(unused)
+}
+
+/// Delete the live copy at `kubeconfig` once its session has ended, unless
+/// another live session still uses it. Paths outside the runtime directory
+/// (unencrypted kubeconfigs) are left alone.
+pub fn release_live_copy(kubeconfig: &Path) -> Result<()> {
+    if kubeconfig.parent() != Some(runtime_dir_path().as_path()) || !kubeconfig.exists() {
+        return Ok(());
+    }
+    let in_use = crate::commands::sessions::list_active()?
+        .iter()
+        .any(|s| Path::new(&s.kubeconfig) == kubeconfig);
+    if !in_use {
+        fs::remove_file(kubeconfig)?;
+    }
+    Ok(())
+}
+
+/// Live copies of the isolated kubeconfigs for `context` (any namespace)
+pub fn live_copies_for(context: &str) -> Vec<PathBuf> {
+    let prefix = kubeconfig::sanitize_filename(context);
+    let Ok(entries) = fs::read_dir(runtime_dir_path()) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| {
+            p.file_name()
+                .map(|n| n.to_string_lossy().starts_with(&prefix))
+                .unwrap_or(false)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unused_copies_skip_in_use_and_fresh() {
+        let dir = tempfile::tempdir().unwrap();
+        let used = dir.path().join("dev.yaml");
+        let idle = dir.path().join("prod.yaml");
+        fs::write(&used, "a").unwrap();
+        fs::write(&idle, "b").unwrap();
+
+        let now = SystemTime::now();
+        assert!(unused_copies(dir.path(), std::slice::from_ref(&used), now).is_empty());
+        let later = now + LIVE_GRACE + Duration::from_secs(1);
+        assert_eq!(unused_copies(dir.path(), &[used], later), vec![idle]);
+    }
+
+    #[test]
+    fn enabled_env_overrides_config() {
+        let mut config = K8pkConfig::default();
+        config.encryption = Some(config::EncryptionSection {
+            enabled: true,
+            ..Default::default()
+        });
+        assert!(enabled_with(None, Some(&config)));
+        assert!(!enabled_with(None, Some(&K8pkConfig::default())));
+        assert!(!enabled_with(Some("0"), Some(&config)));
+        assert!(enabled_with(Some(" True"), Some(&K8pkConfig::default())));
+    }
+}
@@ -15,6 +15,12 @@ pub enum K8pkError {
         suggestions: String,
     },
 
+    #[error("'{pattern}' matches several contexts:\n{matches}\n\n  Pass an exact name or a narrower pattern")]
+    AmbiguousContext { pattern: String, matches: String },
+
+    #[error("alias '{0}' not found\n\n  Run 'k8pk alias list' to see configured aliases")]
+    AliasNotFound(String),
+
     #[error("cluster '{0}' not found in kubeconfig\n\n  The context may reference a deleted cluster. Run 'k8pk lint' to check")]
     ClusterNotFound(String),
 
@@ -62,24 +68,47 @@ pub enum K8pkError {
     )]
     NoPreviousNamespace,
 
+    #[error("no context {steps} step(s) {direction} in this shell's switch history\n\n  See where you have been with:\n    k8pk history --shell")]
+    NoHistoryEntry {
+        steps: usize,
+        direction: &'static str,
+    },
+
     #[error("interactive selection requires a TTY\n\n  This command needs an interactive terminal.\n  For scripts, specify values directly:\n    k8pk ctx <context> -n <namespace>")]
     NoTty,
 
     #[error("selection cancelled")]
     Cancelled,
 
+    #[error("input required: {0}\n\n  Prompts are disabled (--no-input or K8PK_NO_INPUT).\n  Pass the value with a flag instead; see --help.")]
+    InputRequired(String),
+
     #[error("cannot resolve home directory\n\n  HOME environment variable may not be set")]
     NoHomeDir,
 
+    #[error("'{0}' not found on PATH, but kubeconfig encryption is enabled\n\n  Install age (https://age-encryption.org), or turn it off:\n    encryption: {{ enabled: false }} in the k8pk config, or K8PK_ENCRYPT=0")]
+    EncryptionToolNotFound(String),
+
+    #[error("nothing to undo\n\n  k8pk backs up kubeconfigs before rm, rename-context, prune, and\n  organize --remove-from-source rewrite them; none are left")]
+    NothingToUndo,
+
     #[error("command failed: {0}")]
     CommandFailed(String),
 
     #[error("session expired for '{0}'\n\n  Re-authenticate interactively:\n    k8pk ctx {0}\n\n  Or login directly:\n    k8pk login")]
     SessionExpired(String),
 
+    #[error("session for '{context}' expired {ago} ago (session_ttl)\n\n  Renew it in this shell:\n    k8pk refresh")]
+    SessionTtlExpired { context: String, ago: String },
+
     #[error("TLS certificate error for '{context}'\n\n  The cluster uses an untrusted certificate.\n  {hint}")]
     TlsCertificateError { context: String, hint: String },
 
+    #[error(
+        "cluster unreachable: {0}\n\n  Check the server URL, VPN, and proxy settings, then retry"
+    )]
+    ClusterUnreachable(String),
+
     #[error("unknown output format: '{0}'\n\n  Valid formats: env, json, spawn")]
     UnknownOutputFormat(String),
 
@@ -95,6 +124,15 @@ pub enum K8pkError {
     #[error("lint failed\n\n  Run 'k8pk lint' for details")]
     LintFailed,
 
+    #[error("{0} context(s) outside the supported client version skew\n\n  kubectl is supported within one minor version of the API server;\n  install a matching client, or see 'k8pk version-skew'")]
+    VersionSkew(usize),
+
+    #[error("{0} needs network access, but offline mode is on\n\n  Drop --offline, unset K8PK_OFFLINE, or remove `offline: true` from the k8pk config")]
+    Offline(String),
+
+    #[error("k8pk was installed with {manager}; upgrade it there instead:\n    {command}\n\n  Or overwrite the binary anyway: k8pk update --force-binary")]
+    ManagedInstall { manager: String, command: String },
+
     #[error("HTTP request failed: {0}")]
     HttpError(String),
 
@@ -111,6 +149,58 @@ pub enum K8pkError {
     Other(String),
 }
 
+/// Process exit codes, one per failure kind, so wrapper scripts can branch
+/// on why k8pk failed. Listed under "Exit codes" in `k8pk --help`.
+pub mod exit_code {
+    /// Any failure without a more specific code
+    pub const FAILURE: i32 = 1;
+    /// Invalid arguments or missing input (also used by clap for usage errors)
+    pub const USAGE: i32 = 2;
+    /// Context, cluster, user, kubeconfig, or history entry not found
+    pub const NOT_FOUND: i32 = 3;
+    /// Authentication failed or the session expired
+    pub const AUTH: i32 = 4;
+    /// Cluster or API server could not be reached (network, timeout, TLS)
+    pub const UNREACHABLE: i32 = 5;
+    /// Prompt cancelled by the user (128 + SIGINT)
+    pub const CANCELLED: i32 = 130;
+}
+
+impl K8pkError {
+    /// Exit code for this error; see [`exit_code`]
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            K8pkError::InvalidArgument(_)
+            | K8pkError::UnknownOutputFormat(_)
+            | K8pkError::UnsupportedShell(_)
+            | K8pkError::AmbiguousContext { .. }
+            | K8pkError::InputRequired(_)
+            | K8pkError::NoTty => exit_code::USAGE,
+            K8pkError::ContextNotFound(_)
+            | K8pkError::ContextNotFoundSuggestions { .. }
+            | K8pkError::AliasNotFound(_)
+            | K8pkError::ClusterNotFound(_)
+            | K8pkError::UserNotFound(_)
+            | K8pkError::NoContexts
+            | K8pkError::NoNamespaces(_)
+            | K8pkError::KubeconfigNotFound(_)
+            | K8pkError::NotInContext
+            | K8pkError::NoPreviousContext
+            | K8pkError::NoPreviousNamespace
+            | K8pkError::NoHistoryEntry { .. }
+            | K8pkError::NothingToUndo => exit_code::NOT_FOUND,
+            K8pkError::LoginFailed(_)
+            | K8pkError::SessionExpired(_)
+            | K8pkError::SessionTtlExpired { .. } => exit_code::AUTH,
+            K8pkError::ClusterUnreachable(_)
+            | K8pkError::TlsCertificateError { .. }
+            | K8pkError::Offline(_) => exit_code::UNREACHABLE,
+            K8pkError::Cancelled => exit_code::CANCELLED,
+            _ => exit_code::FAILURE,
+        }
+    }
+}
+
 /// Compute edit distance between two strings (Levenshtein) over Unicode chars.
 /// Used for "did you mean?" suggestions.
 pub fn edit_distance(a: &str, b: &str) -> usize {
@@ -295,6 +385,35 @@ mod tests {
         assert!(msg.contains("prod") && msg.contains("use --insecure"));
     }
 
+    #[test]
+    fn test_exit_codes_by_kind() {
+        assert_eq!(
+            K8pkError::ContextNotFound("x".into()).exit_code(),
+            exit_code::NOT_FOUND
+        );
+        assert_eq!(
+            K8pkError::SessionExpired("x".into()).exit_code(),
+            exit_code::AUTH
+        );
+        assert_eq!(
+            K8pkError::ClusterUnreachable("x".into()).exit_code(),
+            exit_code::UNREACHABLE
+        );
+        assert_eq!(
+            K8pkError::Offline("login".into()).exit_code(),
+            exit_code::UNREACHABLE
+        );
+        assert_eq!(
+            K8pkError::InvalidArgument("x".into()).exit_code(),
+            exit_code::USAGE
+        );
+        assert_eq!(K8pkError::Cancelled.exit_code(), 130);
+        assert_eq!(
+            K8pkError::CommandFailed("x".into()).exit_code(),
+            exit_code::FAILURE
+        );
+    }
+
     #[test]
     fn test_no_contexts_suggests_login() {
         let err = K8pkError::NoContexts;
@@ -0,0 +1,148 @@
+//! Translated prompts and messages. The text lives in Fluent catalogs under
+//! `locales/` (compiled into the binary); [`tr`] looks an id up in the
+//! user's language and falls back to English, then to the id itself. The
+//! language comes from `K8PK_LANG`, the config `language:`, then `LC_ALL`,
+//! `LC_MESSAGES`, and `LANG`, as gettext does.
+
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource};
+use std::sync::OnceLock;
+use unic_langid::LanguageIdentifier;
+
+/// Env var holding the language (overrides the config `language:`)
+pub const LANG_ENV: &str = "K8PK_LANG";
+
+// The config `language:` lives in the process, not the environment, so
+// shells and programs k8pk starts don't inherit it
+static CONFIG_LANG: OnceLock<String> = OnceLock::new();
+
+/// Record the config `language:` for this process; `K8PK_LANG` still wins
+pub fn set_config_language(lang: &str) {
+    let _ = CONFIG_LANG.set(lang.to_string());
+}
+
+/// Built-in catalogs; the first is the fallback and must have every id
+const CATALOGS: &[(&str, &str)] = &[
+    ("en", include_str!("../locales/en.ftl")),
+    ("de", include_str!("../locales/de.ftl")),
+];
+
+type Bundle = FluentBundle<FluentResource>;
+
+/// Catalog language for a locale such as `de_DE.UTF-8` or `de-AT`, if k8pk
+/// has one (`C` and `POSIX` are English)
+fn catalog_for(locale: &str) -> Option<&'static str> {
+    let tag = locale.split(['.', '@']).next().unwrap_or_default().trim();
+    if tag.is_empty() || tag == "C" || tag == "POSIX" {
+        return None;
+    }
+    let id: LanguageIdentifier = tag.replace('_', "-").parse().ok()?;
+    CATALOGS
+        .iter()
+        .map(|(lang, _)| *lang)
+        .find(|lang| *lang == id.language.as_str())
+}
+
+/// Language of the user's messages: the first of `K8PK_LANG`, the config,
+/// `LC_ALL`, `LC_MESSAGES`, `LANG` that is set
+pub fn language() -> &'static str {
+    let env = |var: &str| std::env::var(var).ok().filter(|v| !v.trim().is_empty());
+    env(LANG_ENV)
+        .or_else(|| CONFIG_LANG.get().filter(|v| !v.trim().is_empty()).cloned())
+        .or_else(|| ["LC_ALL", "LC_MESSAGES", "LANG"].into_iter().find_map(env))
+        .and_then(|locale| catalog_for(&locale))
+        .unwrap_or(CATALOGS[0].0)
+}
+
+fn load(lang: &str) -> Bundle {
+    let source = CATALOGS
+        .iter()
+        .find(|(l, _)| *l == lang)
+        .map_or(CATALOGS[0].1, |(_, s)| *s);
+    let id: LanguageIdentifier = lang.parse().unwrap_or_default();
+    let mut bundle = FluentBundle::new_concurrent(vec![id]);
+    // Unicode isolation marks show up as garbage in many terminals
+    bundle.set_use_isolating(false);
+    let resource = FluentResource::try_new(source.to_string()).unwrap_or_else(|(res, _)| res);
+    let _ = bundle.add_resource(resource);
+    bundle
+}
+
+fn format(bundle: &Bundle, id: &str, args: Option<&FluentArgs>) -> Option<String> {
+    let pattern = bundle.get_message(id)?.value()?;
+    let mut errors = Vec::new();
+    Some(
+        bundle
+            .format_pattern(pattern, args, &mut errors)
+            .into_owned(),
+    )
+}
+
+/// `(user's language, English)`, loaded on first use
+fn bundles() -> &'static (Bundle, Bundle) {
+    static BUNDLES: OnceLock<(Bundle, Bundle)> = OnceLock::new();
+    BUNDLES.get_or_init(|| (load(language()), load(CATALOGS[0].0)))
+}
+
+fn lookup(id: &str, args: Option<&FluentArgs>) -> String {
+    let (user, english) = bundles();
+    format(user, id, args)
+        .or_else(|| format(english, id, args))
+        .unwrap_or_else(|| id.to_string())
+}
+
+/// The message `id` in the user's language
+pub fn tr(id: &str) -> String {
+    lookup(id, None)
+}
+
+/// The message `id` with `{ $name }` placeholders filled from `args`
+pub fn tr_with(id: &str, args: &[(&str, &str)]) -> String {
+    let mut fluent_args = FluentArgs::new();
+    for (name, value) in args {
+        fluent_args.set(*name, value.to_string());
+    }
+    lookup(id, Some(&fluent_args))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    fn ids(source: &str) -> BTreeSet<String> {
+        source
+            .lines()
+            .filter(|l| !l.starts_with([' ', '#']) && l.contains(" ="))
+            .map(|l| l.split(" =").next().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_catalogs() {
+        assert_eq!(catalog_for("de_DE.UTF-8"), Some("de"));
+        assert_eq!(catalog_for("de-AT"), Some("de"));
+        assert_eq!(catalog_for("C.UTF-8"), None);
+        assert_eq!(catalog_for("ja_JP"), None);
+
+        // Every catalog parses and translates exactly the English ids
+        let english = ids(CATALOGS[0].1);
+        for (lang, source) in CATALOGS {
+            assert!(
+                FluentResource::try_new(source.to_string()).is_ok(),
+                "{}",
+                lang
+            );
+            assert_eq!(ids(source), english, "{}", lang);
+        }
+
+        let de = load("de");
+        let mut args = FluentArgs::new();
+        args.set("cluster", "prod");
+        assert_eq!(
+            format(&de, "pick-context-in", Some(&args)).as_deref(),
+            Some("Kontext in prod wählen:")
+        );
+        assert_eq!(format(&de, "no-such-id", None), None);
+    }
+}
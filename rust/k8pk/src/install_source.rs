@@ -0,0 +1,176 @@
+//! How the running k8pk binary was installed. A binary that Homebrew, dpkg,
+//! rpm, or Nix put in place belongs to that package manager, so `k8pk
+//! update` names its upgrade command instead of overwriting the file.
+
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "manager", rename_all = "lowercase")]
+pub enum InstallSource {
+    Homebrew,
+    Deb {
+        package: String,
+    },
+    Rpm {
+        package: String,
+    },
+    Nix,
+    /// A release tarball, install.sh, or a build from source
+    Standalone,
+}
+
+impl InstallSource {
+    pub fn name(&self) -> &'static str {
+        match self {
+            InstallSource::Homebrew => "Homebrew",
+            InstallSource::Deb { .. } => "apt/dpkg",
+            InstallSource::Rpm { .. } => "rpm",
+            InstallSource::Nix => "Nix",
+            InstallSource::Standalone => "standalone",
+        }
+    }
+
+    /// The command that upgrades k8pk through its package manager (None
+    /// for a standalone binary, which `k8pk update` replaces itself)
+    pub fn upgrade_command(&self) -> Option<String> {
+        match self {
+            InstallSource::Homebrew => Some("brew upgrade vee-sh/tap/k8pk".into()),
+            InstallSource::Deb { package } => {
+                Some(format!("sudo apt-get install --only-upgrade {}", package))
+            }
+            InstallSource::Rpm { package } => Some(format!("sudo dnf upgrade {}", package)),
+            InstallSource::Nix => Some("nix profile upgrade k8pk".into()),
+            InstallSource::Standalone => None,
+        }
+    }
+}
+
+/// How the running binary was installed
+pub fn detect() -> InstallSource {
+    match std::env::current_exe() {
+        Ok(exe) => detect_path(&exe, Path::new("/")),
+        Err(_) => InstallSource::Standalone,
+    }
+}
+
+/// `exe`, as invoked and with symlinks resolved (Homebrew links
+/// `<prefix>/bin/k8pk` into its Cellar), against the package managers' path
+/// conventions and the receipts under `root`
+fn detect_path(exe: &Path, root: &Path) -> InstallSource {
+    let mut candidates = vec![exe.to_path_buf()];
+    if let Ok(real) = fs::canonicalize(exe) {
+        if real != exe {
+            candidates.push(real);
+        }
+    }
+
+    for path in &candidates {
+        if is_homebrew(path) {
+            return InstallSource::Homebrew;
+        }
+        if path.starts_with("/nix/store") {
+            return InstallSource::Nix;
+        }
+    }
+    for path in &candidates {
+        if let Some(package) = dpkg_owner(path, root) {
+            return InstallSource::Deb { package };
+        }
+    }
+    for path in &candidates {
+        if let Some(package) = rpm_owner(path) {
+            return InstallSource::Rpm { package };
+        }
+    }
+    InstallSource::Standalone
+}
+
+/// Inside a Homebrew Cellar (`<prefix>/Cellar/k8pk/<version>/bin/k8pk`), or
+/// under a keg holding Homebrew's INSTALL_RECEIPT.json
+fn is_homebrew(path: &Path) -> bool {
+    path.components().any(|c| c.as_os_str() == "Cellar")
+        || path
+            .ancestors()
+            .skip(1)
+            .take(3)
+            .any(|dir| dir.join("INSTALL_RECEIPT.json").is_file())
+}
+
+/// The package whose dpkg file list (`/var/lib/dpkg/info/<pkg>.list`)
+/// includes `path`, without its `:<arch>` suffix
+fn dpkg_owner(path: &Path, root: &Path) -> Option<String> {
+    let path = path.to_str()?;
+    fs::read_dir(root.join("var/lib/dpkg/info"))
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|list| list.extension().is_some_and(|e| e == "list"))
+        .find(|list| {
+            fs::read_to_string(list).is_ok_and(|files| files.lines().any(|line| line == path))
+        })
+        .and_then(|list| {
+            let stem = list.file_stem()?.to_str()?;
+            Some(stem.split(':').next().unwrap_or(stem).to_string())
+        })
+}
+
+/// The rpm package owning `path` (`rpm -qf`), where rpm is installed
+fn rpm_owner(path: &Path) -> Option<String> {
+    let rpm = which::which("rpm").ok()?;
+    let output = Command::new(rpm)
+        .args(["-qf", "--queryformat", "%{NAME}"])
+        .arg(path)
+        .output()
+        .ok()?;
+    let package = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !package.is_empty()).then_some(package)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn package_manager_paths_and_receipts() {
+        let root = tempfile::tempdir().unwrap();
+        let cellar = root.path().join("opt/homebrew/Cellar/k8pk/1.14.14/bin");
+        fs::create_dir_all(&cellar).unwrap();
+        assert_eq!(
+            detect_path(&cellar.join("k8pk"), root.path()),
+            InstallSource::Homebrew
+        );
+
+        let keg = root.path().join("linuxbrew/k8pk/1.14.14");
+        fs::create_dir_all(keg.join("bin")).unwrap();
+        fs::write(keg.join("INSTALL_RECEIPT.json"), "{}").unwrap();
+        assert_eq!(
+            detect_path(&keg.join("bin/k8pk"), root.path()),
+            InstallSource::Homebrew
+        );
+
+        assert_eq!(
+            detect_path(
+                Path::new("/nix/store/abc-k8pk-1.14.14/bin/k8pk"),
+                root.path()
+            ),
+            InstallSource::Nix
+        );
+
+        let info = root.path().join("var/lib/dpkg/info");
+        fs::create_dir_all(&info).unwrap();
+        fs::write(info.join("coreutils.list"), "/usr/bin/ls\n").unwrap();
+        fs::write(info.join("k8pk:amd64.list"), "/.\n/usr\n/usr/bin/k8pk\n").unwrap();
+        let deb = detect_path(Path::new("/usr/bin/k8pk"), root.path());
+        assert_eq!(
+            deb.upgrade_command().as_deref(),
+            Some("sudo apt-get install --only-upgrade k8pk")
+        );
+
+        let standalone = detect_path(&root.path().join("bin/k8pk"), root.path());
+        assert_eq!(standalone, InstallSource::Standalone);
+        assert_eq!(standalone.upgrade_command(), None);
+    }
+}
@@ -1,27 +1,131 @@
 //! Kubeconfig file parsing, merging, and manipulation
 
+use crate::clock::now_secs;
 use crate::config::{self, K8pkConfig};
 use crate::error::{K8pkError, Result};
 use globset::{Glob, GlobSetBuilder};
 use serde::{Deserialize, Serialize};
 use serde_yaml_ng::Value as Yaml;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 use std::time::SystemTime;
 
 /// Write file content with 0o600 permissions (owner read/write only).
 /// Use this for any file that may contain credentials (kubeconfigs, vault, etc.).
+///
+/// The content goes to a temp file in the same directory which is then
+/// renamed over `path`, so readers never see a half-written file. A symlinked
+/// `path` is written through to its target rather than replaced.
 pub fn write_restricted(path: &Path, content: &str) -> Result<()> {
-    fs::write(path, content)?;
+    use std::io::Write;
+    let target = match fs::symlink_metadata(path) {
+        Ok(m) if m.file_type().is_symlink() => {
+            fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+        }
+        _ => path.to_path_buf(),
+    };
+    let dir = match target.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => Path::new("."),
+    };
+    // NamedTempFile is created 0600 on unix
+    let mut tmp = tempfile::NamedTempFile::new_in(dir)?;
+    tmp.write_all(content.as_bytes())?;
+    tmp.as_file().sync_all()?;
+    tmp.persist(&target).map_err(|e| e.error)?;
+    Ok(())
+}
+
+/// Take an exclusive advisory lock (flock) on `lock_path`, creating it if
+/// needed. The lock is held until the returned handle is dropped; a no-op on
+/// non-Unix platforms.
+pub fn acquire_lock(lock_path: &Path) -> Result<fs::File> {
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .open(lock_path)?;
     #[cfg(unix)]
     {
-        use std::os::unix::fs::PermissionsExt;
-        let mut perms = fs::metadata(path)?.permissions();
-        perms.set_mode(0o600);
-        fs::set_permissions(path, perms)?;
+        use std::os::unix::io::AsRawFd;
+        // Non-blocking attempts first, then block
+        for _ in 0..50 {
+            if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } == 0 {
+                return Ok(file);
+            }
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } != 0 {
+            return Err(K8pkError::Other(format!(
+                "failed to acquire lock {}",
+                lock_path.display()
+            )));
+        }
+    }
+    Ok(file)
+}
+
+/// Held [`lock_kubeconfig`] lock; dropping it removes the lock file
+#[derive(Debug)]
+pub struct KubeconfigLock {
+    path: PathBuf,
+    _file: fs::File,
+}
+
+impl Drop for KubeconfigLock {
+    fn drop(&mut self) {
+        // Still locked here: waiters that opened this file notice it is gone
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// `.<name>.k8pk-lock` next to the kubeconfig (or its symlink target). Not
+/// `<name>.lock`: kubectl creates and deletes that one itself.
+fn kubeconfig_lock_path(path: &Path) -> PathBuf {
+    let target = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let name = target.file_name().unwrap_or_default().to_string_lossy();
+    target.with_file_name(format!(".{}.k8pk-lock", name))
+}
+
+/// Whether `file` is still the file at `path` (false once a releasing
+/// holder removed it)
+fn is_current_file(file: &fs::File, path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        match (file.metadata(), fs::metadata(path)) {
+            (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+            _ => false,
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (file, path);
+        true
+    }
+}
+
+/// Lock serialising k8pk's read-modify-write of the kubeconfig at `path`
+/// across processes, held in a hidden lock file beside it that is removed
+/// on release. Locks are not reentrant: never take the same path twice in
+/// one call chain.
+pub fn lock_kubeconfig(path: &Path) -> Result<KubeconfigLock> {
+    let lock_path = kubeconfig_lock_path(path);
+    if let Some(dir) = lock_path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    loop {
+        let file = acquire_lock(&lock_path)?;
+        // The previous holder may have removed the file while we waited on it
+        if is_current_file(&file, &lock_path) {
+            return Ok(KubeconfigLock {
+                path: lock_path,
+                _file: file,
+            });
+        }
     }
-    Ok(())
 }
 use std::process::Command as ProcCommand;
 
@@ -42,6 +146,9 @@ pub struct KubeConfig {
     pub users: Vec<NamedItem>,
     #[serde(default)]
     pub extensions: Option<Yaml>,
+    /// File each context was loaded from (set by [`load_merged`])
+    #[serde(skip)]
+    pub sources: HashMap<String, PathBuf>,
 }
 
 /// Named item in kubeconfig (context, cluster, user)
@@ -135,27 +242,93 @@ pub fn get_server_for_context(cfg: &KubeConfig, context_name: &str) -> Option<St
     extract_server_url_from_cluster(&cluster.rest)
 }
 
-/// Check if the cluster for a context has `insecure-skip-tls-verify: true` set.
-pub fn get_cluster_insecure_for_context(cfg: &KubeConfig, context_name: &str) -> bool {
-    let Some(ctx) = cfg.find_context(context_name) else {
-        return false;
-    };
-    let Ok((cluster_name, _)) = extract_context_refs(&ctx.rest) else {
-        return false;
-    };
-    let Some(cluster) = cfg.find_cluster(&cluster_name) else {
-        return false;
-    };
-    let Yaml::Mapping(map) = &cluster.rest else {
-        return false;
+/// Namespace set on a context entry (`context.namespace`), if any
+pub fn get_namespace_for_context(cfg: &KubeConfig, context_name: &str) -> Option<String> {
+    let ctx = cfg.find_context(context_name)?;
+    let Yaml::Mapping(map) = &ctx.rest else {
+        return None;
     };
-    let Some(Yaml::Mapping(cluster_map)) = map.get(Yaml::from("cluster")) else {
-        return false;
+    let Some(Yaml::Mapping(inner)) = map.get(Yaml::from("context")) else {
+        return None;
     };
-    matches!(
-        cluster_map.get(Yaml::from("insecure-skip-tls-verify")),
-        Some(Yaml::Bool(true))
-    )
+    match inner.get(Yaml::from("namespace")) {
+        Some(Yaml::String(s)) if !s.is_empty() => Some(s.clone()),
+        _ => None,
+    }
+}
+
+/// The `cluster:` mapping of the cluster a context points at
+fn context_cluster_map<'a>(
+    cfg: &'a KubeConfig,
+    context_name: &str,
+) -> Option<&'a serde_yaml_ng::Mapping> {
+    let ctx = cfg.find_context(context_name)?;
+    let (cluster_name, _) = extract_context_refs(&ctx.rest).ok()?;
+    match cfg.find_cluster(&cluster_name)?.rest.get("cluster")? {
+        Yaml::Mapping(map) => Some(map),
+        _ => None,
+    }
+}
+
+/// Check if the cluster for a context has `insecure-skip-tls-verify: true` set.
+pub fn get_cluster_insecure_for_context(cfg: &KubeConfig, context_name: &str) -> bool {
+    context_cluster_map(cfg, context_name).is_some_and(|map| {
+        matches!(
+            map.get(Yaml::from("insecure-skip-tls-verify")),
+            Some(Yaml::Bool(true))
+        )
+    })
+}
+
+/// How kubectl will check a context's server certificate once k8pk has
+/// isolated it (see `k8pk which`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TlsMode {
+    /// Against `certificate-authority(-data)` from the kubeconfig
+    CustomCa,
+    /// Against the bundle `ca_overrides` in the k8pk config sets
+    CaOverride,
+    /// Against the system trust store
+    SystemCa,
+    /// Not verified: `insecure-skip-tls-verify` in the kubeconfig
+    Insecure,
+    /// Not verified: the context matches `insecure_contexts` in the k8pk config
+    InsecureByConfig,
+}
+
+impl TlsMode {
+    pub fn describe(self) -> &'static str {
+        match self {
+            TlsMode::CustomCa => "verified (CA from kubeconfig)",
+            TlsMode::CaOverride => "verified (CA bundle from ca_overrides in k8pk config)",
+            TlsMode::SystemCa => "verified (system CAs)",
+            TlsMode::Insecure => "NOT verified (insecure-skip-tls-verify in kubeconfig)",
+            TlsMode::InsecureByConfig => "NOT verified (insecure_contexts in k8pk config)",
+        }
+    }
+}
+
+/// Effective [`TlsMode`] of `context`: `insecure_contexts`, then
+/// `ca_overrides` from the k8pk config win over the kubeconfig
+pub fn tls_mode(cfg: &KubeConfig, context: &str, k8pk: &K8pkConfig) -> TlsMode {
+    if config::is_context_insecure_with(k8pk, context) {
+        return TlsMode::InsecureByConfig;
+    }
+    if config::ca_override_with(k8pk, context).is_some() {
+        return TlsMode::CaOverride;
+    }
+    if get_cluster_insecure_for_context(cfg, context) {
+        return TlsMode::Insecure;
+    }
+    let has_ca = context_cluster_map(cfg, context).is_some_and(|map| {
+        map.contains_key("certificate-authority") || map.contains_key("certificate-authority-data")
+    });
+    if has_ca {
+        TlsMode::CustomCa
+    } else {
+        TlsMode::SystemCa
+    }
 }
 
 /// Set `insecure-skip-tls-verify: true` on all clusters in a kubeconfig.
@@ -173,6 +346,31 @@ pub fn set_cluster_insecure(cfg: &mut KubeConfig) {
     }
 }
 
+/// Point every cluster in a kubeconfig at the CA bundle `ca`, replacing
+/// `certificate-authority(-data)`; `insecure-skip-tls-verify` goes too, as
+/// kubectl refuses it together with a CA.
+pub fn set_cluster_ca(cfg: &mut KubeConfig, ca: &Path) {
+    for cluster in &mut cfg.clusters {
+        if let Some(Yaml::Mapping(inner)) = cluster.rest.get_mut("cluster") {
+            inner.remove(Yaml::from("certificate-authority-data"));
+            inner.remove(Yaml::from("insecure-skip-tls-verify"));
+            inner.insert(
+                Yaml::from("certificate-authority"),
+                Yaml::from(ca.display().to_string()),
+            );
+        }
+    }
+}
+
+/// Set `proxy-url` on every cluster in a kubeconfig
+pub fn set_cluster_proxy(cfg: &mut KubeConfig, proxy: &str) {
+    for cluster in &mut cfg.clusters {
+        if let Some(Yaml::Mapping(inner)) = cluster.rest.get_mut("cluster") {
+            inner.insert(Yaml::from("proxy-url"), Yaml::from(proxy));
+        }
+    }
+}
+
 /// Set the namespace for a context in a kubeconfig
 pub fn set_context_namespace(cfg: &mut KubeConfig, context_name: &str, ns: &str) -> Result<()> {
     if let Some(item) = cfg.contexts.iter_mut().find(|c| c.name == context_name) {
@@ -218,83 +416,534 @@ pub fn prune_to_context(cfg: &KubeConfig, name: &str) -> Result<KubeConfig> {
         contexts: vec![ctx.clone()],
         users: vec![user.clone()],
         extensions: None,
+        sources: HashMap::new(),
     })
 }
 
-/// Load and merge multiple kubeconfig files.
-/// Deduplicates by name (first occurrence wins, matching kubectl behavior).
-/// ponytail: no merge cache; mtime fingerprint was more code than benefit for CLI lifetime
-pub fn load_merged(paths: &[PathBuf]) -> Result<KubeConfig> {
-    let mut merged = KubeConfig::default();
-    let mut seen_clusters = std::collections::HashSet::new();
-    let mut seen_contexts = std::collections::HashSet::new();
-    let mut seen_users = std::collections::HashSet::new();
+/// Placeholder written in place of secrets by [`redact_secrets`]
+pub const REDACTED: &str = "REDACTED";
+
+/// Credential fields under `users[].user`, at any depth (auth-provider config included)
+const SECRET_KEYS: &[&str] = &[
+    "token",
+    "client-key-data",
+    "password",
+    "id-token",
+    "refresh-token",
+    "access-token",
+    "client-secret",
+];
+
+/// Replace tokens, client keys, passwords, and exec plugin env values in every
+/// user with [`REDACTED`], keeping names, servers, and CA data so the result
+/// still shows where each context points. Returns the number of values replaced.
+pub fn redact_secrets(cfg: &mut KubeConfig) -> usize {
+    fn redact(value: &mut Yaml) -> usize {
+        match value {
+            Yaml::Mapping(map) => map
+                .iter_mut()
+                .map(|(k, v)| match k.as_str() {
+                    Some(key) if SECRET_KEYS.contains(&key) && !v.is_null() => {
+                        *v = Yaml::from(REDACTED);
+                        1
+                    }
+                    Some("env") => match v {
+                        Yaml::Sequence(items) => items
+                            .iter_mut()
+                            .filter_map(|item| item.get_mut("value"))
+                            .map(|value| *value = Yaml::from(REDACTED))
+                            .count(),
+                        _ => 0,
+                    },
+                    _ => redact(v),
+                })
+                .sum(),
+            Yaml::Sequence(items) => items.iter_mut().map(redact).sum(),
+            _ => 0,
+        }
+    }
+    cfg.users.iter_mut().map(|u| redact(&mut u.rest)).sum()
+}
 
-    for p in paths {
-        if !p.exists() {
+/// The values [`redact_secrets`] would replace, e.g. for masking in CI logs
+pub fn secret_values(cfg: &KubeConfig) -> Vec<String> {
+    let mut redacted = cfg.clone();
+    redact_secrets(&mut redacted);
+    let mut values = Vec::new();
+    for (user, masked) in cfg.users.iter().zip(&redacted.users) {
+        collect_changed(&user.rest, &masked.rest, &mut values);
+    }
+    values.sort();
+    values.dedup();
+    values
+}
+
+fn collect_changed(original: &Yaml, masked: &Yaml, out: &mut Vec<String>) {
+    match (original, masked) {
+        (Yaml::Mapping(a), Yaml::Mapping(b)) => {
+            for (k, v) in a {
+                if let Some(m) = b.get(k) {
+                    collect_changed(v, m, out);
+                }
+            }
+        }
+        (Yaml::Sequence(a), Yaml::Sequence(b)) => {
+            for (v, m) in a.iter().zip(b) {
+                collect_changed(v, m, out);
+            }
+        }
+        (Yaml::String(v), _) if original != masked && !v.is_empty() => out.push(v.clone()),
+        _ => {}
+    }
+}
+
+/// File-path fields of `clusters[].cluster` with their inline `-data` twin
+const CLUSTER_FILE_FIELDS: &[(&str, Option<&str>)] =
+    &[("certificate-authority", Some("certificate-authority-data"))];
+
+/// File-path fields of `users[].user` with their inline `-data` twin
+const USER_FILE_FIELDS: &[(&str, Option<&str>)] = &[
+    ("client-certificate", Some("client-certificate-data")),
+    ("client-key", Some("client-key-data")),
+    ("tokenFile", None),
+];
+
+/// Mutable `cluster` / `user` mappings of every entry, with their path fields
+fn file_field_owners(
+    cfg: &mut KubeConfig,
+) -> impl Iterator<Item = (&mut Yaml, &'static [(&'static str, Option<&'static str>)])> {
+    let clusters = cfg
+        .clusters
+        .iter_mut()
+        .filter_map(|c| c.rest.get_mut("cluster"))
+        .map(|m| (m, CLUSTER_FILE_FIELDS));
+    let users = cfg
+        .users
+        .iter_mut()
+        .filter_map(|u| u.rest.get_mut("user"))
+        .map(|m| (m, USER_FILE_FIELDS));
+    clusters.chain(users)
+}
+
+/// Make relative certificate, key, token-file, and exec command paths
+/// absolute against `base` (the directory of the kubeconfig they came from),
+/// as kubectl does when loading, so the entries still work once written
+/// elsewhere.
+pub fn resolve_relative_paths(cfg: &mut KubeConfig, base: &Path) {
+    let resolve = |v: &mut Yaml, only_with_separator: bool| {
+        let Some(path) = v.as_str() else {
+            return;
+        };
+        let rel = Path::new(path);
+        if path.is_empty()
+            || rel.is_absolute()
+            || path.starts_with('~')
+            || (only_with_separator && !path.contains(std::path::is_separator))
+        {
+            return;
+        }
+        let joined: PathBuf = base
+            .join(rel)
+            .components()
+            .filter(|c| !matches!(c, std::path::Component::CurDir))
+            .collect();
+        *v = Yaml::from(joined.to_string_lossy().into_owned());
+    };
+    for (map, fields) in file_field_owners(cfg) {
+        for (field, _) in fields {
+            if let Some(v) = map.get_mut(*field) {
+                resolve(v, false);
+            }
+        }
+        // A bare exec command is looked up on PATH; only `./bin/x` style is a file
+        if let Some(cmd) = map.get_mut("exec").and_then(|e| e.get_mut("command")) {
+            resolve(cmd, true);
+        }
+    }
+}
+
+/// Clusters and users of `cfg` that reference a certificate, key, or token
+/// file that doesn't exist, each with the first missing path. Relative paths
+/// are checked against `base`, the kubeconfig's directory.
+pub fn missing_file_refs(cfg: &KubeConfig, base: &Path) -> HashMap<(&'static str, String), String> {
+    let entries = cfg
+        .clusters
+        .iter()
+        .map(|c| ("cluster", c, CLUSTER_FILE_FIELDS))
+        .chain(cfg.users.iter().map(|u| ("user", u, USER_FILE_FIELDS)));
+    let mut missing = HashMap::new();
+    for (kind, item, fields) in entries {
+        let Some(map) = item.rest.get(kind) else {
             continue;
+        };
+        let path = fields
+            .iter()
+            .filter_map(|(field, _)| map.get(*field)?.as_str())
+            .filter(|p| !p.is_empty())
+            .find(|p| {
+                let path = match p.strip_prefix("~/") {
+                    Some(rest) => dirs_next::home_dir().unwrap_or_default().join(rest),
+                    None => base.join(p),
+                };
+                !path.exists()
+            });
+        if let Some(path) = path {
+            missing.insert((kind, item.name.clone()), path.to_string());
         }
-        let s = fs::read_to_string(p)?;
-        let cfg: KubeConfig = serde_yaml_ng::from_str(&s)?;
+    }
+    missing
+}
 
-        // current-context: first wins if set
-        if merged.current_context.is_none() && cfg.current_context.is_some() {
-            merged.current_context = cfg.current_context.clone();
+/// Exec credential plugin commands referenced by `cfg`'s users, as
+/// `(user, command)` pairs
+pub fn exec_plugins(cfg: &KubeConfig) -> Vec<(String, String)> {
+    cfg.users
+        .iter()
+        .filter_map(|u| {
+            let command = u.rest.get("user")?.get("exec")?.get("command")?.as_str()?;
+            Some((u.name.clone(), command.to_string()))
+        })
+        .collect()
+}
+
+/// What a flattened `cfg` still needs from the machine it was made on: exec
+/// plugins and token files, which `--flatten` does not inline (kubectl
+/// doesn't either), one description per reference
+pub fn local_dependencies(cfg: &KubeConfig) -> Vec<String> {
+    let mut deps: Vec<String> = exec_plugins(cfg)
+        .into_iter()
+        .map(|(user, command)| format!("user '{}' runs exec plugin '{}'", user, command))
+        .collect();
+    for u in &cfg.users {
+        if let Some(file) = u
+            .rest
+            .get("user")
+            .and_then(|m| m.get("tokenFile")?.as_str())
+        {
+            deps.push(format!("user '{}' reads its token from {}", u.name, file));
         }
+    }
+    deps
+}
 
-        // Deduplicate by name (first occurrence wins)
-        for cluster in cfg.clusters {
-            if seen_clusters.insert(cluster.name.clone()) {
-                merged.clusters.push(cluster);
-            }
+/// Resolve an exec plugin `command` the way kubectl would: a path (relative
+/// ones against `base`, the kubeconfig's directory) when it contains a
+/// separator, otherwise a PATH lookup with `hints` searched first
+pub fn find_exec_plugin(command: &str, hints: &[PathBuf], base: &Path) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH");
+    let search = config::path_with_hints(hints, path.as_deref()).or(path);
+    which::which_in(command, search, base).ok()
+}
+
+/// Replace certificate-authority, client-certificate, and client-key file
+/// references with base64 `-data` fields holding the file contents. Paths
+/// must be absolute (see [`resolve_relative_paths`]). Returns the number of
+/// files embedded.
+pub fn embed_certs(cfg: &mut KubeConfig) -> Result<usize> {
+    use base64::Engine;
+    let mut embedded = 0;
+    for (map, fields) in file_field_owners(cfg) {
+        let Yaml::Mapping(map) = map else {
+            continue;
+        };
+        for (field, data_field) in fields {
+            let Some(data_field) = data_field else {
+                continue;
+            };
+            let Some(path) = map.get(*field).and_then(Yaml::as_str).map(PathBuf::from) else {
+                continue;
+            };
+            let bytes = fs::read(&path).map_err(|e| {
+                K8pkError::InvalidKubeconfig(format!(
+                    "cannot embed {} {}: {}",
+                    field,
+                    path.display(),
+                    e
+                ))
+            })?;
+            map.remove(*field);
+            map.insert(
+                Yaml::from(*data_field),
+                Yaml::from(base64::engine::general_purpose::STANDARD.encode(bytes)),
+            );
+            embedded += 1;
         }
-        for context in cfg.contexts {
-            if seen_contexts.insert(context.name.clone()) {
-                merged.contexts.push(context);
+    }
+    Ok(embedded)
+}
+
+pub use crate::cli::MergeStrategy;
+
+/// Env var holding the merge strategy (like `--merge-strategy`)
+pub const MERGE_STRATEGY_ENV: &str = "K8PK_MERGE_STRATEGY";
+
+// `--merge-strategy` lives in the process, not the environment, so shells and
+// programs k8pk starts don't inherit it
+static MERGE_STRATEGY_FLAG: OnceLock<MergeStrategy> = OnceLock::new();
+
+/// Record the global `--merge-strategy` for this process
+pub fn set_merge_strategy(strategy: MergeStrategy) {
+    let _ = MERGE_STRATEGY_FLAG.set(strategy);
+}
+
+/// Process-wide merge strategy: `--merge-strategy`, then
+/// `K8PK_MERGE_STRATEGY` (first when unset or unrecognised)
+pub fn merge_strategy() -> MergeStrategy {
+    if let Some(strategy) = MERGE_STRATEGY_FLAG.get() {
+        return *strategy;
+    }
+    match std::env::var(MERGE_STRATEGY_ENV) {
+        Ok(v) => match v.trim().to_lowercase().as_str() {
+            "first" | "" => MergeStrategy::First,
+            "last" => MergeStrategy::Last,
+            "strict" => MergeStrategy::Strict,
+            _ => {
+                eprintln!(
+                    "warning: unknown merge strategy '{}' (expected first, last, or strict); using first",
+                    v
+                );
+                MergeStrategy::First
             }
+        },
+        Err(_) => MergeStrategy::First,
+    }
+}
+
+/// An entry hidden by another of the same name during a merge
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Shadowed {
+    /// "cluster", "context", or "user"
+    pub kind: &'static str,
+    pub name: String,
+    /// File whose entry is ignored
+    pub file: PathBuf,
+    /// File whose entry is used (same as `file` for a duplicate within it)
+    pub winner: PathBuf,
+}
+
+impl std::fmt::Display for Shadowed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.file == self.winner {
+            write!(
+                f,
+                "{} '{}' is defined more than once in {}; using the first (kubectl rejects this file)",
+                self.kind,
+                self.name,
+                self.file.display()
+            )
+        } else {
+            write!(
+                f,
+                "{} '{}' in {} is shadowed by {}",
+                self.kind,
+                self.name,
+                self.file.display(),
+                self.winner.display()
+            )
         }
-        for user in cfg.users {
-            if seen_users.insert(user.name.clone()) {
-                merged.users.push(user);
+    }
+}
+
+/// Merge one kind of named entries. Within a file the first entry of a name
+/// is kept; across files `strategy` decides. Order is that of first
+/// appearance.
+fn merge_named(
+    kind: &'static str,
+    files: &[(PathBuf, Vec<NamedItem>)],
+    strategy: MergeStrategy,
+    shadowed: &mut Vec<Shadowed>,
+) -> Result<Vec<NamedItem>> {
+    let mut merged: Vec<NamedItem> = Vec::new();
+    let mut owner: HashMap<String, (usize, &PathBuf)> = HashMap::new();
+    for (file, items) in files {
+        let mut in_file = HashSet::new();
+        for item in items {
+            if !in_file.insert(item.name.as_str()) {
+                shadowed.push(Shadowed {
+                    kind,
+                    name: item.name.clone(),
+                    file: file.clone(),
+                    winner: file.clone(),
+                });
+                continue;
             }
+            match owner.get(&item.name).copied() {
+                None => {
+                    owner.insert(item.name.clone(), (merged.len(), file));
+                    merged.push(item.clone());
+                }
+                Some((_, first)) if strategy != MergeStrategy::Last => {
+                    shadowed.push(Shadowed {
+                        kind,
+                        name: item.name.clone(),
+                        file: file.clone(),
+                        winner: first.clone(),
+                    });
+                }
+                Some((pos, previous)) => {
+                    shadowed.push(Shadowed {
+                        kind,
+                        name: item.name.clone(),
+                        file: previous.clone(),
+                        winner: file.clone(),
+                    });
+                    owner.insert(item.name.clone(), (pos, file));
+                    merged[pos] = item.clone();
+                }
+            }
+        }
+    }
+    if strategy == MergeStrategy::Strict {
+        if let Some(s) = shadowed.first() {
+            let what = if s.file == s.winner {
+                s.to_string()
+            } else {
+                format!(
+                    "{} '{}' is defined in both {} and {}",
+                    s.kind,
+                    s.name,
+                    s.winner.display(),
+                    s.file.display()
+                )
+            };
+            return Err(K8pkError::InvalidKubeconfig(format!(
+                "{} (--merge-strategy strict)",
+                what
+            )));
         }
+    }
+    Ok(merged)
+}
 
-        // carry over top-level defaults only once
-        if merged.api_version.is_none() {
-            merged.api_version = cfg.api_version;
+/// Merge parsed kubeconfigs in precedence order. current-context and the
+/// top-level defaults come from the first file setting them (the last, for
+/// [`MergeStrategy::Last`]).
+pub fn merge_configs(
+    files: Vec<(PathBuf, KubeConfig)>,
+    strategy: MergeStrategy,
+) -> Result<(KubeConfig, Vec<Shadowed>)> {
+    let mut shadowed = Vec::new();
+    let mut merged = KubeConfig::default();
+    let pick = |acc: &mut Option<_>, v: Option<_>| {
+        if v.is_some() && (acc.is_none() || strategy == MergeStrategy::Last) {
+            *acc = v;
         }
-        if merged.kind.is_none() {
-            merged.kind = cfg.kind;
+    };
+    let (mut clusters, mut contexts, mut users) = (Vec::new(), Vec::new(), Vec::new());
+    for (path, cfg) in files {
+        pick(&mut merged.current_context, cfg.current_context);
+        pick(&mut merged.api_version, cfg.api_version);
+        pick(&mut merged.kind, cfg.kind);
+        if merged.preferences.is_none() || strategy == MergeStrategy::Last {
+            merged.preferences = cfg.preferences.or(merged.preferences.take());
         }
-        if merged.preferences.is_none() {
-            merged.preferences = cfg.preferences;
+        if merged.extensions.is_none() || strategy == MergeStrategy::Last {
+            merged.extensions = cfg.extensions.or(merged.extensions.take());
         }
-        if merged.extensions.is_none() {
-            merged.extensions = cfg.extensions;
+        clusters.push((path.clone(), cfg.clusters));
+        contexts.push((path.clone(), cfg.contexts));
+        users.push((path, cfg.users));
+    }
+    for (path, items) in &contexts {
+        for item in items {
+            if strategy == MergeStrategy::Last || !merged.sources.contains_key(&item.name) {
+                merged.sources.insert(item.name.clone(), path.clone());
+            }
         }
     }
+    merged.clusters = merge_named("cluster", &clusters, strategy, &mut shadowed)?;
+    merged.contexts = merge_named("context", &contexts, strategy, &mut shadowed)?;
+    merged.users = merge_named("user", &users, strategy, &mut shadowed)?;
+    Ok((merged, shadowed))
+}
 
-    Ok(merged)
+/// Print each shadowing warning once per process (nothing under --quiet)
+fn warn_shadowed(shadowed: &[Shadowed]) {
+    static WARNED: std::sync::Mutex<Option<HashSet<String>>> = std::sync::Mutex::new(None);
+    if shadowed.is_empty() || crate::render::quiet() {
+        return;
+    }
+    let Ok(mut warned) = WARNED.lock() else {
+        return;
+    };
+    let warned = warned.get_or_insert_with(HashSet::new);
+    for s in shadowed {
+        let msg = s.to_string();
+        if warned.insert(msg.clone()) {
+            eprintln!("warning: {}", msg);
+        }
+    }
 }
 
-/// List contexts with their source file paths
-pub fn list_contexts_with_paths(paths: &[PathBuf]) -> Result<HashMap<String, PathBuf>> {
-    let mut context_paths = HashMap::new();
+/// Kubeconfigs named `*.sops.yaml` / `*.sops.yml` are SOPS-encrypted: k8pk
+/// decrypts them in memory when loading and never rewrites them
+pub fn is_sops_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|n| n.ends_with(".sops.yaml") || n.ends_with(".sops.yml"))
+}
 
+/// Fail for files k8pk must not rewrite in place (see [`is_sops_file`])
+pub fn ensure_editable(path: &Path) -> Result<()> {
+    if is_sops_file(path) {
+        return Err(K8pkError::InvalidArgument(format!(
+            "{} is SOPS-encrypted; edit it with: sops {}",
+            path.display(),
+            path.display()
+        )));
+    }
+    Ok(())
+}
+
+fn load_files(paths: &[PathBuf]) -> Result<Vec<(PathBuf, KubeConfig)>> {
+    let mut files = Vec::new();
     for p in paths {
         if !p.exists() {
             continue;
         }
-        let s = fs::read_to_string(p)?;
-        let cfg: KubeConfig = serde_yaml_ng::from_str(&s)?;
+        let s = if is_sops_file(p) {
+            // Missing sops or keys hides these contexts, not every context
+            match crate::encryption::sops_decrypt(p) {
+                Ok(s) => s,
+                Err(e) => {
+                    eprintln!("warning: skipping {}: {}", p.display(), e);
+                    continue;
+                }
+            }
+        } else {
+            fs::read_to_string(p)?
+        };
+        let mut cfg: KubeConfig = serde_yaml_ng::from_str(&s)?;
+        if let Some(dir) = normalize_path(&p.to_string_lossy()).parent() {
+            resolve_relative_paths(&mut cfg, dir);
+        }
+        files.push((p.clone(), cfg));
+    }
+    Ok(files)
+}
+
+/// Load and merge multiple kubeconfig files under the process-wide merge
+/// strategy (first file wins by default, matching kubectl), warning about
+/// entries hidden by another of the same name.
+/// ponytail: no merge cache; mtime fingerprint was more code than benefit for CLI lifetime
+pub fn load_merged(paths: &[PathBuf]) -> Result<KubeConfig> {
+    let (merged, shadowed) = merge_configs(load_files(paths)?, merge_strategy())?;
+    warn_shadowed(&shadowed);
+    Ok(merged)
+}
 
+/// List contexts with their source file paths (the file whose entry wins
+/// under the merge strategy)
+pub fn list_contexts_with_paths(paths: &[PathBuf]) -> Result<HashMap<String, PathBuf>> {
+    let last_wins = merge_strategy() == MergeStrategy::Last;
+    let mut context_paths = HashMap::new();
+    for (p, cfg) in load_files(paths)? {
         for ctx in &cfg.contexts {
-            if !context_paths.contains_key(&ctx.name) {
+            if last_wins || !context_paths.contains_key(&ctx.name) {
                 context_paths.insert(ctx.name.clone(), p.clone());
             }
         }
     }
-
     Ok(context_paths)
 }
 
@@ -313,32 +962,32 @@ pub fn resolve_paths(
         return Ok(paths);
     }
 
+    // Same file reached twice (symlink, `./`, relative vs absolute) is kept once;
+    // files switched off with `k8pk sources disable` are skipped
+    let disabled = &k8pk_config.configs.disabled;
+    let mut add = |p: PathBuf, paths: &mut Vec<PathBuf>| {
+        if !is_source_disabled(&p, disabled) && visited.insert(path_identity(&p)) {
+            paths.push(p);
+        }
+    };
+
     // Priority 2: $KUBECONFIG env var
-    if let Ok(kc) = std::env::var("KUBECONFIG") {
-        for p in kc.split(':').filter(|s| !s.is_empty()).map(PathBuf::from) {
-            if !visited.contains(&p) {
-                paths.push(p.clone());
-                visited.insert(p);
-            }
+    if let Some(kc) = std::env::var_os("KUBECONFIG") {
+        for p in split_kubeconfig_env(&kc) {
+            add(p, &mut paths);
         }
     }
 
     // Priority 3: CLI-specified directories
     for dir in kubeconfig_dirs {
         for p in scan_directory(dir)? {
-            if !visited.contains(&p) {
-                paths.push(p.clone());
-                visited.insert(p);
-            }
+            add(p, &mut paths);
         }
     }
 
     // Priority 4: Config file patterns
     for p in find_from_config(k8pk_config)? {
-        if !visited.contains(&p) {
-            paths.push(p.clone());
-            visited.insert(p);
-        }
+        add(p, &mut paths);
     }
 
     // Priority 5: k8pk-generated kubeconfig directories (login outputs)
@@ -346,26 +995,33 @@ pub fn resolve_paths(
         let k8pk_dirs = [
             home.join(".kube/rancher"),
             home.join(".kube/ocp"),
+            home.join(".kube/eks"),
             home.join(".kube/gke"),
+            home.join(".kube/aks"),
+            home.join(".kube/teleport"),
             home.join(".kube/k8s"),
         ];
         for dir in &k8pk_dirs {
             if dir.exists() && dir.is_dir() {
                 for p in scan_directory(dir)? {
-                    if !visited.contains(&p) {
-                        paths.push(p.clone());
-                        visited.insert(p);
-                    }
+                    add(p, &mut paths);
                 }
             }
         }
     }
 
+    // Team kubeconfigs checked out by `k8pk sync` (nested directories too)
+    if let Some(dir) = managed_dir() {
+        for p in scan_tree(&dir)? {
+            add(p, &mut paths);
+        }
+    }
+
     // Priority 6: Default fallback
     if paths.is_empty() {
         let home = dirs_next::home_dir().ok_or(K8pkError::NoHomeDir)?;
         let default = home.join(".kube").join("config");
-        if default.exists() {
+        if default.exists() && !is_source_disabled(&default, disabled) {
             paths.push(default);
         }
     }
@@ -397,12 +1053,45 @@ pub fn scan_directory(dir: &Path) -> Result<Vec<PathBuf>> {
     Ok(configs)
 }
 
+/// `~/.kube/managed`, where `k8pk sync` checks out its repository
+pub fn managed_dir() -> Option<PathBuf> {
+    dirs_next::home_dir().map(|home| home.join(".kube/managed"))
+}
+
+/// [`scan_directory`] of `dir` and its subdirectories, skipping hidden ones
+/// such as `.git`, in a stable order
+pub fn scan_tree(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut configs = scan_directory(dir)?;
+    if dir.is_dir() {
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            let hidden = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with('.'));
+            if path.is_dir() && !hidden {
+                configs.extend(scan_tree(&path)?);
+            }
+        }
+    }
+    configs.sort();
+    Ok(configs)
+}
+
 /// Find kubeconfigs from k8pk config patterns
 pub fn find_from_config(config: &K8pkConfig) -> Result<Vec<PathBuf>> {
     let mut paths = Vec::new();
     let mut visited = HashSet::new();
 
     for include_pattern in &config.configs.include {
+        if crate::remote::is_remote(include_pattern) {
+            if let Some(path) = crate::remote::cached_source(include_pattern, config) {
+                if visited.insert(path.clone()) {
+                    paths.push(path);
+                }
+            }
+            continue;
+        }
         let expanded = config::expand_home(include_pattern);
 
         if include_pattern.contains('*') {
@@ -472,6 +1161,9 @@ pub fn match_globs(path: &Path, patterns: &[String]) -> Result<bool> {
     Ok(globset.is_match(path))
 }
 
+/// Separator between KUBECONFIG entries: `;` on Windows, `:` elsewhere
+pub const KUBECONFIG_SEPARATOR: char = if cfg!(windows) { ';' } else { ':' };
+
 /// Join paths for KUBECONFIG environment variable
 pub fn join_paths_for_env(paths: &[PathBuf]) -> Option<String> {
     if paths.is_empty() {
@@ -482,10 +1174,106 @@ pub fn join_paths_for_env(paths: &[PathBuf]) -> Option<String> {
             .iter()
             .map(|p| p.as_os_str().to_string_lossy().into_owned())
             .collect::<Vec<_>>()
-            .join(":"),
+            .join(&KUBECONFIG_SEPARATOR.to_string()),
     )
 }
 
+/// Paths listed in a KUBECONFIG value, split on the platform separator, with
+/// `~` and environment variables (`$VAR`, `${VAR}`, and `%VAR%` on Windows)
+/// expanded, relative entries made absolute, and `.` components dropped.
+/// Empty entries and repeats of the same file (including via symlinks) are
+/// skipped, keeping the first.
+pub fn split_kubeconfig_env(value: &std::ffi::OsStr) -> Vec<PathBuf> {
+    let mut seen = HashSet::new();
+    std::env::split_paths(value)
+        .filter(|p| !p.as_os_str().is_empty())
+        .map(|p| normalize_path(&expand_env_vars(&p.to_string_lossy())))
+        .filter(|p| seen.insert(path_identity(p)))
+        .collect()
+}
+
+/// Expand `$VAR` and `${VAR}` (plus `%VAR%` on Windows); unset variables are
+/// left as written
+pub fn expand_env_vars(s: &str) -> String {
+    let lookup = |name: &str| std::env::var(name).ok();
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(i) = rest.find(['$', '%']) {
+        out.push_str(&rest[..i]);
+        let tail = &rest[i..];
+        let (name, len) = if let Some(braced) = tail.strip_prefix("${") {
+            match braced.find('}') {
+                Some(end) => (&braced[..end], end + 3),
+                None => ("", 0),
+            }
+        } else if let Some(bare) = tail.strip_prefix('$') {
+            let end = bare
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(bare.len());
+            (&bare[..end], end + 1)
+        } else if cfg!(windows) {
+            match tail[1..].find('%') {
+                Some(end) => (&tail[1..1 + end], end + 2),
+                None => ("", 0),
+            }
+        } else {
+            ("", 0)
+        };
+        match (len, lookup(name).filter(|_| !name.is_empty())) {
+            (0, _) | (_, None) => {
+                let keep = len.max(1);
+                out.push_str(&tail[..keep]);
+                rest = &tail[keep..];
+            }
+            (_, Some(value)) => {
+                out.push_str(&value);
+                rest = &tail[len..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// `~`-expanded absolute form of `path` with `.` components removed. `..` is
+/// kept, since resolving it lexically is wrong across symlinks.
+pub fn normalize_path(path: &str) -> PathBuf {
+    let expanded = if path == "~" {
+        dirs_next::home_dir().unwrap_or_else(|| PathBuf::from(path))
+    } else {
+        config::expand_home(path)
+    };
+    let absolute = if expanded.is_absolute() {
+        expanded
+    } else {
+        std::env::current_dir()
+            .map(|cwd| cwd.join(&expanded))
+            .unwrap_or(expanded)
+    };
+    absolute
+        .components()
+        .filter(|c| !matches!(c, std::path::Component::CurDir))
+        .collect()
+}
+
+/// Key under which two paths to the same file compare equal: the canonical
+/// path when it exists, else the normalized path
+fn path_identity(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| normalize_path(&path.to_string_lossy()))
+}
+
+/// True if `path` is one of the `configs.disabled` entries (`~/` expanded,
+/// symlinks and relative paths resolved)
+pub fn is_source_disabled(path: &Path, disabled: &[String]) -> bool {
+    if disabled.is_empty() {
+        return false;
+    }
+    let id = path_identity(path);
+    disabled
+        .iter()
+        .any(|d| path_identity(&config::expand_home(d)) == id)
+}
+
 /// OpenShift CLI binary for subprocess calls.
 /// Override with **`K8PK_OC`** (absolute path or `oc`) for CI, fake `oc` scripts, or non-standard installs.
 pub fn oc_cli_path() -> PathBuf {
@@ -565,14 +1353,12 @@ pub fn find_fast_cli() -> Result<String> {
 }
 
 const NS_LIST_TIMEOUT_SECS: u64 = 5;
+const POD_LIST_TIMEOUT_SECS: u64 = 10;
 const NS_CACHE_TTL_SECS: u64 = 60;
 
 fn ns_cache_path(context: &str) -> Option<PathBuf> {
-    let home = dirs_next::home_dir()?;
-    Some(
-        home.join(".local/share/k8pk/ns_cache")
-            .join(format!("{}.json", sanitize_filename(context))),
-    )
+    let dir = crate::storage::data_dir().ok()?.join("ns_cache");
+    Some(dir.join(format!("{}.json", sanitize_filename(context))))
 }
 
 fn read_ns_cache(context: &str) -> Option<Vec<String>> {
@@ -583,7 +1369,13 @@ fn read_ns_cache(context: &str) -> Option<Vec<String>> {
     if age > NS_CACHE_TTL_SECS {
         return None;
     }
-    let data = fs::read_to_string(&path).ok()?;
+    cached_namespaces(context)
+}
+
+/// Last namespace list cached for a context, however old (for completion,
+/// where a stale list beats waiting on the API server)
+pub fn cached_namespaces(context: &str) -> Option<Vec<String>> {
+    let data = fs::read_to_string(ns_cache_path(context)?).ok()?;
     serde_json::from_str(&data).ok()
 }
 
@@ -601,16 +1393,46 @@ fn write_ns_cache(context: &str, namespaces: &[String]) {
 
 /// List namespaces via kubectl. `kubeconfig` may be a single file path or a
 /// colon-separated KUBECONFIG list.
+///
+/// For OpenShift contexts, falls back to the projects API when `get ns` is
+/// forbidden (or no CLI is installed): regular OCP users can list their
+/// projects but not cluster-wide namespaces.
 pub fn list_namespaces(context: &str, kubeconfig: Option<&str>) -> Result<Vec<String>> {
     if let Some(cached) = read_ns_cache(context) {
         return Ok(cached);
     }
+    if config::offline() {
+        return cached_namespaces(context)
+            .ok_or_else(|| K8pkError::Offline("listing namespaces (none cached)".into()));
+    }
+
+    let mut namespaces = match list_namespaces_cli(context, kubeconfig) {
+        Ok(ns) => ns,
+        Err(e) => match list_ocp_projects(context, kubeconfig) {
+            Ok(Some(projects)) => projects,
+            _ => return Err(e),
+        },
+    };
+
+    namespaces.sort();
+    write_ns_cache(context, &namespaces);
+    Ok(namespaces)
+}
 
+/// kubectl/oc invocation for `context`, with a request timeout
+fn cli_command(context: &str, kubeconfig: Option<&str>) -> Result<(String, ProcCommand)> {
+    cli_command_with_timeout(context, kubeconfig, NS_LIST_TIMEOUT_SECS)
+}
+
+pub fn cli_command_with_timeout(
+    context: &str,
+    kubeconfig: Option<&str>,
+    timeout_secs: u64,
+) -> Result<(String, ProcCommand)> {
     let cli = find_fast_cli()?;
-    let timeout = format!("--request-timeout={}s", NS_LIST_TIMEOUT_SECS);
+    let timeout = format!("--request-timeout={}s", timeout_secs);
     let mut cmd = ProcCommand::new(&cli);
-    // ponytail: -o name is lighter than full JSON
-    cmd.args(["--context", context, &timeout, "get", "ns", "-o", "name"]);
+    cmd.args(["--context", context, &timeout]);
 
     // ponytail: single-file --kubeconfig avoids parsing the mega merge list
     if let Some(kc) = kubeconfig {
@@ -620,6 +1442,21 @@ pub fn list_namespaces(context: &str, kubeconfig: Option<&str>) -> Result<Vec<St
             cmd.env("KUBECONFIG", kc);
         }
     }
+    Ok((cli, cmd))
+}
+
+fn list_namespaces_cli(context: &str, kubeconfig: Option<&str>) -> Result<Vec<String>> {
+    list_namespaces_cli_with_timeout(context, kubeconfig, NS_LIST_TIMEOUT_SECS)
+}
+
+fn list_namespaces_cli_with_timeout(
+    context: &str,
+    kubeconfig: Option<&str>,
+    timeout_secs: u64,
+) -> Result<Vec<String>> {
+    let (cli, mut cmd) = cli_command_with_timeout(context, kubeconfig, timeout_secs)?;
+    // ponytail: -o name is lighter than full JSON
+    cmd.args(["get", "ns", "-o", "name"]);
 
     let output = cmd.output()?;
     if !output.status.success() {
@@ -631,7 +1468,7 @@ pub fn list_namespaces(context: &str, kubeconfig: Option<&str>) -> Result<Vec<St
         )));
     }
 
-    let mut namespaces: Vec<String> = String::from_utf8_lossy(&output.stdout)
+    let namespaces: Vec<String> = String::from_utf8_lossy(&output.stdout)
         .lines()
         .filter_map(|line| {
             let name = line
@@ -645,12 +1482,342 @@ pub fn list_namespaces(context: &str, kubeconfig: Option<&str>) -> Result<Vec<St
             }
         })
         .collect();
-
-    namespaces.sort();
-    write_ns_cache(context, &namespaces);
     Ok(namespaces)
 }
 
+/// Count pods by phase in every namespace of `context`, with one cluster-wide
+/// list call (for `k8pk ns --details`)
+pub fn pod_phases_by_namespace(
+    context: &str,
+    kubeconfig: Option<&str>,
+) -> Result<HashMap<String, BTreeMap<String, usize>>> {
+    config::require_online("counting pods")?;
+    let (cli, mut cmd) = cli_command_with_timeout(context, kubeconfig, POD_LIST_TIMEOUT_SECS)?;
+    // Two columns instead of full pod JSON keeps large clusters fast
+    cmd.args([
+        "get",
+        "pods",
+        "--all-namespaces",
+        "--no-headers",
+        "-o",
+        "custom-columns=NS:.metadata.namespace,PHASE:.status.phase",
+    ]);
+    let output = cmd.output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(K8pkError::CommandFailed(format!(
+            "{} get pods failed: {}",
+            cli,
+            stderr.trim()
+        )));
+    }
+    Ok(parse_pod_phases(&String::from_utf8_lossy(&output.stdout)))
+}
+
+fn parse_pod_phases(output: &str) -> HashMap<String, BTreeMap<String, usize>> {
+    let mut phases: HashMap<String, BTreeMap<String, usize>> = HashMap::new();
+    for line in output.lines() {
+        let mut cols = line.split_whitespace();
+        let (Some(ns), Some(phase)) = (cols.next(), cols.next()) else {
+            continue;
+        };
+        *phases
+            .entry(ns.to_string())
+            .or_default()
+            .entry(phase.to_string())
+            .or_default() += 1;
+    }
+    phases
+}
+
+/// List project names through `/apis/project.openshift.io/v1/projects` using the
+/// context's bearer token. `Ok(None)` when the context is not OpenShift or has
+/// no token to call the API with.
+fn list_ocp_projects(context: &str, kubeconfig: Option<&str>) -> Result<Option<Vec<String>>> {
+    let Some(api) = BearerApi::for_context(context, kubeconfig, NS_LIST_TIMEOUT_SECS)? else {
+        return Ok(None);
+    };
+    if detect_cluster_type(context, Some(&api.server)) != "ocp" {
+        return Ok(None);
+    }
+    let resp = api
+        .get("/apis/project.openshift.io/v1/projects")
+        .send()
+        .map_err(|e| K8pkError::HttpError(e.to_string()))?;
+    if !resp.status().is_success() {
+        return Err(K8pkError::HttpError(format!(
+            "listing projects failed: HTTP {}",
+            resp.status()
+        )));
+    }
+    let body: serde_json::Value = resp
+        .json()
+        .map_err(|e| K8pkError::HttpError(e.to_string()))?;
+    Ok(Some(parse_project_list(&body)))
+}
+
+/// Direct HTTP access to a context's API server with the user's bearer token
+/// (`token` or `tokenFile`), trusting the cluster's CA
+pub struct BearerApi {
+    client: reqwest::blocking::Client,
+    pub server: String,
+    token: String,
+}
+
+impl BearerApi {
+    /// `Ok(None)` when the context's user has no bearer token (exec plugins,
+    /// client certificates), so callers fall back to kubectl
+    pub fn for_context(
+        context: &str,
+        kubeconfig: Option<&str>,
+        timeout_secs: u64,
+    ) -> Result<Option<Self>> {
+        let paths: Vec<PathBuf> = match kubeconfig {
+            Some(kc) => split_kubeconfig_env(kc.as_ref()),
+            None => match std::env::var_os("KUBECONFIG") {
+                Some(kc) => split_kubeconfig_env(&kc),
+                None => {
+                    let home = dirs_next::home_dir().ok_or(K8pkError::NoHomeDir)?;
+                    vec![home.join(".kube/config")]
+                }
+            },
+        };
+        let cfg = load_merged(&paths)?;
+        let ctx = cfg
+            .find_context(context)
+            .ok_or_else(|| K8pkError::ContextNotFound(context.to_string()))?;
+        let (cluster_name, user_name) = extract_context_refs(&ctx.rest)?;
+        let Some(cluster) = cfg.find_cluster(&cluster_name) else {
+            return Ok(None);
+        };
+        let Some(server) = extract_server_url_from_cluster(&cluster.rest) else {
+            return Ok(None);
+        };
+        let user = cfg.find_user(&user_name).map(|u| &u.rest["user"]);
+        let token = match user.and_then(|u| u["token"].as_str()) {
+            Some(t) => t.to_string(),
+            None => match user.and_then(|u| u["tokenFile"].as_str()) {
+                Some(file) => fs::read_to_string(file)?.trim().to_string(),
+                None => return Ok(None),
+            },
+        };
+
+        let cluster_rest = &cluster.rest["cluster"];
+        let insecure = cluster_rest["insecure-skip-tls-verify"].as_bool() == Some(true);
+        let mut builder = reqwest::blocking::Client::builder()
+            .danger_accept_invalid_certs(insecure)
+            .timeout(std::time::Duration::from_secs(timeout_secs));
+        let ca_pem = if let Some(data) = cluster_rest["certificate-authority-data"].as_str() {
+            use base64::Engine;
+            Some(
+                base64::engine::general_purpose::STANDARD
+                    .decode(data.trim())
+                    .map_err(|e| {
+                        K8pkError::InvalidKubeconfig(format!(
+                            "bad certificate-authority-data: {}",
+                            e
+                        ))
+                    })?,
+            )
+        } else if let Some(file) = cluster_rest["certificate-authority"].as_str() {
+            Some(fs::read(file)?)
+        } else {
+            None
+        };
+        if let Some(pem) = ca_pem {
+            let cert = reqwest::Certificate::from_pem(&pem)
+                .map_err(|e| K8pkError::HttpError(e.to_string()))?;
+            builder = builder.add_root_certificate(cert);
+        }
+        let client = builder
+            .build()
+            .map_err(|e| K8pkError::HttpError(e.to_string()))?;
+        Ok(Some(BearerApi {
+            client,
+            server: server.trim_end_matches('/').to_string(),
+            token,
+        }))
+    }
+
+    pub fn get(&self, path: &str) -> reqwest::blocking::RequestBuilder {
+        self.client
+            .get(format!("{}{}", self.server, path))
+            .bearer_auth(&self.token)
+    }
+
+    pub fn post(&self, path: &str) -> reqwest::blocking::RequestBuilder {
+        self.client
+            .post(format!("{}{}", self.server, path))
+            .bearer_auth(&self.token)
+    }
+}
+
+/// Request timeout for each [`ClusterFacts`] query
+const PEEK_TIMEOUT_SECS: u64 = 2;
+/// How long fetched [`ClusterFacts`] are reused
+const PEEK_CACHE_TTL_SECS: u64 = 300;
+
+/// Quick facts about a context's cluster for the picker preview
+/// (`k8pk which --peek`). Each is None when the query failed or was forbidden.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClusterFacts {
+    /// Server `gitVersion`, e.g. `v1.29.3`
+    pub version: Option<String>,
+    pub nodes: Option<usize>,
+    pub namespaces: Option<usize>,
+    /// When they were fetched (unix seconds)
+    pub fetched_at: u64,
+}
+
+impl ClusterFacts {
+    /// Older than the cache TTL (shown because a fresh fetch failed)
+    pub fn is_stale(&self) -> bool {
+        now_secs().saturating_sub(self.fetched_at) > PEEK_CACHE_TTL_SECS
+    }
+}
+
+fn peek_cache_path(context: &str) -> Option<PathBuf> {
+    let dir = crate::storage::data_dir().ok()?.join("peek_cache");
+    Some(dir.join(format!("{}.json", sanitize_filename(context))))
+}
+
+/// Facts for `context`: cached ones when younger than five minutes, else
+/// fetched, with the server version, node count, and namespace count queried
+/// in parallel and a short timeout each. When the cluster can't be reached,
+/// the last cached facts (however old), if any.
+pub fn cluster_facts(context: &str, kubeconfig: Option<&str>) -> Option<ClusterFacts> {
+    let cached = cached_cluster_facts(context);
+    if config::offline() || cached.as_ref().is_some_and(|c| !c.is_stale()) {
+        return cached;
+    }
+
+    let fetched = fetch_cluster_facts(context, kubeconfig);
+    if fetched.version.is_none() && fetched.nodes.is_none() && fetched.namespaces.is_none() {
+        return cached;
+    }
+    if let Some(path) = peek_cache_path(context) {
+        if let Some(parent) = path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        if let Ok(json) = serde_json::to_string(&fetched) {
+            let _ = write_restricted(&path, &json);
+        }
+    }
+    Some(fetched)
+}
+
+/// Last facts fetched for `context`, however old (no cluster access)
+pub fn cached_cluster_facts(context: &str) -> Option<ClusterFacts> {
+    let data = fs::read_to_string(peek_cache_path(context)?).ok()?;
+    serde_json::from_str(&data).ok()
+}
+
+fn fetch_cluster_facts(context: &str, kubeconfig: Option<&str>) -> ClusterFacts {
+    std::thread::scope(|s| {
+        let version = s.spawn(|| server_version(context, kubeconfig));
+        let nodes = s.spawn(|| {
+            let (_, mut cmd) =
+                cli_command_with_timeout(context, kubeconfig, PEEK_TIMEOUT_SECS).ok()?;
+            let output = cmd.args(["get", "nodes", "-o", "name"]).output().ok()?;
+            output
+                .status
+                .success()
+                .then(|| String::from_utf8_lossy(&output.stdout).lines().count())
+        });
+        let namespaces = s.spawn(|| {
+            if let Some(cached) = read_ns_cache(context) {
+                return Some(cached.len());
+            }
+            let mut namespaces =
+                list_namespaces_cli_with_timeout(context, kubeconfig, PEEK_TIMEOUT_SECS).ok()?;
+            namespaces.sort();
+            write_ns_cache(context, &namespaces);
+            Some(namespaces.len())
+        });
+        ClusterFacts {
+            version: version.join().ok().flatten(),
+            nodes: nodes.join().ok().flatten(),
+            namespaces: namespaces.join().ok().flatten(),
+            fetched_at: now_secs(),
+        }
+    })
+}
+
+fn server_version(context: &str, kubeconfig: Option<&str>) -> Option<String> {
+    let (_, mut cmd) = cli_command_with_timeout(context, kubeconfig, PEEK_TIMEOUT_SECS).ok()?;
+    // Exits non-zero when only the client version could be printed
+    let output = cmd.args(["version", "-o", "json"]).output().ok()?;
+    parse_server_version(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// `serverVersion.gitVersion` from `kubectl version -o json` / `oc version -o json`
+fn parse_server_version(json: &str) -> Option<String> {
+    let value: serde_json::Value = serde_json::from_str(json).ok()?;
+    value["serverVersion"]["gitVersion"]
+        .as_str()
+        .map(String::from)
+}
+
+/// Whether `namespace` exists in the cluster. Falls back to the namespace
+/// list (OpenShift projects included) when reading the namespace is forbidden.
+pub fn namespace_exists(context: &str, kubeconfig: Option<&str>, namespace: &str) -> Result<bool> {
+    config::require_online("checking the namespace")?;
+    let (cli, mut cmd) = cli_command(context, kubeconfig)?;
+    cmd.args(["get", "ns", namespace, "-o", "name"]);
+    let output = cmd.output()?;
+    if output.status.success() {
+        return Ok(true);
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if stderr.contains("NotFound") {
+        return Ok(false);
+    }
+    if stderr.contains("Forbidden") || stderr.contains("forbidden") {
+        return Ok(list_namespaces(context, kubeconfig)?
+            .iter()
+            .any(|ns| ns == namespace));
+    }
+    Err(K8pkError::CommandFailed(format!(
+        "{} get ns {} failed: {}",
+        cli,
+        namespace,
+        stderr.trim()
+    )))
+}
+
+/// Create `namespace` with kubectl/oc and drop the cached namespace list
+pub fn create_namespace(context: &str, kubeconfig: Option<&str>, namespace: &str) -> Result<()> {
+    config::require_online("creating a namespace")?;
+    let (cli, mut cmd) = cli_command(context, kubeconfig)?;
+    cmd.args(["create", "namespace", namespace]);
+    let output = cmd.output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(K8pkError::CommandFailed(format!(
+            "{} create namespace {} failed: {}",
+            cli,
+            namespace,
+            stderr.trim()
+        )));
+    }
+    if let Some(path) = ns_cache_path(context) {
+        let _ = fs::remove_file(path);
+    }
+    Ok(())
+}
+
+fn parse_project_list(body: &serde_json::Value) -> Vec<String> {
+    body["items"]
+        .as_array()
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|p| p["metadata"]["name"].as_str().map(String::from))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
 /// Sanitize a string for use in filenames
 pub fn sanitize_filename(s: &str) -> String {
     s.chars()
@@ -1268,6 +2435,69 @@ current-context: dev
         assert_eq!(mode, 0o600, "file should be owner read/write only");
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn test_write_restricted_follows_symlink() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("real.yaml");
+        let link = dir.path().join("config");
+        fs::write(&target, "old").unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+        write_restricted(&link, "new").unwrap();
+        assert!(fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "new");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_acquire_lock_serialises_read_modify_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("counter");
+        let lock = dir.path().join("counter.lock");
+        write_restricted(&path, "0").unwrap();
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    for _ in 0..10 {
+                        let _guard = acquire_lock(&lock).unwrap();
+                        let n: u32 = fs::read_to_string(&path).unwrap().parse().unwrap();
+                        write_restricted(&path, &(n + 1).to_string()).unwrap();
+                    }
+                });
+            }
+        });
+        assert_eq!(fs::read_to_string(&path).unwrap(), "80");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_lock_kubeconfig_is_removed_on_release() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config");
+        write_restricted(&path, "0").unwrap();
+        // Lock files come and go while others wait on them
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    for _ in 0..10 {
+                        let _guard = lock_kubeconfig(&path).unwrap();
+                        let n: u32 = fs::read_to_string(&path).unwrap().parse().unwrap();
+                        write_restricted(&path, &(n + 1).to_string()).unwrap();
+                    }
+                });
+            }
+        });
+        assert_eq!(fs::read_to_string(&path).unwrap(), "80");
+        let left: Vec<_> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(left, ["config"]);
+    }
+
     #[test]
     fn test_prune_to_context() {
         let cfg = sample_kubeconfig();
@@ -1281,6 +2511,61 @@ current-context: dev
         assert_eq!(pruned.current_context, Some("dev".to_string()));
     }
 
+    #[test]
+    fn test_redact_secrets() {
+        let mut cfg: KubeConfig = serde_yaml_ng::from_str(
+            r#"
+clusters:
+- name: c
+  cluster:
+    server: https://api.example.com
+    certificate-authority-data: Q0E=
+contexts:
+- name: dev
+  context: {cluster: c, user: u}
+users:
+- name: u
+  user:
+    token: abc
+    client-certificate-data: Q0VSVA==
+    client-key-data: S0VZ
+    username: admin
+    password: hunter2
+- name: oidc
+  user:
+    auth-provider:
+      name: oidc
+      config: {client-id: k8s, client-secret: s, id-token: i, refresh-token: r}
+- name: eks
+  user:
+    exec:
+      command: aws
+      env:
+      - {name: AWS_PROFILE, value: prod}
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            secret_values(&cfg),
+            ["S0VZ", "abc", "hunter2", "i", "prod", "r", "s"]
+        );
+        assert_eq!(redact_secrets(&mut cfg), 7);
+        let out = serde_yaml_ng::to_string(&cfg).unwrap();
+        for secret in ["abc", "S0VZ", "hunter2", ": s\n", ": i\n", ": r\n", "prod"] {
+            assert!(!out.contains(secret), "{secret} leaked:\n{out}");
+        }
+        for kept in [
+            "https://api.example.com",
+            "Q0E=",
+            "Q0VSVA==",
+            "admin",
+            "k8s",
+            "AWS_PROFILE",
+        ] {
+            assert!(out.contains(kept), "{kept} missing:\n{out}");
+        }
+    }
+
     #[test]
     fn test_prune_to_context_not_found() {
         let cfg = sample_kubeconfig();
@@ -1305,6 +2590,46 @@ current-context: dev
         assert_eq!(url, Some("https://dev.example.com:6443".to_string()));
     }
 
+    #[test]
+    fn test_list_ocp_projects_mock_http() {
+        use crate::test_http::{spawn_one_shot, HttpResponse};
+        let base = spawn_one_shot(HttpResponse::json(
+            200,
+            r#"{"kind":"ProjectList","items":[{"metadata":{"name":"team-a"}},{"metadata":{"name":"team-b"}}]}"#,
+        ));
+        let dir = tempfile::tempdir().unwrap();
+        let kc = dir.path().join("ocp.yaml");
+        std::fs::write(
+            &kc,
+            format!(
+                "apiVersion: v1\nkind: Config\nclusters:\n- name: c\n  cluster:\n    server: {base}\n\
+                 contexts:\n- name: default/api-ocp-example-com:6443/dev\n  context:\n    cluster: c\n    user: u\n\
+                 users:\n- name: u\n  user:\n    token: sha256~abc\n"
+            ),
+        )
+        .unwrap();
+        // Neither the context name nor the (mock) server URL looks like OpenShift: no fallback
+        let kc_str = kc.to_string_lossy().to_string();
+        assert!(
+            list_ocp_projects("default/api-ocp-example-com:6443/dev", Some(&kc_str))
+                .unwrap()
+                .is_none()
+        );
+
+        std::fs::write(
+            &kc,
+            std::fs::read_to_string(&kc).unwrap().replace(
+                "default/api-ocp-example-com:6443/dev",
+                "default/api.ocp.example.com:6443/dev",
+            ),
+        )
+        .unwrap();
+        let projects = list_ocp_projects("default/api.ocp.example.com:6443/dev", Some(&kc_str))
+            .unwrap()
+            .unwrap();
+        assert_eq!(projects, vec!["team-a", "team-b"]);
+    }
+
     #[test]
     fn test_get_server_for_context() {
         let cfg = sample_kubeconfig();
@@ -1336,6 +2661,18 @@ current-context: dev
         }
     }
 
+    #[test]
+    fn test_get_namespace_for_context() {
+        let mut cfg = sample_kubeconfig();
+        assert!(get_namespace_for_context(&cfg, "dev").is_none());
+        set_context_namespace(&mut cfg, "dev", "kube-system").unwrap();
+        assert_eq!(
+            get_namespace_for_context(&cfg, "dev").as_deref(),
+            Some("kube-system")
+        );
+        assert!(get_namespace_for_context(&cfg, "nonexistent").is_none());
+    }
+
     #[test]
     fn test_set_context_namespace_not_found() {
         let mut cfg = sample_kubeconfig();
@@ -1362,6 +2699,140 @@ current-context: dev
         assert_eq!(parsed.users.len(), cfg.users.len());
     }
 
+    fn server_of(cfg: &KubeConfig, cluster: &str) -> Option<String> {
+        cfg.find_cluster(cluster)
+            .and_then(|c| extract_server_url_from_cluster(&c.rest))
+    }
+
+    #[test]
+    fn test_merge_configs_strategies() {
+        let a: KubeConfig = serde_yaml_ng::from_str(
+            "clusters:\n- name: c\n  cluster: {server: https://a}\n- name: c\n  cluster: {server: https://a2}\ncurrent-context: x\n",
+        )
+        .unwrap();
+        let b: KubeConfig = serde_yaml_ng::from_str(
+            "clusters:\n- name: c\n  cluster: {server: https://b}\n- name: d\n  cluster: {server: https://d}\ncurrent-context: y\n",
+        )
+        .unwrap();
+        let files = || {
+            vec![
+                (PathBuf::from("/a"), a.clone()),
+                (PathBuf::from("/b"), b.clone()),
+            ]
+        };
+
+        let (merged, shadowed) = merge_configs(files(), MergeStrategy::First).unwrap();
+        assert_eq!(server_of(&merged, "c").as_deref(), Some("https://a"));
+        assert_eq!(merged.current_context.as_deref(), Some("x"));
+        assert_eq!(merged.clusters.len(), 2);
+        // Duplicate within /a, then /b's entry hidden by /a
+        assert_eq!(shadowed.len(), 2);
+        assert_eq!(shadowed[0].file, shadowed[0].winner);
+        assert_eq!(
+            shadowed[1].to_string(),
+            "cluster 'c' in /b is shadowed by /a"
+        );
+
+        let (merged, shadowed) = merge_configs(files(), MergeStrategy::Last).unwrap();
+        assert_eq!(server_of(&merged, "c").as_deref(), Some("https://b"));
+        assert_eq!(merged.current_context.as_deref(), Some("y"));
+        assert_eq!(merged.clusters[0].name, "c");
+        assert_eq!(shadowed[1].file, PathBuf::from("/a"));
+
+        assert!(merge_configs(files(), MergeStrategy::Strict).is_err());
+        let single = vec![(PathBuf::from("/b"), b.clone())];
+        assert!(merge_configs(single, MergeStrategy::Strict).is_ok());
+    }
+
+    #[test]
+    fn test_sops_files_are_not_editable() {
+        assert!(is_sops_file(Path::new("/k/team.sops.yaml")));
+        assert!(is_sops_file(Path::new("team.sops.yml")));
+        assert!(!is_sops_file(Path::new("/k/sops.yaml")));
+        assert!(!is_sops_file(Path::new("/k.sops.yaml/config")));
+        assert!(ensure_editable(Path::new("/k/team.sops.yaml")).is_err());
+        assert!(ensure_editable(Path::new("/k/team.yaml")).is_ok());
+    }
+
+    #[test]
+    fn test_merge_records_context_sources() {
+        let a: KubeConfig =
+            serde_yaml_ng::from_str("contexts:\n- name: dev\n  context: {}\n").unwrap();
+        let b: KubeConfig = serde_yaml_ng::from_str(
+            "contexts:\n- name: dev\n  context: {}\n- name: prod\n  context: {}\n",
+        )
+        .unwrap();
+        let files = || {
+            vec![
+                (PathBuf::from("/a"), a.clone()),
+                (PathBuf::from("/b"), b.clone()),
+            ]
+        };
+        let (merged, _) = merge_configs(files(), MergeStrategy::First).unwrap();
+        assert_eq!(merged.sources["dev"], PathBuf::from("/a"));
+        assert_eq!(merged.sources["prod"], PathBuf::from("/b"));
+        let (merged, _) = merge_configs(files(), MergeStrategy::Last).unwrap();
+        assert_eq!(merged.sources["dev"], PathBuf::from("/b"));
+    }
+
+    #[test]
+    fn test_resolve_relative_paths_and_embed() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("certs")).unwrap();
+        fs::write(dir.path().join("certs/ca.crt"), "CA").unwrap();
+        fs::write(dir.path().join("certs/me.key"), "KEY").unwrap();
+        let mut cfg: KubeConfig = serde_yaml_ng::from_str(
+            r#"
+clusters:
+- name: c
+  cluster: {server: https://x, certificate-authority: ./certs/ca.crt}
+users:
+- name: u
+  user:
+    client-key: certs/me.key
+    tokenFile: /abs/token
+    exec: {command: ./bin/auth, args: []}
+- name: v
+  user:
+    exec: {command: aws}
+"#,
+        )
+        .unwrap();
+        resolve_relative_paths(&mut cfg, dir.path());
+        let user = cfg.users[0].rest.get("user").unwrap();
+        let key = dir.path().join("certs/me.key");
+        assert_eq!(user["client-key"].as_str(), key.to_str());
+        assert_eq!(user["tokenFile"].as_str(), Some("/abs/token"));
+        assert_eq!(
+            user["exec"]["command"].as_str(),
+            dir.path().join("bin/auth").to_str()
+        );
+        assert_eq!(
+            cfg.users[1].rest["user"]["exec"]["command"].as_str(),
+            Some("aws")
+        );
+
+        assert_eq!(embed_certs(&mut cfg).unwrap(), 2);
+        let cluster = cfg.clusters[0].rest.get("cluster").unwrap();
+        assert!(cluster.get("certificate-authority").is_none());
+        assert_eq!(cluster["certificate-authority-data"].as_str(), Some("Q0E="));
+        assert_eq!(
+            cfg.users[0].rest["user"]["client-key-data"].as_str(),
+            Some("S0VZ")
+        );
+        assert_eq!(
+            local_dependencies(&cfg),
+            [
+                format!(
+                    "user 'u' runs exec plugin '{}'",
+                    dir.path().join("bin/auth").display()
+                ),
+                "user 'v' runs exec plugin 'aws'".to_string(),
+                "user 'u' reads its token from /abs/token".to_string(),
+            ]
+        );
+    }
+
     #[test]
     fn test_join_paths_for_env_empty() {
         assert_eq!(join_paths_for_env(&[]), None);
@@ -1382,6 +2853,40 @@ current-context: dev
         assert!(result.contains(':') || result.contains(';'));
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn test_split_kubeconfig_env_normalizes_and_dedups() {
+        let dir = tempfile::tempdir().unwrap();
+        let real = dir.path().join("config");
+        fs::write(&real, "").unwrap();
+        let link = dir.path().join("link");
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+        std::env::set_var("K8PK_TEST_KC_DIR", dir.path());
+
+        let value = format!(
+            "$K8PK_TEST_KC_DIR/config::{}/./config:{}:${{K8PK_TEST_KC_DIR}}/other",
+            dir.path().display(),
+            link.display()
+        );
+        let paths = split_kubeconfig_env(value.as_ref());
+        std::env::remove_var("K8PK_TEST_KC_DIR");
+        assert_eq!(paths, vec![real, dir.path().join("other")]);
+    }
+
+    #[test]
+    fn test_expand_env_vars_leaves_unset() {
+        std::env::set_var("K8PK_TEST_EXPAND", "/x");
+        assert_eq!(expand_env_vars("$K8PK_TEST_EXPAND/a"), "/x/a");
+        assert_eq!(expand_env_vars("${K8PK_TEST_EXPAND}b"), "/xb");
+        assert_eq!(
+            expand_env_vars("$K8PK_TEST_UNSET_VAR/a $"),
+            "$K8PK_TEST_UNSET_VAR/a $"
+        );
+        std::env::remove_var("K8PK_TEST_EXPAND");
+        assert!(normalize_path("rel/./f").is_absolute());
+        assert!(!normalize_path("./rel").to_string_lossy().contains("/./"));
+    }
+
     #[test]
     fn test_set_cluster_insecure() {
         let mut cfg = sample_kubeconfig();
@@ -1407,8 +2912,21 @@ current-context: dev
     #[test]
     fn test_get_cluster_insecure_for_context_after_set() {
         let mut cfg = sample_kubeconfig();
+        let k8pk: K8pkConfig = serde_yaml_ng::from_str(
+            "insecure_contexts: [prod]\nca_overrides:\n  \"*\": /etc/corp.pem\n",
+        )
+        .unwrap();
+        let plain = K8pkConfig::default();
+        assert_eq!(tls_mode(&cfg, "dev", &plain), TlsMode::SystemCa);
+        assert_eq!(tls_mode(&cfg, "dev", &k8pk), TlsMode::CaOverride);
+        assert_eq!(tls_mode(&cfg, "prod", &k8pk), TlsMode::InsecureByConfig);
         set_cluster_insecure(&mut cfg);
         assert!(get_cluster_insecure_for_context(&cfg, "dev"));
+        assert_eq!(tls_mode(&cfg, "dev", &plain), TlsMode::Insecure);
+
+        set_cluster_ca(&mut cfg, Path::new("/etc/corp.pem"));
+        assert!(!get_cluster_insecure_for_context(&cfg, "dev"));
+        assert_eq!(tls_mode(&cfg, "dev", &plain), TlsMode::CustomCa);
     }
 
     #[test]
@@ -1544,6 +3062,7 @@ current-context: dev
             configs: crate::config::ConfigsSection {
                 include: vec![include],
                 exclude: vec![exclude],
+                ..Default::default()
             },
             ..Default::default()
         };
@@ -1567,6 +3086,7 @@ current-context: dev
             configs: crate::config::ConfigsSection {
                 include: vec![file.to_string_lossy().to_string()],
                 exclude: vec![],
+                ..Default::default()
             },
             ..Default::default()
         };
@@ -1616,4 +3136,16 @@ current-context: dev
         let base = extract_base_cluster_name(name, Some("https://k8s.example.com"));
         assert_eq!(base, "my-cluster");
     }
+
+    #[test]
+    fn test_parse_server_version() {
+        let json = r#"{"clientVersion":{"gitVersion":"v1.30.1"},"serverVersion":{"gitVersion":"v1.29.3+k3s1"}}"#;
+        assert_eq!(parse_server_version(json).as_deref(), Some("v1.29.3+k3s1"));
+        // Unreachable server: only the client version
+        assert_eq!(
+            parse_server_version(r#"{"clientVersion":{"gitVersion":"v1.30.1"}}"#),
+            None
+        );
+        assert_eq!(parse_server_version(""), None);
+    }
 }
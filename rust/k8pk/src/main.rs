@@ -3,12 +3,24 @@
 //! Cross-terminal Kubernetes context/namespace switcher with isolated kubeconfigs.
 
 mod cli;
+mod clock;
 mod commands;
 mod config;
+mod encryption;
 mod error;
+mod i18n;
+mod install_source;
 mod kubeconfig;
+mod picker;
+mod prompt;
+mod provenance;
+mod remote;
+mod render;
 mod shell;
 mod state;
+mod storage;
+mod theme;
+mod yaml_edit;
 
 /// Minimal HTTP mock servers for unit tests (Rancher-style APIs). See `test_http.rs`.
 #[cfg(test)]
@@ -16,6 +28,7 @@ mod test_http;
 
 use crate::cli::{Cli, Command};
 use crate::error::{K8pkError, Result};
+use crate::prompt::Ask;
 use crate::state::CurrentState;
 
 use clap::Parser;
@@ -23,7 +36,7 @@ use inquire::MultiSelect;
 use std::env;
 use std::fs;
 use std::io::{self, IsTerminal};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Get default kubeconfig path (~/.kube/config)
 fn default_kubeconfig_path() -> Result<PathBuf> {
@@ -34,22 +47,92 @@ fn default_kubeconfig_path() -> Result<PathBuf> {
 
 fn main() {
     if let Err(e) = run() {
-        if matches!(e, K8pkError::Cancelled) {
-            std::process::exit(130); // 128 + SIGINT
+        if !matches!(e, K8pkError::Cancelled) {
+            eprintln!("{}", e);
         }
-        eprintln!("{}", e);
-        std::process::exit(1);
+        std::process::exit(e.exit_code());
     }
 }
 
+/// `k8pk back` / `forward` as the `k8pk ctx` switch to the entry `steps`
+/// away in this shell's history
+fn history_switch(steps: isize, output: Option<String>, no_tmux: bool) -> Result<Command> {
+    let entry = commands::navigate(steps)?;
+    Ok(Command::Ctx(cli::CtxArgs {
+        context: Some(entry.context),
+        namespace: entry.namespace,
+        recursive: false,
+        output,
+        no_tmux,
+        insecure_skip_tls: false,
+        no_session_check: false,
+    }))
+}
+
 fn run() -> Result<()> {
     let cli = Cli::parse();
     if let Some(ref p) = cli.oc {
         env::set_var("K8PK_OC", p.as_os_str());
     }
+    if cli.no_input {
+        prompt::set_no_input();
+    }
+    if cli.quiet {
+        render::set_quiet();
+    }
+    if cli.no_refresh {
+        provenance::set_no_refresh();
+    }
+    if cli.no_color {
+        theme::set_no_color();
+    }
+    if cli.no_motd {
+        commands::set_no_motd();
+    }
+    if cli.offline {
+        config::set_offline();
+    }
+    if cli.yaml {
+        render::set_format(render::OutputFormat::Yaml);
+    } else if let Some(format) = cli.format {
+        match commands::env_file_format(format) {
+            None => render::set_format(format),
+            Some(_) if matches!(cli.command, Some(Command::Env(_))) => {}
+            Some(name) => {
+                return Err(K8pkError::InvalidArgument(format!(
+                    "--format {} only applies to k8pk env",
+                    name
+                )))
+            }
+        }
+    }
+    if let Some(kind) = cli.picker {
+        picker::set_picker(kind);
+    }
+    if let Some(strategy) = cli.merge_strategy {
+        kubeconfig::set_merge_strategy(strategy);
+    }
+    if !cli.alias.is_empty() {
+        let aliases = cli
+            .alias
+            .iter()
+            .map(|def| config::parse_alias(def))
+            .collect::<Result<Vec<_>>>()?;
+        config::set_aliases(aliases);
+    }
     // ponytail: -v kept for clap compat; tracing dropped
     let _ = cli.verbose;
     let k8pk_config = config::load()?;
+    if let Some(ref kind) = k8pk_config.picker {
+        picker::set_config_picker(kind);
+    }
+    if let Some(ref name) = k8pk_config.theme {
+        theme::set_config_theme(name);
+    }
+    if let Some(ref lang) = k8pk_config.language {
+        i18n::set_config_language(lang);
+    }
+    inquire::set_global_render_config(theme::stderr().render_config());
 
     let paths =
         kubeconfig::resolve_paths(cli.kubeconfig.as_deref(), &cli.kubeconfig_dir, &k8pk_config)?;
@@ -57,14 +140,34 @@ fn run() -> Result<()> {
     let kubeconfig_env = kubeconfig::join_paths_for_env(&paths);
 
     // Default to interactive picker if no command specified
-    let command = cli.command.unwrap_or(Command::Pick {
+    let command = cli.command.unwrap_or(Command::Pick(cli::PickArgs {
         filter: None,
         output: None,
         detail: false,
         no_tmux: false,
         insecure_skip_tls: false,
         no_session_check: false,
-    });
+    }));
+
+    // `back` and `forward` switch to an entry of this shell's history, which
+    // is applied as recorded (no remembered namespace)
+    let navigated = matches!(
+        command,
+        Command::Back(cli::BackArgs { .. }) | Command::Forward(cli::ForwardArgs { .. })
+    );
+    let command = match command {
+        Command::Back(cli::BackArgs {
+            steps,
+            output,
+            no_tmux,
+        }) => history_switch(-(steps as isize), output, no_tmux)?,
+        Command::Forward(cli::ForwardArgs {
+            steps,
+            output,
+            no_tmux,
+        }) => history_switch(steps as isize, output, no_tmux)?,
+        other => other,
+    };
 
     let session_check_ttl = k8pk_config.pick.as_ref().map(|p| p.session_check_ttl);
     let clusters_only = k8pk_config
@@ -73,27 +176,54 @@ fn run() -> Result<()> {
         .map(|p| p.clusters_only)
         .unwrap_or(false);
 
+    let quiet = render::quiet();
+
     match command {
-        Command::Contexts { json, path } => {
-            if path {
-                let ctx_paths = kubeconfig::list_contexts_with_paths(&paths)?;
+        Command::Contexts(cli::ContextsArgs {
+            json,
+            path,
+            wide,
+            filter,
+            cluster_type,
+            sort,
+        }) => {
+            let json = render::structured(json);
+            let filter = commands::ContextFilter {
+                pattern: filter,
+                cluster_type,
+                ..Default::default()
+            };
+            // --path has always listed by name
+            let sort = if path {
+                sort.or(Some(commands::SortKey::Name))
+            } else {
+                sort
+            };
+            let rows = commands::select_context_rows(&paths, &filter, sort)?;
+            if wide {
                 if json {
-                    println!("{}", serde_json::to_string(&ctx_paths)?);
+                    println!("{}", render::to_string(&rows)?);
                 } else {
-                    let mut names: Vec<_> = ctx_paths.keys().collect();
-                    names.sort();
-                    for name in names {
-                        println!("{}\t{}", name, ctx_paths[name].display());
+                    commands::print_context_table(&rows);
+                }
+            } else if path {
+                if json {
+                    let ctx_paths: std::collections::BTreeMap<&str, &PathBuf> = rows
+                        .iter()
+                        .filter_map(|r| Some((r.context.as_str(), r.source.as_ref()?)))
+                        .collect();
+                    println!("{}", render::to_string(&ctx_paths)?);
+                } else {
+                    for r in &rows {
+                        if let Some(source) = &r.source {
+                            println!("{}\t{}", r.context, source.display());
+                        }
                     }
                 }
             } else {
-                let merged = kubeconfig::load_merged(&paths)?;
-                let names = merged.context_names();
-                if names.is_empty() {
-                    return Err(K8pkError::NoContexts);
-                }
+                let names: Vec<&str> = rows.iter().map(|r| r.context.as_str()).collect();
                 if json {
-                    println!("{}", serde_json::to_string(&names)?);
+                    println!("{}", render::to_string(&names)?);
                 } else {
                     for name in names {
                         println!("{}", name);
@@ -102,42 +232,111 @@ fn run() -> Result<()> {
             }
         }
 
-        Command::Gen {
+        Command::Gen(cli::GenArgs {
             context,
             out,
             namespace,
+            redact,
+            embed_certs,
             json,
-            quiet,
-        } => {
+        }) => {
             let merged = kubeconfig::load_merged(&paths)?;
+            let context = match context {
+                Some(c) => commands::unique_context(&c, &merged.context_names())?,
+                None => merged
+                    .current_context
+                    .clone()
+                    .filter(|c| !c.is_empty())
+                    .ok_or_else(|| {
+                        K8pkError::InvalidArgument(
+                            "current-context is not set; pass --context".into(),
+                        )
+                    })?,
+            };
             let mut pruned = kubeconfig::prune_to_context(&merged, &context)?;
             if let Some(ref ns) = namespace {
                 kubeconfig::set_context_namespace(&mut pruned, &context, ns)?;
             }
+            if embed_certs {
+                kubeconfig::embed_certs(&mut pruned)?;
+                if !quiet {
+                    for dep in kubeconfig::local_dependencies(&pruned) {
+                        eprintln!("Warning: not self-contained: {}", dep);
+                    }
+                }
+            }
+            let redacted = redact.then(|| kubeconfig::redact_secrets(&mut pruned));
             let yaml = serde_yaml_ng::to_string(&pruned)?;
+            let Some(out) = out else {
+                print!("{}", yaml);
+                return Ok(());
+            };
             kubeconfig::write_restricted(&out, &yaml)?;
-            if json {
+            if render::structured(json) {
                 let j = serde_json::json!({
                     "context": context,
                     "namespace": namespace.as_ref(),
-                    "path": out.to_string_lossy()
+                    "path": out.to_string_lossy(),
+                    "redacted": redacted,
                 });
-                println!("{}", serde_json::to_string_pretty(&j)?);
+                println!("{}", render::to_string(&j)?);
             } else if !quiet {
                 println!(
-                    "Generated kubeconfig for context '{}' at {}",
+                    "Generated {}kubeconfig for context '{}' at {}",
+                    if redact { "redacted " } else { "" },
                     context,
                     out.display()
                 );
             }
         }
 
+        Command::ExportSafe(cli::ExportSafeArgs { context, out, json }) => {
+            let json = render::structured(json);
+            let merged = kubeconfig::load_merged(&paths)?;
+            let mut cfg = match context {
+                Some(ref c) => {
+                    let context = commands::unique_context(c, &merged.context_names())?;
+                    kubeconfig::prune_to_context(&merged, &context)?
+                }
+                None => merged,
+            };
+            let redacted = kubeconfig::redact_secrets(&mut cfg);
+            let yaml = serde_yaml_ng::to_string(&cfg)?;
+            match out {
+                Some(out) => {
+                    kubeconfig::write_restricted(&out, &yaml)?;
+                    if json {
+                        let j = serde_json::json!({
+                            "path": out.to_string_lossy(),
+                            "contexts": cfg.context_names(),
+                            "redacted": redacted,
+                        });
+                        println!("{}", render::to_string(&j)?);
+                    } else if !quiet {
+                        println!(
+                            "Wrote {} context(s) to {} ({} secret value(s) redacted)",
+                            cfg.contexts.len(),
+                            out.display(),
+                            redacted
+                        );
+                    }
+                }
+                None => {
+                    print!("{}", yaml);
+                    if !quiet {
+                        eprintln!("{} secret value(s) redacted", redacted);
+                    }
+                }
+            }
+        }
+
         Command::Current { json } => {
+            let json = render::structured(json);
             let merged = kubeconfig::load_merged(&paths)?;
             if let Some(ctx) = merged.current_context {
                 if json {
                     let j = serde_json::json!({ "context": ctx });
-                    println!("{}", serde_json::to_string_pretty(&j)?);
+                    println!("{}", render::to_string(&j)?);
                 } else {
                     println!("{}", ctx);
                 }
@@ -146,12 +345,14 @@ fn run() -> Result<()> {
             }
         }
 
-        Command::Namespaces { context, json } => {
+        Command::Namespaces(cli::NamespacesArgs { context, json }) => {
+            let json = render::structured(json);
             // Auto-detect context: explicit flag > K8PK_CONTEXT > current-context
             let context = match context {
-                Some(c) => c,
+                Some(c) => commands::resolve_context(&kubeconfig::load_merged(&paths)?, &c)?,
                 None => {
                     let state = CurrentState::from_env();
+                    state.ensure_not_expired()?;
                     if let Some(ctx) = state.context {
                         ctx
                     } else {
@@ -169,7 +370,7 @@ fn run() -> Result<()> {
                 return Err(K8pkError::NoNamespaces(context));
             }
             if json {
-                println!("{}", serde_json::to_string(&namespaces)?);
+                println!("{}", render::to_string(&namespaces)?);
             } else {
                 for ns in namespaces {
                     println!("{}", ns);
@@ -177,33 +378,69 @@ fn run() -> Result<()> {
             }
         }
 
-        Command::Env {
+        Command::Env(cli::EnvArgs {
             context,
             namespace,
             shell,
             detail,
-        } => {
-            let context = config::resolve_alias(&context);
+        }) => {
+            if commands::ENV_FILE_FORMATS.contains(&shell.as_str()) {
+                return Err(K8pkError::InvalidArgument(format!(
+                    "{} is not a shell; use --format {}",
+                    shell, shell
+                )));
+            }
+            let syntax = cli
+                .format
+                .and_then(commands::env_file_format)
+                .unwrap_or(&shell);
+            let context = commands::resolve_context(&kubeconfig::load_merged(&paths)?, &context)?;
             let kubeconfig =
                 commands::ensure_isolated_kubeconfig(&context, namespace.as_deref(), &paths)?;
             commands::print_env_exports(
                 &context,
                 namespace.as_deref(),
                 &kubeconfig,
-                &shell,
+                syntax,
                 detail,
                 false,
             )?;
         }
 
-        Command::Pick {
+        Command::Direnv(cli::DirenvArgs {
+            context,
+            namespace,
+            dir,
+            print,
+            watch,
+            json,
+        }) => {
+            let json = render::structured(json);
+            let context = commands::resolve_context(&kubeconfig::load_merged(&paths)?, &context)?;
+            let dir = match dir {
+                Some(d) => d,
+                None => std::env::current_dir()?,
+            };
+            let result = commands::direnv(&context, namespace.as_deref(), &dir, print, &paths)?;
+            if json {
+                println!("{}", render::to_string(&result)?);
+            } else if result.envrc.is_none() || !quiet {
+                commands::print_direnv_result(&result);
+            }
+            if watch {
+                commands::watch_direnv(&context, namespace.as_deref(), &dir, &paths)?;
+            }
+        }
+
+        Command::Pick(cli::PickArgs {
             filter,
             output,
             detail,
             no_tmux,
             insecure_skip_tls,
             no_session_check,
-        } => {
+        }) => {
+            let output = render::output_mode(output);
             let merged = kubeconfig::load_merged(&paths)?;
             let (context, namespace) = commands::pick_context_namespace(
                 &merged,
@@ -233,6 +470,7 @@ fn run() -> Result<()> {
                 session_check_ttl,
             )?;
 
+            commands::record_switch(&context, namespace.as_deref());
             let shell = commands::detect_shell();
             commands::apply_context_output(
                 output.as_deref(),
@@ -246,7 +484,7 @@ fn run() -> Result<()> {
             )?;
         }
 
-        Command::Cleanup {
+        Command::Cleanup(cli::CleanupArgs {
             days,
             orphaned,
             dry_run,
@@ -254,8 +492,7 @@ fn run() -> Result<()> {
             from_file,
             interactive,
             json,
-            quiet,
-        } => {
+        }) => {
             let merged = kubeconfig::load_merged(&paths)?;
             let allowed_contexts = merged.context_names();
 
@@ -265,24 +502,26 @@ fn run() -> Result<()> {
                         "--json is not supported with --interactive".into(),
                     ));
                 }
-                let base = dirs_next::home_dir()
-                    .ok_or(K8pkError::NoHomeDir)?
-                    .join(".local/share/k8pk");
-
-                if !base.exists() {
+                let dirs = storage::isolated_dirs();
+                if dirs.is_empty() {
                     if !quiet {
-                        println!("No generated configs directory found ({})", base.display());
+                        println!(
+                            "No generated configs directory found ({})",
+                            storage::data_dir()?.display()
+                        );
                     }
                     return Ok(());
                 }
 
                 let mut configs: Vec<String> = Vec::new();
-                for entry in fs::read_dir(&base)? {
-                    let entry = entry?;
-                    let path = entry.path();
-                    if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-                        if name.ends_with(".yaml") || name.ends_with(".yml") {
-                            configs.push(name.to_string());
+                for dir in &dirs {
+                    for entry in fs::read_dir(dir)? {
+                        let path = entry?.path();
+                        if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
+                            let name = name.trim_end_matches(encryption::SEALED_SUFFIX);
+                            if name.ends_with(".yaml") || name.ends_with(".yml") {
+                                configs.push(path.display().to_string());
+                            }
                         }
                     }
                 }
@@ -294,12 +533,10 @@ fn run() -> Result<()> {
                     return Ok(());
                 }
 
-                let selected = MultiSelect::new("Select configs to remove:", configs)
-                    .prompt()
-                    .map_err(|_| K8pkError::Cancelled)?;
+                let selected = MultiSelect::new(&i18n::tr("pick-configs-remove"), configs).ask()?;
 
-                for name in selected {
-                    let path = base.join(&name);
+                for path in selected {
+                    let path = PathBuf::from(path);
                     if dry_run {
                         if !quiet {
                             println!("Would remove: {}", path.display());
@@ -320,8 +557,8 @@ fn run() -> Result<()> {
                     from_file.as_deref(),
                     &allowed_contexts,
                 )?;
-                if json {
-                    println!("{}", serde_json::to_string_pretty(&result)?);
+                if render::structured(json) {
+                    println!("{}", render::to_string(&result)?);
                 } else if !quiet {
                     commands::print_cleanup_summary(&result);
                 }
@@ -332,46 +569,89 @@ fn run() -> Result<()> {
             commands::print_guide();
         }
 
-        Command::Rm {
+        Command::Rm(cli::RmArgs {
             context,
             dry_run,
             yes,
             json,
-        } => {
-            commands::run_rm(&paths, context, dry_run, yes, json)?;
+        }) => {
+            let json = render::structured(json);
+            let results =
+                commands::run_rm(&paths, context, dry_run, yes, &commands::backups_dir()?)?;
+            if json {
+                println!("{}", render::to_string(&results)?);
+            } else if !quiet || dry_run {
+                for result in &results {
+                    commands::print_remove_context_summary(result);
+                }
+            }
         }
 
-        Command::RenameContext {
+        Command::RenameContext(cli::RenameContextArgs {
             from_file,
             context,
             new_name,
+            pattern,
+            template,
             dry_run,
             json,
-            quiet,
-        } => {
+        }) => {
+            let json = render::structured(json);
             let file_path = match from_file {
                 Some(p) => p,
                 None => default_kubeconfig_path()?,
             };
 
-            let result =
-                commands::rename_context_in_file(&file_path, &context, &new_name, dry_run)?;
-            if json {
-                println!("{}", serde_json::to_string_pretty(&result)?);
-            } else if !quiet {
-                commands::print_rename_context_summary(&result);
+            let rule = match (pattern, template) {
+                (Some(expr), _) => Some(commands::RenameRule::parse_pattern(&expr)?),
+                (None, Some(t)) => Some(commands::RenameRule::Template(t)),
+                (None, None) => None,
+            };
+
+            if let Some(rule) = rule {
+                let result = commands::rename_contexts_bulk(
+                    &file_path,
+                    &rule,
+                    dry_run,
+                    &commands::backups_dir()?,
+                )?;
+                if json {
+                    println!("{}", render::to_string(&result)?);
+                } else if !quiet || dry_run {
+                    commands::print_bulk_rename_summary(&result);
+                }
+            } else {
+                // clap guarantees both are present when neither bulk flag is
+                let (Some(context), Some(new_name)) = (context, new_name) else {
+                    return Err(K8pkError::InvalidArgument(
+                        "--context and --new-name are required".into(),
+                    ));
+                };
+                let result = commands::rename_context_in_file(
+                    &file_path,
+                    &context,
+                    &new_name,
+                    dry_run,
+                    &commands::backups_dir()?,
+                )?;
+                if json {
+                    println!("{}", render::to_string(&result)?);
+                } else if !quiet {
+                    commands::print_rename_context_summary(&result);
+                }
             }
         }
 
-        Command::CopyContext {
+        Command::CopyContext(cli::CopyContextArgs {
             from_file,
             to_file,
             context,
             new_name,
+            embed_certs,
             dry_run,
             json,
-            quiet,
-        } => {
+        }) => {
+            let json = render::structured(json);
             let dest_path = match to_file {
                 Some(p) => p,
                 None => default_kubeconfig_path()?,
@@ -382,46 +662,170 @@ fn run() -> Result<()> {
                 &dest_path,
                 &context,
                 new_name.as_deref(),
+                embed_certs,
                 dry_run,
             )?;
             if json {
-                println!("{}", serde_json::to_string_pretty(&result)?);
+                println!("{}", render::to_string(&result)?);
             } else if !quiet {
                 commands::print_copy_context_summary(&result);
             }
         }
 
-        Command::Merge {
+        Command::CreateContext(cli::CreateContextArgs {
+            name,
+            server,
+            cluster_name,
+            user_from,
+            namespace,
+            template,
+            to_file,
+            force,
+            dry_run,
+            json,
+        }) => {
+            let json = render::structured(json);
+            let flags = commands::ContextSpec {
+                name,
+                server,
+                cluster_name,
+                user_from,
+                namespace,
+            };
+            let spec = match template {
+                Some(t) => commands::ContextSpec::load(&t)?.overlay(flags),
+                None => flags,
+            };
+            let dest_path = match to_file {
+                Some(p) => p,
+                None => default_kubeconfig_path()?,
+            };
+            let available = kubeconfig::load_merged(&paths)?;
+            let result = commands::create_context(&spec, &dest_path, &available, force, dry_run)?;
+            if json {
+                println!("{}", render::to_string(&result)?);
+            } else if !quiet {
+                commands::print_create_context_summary(&result);
+            }
+        }
+
+        Command::SetContext(crate::cli::SetContextArgs {
+            name,
+            current,
+            cluster,
+            user,
+            namespace,
+            json,
+        }) => {
+            let context = match name {
+                Some(name) if !current => config::resolve_alias(&name),
+                _ => kubeconfig::load_merged(&paths)?
+                    .current_context
+                    .filter(|c| !c.is_empty())
+                    .ok_or_else(|| {
+                        K8pkError::InvalidArgument("current-context is not set".into())
+                    })?,
+            };
+            let fields = commands::ContextFields {
+                cluster,
+                user,
+                namespace,
+            };
+            let result =
+                commands::set_context(&paths, &context, &fields, &commands::backups_dir()?)?;
+            if render::structured(json) {
+                println!("{}", render::to_string(&result)?);
+            } else if !quiet {
+                let action = if result.created {
+                    "created"
+                } else {
+                    "modified"
+                };
+                println!("Context \"{}\" {}.", result.context, action);
+            }
+        }
+
+        Command::UseContext(cli::UseContextArgs { name, json }) => {
+            let name = commands::resolve_context(&kubeconfig::load_merged(&paths)?, &name)?;
+            let result = commands::use_context(&paths, &name, &commands::backups_dir()?)?;
+            if render::structured(json) {
+                println!("{}", render::to_string(&result)?);
+            } else if !quiet {
+                println!("Switched to context \"{}\".", result.context);
+            }
+        }
+
+        Command::View(crate::cli::ViewArgs {
+            minify,
+            flatten,
+            raw,
+            context,
+            output,
+        }) => {
+            let json = match output.as_deref() {
+                None => render::format() == render::OutputFormat::Json,
+                Some("json") => true,
+                Some("yaml") => false,
+                Some(other) => {
+                    return Err(K8pkError::InvalidArgument(format!(
+                        "unsupported output format '{}' (use yaml or json)",
+                        other
+                    )))
+                }
+            };
+            let context = match context {
+                Some(c) => Some(commands::resolve_context(
+                    &kubeconfig::load_merged(&paths)?,
+                    &c,
+                )?),
+                None => None,
+            };
+            let opts = commands::ViewOptions {
+                minify,
+                flatten,
+                raw,
+                context,
+            };
+            let cfg = commands::view(&paths, &opts)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&cfg)?);
+            } else {
+                print!("{}", serde_yaml_ng::to_string(&cfg)?);
+            }
+        }
+
+        Command::Merge(cli::MergeArgs {
             files,
             out,
             overwrite,
+            prefer,
             json,
-            quiet,
-        } => {
-            let result = commands::merge_files(&files, out.as_deref(), overwrite)?;
+        }) => {
+            let json = render::structured(json);
+            let result = commands::merge_files(&files, out.as_deref(), overwrite, prefer)?;
             if json {
-                println!("{}", serde_json::to_string_pretty(&result)?);
+                println!("{}", render::to_string(&result)?);
             } else if !quiet || result.output.is_none() {
                 commands::print_merge_summary(&result);
             }
         }
 
-        Command::Diff {
+        Command::Diff(cli::DiffArgs {
             file1,
             file2,
             diff_only,
             json,
-            quiet: _quiet,
-        } => {
+        }) => {
+            let json = render::structured(json);
             let result = commands::diff_files(&file1, &file2, diff_only)?;
             if json {
-                println!("{}", serde_json::to_string_pretty(&result)?);
+                println!("{}", render::to_string(&result)?);
             } else {
                 commands::print_diff_summary(&result, diff_only);
             }
         }
 
-        Command::Exec {
+        Command::Exec(cli::ExecArgs {
             context,
             namespace,
             command,
@@ -429,13 +833,23 @@ fn run() -> Result<()> {
             no_headers,
             json,
             no_session_check,
-        } => {
+            docker,
+            devcontainer,
+            ssh,
+            redact,
+        }) => {
+            let json = render::structured(json);
+            let target =
+                shell::ExecTarget::from_flags(docker, devcontainer.as_deref(), ssh, redact)?;
             let merged = kubeconfig::load_merged(&paths)?;
             let all_contexts = merged.context_names();
-            let matched = commands::match_pattern(&context, &all_contexts);
+            let matched = commands::expand_context(&context, &all_contexts);
 
             if matched.is_empty() {
-                return Err(K8pkError::ContextNotFound(context));
+                return Err(commands::no_match_error(
+                    &config::resolve_alias(&context),
+                    &all_contexts,
+                ));
             }
 
             if json {
@@ -447,6 +861,7 @@ fn run() -> Result<()> {
                         &command,
                         &paths,
                         no_session_check,
+                        &target,
                     )?;
                     let success = result.exit_code == 0;
                     results.push(result);
@@ -454,7 +869,7 @@ fn run() -> Result<()> {
                         break;
                     }
                 }
-                println!("{}", serde_json::to_string_pretty(&results)?);
+                println!("{}", render::to_string(&results)?);
                 let any_failed = results.iter().any(|r| r.exit_code != 0);
                 if any_failed {
                     return Err(K8pkError::CommandFailed(
@@ -471,6 +886,7 @@ fn run() -> Result<()> {
                         !no_headers && matched.len() > 1,
                         &paths,
                         no_session_check,
+                        &target,
                     )?;
 
                     if exit_code != 0 {
@@ -492,7 +908,41 @@ fn run() -> Result<()> {
             }
         }
 
-        Command::Info { what, display, raw } => {
+        Command::Run(cli::RunArgs {
+            target,
+            command,
+            no_session_check,
+        }) => {
+            let merged = kubeconfig::load_merged(&paths)?;
+            let (ctx, ns) = shell::parse_run_target(&target, &merged.context_names())?;
+            let exit_code = shell::exec_command_in_context(
+                &ctx,
+                ns.as_deref(),
+                &command,
+                false,
+                &paths,
+                no_session_check,
+                &shell::ExecTarget::Host,
+            )?;
+            // Pass the command's own exit code through for scripts and make
+            if exit_code != 0 {
+                std::process::exit(exit_code);
+            }
+        }
+
+        Command::Refresh(cli::RefreshArgs {
+            no_session_check,
+            json,
+        }) => {
+            let result = commands::refresh_session(&paths, no_session_check)?;
+            if render::structured(json) {
+                println!("{}", render::to_string(&result)?);
+            } else if !quiet {
+                commands::print_refresh_result(&result);
+            }
+        }
+
+        Command::Info(cli::InfoArgs { what, display, raw }) => {
             let state = CurrentState::from_env();
             match what.as_str() {
                 "ctx" | "context" => {
@@ -568,6 +1018,28 @@ fn run() -> Result<()> {
                         None => return Err(K8pkError::NotInContext),
                     }
                 }
+                "cluster" | "user" | "server" => {
+                    if display || raw {
+                        return Err(K8pkError::InvalidArgument(
+                            "--display/--raw only apply to ctx".into(),
+                        ));
+                    }
+                    state.ensure_not_expired()?;
+                    let target = state.target()?;
+                    match what.as_str() {
+                        "cluster" => println!("{}", target.cluster),
+                        "user" => println!("{}", target.user),
+                        _ => match target.server {
+                            Some(server) => println!("{}", server),
+                            None => {
+                                return Err(K8pkError::InvalidKubeconfig(format!(
+                                    "cluster {} has no server",
+                                    target.cluster
+                                )))
+                            }
+                        },
+                    }
+                }
                 "all" => {
                     if display || raw {
                         return Err(K8pkError::InvalidArgument(
@@ -576,9 +1048,40 @@ fn run() -> Result<()> {
                     }
                     let mut v = state.to_json();
                     if let serde_json::Value::Object(ref mut m) = v {
+                        // Best effort: the session kubeconfig may be gone or unreadable
+                        if let Ok(target) = state.target() {
+                            m.insert("cluster".to_string(), target.cluster.into());
+                            m.insert("user".to_string(), target.user.into());
+                            if let Some(server) = target.server {
+                                m.insert("server".to_string(), server.into());
+                            }
+                        }
+                        if let Some(left) = state.expires_in() {
+                            m.insert("expires_in".to_string(), left.into());
+                        }
                         m.insert("oc".to_string(), kubeconfig::oc_cli_info_json());
                     }
-                    println!("{}", serde_json::to_string_pretty(&v)?);
+                    println!("{}", render::to_string(&v)?);
+                }
+                "expiry" | "expires" => {
+                    if display || raw {
+                        return Err(K8pkError::InvalidArgument(
+                            "--display/--raw only apply to ctx".into(),
+                        ));
+                    }
+                    if state.context.is_none() {
+                        return Err(K8pkError::NotInContext);
+                    }
+                    match state.expires_in() {
+                        Some(left) if left > 0 => {
+                            println!("{} left", clock::format_duration(left.unsigned_abs()))
+                        }
+                        Some(left) => println!(
+                            "expired {} ago; run 'k8pk refresh'",
+                            clock::format_duration(left.unsigned_abs())
+                        ),
+                        None => println!("no expiry (session_ttl is not set)"),
+                    }
                 }
                 "oc" => {
                     if display || raw {
@@ -596,7 +1099,7 @@ fn run() -> Result<()> {
                 }
                 _ => {
                     return Err(K8pkError::InvalidArgument(format!(
-                        "unknown info type: '{}'. Use: ctx, ns, depth, config, oc, all\n\
+                        "unknown info type: '{}'. Use: ctx, ns, depth, config, cluster, user, server, expiry, oc, all\n\
                          Hint: for JSON output use: k8pk info all",
                         what
                     )));
@@ -604,7 +1107,7 @@ fn run() -> Result<()> {
             }
         }
 
-        Command::Ctx {
+        Command::Ctx(cli::CtxArgs {
             context,
             namespace,
             recursive,
@@ -612,58 +1115,42 @@ fn run() -> Result<()> {
             no_tmux,
             insecure_skip_tls,
             no_session_check,
-        } => {
+        }) => {
+            let output = render::output_mode(output);
             let merged = kubeconfig::load_merged(&paths)?;
 
+            // `-` and `-N` step back through this shell's switches; a shell
+            // without any falls back to the last switch anywhere for `-`
+            let (context, namespace, navigated) =
+                match context.as_deref().and_then(commands::back_steps) {
+                    Some(steps) => match commands::navigate(-(steps as isize)) {
+                        Ok(entry) => (Some(entry.context), namespace.or(entry.namespace), true),
+                        Err(_) if context.as_deref() == Some("-") => (context, namespace, false),
+                        Err(e) => return Err(e),
+                    },
+                    None => (context, namespace, navigated),
+                };
+
             let context = match context {
                 Some(c) if c == "-" => {
                     commands::get_previous_context()?.ok_or(K8pkError::NoPreviousContext)?
                 }
-                Some(c) => {
-                    let resolved = config::resolve_alias(&c);
-                    // Use match_pattern for exact -> substring fallback
-                    let all = merged.context_names();
-                    let matches = commands::match_pattern(&resolved, &all);
-                    match matches.len() {
-                        0 => {
-                            let suggestions = crate::error::closest_matches(&resolved, &all, 3);
-                            if suggestions.is_empty() {
-                                return Err(K8pkError::ContextNotFound(resolved));
-                            } else {
-                                return Err(K8pkError::ContextNotFoundSuggestions {
-                                    pattern: resolved,
-                                    suggestions: suggestions
-                                        .iter()
-                                        .map(|s| format!("    - {}", s))
-                                        .collect::<Vec<_>>()
-                                        .join("\n"),
-                                });
-                            }
-                        }
-                        1 => matches.into_iter().next().expect("len checked"),
-                        _ => {
-                            // Multiple matches -- let user disambiguate
-                            if io::stdin().is_terminal() {
-                                eprintln!("'{}' matched {} contexts:", c, matches.len());
-                                inquire::Select::new("Select context:", matches)
-                                    .prompt()
-                                    .map_err(|_| K8pkError::Cancelled)?
-                            } else {
-                                return Err(K8pkError::InvalidArgument(format!(
-                                    "'{}' matches multiple contexts: {}. Be more specific.",
-                                    c,
-                                    matches.join(", ")
-                                )));
-                            }
-                        }
-                    }
-                }
+                Some(c) => commands::resolve_context(&merged, &c)?,
                 None => {
                     // Interactive pick with dedup and active marker
                     commands::pick_context(&merged, None)?
                 }
             };
 
+            // Restore the namespace last used in this context unless -n was given
+            let namespace = match namespace {
+                Some(ns) => Some(ns),
+                None if !navigated && config::remember_namespaces(&k8pk_config) => {
+                    commands::get_last_namespace(&context).unwrap_or(None)
+                }
+                None => None,
+            };
+
             let initial_kubeconfig = commands::ensure_isolated_kubeconfig_from(
                 &merged,
                 &context,
@@ -712,14 +1199,21 @@ fn run() -> Result<()> {
             }
         }
 
-        Command::Ns {
+        Command::Ns(cli::NsArgs {
             namespace,
+            forget,
+            create,
+            yes,
             recursive,
             output,
             no_tmux,
             insecure_skip_tls,
-        } => {
+            multi,
+            details,
+        }) => {
+            let output = render::output_mode(output);
             let state = CurrentState::from_env();
+            state.ensure_not_expired()?;
             // Try to get context from K8PK_CONTEXT, or fall back to current-context from kubeconfig
             let context = if let Some(ctx) = state.context {
                 ctx
@@ -737,6 +1231,26 @@ fn run() -> Result<()> {
                 ctx
             };
 
+            if multi {
+                let isolated = commands::ensure_isolated_kubeconfig(&context, None, &paths)?;
+                let namespaces = commands::pick_namespaces(&context, isolated.to_str(), details)?;
+                return commands::open_namespaces(
+                    &context,
+                    &namespaces,
+                    &paths,
+                    no_tmux,
+                    insecure_skip_tls,
+                );
+            }
+
+            if forget {
+                match commands::forget_last_namespace(&context)? {
+                    Some(ns) => eprintln!("Forgot namespace '{}' for context '{}'", ns, context),
+                    None => eprintln!("No remembered namespace for context '{}'", context),
+                }
+                return Ok(());
+            }
+
             let namespace = match namespace {
                 Some(ns) if ns == "-" => {
                     commands::get_previous_namespace()?.ok_or(K8pkError::NoPreviousNamespace)?
@@ -745,10 +1259,15 @@ fn run() -> Result<()> {
                 None => {
                     // Interactive pick via isolated kubeconfig (faster than mega KUBECONFIG)
                     let isolated = commands::ensure_isolated_kubeconfig(&context, None, &paths)?;
-                    commands::pick_namespace(&context, isolated.to_str())?
+                    commands::pick_namespace(&context, isolated.to_str(), details)?
                 }
             };
 
+            if create {
+                let isolated = commands::ensure_isolated_kubeconfig(&context, None, &paths)?;
+                commands::ensure_namespace(&context, &namespace, isolated.to_str(), yes)?;
+            }
+
             commands::save_to_history(&context, Some(&namespace))?;
 
             let kubeconfig =
@@ -784,8 +1303,49 @@ fn run() -> Result<()> {
             }
         }
 
-        Command::History { json, clear } => {
-            if clear {
+        // Rewritten to `Ctx` by `history_switch` before dispatch
+        Command::Back(cli::BackArgs { .. }) | Command::Forward(cli::ForwardArgs { .. }) => {
+            unreachable!()
+        }
+
+        Command::History(cli::HistoryArgs { json, clear, shell }) => {
+            let json = render::structured(json);
+            if shell {
+                let stack = state::NavStack::from_env();
+                if json {
+                    let entries: Vec<_> = stack
+                        .entries
+                        .iter()
+                        .enumerate()
+                        .map(|(i, e)| {
+                            serde_json::json!({
+                                "context": e.context,
+                                "namespace": e.namespace,
+                                "offset": i as isize - stack.pos as isize,
+                            })
+                        })
+                        .collect();
+                    println!("{}", render::to_string(&entries)?);
+                } else if stack.entries.is_empty() {
+                    println!("No switches in this shell yet.");
+                } else {
+                    for (i, e) in stack.entries.iter().enumerate().rev() {
+                        let offset = i as isize - stack.pos as isize;
+                        let ns = e.namespace.as_deref().map(|n| format!(" ({})", n));
+                        let (label, marker) = match offset {
+                            0 => ("0".to_string(), "  <- current"),
+                            n => (format!("{:+}", n), ""),
+                        };
+                        println!(
+                            "{:>4}  {}{}{}",
+                            label,
+                            e.context,
+                            ns.unwrap_or_default(),
+                            marker
+                        );
+                    }
+                }
+            } else if clear {
                 commands::clear_history()?;
                 if !json {
                     println!("History cleared.");
@@ -797,7 +1357,7 @@ fn run() -> Result<()> {
                         "contexts": contexts,
                         "namespaces": namespaces,
                     });
-                    println!("{}", serde_json::to_string_pretty(&j)?);
+                    println!("{}", render::to_string(&j)?);
                 } else if contexts.is_empty() && namespaces.is_empty() {
                     println!("No history yet.");
                 } else {
@@ -819,7 +1379,7 @@ fn run() -> Result<()> {
             }
         }
 
-        Command::Clean { output } => match output.as_deref() {
+        Command::Clean { output } => match render::output_mode(output).as_deref() {
             Some("json") => {
                 commands::print_exit_commands(Some("json"))?;
             }
@@ -834,29 +1394,45 @@ fn run() -> Result<()> {
             }
         },
 
-        Command::Update {
+        Command::Update(crate::cli::UpdateArgs {
             check,
             force,
+            channel,
+            pin,
+            cosign,
+            minisign_key,
+            force_binary,
             json,
-            quiet,
-        } => {
+        }) => {
+            let json = render::structured(json);
             let effective_quiet = quiet || json;
-            let result = commands::check_and_update(check, force, effective_quiet)?;
+            let opts = commands::UpdateOptions {
+                check_only: check,
+                force,
+                channel,
+                version: pin,
+                cosign,
+                minisign_key,
+                force_binary,
+            };
+            let result = commands::check_and_update(&opts, effective_quiet)?;
             if json {
-                println!("{}", serde_json::to_string_pretty(&result)?);
+                println!("{}", render::to_string(&result)?);
             }
         }
 
-        Command::Export {
+        Command::Export(cli::ExportArgs {
             context,
             namespace,
             json,
-        } => {
+        }) => {
+            let json = render::structured(json);
+            let context = commands::resolve_context(&kubeconfig::load_merged(&paths)?, &context)?;
             let kubeconfig =
                 commands::ensure_isolated_kubeconfig(&context, Some(&namespace), &paths)?;
             if json {
                 let j = serde_json::json!({ "kubeconfig": kubeconfig.to_string_lossy() });
-                println!("{}", serde_json::to_string_pretty(&j)?);
+                println!("{}", render::to_string(&j)?);
             } else {
                 println!("{}", kubeconfig.display());
             }
@@ -866,44 +1442,161 @@ fn run() -> Result<()> {
             shell::generate_completions(&shell)?;
         }
 
-        Command::Config(cmd) => match cmd {
-            cli::ConfigCommand::Path { json } => {
-                let config_path = config::config_path()?;
-                if json {
-                    println!(
-                        "{}",
-                        serde_json::json!({"path": config_path.to_string_lossy()})
-                    );
-                } else {
-                    println!("{}", config_path.display());
-                }
+        Command::Alias(cli::AliasArgs {
+            command,
+            install,
+            shell,
+        }) => {
+            use crate::cli::AliasCommand;
+            if install {
+                return commands::run_init_install(shell.as_deref(), quiet);
             }
-            cli::ConfigCommand::Init { json } => {
-                let (config_path, created) = config::init_config()?;
-                if json {
-                    println!(
-                        "{}",
-                        serde_json::json!({
-                            "path": config_path.to_string_lossy(),
-                            "status": if created { "initialized" } else { "exists" }
-                        })
-                    );
-                } else if created {
-                    println!("Config file initialized at: {}", config_path.display());
-                    eprintln!();
-                    eprintln!("Next steps:");
-                    eprintln!("  k8pk                    # pick a context and open a shell (once you have kubeconfigs)");
-                    eprintln!("  k8pk login …            # add a cluster if nothing is listed yet");
-                    eprintln!("  k8pk doctor             # verify kubectl and paths");
-                    eprintln!("  Edit the config file to tune includes, aliases, tmux, insecure_contexts.");
-                } else {
-                    println!("Config file already exists at: {}", config_path.display());
+            match command.unwrap_or(AliasCommand::List { json: false }) {
+                AliasCommand::List { json } => {
+                    let entries = commands::list_aliases()?;
+                    if render::structured(json) {
+                        println!("{}", render::to_string(&entries)?);
+                    } else {
+                        commands::print_alias_list(&entries);
+                    }
+                }
+                AliasCommand::Add {
+                    name,
+                    context,
+                    force,
+                    json,
+                } => {
+                    let entry = commands::add_alias(&name, &context, &paths, force)?;
+                    if render::structured(json) {
+                        println!("{}", render::to_string(&entry)?);
+                    } else if !quiet {
+                        println!("Added alias: {} -> {}", entry.alias, entry.context);
+                    }
+                }
+                AliasCommand::Remove { names, json } => {
+                    let removed = commands::remove_aliases(&names)?;
+                    if render::structured(json) {
+                        println!("{}", render::to_string(&removed)?);
+                    } else if !quiet {
+                        for entry in &removed {
+                            println!("Removed alias: {} -> {}", entry.alias, entry.context);
+                        }
+                    }
+                }
+            }
+        }
+
+        Command::Init(cli::InitArgs { shell, install }) => {
+            if install {
+                commands::run_init_install(shell.as_deref(), quiet)?;
+            } else {
+                let shell = commands::resolve_init_shell(shell.as_deref())?;
+                print!("{}", commands::init_script(shell)?);
+            }
+        }
+
+        Command::Sources { command } => {
+            match command.unwrap_or(cli::SourcesCommand::List { json: false }) {
+                cli::SourcesCommand::List { json } => {
+                    let entries = commands::list_sources(
+                        cli.kubeconfig.as_deref(),
+                        &cli.kubeconfig_dir,
+                        &k8pk_config,
+                    )?;
+                    if render::structured(json) {
+                        println!("{}", render::to_string(&entries)?);
+                    } else {
+                        commands::print_sources(&entries);
+                    }
+                }
+                cli::SourcesCommand::Disable { path } => {
+                    let (entry, changed) = commands::disable_source(&path)?;
+                    if !quiet {
+                        if changed {
+                            println!("Disabled {}", entry);
+                        } else {
+                            println!("{} is already disabled", entry);
+                        }
+                    }
+                }
+                cli::SourcesCommand::Enable { path } => {
+                    let entry = commands::enable_source(&path)?;
+                    if !quiet {
+                        println!("Enabled {}", entry);
+                    }
+                }
+            }
+        }
+
+        Command::Sync(cli::SyncArgs { repo, branch, json }) => {
+            let mut settings = k8pk_config.sync.clone().unwrap_or_default();
+            if let Some(repo) = repo {
+                settings.repo = repo;
+            }
+            if branch.is_some() {
+                settings.branch = branch;
+            }
+            if settings.repo.is_empty() {
+                return Err(K8pkError::InvalidArgument(
+                    "no repository to sync; set sync.repo in the k8pk config or pass --repo".into(),
+                ));
+            }
+            let report = commands::sync(&settings)?;
+            if render::structured(json) {
+                println!("{}", render::to_string(&report)?);
+            } else if !quiet {
+                commands::print_sync_report(&report);
+            }
+        }
+
+        Command::Config(cmd) => match cmd {
+            cli::ConfigCommand::Path { json } => {
+                let json = render::structured(json);
+                let config_path = config::config_path()?;
+                let team_path = config::team_config_path();
+                if json {
+                    println!(
+                        "{}",
+                        render::to_string(&serde_json::json!({
+                            "path": config_path.to_string_lossy(),
+                            "team": team_path.as_ref().map(|p| p.to_string_lossy()),
+                        }))?
+                    );
+                } else {
+                    println!("{}", config_path.display());
+                    if let Some(team) = team_path {
+                        eprintln!("team defaults: {}", team.display());
+                    }
+                }
+            }
+            cli::ConfigCommand::Init { json } => {
+                let json = render::structured(json);
+                let (config_path, created) = config::init_config()?;
+                if json {
+                    println!(
+                        "{}",
+                        render::to_string(&serde_json::json!({
+                            "path": config_path.to_string_lossy(),
+                            "status": if created { "initialized" } else { "exists" }
+                        }))?
+                    );
+                } else if created {
+                    println!("Config file initialized at: {}", config_path.display());
+                    eprintln!();
+                    eprintln!("Next steps:");
+                    eprintln!("  k8pk                    # pick a context and open a shell (once you have kubeconfigs)");
+                    eprintln!("  k8pk login …            # add a cluster if nothing is listed yet");
+                    eprintln!("  k8pk doctor             # verify kubectl and paths");
+                    eprintln!("  Edit the config file to tune includes, aliases, tmux, insecure_contexts.");
+                } else {
+                    println!("Config file already exists at: {}", config_path.display());
                 }
             }
             cli::ConfigCommand::Show { json } => {
+                let json = render::structured(json);
                 let cfg = config::load_uncached()?;
                 if json {
-                    println!("{}", serde_json::to_string_pretty(&cfg)?);
+                    println!("{}", render::to_string(&cfg)?);
                 } else {
                     let yaml = serde_yaml_ng::to_string(&cfg)?;
                     println!("{}", yaml);
@@ -914,15 +1607,32 @@ fn run() -> Result<()> {
             }
         },
 
-        Command::Lint {
+        Command::Lint(cli::LintArgs {
             file,
             strict,
+            fix,
+            dry_run,
             json,
-            quiet,
-        } => {
-            let result = commands::lint(file.as_deref(), &paths, strict)?;
+        }) => {
+            let json = render::structured(json);
+            let fixes = if fix {
+                commands::lint_fix(
+                    file.as_deref(),
+                    &paths,
+                    strict,
+                    dry_run,
+                    &commands::backups_dir()?,
+                )?
+            } else {
+                Vec::new()
+            };
+            if fix && !json && !quiet {
+                commands::print_lint_fixes(&fixes);
+            }
+            let mut result = commands::lint(file.as_deref(), &paths, strict)?;
+            result.fixes = fixes;
             if json {
-                println!("{}", serde_json::to_string_pretty(&result)?);
+                println!("{}", render::to_string(&result)?);
             } else if !quiet {
                 println!(
                     "Lint complete: {} errors, {} warnings",
@@ -934,48 +1644,242 @@ fn run() -> Result<()> {
             }
         }
 
-        Command::Edit { context, editor } => {
-            let merged = kubeconfig::load_merged(&paths)?;
-            commands::edit_kubeconfig(context.as_deref(), editor.as_deref(), &merged, &paths)?;
+        Command::Edit(cli::EditArgs {
+            context,
+            editor,
+            goto,
+            no_review,
+        }) => {
+            let context = match context {
+                Some(c) => Some(commands::resolve_context(
+                    &kubeconfig::load_merged(&paths)?,
+                    &c,
+                )?),
+                None => None,
+            };
+            commands::edit_kubeconfig(
+                context.as_deref(),
+                editor.as_deref(),
+                goto,
+                no_review,
+                &paths,
+            )?;
         }
 
         Command::Login(args) => {
             commands::run_login_cli(&paths, args)?;
         }
 
-        Command::Organize {
+        Command::Organize(cli::OrganizeArgs {
+            rollback: Some(manifest),
+            dry_run,
+            json,
+            ..
+        }) => {
+            let json = render::structured(json);
+            let result = commands::rollback_organize(&manifest, dry_run)?;
+            if json {
+                println!("{}", render::to_string(&result)?);
+            } else if !quiet || dry_run {
+                commands::print_rollback_summary(&result);
+            }
+        }
+
+        Command::Organize(cli::OrganizeArgs {
             file,
+            rollback: _,
             output_dir,
             dry_run,
             remove_from_source,
+            auto_alias,
+            embed_certs,
             json,
-            quiet,
-        } => {
-            let result = commands::organize_by_cluster_type(
+        }) => {
+            let json = render::structured(json);
+            let mut result = commands::organize_by_cluster_type(
                 file.as_deref(),
                 output_dir.as_deref(),
                 dry_run,
                 remove_from_source,
+                embed_certs,
+                &commands::backups_dir()?,
             )?;
+            if !dry_run {
+                let candidates: Vec<(String, &str)> = result
+                    .groups
+                    .iter()
+                    .flat_map(|g| {
+                        g.contexts
+                            .iter()
+                            .map(|c| (c.clone(), g.cluster_type.as_str()))
+                    })
+                    .collect();
+                let known = kubeconfig::load_merged(&paths)
+                    .map(|m| m.context_names().into_iter().collect())
+                    .unwrap_or_default();
+                result.aliases = commands::register_friendly_aliases(
+                    &candidates,
+                    &known,
+                    auto_alias,
+                    quiet || json,
+                )?;
+            }
             if json {
-                println!("{}", serde_json::to_string_pretty(&result)?);
+                println!("{}", render::to_string(&result)?);
             } else if !quiet {
                 commands::print_organize_summary(&result);
             }
         }
 
-        Command::Which { context, json } => {
-            commands::display_context_info(context.as_deref(), &paths, json)?;
+        Command::Undo(cli::UndoArgs {
+            list,
+            dry_run,
+            json,
+        }) => {
+            let json = render::structured(json);
+            if list {
+                let backups = commands::list_backups()?;
+                if json {
+                    println!("{}", render::to_string(&backups)?);
+                } else {
+                    commands::print_backups(&backups);
+                }
+            } else {
+                let result = commands::undo_last(dry_run)?;
+                if json {
+                    println!("{}", render::to_string(&result)?);
+                } else if !quiet || dry_run {
+                    commands::print_undo_result(&result);
+                }
+            }
+        }
+        Command::Prune(cli::PruneArgs {
+            days,
+            include_reachable,
+            timeout,
+            dry_run,
+            yes,
+            json,
+        }) => {
+            let json = render::structured(json);
+            let result = commands::run_prune(
+                &paths,
+                commands::PruneOptions {
+                    days,
+                    include_reachable,
+                    timeout_secs: timeout,
+                    dry_run,
+                    yes,
+                },
+                &commands::backups_dir()?,
+            )?;
+            if json {
+                println!("{}", render::to_string(&result)?);
+            } else if !quiet || dry_run {
+                commands::print_prune_result(&result);
+            }
+        }
+
+        Command::Stats(cli::StatsArgs {
+            stale_days,
+            top,
+            json,
+        }) => {
+            let stats = commands::usage_stats(&paths, stale_days, top)?;
+            if render::structured(json) {
+                println!("{}", render::to_string(&stats)?);
+            } else {
+                commands::print_usage_stats(&stats);
+            }
+        }
+
+        Command::Report(cli::ReportArgs {
+            expiring_days,
+            json,
+        }) => {
+            let report = commands::report(&paths, expiring_days)?;
+            if render::structured(json) {
+                println!("{}", render::to_string(&report)?);
+            } else {
+                print!("{}", commands::render_report_markdown(&report));
+            }
+        }
+
+        Command::CanI(args) => {
+            let filter = commands::ContextFilter {
+                pattern: args.context,
+                tag: args.tag,
+                ..Default::default()
+            };
+            let matrix = commands::can_i(
+                &paths,
+                &commands::CanIQuery {
+                    verb: &args.verb,
+                    resource: &args.resource,
+                    filter: &filter,
+                    namespaces: &args.namespaces,
+                    all_namespaces: args.all_namespaces,
+                },
+            )?;
+            if render::structured(args.json) {
+                println!("{}", render::to_string(&matrix)?);
+            } else {
+                commands::print_can_i(&matrix);
+            }
+        }
+
+        Command::VersionSkew(cli::VersionSkewArgs { pattern, json }) => {
+            let filter = commands::ContextFilter {
+                pattern,
+                ..Default::default()
+            };
+            let report = commands::version_skew(&paths, &filter)?;
+            if render::structured(json) {
+                println!("{}", render::to_string(&report)?);
+            } else {
+                commands::print_version_skew(&report);
+            }
+            if report.unsupported > 0 {
+                return Err(K8pkError::VersionSkew(report.unsupported));
+            }
+        }
+
+        Command::Which(cli::WhichArgs {
+            context,
+            filter,
+            cluster_type,
+            sort,
+            peek,
+            json,
+        }) => {
+            let json = render::structured(json);
+            // A generated kubeconfig (e.g. $KUBECONFIG): trace it to its origin
+            if let Some(file) = context.as_deref().map(Path::new).filter(|p| p.is_file()) {
+                let info = commands::generated_file_info(file, &paths)?;
+                if json {
+                    println!("{}", render::to_string(&info)?);
+                } else {
+                    commands::print_generated_file_info(&info);
+                }
+                return Ok(());
+            }
+            let filter = commands::ContextFilter {
+                pattern: context.or(filter),
+                cluster_type,
+                ..Default::default()
+            };
+            commands::display_context_info(&filter, sort, &paths, peek, json)?;
         }
 
         Command::Vault(vault_cmd) => {
             use crate::cli::VaultCommand;
             match vault_cmd {
                 VaultCommand::List { json } => {
+                    let json = render::structured(json);
                     let vault = commands::Vault::new()?;
                     let keys = vault.list_keys();
                     if json {
-                        println!("{}", serde_json::to_string_pretty(&keys)?);
+                        println!("{}", render::to_string(&keys)?);
                     } else if keys.is_empty() {
                         println!("No credentials stored in vault.");
                     } else {
@@ -990,10 +1894,16 @@ fn run() -> Result<()> {
                     }
                 }
                 VaultCommand::Delete { key, json } => {
+                    let json = render::structured(json);
                     let mut vault = commands::Vault::new()?;
                     let deleted = vault.delete(&key)?;
                     if json {
-                        println!("{}", serde_json::json!({"key": key, "deleted": deleted}));
+                        println!(
+                            "{}",
+                            render::to_string(
+                                &serde_json::json!({"key": key, "deleted": deleted})
+                            )?
+                        );
                     } else if deleted {
                         println!("Deleted vault entry: {}", key);
                     } else {
@@ -1001,11 +1911,14 @@ fn run() -> Result<()> {
                     }
                 }
                 VaultCommand::Path { json } => {
+                    let json = render::structured(json);
                     let vault = commands::Vault::new()?;
                     if json {
                         println!(
                             "{}",
-                            serde_json::json!({"path": vault.path().to_string_lossy()})
+                            render::to_string(
+                                &serde_json::json!({"path": vault.path().to_string_lossy()})
+                            )?
                         );
                     } else {
                         println!("{}", vault.path().display());
@@ -1014,6 +1927,319 @@ fn run() -> Result<()> {
             }
         }
 
+        Command::Dashboard(cli::DashboardArgs { interval, json }) => {
+            if render::structured(json) {
+                println!(
+                    "{}",
+                    render::to_string(&commands::dashboard_snapshot(&paths)?)?
+                );
+            } else {
+                commands::dashboard(&paths, interval)?;
+            }
+        }
+
+        Command::Events(cli::EventsArgs {
+            context,
+            namespace,
+            all_namespaces,
+            watch_only,
+            json,
+        }) => {
+            let opts = commands::EventsOptions {
+                all_namespaces,
+                watch_only,
+                json: render::structured(json),
+            };
+            commands::events(&opts, context.as_deref(), namespace.as_deref(), &paths)?;
+        }
+
+        Command::ShellPod(cli::ShellPodArgs {
+            image,
+            shell,
+            name,
+            context,
+            namespace,
+            keep,
+            yes,
+        }) => {
+            let opts = commands::ShellPodOptions {
+                image,
+                name,
+                shell,
+                keep,
+                yes,
+            };
+            let exit_code =
+                commands::shell_pod(&opts, context.as_deref(), namespace.as_deref(), &paths)?;
+            if exit_code != 0 {
+                std::process::exit(exit_code);
+            }
+        }
+
+        Command::Pf(pf_cmd) => {
+            use crate::cli::PfCommand;
+            use commands::port_forward;
+            match pf_cmd {
+                PfCommand::Add {
+                    context,
+                    namespace,
+                    target,
+                    ports,
+                    name,
+                    address,
+                    json,
+                } => {
+                    let merged = kubeconfig::load_merged(&paths)?;
+                    let context = commands::unique_context(&context, &merged.context_names())?;
+                    let ports = port_forward::parse_ports(&ports)?;
+                    let forward = port_forward::PortForward {
+                        name: name.unwrap_or_else(|| port_forward::default_name(&target, &ports)),
+                        context,
+                        namespace,
+                        target,
+                        ports,
+                        address,
+                    };
+                    let status = port_forward::add(forward, kubeconfig_env.as_deref())?;
+                    if render::structured(json) {
+                        println!("{}", render::to_string(&status)?);
+                    } else if !quiet {
+                        let f = &status.forward;
+                        eprintln!(
+                            "Started port-forward '{}': {} in {}/{} on {}",
+                            f.name,
+                            f.target,
+                            f.context,
+                            f.namespace,
+                            f.ports.join(" ")
+                        );
+                        eprintln!("  Log: {}", port_forward::log_path(&f.name)?.display());
+                    }
+                }
+                PfCommand::List { context, json } => {
+                    let mut forwards = port_forward::list()?;
+                    if let Some(pattern) = context {
+                        let names: Vec<String> =
+                            forwards.iter().map(|f| f.forward.context.clone()).collect();
+                        let keep = commands::expand_context(&pattern, &names);
+                        forwards.retain(|f| keep.contains(&f.forward.context));
+                    }
+                    if render::structured(json) {
+                        println!("{}", render::to_string(&forwards)?);
+                    } else if forwards.is_empty() {
+                        println!("No port-forwards. Add one with `k8pk pf add CONTEXT NAMESPACE svc/NAME 8080:80`.");
+                    } else {
+                        println!(
+                            "{:<20} {:<24} {:<20} {:<16} STATUS",
+                            "NAME", "CONTEXT/NAMESPACE", "TARGET", "PORTS"
+                        );
+                        for s in &forwards {
+                            let f = &s.forward;
+                            let status = match s.started_at {
+                                Some(started) if s.restarts > 0 => format!(
+                                    "running {}, restarts: {}",
+                                    commands::sessions::format_age(started),
+                                    s.restarts
+                                ),
+                                Some(started) => {
+                                    format!("running {}", commands::sessions::format_age(started))
+                                }
+                                None => "stopped".to_string(),
+                            };
+                            println!(
+                                "{:<20} {:<24} {:<20} {:<16} {}",
+                                f.name,
+                                format!("{}/{}", f.context, f.namespace),
+                                f.target,
+                                f.ports.join(","),
+                                status
+                            );
+                        }
+                    }
+                }
+                PfCommand::Start { name } => {
+                    let started = port_forward::start(name.as_deref(), kubeconfig_env.as_deref())?;
+                    if !quiet {
+                        if started.is_empty() {
+                            eprintln!("Nothing to start: all port-forwards are running.");
+                        }
+                        for s in &started {
+                            eprintln!("Started port-forward '{}'", s.forward.name);
+                        }
+                    }
+                }
+                PfCommand::Stop { name } => {
+                    let stopped = port_forward::stop(name.as_deref())?;
+                    if !quiet {
+                        if stopped.is_empty() {
+                            eprintln!("No running port-forwards to stop.");
+                        }
+                        for f in &stopped {
+                            eprintln!("Stopped port-forward '{}'", f.name);
+                        }
+                    }
+                }
+                PfCommand::Rm { name } => {
+                    let removed = port_forward::remove(&name)?;
+                    if !quiet {
+                        eprintln!("Removed port-forward '{}'", removed.name);
+                    }
+                }
+                PfCommand::Logs { name } => {
+                    let path = port_forward::log_path(&name)?;
+                    match fs::read_to_string(&path) {
+                        Ok(log) => print!("{}", log),
+                        Err(_) => {
+                            return Err(K8pkError::InvalidArgument(format!(
+                                "no log for port-forward '{}'",
+                                name
+                            )))
+                        }
+                    }
+                }
+                PfCommand::Supervise { name } => port_forward::supervise(&name, &paths)?,
+            }
+        }
+
+        Command::Discover { provider } => {
+            config::require_online("k8pk discover")?;
+            use crate::cli::DiscoverCommand;
+            match provider {
+                DiscoverCommand::Eks {
+                    profile,
+                    region,
+                    all,
+                    pattern,
+                    output_dir,
+                    dry_run,
+                    json,
+                } => {
+                    let json = render::structured(json);
+                    let result = commands::discover_eks(
+                        &profile,
+                        &region,
+                        pattern.as_deref(),
+                        all,
+                        output_dir.as_deref(),
+                        dry_run,
+                        quiet || json,
+                    )?;
+                    if json {
+                        println!("{}", render::to_string(&result)?);
+                    } else if !quiet {
+                        commands::print_discover_summary(&result);
+                    }
+                }
+                DiscoverCommand::Aks {
+                    subscription,
+                    admin,
+                    all,
+                    pattern,
+                    output_dir,
+                    dry_run,
+                    json,
+                } => {
+                    let json = render::structured(json);
+                    let result = commands::discover_aks(
+                        &subscription,
+                        admin,
+                        pattern.as_deref(),
+                        all,
+                        output_dir.as_deref(),
+                        dry_run,
+                        quiet || json,
+                    )?;
+                    if json {
+                        println!("{}", render::to_string(&result)?);
+                    } else if !quiet {
+                        commands::print_discover_summary(&result);
+                    }
+                }
+                DiscoverCommand::Teleport {
+                    proxy,
+                    all,
+                    pattern,
+                    output_dir,
+                    dry_run,
+                    json,
+                } => {
+                    let json = render::structured(json);
+                    let result = commands::discover_teleport(
+                        proxy.as_deref(),
+                        pattern.as_deref(),
+                        all,
+                        output_dir.as_deref(),
+                        dry_run,
+                        quiet || json,
+                    )?;
+                    if json {
+                        println!("{}", render::to_string(&result)?);
+                    } else if !quiet {
+                        commands::print_discover_summary(&result);
+                    }
+                }
+                DiscoverCommand::Gke {
+                    project,
+                    all,
+                    pattern,
+                    output_dir,
+                    dry_run,
+                    json,
+                } => {
+                    let json = render::structured(json);
+                    let result = commands::discover_gke(
+                        &project,
+                        pattern.as_deref(),
+                        all,
+                        output_dir.as_deref(),
+                        dry_run,
+                        quiet || json,
+                    )?;
+                    if json {
+                        println!("{}", render::to_string(&result)?);
+                    } else if !quiet {
+                        commands::print_discover_summary(&result);
+                    }
+                }
+            }
+        }
+
+        Command::Ci { command } => match command {
+            crate::cli::CiCommand::Setup {
+                context,
+                namespace,
+                out,
+                kubeconfig_var,
+                server_var,
+                token_var,
+                ca_var,
+                no_verify,
+                shell,
+                json,
+            } => {
+                let json = render::structured(json);
+                let opts = commands::CiSetupOptions {
+                    context,
+                    namespace,
+                    out,
+                    kubeconfig_var,
+                    server_var,
+                    token_var,
+                    ca_var,
+                    verify: !no_verify,
+                };
+                let result = commands::ci_setup(&opts, &|name| env::var(name).ok())?;
+                if json {
+                    println!("{}", render::to_string(&result)?);
+                } else {
+                    print!("{}", commands::ci_exports(&result, &shell));
+                    if !quiet {
+                        commands::print_ci_summary(&result);
+                    }
+                }
+            }
+        },
+
         Command::Rancher { command } => {
             use crate::cli::RancherCommand;
             match command {
@@ -1029,8 +2255,8 @@ fn run() -> Result<()> {
                     insecure_skip_tls_verify,
                     use_vault,
                     json,
-                    quiet,
                 } => {
+                    let json = render::structured(json);
                     let server_url = server.or(server_pos).ok_or_else(|| {
                         K8pkError::InvalidArgument(
                             "server URL is required (use --server or provide as positional argument)"
@@ -1053,7 +2279,7 @@ fn run() -> Result<()> {
                     )?;
 
                     if json {
-                        println!("{}", serde_json::to_string_pretty(&pulled)?);
+                        println!("{}", render::to_string(&pulled)?);
                     } else {
                         eprintln!(
                             "Pulled {} cluster kubeconfig{}.",
@@ -1066,19 +2292,43 @@ fn run() -> Result<()> {
             }
         }
 
-        Command::Sessions {
+        Command::Swap(cli::SwapArgs {
+            first,
+            second,
+            json,
+        }) => {
+            let (a, b) = commands::tmux::swap_windows(first.as_deref(), second.as_deref())?;
+            if render::structured(json) {
+                println!("{}", render::to_string(&[a, b])?);
+            } else if !render::quiet() {
+                let show = |w: &commands::tmux::SwapWindow| match &w.namespace {
+                    Some(ns) => format!("{}/{}", w.context, ns),
+                    None => w.context.clone(),
+                };
+                eprintln!(
+                    "Swapped window {} ({}) and window {} ({})",
+                    a.window_index,
+                    show(&b),
+                    b.window_index,
+                    show(&a)
+                );
+            }
+        }
+        Command::Sessions(cli::SessionsArgs {
             action,
             target,
             json,
             no_tmux,
-        } => {
+        }) => {
+            let json = render::structured(json);
             // Auto-register the current shell if it is inside a k8pk session
             // but not yet tracked (e.g. session predates the registry feature).
             if let Ok(ctx) = env::var("K8PK_CONTEXT") {
                 if !ctx.is_empty() {
                     let ns = env::var("K8PK_NAMESPACE").ok();
                     let kc = env::var("KUBECONFIG").unwrap_or_default();
-                    let _ = commands::sessions::register(&ctx, ns.as_deref(), &kc, None);
+                    let depth = CurrentState::from_env().depth;
+                    let _ = commands::sessions::register(&ctx, ns.as_deref(), &kc, None, depth);
                 }
             }
 
@@ -1101,18 +2351,18 @@ fn run() -> Result<()> {
                         commands::sessions::deduplicated_sessions(&registry, &tmux_sessions);
 
                     if json {
-                        println!("{}", serde_json::to_string_pretty(&groups)?);
+                        println!("{}", render::to_string(&groups)?);
                     } else if groups.is_empty() {
                         println!("No active k8pk sessions.");
                         println!("  Switch to a context to start a session:");
                         println!("    k8pk ctx <context>");
-                    } else if io::stdin().is_terminal() && io::stderr().is_terminal() {
+                    } else if crate::prompt::can_prompt() && io::stderr().is_terminal() {
                         // Interactive picker -- use index-based matching so that
                         // time-dependent labels (age) don't cause a mismatch.
                         let labels: Vec<String> = groups.iter().map(|g| g.to_string()).collect();
-                        let selection = inquire::Select::new("Switch to session:", labels.clone())
-                            .prompt()
-                            .map_err(|_| K8pkError::Cancelled)?;
+                        let selection =
+                            inquire::Select::new(&i18n::tr("pick-session"), labels.clone())
+                                .ask()?;
 
                         let idx = labels.iter().position(|l| *l == selection).ok_or_else(|| {
                             K8pkError::InvalidArgument("selection not found".into())
@@ -1218,10 +2468,16 @@ fn run() -> Result<()> {
                     }
                     let ns = env::var("K8PK_NAMESPACE").ok();
                     let kc = env::var("KUBECONFIG").unwrap_or_default();
-                    commands::sessions::register(&ctx, ns.as_deref(), &kc, None)?;
+                    let depth = CurrentState::from_env().depth;
+                    commands::sessions::register(&ctx, ns.as_deref(), &kc, None, depth)?;
                 }
                 "deregister" | "dereg" | "unreg" => {
-                    commands::sessions::deregister_current()?;
+                    // Called from the exit trap `k8pk init` installs: the shell is
+                    // leaving its context for good
+                    for entry in commands::sessions::deregister_current()? {
+                        commands::run_stop_hook(&entry.state())?;
+                        encryption::release_live_copy(std::path::Path::new(&entry.kubeconfig))?;
+                    }
                 }
                 other => {
                     return Err(K8pkError::InvalidArgument(format!(
@@ -1232,10 +2488,18 @@ fn run() -> Result<()> {
             }
         }
 
-        Command::Complete {
+        Command::GssapiToken { url } => {
+            commands::gssapi_token(&url)?;
+        }
+
+        Command::SessionCredential => {
+            commands::session_credential::print_session_credential()?;
+        }
+
+        Command::Complete(cli::CompleteArgs {
             complete_type,
             context,
-        } => match complete_type.as_str() {
+        }) => match complete_type.as_str() {
             "contexts" => {
                 let merged = kubeconfig::load_merged(&paths)?;
                 for name in merged.context_names() {
@@ -1259,8 +2523,17 @@ fn run() -> Result<()> {
             _ => {}
         },
 
-        Command::Doctor { fix, json } => {
-            commands::doctor(fix, json)?;
+        Command::DynamicComplete(cli::DynamicCompleteArgs { shell, words }) => {
+            commands::print_dynamic_completions(&shell, &words, &paths, &k8pk_config)?;
+        }
+
+        Command::Doctor(cli::DoctorArgs {
+            fix,
+            json,
+            connectivity,
+        }) => {
+            let json = render::structured(json);
+            commands::doctor(fix, json, connectivity)?;
         }
     }
 
@@ -1304,11 +2577,23 @@ mod tests {
 
     use clap::Parser;
 
+    #[test]
+    fn test_cli_builds_on_a_small_stack() {
+        // Subcommands keep their arguments in Args structs, so no single
+        // clap builder frame grows with the whole command tree
+        std::thread::Builder::new()
+            .stack_size(1024 * 1024)
+            .spawn(|| <Cli as clap::CommandFactory>::command().debug_assert())
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+
     #[test]
     fn test_cli_ctx_parse() {
         let cli = Cli::parse_from(["k8pk", "ctx", "my-context"]);
         match cli.command {
-            Some(Command::Ctx {
+            Some(Command::Ctx(cli::CtxArgs {
                 context,
                 namespace,
                 recursive,
@@ -1316,7 +2601,7 @@ mod tests {
                 no_tmux,
                 insecure_skip_tls,
                 no_session_check,
-            }) => {
+            })) => {
                 assert_eq!(context, Some("my-context".to_string()));
                 assert!(namespace.is_none());
                 assert!(!recursive);
@@ -1333,9 +2618,9 @@ mod tests {
     fn test_cli_ctx_with_namespace() {
         let cli = Cli::parse_from(["k8pk", "ctx", "my-ctx", "--namespace", "kube-system"]);
         match cli.command {
-            Some(Command::Ctx {
+            Some(Command::Ctx(cli::CtxArgs {
                 context, namespace, ..
-            }) => {
+            })) => {
                 assert_eq!(context, Some("my-ctx".to_string()));
                 assert_eq!(namespace, Some("kube-system".to_string()));
             }
@@ -1347,9 +2632,9 @@ mod tests {
     fn test_cli_ns_parse() {
         let cli = Cli::parse_from(["k8pk", "ns", "default"]);
         match cli.command {
-            Some(Command::Ns {
+            Some(Command::Ns(cli::NsArgs {
                 namespace, output, ..
-            }) => {
+            })) => {
                 assert_eq!(namespace, Some("default".to_string()));
                 assert!(output.is_none());
             }
@@ -1357,11 +2642,33 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_cli_ns_create() {
+        let cli = Cli::parse_from(["k8pk", "ns", "feature-x", "--create", "-y"]);
+        match cli.command {
+            Some(Command::Ns(cli::NsArgs { create, yes, .. })) => assert!(create && yes),
+            _ => panic!("expected Ns command"),
+        }
+        assert!(Cli::try_parse_from(["k8pk", "ns", "--create"]).is_err());
+        assert!(Cli::try_parse_from(["k8pk", "ns", "x", "-y"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_ns_multi() {
+        let cli = Cli::parse_from(["k8pk", "ns", "--multi", "--no-tmux"]);
+        match cli.command {
+            Some(Command::Ns(cli::NsArgs { multi, no_tmux, .. })) => assert!(multi && no_tmux),
+            _ => panic!("expected Ns command"),
+        }
+        assert!(Cli::try_parse_from(["k8pk", "ns", "web", "--multi"]).is_err());
+        assert!(Cli::try_parse_from(["k8pk", "ns", "--multi", "-o", "json"]).is_err());
+    }
+
     #[test]
     fn test_cli_info_default() {
         let cli = Cli::parse_from(["k8pk", "info"]);
         match cli.command {
-            Some(Command::Info { what, display, raw }) => {
+            Some(Command::Info(cli::InfoArgs { what, display, raw })) => {
                 assert_eq!(what, "all");
                 assert!(!display);
                 assert!(!raw);
@@ -1374,7 +2681,7 @@ mod tests {
     fn test_cli_info_ctx_display() {
         let cli = Cli::parse_from(["k8pk", "info", "ctx", "--display"]);
         match cli.command {
-            Some(Command::Info { what, display, .. }) => {
+            Some(Command::Info(cli::InfoArgs { what, display, .. })) => {
                 assert_eq!(what, "ctx");
                 assert!(display);
             }
@@ -1386,7 +2693,7 @@ mod tests {
     fn test_cli_status_alias() {
         let cli = Cli::parse_from(["k8pk", "status"]);
         match cli.command {
-            Some(Command::Info { what, .. }) => {
+            Some(Command::Info(cli::InfoArgs { what, .. })) => {
                 assert_eq!(what, "all");
             }
             _ => panic!("expected Info command via status alias"),
@@ -1397,7 +2704,7 @@ mod tests {
     fn test_cli_history() {
         let cli = Cli::parse_from(["k8pk", "history", "--json"]);
         match cli.command {
-            Some(Command::History { json, clear }) => {
+            Some(Command::History(cli::HistoryArgs { json, clear, .. })) => {
                 assert!(json);
                 assert!(!clear);
             }
@@ -1420,14 +2727,14 @@ mod tests {
     fn test_cli_pick_default() {
         let cli = Cli::parse_from(["k8pk", "pick"]);
         match cli.command {
-            Some(Command::Pick {
+            Some(Command::Pick(cli::PickArgs {
                 filter,
                 output,
                 detail,
                 no_tmux,
                 insecure_skip_tls,
                 no_session_check,
-            }) => {
+            })) => {
                 assert!(filter.is_none());
                 assert!(output.is_none());
                 assert!(!detail);
@@ -1443,7 +2750,7 @@ mod tests {
     fn test_cli_pick_filter() {
         let cli = Cli::parse_from(["k8pk", "pick", "prod"]);
         match cli.command {
-            Some(Command::Pick { filter, .. }) => {
+            Some(Command::Pick(cli::PickArgs { filter, .. })) => {
                 assert_eq!(filter.as_deref(), Some("prod"));
             }
             _ => panic!("expected Pick command"),
@@ -1471,25 +2778,101 @@ mod tests {
             "ns",
         ]);
         match cli.command {
-            Some(Command::Exec {
+            Some(Command::Exec(cli::ExecArgs {
                 no_session_check, ..
-            }) => {
+            })) => {
                 assert!(no_session_check);
             }
             _ => panic!("expected Exec command"),
         }
     }
 
+    #[test]
+    fn test_cli_exec_container_targets() {
+        let cli = Cli::parse_from([
+            "k8pk",
+            "exec",
+            "dev",
+            "web",
+            "--devcontainer",
+            "--",
+            "kubectl",
+        ]);
+        match cli.command {
+            Some(Command::Exec(cli::ExecArgs {
+                docker,
+                devcontainer,
+                command,
+                ..
+            })) => {
+                assert_eq!(docker, None);
+                assert_eq!(devcontainer, Some(PathBuf::from(".")));
+                assert_eq!(command, ["kubectl"]);
+            }
+            _ => panic!("expected Exec command"),
+        }
+        assert!(Cli::try_parse_from([
+            "k8pk",
+            "exec",
+            "dev",
+            "--docker",
+            "tools",
+            "--devcontainer",
+            "--",
+            "kubectl"
+        ])
+        .is_err());
+        let cli = Cli::parse_from([
+            "k8pk", "exec", "prod", "web", "--ssh", "ops@jump", "--redact", "--", "kubectl",
+        ]);
+        assert!(matches!(
+            cli.command,
+            Some(Command::Exec(cli::ExecArgs { ssh: Some(ref host), redact: true, .. })) if host == "ops@jump"
+        ));
+        assert!(
+            Cli::try_parse_from(["k8pk", "exec", "prod", "--redact", "--", "kubectl"]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_cli_run() {
+        let cli = Cli::parse_from(["k8pk", "run", "prod/web", "--", "kubectl", "get", "-A"]);
+        match cli.command {
+            Some(Command::Run(cli::RunArgs {
+                target, command, ..
+            })) => {
+                assert_eq!(target, "prod/web");
+                assert_eq!(command, ["kubectl", "get", "-A"]);
+            }
+            _ => panic!("expected Run command"),
+        }
+        assert!(Cli::try_parse_from(["k8pk", "run", "prod"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_set_context() {
+        let cli = Cli::parse_from(["k8pk", "set-context", "--current", "--namespace", "web"]);
+        match cli.command {
+            Some(Command::SetContext(args)) => {
+                assert!(args.current && args.name.is_none());
+                assert_eq!(args.namespace.as_deref(), Some("web"));
+            }
+            _ => panic!("expected SetContext command"),
+        }
+        assert!(Cli::try_parse_from(["k8pk", "set-context", "--namespace", "web"]).is_err());
+        assert!(Cli::try_parse_from(["k8pk", "set-context", "dev", "--current"]).is_err());
+    }
+
     #[test]
     fn test_cli_rm_yes() {
         let cli = Cli::parse_from(["k8pk", "rm", "ctx-a", "--yes"]);
         match cli.command {
-            Some(Command::Rm {
+            Some(Command::Rm(cli::RmArgs {
                 context,
                 dry_run,
                 yes,
                 json,
-            }) => {
+            })) => {
                 assert_eq!(context, Some("ctx-a".to_string()));
                 assert!(!dry_run);
                 assert!(yes);
@@ -1511,6 +2894,34 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_cli_login_password_managers() {
+        let cli = Cli::parse_from([
+            "k8pk",
+            "login",
+            "--server",
+            "https://k8s:6443",
+            "--op-item",
+            "dev",
+        ]);
+        match cli.command {
+            Some(Command::Login(args)) => {
+                assert_eq!(args.op_item.as_deref(), Some("dev"));
+                assert!(args.pass_entry.is_none() && args.bw_item.is_none());
+            }
+            _ => panic!("expected Login command"),
+        }
+        assert!(Cli::try_parse_from([
+            "k8pk",
+            "login",
+            "--bw-item",
+            "dev",
+            "--pass-entry",
+            "k8pk/dev",
+        ])
+        .is_err());
+    }
+
     #[test]
     fn test_cli_global_oc_flag() {
         let cli = Cli::parse_from([
@@ -1524,11 +2935,165 @@ mod tests {
         assert_eq!(cli.oc.as_deref(), Some(Path::new("/tmp/fake-oc")));
     }
 
+    #[test]
+    fn test_cli_global_format_flags() {
+        let cli = Cli::parse_from(["k8pk", "current", "--format", "yaml"]);
+        assert_eq!(cli.format, Some(render::OutputFormat::Yaml));
+        let cli = Cli::parse_from(["k8pk", "--yaml", "contexts"]);
+        assert!(cli.yaml);
+        assert!(Cli::try_parse_from(["k8pk", "--yaml", "--format", "json", "current"]).is_err());
+        let cli = Cli::parse_from([
+            "k8pk",
+            "env",
+            "--context",
+            "dev",
+            "--format",
+            "github-actions",
+        ]);
+        assert_eq!(
+            cli.format.and_then(commands::env_file_format),
+            Some("github-actions")
+        );
+    }
+
+    #[test]
+    fn test_cli_global_picker() {
+        let cli = Cli::parse_from(["k8pk", "ctx", "--picker", "fzf"]);
+        assert_eq!(cli.picker, Some(picker::PickerKind::Fzf));
+        let cli = Cli::parse_from(["k8pk", "--picker", "inquire", "pick"]);
+        assert_eq!(cli.picker, Some(picker::PickerKind::Builtin));
+        assert!(Cli::try_parse_from(["k8pk", "--picker", "peco"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_global_quiet_after_subcommand() {
+        let cli = Cli::parse_from(["k8pk", "merge", "--files", "a.yaml", "--quiet"]);
+        assert!(cli.quiet);
+        let cli = Cli::parse_from(["k8pk", "-q", "login", "--server", "https://api:6443"]);
+        assert!(cli.quiet);
+    }
+
+    #[test]
+    fn test_cli_alias_subcommands() {
+        let cli = Cli::parse_from(["k8pk", "alias"]);
+        assert!(matches!(
+            cli.command,
+            Some(Command::Alias(cli::AliasArgs {
+                command: None,
+                install: false,
+                ..
+            }))
+        ));
+        let cli = Cli::parse_from(["k8pk", "alias", "add", "p", "prod", "--force"]);
+        match cli.command {
+            Some(Command::Alias(cli::AliasArgs {
+                command: Some(cli::AliasCommand::Add { name, force, .. }),
+                ..
+            })) => {
+                assert_eq!(name, "p");
+                assert!(force);
+            }
+            _ => panic!("expected alias add"),
+        }
+        let cli = Cli::parse_from(["k8pk", "alias", "--install", "--shell", "zsh"]);
+        assert!(matches!(
+            cli.command,
+            Some(Command::Alias(cli::AliasArgs { install: true, .. }))
+        ));
+        assert!(Cli::try_parse_from(["k8pk", "alias", "--shell", "zsh"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_pf_add() {
+        let cli = Cli::parse_from([
+            "k8pk", "pf", "add", "dev", "web", "svc/api", "8080:80", "9090", "--name", "api",
+        ]);
+        match cli.command {
+            Some(Command::Pf(cli::PfCommand::Add {
+                target,
+                ports,
+                name,
+                ..
+            })) => {
+                assert_eq!(target, "svc/api");
+                assert_eq!(ports, ["8080:80", "9090"]);
+                assert_eq!(name.as_deref(), Some("api"));
+            }
+            _ => panic!("expected pf add"),
+        }
+        assert!(Cli::try_parse_from(["k8pk", "pf", "add", "dev", "web", "svc/api"]).is_err());
+        assert!(Cli::try_parse_from(["k8pk", "port-forward", "ls"]).is_ok());
+    }
+
+    #[test]
+    fn test_cli_shell_pod() {
+        let cli = Cli::parse_from(["k8pk", "shell-pod", "-n", "web"]);
+        match cli.command {
+            Some(Command::ShellPod(cli::ShellPodArgs {
+                image,
+                shell,
+                namespace,
+                context,
+                ..
+            })) => {
+                assert_eq!(image, "busybox");
+                assert_eq!(shell, "sh");
+                assert_eq!(namespace.as_deref(), Some("web"));
+                assert_eq!(context, None);
+            }
+            _ => panic!("expected shell-pod"),
+        }
+    }
+
+    #[test]
+    fn test_cli_events() {
+        let cli = Cli::parse_from(["k8pk", "events", "-A", "--watch-only"]);
+        assert!(matches!(
+            cli.command,
+            Some(Command::Events(cli::EventsArgs {
+                all_namespaces: true,
+                watch_only: true,
+                ..
+            }))
+        ));
+        assert!(Cli::try_parse_from(["k8pk", "events", "-A", "-n", "web"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_sources() {
+        let cli = Cli::parse_from(["k8pk", "sources"]);
+        assert!(matches!(
+            cli.command,
+            Some(Command::Sources { command: None })
+        ));
+        let cli = Cli::parse_from(["k8pk", "sources", "disable", "~/.kube/acme.yaml"]);
+        match cli.command {
+            Some(Command::Sources {
+                command: Some(cli::SourcesCommand::Disable { path }),
+            }) => assert_eq!(path, "~/.kube/acme.yaml"),
+            _ => panic!("expected sources disable"),
+        }
+        assert!(Cli::try_parse_from(["k8pk", "sources", "enable"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_sync() {
+        let cli = Cli::parse_from(["k8pk", "sync", "--repo", "git@x:kube.git", "--json"]);
+        match cli.command {
+            Some(Command::Sync(cli::SyncArgs { repo, branch, json })) => {
+                assert_eq!(repo.as_deref(), Some("git@x:kube.git"));
+                assert!(branch.is_none());
+                assert!(json);
+            }
+            _ => panic!("expected sync"),
+        }
+    }
+
     #[test]
     fn test_cli_info_oc() {
         let cli = Cli::parse_from(["k8pk", "info", "oc"]);
         match cli.command {
-            Some(Command::Info { what, .. }) => assert_eq!(what, "oc"),
+            Some(Command::Info(cli::InfoArgs { what, .. })) => assert_eq!(what, "oc"),
             _ => panic!("expected Info oc"),
         }
     }
@@ -0,0 +1,320 @@
+//! Interactive list selection. The context, cluster, and namespace pickers go
+//! through [`select`], which hands the list to an external fuzzy finder (fzf
+//! or sk) when `--picker` / `K8PK_PICKER` / `picker:` asks for one and it is
+//! installed, and uses the built-in inquire list otherwise.
+
+pub use crate::cli::PickerKind;
+use crate::error::{K8pkError, Result};
+use crate::prompt::Ask;
+use inquire::Select;
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+
+/// Env var holding the picker (overrides the config `picker:`)
+pub const PICKER_ENV: &str = "K8PK_PICKER";
+
+// `--picker` and the config `picker:` live in the process, not the
+// environment, so shells and programs k8pk starts don't inherit them
+static PICKER_FLAG: OnceLock<PickerKind> = OnceLock::new();
+static CONFIG_PICKER: OnceLock<String> = OnceLock::new();
+
+/// Record the global `--picker` for this process
+pub fn set_picker(kind: PickerKind) {
+    let _ = PICKER_FLAG.set(kind);
+}
+
+/// Record the config `picker:` for this process; `K8PK_PICKER` still wins
+pub fn set_config_picker(name: &str) {
+    let _ = CONFIG_PICKER.set(name.to_string());
+}
+
+/// Name of a picker, as `K8PK_PICKER` spells it (also the finder's program)
+pub fn name(kind: PickerKind) -> &'static str {
+    match kind {
+        PickerKind::Builtin => "builtin",
+        PickerKind::Fzf => "fzf",
+        PickerKind::Sk => "sk",
+    }
+}
+
+fn parse(s: &str) -> Option<PickerKind> {
+    match s.trim().to_lowercase().as_str() {
+        "builtin" | "inquire" | "" => Some(PickerKind::Builtin),
+        "fzf" => Some(PickerKind::Fzf),
+        "sk" | "skim" => Some(PickerKind::Sk),
+        _ => None,
+    }
+}
+
+/// Process-wide picker: `--picker`, then `K8PK_PICKER`, then the config
+/// (builtin when unset or unrecognised)
+pub fn kind() -> PickerKind {
+    if let Some(kind) = PICKER_FLAG.get() {
+        return *kind;
+    }
+    match std::env::var(PICKER_ENV)
+        .ok()
+        .or_else(|| CONFIG_PICKER.get().cloned())
+    {
+        Some(v) => parse(&v).unwrap_or_else(|| {
+            eprintln!(
+                "warning: unknown picker '{}' (expected builtin, fzf, or sk); using builtin",
+                v
+            );
+            PickerKind::Builtin
+        }),
+        None => PickerKind::Builtin,
+    }
+}
+
+/// Let the user choose one of `items`. `preview` is a shell command run by
+/// fzf/sk for the highlighted line (`{1}` is its first word); the built-in
+/// picker ignores it.
+pub fn select(message: &str, items: Vec<String>, preview: Option<&str>) -> Result<String> {
+    match external_program(message)? {
+        Some(program) => run_external(&program, &finder_args(message, preview), &items),
+        None => Select::new(message, items)
+            .with_page_size(20)
+            .with_help_message(&crate::i18n::tr("help-select"))
+            .ask(),
+    }
+}
+
+/// Like [`select`], with the items listed under a header per group (groups
+/// and items in the given order). In the built-in picker choosing a header
+/// collapses or expands its group; fzf/sk get a flat list with the group
+/// after each item.
+pub fn select_grouped(
+    message: &str,
+    groups: &[(String, Vec<String>)],
+    preview: Option<&str>,
+) -> Result<String> {
+    match external_program(message)? {
+        Some(program) => {
+            let mut lines = Vec::new();
+            let mut items = HashMap::new();
+            for (group, members) in groups {
+                for item in members {
+                    let line = format!("{}  [{}]", item, group);
+                    items.insert(line.clone(), item.clone());
+                    lines.push(line);
+                }
+            }
+            let chosen = run_external(&program, &finder_args(message, preview), &lines)?;
+            Ok(items.get(&chosen).cloned().unwrap_or(chosen))
+        }
+        None => {
+            let mut collapsed = HashSet::new();
+            let mut cursor = 0;
+            loop {
+                let rows = grouped_rows(groups, &collapsed);
+                match Select::new(message, rows)
+                    .with_page_size(20)
+                    .with_help_message(&crate::i18n::tr("help-select"))
+                    .with_starting_cursor(cursor)
+                    .ask()?
+                {
+                    GroupRow::Item(item) => return Ok(item.to_string()),
+                    GroupRow::Header { index, .. } => {
+                        if !collapsed.remove(&index) {
+                            collapsed.insert(index);
+                        }
+                        cursor = grouped_rows(groups, &collapsed)
+                            .iter()
+                            .position(
+                                |r| matches!(r, GroupRow::Header { index: i, .. } if *i == index),
+                            )
+                            .unwrap_or(0);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Line of the grouped built-in picker
+#[derive(Debug, Clone, PartialEq)]
+enum GroupRow<'a> {
+    Header {
+        index: usize,
+        group: &'a str,
+        count: usize,
+        collapsed: bool,
+    },
+    Item(&'a str),
+}
+
+impl std::fmt::Display for GroupRow<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GroupRow::Header {
+                group,
+                count,
+                collapsed,
+                ..
+            } => write!(
+                f,
+                "{} {} ({})",
+                if *collapsed { "+" } else { "-" },
+                group,
+                count
+            ),
+            GroupRow::Item(item) => write!(f, "    {}", item),
+        }
+    }
+}
+
+fn grouped_rows<'a>(
+    groups: &'a [(String, Vec<String>)],
+    collapsed: &HashSet<usize>,
+) -> Vec<GroupRow<'a>> {
+    let mut rows = Vec::new();
+    for (index, (group, items)) in groups.iter().enumerate() {
+        let is_collapsed = collapsed.contains(&index);
+        rows.push(GroupRow::Header {
+            index,
+            group,
+            count: items.len(),
+            collapsed: is_collapsed,
+        });
+        if !is_collapsed {
+            rows.extend(items.iter().map(|i| GroupRow::Item(i)));
+        }
+    }
+    rows
+}
+
+/// The fzf/sk binary to use, or None for the built-in picker
+fn external_program(message: &str) -> Result<Option<PathBuf>> {
+    let program = match kind() {
+        PickerKind::Builtin => None,
+        external => {
+            let found = which::which(name(external)).ok();
+            if found.is_none() && !crate::render::quiet() {
+                eprintln!(
+                    "{} not found on PATH; using the built-in picker",
+                    name(external)
+                );
+            }
+            found
+        }
+    };
+    if program.is_some() && crate::prompt::no_input() {
+        return Err(K8pkError::InputRequired(
+            message.trim().trim_end_matches(':').to_string(),
+        ));
+    }
+    Ok(program)
+}
+
+/// Preview command showing `k8pk which --peek` for the highlighted context
+/// (fzf/sk run it in the background, so a slow cluster doesn't block typing)
+pub fn which_preview() -> String {
+    let exe = std::env::current_exe()
+        .ok()
+        .map(|p| p.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "k8pk".to_string());
+    format!("{} which --peek {{1}}", shell_words::quote(&exe))
+}
+
+/// Arguments understood by both fzf and sk
+fn finder_args(message: &str, preview: Option<&str>) -> Vec<String> {
+    let prompt = format!("{}> ", message.trim().trim_end_matches(':'));
+    let mut args = vec![
+        "--prompt".to_string(),
+        prompt,
+        "--height".to_string(),
+        "40%".to_string(),
+        "--reverse".to_string(),
+    ];
+    if crate::theme::theme() == crate::theme::Theme::Mono {
+        args.push("--color=bw".to_string());
+    }
+    if let Some(preview) = preview {
+        args.push("--preview".to_string());
+        args.push(preview.to_string());
+    }
+    args
+}
+
+/// Feed `items` to the finder on stdin and read the chosen line from stdout.
+/// The finder draws on the terminal itself; no selection (Esc, Ctrl-C, or no
+/// match) is reported as cancelled.
+fn run_external(program: &Path, args: &[String], items: &[String]) -> Result<String> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // The finder may exit before reading everything; that is not an error
+        let _ = stdin.write_all(items.join("\n").as_bytes());
+    }
+    let output = child.wait_with_output()?;
+    let selected = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    if !output.status.success() || selected.is_empty() {
+        return Err(K8pkError::Cancelled);
+    }
+    Ok(selected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_picker_names() {
+        assert_eq!(parse("FZF"), Some(PickerKind::Fzf));
+        assert_eq!(parse("skim"), Some(PickerKind::Sk));
+        assert_eq!(parse("inquire"), Some(PickerKind::Builtin));
+        assert_eq!(parse("peco"), None);
+        for kind in [PickerKind::Builtin, PickerKind::Fzf, PickerKind::Sk] {
+            assert_eq!(parse(name(kind)), Some(kind));
+        }
+    }
+
+    #[test]
+    fn grouped_rows_collapse() {
+        let groups = vec![
+            ("eks".to_string(), vec!["a".to_string(), "b".to_string()]),
+            ("gke".to_string(), vec!["c".to_string()]),
+        ];
+        let rows = grouped_rows(&groups, &HashSet::new());
+        let shown: Vec<String> = rows.iter().map(|r| r.to_string()).collect();
+        assert_eq!(shown, ["- eks (2)", "    a", "    b", "- gke (1)", "    c"]);
+        let rows = grouped_rows(&groups, &HashSet::from([0]));
+        let shown: Vec<String> = rows.iter().map(|r| r.to_string()).collect();
+        assert_eq!(shown, ["+ eks (2)", "- gke (1)", "    c"]);
+    }
+
+    #[test]
+    fn finder_args_include_preview() {
+        let args = finder_args("Select context:", Some("k8pk which {1}"));
+        assert_eq!(args[..2], ["--prompt", "Select context> "]);
+        assert_eq!(args[args.len() - 2..], ["--preview", "k8pk which {1}"]);
+        assert!(!finder_args("Select namespace:", None).contains(&"--preview".to_string()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn run_external_reads_selection() {
+        let items = vec!["dev".to_string(), "prod *".to_string()];
+        let tail = which::which("tail").unwrap();
+        let picked = run_external(&tail, &["-n".into(), "1".into()], &items).unwrap();
+        assert_eq!(picked, "prod *");
+
+        let falsy = which::which("false").unwrap();
+        assert!(matches!(
+            run_external(&falsy, &[], &items),
+            Err(K8pkError::Cancelled)
+        ));
+    }
+}
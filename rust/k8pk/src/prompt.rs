@@ -0,0 +1,115 @@
+//! Prompt gate: every interactive question goes through [`Ask::ask`] so that
+//! `--no-input` / `K8PK_NO_INPUT` refuses it instead of waiting on a terminal.
+
+use crate::error::{K8pkError, Result};
+use inquire::{Confirm, InquireError, MultiSelect, Password, Select, Text};
+use std::fmt::Display;
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Env var that disables all prompts
+pub const NO_INPUT_ENV: &str = "K8PK_NO_INPUT";
+
+// `--no-input` lives in the process, not the environment, so shells k8pk
+// spawns still prompt
+static NO_INPUT_FLAG: AtomicBool = AtomicBool::new(false);
+
+/// Record the global `--no-input` for this process
+pub fn set_no_input() {
+    NO_INPUT_FLAG.store(true, Ordering::Relaxed);
+}
+
+/// Prompts are disabled: `--no-input`, or `K8PK_NO_INPUT` set to anything but
+/// empty, `0`, or `false`
+pub fn no_input() -> bool {
+    NO_INPUT_FLAG.load(Ordering::Relaxed)
+        || std::env::var(NO_INPUT_ENV)
+            .is_ok_and(|v| !matches!(v.trim().to_lowercase().as_str(), "" | "0" | "false"))
+}
+
+/// Whether k8pk may ask the user something: stdin is a terminal and input is allowed
+pub fn can_prompt() -> bool {
+    !no_input() && std::io::stdin().is_terminal()
+}
+
+/// `inquire` prompt that fails fast under `--no-input`
+pub trait Ask {
+    type Output;
+    fn ask(self) -> Result<Self::Output>;
+}
+
+fn gate(message: &str) -> Result<()> {
+    if no_input() {
+        return Err(K8pkError::InputRequired(
+            message.trim().trim_end_matches(':').to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn map_err(e: InquireError) -> K8pkError {
+    match e {
+        InquireError::NotTTY => K8pkError::NoTty,
+        _ => K8pkError::Cancelled,
+    }
+}
+
+impl Ask for Text<'_> {
+    type Output = String;
+    fn ask(self) -> Result<String> {
+        gate(self.message)?;
+        self.prompt().map_err(map_err)
+    }
+}
+
+impl Ask for Password<'_> {
+    type Output = String;
+    fn ask(self) -> Result<String> {
+        gate(self.message)?;
+        self.prompt().map_err(map_err)
+    }
+}
+
+impl Ask for Confirm<'_> {
+    type Output = bool;
+    fn ask(self) -> Result<bool> {
+        gate(self.message)?;
+        self.prompt().map_err(map_err)
+    }
+}
+
+impl<T: Display> Ask for Select<'_, T> {
+    type Output = T;
+    fn ask(self) -> Result<T> {
+        gate(self.message)?;
+        self.prompt().map_err(map_err)
+    }
+}
+
+impl<T: Display> Ask for MultiSelect<'_, T> {
+    type Output = Vec<T>;
+    fn ask(self) -> Result<Vec<T>> {
+        gate(self.message)?;
+        self.prompt().map_err(map_err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_input_refuses_prompts() {
+        std::env::set_var(NO_INPUT_ENV, "1");
+        let err = Text::new("Username:").ask().unwrap_err();
+        let confirmed = Confirm::new("Save?").ask().unwrap_or(false);
+        std::env::set_var(NO_INPUT_ENV, "false");
+        let disabled = no_input();
+        std::env::remove_var(NO_INPUT_ENV);
+
+        assert!(matches!(err, K8pkError::InputRequired(ref m) if m == "Username"));
+        assert!(err.to_string().contains("--no-input"));
+        assert!(!confirmed);
+        assert!(!disabled);
+    }
+}
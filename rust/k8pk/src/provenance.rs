@@ -0,0 +1,246 @@
+//! Provenance of generated kubeconfigs: each isolated file records, as a
+//! `k8pk.io/source` extension, the context and kubeconfig file it came from
+//! and a hash of that file when it was generated. With `session_ttl` set it
+//! also records when the session using the file expires.
+
+use crate::clock::now_secs;
+use crate::commands::session_credential;
+use crate::error::Result;
+use crate::kubeconfig::KubeConfig;
+use serde::{Deserialize, Serialize};
+use serde_yaml_ng::{Mapping, Value as Yaml};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Name of the top-level kubeconfig extension holding the [`Provenance`]
+pub const EXTENSION_NAME: &str = "k8pk.io/source";
+
+/// Env var that keeps existing isolated kubeconfigs as they are, even when
+/// their source changed (like `--no-refresh`)
+pub const NO_REFRESH_ENV: &str = "K8PK_NO_REFRESH";
+
+// `--no-refresh` lives in the process, not the environment, so k8pk run from
+// a shell it started still refreshes
+static NO_REFRESH_FLAG: AtomicBool = AtomicBool::new(false);
+static FORCE_REFRESH_FLAG: AtomicBool = AtomicBool::new(false);
+
+/// Record the global `--no-refresh` for this process
+pub fn set_no_refresh() {
+    NO_REFRESH_FLAG.store(true, Ordering::Relaxed);
+}
+
+/// Regenerate for the rest of this process whatever `--no-refresh` or
+/// `K8PK_NO_REFRESH` say (`k8pk refresh`)
+pub fn set_force_refresh() {
+    FORCE_REFRESH_FLAG.store(true, Ordering::Relaxed);
+}
+
+/// `--no-refresh`, or `K8PK_NO_REFRESH` set to anything but empty, `0`, or
+/// `false`, unless [`set_force_refresh`] was called
+pub fn refresh_disabled() -> bool {
+    if FORCE_REFRESH_FLAG.load(Ordering::Relaxed) {
+        return false;
+    }
+    NO_REFRESH_FLAG.load(Ordering::Relaxed)
+        || std::env::var(NO_REFRESH_ENV)
+            .is_ok_and(|v| !matches!(v.trim().to_lowercase().as_str(), "" | "0" | "false"))
+}
+
+/// The user entries (credentials) differ between two kubeconfigs
+pub fn credentials_changed(old: &KubeConfig, new: &KubeConfig) -> bool {
+    let credentials = |cfg: &KubeConfig| -> Vec<_> {
+        cfg.users
+            .iter()
+            .map(|u| (u.name.clone(), session_credential::ungated(&u.rest)))
+            .collect()
+    };
+    credentials(old) != credentials(new)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Provenance {
+    /// Context name in the source file at generation time
+    pub context: String,
+    pub file: PathBuf,
+    /// [`hash_bytes`] of the source file
+    pub hash: String,
+    /// Unix seconds
+    pub generated_at: u64,
+    pub k8pk_version: String,
+    /// Unix seconds after which commands in the session refuse to run
+    /// (`session_ttl`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u64>,
+}
+
+impl Provenance {
+    /// Provenance for an isolated copy of `context` from `file`, generated now
+    pub fn for_source(context: &str, file: &Path) -> Result<Self> {
+        Ok(Provenance {
+            context: context.to_string(),
+            file: file.to_path_buf(),
+            hash: hash_bytes(&fs::read(file)?),
+            generated_at: now_secs(),
+            k8pk_version: env!("CARGO_PKG_VERSION").to_string(),
+            expires_at: None,
+        })
+    }
+
+    /// Seconds until the session expires (negative once it has), `None`
+    /// without a TTL
+    pub fn expires_in(&self) -> Option<i64> {
+        self.expires_at.map(|at| at as i64 - now_secs() as i64)
+    }
+
+    /// The session TTL has run out
+    pub fn expired(&self) -> bool {
+        self.expires_in().is_some_and(|left| left <= 0)
+    }
+
+    /// Same context, file, and file content
+    pub fn same_source(&self, other: &Provenance) -> bool {
+        self.context == other.context && self.file == other.file && self.hash == other.hash
+    }
+
+    /// The source file is gone or its content no longer matches the hash
+    pub fn source_changed(&self) -> bool {
+        fs::read(&self.file)
+            .map(|bytes| hash_bytes(&bytes) != self.hash)
+            .unwrap_or(true)
+    }
+
+    /// This record for a copy regenerated over one recorded as `previous`.
+    /// An unchanged source keeps its original generation time. A running
+    /// session keeps its expiry even when the source changed (a token
+    /// refresh must not extend it); an expired one starts over.
+    pub fn carried_over(&self, previous: &Provenance) -> Provenance {
+        let mut kept = self.clone();
+        if self.same_source(previous) {
+            kept.generated_at = previous.generated_at;
+        }
+        if self.expires_at.is_some() && previous.expires_at.is_some() && !previous.expired() {
+            kept.expires_at = previous.expires_at;
+        }
+        kept
+    }
+}
+
+/// Stable content hash (64-bit FNV-1a), as `fnv1a64:<hex>`. Not
+/// cryptographic; it only detects that a source file changed.
+pub fn hash_bytes(bytes: &[u8]) -> String {
+    let hash = bytes.iter().fold(0xcbf2_9ce4_8422_2325u64, |h, b| {
+        (h ^ u64::from(*b)).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("fnv1a64:{:016x}", hash)
+}
+
+/// The provenance recorded in `cfg`, if any
+pub fn read(cfg: &KubeConfig) -> Option<Provenance> {
+    let Some(Yaml::Sequence(entries)) = &cfg.extensions else {
+        return None;
+    };
+    let entry = entries
+        .iter()
+        .find(|e| e.get("name").and_then(Yaml::as_str) == Some(EXTENSION_NAME))?;
+    serde_yaml_ng::from_value(entry.get("extension")?.clone()).ok()
+}
+
+/// The provenance recorded in the kubeconfig at `path` (None for files that
+/// don't parse, such as encrypted ones)
+pub fn read_file(path: &Path) -> Option<Provenance> {
+    let content = fs::read_to_string(path).ok()?;
+    read(&serde_yaml_ng::from_str(&content).ok()?)
+}
+
+/// Record `provenance` in `cfg`, replacing an earlier record
+pub fn write(cfg: &mut KubeConfig, provenance: &Provenance) -> Result<()> {
+    let mut entry = Mapping::new();
+    entry.insert(Yaml::from("name"), Yaml::from(EXTENSION_NAME));
+    entry.insert(
+        Yaml::from("extension"),
+        serde_yaml_ng::to_value(provenance)?,
+    );
+    let mut entries = match cfg.extensions.take() {
+        Some(Yaml::Sequence(entries)) => entries,
+        _ => Vec::new(),
+    };
+    entries.retain(|e| e.get("name").and_then(Yaml::as_str) != Some(EXTENSION_NAME));
+    entries.push(Yaml::Mapping(entry));
+    cfg.extensions = Some(Yaml::Sequence(entries));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip_and_change_detection() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("config");
+        fs::write(&source, "clusters: []\n").unwrap();
+
+        let provenance = Provenance::for_source("dev", &source).unwrap();
+        assert_eq!(provenance.hash, hash_bytes(b"clusters: []\n"));
+        assert!(!provenance.source_changed());
+
+        let mut cfg: KubeConfig = serde_yaml_ng::from_str(
+            "extensions:\n- name: other\n  extension: {a: 1}\n- name: k8pk.io/source\n  extension: {}\n",
+        )
+        .unwrap();
+        write(&mut cfg, &provenance).unwrap();
+        let yaml = serde_yaml_ng::to_string(&cfg).unwrap();
+        let reread: KubeConfig = serde_yaml_ng::from_str(&yaml).unwrap();
+        assert_eq!(read(&reread), Some(provenance.clone()));
+        assert!(yaml.contains("name: other"));
+        assert_eq!(yaml.matches(EXTENSION_NAME).count(), 1);
+
+        let mut relogged = cfg.clone();
+        assert!(!credentials_changed(&cfg, &relogged));
+        relogged.users = serde_yaml_ng::from_str("- name: u\n  user: {token: new}\n").unwrap();
+        assert!(credentials_changed(&cfg, &relogged));
+
+        fs::write(&source, "clusters: [] # edited\n").unwrap();
+        assert!(provenance.source_changed());
+        fs::remove_file(&source).unwrap();
+        assert!(provenance.source_changed());
+    }
+
+    #[test]
+    fn carried_over_keeps_a_running_session() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("config");
+        fs::write(&source, "users: [{name: u, user: {token: a}}]\n").unwrap();
+        let started = Provenance {
+            generated_at: now_secs() - 600,
+            expires_at: Some(now_secs() + 3000),
+            ..Provenance::for_source("dev", &source).unwrap()
+        };
+        let regenerate = || {
+            let mut p = Provenance::for_source("dev", &source).unwrap();
+            p.expires_at = Some(p.generated_at + 3600);
+            p
+        };
+
+        let kept = regenerate().carried_over(&started);
+        assert_eq!(kept.generated_at, started.generated_at);
+        assert_eq!(kept.expires_at, started.expires_at);
+
+        // A token refresh in the source mid-session doesn't extend it
+        fs::write(&source, "users: [{name: u, user: {token: b}}]\n").unwrap();
+        let refreshed = regenerate();
+        let kept = refreshed.carried_over(&started);
+        assert_eq!(kept.hash, refreshed.hash);
+        assert_eq!(kept.generated_at, refreshed.generated_at);
+        assert_eq!(kept.expires_at, started.expires_at);
+
+        // An expired session starts over
+        let expired = Provenance {
+            expires_at: Some(now_secs() - 1),
+            ..started.clone()
+        };
+        assert_eq!(refreshed.carried_over(&expired), refreshed);
+    }
+}
@@ -0,0 +1,278 @@
+//! Remote kubeconfig sources: `https://` entries in `configs.include` are
+//! downloaded to `~/.local/share/k8pk/remote` and merged like local files.
+//! A download is reused for `configs.remote_ttl` seconds, then revalidated
+//! with its ETag. When the server can't be reached (or offline mode is on)
+//! the last copy is used.
+
+use crate::clock::now_secs;
+use crate::config::{self, K8pkConfig, RemoteAuth};
+use crate::error::{K8pkError, Result};
+use crate::kubeconfig::{self, KubeConfig};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+const DEFAULT_TTL_SECS: u64 = 3600;
+const FETCH_TIMEOUT_SECS: u64 = 5;
+/// After a failed refresh, wait this long before trying again
+const RETRY_SECS: u64 = 300;
+
+/// Sidecar of a cached download
+#[derive(serde::Serialize, serde::Deserialize, Debug, Default)]
+struct CacheMeta {
+    url: String,
+    #[serde(default)]
+    etag: Option<String>,
+    fetched_at: u64,
+}
+
+/// True for `configs.include` entries that are URLs rather than paths
+pub fn is_remote(pattern: &str) -> bool {
+    pattern.starts_with("https://") || pattern.starts_with("http://")
+}
+
+/// Local copy of the kubeconfig at `url`, downloaded or revalidated when
+/// due. Problems are printed as warnings; `None` means there is no copy to
+/// merge, so one broken URL never blocks the local kubeconfigs.
+pub fn cached_source(url: &str, config: &K8pkConfig) -> Option<PathBuf> {
+    if !url.starts_with("https://") {
+        eprintln!(
+            "warning: skipping include {}: remote kubeconfigs must use https://",
+            url
+        );
+        return None;
+    }
+    let synced = crate::storage::data_dir().and_then(|data| {
+        sync(
+            url,
+            config::remote_auth_with(config, url),
+            config.configs.remote_ttl.unwrap_or(DEFAULT_TTL_SECS),
+            config::offline(),
+            &data.join("remote"),
+        )
+    });
+    match synced {
+        Ok(path) => Some(path),
+        Err(e) => {
+            let reason = e.to_string();
+            eprintln!(
+                "warning: skipping include {}: {}",
+                url,
+                reason.lines().next().unwrap_or_default()
+            );
+            None
+        }
+    }
+}
+
+fn cache_key(url: &str) -> String {
+    Sha256::digest(url.as_bytes())
+        .iter()
+        .take(8)
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// `(name, value)` of the auth header, with the token read from the
+/// environment or the output of `token_cmd`
+fn auth_header(auth: &RemoteAuth) -> Result<(String, String)> {
+    let token = match (&auth.token_env, &auth.token_cmd) {
+        (Some(var), _) => std::env::var(var)
+            .map_err(|_| K8pkError::InvalidArgument(format!("remote_auth: ${} is not set", var)))?,
+        (None, Some(cmd)) => {
+            let argv = shell_words::split(cmd).map_err(|e| {
+                K8pkError::InvalidArgument(format!("remote_auth token_cmd '{}': {}", cmd, e))
+            })?;
+            let (program, args) = argv.split_first().ok_or_else(|| {
+                K8pkError::InvalidArgument("remote_auth token_cmd is empty".into())
+            })?;
+            let output = Command::new(program).args(args).output()?;
+            if !output.status.success() {
+                return Err(K8pkError::CommandFailed(format!(
+                    "{}: {}",
+                    cmd,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                )));
+            }
+            // `pass show` prints the secret on the first line
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .next()
+                .unwrap_or_default()
+                .to_string()
+        }
+        (None, None) => {
+            return Err(K8pkError::InvalidArgument(
+                "remote_auth needs token_env or token_cmd".into(),
+            ))
+        }
+    };
+    let name = auth.header.as_deref().unwrap_or("Authorization");
+    let value = auth
+        .value
+        .as_deref()
+        .unwrap_or("Bearer {token}")
+        .replace("{token}", token.trim());
+    Ok((name.to_string(), value))
+}
+
+/// Bring the cached copy of `url` in `dir` up to date and return its path
+fn sync(
+    url: &str,
+    auth: Option<&RemoteAuth>,
+    ttl_secs: u64,
+    offline: bool,
+    dir: &Path,
+) -> Result<PathBuf> {
+    let key = cache_key(url);
+    let file = dir.join(format!("{}.yaml", key));
+    let meta_path = dir.join(format!("{}.json", key));
+    let meta: Option<CacheMeta> = fs::read_to_string(&meta_path)
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .filter(|_| file.exists());
+
+    if let Some(meta) = &meta {
+        if offline || now_secs().saturating_sub(meta.fetched_at) < ttl_secs {
+            return Ok(file);
+        }
+    } else if offline {
+        return Err(K8pkError::Offline(format!("downloading {}", url)));
+    }
+
+    let client = reqwest::blocking::Client::builder()
+        .timeout(Duration::from_secs(FETCH_TIMEOUT_SECS))
+        .build()
+        .map_err(|e| K8pkError::HttpError(e.to_string()))?;
+    let mut request = client.get(url);
+    if let Some(auth) = auth {
+        let (name, value) = auth_header(auth)?;
+        request = request.header(name, value);
+    }
+    if let Some(etag) = meta.as_ref().and_then(|m| m.etag.as_deref()) {
+        request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+    }
+
+    let fetched = request
+        .send()
+        .map_err(|e| K8pkError::HttpError(e.to_string()))
+        .and_then(|response| {
+            let status = response.status();
+            if status == reqwest::StatusCode::NOT_MODIFIED && meta.is_some() {
+                return Ok(None);
+            }
+            if !status.is_success() {
+                return Err(K8pkError::HttpError(format!("{} returned {}", url, status)));
+            }
+            let etag = response
+                .headers()
+                .get(reqwest::header::ETAG)
+                .and_then(|v| v.to_str().ok())
+                .map(String::from);
+            let body = response
+                .text()
+                .map_err(|e| K8pkError::HttpError(e.to_string()))?;
+            serde_yaml_ng::from_str::<KubeConfig>(&body)
+                .map_err(|e| K8pkError::InvalidKubeconfig(format!("{}: {}", url, e)))?;
+            Ok(Some((body, etag)))
+        });
+
+    let etag = match fetched {
+        Ok(Some((body, etag))) => {
+            crate::storage::create_private_dir(dir)?;
+            kubeconfig::write_restricted(&file, &body)?;
+            etag
+        }
+        Ok(None) => meta.and_then(|m| m.etag),
+        Err(e) if meta.is_some() => {
+            // Keep commands fast while the server is down: retry later
+            let mut meta = meta.unwrap_or_default();
+            meta.fetched_at = now_secs().saturating_sub(ttl_secs.saturating_sub(RETRY_SECS));
+            kubeconfig::write_restricted(&meta_path, &serde_json::to_string_pretty(&meta)?)?;
+            if !crate::render::quiet() {
+                let reason = e.to_string();
+                eprintln!(
+                    "warning: could not refresh {} ({}); using the cached copy",
+                    url,
+                    reason.lines().next().unwrap_or_default()
+                );
+            }
+            return Ok(file);
+        }
+        Err(e) => return Err(e),
+    };
+    let meta = CacheMeta {
+        url: url.to_string(),
+        etag,
+        fetched_at: now_secs(),
+    };
+    kubeconfig::write_restricted(&meta_path, &serde_json::to_string_pretty(&meta)?)?;
+    Ok(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_http::{spawn_one_shot, spawn_sequential_same_socket, HttpResponse};
+
+    const KC: &str =
+        "apiVersion: v1\nkind: Config\ncontexts:\n- name: team\n  context: {cluster: c}\n";
+
+    #[test]
+    fn test_sync_caches_and_revalidates() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = spawn_sequential_same_socket(vec![
+            HttpResponse::json(200, KC).with_header("ETag", "\"v1\""),
+            HttpResponse::json(304, ""),
+        ]);
+        let url = format!("{}/team.yaml", base);
+
+        let path = sync(&url, None, 3600, false, dir.path()).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), KC);
+        // Fresh: no request (the server would answer 304 otherwise)
+        assert_eq!(sync(&url, None, 3600, false, dir.path()).unwrap(), path);
+        // Due: 304 keeps the copy and the ETag
+        assert_eq!(sync(&url, None, 0, false, dir.path()).unwrap(), path);
+        let meta: CacheMeta = serde_json::from_str(
+            &fs::read_to_string(dir.path().join(format!("{}.json", cache_key(&url)))).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(meta.etag.as_deref(), Some("\"v1\""));
+        // Server gone: stale copy
+        assert_eq!(sync(&url, None, 0, false, dir.path()).unwrap(), path);
+    }
+
+    #[test]
+    fn test_sync_rejects_bad_downloads() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = spawn_one_shot(HttpResponse::json(200, "<html>login</html>"));
+        let url = format!("{}/team.yaml", base);
+        assert!(sync(&url, None, 3600, false, dir.path()).is_err());
+        assert!(matches!(
+            sync(
+                "https://example.invalid/x.yaml",
+                None,
+                3600,
+                true,
+                dir.path()
+            ),
+            Err(K8pkError::Offline(_))
+        ));
+    }
+
+    #[test]
+    fn test_auth_header() {
+        let auth = RemoteAuth {
+            value: Some("token {token}".into()),
+            token_cmd: Some("echo secret".into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            auth_header(&auth).unwrap(),
+            ("Authorization".to_string(), "token secret".to_string())
+        );
+        assert!(auth_header(&RemoteAuth::default()).is_err());
+    }
+}
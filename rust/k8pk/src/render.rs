@@ -0,0 +1,130 @@
+//! Output policy. Every `--json` result goes through [`to_string`] so the
+//! global `--format` / `K8PK_OUTPUT` setting can switch it to YAML (or force
+//! structured output on commands run without `--json`), and informational
+//! messages check [`quiet`] so `-q` / `K8PK_QUIET` silences them in one place.
+
+pub use crate::cli::OutputFormat;
+use crate::error::Result;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+/// Env var holding a default output format for scripts
+pub const OUTPUT_ENV: &str = "K8PK_OUTPUT";
+
+/// Env var that suppresses informational messages
+pub const QUIET_ENV: &str = "K8PK_QUIET";
+
+// `--format` / `--yaml` and `-q` live in the process, not the environment, so
+// they don't reach shells and programs k8pk starts
+static FORMAT_FLAG: OnceLock<OutputFormat> = OnceLock::new();
+static QUIET_FLAG: AtomicBool = AtomicBool::new(false);
+
+/// Record the global `--format` / `--yaml` for this process
+pub fn set_format(format: OutputFormat) {
+    let _ = FORMAT_FLAG.set(format);
+}
+
+/// Record the global `-q` / `--quiet` for this process
+pub fn set_quiet() {
+    QUIET_FLAG.store(true, Ordering::Relaxed);
+}
+
+fn parse(s: &str) -> Option<OutputFormat> {
+    match s.trim().to_lowercase().as_str() {
+        "plain" | "text" => Some(OutputFormat::Plain),
+        "json" => Some(OutputFormat::Json),
+        "yaml" | "yml" => Some(OutputFormat::Yaml),
+        _ => None,
+    }
+}
+
+/// Process-wide format: `--format`, then `K8PK_OUTPUT` (plain when unset or
+/// unrecognised)
+pub fn format() -> OutputFormat {
+    FORMAT_FLAG.get().copied().unwrap_or_else(|| {
+        std::env::var(OUTPUT_ENV)
+            .ok()
+            .and_then(|v| parse(&v))
+            .unwrap_or(OutputFormat::Plain)
+    })
+}
+
+/// Whether a command should print structured output: its own `--json` flag,
+/// or a global json/yaml format
+pub fn structured(json: bool) -> bool {
+    json || format() != OutputFormat::Plain
+}
+
+/// Informational messages are suppressed: `-q`, `K8PK_QUIET` set to anything
+/// but empty, `0`, or `false`, or a structured format that owns stdout. Errors
+/// and command results are printed either way.
+pub fn quiet() -> bool {
+    let quiet = QUIET_FLAG.load(Ordering::Relaxed)
+        || std::env::var(QUIET_ENV)
+            .is_ok_and(|v| !matches!(v.trim().to_lowercase().as_str(), "" | "0" | "false"));
+    quiet || structured(false)
+}
+
+/// Map the global format onto the env/json/spawn `--output` option of
+/// pick, ctx, ns and clean; an explicit `--output` wins
+pub fn output_mode(output: Option<String>) -> Option<String> {
+    output.or_else(|| explicit_json(FORMAT_FLAG.get().copied()))
+}
+
+/// `json` for a structured `--format` given on the command line. Ignores
+/// `K8PK_OUTPUT`: these commands print shell code the wrappers eval.
+fn explicit_json(flag: Option<OutputFormat>) -> Option<String> {
+    flag.filter(|f| *f != OutputFormat::Plain)
+        .map(|_| "json".to_string())
+}
+
+/// Serialize a result in the selected format (YAML under `--format yaml`,
+/// pretty JSON otherwise)
+pub fn to_string<T: Serialize + ?Sized>(value: &T) -> Result<String> {
+    render(value, format())
+}
+
+fn render<T: Serialize + ?Sized>(value: &T, format: OutputFormat) -> Result<String> {
+    Ok(match format {
+        OutputFormat::Yaml => serde_yaml_ng::to_string(value)?.trim_end().to_string(),
+        _ => serde_json::to_string_pretty(value)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_formats() {
+        assert_eq!(parse("JSON"), Some(OutputFormat::Json));
+        assert_eq!(parse("yml"), Some(OutputFormat::Yaml));
+        assert_eq!(parse(" plain "), Some(OutputFormat::Plain));
+        assert_eq!(parse("xml"), None);
+    }
+
+    #[test]
+    fn output_mode_needs_the_flag() {
+        assert_eq!(explicit_json(None), None);
+        assert_eq!(explicit_json(Some(OutputFormat::Plain)), None);
+        assert_eq!(
+            explicit_json(Some(OutputFormat::Yaml)).as_deref(),
+            Some("json")
+        );
+        // An explicit --output wins
+        assert_eq!(output_mode(Some("env".into())).as_deref(), Some("env"));
+    }
+
+    #[test]
+    fn render_same_schema_in_json_and_yaml() {
+        let value = serde_json::json!({ "context": "dev", "namespaces": ["a", "b"] });
+        let json = render(&value, OutputFormat::Json).unwrap();
+        let yaml = render(&value, OutputFormat::Yaml).unwrap();
+        let from_json: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let from_yaml: serde_json::Value = serde_yaml_ng::from_str(&yaml).unwrap();
+        assert_eq!(from_json, value);
+        assert_eq!(from_yaml, value);
+        assert!(yaml.starts_with("context: dev"));
+    }
+}
@@ -4,7 +4,7 @@ use crate::commands;
 use crate::config;
 use crate::error::{K8pkError, Result};
 use crate::kubeconfig;
-use crate::state::CurrentState;
+use crate::state::{CurrentState, NavStack};
 
 use clap_complete::{generate, shells};
 use std::env;
@@ -99,7 +99,10 @@ fn spawn_shell_inner(
 
     let state = CurrentState::from_env();
     let nested = nested_shells_enabled();
-    let new_depth = spawn_depth(state.depth, nested);
+    // The calling shell's own state file beats K8PK_DEPTH, which programs
+    // started from a k8pk shell (editors, new terminal windows) inherit
+    let current_depth = commands::sessions::current_session().map_or(state.depth, |s| s.depth);
+    let new_depth = spawn_depth(current_depth, nested);
 
     if nested && new_depth > 1 {
         eprintln!(
@@ -116,16 +119,7 @@ fn spawn_shell_inner(
         )));
     }
 
-    let display_context = {
-        let content = std::fs::read_to_string(kubeconfig)?;
-        let cfg: kubeconfig::KubeConfig = serde_yaml_ng::from_str(&content)?;
-        let server_url = cfg
-            .clusters
-            .first()
-            .and_then(|c| kubeconfig::extract_server_url_from_cluster(&c.rest));
-        let cluster_type = kubeconfig::detect_cluster_type(context, server_url.as_deref());
-        kubeconfig::friendly_context_name(context, cluster_type)
-    };
+    let display_context = config::display_context(context, namespace, kubeconfig);
 
     if let Ok(config) = config::load() {
         if let Some(ref hooks) = config.hooks {
@@ -148,44 +142,402 @@ fn spawn_shell_inner(
     cmd.env("K8PK_CONTEXT", context);
     cmd.env("K8PK_CONTEXT_DISPLAY", &display_context);
     cmd.env("K8PK_DEPTH", new_depth.to_string());
+    for (name, value) in NavStack::after_switch(&state, context, namespace).exports() {
+        cmd.env(name, value);
+    }
 
     if let Some(ns) = namespace {
         cmd.env("K8PK_NAMESPACE", ns);
         cmd.env("OC_NAMESPACE", ns);
     }
 
-    let _ = commands::sessions::register(
-        context,
-        namespace,
-        &kubeconfig.display().to_string(),
-        Some(std::process::id()),
-    );
+    // Exec credential plugins outside the usual PATH (exec_path_hints)
+    if let Some(path) = config::hinted_path() {
+        cmd.env("PATH", path);
+    }
+
+    let settings = config::context_settings(context);
+    if let Some(proxy) = settings.proxy_url()? {
+        cmd.env("HTTPS_PROXY", proxy);
+    }
+    if let Some(dir) = start_dir(&settings, context, namespace) {
+        cmd.current_dir(dir);
+    }
+    if let Some(init) = settings.init_command(context, namespace) {
+        // Same environment and directory as the shell about to start
+        let mut init_cmd = commands::user_command(&init);
+        for (key, value) in cmd.get_envs() {
+            if let Some(value) = value {
+                init_cmd.env(key, value);
+            }
+        }
+        if let Some(dir) = cmd.get_current_dir() {
+            init_cmd.current_dir(dir);
+        }
+        if !init_cmd.status().is_ok_and(|s| s.success()) {
+            eprintln!("warning: init_cmd failed: {}", init);
+        }
+    }
+
+    let register = |pid: u32| {
+        let _ = commands::sessions::register(
+            context,
+            namespace,
+            &kubeconfig.display().to_string(),
+            Some(pid),
+            new_depth,
+        );
+    };
 
+    // With encryption the live plaintext copy must go when the shell exits,
+    // so wait for the shell instead of replacing this process
     #[cfg(unix)]
-    {
+    if spawn_mode() == config::SpawnMode::Exec && !crate::encryption::enabled(None) {
+        register(std::process::id());
         let err = cmd.exec();
-        Err(K8pkError::Io(err))
+        return Err(K8pkError::Io(err));
     }
 
-    #[cfg(not(unix))]
-    {
-        let status = cmd.status()?;
-        if !status.success() {
-            return Err(K8pkError::CommandFailed("shell exited with error".into()));
+    let mut child = cmd.spawn()?;
+    // The shell's own PID, so the exit trap from `k8pk init` and this wrapper
+    // deregister (and run stop_ctx for) the session only once between them
+    register(child.id());
+    let status = child.wait()?;
+    for entry in commands::sessions::deregister(child.id()).unwrap_or_default() {
+        let _ = commands::run_stop_hook(&entry.state());
+    }
+    let _ = crate::encryption::release_live_copy(kubeconfig);
+    after_shell_exit(context, namespace);
+    if !status.success() {
+        return Err(K8pkError::CommandFailed("shell exited with error".into()));
+    }
+    Ok(())
+}
+
+fn spawn_mode() -> config::SpawnMode {
+    config::load()
+        .ok()
+        .and_then(|c| c.shell.map(|s| s.spawn_mode))
+        .unwrap_or_default()
+}
+
+/// Once a wrapped shell has exited: record the end of its time in the
+/// context, say which shell the user is back in, and offer cleanup
+fn after_shell_exit(context: &str, namespace: Option<&str>) {
+    commands::record_session_end(context, namespace);
+    if !crate::render::quiet() {
+        match commands::sessions::current_session() {
+            Some(parent) => eprintln!(
+                "Left '{}'; back in '{}' (depth {})",
+                context, parent.context, parent.depth
+            ),
+            None => eprintln!("Left '{}'", context),
         }
-        Ok(())
     }
+    offer_stale_cleanup();
 }
 
-/// Execute a command in a specific context (streaming output)
-pub fn exec_command_in_context(
+/// Files `k8pk cleanup --orphaned` removes: unused for this many days
+const STALE_CLEANUP_DAYS: u64 = 30;
+
+/// At most once a day, and only when someone can answer: offer to remove
+/// isolated kubeconfigs that `k8pk cleanup --orphaned` would remove
+fn offer_stale_cleanup() {
+    use crate::prompt::Ask;
+
+    if !crate::prompt::can_prompt() || !io::stderr().is_terminal() {
+        return;
+    }
+    let Ok(base) = crate::storage::data_dir() else {
+        return;
+    };
+    let stamp = base.join(".cleanup_offer_stamp");
+    let asked_today = std::fs::metadata(&stamp)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.elapsed().ok())
+        .is_some_and(|age| age.as_secs() < 24 * 60 * 60);
+    if asked_today {
+        return;
+    }
+    let _ = std::fs::write(&stamp, b"");
+
+    let k8pk_config = config::load().unwrap_or_default();
+    let Ok(contexts) = kubeconfig::resolve_paths(None, &[], &k8pk_config)
+        .and_then(|paths| kubeconfig::load_merged(&paths))
+        .map(|merged| merged.context_names())
+    else {
+        return;
+    };
+    let cleanup = |dry_run| {
+        commands::cleanup_generated(STALE_CLEANUP_DAYS, true, dry_run, false, None, &contexts)
+    };
+    let Ok(stale) = cleanup(true) else {
+        return;
+    };
+    if stale.removed.is_empty() {
+        return;
+    }
+    let confirmed = inquire::Confirm::new(&crate::i18n::tr_with(
+        "confirm-remove-stale",
+        &[
+            ("count", &stale.removed.len().to_string()),
+            ("days", &STALE_CLEANUP_DAYS.to_string()),
+        ],
+    ))
+    .with_default(false)
+    .with_help_message("Same as: k8pk cleanup --orphaned")
+    .ask()
+    .unwrap_or(false);
+    if confirmed {
+        match cleanup(false) {
+            Ok(result) => eprintln!("Removed {} file(s)", result.removed.len()),
+            Err(e) => eprintln!("warning: cleanup failed: {}", e),
+        }
+    }
+}
+
+/// Configured start directory for a context's shells, if it exists
+pub fn start_dir(
+    settings: &config::ContextSettings,
+    context: &str,
+    namespace: Option<&str>,
+) -> Option<PathBuf> {
+    let dir = settings.start_dir(context, namespace)?;
+    if dir.is_dir() {
+        Some(dir)
+    } else {
+        eprintln!(
+            "warning: cwd for {} does not exist: {}",
+            context,
+            dir.display()
+        );
+        None
+    }
+}
+
+/// Where `k8pk exec` runs the command
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExecTarget {
+    Host,
+    /// A running container (`--docker`, or the one `--devcontainer` found)
+    Container(String),
+    /// `user@host` reached over ssh (`--ssh`); `redact` sends the kubeconfig
+    /// with secrets replaced by placeholders
+    Ssh {
+        host: String,
+        redact: bool,
+    },
+}
+
+impl ExecTarget {
+    /// Resolve the `--docker` / `--devcontainer` flags
+    pub fn from_flags(
+        docker: Option<String>,
+        devcontainer: Option<&Path>,
+        ssh: Option<String>,
+        redact: bool,
+    ) -> Result<Self> {
+        if let Some(host) = ssh {
+            if host.is_empty() || host.starts_with('-') {
+                return Err(K8pkError::InvalidArgument(format!(
+                    "--ssh: '{}' is not a host",
+                    host
+                )));
+            }
+            return Ok(ExecTarget::Ssh { host, redact });
+        }
+        match (docker, devcontainer) {
+            (Some(container), _) => Ok(ExecTarget::Container(container)),
+            (None, Some(dir)) => find_devcontainer(dir).map(ExecTarget::Container),
+            (None, None) => Ok(ExecTarget::Host),
+        }
+    }
+}
+
+/// Running container the devcontainer CLI (and VS Code) started for `dir`,
+/// found by its `devcontainer.local_folder` label
+fn find_devcontainer(dir: &Path) -> Result<String> {
+    let dir = dir.canonicalize().map_err(|e| {
+        K8pkError::InvalidArgument(format!("--devcontainer {}: {}", dir.display(), e))
+    })?;
+    let output = ProcCommand::new("docker")
+        .args(["ps", "-q", "--filter"])
+        .arg(format!("label=devcontainer.local_folder={}", dir.display()))
+        .output()
+        .map_err(|e| K8pkError::CommandFailed(format!("failed to run docker: {}", e)))?;
+    if !output.status.success() {
+        return Err(K8pkError::CommandFailed(format!(
+            "docker ps failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .next()
+        .map(|id| id.trim().to_string())
+        .ok_or_else(|| {
+            K8pkError::InvalidArgument(format!(
+                "no running dev container for {} (start it with 'devcontainer up')",
+                dir.display()
+            ))
+        })
+}
+
+/// `sh` script storing stdin in a private temp file and printing its path
+const COPY_SCRIPT: &str =
+    r#"umask 077 && f=$(mktemp "${TMPDIR:-/tmp}/k8pk.XXXXXX") && cat > "$f" && echo "$f""#;
+
+/// `kubeconfig` as sent to another machine: certificates inlined (host cert
+/// paths don't exist there), and with `redact` the secrets replaced
+fn portable_kubeconfig(kubeconfig: &Path, redact: bool) -> Result<String> {
+    let mut cfg: kubeconfig::KubeConfig =
+        serde_yaml_ng::from_str(&std::fs::read_to_string(kubeconfig)?)?;
+    // k8pk may not be installed where the copy goes
+    crate::commands::session_credential::ungate_credentials(&mut cfg);
+    kubeconfig::embed_certs(&mut cfg)?;
+    if redact {
+        kubeconfig::redact_secrets(&mut cfg);
+    }
+    Ok(serde_yaml_ng::to_string(&cfg)?)
+}
+
+/// Run `cmd` with `content` on stdin; returns its trimmed stdout
+fn pipe_into(mut cmd: ProcCommand, content: &str, target: &str) -> Result<String> {
+    use std::io::Write;
+
+    let program = cmd.get_program().to_string_lossy().into_owned();
+    let mut child = cmd
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .map_err(|e| K8pkError::CommandFailed(format!("failed to run {}: {}", program, e)))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(content.as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(K8pkError::CommandFailed(format!(
+            "could not copy kubeconfig to {}: {}",
+            target,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Stream `kubeconfig` into a private temp file in `container`. Returns its
+/// path there.
+fn copy_into_container(container: &str, kubeconfig: &Path) -> Result<String> {
+    // Written through the container's own shell rather than `docker cp`, so
+    // the file belongs to the container user (docker cp leaves it root-owned
+    // and unreadable for non-root toolbox users)
+    let mut cmd = ProcCommand::new("docker");
+    cmd.args(["exec", "-i", container, "sh", "-c", COPY_SCRIPT]);
+    let dest = pipe_into(cmd, &portable_kubeconfig(kubeconfig, false)?, container)?;
+    if dest.is_empty() {
+        return Err(K8pkError::CommandFailed(format!(
+            "could not create a temp file in {}",
+            container
+        )));
+    }
+    Ok(dest)
+}
+
+/// Stream `kubeconfig` into a private temp file on `host`. Returns its path there.
+fn copy_over_ssh(host: &str, kubeconfig: &Path, redact: bool) -> Result<String> {
+    let mut cmd = ProcCommand::new("ssh");
+    cmd.arg("--")
+        .arg(host)
+        .arg(format!("sh -c {}", shell_words::quote(COPY_SCRIPT)));
+    let dest = pipe_into(cmd, &portable_kubeconfig(kubeconfig, redact)?, host)?;
+    if dest.is_empty() {
+        return Err(K8pkError::CommandFailed(format!(
+            "could not create a temp file on {}",
+            host
+        )));
+    }
+    Ok(dest)
+}
+
+/// `sh` script running `command` against the copied kubeconfig at
+/// `remote_kubeconfig`, which is removed when the command exits
+fn remote_script(
+    remote_kubeconfig: &str,
+    context: &str,
+    namespace: Option<&str>,
+    command: &[String],
+) -> String {
+    let file = shell_words::quote(remote_kubeconfig);
+    let mut script = format!(
+        "trap 'rm -f {file}' EXIT; trap 'exit 129' HUP; trap 'exit 130' INT; trap 'exit 143' TERM; \
+         KUBECONFIG={file} K8PK_CONTEXT={}",
+        shell_words::quote(context)
+    );
+    if let Some(ns) = namespace {
+        let ns = shell_words::quote(ns);
+        script.push_str(&format!(" K8PK_NAMESPACE={ns} OC_NAMESPACE={ns}"));
+    }
+    script.push(' ');
+    script.push_str(&shell_words::join(command));
+    script
+}
+
+/// `ssh` invocation running `command` on `host` against the kubeconfig at
+/// `remote_kubeconfig`, which is removed when the command exits
+fn ssh_exec_command(
+    host: &str,
+    remote_kubeconfig: &str,
+    context: &str,
+    namespace: Option<&str>,
+    command: &[String],
+    tty: bool,
+) -> ProcCommand {
+    let script = remote_script(remote_kubeconfig, context, namespace, command);
+    let mut cmd = ProcCommand::new("ssh");
+    if tty {
+        cmd.arg("-t");
+    }
+    cmd.arg("--")
+        .arg(host)
+        .arg(format!("sh -c {}", shell_words::quote(&script)));
+    cmd
+}
+
+/// `docker exec` invocation running `command` in `container` against the
+/// kubeconfig copied to `container_kubeconfig`, which is removed when the
+/// command exits
+fn docker_exec_command(
+    container: &str,
+    container_kubeconfig: &str,
+    context: &str,
+    namespace: Option<&str>,
+    command: &[String],
+    tty: bool,
+) -> ProcCommand {
+    let script = remote_script(container_kubeconfig, context, namespace, command);
+    let mut cmd = ProcCommand::new("docker");
+    cmd.args(["exec", "-i"]);
+    if tty {
+        cmd.arg("-t");
+    }
+    cmd.arg(container).args(["sh", "-c", &script]);
+    cmd
+}
+
+/// Build the command for `k8pk exec`: the isolated kubeconfig (session
+/// checked unless `no_session_check`) exported on the host, or copied into
+/// the target container
+fn context_command(
     context: &str,
     namespace: Option<&str>,
     command: &[String],
-    show_header: bool,
     paths: &[PathBuf],
     no_session_check: bool,
-) -> Result<i32> {
+    target: &ExecTarget,
+    tty: bool,
+) -> Result<ProcCommand> {
     if command.is_empty() {
         return Err(K8pkError::InvalidArgument(
             "no command specified after '--'".into(),
@@ -198,8 +550,24 @@ pub fn exec_command_in_context(
     } else {
         commands::ensure_session_alive(&initial, context, namespace, paths, no_session_check, None)?
     };
-    let cache_dir = commands::isolated_cache_dir(&kubeconfig, context);
 
+    match target {
+        ExecTarget::Host => {}
+        ExecTarget::Container(container) => {
+            let dest = copy_into_container(container, &kubeconfig)?;
+            return Ok(docker_exec_command(
+                container, &dest, context, namespace, command, tty,
+            ));
+        }
+        ExecTarget::Ssh { host, redact } => {
+            let dest = copy_over_ssh(host, &kubeconfig, *redact)?;
+            return Ok(ssh_exec_command(
+                host, &dest, context, namespace, command, tty,
+            ));
+        }
+    }
+
+    let cache_dir = commands::isolated_cache_dir(&kubeconfig, context);
     let (cmd_name, args) = command
         .split_first()
         .ok_or_else(|| K8pkError::InvalidArgument("empty command".into()))?;
@@ -213,6 +581,35 @@ pub fn exec_command_in_context(
         cmd.env("K8PK_NAMESPACE", ns);
         cmd.env("OC_NAMESPACE", ns);
     }
+    if let Some(path) = config::hinted_path() {
+        cmd.env("PATH", path);
+    }
+    if let Some(proxy) = config::context_settings(context).proxy_url()? {
+        cmd.env("HTTPS_PROXY", proxy);
+    }
+    Ok(cmd)
+}
+
+/// Execute a command in a specific context (streaming output)
+pub fn exec_command_in_context(
+    context: &str,
+    namespace: Option<&str>,
+    command: &[String],
+    show_header: bool,
+    paths: &[PathBuf],
+    no_session_check: bool,
+    target: &ExecTarget,
+) -> Result<i32> {
+    let tty = io::stdin().is_terminal() && io::stdout().is_terminal();
+    let mut cmd = context_command(
+        context,
+        namespace,
+        command,
+        paths,
+        no_session_check,
+        target,
+        tty,
+    )?;
 
     if show_header && io::stdout().is_terminal() {
         let ns_display = namespace.unwrap_or("(default)");
@@ -223,6 +620,24 @@ pub fn exec_command_in_context(
     Ok(status.code().unwrap_or(1))
 }
 
+/// Split a `k8pk run` target into context and namespace. `CONTEXT/NAMESPACE`
+/// is only split when the whole string isn't itself a context (or alias),
+/// since OpenShift-style context names contain slashes.
+pub fn parse_run_target(target: &str, contexts: &[String]) -> Result<(String, Option<String>)> {
+    let resolve = |name: &str| config::resolve_alias(name);
+    let whole = resolve(target);
+    if contexts.contains(&whole) {
+        return Ok((whole, None));
+    }
+    if let Some((ctx, ns)) = target.rsplit_once('/') {
+        let ctx = resolve(ctx);
+        if !ns.is_empty() && contexts.contains(&ctx) {
+            return Ok((ctx, Some(ns.to_string())));
+        }
+    }
+    Err(K8pkError::ContextNotFound(target.to_string()))
+}
+
 /// Structured result from exec --json
 #[derive(Debug, serde::Serialize)]
 pub struct ExecResult {
@@ -240,34 +655,17 @@ pub fn exec_command_in_context_captured(
     command: &[String],
     paths: &[PathBuf],
     no_session_check: bool,
+    target: &ExecTarget,
 ) -> Result<ExecResult> {
-    if command.is_empty() {
-        return Err(K8pkError::InvalidArgument(
-            "no command specified after '--'".into(),
-        ));
-    }
-
-    let initial = commands::ensure_isolated_kubeconfig(context, namespace, paths)?;
-    let kubeconfig = if no_session_check {
-        initial
-    } else {
-        commands::ensure_session_alive(&initial, context, namespace, paths, no_session_check, None)?
-    };
-    let cache_dir = commands::isolated_cache_dir(&kubeconfig, context);
-
-    let (cmd_name, args) = command
-        .split_first()
-        .ok_or_else(|| K8pkError::InvalidArgument("empty command".into()))?;
-
-    let mut cmd = ProcCommand::new(cmd_name);
-    cmd.args(args);
-    cmd.env("KUBECONFIG", kubeconfig.as_os_str());
-    cmd.env("KUBECACHEDIR", cache_dir.as_os_str());
-    cmd.env("K8PK_CONTEXT", context);
-    if let Some(ns) = namespace {
-        cmd.env("K8PK_NAMESPACE", ns);
-        cmd.env("OC_NAMESPACE", ns);
-    }
+    let mut cmd = context_command(
+        context,
+        namespace,
+        command,
+        paths,
+        no_session_check,
+        target,
+        false,
+    )?;
     cmd.stdout(std::process::Stdio::piped());
     cmd.stderr(std::process::Stdio::piped());
 
@@ -293,16 +691,20 @@ pub fn generate_completions(shell: &str) -> Result<()> {
             generate(shells::Bash, &mut cmd, "k8pk", &mut stdout);
             print!(
                 r#"
-# Dynamic context completions for ctx subcommand
-_k8pk_dynamic_ctx() {{
+# Dynamic completions: contexts, aliases, and namespaces come from
+# `k8pk __complete`; everything else falls back to the static _k8pk
+_k8pk_dynamic() {{
     local cur="${{COMP_WORDS[COMP_CWORD]}}"
-    if [[ "${{COMP_WORDS[1]}}" == "ctx" && $COMP_CWORD -eq 2 ]]; then
-        COMPREPLY=($(compgen -W "$(k8pk complete contexts 2>/dev/null)" -- "$cur"))
-    elif [[ "${{COMP_WORDS[1]}}" == "ns" && $COMP_CWORD -eq 2 ]]; then
-        COMPREPLY=($(compgen -W "$(k8pk complete namespaces 2>/dev/null)" -- "$cur"))
+    local dyn
+    dyn="$(k8pk __complete bash "${{COMP_WORDS[@]:1:COMP_CWORD}}" 2>/dev/null)"
+    if [[ -n "$dyn" ]]; then
+        local IFS=$'\n'
+        COMPREPLY=($(compgen -W "$dyn" -- "$cur"))
+        return 0
     fi
+    _k8pk "$@"
 }}
-complete -F _k8pk_dynamic_ctx k8pk
+complete -F _k8pk_dynamic -o bashdefault -o default k8pk
 "#
             );
         }
@@ -310,17 +712,18 @@ complete -F _k8pk_dynamic_ctx k8pk
             generate(shells::Zsh, &mut cmd, "k8pk", &mut stdout);
             print!(
                 r#"
-# Dynamic context/namespace completions
-_k8pk_contexts() {{
-    local -a contexts
-    contexts=(${{(f)"$(k8pk complete contexts 2>/dev/null)"}})
-    _describe 'context' contexts
-}}
-_k8pk_namespaces() {{
-    local -a namespaces
-    namespaces=(${{(f)"$(k8pk complete namespaces 2>/dev/null)"}})
-    _describe 'namespace' namespaces
+# Dynamic completions: contexts, aliases, and namespaces come from
+# `k8pk __complete`; everything else falls back to the static _k8pk
+_k8pk_dynamic() {{
+    local -a candidates
+    candidates=(${{(f)"$(k8pk __complete zsh "${{(@)words[2,CURRENT]}}" 2>/dev/null)"}})
+    if (( ${{#candidates}} )); then
+        _describe 'value' candidates
+    else
+        _k8pk "$@"
+    fi
 }}
+compdef _k8pk_dynamic k8pk
 "#
             );
         }
@@ -328,9 +731,11 @@ _k8pk_namespaces() {{
             generate(shells::Fish, &mut cmd, "k8pk", &mut stdout);
             print!(
                 r#"
-# Dynamic context completions
-complete -c k8pk -n '__fish_seen_subcommand_from ctx' -f -a '(k8pk complete contexts 2>/dev/null)'
-complete -c k8pk -n '__fish_seen_subcommand_from ns' -f -a '(k8pk complete namespaces 2>/dev/null)'
+# Dynamic completions: contexts, aliases, and namespaces from `k8pk __complete`
+function __k8pk_dynamic
+    k8pk __complete fish (commandline -opc)[2..-1] (commandline -ct) 2>/dev/null
+end
+complete -c k8pk -f -n 'count (__k8pk_dynamic) >/dev/null' -a '(__k8pk_dynamic)'
 "#
             );
         }
@@ -362,16 +767,130 @@ mod tests {
 
     #[test]
     fn exec_command_empty_returns_error() {
-        let err = exec_command_in_context("ctx", None, &[], false, &[], true).unwrap_err();
+        let err = exec_command_in_context("ctx", None, &[], false, &[], true, &ExecTarget::Host)
+            .unwrap_err();
         assert!(err.to_string().contains("no command specified"));
     }
 
     #[test]
     fn exec_command_captured_empty_returns_error() {
-        let err = exec_command_in_context_captured("ctx", None, &[], &[], true).unwrap_err();
+        let err = exec_command_in_context_captured("ctx", None, &[], &[], true, &ExecTarget::Host)
+            .unwrap_err();
         assert!(err.to_string().contains("no command specified"));
     }
 
+    #[test]
+    fn docker_exec_passes_kubeconfig_and_context() {
+        let command = vec!["kubectl".to_string(), "get".into(), "pods".into()];
+        let cmd = docker_exec_command(
+            "toolbox",
+            "/tmp/k8pk.Ab12",
+            "dev",
+            Some("web"),
+            &command,
+            false,
+        );
+        assert_eq!(cmd.get_program(), "docker");
+        let args: Vec<_> = cmd.get_args().map(|a| a.to_string_lossy()).collect();
+        assert_eq!(
+            args,
+            [
+                "exec",
+                "-i",
+                "toolbox",
+                "sh",
+                "-c",
+                "trap 'rm -f /tmp/k8pk.Ab12' EXIT; trap 'exit 129' HUP; trap 'exit 130' INT; \
+                 trap 'exit 143' TERM; KUBECONFIG=/tmp/k8pk.Ab12 K8PK_CONTEXT=dev \
+                 K8PK_NAMESPACE=web OC_NAMESPACE=web kubectl get pods"
+            ]
+        );
+    }
+
+    #[test]
+    fn ssh_exec_sets_env_and_cleans_up() {
+        let command = vec![
+            "kubectl".to_string(),
+            "get".into(),
+            "pods".into(),
+            "-l".into(),
+            "app=a b".into(),
+        ];
+        let cmd = ssh_exec_command(
+            "ops@jump",
+            "/tmp/k8pk.Ab12",
+            "prod eu",
+            Some("web"),
+            &command,
+            true,
+        );
+        assert_eq!(cmd.get_program(), "ssh");
+        let args: Vec<_> = cmd
+            .get_args()
+            .map(|a| a.to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(args[..3], ["-t", "--", "ops@jump"]);
+        let remote = shell_words::split(&args[3]).unwrap();
+        assert_eq!(remote[..2], ["sh", "-c"]);
+        assert_eq!(
+            remote[2],
+            "trap 'rm -f /tmp/k8pk.Ab12' EXIT; trap 'exit 129' HUP; trap 'exit 130' INT; \
+             trap 'exit 143' TERM; KUBECONFIG=/tmp/k8pk.Ab12 K8PK_CONTEXT='prod eu' \
+             K8PK_NAMESPACE=web OC_NAMESPACE=web kubectl get pods -l 'app=a b'"
+        );
+    }
+
+    #[test]
+    fn ssh_host_is_not_an_option() {
+        let target = ExecTarget::from_flags(None, None, Some("ops@jump".into()), false).unwrap();
+        assert_eq!(
+            target,
+            ExecTarget::Ssh {
+                host: "ops@jump".into(),
+                redact: false
+            }
+        );
+        let err = ExecTarget::from_flags(None, None, Some("-oProxyCommand=sh".into()), false);
+        assert!(matches!(err, Err(K8pkError::InvalidArgument(_))));
+    }
+
+    #[test]
+    fn run_target_splits_only_when_needed() {
+        let contexts = vec!["dev".to_string(), "default/api-ocp:6443/admin".to_string()];
+        assert_eq!(
+            parse_run_target("dev", &contexts).unwrap(),
+            ("dev".to_string(), None)
+        );
+        assert_eq!(
+            parse_run_target("dev/web", &contexts).unwrap(),
+            ("dev".to_string(), Some("web".to_string()))
+        );
+        assert_eq!(
+            parse_run_target("default/api-ocp:6443/admin", &contexts).unwrap(),
+            ("default/api-ocp:6443/admin".to_string(), None)
+        );
+        assert_eq!(
+            parse_run_target("default/api-ocp:6443/admin/kube-system", &contexts).unwrap(),
+            (
+                "default/api-ocp:6443/admin".to_string(),
+                Some("kube-system".to_string())
+            )
+        );
+        assert!(matches!(
+            parse_run_target("dev/", &contexts),
+            Err(K8pkError::ContextNotFound(_))
+        ));
+        assert!(parse_run_target("prod/web", &contexts).is_err());
+    }
+
+    #[test]
+    fn generate_completions_supported_shells() {
+        // clap_complete panics on subcommand names it cannot encode (e.g. `__x` in bash)
+        for shell in ["bash", "zsh", "fish"] {
+            generate_completions(shell).unwrap();
+        }
+    }
+
     #[test]
     fn generate_completions_unsupported_shell() {
         let err = generate_completions("tcsh").unwrap_err();
@@ -1,5 +1,7 @@
 //! Current state management for k8pk
 
+use crate::error::{K8pkError, Result};
+use crate::kubeconfig::{self, KubeConfig};
 use serde::{Deserialize, Serialize};
 use std::env;
 use std::path::PathBuf;
@@ -33,8 +35,10 @@ impl CurrentState {
             .ok()
             .and_then(|d| d.parse::<u32>().ok())
             .unwrap_or(0);
-        let config_path = env::var("KUBECONFIG").ok().and_then(|k| {
-            let p = PathBuf::from(k.split(':').next()?);
+        let config_path = env::var_os("KUBECONFIG").and_then(|k| {
+            let p = crate::kubeconfig::split_kubeconfig_env(&k)
+                .into_iter()
+                .next()?;
             if p.exists() {
                 Some(p)
             } else {
@@ -51,16 +55,253 @@ impl CurrentState {
         }
     }
 
+    /// Cluster, user, and server of the session's context, read from the
+    /// session kubeconfig
+    pub fn target(&self) -> Result<SessionTarget> {
+        let (Some(context), Some(path)) = (&self.context, &self.config_path) else {
+            return Err(K8pkError::NotInContext);
+        };
+        let cfg: KubeConfig = serde_yaml_ng::from_str(&std::fs::read_to_string(path)?)?;
+        let entry = cfg
+            .find_context(context)
+            .ok_or_else(|| K8pkError::ContextNotFound(context.clone()))?;
+        let (cluster, user) = kubeconfig::extract_context_refs(&entry.rest)?;
+        Ok(SessionTarget {
+            server: kubeconfig::get_server_for_context(&cfg, context),
+            cluster,
+            user,
+        })
+    }
+
+    /// Seconds until the session's `session_ttl` runs out (negative once it
+    /// has); `None` outside a session or without a TTL
+    pub fn expires_in(&self) -> Option<i64> {
+        self.config_path
+            .as_deref()
+            .and_then(crate::provenance::read_file)
+            .and_then(|p| p.expires_in())
+    }
+
+    /// Fail once the session has expired, for commands that act on it
+    pub fn ensure_not_expired(&self) -> Result<()> {
+        match (&self.context, self.expires_in()) {
+            (Some(context), Some(left)) if left <= 0 => Err(K8pkError::SessionTtlExpired {
+                context: self.context_display.clone().unwrap_or(context.clone()),
+                ago: crate::clock::format_duration(left.unsigned_abs()),
+            }),
+            _ => Ok(()),
+        }
+    }
+
     /// Convert to JSON for `info all` command
     pub fn to_json(&self) -> serde_json::Value {
         serde_json::to_value(self).unwrap_or_default()
     }
 }
 
+/// Where the current session points (`k8pk info cluster|user|server`)
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SessionTarget {
+    pub cluster: String,
+    pub user: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub server: Option<String>,
+}
+
+/// Env var holding a shell's switch history for `k8pk back` / `forward`
+pub const STACK_ENV: &str = "K8PK_STACK";
+
+/// Env var holding the index of the current entry in [`STACK_ENV`]
+pub const STACK_POS_ENV: &str = "K8PK_STACK_POS";
+
+/// Entries kept per shell; the oldest are dropped first
+const STACK_LIMIT: usize = 50;
+
+/// One context (and namespace) a shell switched to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NavEntry {
+    pub context: String,
+    pub namespace: Option<String>,
+}
+
+/// A shell's switch history, navigated like a browser's: switching drops
+/// the entries ahead of the current one, `back` and `forward` only move the
+/// position. It lives in the shell's environment, so each shell (and each
+/// subshell, from the point it was spawned) has its own.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NavStack {
+    /// Oldest first
+    pub entries: Vec<NavEntry>,
+    /// Index of the current entry
+    pub pos: usize,
+}
+
+impl NavStack {
+    /// The stack exported to this process, if any
+    pub fn from_env() -> Self {
+        Self::parse(
+            &env::var(STACK_ENV).unwrap_or_default(),
+            env::var(STACK_POS_ENV).ok().as_deref(),
+        )
+    }
+
+    /// Entries are `context` or `context@namespace`, comma-separated, with
+    /// `%`, `,`, and `@` percent-escaped; the position defaults to the last
+    fn parse(stack: &str, pos: Option<&str>) -> Self {
+        let entries: Vec<NavEntry> = stack
+            .split(',')
+            .filter(|e| !e.is_empty())
+            .map(|e| {
+                let (context, namespace) = match e.split_once('@') {
+                    Some((c, ns)) => (c, Some(unescape(ns))),
+                    None => (e, None),
+                };
+                NavEntry {
+                    context: unescape(context),
+                    namespace,
+                }
+            })
+            .collect();
+        let last = entries.len().saturating_sub(1);
+        let pos = pos.and_then(|p| p.parse().ok()).unwrap_or(last).min(last);
+        Self { entries, pos }
+    }
+
+    fn encode(&self) -> String {
+        self.entries
+            .iter()
+            .map(|e| match &e.namespace {
+                Some(ns) => format!("{}@{}", escape(&e.context), escape(ns)),
+                None => escape(&e.context),
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    pub fn current(&self) -> Option<&NavEntry> {
+        self.entries.get(self.pos)
+    }
+
+    /// Record a switch: unless it is the current entry, drop everything
+    /// ahead of the position and append it
+    pub fn push(&mut self, context: &str, namespace: Option<&str>) {
+        let entry = NavEntry {
+            context: context.to_string(),
+            namespace: namespace.map(str::to_string),
+        };
+        if self.current() == Some(&entry) {
+            return;
+        }
+        if !self.entries.is_empty() {
+            self.entries.truncate(self.pos + 1);
+        }
+        self.entries.push(entry);
+        if self.entries.len() > STACK_LIMIT {
+            self.entries.drain(..self.entries.len() - STACK_LIMIT);
+        }
+        self.pos = self.entries.len() - 1;
+    }
+
+    /// The stack after switching to `context` from `prior` (the shell's
+    /// state): seeded with `prior` in shells that switched before the stack
+    /// was exported
+    pub fn after_switch(prior: &CurrentState, context: &str, namespace: Option<&str>) -> Self {
+        let mut stack = Self::from_env();
+        if stack.entries.is_empty() {
+            if let Some(ref c) = prior.context {
+                stack.push(c, prior.namespace.as_deref());
+            }
+        }
+        stack.push(context, namespace);
+        stack
+    }
+
+    /// Move `steps` entries back (negative) or forward (positive); None when
+    /// that runs off either end
+    pub fn step(&self, steps: isize) -> Option<usize> {
+        let pos = self.pos.checked_add_signed(steps)?;
+        (pos < self.entries.len()).then_some(pos)
+    }
+
+    /// Make this the stack of the running process, so a switch it goes on to
+    /// make exports it (see [`NavStack::from_env`])
+    pub fn set_env(&self) {
+        env::set_var(STACK_ENV, self.encode());
+        env::set_var(STACK_POS_ENV, self.pos.to_string());
+    }
+
+    /// Variables to export into the shell
+    pub fn exports(&self) -> Vec<(&'static str, String)> {
+        vec![
+            (STACK_ENV, self.encode()),
+            (STACK_POS_ENV, self.pos.to_string()),
+        ]
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('%', "%25")
+        .replace(',', "%2C")
+        .replace('@', "%40")
+}
+
+fn unescape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(i) = rest.find('%') {
+        out.push_str(&rest[..i]);
+        let decoded = match rest.get(i..i + 3) {
+            Some("%25") => Some('%'),
+            Some("%2C") => Some(','),
+            Some("%40") => Some('@'),
+            _ => None,
+        };
+        match decoded {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[i + 3..];
+            }
+            None => {
+                out.push('%');
+                rest = &rest[i + 1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn nav_stack_moves_like_browser_history() {
+        let mut stack = NavStack::default();
+        stack.push("dev", None);
+        stack.push("user@ocp,1%2C", Some("web"));
+        stack.push("prod", Some("api"));
+        stack.push("prod", Some("api"));
+        assert_eq!((stack.entries.len(), stack.pos), (3, 2));
+
+        let parsed = NavStack::parse(&stack.encode(), Some("2"));
+        assert_eq!(parsed, stack);
+        assert_eq!(parsed.entries[1].context, "user@ocp,1%2C");
+
+        // Back two, then switching elsewhere drops the forward entries
+        stack.pos = stack.step(-2).unwrap();
+        assert_eq!(stack.current().unwrap().context, "dev");
+        assert_eq!(stack.step(-1), None);
+        assert_eq!(stack.step(2), Some(2));
+        stack.push("staging", None);
+        let names: Vec<&str> = stack.entries.iter().map(|e| e.context.as_str()).collect();
+        assert_eq!(names, ["dev", "staging"]);
+        assert_eq!(stack.step(1), None);
+
+        assert_eq!(NavStack::parse("", None), NavStack::default());
+        assert_eq!(NavStack::parse("a,b", Some("9")).pos, 1);
+    }
+
     #[test]
     fn default_state_depth_zero() {
         let s = CurrentState::default();
@@ -94,6 +335,71 @@ mod tests {
         assert_eq!(j["depth"], 0);
     }
 
+    #[test]
+    fn target_reads_session_kubeconfig() {
+        let dir = tempfile::tempdir().unwrap();
+        let kc_path = dir.path().join("dev.yaml");
+        std::fs::write(
+            &kc_path,
+            "contexts:\n- name: dev\n  context: {cluster: c1, user: admin}\n\
+             clusters:\n- name: c1\n  cluster: {server: https://api.dev:6443}\n",
+        )
+        .unwrap();
+        let mut s = CurrentState {
+            context: Some("dev".into()),
+            config_path: Some(kc_path),
+            ..Default::default()
+        };
+        assert_eq!(
+            s.target().unwrap(),
+            SessionTarget {
+                cluster: "c1".into(),
+                user: "admin".into(),
+                server: Some("https://api.dev:6443".into()),
+            }
+        );
+        s.context = Some("prod".into());
+        assert!(matches!(s.target(), Err(K8pkError::ContextNotFound(_))));
+        assert!(matches!(
+            CurrentState::default().target(),
+            Err(K8pkError::NotInContext)
+        ));
+    }
+
+    #[test]
+    fn test_session_expiry() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("config");
+        std::fs::write(&source, "clusters: []\n").unwrap();
+        let mut provenance = crate::provenance::Provenance::for_source("dev", &source).unwrap();
+        let session = dir.path().join("dev.yaml");
+        let write = |p: &crate::provenance::Provenance| {
+            let mut cfg: KubeConfig = serde_yaml_ng::from_str("{}").unwrap();
+            crate::provenance::write(&mut cfg, p).unwrap();
+            std::fs::write(&session, serde_yaml_ng::to_string(&cfg).unwrap()).unwrap();
+        };
+        let state = CurrentState {
+            context: Some("dev".into()),
+            config_path: Some(session.clone()),
+            ..Default::default()
+        };
+
+        write(&provenance);
+        assert_eq!(state.expires_in(), None);
+        assert!(state.ensure_not_expired().is_ok());
+
+        provenance.expires_at = Some(provenance.generated_at + 3600);
+        write(&provenance);
+        assert!(state.expires_in().is_some_and(|left| left > 3500));
+        assert!(state.ensure_not_expired().is_ok());
+
+        provenance.expires_at = Some(provenance.generated_at - 120);
+        write(&provenance);
+        let err = state.ensure_not_expired().unwrap_err();
+        assert!(matches!(err, K8pkError::SessionTtlExpired { .. }));
+        assert!(err.to_string().contains("expired 2m ago"));
+    }
+
     static ENV_MUTEX: std::sync::Mutex<()> = std::sync::Mutex::new(());
 
     #[test]
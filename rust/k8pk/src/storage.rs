@@ -0,0 +1,98 @@
+//! Where isolated kubeconfigs are written. `session_storage: persistent` (the
+//! default) keeps them in `~/.local/share/k8pk`; `runtime` puts them in
+//! `$XDG_RUNTIME_DIR/k8pk` (tmpfs, wiped on reboot) and falls back to the
+//! persistent directory when no runtime directory is available.
+
+use crate::config::K8pkConfig;
+use crate::error::{K8pkError, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Value of `session_storage:` in the config
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionStorage {
+    #[default]
+    Persistent,
+    Runtime,
+}
+
+/// `~/.local/share/k8pk`: history, sessions, and persistent isolated kubeconfigs
+pub fn data_dir() -> Result<PathBuf> {
+    let home = dirs_next::home_dir().ok_or(K8pkError::NoHomeDir)?;
+    Ok(home.join(".local/share/k8pk"))
+}
+
+/// `$XDG_RUNTIME_DIR/k8pk`, when the runtime directory is set
+pub fn runtime_dir_path() -> Option<PathBuf> {
+    std::env::var_os("XDG_RUNTIME_DIR")
+        .filter(|d| !d.is_empty())
+        .map(|d| PathBuf::from(d).join("k8pk"))
+}
+
+/// Create `dir` readable by the owner only
+pub fn create_private_dir(dir: &Path) -> Result<()> {
+    fs::create_dir_all(dir)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(dir, fs::Permissions::from_mode(0o700))?;
+    }
+    Ok(())
+}
+
+/// Runtime directory to use for `config`, or None for persistent storage
+fn runtime_target(config: &K8pkConfig) -> Option<PathBuf> {
+    match config.session_storage.unwrap_or_default() {
+        SessionStorage::Persistent => None,
+        SessionStorage::Runtime => runtime_dir_path(),
+    }
+}
+
+/// Directory for new isolated kubeconfigs (created if missing)
+pub fn isolated_dir(config: &K8pkConfig) -> Result<PathBuf> {
+    match runtime_target(config) {
+        Some(dir) => {
+            create_private_dir(&dir)?;
+            Ok(dir)
+        }
+        None => {
+            let dir = data_dir()?;
+            fs::create_dir_all(&dir)?;
+            Ok(dir)
+        }
+    }
+}
+
+/// Whether isolated kubeconfigs currently go to the runtime directory, making
+/// plain copies left in the persistent directory obsolete
+pub fn runtime_active(config: &K8pkConfig) -> bool {
+    runtime_target(config).is_some()
+}
+
+/// Every existing directory that may hold isolated kubeconfigs (persistent
+/// first), for cleanup
+pub fn isolated_dirs() -> Vec<PathBuf> {
+    data_dir()
+        .ok()
+        .into_iter()
+        .chain(runtime_dir_path())
+        .filter(|d| d.is_dir())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn session_storage_parses_lowercase() {
+        let config: K8pkConfig = serde_yaml_ng::from_str("session_storage: runtime").unwrap();
+        assert_eq!(config.session_storage, Some(SessionStorage::Runtime));
+        assert!(serde_yaml_ng::from_str::<K8pkConfig>("session_storage: tmpfs").is_err());
+        assert_eq!(
+            K8pkConfig::default().session_storage.unwrap_or_default(),
+            SessionStorage::Persistent
+        );
+    }
+}
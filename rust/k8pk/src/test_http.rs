@@ -26,6 +26,7 @@ use std::time::Duration;
 pub struct HttpResponse {
     pub status: u16,
     pub body: String,
+    pub headers: Vec<(String, String)>,
 }
 
 impl HttpResponse {
@@ -33,8 +34,15 @@ impl HttpResponse {
         Self {
             status,
             body: body.into(),
+            headers: Vec::new(),
         }
     }
+
+    /// Extra response header (e.g. `ETag`)
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
 }
 
 fn write_http_response(stream: &mut impl Write, response: &HttpResponse) -> std::io::Result<()> {
@@ -45,11 +53,17 @@ fn write_http_response(stream: &mut impl Write, response: &HttpResponse) -> std:
     } else {
         "Error"
     };
+    let extra: String = response
+        .headers
+        .iter()
+        .map(|(name, value)| format!("{}: {}\r\n", name, value))
+        .collect();
     let resp = format!(
-        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n{}Connection: close\r\n\r\n{}",
         response.status,
         reason,
         response.body.len(),
+        extra,
         response.body
     );
     stream.write_all(resp.as_bytes())
@@ -0,0 +1,183 @@
+//! Output colors. Colored output names a [`Role`] and the theme picks the
+//! color: `default`, `colorblind` (blue/orange instead of green/red), or
+//! `mono`, from `K8PK_THEME` or the config `theme:`. `NO_COLOR`,
+//! `--no-color`, and output that isn't a terminal turn color off everywhere:
+//! plain text, the dashboard, and the pickers.
+
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+/// Env var holding the theme (overrides the config `theme:`)
+pub const THEME_ENV: &str = "K8PK_THEME";
+
+/// <https://no-color.org>: set to anything but empty to turn color off
+pub const NO_COLOR_ENV: &str = "NO_COLOR";
+
+// `--no-color` and the config `theme:` live in the process, not the
+// environment, so shells and programs k8pk starts don't inherit them
+static NO_COLOR_FLAG: AtomicBool = AtomicBool::new(false);
+static CONFIG_THEME: OnceLock<String> = OnceLock::new();
+
+/// Record the global `--no-color` for this process
+pub fn set_no_color() {
+    NO_COLOR_FLAG.store(true, Ordering::Relaxed);
+}
+
+/// Record the config `theme:` for this process; `K8PK_THEME` still wins
+pub fn set_config_theme(name: &str) {
+    let _ = CONFIG_THEME.set(name.to_string());
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Theme {
+    Default,
+    /// Distinguishable with red-green color blindness (Okabe-Ito hues)
+    Colorblind,
+    /// No color, as with `NO_COLOR`
+    Mono,
+}
+
+/// What a piece of output means
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Ok,
+    Warning,
+    Error,
+    /// Highlights: the current item, the picker cursor
+    Accent,
+    /// Secondary text
+    Muted,
+}
+
+fn parse(s: &str) -> Option<Theme> {
+    match s.trim().to_lowercase().as_str() {
+        "default" | "" => Some(Theme::Default),
+        "colorblind" | "colourblind" | "cb" => Some(Theme::Colorblind),
+        "mono" | "none" | "monochrome" => Some(Theme::Mono),
+        _ => None,
+    }
+}
+
+/// Process-wide theme from `K8PK_THEME`, then the config; `--no-color` or
+/// `NO_COLOR` makes it mono
+pub fn theme() -> Theme {
+    if NO_COLOR_FLAG.load(Ordering::Relaxed)
+        || std::env::var_os(NO_COLOR_ENV).is_some_and(|v| !v.is_empty())
+    {
+        return Theme::Mono;
+    }
+    match std::env::var(THEME_ENV)
+        .ok()
+        .or_else(|| CONFIG_THEME.get().cloned())
+    {
+        Some(v) => parse(&v).unwrap_or_else(|| {
+            eprintln!(
+                "warning: unknown theme '{}' (expected default, colorblind, or mono); using default",
+                v
+            );
+            Theme::Default
+        }),
+        None => Theme::Default,
+    }
+}
+
+impl Theme {
+    /// 256-color palette index for `role` (`None`: leave the text as is)
+    pub fn color(self, role: Role) -> Option<u8> {
+        match (self, role) {
+            (Theme::Mono, _) => None,
+            (Theme::Default, Role::Ok) => Some(2),
+            (Theme::Default, Role::Warning) => Some(3),
+            (Theme::Default, Role::Error) => Some(1),
+            (Theme::Default, Role::Accent) => Some(6),
+            (Theme::Default, Role::Muted) => Some(8),
+            (Theme::Colorblind, Role::Ok) => Some(33),
+            (Theme::Colorblind, Role::Warning) => Some(220),
+            (Theme::Colorblind, Role::Error) => Some(202),
+            (Theme::Colorblind, Role::Accent) => Some(74),
+            (Theme::Colorblind, Role::Muted) => Some(244),
+        }
+    }
+
+    /// `text` wrapped in the ANSI color for `role`
+    pub fn paint(self, role: Role, text: &str) -> String {
+        match self.color(role) {
+            None => text.to_string(),
+            Some(c @ 0..=7) => format!("\x1b[{}m{}\x1b[0m", 30 + c, text),
+            Some(c @ 8..=15) => format!("\x1b[{}m{}\x1b[0m", 90 + c - 8, text),
+            Some(c) => format!("\x1b[38;5;{}m{}\x1b[0m", c, text),
+        }
+    }
+
+    /// Color for `role` in the full-screen dashboard
+    pub fn tui_color(self, role: Role) -> ratatui::style::Color {
+        self.color(role)
+            .map_or(ratatui::style::Color::Reset, ratatui::style::Color::Indexed)
+    }
+
+    /// inquire styles for the built-in pickers and prompts
+    pub fn render_config(self) -> inquire::ui::RenderConfig<'static> {
+        use inquire::ui::{Color, RenderConfig, StyleSheet, Styled};
+        let color = |role| self.color(role).map(Color::AnsiValue);
+        let (Some(ok), Some(accent)) = (color(Role::Ok), color(Role::Accent)) else {
+            return RenderConfig::empty();
+        };
+        RenderConfig::default_colored()
+            .with_prompt_prefix(Styled::new("?").with_fg(ok))
+            .with_answered_prompt_prefix(Styled::new(">").with_fg(ok))
+            .with_highlighted_option_prefix(Styled::new(">").with_fg(accent))
+            .with_selected_option(Some(StyleSheet::new().with_fg(accent)))
+            .with_answer(StyleSheet::new().with_fg(accent))
+            .with_help_message(StyleSheet::new().with_fg(accent))
+    }
+}
+
+/// Theme for stdout: mono unless it is a terminal
+pub fn stdout() -> Theme {
+    if std::io::stdout().is_terminal() {
+        theme()
+    } else {
+        Theme::Mono
+    }
+}
+
+/// Theme for stderr: mono unless it is a terminal
+pub fn stderr() -> Theme {
+    if std::io::stderr().is_terminal() {
+        theme()
+    } else {
+        Theme::Mono
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paint_by_theme() {
+        assert_eq!(Theme::Default.paint(Role::Error, "x"), "\x1b[31mx\x1b[0m");
+        assert_eq!(Theme::Default.paint(Role::Muted, "x"), "\x1b[90mx\x1b[0m");
+        assert_eq!(
+            Theme::Colorblind.paint(Role::Ok, "x"),
+            "\x1b[38;5;33mx\x1b[0m"
+        );
+        assert_eq!(Theme::Mono.paint(Role::Warning, "x"), "x");
+        // No role shares a color within a palette
+        for theme in [Theme::Default, Theme::Colorblind] {
+            let roles = [
+                Role::Ok,
+                Role::Warning,
+                Role::Error,
+                Role::Accent,
+                Role::Muted,
+            ];
+            let colors: std::collections::HashSet<_> =
+                roles.iter().map(|r| theme.color(*r)).collect();
+            assert_eq!(colors.len(), roles.len());
+        }
+        assert_eq!(parse("Colorblind"), Some(Theme::Colorblind));
+        assert_eq!(parse("neon"), None);
+    }
+}
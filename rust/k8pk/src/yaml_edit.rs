@@ -0,0 +1,600 @@
+//! Targeted edits on kubeconfig text, so rewrites of user kubeconfigs (rm,
+//! rename-context, organize --remove-from-source) keep comments, key order,
+//! and formatting outside the nodes they change.
+//!
+//! The editor understands the block layout kubectl writes: top-level
+//! `clusters:` / `contexts:` / `users:` sequences of mappings with a `name:`
+//! key, and a `current-context:` scalar. Anything else makes an edit return
+//! None, and [`render`] falls back to re-serialising the config.
+
+use crate::error::Result;
+use crate::kubeconfig::KubeConfig;
+use std::collections::HashMap;
+
+/// Serialise `expected` by applying `edit` to `original` when the result
+/// parses to exactly `expected`; otherwise re-serialise `expected` (comments
+/// and formatting are lost then, content never is).
+pub fn render(
+    original: &str,
+    expected: &KubeConfig,
+    edit: impl FnOnce(&str) -> Option<String>,
+) -> Result<String> {
+    if let Some(edited) = edit(original) {
+        let same = serde_yaml_ng::from_str::<KubeConfig>(&edited)
+            .ok()
+            .and_then(|cfg| serde_yaml_ng::to_value(&cfg).ok())
+            .is_some_and(|v| serde_yaml_ng::to_value(expected).is_ok_and(|e| e == v));
+        if same {
+            return Ok(edited);
+        }
+    }
+    Ok(serde_yaml_ng::to_string(expected)?)
+}
+
+/// Line content without its line ending
+fn content(line: &str) -> &str {
+    line.trim_end_matches(['\n', '\r'])
+}
+
+fn indent(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+fn is_blank(line: &str) -> bool {
+    content(line).trim().is_empty()
+}
+
+fn is_comment(line: &str) -> bool {
+    content(line).trim_start().starts_with('#')
+}
+
+fn is_trivia(line: &str) -> bool {
+    is_blank(line) || is_comment(line)
+}
+
+/// Byte offset of a trailing ` #` comment in `s`, ignoring quoted text
+fn comment_start(s: &str) -> Option<usize> {
+    let (mut single, mut double) = (false, false);
+    let mut prev = ' ';
+    for (i, c) in s.char_indices() {
+        match c {
+            '\'' if !double => single = !single,
+            '"' if !single && prev != '\\' => double = !double,
+            '#' if !single && !double && prev.is_whitespace() => return Some(i),
+            _ => {}
+        }
+        prev = c;
+    }
+    None
+}
+
+/// Plain or quoted scalar for `value`, as serde_yaml_ng would write it
+fn format_scalar(value: &str) -> Option<String> {
+    let out = serde_yaml_ng::to_string(value).ok()?;
+    let out = out.trim_end();
+    (!out.contains('\n')).then(|| out.to_string())
+}
+
+/// Scalar after a `key:` on `line`, starting at byte `from`
+struct Scalar {
+    value: String,
+    /// Byte range of the value text within the line
+    start: usize,
+    end: usize,
+}
+
+fn scalar(line: &str, from: usize) -> Option<Scalar> {
+    let body = content(line);
+    let rest = &body[from..];
+    let raw = match comment_start(rest) {
+        Some(c) => &rest[..c],
+        None => rest,
+    };
+    let trimmed = raw.trim();
+    if trimmed.is_empty() || trimmed.starts_with(['&', '*', '!', '|', '>', '[', '{']) {
+        return None;
+    }
+    let value = serde_yaml_ng::from_str::<String>(trimmed).ok()?;
+    let start = from + (raw.len() - raw.trim_start().len());
+    Some(Scalar {
+        value,
+        start,
+        end: start + trimmed.len(),
+    })
+}
+
+fn replace_scalar(line: &str, s: &Scalar, value: &str) -> Option<String> {
+    Some(format!(
+        "{}{}{}",
+        &line[..s.start],
+        format_scalar(value)?,
+        &line[s.end..]
+    ))
+}
+
+/// Index of the top-level `key:` line
+fn top_level(lines: &[&str], key: &str) -> Option<usize> {
+    let prefix = format!("{}:", key);
+    lines
+        .iter()
+        .position(|l| indent(l) == 0 && content(l).starts_with(&prefix))
+}
+
+struct Item {
+    /// First line, including comments directly above the `-`
+    start: usize,
+    /// One past the last line
+    end: usize,
+    name: Option<(usize, Scalar)>,
+}
+
+struct Section {
+    header: usize,
+    items: Vec<Item>,
+}
+
+/// Parse the block sequence under top-level `key:`. Ok(None) when the key is
+/// absent; Err(()) for layouts the editor does not handle.
+fn section(lines: &[&str], key: &str) -> std::result::Result<Option<Section>, ()> {
+    let Some(header) = top_level(lines, key) else {
+        return Ok(None);
+    };
+    let after = &content(lines[header])[key.len() + 1..];
+    let inline = match comment_start(after) {
+        Some(c) => &after[..c],
+        None => after,
+    };
+    if !inline.trim().is_empty() {
+        return Err(());
+    }
+
+    let body_start = header + 1;
+    let mut body_end = lines.len();
+    for (i, line) in lines.iter().enumerate().skip(body_start) {
+        if !is_trivia(line) && indent(line) == 0 && !content(line).starts_with('-') {
+            body_end = i;
+            break;
+        }
+    }
+    // Comments right above the next key belong to it
+    while body_end > body_start && is_comment(lines[body_end - 1]) && body_end < lines.len() {
+        body_end -= 1;
+    }
+
+    let Some(first) = (body_start..body_end).find(|&i| !is_trivia(lines[i])) else {
+        return Ok(Some(Section {
+            header,
+            items: Vec::new(),
+        }));
+    };
+    let item_indent = indent(lines[first]);
+    let mut dashes = Vec::new();
+    for (i, line) in lines.iter().enumerate().take(body_end).skip(first) {
+        if is_trivia(line) || indent(line) > item_indent {
+            continue;
+        }
+        let c = &content(line)[item_indent..];
+        if indent(line) < item_indent || !(c == "-" || c.starts_with("- ")) {
+            return Err(());
+        }
+        dashes.push(i);
+    }
+
+    let mut items = Vec::new();
+    for (n, &dash) in dashes.iter().enumerate() {
+        let floor = if n == 0 {
+            body_start
+        } else {
+            dashes[n - 1] + 1
+        };
+        let mut start = dash;
+        while start > floor && is_comment(lines[start - 1]) {
+            start -= 1;
+        }
+        items.push(Item {
+            start,
+            end: body_end,
+            name: item_name(lines, dash, item_indent, body_end),
+        });
+    }
+    for n in 1..items.len() {
+        items[n - 1].end = items[n].start;
+    }
+    Ok(Some(Section { header, items }))
+}
+
+/// Location and value of the `name:` key of the item starting at `dash`
+fn item_name(
+    lines: &[&str],
+    dash: usize,
+    item_indent: usize,
+    limit: usize,
+) -> Option<(usize, Scalar)> {
+    let first = content(lines[dash]);
+    let after = &first[item_indent + 1..];
+    let inline = !is_trivia(after);
+    // Column of the item's keys: after `- `, or that of the next line
+    let col = if inline {
+        item_indent + 1 + (after.len() - after.trim_start().len())
+    } else {
+        indent(lines[dash + 1..limit].iter().find(|l| !is_trivia(l))?)
+    };
+    if inline && first[col..].starts_with("name:") {
+        return scalar(lines[dash], col + "name:".len()).map(|s| (dash, s));
+    }
+    for (i, line) in lines.iter().enumerate().take(limit).skip(dash + 1) {
+        if is_trivia(line) {
+            continue;
+        }
+        if indent(line) < col {
+            break;
+        }
+        if indent(line) == col && content(line)[col..].starts_with("name:") {
+            return scalar(line, col + "name:".len()).map(|s| (i, s));
+        }
+    }
+    None
+}
+
+fn lines(text: &str) -> Vec<&str> {
+    text.split_inclusive('\n').collect()
+}
+
+/// Rename items of the `key` sequence per `mapping` (old name -> new name)
+pub fn rename_items(text: &str, key: &str, mapping: &HashMap<String, String>) -> Option<String> {
+    let lines = lines(text);
+    let section = section(&lines, key).ok()??;
+    let mut out: Vec<String> = lines.iter().map(|l| l.to_string()).collect();
+    for item in &section.items {
+        let Some((line, s)) = &item.name else {
+            continue;
+        };
+        if let Some(new) = mapping.get(&s.value) {
+            out[*line] = replace_scalar(lines[*line], s, new)?;
+        }
+    }
+    Some(out.concat())
+}
+
+/// Drop items of the `key` sequence whose name is in `names`, with the
+/// comments directly above them
+pub fn remove_items(text: &str, key: &str, names: &[String]) -> Option<String> {
+    if names.is_empty() {
+        return Some(text.to_string());
+    }
+    let lines = lines(text);
+    let section = section(&lines, key).ok()??;
+    let mut drop = vec![false; lines.len()];
+    let mut kept = 0;
+    for item in &section.items {
+        match &item.name {
+            Some((_, s)) if names.contains(&s.value) => {
+                drop[item.start..item.end]
+                    .iter_mut()
+                    .for_each(|d| *d = true);
+            }
+            _ => kept += 1,
+        }
+    }
+    let mut out = String::with_capacity(text.len());
+    for (i, line) in lines.iter().enumerate() {
+        if drop[i] {
+            continue;
+        }
+        if i == section.header && kept == 0 {
+            // `key:` with nothing under it would read as null
+            let c = content(line);
+            let eol = &line[c.len()..];
+            let after = &c[key.len() + 1..];
+            let comment = comment_start(after)
+                .map(|p| format!(" {}", &after[p..]))
+                .unwrap_or_default();
+            out.push_str(&format!("{}: []{}{}", key, comment, eol));
+            continue;
+        }
+        out.push_str(line);
+    }
+    Some(out)
+}
+
+/// Set (or with None, drop) the top-level `current-context:`
+pub fn set_current_context(text: &str, value: Option<&str>) -> Option<String> {
+    set_top_level(text, "current-context", value)
+}
+
+/// Set (or with None, drop) the top-level scalar `key:` (`apiVersion`,
+/// `kind`, `current-context`). A missing key is appended, except that
+/// `apiVersion` and `kind` open the document as kubectl writes them.
+pub fn set_top_level(text: &str, key: &str, value: Option<&str>) -> Option<String> {
+    let mut lines: Vec<String> = lines(text).iter().map(|l| l.to_string()).collect();
+    let borrowed: Vec<&str> = lines.iter().map(String::as_str).collect();
+    match (top_level(&borrowed, key), value) {
+        (Some(i), Some(v)) => {
+            let line = lines[i].clone();
+            let from = key.len() + 1;
+            let after = content(&line)[from..].trim();
+            lines[i] = if matches!(after, "" | "null" | "~") {
+                let eol = &line[content(&line).len()..];
+                format!("{}: {}{}", key, format_scalar(v)?, eol)
+            } else {
+                replace_scalar(&line, &scalar(&line, from)?, v)?
+            };
+        }
+        (Some(i), None) => {
+            lines.remove(i);
+        }
+        (None, Some(v)) => {
+            // After the apiVersion line or the comments opening the file
+            let at = match key {
+                "apiVersion" | "kind" => Some(
+                    top_level(&borrowed, "apiVersion")
+                        .map(|i| i + 1)
+                        .unwrap_or_else(|| borrowed.iter().take_while(|l| is_trivia(l)).count()),
+                ),
+                _ => None,
+            };
+            if lines.last().is_some_and(|l| !l.ends_with('\n')) {
+                lines.push("\n".into());
+            }
+            let line = format!("{}: {}\n", key, format_scalar(v)?);
+            match at {
+                Some(i) => lines.insert(i, line),
+                None => lines.push(line),
+            }
+        }
+        (None, None) => {}
+    }
+    Some(lines.concat())
+}
+
+/// Set `fields` in the `inner:` mapping of the `key` item named `name` (the
+/// `context:` of a context, say): existing scalars are replaced, missing keys
+/// added after the mapping's last line
+pub fn set_item_fields(
+    text: &str,
+    key: &str,
+    name: &str,
+    inner: &str,
+    fields: &[(&str, &str)],
+) -> Option<String> {
+    let lines = lines(text);
+    let section = section(&lines, key).ok()??;
+    let item = section
+        .items
+        .iter()
+        .find(|i| i.name.as_ref().is_some_and(|(_, s)| s.value == name))?;
+    let (name_line, _) = item.name.as_ref()?;
+    let col = content(lines[*name_line]).find("name:")?;
+
+    // The `inner:` key at the item's key column, opening a block mapping
+    let prefix = format!("{}:", inner);
+    let header = (item.start..item.end).find(|&i| {
+        let c = content(lines[i]);
+        let lead = c.get(..col).unwrap_or("").trim();
+        (lead.is_empty() || lead == "-")
+            && c.get(col..).is_some_and(|rest| rest.starts_with(&prefix))
+    })?;
+    let after = &content(lines[header])[col + prefix.len()..];
+    if !after.trim().is_empty() && !after.trim_start().starts_with('#') {
+        return None;
+    }
+    let mut last = header;
+    let mut child_col = None;
+    for (i, line) in lines.iter().enumerate().take(item.end).skip(header + 1) {
+        if is_trivia(line) {
+            continue;
+        }
+        if indent(line) <= col {
+            break;
+        }
+        child_col.get_or_insert(indent(line));
+        last = i;
+    }
+    let child_col = child_col?;
+
+    let mut out: Vec<String> = lines.iter().map(|l| l.to_string()).collect();
+    let mut added = String::new();
+    for (field, value) in fields {
+        let prefix = format!("{}:", field);
+        let existing = (header + 1..=last).find(|&i| {
+            indent(lines[i]) == child_col && content(lines[i])[child_col..].starts_with(&prefix)
+        });
+        match existing {
+            Some(i) => {
+                let s = scalar(lines[i], child_col + prefix.len())?;
+                out[i] = replace_scalar(lines[i], &s, value)?;
+            }
+            None => added.push_str(&format!(
+                "{}{} {}\n",
+                " ".repeat(child_col),
+                prefix,
+                format_scalar(value)?
+            )),
+        }
+    }
+    if !out[last].ends_with('\n') {
+        out[last].push('\n');
+    }
+    out[last].push_str(&added);
+    Some(out.concat())
+}
+
+/// Append `item` to the `key` sequence, indented like the items already there
+pub fn append_item(text: &str, key: &str, item: &impl serde::Serialize) -> Option<String> {
+    let lines = lines(text);
+    let section = section(&lines, key).ok()??;
+    let last = section.items.last()?;
+    let dash = (last.start..last.end).find(|&i| !is_trivia(lines[i]))?;
+    let end = (dash..last.end).rfind(|&i| !is_trivia(lines[i]))? + 1;
+    let pad = " ".repeat(indent(lines[dash]));
+    let yaml = serde_yaml_ng::to_string(&[item]).ok()?;
+
+    let mut out: Vec<String> = lines.iter().map(|l| l.to_string()).collect();
+    if !out[end - 1].ends_with('\n') {
+        out[end - 1].push('\n');
+    }
+    for line in yaml.lines() {
+        out[end - 1].push_str(&format!("{}{}\n", pad, line));
+    }
+    Some(out.concat())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ANNOTATED: &str = r#"# Work clusters -- keep in sync with the wiki
+apiVersion: v1
+kind: Config
+clusters:
+- cluster:
+    server: https://dev:6443 # via VPN
+  name: dev-cluster
+- cluster:
+    server: https://prod:6443
+  name: prod-cluster
+contexts:
+# dev is safe to break
+- context:
+    cluster: dev-cluster
+    user: dev-user
+  name: dev
+- context:
+    cluster: prod-cluster
+    user: prod-user
+  name: "prod" # careful
+current-context: prod # default
+users:
+- name: dev-user
+  user:
+    token: a
+- name: prod-user
+  user:
+    token: b
+"#;
+
+    fn parse(text: &str) -> KubeConfig {
+        serde_yaml_ng::from_str(text).unwrap()
+    }
+
+    #[test]
+    fn rename_keeps_comments() {
+        let mapping = HashMap::from([("prod".to_string(), "production".to_string())]);
+        let out = rename_items(ANNOTATED, "contexts", &mapping)
+            .and_then(|t| set_current_context(&t, Some("production")))
+            .unwrap();
+        assert!(out.contains("  name: production # careful\n"));
+        assert!(out.contains("current-context: production # default\n"));
+        assert!(out.starts_with("# Work clusters"));
+        assert!(out.contains("# via VPN"));
+        let cfg = parse(&out);
+        assert_eq!(cfg.context_names(), ["dev", "production"]);
+    }
+
+    #[test]
+    fn set_top_level_replaces_or_appends() {
+        let out = set_top_level(
+            "apiVersion: v1beta1 # old\nclusters: []\n",
+            "apiVersion",
+            Some("v1"),
+        )
+        .and_then(|t| set_top_level(&t, "kind", Some("Config")))
+        .unwrap();
+        assert_eq!(out, "apiVersion: v1 # old\nkind: Config\nclusters: []\n");
+        let out = set_top_level("# team\nclusters: []\n", "apiVersion", Some("v1"))
+            .and_then(|t| set_top_level(&t, "current-context", Some("a")))
+            .unwrap();
+        assert_eq!(
+            out,
+            "# team\napiVersion: v1\nclusters: []\ncurrent-context: a\n"
+        );
+    }
+
+    #[test]
+    fn remove_drops_item_and_its_comment() {
+        let out = remove_items(ANNOTATED, "contexts", &["dev".into()])
+            .and_then(|t| remove_items(&t, "clusters", &["dev-cluster".into()]))
+            .and_then(|t| remove_items(&t, "users", &["dev-user".into()]))
+            .unwrap();
+        assert!(!out.contains("dev is safe"));
+        assert!(!out.contains("via VPN"));
+        assert!(out.contains("  name: \"prod\" # careful\n"));
+        let cfg = parse(&out);
+        assert_eq!(cfg.context_names(), ["prod"]);
+        assert_eq!(cfg.clusters.len(), 1);
+        assert_eq!(cfg.users[0].name, "prod-user");
+    }
+
+    #[test]
+    fn remove_all_leaves_empty_sequence() {
+        let out =
+            remove_items(ANNOTATED, "users", &["dev-user".into(), "prod-user".into()]).unwrap();
+        assert!(out.contains("users: []\n"));
+        assert!(parse(&out).users.is_empty());
+    }
+
+    #[test]
+    fn indented_sequences_and_inline_names() {
+        let text = "contexts:\n  - name: a\n    context:\n      cluster: c\n  - name: b # keep\n    context:\n      cluster: c\n";
+        let out = rename_items(
+            text,
+            "contexts",
+            &HashMap::from([("b".to_string(), "team: b".to_string())]),
+        )
+        .unwrap();
+        assert!(out.contains("  - name: 'team: b' # keep\n"));
+        let out = remove_items(&out, "contexts", &["a".into()]).unwrap();
+        assert_eq!(parse(&out).context_names(), ["team: b"]);
+    }
+
+    #[test]
+    fn set_fields_and_append_keep_comments() {
+        let out = set_item_fields(
+            ANNOTATED,
+            "contexts",
+            "prod",
+            "context",
+            &[("user", "admin"), ("namespace", "web")],
+        )
+        .unwrap();
+        assert!(out.contains("    user: admin\n    namespace: web\n  name: \"prod\" # careful\n"));
+        assert!(out.contains("# dev is safe to break"));
+
+        let item = crate::kubeconfig::NamedItem {
+            name: "staging".into(),
+            rest: serde_yaml_ng::from_str("context:\n  cluster: dev-cluster\n  user: dev-user\n")
+                .unwrap(),
+        };
+        let out = append_item(&out, "contexts", &item).unwrap();
+        assert!(out.contains(
+            "  name: \"prod\" # careful\n- name: staging\n  context:\n    cluster: dev-cluster\n"
+        ));
+        let cfg = parse(&out);
+        assert_eq!(cfg.context_names(), ["dev", "prod", "staging"]);
+        assert_eq!(cfg.current_context.as_deref(), Some("prod"));
+    }
+
+    #[test]
+    fn unsupported_layout_falls_back() {
+        let text = "contexts: [{name: a, context: {cluster: c}}]\n";
+        assert!(rename_items(text, "contexts", &HashMap::new()).is_none());
+
+        let mut expected = parse(text);
+        expected.contexts[0].name = "b".into();
+        let out = render(text, &expected, |t| {
+            rename_items(t, "contexts", &HashMap::from([("a".into(), "b".into())]))
+        })
+        .unwrap();
+        assert_eq!(parse(&out).context_names(), ["b"]);
+    }
+
+    #[test]
+    fn render_rejects_edits_that_disagree() {
+        let mut expected = parse(ANNOTATED);
+        expected.contexts.retain(|c| c.name != "dev");
+        // An edit that forgot the removal is not used
+        let out = render(ANNOTATED, &expected, |t| Some(t.to_string())).unwrap();
+        assert_eq!(parse(&out).context_names(), ["prod"]);
+        assert!(!out.contains("# Work clusters"));
+    }
+}
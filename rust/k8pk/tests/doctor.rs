@@ -0,0 +1,139 @@
+//! `k8pk doctor` end to end: the binary run against a throwaway HOME
+
+use std::fs;
+use std::net::TcpListener;
+use std::path::Path;
+use std::process::Command;
+
+fn kubeconfig(servers: &[(&str, &str)]) -> String {
+    let mut yaml = String::from("apiVersion: v1\nkind: Config\nclusters:\n");
+    for (name, server) in servers {
+        yaml.push_str(&format!(
+            "- name: {}\n  cluster: {{server: \"{}\"}}\n",
+            name, server
+        ));
+    }
+    yaml.push_str("users:\n- name: u\n  user: {token: t}\ncontexts:\n");
+    for (name, _) in servers {
+        yaml.push_str(&format!(
+            "- name: {}\n  context: {{cluster: {}, user: u}}\n",
+            name, name
+        ));
+    }
+    yaml
+}
+
+/// Run `k8pk doctor --json <args>` with `home` as HOME and nothing else
+/// from the environment but PATH; returns the parsed checks
+fn doctor(home: &Path, args: &[&str]) -> Vec<serde_json::Value> {
+    let output = Command::new(env!("CARGO_BIN_EXE_k8pk"))
+        .arg("doctor")
+        .arg("--json")
+        .args(args)
+        .env_clear()
+        .env("HOME", home)
+        .env("PATH", std::env::var_os("PATH").unwrap_or_default())
+        .env("K8PK_TEAM_CONFIG", "")
+        .env("K8PK_NO_SESSION_CHECK", "1")
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+fn check<'a>(results: &'a [serde_json::Value], name: &str) -> &'a serde_json::Value {
+    results
+        .iter()
+        .find(|r| r["name"] == name)
+        .unwrap_or_else(|| panic!("no check named {}", name))
+}
+
+#[test]
+fn test_doctor_reports_config_and_kubeconfig() {
+    let home = tempfile::tempdir().unwrap();
+    let results = doctor(home.path(), &[]);
+    assert_eq!(check(&results, "k8pk config")["status"], "warning");
+
+    let kube = home.path().join(".kube");
+    fs::create_dir_all(&kube).unwrap();
+    fs::write(kube.join("config"), kubeconfig(&[("dev", "https://dev")])).unwrap();
+    let config = home.path().join(".config/k8pk/config.yaml");
+    fs::create_dir_all(config.parent().unwrap()).unwrap();
+    fs::write(&config, "picker: builtin\n").unwrap();
+    let results = doctor(home.path(), &[]);
+    assert_eq!(check(&results, "k8pk config")["status"], "ok");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_doctor_fix_permissions_and_legacy_config() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let home = tempfile::tempdir().unwrap();
+    let kube = home.path().join(".kube");
+    fs::create_dir_all(&kube).unwrap();
+    let config = kube.join("config");
+    fs::write(&config, kubeconfig(&[("dev", "https://dev")])).unwrap();
+    fs::set_permissions(&config, fs::Permissions::from_mode(0o644)).unwrap();
+    fs::write(kube.join("k8pk.yaml"), "picker: fzf\n").unwrap();
+
+    let results = doctor(home.path(), &[]);
+    assert_eq!(check(&results, "legacy config")["status"], "warning");
+    let permissions = format!("file permissions: {}", config.display());
+    assert_eq!(check(&results, &permissions)["status"], "warning");
+
+    let results = doctor(home.path(), &["--fix"]);
+    assert_eq!(check(&results, "legacy config")["status"], "ok");
+    assert_eq!(check(&results, &permissions)["status"], "ok");
+    assert_eq!(
+        fs::metadata(&config).unwrap().permissions().mode() & 0o777,
+        0o600
+    );
+    assert_eq!(
+        fs::read_to_string(home.path().join(".config/k8pk/config.yaml")).unwrap(),
+        "picker: fzf\n"
+    );
+
+    // Fixed for good: the next run has nothing to report
+    let results = doctor(home.path(), &[]);
+    assert!(results.iter().all(|r| r["name"] != "legacy config"));
+    assert_eq!(check(&results, &permissions)["status"], "ok");
+}
+
+#[test]
+fn test_doctor_connectivity() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let up = format!("https://{}", listener.local_addr().unwrap());
+    // A port nobody listens on: bind one, then free it
+    let down = {
+        let closed = TcpListener::bind("127.0.0.1:0").unwrap();
+        format!("https://{}", closed.local_addr().unwrap())
+    };
+
+    let home = tempfile::tempdir().unwrap();
+    let kube = home.path().join(".kube");
+    fs::create_dir_all(&kube).unwrap();
+    fs::write(
+        kube.join("config"),
+        kubeconfig(&[("up", &up), ("down", &down)]),
+    )
+    .unwrap();
+
+    let results = doctor(home.path(), &[]);
+    assert!(results
+        .iter()
+        .all(|r| !r["name"].as_str().unwrap().starts_with("connectivity")));
+
+    let results = doctor(home.path(), &["--connectivity"]);
+    let reachable = check(&results, &format!("connectivity: {}", up));
+    assert_eq!(reachable["status"], "ok");
+    assert!(reachable["message"].as_str().unwrap().contains("up"));
+    assert_eq!(
+        check(&results, &format!("connectivity: {}", down))["status"],
+        "error"
+    );
+}
@@ -91,28 +91,28 @@ end
 
 function kctx
   if test (count $argv) -eq 0
-    _k8pk_eval_cmd ctx; or return $status
+    _k8pk_eval_cmd ctx --output env; or return $status
   else if test (count $argv) -eq 1
-    _k8pk_eval_cmd ctx $argv[1]; or return $status
+    _k8pk_eval_cmd ctx $argv[1] --output env; or return $status
   else
-    _k8pk_eval_cmd ctx $argv[1] --namespace $argv[2]; or return $status
+    _k8pk_eval_cmd ctx $argv[1] --namespace $argv[2] --output env; or return $status
   end
 end
 
 function kns
   if test (count $argv) -eq 0
-    _k8pk_eval_cmd ns; or return $status
+    _k8pk_eval_cmd ns --output env; or return $status
   else
-    _k8pk_eval_cmd ns $argv[1]; or return $status
+    _k8pk_eval_cmd ns $argv[1] --output env; or return $status
   end
 end
 
 function kprev
-  _k8pk_eval_cmd ctx -
+  _k8pk_eval_cmd ctx - --output env
 end
 
 function kclean
-  _k8pk_eval_cmd clean
+  _k8pk_eval_cmd clean --output env
 end
 
 function _k8pk_prompt
@@ -125,6 +125,13 @@ function _k8pk_prompt
     if test -n "$K8PK_DEPTH"; and test "$K8PK_DEPTH" -gt 0
       set prompt "$prompt:$K8PK_DEPTH"
     end
+    set -l kubeconfig (string split -m1 : -- "$KUBECONFIG")[1]
+    if test -f "$kubeconfig"
+      set -l expires (string replace -rf '^\s*expires-at:\s*(\d+).*' '$1' < $kubeconfig)
+      if test -n "$expires[1]"; and test (date +%s) -ge $expires[1]
+        set prompt "$prompt:expired"
+      end
+    end
     set prompt "$prompt]"
     echo "$prompt"
   end
@@ -75,18 +75,18 @@ kpick() {
 # Jump to previous context, like cd -
 kprev() {
   _k8pk_check || return $?
-  _k8pk_eval_cmd ctx -
+  _k8pk_eval_cmd ctx - --output env
 }
 
 # Quick context switch (supports history with "-")
 kctx() {
   _k8pk_check || return $?
   if [ -n "${1:-}" ] && [ -n "${2:-}" ]; then
-    _k8pk_eval_cmd ctx "$1" --namespace "$2"
+    _k8pk_eval_cmd ctx "$1" --namespace "$2" --output env
   elif [ -n "${1:-}" ]; then
-    _k8pk_eval_cmd ctx "$1"
+    _k8pk_eval_cmd ctx "$1" --output env
   else
-    _k8pk_eval_cmd ctx
+    _k8pk_eval_cmd ctx --output env
   fi
 }
 
@@ -94,23 +94,29 @@ kctx() {
 kns() {
   _k8pk_check || return $?
   if [ -n "${1:-}" ]; then
-    _k8pk_eval_cmd ns "$1"
+    _k8pk_eval_cmd ns "$1" --output env
   else
-    _k8pk_eval_cmd ns
+    _k8pk_eval_cmd ns --output env
   fi
 }
 
-# Prompt helper function
+# Prompt helper function (":expired" once the session_ttl ran out)
 _k8pk_prompt() {
   if [ -n "${K8PK_CONTEXT:-}" ]; then
     local display_ctx="${K8PK_CONTEXT_DISPLAY:-$K8PK_CONTEXT}"
-    local prompt="[${display_ctx}"
+    local prompt="[${display_ctx}" expires
     if [ -n "${K8PK_NAMESPACE:-}" ]; then
       prompt="${prompt}:${K8PK_NAMESPACE}"
     fi
     if [ -n "${K8PK_DEPTH:-}" ] && [ "${K8PK_DEPTH}" -gt 0 ]; then
       prompt="${prompt}:${K8PK_DEPTH}"
     fi
+    if [ -f "${KUBECONFIG%%:*}" ]; then
+      expires="$(sed -n 's/^ *expires-at: *\([0-9][0-9]*\).*/\1/p' "${KUBECONFIG%%:*}")"
+      if [ -n "$expires" ] && [ "$(date +%s)" -ge "$expires" ]; then
+        prompt="${prompt}:expired"
+      fi
+    fi
     prompt="${prompt}]"
     echo "$prompt"
   fi
@@ -137,7 +143,7 @@ kswitch() {
 # Clean up k8pk session (unset all k8pk environment variables)
 kclean() {
   _k8pk_check || return $?
-  _k8pk_eval_cmd clean
+  _k8pk_eval_cmd clean --output env
 }
 
 # List active k8pk sessions across terminals